@@ -11,7 +11,8 @@ use xwdoc::core::filters::html::ImagesFilter;
 use xwdoc::core::scraper::filter::FilterContext;
 use xwdoc::core::scraper::filter::Filter;
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     println!("=== 图片过滤器测试 ===");
 
     // 测试目录
@@ -22,7 +23,7 @@ fn main() -> Result<()> {
     test_data_url_preservation(&test_dir)?;
 
     // 测试相对URL处理
-    test_relative_url_handling(&test_dir)?;
+    test_relative_url_handling(&test_dir).await?;
 
     // 测试过滤器的辅助方法
     test_helper_methods()?;
@@ -49,14 +50,14 @@ fn test_data_url_preservation(test_dir: &Path) -> Result<()> {
     "#;
 
     // 创建过滤器上下文
-    let mut context = FilterContext::default();
+    let context = FilterContext::default();
 
     // 创建图片过滤器
     let filter = ImagesFilter::new().with_max_width(50);
 
     // 应用过滤器
     println!("处理图片...");
-    let processed_html = filter.apply(html, &mut context)?;
+    let processed_html = filter.apply_v2(html, &context)?.html;
     write_file(&test_dir.join("data_url_test.html"), &processed_html)?;
 
     // 检查是否成功保留了data URL
@@ -72,7 +73,7 @@ fn test_data_url_preservation(test_dir: &Path) -> Result<()> {
 }
 
 // 测试相对URL处理
-fn test_relative_url_handling(test_dir: &Path) -> Result<()> {
+async fn test_relative_url_handling(test_dir: &Path) -> Result<()> {
     println!("\n测试相对URL处理:");
 
     // 创建带有相对URL的HTML
@@ -88,18 +89,16 @@ fn test_relative_url_handling(test_dir: &Path) -> Result<()> {
     "#;
 
     // 创建过滤器上下文 - 设置基础URL
-    let mut context = FilterContext {
-        base_url: "https://example.com".to_string(),
-        ..FilterContext::default()
-    };
+    let context = FilterContext::new().with_base_url("https://example.com");
 
     // 创建图片过滤器
     let filter = ImagesFilter::new();
 
-    // 应用过滤器 - 即使下载失败也不会崩溃
+    // 应用过滤器并下载图片 - 即使下载失败也不会崩溃
     println!("处理相对URL图片...");
-    match filter.apply(html, &mut context) {
-        Ok(processed_html) => {
+    match filter.apply_async(html, &context).await {
+        Ok(output) => {
+            let processed_html = output.html;
             write_file(&test_dir.join("relative_url_test.html"), &processed_html)?;
             println!("✅ 成功处理: 相对URL处理正常（即使图片下载可能失败）");
         }
@@ -1,47 +1,132 @@
 //! 命令行处理器
 
+use crate::cli::output::{
+    self, DocInfoOutput, DocsListOutput, DocsOutdatedOutput, DownloadOutput, OutdatedDoc, SearchOutput, ValidateOutput,
+};
 use crate::cli::{Cli, Commands};
+use crate::core::http_fetch::ReqwestFetch;
+use crate::core::subscriber::{ConsoleSubscriber, Subscriber};
+use crate::core::version_probe::{VersionCache, DEFAULT_PROBE_TIMEOUT, VERSION_CACHE_FILENAME};
 use clap::Parser;
 use std::error::Error;
+use std::time::Duration;
 
 /// 处理命令行参数
 pub async fn handle_cli() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
+    // 抓取器和文档管理发布的消息事件由控制台订阅者输出，库本身不直接输出
+    let console = std::sync::Arc::new(ConsoleSubscriber::new().with_verbosity(cli.verbosity()));
+    crate::core::instrumentable::subscribe("*", move |info| console.handle_event(info));
+
+    // 指定了 --namespace 时为该命名空间的视图，命令不支持或没有配置该命名空间时报错
+    let config = cli.config()?;
+
     match &cli.command {
-        Commands::Server { host, port } => {
-            println!("启动服务器在 {}:{}", host, port);
-            crate::start(host, *port).await?;
+        Commands::Server {
+            host,
+            port,
+            cache_max_memory_mb,
+            precompress,
+            read_only,
+        } => {
+            let config = config
+                .clone()
+                .with_cache_budget(*cache_max_memory_mb)
+                .with_precompression(*precompress)
+                .with_read_only(*read_only);
+            let server = crate::build_server(config, host, *port)?;
+            println!("服务器启动在 http://{}", server.address());
+            println!("Web界面准备就绪，请在浏览器中访问");
+            server.run().await?;
         }
 
         // 文档相关命令
         Commands::DocsList => {
-            println!("可用文档:");
-            for doc in crate::docs::get_available_docs() {
-                println!("  {}", doc);
+            let mut registry = crate::docs::DocRegistry::new();
+            registry.load_from_disk(&config.docs_paths)?;
+            let docs = DocsListOutput::new(crate::docs::get_available_docs(), &registry, cli.time_display(&config))
+                .with_quality(config.quality.clone(), atty::is(atty::Stream::Stdout));
+            output::emit(&docs, cli.output)?;
+        }
+        Commands::DocsInfo { doc } => {
+            let mut registry = crate::docs::DocRegistry::new();
+            registry.load_from_disk(&config.docs_paths)?;
+            let doc = crate::docs::installed_doc(&registry, doc)?;
+            output::emit(&DocInfoOutput::new(doc), cli.output)?;
+        }
+        Commands::DocsOutdated { refresh } => {
+            let mut registry = crate::docs::DocRegistry::new();
+            registry.load_from_disk(&config.docs_paths)?;
+            let cache_path = config.writable_root().join(VERSION_CACHE_FILENAME);
+            let mut cache = VersionCache::load(&cache_path);
+            let ttl = Duration::from_secs(if *refresh { 0 } else { config.version_check_ttl });
+            let fetch = ReqwestFetch::with_options(DEFAULT_PROBE_TIMEOUT, &config.tls)?;
+            let now = chrono::Utc::now().timestamp();
+
+            let mut docs = Vec::new();
+            for name in crate::docs::get_available_docs() {
+                let Some(probe) = crate::version_probe(&name) else {
+                    continue;
+                };
+                let latest = cache.latest_version(&name, probe.as_ref(), &fetch, now, ttl).await;
+                docs.push(OutdatedDoc::new(&name, &registry.versions(&name, None), latest));
             }
+            // 缓存只是为了少请求上游，写入失败不影响结果
+            if let Err(e) = cache.save(&cache_path) {
+                log::warn!("无法保存版本查询缓存: {}", e);
+            }
+            output::emit(&DocsOutdatedOutput { docs }, cli.output)?;
         }
         Commands::DocsDownload {
             docs,
             all,
             default,
             installed,
+            from_url,
+            prebuilt,
+            ca_certs,
+            insecure,
         } => {
             // 下载指定文档
-            if *all {
-                println!("下载所有文档");
-                crate::docs::download_all_docs().await?;
-            } else if *default {
-                println!("下载默认文档");
-                crate::docs::download_default_docs().await?;
-            } else if *installed {
-                println!("更新已安装的文档");
-                crate::docs::download_installed_docs().await?;
-            } else if !docs.is_empty() {
-                println!("下载指定文档");
-                crate::docs::download_specific_docs(docs).await?;
+            if let Some(mirror_url) = from_url {
+                crate::docs::download_from_mirror(mirror_url, docs).await?;
+            } else if *prebuilt {
+                let docs = if *installed {
+                    crate::docs::installed_doc_dirs()
+                } else {
+                    docs.clone()
+                };
+                if docs.is_empty() {
+                    eprintln!("请提供要下载的预构建文档名列表，或使用 --installed 更新已安装的文档");
+                } else {
+                    let tls = crate::core::http_fetch::TlsOptions {
+                        ca_certs: ca_certs.clone(),
+                        accept_invalid_certs: *insecure,
+                    };
+                    crate::docs::download_prebuilt_docs(&docs, &tls).await?;
+                }
             } else {
-                eprintln!("请指定要下载的文档，使用 --all 或 --default 或提供文档名列表");
+                // 逐个下载，某个文档失败不影响其余的文档；先输出汇总，再按是否有失败决定退出码
+                let outcomes = if *all {
+                    eprintln!("下载所有文档");
+                    crate::docs::download_all_docs().await
+                } else if *default {
+                    eprintln!("下载默认文档");
+                    crate::docs::download_default_docs().await
+                } else if *installed {
+                    eprintln!("更新已安装的文档");
+                    crate::docs::download_installed_docs().await
+                } else if !docs.is_empty() {
+                    eprintln!("下载指定文档");
+                    crate::docs::download_specific_docs(docs).await
+                } else {
+                    eprintln!("请指定要下载的文档，使用 --all 或 --default 或提供文档名列表");
+                    return Ok(());
+                };
+                let result = DownloadOutput::new(outcomes);
+                output::emit(&result, cli.output)?;
+                crate::docs::downloads_result(&result.docs)?;
             }
         }
         Commands::DocsGenerate { doc, version } => {
@@ -59,32 +144,213 @@ pub async fn handle_cli() -> Result<(), Box<dyn Error>> {
             println!("生成页面: {}/{}", doc, page);
             crate::docs::generate_page(&doc, &page).await?;
         }
-        Commands::DocsPackage { doc } => {
+        Commands::Page { doc, path, format } => {
+            // 页面内容直接输出到标准输出，便于通过管道交给其他工具
+            print!("{}", crate::docs::read_page(&config, doc, path, format == "markdown")?);
+        }
+        Commands::DocsPackage {
+            doc,
+            all,
+            output,
+            format,
+        } => {
             // 打包文档
-            println!("打包文档: {}", doc);
-            crate::docs::package_doc(&doc)?;
+            if *all {
+                crate::docs::package_all_docs(std::path::Path::new(output))?;
+            } else if let Some(doc) = doc {
+                let format: crate::docs::PackageFormat = format.parse()?;
+                crate::docs::package_doc_archive(doc, format, std::path::Path::new(output))?;
+            }
+        }
+        Commands::DocsUnpack { path } => {
+            // 安装文档包
+            crate::docs::unpack_doc(path).await?;
         }
         Commands::DocsClean => {
             // 清理文档
             println!("清理文档包");
             crate::docs::clean_docs()?;
         }
+        Commands::DocsRemove { doc, version } => {
+            // 删除文档
+            println!("删除文档: {}", doc);
+            crate::docs::remove_doc(&config, doc, version.as_deref()).await?;
+        }
         Commands::DocsManifest => {
             // 生成清单
             println!("生成文档清单");
-            crate::docs::generate_manifest()?;
+            let manifest = crate::docs::refresh_manifest(&config).await?;
+            println!("已写入 {} 个文档（{} 个条目）", manifest.docs.len(), manifest.entry_count());
+        }
+        Commands::DocsInstall { slug, from, version } => {
+            // 从远程文档目录安装文档
+            crate::docs::install_doc(from, slug, version.as_deref()).await?;
+        }
+        Commands::DocsSync { from, docs } => {
+            // 同步文档
+            crate::docs::sync_docs(from, docs).await?;
+        }
+
+        Commands::DocsUpgradeFormat { doc } => {
+            // 升级 db.json 格式
+            println!("升级 db.json 格式");
+            crate::docs::upgrade_format(doc.as_deref())?;
+        }
+
+        Commands::DocsFixRedirects { doc } => {
+            // 应用重定向
+            crate::docs::fix_redirects(doc)?;
+        }
+
+        Commands::DocsValidate { doc } => {
+            // 检查页面路径冲突并重新计算质量分数，先输出结果，再按是否有冲突决定退出码
+            let collisions = crate::docs::find_path_collisions(&config.docs_paths, doc.as_deref())?;
+            let quality = crate::docs::recompute_quality(config.writable_root(), doc.as_deref())?;
+            let result = ValidateOutput::new(collisions).with_quality(quality);
+            output::emit(&result, cli.output)?;
+            crate::docs::collisions_result(&result.collisions)?;
+        }
+
+        Commands::Search {
+            query,
+            limit,
+            snippets,
+            clear_history,
+        } => {
+            if *clear_history {
+                // 没有开启搜索历史时也清空，关闭前留下的历史文件同样会被删除
+                let path = config.search_history_path();
+                crate::docs::search_history::SearchHistory::open(&path, 0, 0).clear()?;
+                println!("已清空搜索历史: {:?}", path);
+                return Ok(());
+            }
+            let query = query.clone().unwrap_or_default();
+            let mut registry = crate::docs::DocRegistry::new();
+            registry.load_from_disk(&config.docs_paths)?;
+            let cache = crate::docs::search::SnippetCache::new();
+            let history = crate::docs::search_history::SearchHistory::from_config(&config);
+            let results = crate::docs::search::search_with_history(
+                &registry,
+                None,
+                &query,
+                *limit,
+                snippets.then_some(&cache),
+                history.as_ref(),
+            );
+            output::emit(&SearchOutput { query, results }, cli.output)?;
+        }
+
+        Commands::DocsExport {
+            doc,
+            format,
+            types,
+            output,
+        } => {
+            println!("导出文档: {}", doc);
+            let output = output.as_deref().map(std::path::Path::new);
+            match format.as_str() {
+                "epub" => crate::docs::export_doc(doc, types, output)?,
+                "html" => crate::docs::export_site(doc, types, output)?,
+                other => return Err(format!("不支持的导出格式: {}（可用 epub、html）", other).into()),
+            }
+        }
+
+        Commands::DocsRefilter { doc, version, url } => {
+            // 离线重新过滤
+            println!("重新过滤文档: {} (版本: {})", doc, version);
+            crate::refilter_async(doc, version, url.as_deref().unwrap_or("")).await?;
+        }
+
+        // 前端资源相关命令
+        Commands::AssetsCompile => {
+            println!("编译前端资源: {:?}", config.assets_source_path);
+            let manifest =
+                crate::web::assets::compile(&config.assets_source_path, &config.assets_output_path)?;
+            println!("已编译 {} 个资源到 {:?}", manifest.len(), config.assets_output_path);
+        }
+        Commands::AssetsClean => {
+            println!("清理旧资源: {:?}", config.assets_output_path);
+            for file in crate::web::assets::clean(&config.assets_output_path)? {
+                println!("  已删除 {:?}", file);
+            }
         }
 
         // 抓取器相关命令
         Commands::ScraperList => {
-            crate::cli::list_scrapers()?;
+            crate::cli::list_scrapers(cli.output)?;
         }
         Commands::ScraperRun {
             name,
             version,
             output,
+            store_on_filter_error,
+            rate_limit,
+            concurrency,
+            include_stats,
+            resume,
+            db_format,
+            drop_near_duplicates,
+            adaptive_concurrency,
+            keep_raw,
+            max_page_size,
+            oversize_policy,
+            trace_urls,
+            event_sample_rate,
+            strip_broken_fragments,
+            no_favicon,
+            ca_certs,
+            insecure,
+            ignore_disk_space,
+            strict,
+            dry_run,
         } => {
-            crate::cli::run_scraper(name, version, output.as_deref()).await?;
+            let cancel = tokio_util::sync::CancellationToken::new();
+            let options = crate::ScrapeOptions {
+                store_on_filter_error: *store_on_filter_error,
+                rate_limit: *rate_limit,
+                concurrency: *concurrency,
+                include_stats: *include_stats,
+                resume: *resume,
+                db_format: db_format.and_then(crate::core::page_db::DbFormat::from_version),
+                drop_near_duplicates: *drop_near_duplicates,
+                adaptive_concurrency: *adaptive_concurrency,
+                keep_raw: *keep_raw,
+                max_page_size: *max_page_size,
+                oversize_policy: oversize_policy.as_deref().and_then(|policy| policy.parse().ok()),
+                trace_urls: trace_urls.clone(),
+                event_sample_rate: *event_sample_rate,
+                strip_broken_fragments: *strip_broken_fragments,
+                no_favicon: *no_favicon,
+                ca_certs: ca_certs.clone(),
+                insecure: *insecure,
+                ignore_disk_space: *ignore_disk_space,
+                strict: *strict,
+                dry_run: *dry_run,
+                namespace: cli.namespace.clone(),
+                cancel: Some(cancel.clone()),
+            };
+
+            // 第一次 Ctrl-C 停止抓取并保存恢复所需的状态，第二次直接退出
+            let interrupt = tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    eprintln!("正在停止抓取，再次按 Ctrl-C 立即退出");
+                    cancel.cancel();
+                    if tokio::signal::ctrl_c().await.is_ok() {
+                        std::process::exit(crate::cli::EXIT_CANCELLED);
+                    }
+                }
+            });
+            let result = crate::cli::run_scraper(name, version, output.as_deref(), &options).await;
+            interrupt.abort();
+            if let Err(e) = &result {
+                if let Some(code) = e.downcast_ref().and_then(crate::cli::exit_code) {
+                    if code == crate::cli::EXIT_STRICT {
+                        eprintln!("{}", e);
+                    }
+                    std::process::exit(code);
+                }
+            }
+            result?;
         }
     }
 
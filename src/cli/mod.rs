@@ -1,6 +1,25 @@
 //! 命令行参数解析模块
 
+use crate::core::config::Config;
+use crate::core::format::TimeDisplay;
+use crate::core::subscriber::Verbosity;
 use clap::{Parser, Subcommand};
+use output::OutputFormat;
+
+/// 抓取被 Ctrl-C 取消时的退出码（128 + SIGINT），与抓取失败区分开
+pub const EXIT_CANCELLED: i32 = 130;
+
+/// 严格模式的抓取质量检查没有通过时的退出码，与抓取失败（1）区分开
+pub const EXIT_STRICT: i32 = 3;
+
+/// 需要使用专门退出码的错误，其余错误返回 None（按一般失败处理）
+pub fn exit_code(error: &crate::core::error::Error) -> Option<i32> {
+    match error {
+        crate::core::error::Error::Cancelled => Some(EXIT_CANCELLED),
+        crate::core::error::Error::StrictFailed(_) => Some(EXIT_STRICT),
+        _ => None,
+    }
+}
 
 /// xwdoc 命令行参数定义
 #[derive(Parser)]
@@ -9,6 +28,65 @@ pub struct Cli {
     /// 要执行的命令
     #[clap(subcommand)]
     pub command: Commands,
+
+    /// 信息类命令的输出格式（不影响退出码）
+    #[clap(long, global = true, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+
+    /// 表格中的时间按 UTC 显示（默认使用配置，配置默认为本地时区）
+    #[clap(long, global = true)]
+    pub utc: bool,
+
+    /// 只输出警告和错误
+    #[clap(long, short, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// 另外输出每个请求的进度和内部事件（`-v` 已用于部分命令的版本参数）
+    #[clap(long, global = true)]
+    pub verbose: bool,
+
+    /// 在配置的命名空间中操作：文档写入命名空间的目录，全局文档只读可见
+    /// （支持 docs-list、docs-remove、docs-manifest、docs-validate、search 和 scraper-run）
+    #[clap(long, global = true, value_name = "NAME")]
+    pub namespace: Option<String>,
+}
+
+impl Cli {
+    /// 表格中时间的显示方式，`--utc` 优先于配置
+    pub fn time_display(&self, config: &crate::core::config::Config) -> TimeDisplay {
+        if self.utc {
+            TimeDisplay::Utc
+        } else {
+            config.time_display
+        }
+    }
+
+    /// 命令使用的配置，指定了 `--namespace` 时为该命名空间的视图
+    pub fn config(&self) -> Result<Config, String> {
+        let config = Config::default();
+        match &self.namespace {
+            Some(namespace) => {
+                if !self.command.supports_namespace() {
+                    return Err("该命令不支持 --namespace".to_string());
+                }
+                config
+                    .in_namespace(namespace)
+                    .ok_or_else(|| format!("没有配置命名空间: {}", namespace))
+            }
+            None => Ok(config),
+        }
+    }
+
+    /// 控制台输出的详细程度
+    pub fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
 }
 
 /// 可用命令
@@ -23,11 +101,29 @@ pub enum Commands {
         /// 监听的端口
         #[clap(long, default_value = "3000")]
         port: u16,
+
+        /// 缓存页面内容的内存上限（MB），超过时淘汰最近最少使用的页面
+        #[clap(long, value_name = "MB")]
+        cache_max_memory_mb: Option<u64>,
+
+        /// 缓存页面时同时保存 gzip 压缩的副本，客户端接受 gzip 时直接返回
+        #[clap(long)]
+        precompress: bool,
+
+        /// 只读模式：拒绝修改请求，文档由 `docs sync` 等其他进程更新后自动重新加载
+        #[clap(long)]
+        read_only: bool,
     },
 
     /// 列出可用文档
     DocsList,
 
+    /// 显示已安装文档的信息，包括内容的许可证和版权说明
+    DocsInfo {
+        /// 文档目录名称（如 `react~18`）或别名
+        doc: String,
+    },
+
     /// 下载文档
     DocsDownload {
         /// 要下载的文档列表
@@ -45,6 +141,22 @@ pub enum Commands {
         /// 更新已安装的文档
         #[clap(long)]
         installed: bool,
+
+        /// 从镜像地址（或本地镜像目录）下载，校验摘要后安装
+        #[clap(long, value_name = "MIRROR")]
+        from_url: Option<String>,
+
+        /// 下载 devdocs 的预构建文档，未变化的跳过，中断的下载续传
+        #[clap(long)]
+        prebuilt: bool,
+
+        /// 额外信任的 CA 证书（PEM 格式，可重复指定）
+        #[clap(long = "ca-cert", value_name = "FILE")]
+        ca_certs: Vec<std::path::PathBuf>,
+
+        /// 不校验 TLS 证书（不安全，仅用于可信的内部网站）
+        #[clap(long)]
+        insecure: bool,
     },
 
     /// 生成文档
@@ -68,19 +180,171 @@ pub enum Commands {
         page: String,
     },
 
+    /// 输出已安装文档的页面
+    Page {
+        /// 文档名称
+        doc: String,
+
+        /// 页面路径
+        path: String,
+
+        /// 输出格式
+        #[clap(long, default_value = "html", value_parser = ["html", "markdown"])]
+        format: String,
+    },
+
     /// 打包文档
     DocsPackage {
         /// 要打包的文档名称
-        #[clap(required = true)]
-        doc: String,
+        #[clap(required_unless_present = "all")]
+        doc: Option<String>,
+
+        /// 将所有已安装文档打包为镜像（归档、SHA256SUMS 和 mirror.json）
+        #[clap(long, conflicts_with = "doc")]
+        all: bool,
+
+        /// 输出目录（打包单个文档时写入离线包，使用 --all 时写入镜像）
+        #[clap(long, default_value = "mirror")]
+        output: String,
+
+        /// 单个文档的打包格式：tar.gz 或 json.gz
+        #[clap(long, default_value = "tar.gz", value_parser = ["tar.gz", "json.gz"], conflicts_with = "all")]
+        format: String,
+    },
+
+    /// 安装离线文档包（.tar.gz 或 .json.gz）
+    DocsUnpack {
+        /// 文档包路径
+        #[clap(value_name = "FILE")]
+        path: std::path::PathBuf,
     },
 
     /// 清理文档包
     DocsClean,
 
+    /// 删除已安装的文档（仅限可写文档目录）
+    DocsRemove {
+        /// 要删除的文档名称
+        #[clap(required = true)]
+        doc: String,
+
+        /// 文档版本
+        #[clap(long, short)]
+        version: Option<String>,
+    },
+
     /// 生成文档清单
     DocsManifest,
 
+    /// 从远程文档目录（含 manifest.json）安装单个文档
+    DocsInstall {
+        /// 文档别名或目录名
+        #[clap(required = true)]
+        slug: String,
+
+        /// 远程文档目录（HTTP 地址或本地目录）
+        #[clap(long, value_name = "BASE_URL")]
+        from: String,
+
+        /// 文档版本
+        #[clap(long = "doc-version")]
+        version: Option<String>,
+    },
+
+    /// 从构建机的镜像同步文档，全部校验解压后再原子地切换
+    DocsSync {
+        /// 镜像地址（HTTP 地址或本地目录）
+        #[clap(long, value_name = "URL_OR_PATH")]
+        from: String,
+
+        /// 要同步的文档，不指定时同步镜像中的所有文档
+        #[clap(value_name = "DOCS")]
+        docs: Vec<String>,
+    },
+
+    /// 将已安装文档的 db.json 原地升级为 v2 格式（仅限可写文档目录）
+    DocsUpgradeFormat {
+        /// 要升级的文档目录名称，省略时升级全部文档
+        doc: Option<String>,
+    },
+
+    /// 对已抓取的文档应用抓取时记录的重定向，改写页面中的内部链接和条目路径（仅限可写文档目录）
+    DocsFixRedirects {
+        /// 文档目录名称
+        #[clap(required = true)]
+        doc: String,
+    },
+
+    /// 查询内置文档的上游最新版本，列出已安装的版本是否过期
+    DocsOutdated {
+        /// 忽略缓存的查询结果（缓存时间见配置中的 `version_check_ttl`）
+        #[clap(long)]
+        refresh: bool,
+    },
+
+    /// 检查已安装文档中是否有与保留文件名冲突的页面
+    DocsValidate {
+        /// 要检查的文档目录名称，省略时检查全部文档
+        doc: Option<String>,
+    },
+
+    /// 在已安装的文档中搜索条目
+    Search {
+        /// 搜索关键字
+        #[clap(required_unless_present = "clear_history")]
+        query: Option<String>,
+
+        /// 最多返回的结果数
+        #[clap(long, default_value = "20")]
+        limit: usize,
+
+        /// 同时显示目标页面的文本摘要（需要读取页面）
+        #[clap(long)]
+        snippets: bool,
+
+        /// 清空搜索历史（配置中的 `search.history`）
+        #[clap(long, conflicts_with = "query")]
+        clear_history: bool,
+    },
+
+    /// 导出文档供离线阅读
+    DocsExport {
+        /// 要导出的文档名称
+        doc: String,
+
+        /// 导出格式：`epub` 或静态 HTML 站点 `html`
+        #[clap(long, default_value = "epub", value_parser = ["epub", "html"])]
+        format: String,
+
+        /// 只导出这些条目类型（逗号分隔）
+        #[clap(long, value_delimiter = ',')]
+        types: Vec<String>,
+
+        /// 输出文件（EPUB）或目录（HTML），默认为当前目录下的 `<doc>.epub` 或 `<doc>/`
+        #[clap(long, alias = "out")]
+        output: Option<String>,
+    },
+
+    /// 用当前的过滤器重新过滤文档保存的原始页面，不访问网络
+    DocsRefilter {
+        /// 文档名称（抓取器名称）
+        doc: String,
+
+        /// 文档版本
+        #[clap(long, default_value = "latest")]
+        version: String,
+
+        /// 基础网址（非内置文档类型，默认使用 scraper.json 中记录的网址）
+        #[clap(long)]
+        url: Option<String>,
+    },
+
+    /// 编译前端资源，生成带内容哈希的文件名
+    AssetsCompile,
+
+    /// 清理未被资源清单引用的旧资源
+    AssetsClean,
+
     /// 列出可用的文档抓取器
     ScraperList,
 
@@ -97,11 +361,170 @@ pub enum Commands {
         /// 输出路径或URL（取决于抓取器类型）
         #[clap(long)]
         output: Option<String>,
+
+        /// 过滤器出错时保存未过滤的HTML
+        #[clap(long)]
+        store_on_filter_error: bool,
+
+        /// 每分钟最大请求数（覆盖配置文件中的设置）
+        #[clap(long)]
+        rate_limit: Option<u32>,
+
+        /// 最大并发请求数（覆盖配置文件中的设置）
+        #[clap(long)]
+        concurrency: Option<usize>,
+
+        /// 在索引中输出页面字数并生成 stats.json
+        #[clap(long)]
+        include_stats: bool,
+
+        /// 从上次中断的抓取日志和抓取状态（crawl_state.json）中恢复
+        #[clap(long)]
+        resume: bool,
+
+        /// db.json 格式版本（1 或 2，覆盖配置文件中的设置）
+        #[clap(long, value_parser = clap::value_parser!(u64).range(1..=2))]
+        db_format: Option<u64>,
+
+        /// 去掉近似重复的页面，只保留路径较短的页面
+        #[clap(long)]
+        drop_near_duplicates: bool,
+
+        /// 根据延迟和错误率自动调整并发数，从 1 开始，不超过 --concurrency
+        #[clap(long)]
+        adaptive_concurrency: bool,
+
+        /// 在文档目录的 _raw/ 中保存过滤前的页面，供 docs-refilter 使用
+        #[clap(long)]
+        keep_raw: bool,
+
+        /// 过滤后单个页面的大小限制，单位字节（覆盖配置文件中的设置）
+        #[clap(long)]
+        max_page_size: Option<usize>,
+
+        /// 超过大小限制的页面的处理策略：skip、truncate 或 keep（覆盖配置文件中的设置）
+        #[clap(long, value_parser = ["skip", "truncate", "keep"])]
+        oversize_policy: Option<String>,
+
+        /// 将全部入队、去重和跳过事件（不采样）以 JSON Lines 格式写入文件
+        #[clap(long, value_name = "FILE")]
+        trace_urls: Option<std::path::PathBuf>,
+
+        /// 入队、去重和跳过事件发布到事件总线时的采样率（每 N 个发布一个）
+        #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+        event_sample_rate: Option<u64>,
+
+        /// 从链接中去掉目标页面中找不到的锚点（默认保留，只记录在抓取报告中）
+        #[clap(long)]
+        strip_broken_fragments: bool,
+
+        /// 不下载网站图标（默认从根页面或 /favicon.ico 获取，保存为 icon.png）
+        #[clap(long)]
+        no_favicon: bool,
+
+        /// 额外信任的 CA 证书（PEM 格式，可重复指定），用于使用内部 CA 的网站
+        #[clap(long = "ca-cert", value_name = "FILE")]
+        ca_certs: Vec<std::path::PathBuf>,
+
+        /// 不校验 TLS 证书（不安全，仅用于可信的内部网站）
+        #[clap(long)]
+        insecure: bool,
+
+        /// 跳过开始抓取前的磁盘空间检查（抓取过程中可用空间低于下限时仍会中止）
+        #[clap(long)]
+        ignore_disk_space: bool,
+
+        /// 严格模式：有页面过滤失败、请求错误率过高、缺少预期的条目类型或条目数
+        /// 比上次大幅减少时以退出码 3 失败，阈值见配置中的 `[strict]`
+        #[clap(long)]
+        strict: bool,
+
+        /// 试运行：正常抓取和过滤，但不写入磁盘，结束后列出将写入的文件和总大小
+        #[clap(long, conflicts_with = "resume")]
+        dry_run: bool,
     },
 }
 
+impl Commands {
+    /// 命令是否可以在命名空间中执行（`--namespace`）
+    pub fn supports_namespace(&self) -> bool {
+        matches!(
+            self,
+            Commands::DocsList
+                | Commands::DocsInfo { .. }
+                | Commands::DocsRemove { .. }
+                | Commands::DocsManifest
+                | Commands::DocsValidate { .. }
+                | Commands::Page { .. }
+                | Commands::Search { .. }
+                | Commands::ScraperRun { .. }
+        )
+    }
+}
+
 pub mod handler;
+pub mod output;
 pub mod scraper_cmd;
 
 pub use handler::handle_cli;
 pub use scraper_cmd::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::Error;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code(&Error::Cancelled), Some(EXIT_CANCELLED));
+        assert_eq!(exit_code(&Error::StrictFailed(vec!["过滤失败".to_string()])), Some(EXIT_STRICT));
+        assert_eq!(exit_code(&Error::Message("无法连接".to_string())), None);
+    }
+
+    #[test]
+    fn test_verbosity_flags() {
+        let verbosity = |args: &[&str]| Cli::try_parse_from(args).map(|cli| cli.verbosity());
+        assert_eq!(verbosity(&["xwdoc", "docs-list"]).unwrap(), Verbosity::Normal);
+        assert_eq!(verbosity(&["xwdoc", "--quiet", "docs-list"]).unwrap(), Verbosity::Quiet);
+        assert_eq!(verbosity(&["xwdoc", "docs-list", "--verbose"]).unwrap(), Verbosity::Verbose);
+        assert!(verbosity(&["xwdoc", "-q", "--verbose", "docs-list"]).is_err());
+    }
+
+    #[test]
+    fn test_namespace_flag() {
+        let cli = Cli::try_parse_from(["xwdoc", "docs-list", "--namespace", "platform"]).unwrap();
+        assert_eq!(cli.namespace.as_deref(), Some("platform"));
+        assert!(cli.command.supports_namespace());
+        // 默认配置中没有命名空间
+        assert_eq!(cli.config().unwrap_err(), "没有配置命名空间: platform");
+
+        let cli = Cli::try_parse_from(["xwdoc", "--namespace", "platform", "assets-clean"]).unwrap();
+        assert_eq!(cli.config().unwrap_err(), "该命令不支持 --namespace");
+        assert!(Cli::try_parse_from(["xwdoc", "assets-clean"]).unwrap().config().is_ok());
+    }
+
+    #[test]
+    fn test_page_format() {
+        let cli = Cli::try_parse_from(["xwdoc", "page", "css", "color", "--format", "markdown"]).unwrap();
+        assert!(matches!(cli.command, Commands::Page { ref format, .. } if format == "markdown"));
+        let cli = Cli::try_parse_from(["xwdoc", "page", "css", "color"]).unwrap();
+        assert!(matches!(cli.command, Commands::Page { ref format, .. } if format == "html"));
+        assert!(Cli::try_parse_from(["xwdoc", "page", "css", "color", "--format", "pdf"]).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_flag() {
+        let cli = Cli::try_parse_from(["xwdoc", "scraper-run", "css", "--dry-run"]).unwrap();
+        assert!(matches!(cli.command, Commands::ScraperRun { dry_run: true, .. }));
+        assert!(Cli::try_parse_from(["xwdoc", "scraper-run", "css", "--dry-run", "--resume"]).is_err());
+    }
+    #[test]
+    fn test_export_html_site() {
+        let cli = Cli::try_parse_from(["xwdoc", "docs-export", "css", "--format", "html", "--out", "./site"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::DocsExport { ref format, ref output, .. } if format == "html" && output.as_deref() == Some("./site")
+        ));
+        assert!(Cli::try_parse_from(["xwdoc", "docs-export", "css", "--format", "pdf"]).is_err());
+    }
+}
@@ -0,0 +1,731 @@
+//! 命令行输出格式
+//!
+//! 信息类命令（`docs-list`、`docs-validate`、`scraper-list` 等）把结果收集到
+//! 可序列化的结构中，表格和 JSON 两种输出都从同一个结构生成，保证两者内容
+//! 一致。输出格式只影响标准输出，命令的退出码与格式无关。
+
+use crate::core::config::QualitySettings;
+use crate::core::format::{self, TimeDisplay};
+use crate::core::scraper::{QualityBand, QualityScore};
+use crate::core::version_probe;
+use crate::docs::search::SearchResult;
+use crate::docs::{DocQuality, DocRegistry, Documentation, DownloadOutcome, PathCollision};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use std::time::Duration;
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// 便于阅读的表格
+    #[default]
+    Table,
+    /// JSON，便于脚本处理
+    Json,
+}
+
+/// 可以用表格或 JSON 输出的命令结果
+pub trait CommandOutput: Serialize {
+    /// 生成表格输出
+    fn render_table(&self) -> String;
+}
+
+/// 按指定格式生成命令结果
+pub fn render<T: CommandOutput>(output: &T, format: OutputFormat) -> Result<String, Box<dyn Error>> {
+    match format {
+        OutputFormat::Table => Ok(output.render_table()),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(output)?),
+    }
+}
+
+/// 按指定格式输出命令结果
+pub fn emit<T: CommandOutput>(output: &T, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    println!("{}", render(output, format)?);
+    Ok(())
+}
+
+/// 已安装的文档
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstalledDoc {
+    /// 文档目录名（如 `react~18`）
+    pub doc: String,
+    /// 上次修改时间（Unix 秒）
+    pub mtime: u64,
+    /// db.json 的大小（字节）
+    pub db_size: u64,
+    /// 质量分数，旧的文档没有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<f64>,
+    /// 加载时无法读取的文件的错误说明
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// `docs-list` 的结果
+///
+/// JSON 中保留原始的时间戳和字节数，表格中按 `time_display` 显示为日期、
+/// 相对时间和 `87.3 MB` 这样的大小，质量分数前按 `quality_settings` 的区间
+/// 标出绿、黄、红色的圆点。加载时出错的文档在质量一栏标为“出错”，错误说明
+/// 列在表格之后。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocsListOutput {
+    /// 可用文档名称
+    pub docs: Vec<String>,
+    /// 已安装的文档
+    pub installed: Vec<InstalledDoc>,
+    /// 表格中的时间按本地时区还是 UTC 显示
+    #[serde(skip)]
+    pub time_display: TimeDisplay,
+    /// 计算相对时间的当前时间（Unix 秒）
+    #[serde(skip)]
+    pub now: i64,
+    /// 质量分数的区间
+    #[serde(skip)]
+    pub quality_settings: QualitySettings,
+    /// 表格中是否用颜色标出质量分数的区间
+    #[serde(skip)]
+    pub color: bool,
+}
+
+impl DocsListOutput {
+    /// 由可用文档和已加载的文档生成结果
+    pub fn new(docs: Vec<String>, registry: &DocRegistry, time_display: TimeDisplay) -> Self {
+        Self {
+            docs,
+            installed: registry
+                .all()
+                .iter()
+                .map(|doc| InstalledDoc {
+                    doc: doc.path(),
+                    mtime: doc.mtime,
+                    db_size: doc.db_size as u64,
+                    quality: doc.quality,
+                    errors: doc.load_errors.clone(),
+                })
+                .collect(),
+            time_display,
+            now: chrono::Utc::now().timestamp(),
+            quality_settings: QualitySettings::default(),
+            color: false,
+        }
+    }
+
+    /// 设置质量分数的区间和是否使用颜色
+    pub fn with_quality(mut self, settings: QualitySettings, color: bool) -> Self {
+        self.quality_settings = settings;
+        self.color = color;
+        self
+    }
+
+    /// 表格中的质量分数，没有分数时为 `-`
+    fn quality_cell(&self, score: Option<f64>) -> String {
+        let Some(score) = score else {
+            return "-".to_string();
+        };
+        let band = QualityScore { score, pages: 0, pages_with_warnings: 0 }.band(&self.quality_settings);
+        if !self.color {
+            return format!("● {:.1}", score);
+        }
+        let code = match band {
+            QualityBand::Good => 32,
+            QualityBand::Fair => 33,
+            QualityBand::Poor => 31,
+        };
+        format!("\x1b[{}m●\x1b[0m {:.1}", code, score)
+    }
+}
+
+impl CommandOutput for DocsListOutput {
+    fn render_table(&self) -> String {
+        let mut table = String::from("可用文档:");
+        for doc in &self.docs {
+            table.push_str(&format!("\n  {}", doc));
+        }
+        if self.installed.is_empty() {
+            return table;
+        }
+        let rows: Vec<[String; 4]> = self
+            .installed
+            .iter()
+            .map(|doc| {
+                [
+                    doc.doc.clone(),
+                    format::format_bytes(doc.db_size),
+                    format::format_time_with_relative(doc.mtime as i64, self.now, self.time_display),
+                    if doc.errors.is_empty() {
+                        self.quality_cell(doc.quality)
+                    } else {
+                        "出错".to_string()
+                    },
+                ]
+            })
+            .collect();
+        table.push_str("\n已安装的文档:\n");
+        table.push_str(&render_rows(["文档", "大小", "更新时间", "质量"], &rows));
+        for doc in &self.installed {
+            for error in &doc.errors {
+                table.push_str(&format!("\n错误: {}: {}", doc.doc, error));
+            }
+        }
+        table
+    }
+}
+
+/// 可用的抓取器
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScraperInfo {
+    /// 抓取器名称
+    pub name: String,
+    /// 说明
+    pub description: String,
+}
+
+/// `scraper-list` 的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScraperListOutput {
+    /// 可用的抓取器
+    pub scrapers: Vec<ScraperInfo>,
+}
+
+impl CommandOutput for ScraperListOutput {
+    fn render_table(&self) -> String {
+        let mut table = String::from("可用的文档抓取器:");
+        for scraper in &self.scrapers {
+            table.push_str(&format!("\n  {} - {}", scraper.name, scraper.description));
+        }
+        table
+    }
+}
+
+/// `docs-validate` 的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidateOutput {
+    /// 是否没有冲突
+    pub ok: bool,
+    /// 与保留名称冲突、未转义保存的页面
+    pub collisions: Vec<PathCollision>,
+    /// 由抓取报告重新计算的质量分数，不影响 `ok`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quality: Vec<DocQuality>,
+}
+
+impl ValidateOutput {
+    /// 由冲突的页面生成结果
+    pub fn new(collisions: Vec<PathCollision>) -> Self {
+        Self {
+            ok: collisions.is_empty(),
+            collisions,
+            quality: Vec::new(),
+        }
+    }
+
+    /// 设置重新计算的质量分数
+    pub fn with_quality(mut self, quality: Vec<DocQuality>) -> Self {
+        self.quality = quality;
+        self
+    }
+}
+
+impl CommandOutput for ValidateOutput {
+    fn render_table(&self) -> String {
+        let mut lines: Vec<String> = if self.ok {
+            vec!["未发现页面路径冲突".to_string()]
+        } else {
+            self.collisions
+                .iter()
+                .map(|collision| format!("未转义的页面: {:?}", collision.file))
+                .collect()
+        };
+        for doc in &self.quality {
+            lines.push(format!(
+                "质量分数: {} {:.1}（{}/{} 个页面有问题）",
+                doc.doc, doc.score.score, doc.score.pages_with_warnings, doc.score.pages
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// `docs-download` 的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DownloadOutput {
+    /// 是否全部下载成功
+    pub ok: bool,
+    /// 每个文档的下载结果
+    pub docs: Vec<DownloadOutcome>,
+}
+
+impl DownloadOutput {
+    /// 由每个文档的下载结果生成结果
+    pub fn new(docs: Vec<DownloadOutcome>) -> Self {
+        Self {
+            ok: docs.iter().all(|outcome| outcome.ok),
+            docs,
+        }
+    }
+}
+
+impl CommandOutput for DownloadOutput {
+    fn render_table(&self) -> String {
+        let rows: Vec<[String; 4]> = self
+            .docs
+            .iter()
+            .map(|outcome| {
+                let result = match &outcome.error {
+                    Some(error) => format!("失败: {}", error),
+                    None => "成功".to_string(),
+                };
+                [
+                    outcome.doc.clone(),
+                    outcome.version.clone(),
+                    format::format_duration(Duration::from_millis(outcome.elapsed_ms)),
+                    result,
+                ]
+            })
+            .collect();
+
+        let mut table = render_rows(["文档", "版本", "耗时", "结果"], &rows);
+        let failed = self.docs.iter().filter(|outcome| !outcome.ok).count();
+        table.push_str(&format!("\n共 {} 个文档，{} 个失败", self.docs.len(), failed));
+        table
+    }
+}
+
+/// 按列宽对齐的表格，第一行为表头
+fn render_rows<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> String {
+    let mut widths = header.map(display_width);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+    let format_row = |cells: [&str; N]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - display_width(cell))))
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
+
+    let mut table = format_row(header);
+    for row in rows {
+        table.push('\n');
+        table.push_str(&format_row(row.each_ref().map(String::as_str)));
+    }
+    table
+}
+
+/// 文本在终端中占的列数，非 ASCII 字符（主要是中文）按两列计算
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+/// `search` 的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchOutput {
+    /// 搜索关键字
+    pub query: String,
+    /// 匹配的条目
+    pub results: Vec<SearchResult>,
+}
+
+impl CommandOutput for SearchOutput {
+    fn render_table(&self) -> String {
+        if self.results.is_empty() {
+            return format!("没有找到与 \"{}\" 匹配的条目", self.query);
+        }
+        let mut table = String::new();
+        for result in &self.results {
+            if !table.is_empty() {
+                table.push('\n');
+            }
+            table.push_str(&format!(
+                "{} ({}) - {}  {}/{}",
+                result.name, result.entry_type, result.doc_name, result.doc, result.path
+            ));
+            if let Some(snippet) = &result.snippet {
+                table.push_str(&format!("\n    {}", snippet));
+            }
+        }
+        table
+    }
+}
+
+/// `docs-info` 的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocInfoOutput {
+    /// 文档目录名称
+    pub doc: String,
+    /// 文档显示名称（包含版本）
+    pub name: String,
+    /// 发布版本
+    pub release: String,
+    /// 内容的许可证（已知时为 SPDX 标识）
+    pub license: Option<String>,
+    /// 版权和许可说明
+    pub attribution: Option<String>,
+    /// 条目数
+    pub entries: usize,
+    /// 条目类型数
+    pub types: usize,
+}
+
+impl DocInfoOutput {
+    /// 由已安装的文档生成结果
+    pub fn new(doc: &Documentation) -> Self {
+        Self {
+            doc: doc.path(),
+            name: doc.full_name(),
+            release: doc.release.clone(),
+            license: doc.license.clone(),
+            attribution: doc.attribution.clone(),
+            entries: doc.entries.len(),
+            types: doc.types.len(),
+        }
+    }
+}
+
+impl CommandOutput for DocInfoOutput {
+    fn render_table(&self) -> String {
+        // 许可证放在最前面，转载内容时首先需要它
+        let mut table = format!(
+            "{} ({})\n许可证: {}",
+            self.name,
+            self.doc,
+            self.license.as_deref().unwrap_or("未知")
+        );
+        if let Some(attribution) = &self.attribution {
+            table.push_str(&format!("\n版权: {}", attribution.replace('\n', "\n      ")));
+        }
+        if !self.release.is_empty() {
+            table.push_str(&format!("\n发布版本: {}", self.release));
+        }
+        table.push_str(&format!("\n条目: {}（{} 种类型）", self.entries, self.types));
+        table
+    }
+}
+
+/// `docs-outdated` 中的一个文档
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutdatedDoc {
+    /// 文档名称
+    pub doc: String,
+    /// 已安装的版本（优先使用发布版本号）
+    pub installed: Vec<String>,
+    /// 上游的最新版本，查询失败时为 None
+    pub latest: Option<String>,
+    /// 状态：`Up-to-date`、`Outdated minor version`、`Outdated major version`，
+    /// 未安装时为 `Not installed`，查询失败时为 `Unknown`
+    pub status: String,
+    /// 查询失败时的错误说明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl OutdatedDoc {
+    /// 由已安装的各版本和查询结果生成，已安装多个版本时取其中最新的状态
+    pub fn new(doc: &str, installed: &[&Documentation], latest: crate::core::error::Result<String>) -> Self {
+        let installed: Vec<String> = installed
+            .iter()
+            .map(|doc| if doc.release.is_empty() { doc.version.clone() } else { doc.release.clone() })
+            .filter(|version| !version.is_empty())
+            .collect();
+        let (latest, error) = match latest {
+            Ok(latest) => (Some(latest), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let status = match &latest {
+            None => "Unknown",
+            Some(_) if installed.is_empty() => "Not installed",
+            Some(latest) => installed
+                .iter()
+                .map(|version| version_probe::outdated_state(version, latest))
+                .min_by_key(|state| match *state {
+                    "Up-to-date" => 0,
+                    "Outdated minor version" => 1,
+                    _ => 2,
+                })
+                .unwrap_or("Unknown"),
+        };
+        Self {
+            doc: doc.to_string(),
+            installed,
+            latest,
+            status: status.to_string(),
+            error,
+        }
+    }
+}
+
+/// `docs-outdated` 的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocsOutdatedOutput {
+    /// 声明了版本查询方式的文档
+    pub docs: Vec<OutdatedDoc>,
+}
+
+impl CommandOutput for DocsOutdatedOutput {
+    fn render_table(&self) -> String {
+        let rows: Vec<[String; 4]> = self
+            .docs
+            .iter()
+            .map(|doc| {
+                [
+                    doc.doc.clone(),
+                    if doc.installed.is_empty() { "-".to_string() } else { doc.installed.join(", ") },
+                    doc.latest.clone().unwrap_or_else(|| "-".to_string()),
+                    doc.status.clone(),
+                ]
+            })
+            .collect();
+        let mut table = render_rows(["文档", "已安装", "最新版本", "状态"], &rows);
+        for doc in &self.docs {
+            if let Some(error) = &doc.error {
+                table.push_str(&format!("\n错误: {}: {}", doc.doc, error));
+            }
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn json_of<T: CommandOutput>(output: &T) -> serde_json::Value {
+        serde_json::from_str(&render(output, OutputFormat::Json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_docs_list_schema() {
+        let mut output = DocsListOutput::new(
+            vec!["css".to_string(), "html".to_string()],
+            &crate::docs::DocRegistry::new(),
+            TimeDisplay::Utc,
+        );
+        assert_eq!(json_of(&output), json!({ "docs": ["css", "html"], "installed": [] }));
+        assert_eq!(render(&output, OutputFormat::Table).unwrap(), "可用文档:\n  css\n  html");
+
+        output.installed = vec![
+            InstalledDoc {
+                doc: "react~18".to_string(),
+                mtime: 1714572120,
+                db_size: 91_540_000,
+                quality: None,
+                errors: Vec::new(),
+            },
+            InstalledDoc {
+                doc: "vue~3".to_string(),
+                mtime: 1714572120,
+                db_size: 1_024,
+                quality: Some(82.5),
+                errors: Vec::new(),
+            },
+        ];
+        output.now = 1714572120 + 3 * 86_400;
+        assert_eq!(
+            json_of(&output)["installed"],
+            json!([
+                { "doc": "react~18", "mtime": 1714572120, "db_size": 91_540_000 },
+                { "doc": "vue~3", "mtime": 1714572120, "db_size": 1_024, "quality": 82.5 },
+            ])
+        );
+        assert_eq!(
+            output.render_table(),
+            concat!(
+                "可用文档:\n  css\n  html\n已安装的文档:\n",
+                "文档      大小     更新时间                        质量\n",
+                "react~18  87.3 MB  2024-05-01 14:02 UTC（3 天前）  -\n",
+                "vue~3     1.0 KB   2024-05-01 14:02 UTC（3 天前）  ● 82.5",
+            )
+        );
+
+        // 终端中按区间着色：82.5 在默认设置下为一般（黄色）
+        let output = output.with_quality(QualitySettings::default(), true);
+        assert!(output.render_table().ends_with("\x1b[33m●\x1b[0m 82.5"));
+    }
+
+    #[test]
+    fn test_docs_list_error_rows() {
+        let root = TempDir::new().unwrap();
+        let dir = root.path().join("css");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("index.json"), r#"{"entries":["#).unwrap();
+        fs::write(dir.join("db.json"), "{}").unwrap();
+        let mut registry = crate::docs::DocRegistry::new();
+        registry.load_from_disk(&[root.path()]).unwrap();
+
+        let output = DocsListOutput::new(Vec::new(), &registry, TimeDisplay::Utc);
+        let errors = json_of(&output)["installed"][0]["errors"].clone();
+        assert_eq!(errors.as_array().unwrap().len(), 1);
+        assert!(errors[0].as_str().unwrap().contains("index.json"), "{}", errors);
+
+        let table = output.render_table();
+        let rows: Vec<&str> = table.lines().collect();
+        assert!(rows[3].starts_with("css ") && rows[3].ends_with("出错"), "{}", table);
+        assert!(rows[4].starts_with("错误: css: 无法解析 "), "{}", table);
+    }
+
+    #[test]
+    fn test_scraper_list_schema() {
+        let output = crate::cli::scraper_list();
+        let json = json_of(&output);
+        assert_eq!(json["scrapers"][0], json!({ "name": "babel", "description": "Babel 文档抓取器" }));
+        assert_eq!(json["scrapers"].as_array().unwrap().len(), output.scrapers.len());
+        assert!(output.render_table().contains("\n  url - 通用URL抓取器 (需要指定URL)"));
+    }
+
+    #[test]
+    fn test_download_schema() {
+        let outcome = |doc: &str, error: Option<&str>, elapsed_ms| DownloadOutcome {
+            doc: doc.to_string(),
+            version: "latest".to_string(),
+            ok: error.is_none(),
+            error: error.map(str::to_string),
+            elapsed_ms,
+        };
+        let output = DownloadOutput::new(vec![
+            outcome("babel", None, 1340),
+            outcome("rust", Some("未支持的文档类型: rust"), 3),
+        ]);
+
+        assert_eq!(
+            json_of(&output),
+            json!({
+                "ok": false,
+                "docs": [
+                    { "doc": "babel", "version": "latest", "ok": true, "elapsed_ms": 1340 },
+                    {
+                        "doc": "rust",
+                        "version": "latest",
+                        "ok": false,
+                        "error": "未支持的文档类型: rust",
+                        "elapsed_ms": 3,
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            output.render_table(),
+            concat!(
+                "文档   版本    耗时  结果\n",
+                "babel  latest  1.3s  成功\n",
+                "rust   latest  3ms   失败: 未支持的文档类型: rust\n",
+                "共 2 个文档，1 个失败",
+            )
+        );
+        assert!(crate::docs::downloads_result(&output.docs).is_err());
+    }
+
+    #[test]
+    fn test_validate_schema_against_fixture_registry() {
+        let root = TempDir::new().unwrap();
+        let clean = root.path().join("css");
+        fs::create_dir_all(clean.join("_p/meta")).unwrap();
+        fs::write(clean.join("_p/meta/index.html"), "").unwrap();
+        let broken = root.path().join("react~18");
+        fs::create_dir_all(broken.join("icon")).unwrap();
+        fs::write(broken.join("icon/index.html"), "").unwrap();
+        fs::write(broken.join("meta.html"), "").unwrap();
+        let roots = vec![root.path().to_path_buf()];
+
+        let output = ValidateOutput::new(crate::docs::find_path_collisions(&roots, Some("css")).unwrap());
+        assert_eq!(json_of(&output), json!({ "ok": true, "collisions": [] }));
+
+        let output = ValidateOutput::new(crate::docs::find_path_collisions(&roots, None).unwrap());
+        assert_eq!(
+            json_of(&output),
+            json!({
+                "ok": false,
+                "collisions": [
+                    {
+                        "doc": "react~18",
+                        "page": "icon/index.html",
+                        "file": broken.join("icon/index.html"),
+                    },
+                    {
+                        "doc": "react~18",
+                        "page": "meta.html",
+                        "file": broken.join("meta.html"),
+                    },
+                ],
+            })
+        );
+        assert_eq!(output.render_table().lines().count(), 2);
+        assert!(crate::docs::collisions_result(&output.collisions).is_err());
+    }
+
+    #[test]
+    fn test_validate_recomputes_quality() {
+        let root = TempDir::new().unwrap();
+        let css = root.path().join("css");
+        fs::create_dir_all(&css).unwrap();
+        fs::create_dir_all(root.path().join("html")).unwrap();
+        fs::write(css.join("meta.json"), r#"{"name":"CSS"}"#).unwrap();
+        fs::write(
+            css.join("report.json"),
+            json!({ "pages_ok": 7, "failures": [{ "url": "https://example.com/x" }] }).to_string(),
+        )
+        .unwrap();
+
+        // 没有抓取报告的 html 跳过
+        let quality = crate::docs::recompute_quality(root.path(), None).unwrap();
+        let output = ValidateOutput::new(Vec::new()).with_quality(quality);
+        assert_eq!(
+            json_of(&output)["quality"],
+            json!([{ "doc": "css", "score": 87.5, "pages": 8, "pages_with_warnings": 1 }])
+        );
+        assert_eq!(output.render_table(), "未发现页面路径冲突\n质量分数: css 87.5（1/8 个页面有问题）");
+
+        let meta: serde_json::Value = serde_json::from_str(&fs::read_to_string(css.join("meta.json")).unwrap()).unwrap();
+        assert_eq!(meta["name"], "CSS");
+        assert_eq!(meta["quality"]["score"], 87.5);
+    }
+
+    #[test]
+    fn test_doc_info_shows_license_first() {
+        let doc = Documentation::new("CSS", "css", "")
+            .with_release("2023")
+            .with_attribution("© MDN contributors.\nLicensed under CC-BY-SA v2.5.")
+            .with_license("CC-BY-SA-2.5");
+        let output = DocInfoOutput::new(&doc);
+        assert_eq!(json_of(&output)["license"], "CC-BY-SA-2.5");
+        assert_eq!(
+            output.render_table(),
+            "CSS (css)\n许可证: CC-BY-SA-2.5\n版权: © MDN contributors.\n      Licensed under CC-BY-SA v2.5.\n发布版本: 2023\n条目: 0（0 种类型）"
+        );
+
+        let output = DocInfoOutput::new(&Documentation::new("HTML", "html", ""));
+        assert!(output.render_table().starts_with("HTML (html)\n许可证: 未知\n条目"));
+    }
+
+    #[test]
+    fn test_docs_outdated_schema() {
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&["test_docs/web_versions"]).unwrap();
+        let installed = registry.versions("lib", None);
+        let output = DocsOutdatedOutput {
+            docs: vec![
+                OutdatedDoc::new("lib", &installed, Ok("2.1".to_string())),
+                OutdatedDoc::new("babel", &[], Ok("7.24.5".to_string())),
+                OutdatedDoc::new("rust", &[], Err(crate::core::error::Error::HttpError(503))),
+            ],
+        };
+
+        assert_eq!(
+            json_of(&output)["docs"][0],
+            json!({ "doc": "lib", "installed": ["1.0", "2.0"], "latest": "2.1", "status": "Outdated minor version" })
+        );
+        assert_eq!(output.docs[1].status, "Not installed");
+        assert_eq!(output.docs[2].status, "Unknown");
+        let table = output.render_table();
+        assert!(table.starts_with("文档   已安装    最新版本  状态\n"), "{}", table);
+        assert!(table.contains("\nlib    1.0, 2.0  2.1       Outdated minor version"), "{}", table);
+        assert!(table.ends_with("\n错误: rust: HTTP错误代码: 503"), "{}", table);
+    }
+}
@@ -1,26 +1,46 @@
 //! 文档抓取命令处理
 
+use crate::cli::output::{self, OutputFormat, ScraperInfo, ScraperListOutput};
+use crate::ScrapeOptions;
 use std::error::Error;
 
+/// 所有可用的抓取器
+pub fn scraper_list() -> ScraperListOutput {
+    let scrapers = [
+        ("babel", "Babel 文档抓取器"),
+        ("html", "HTML 文档抓取器"),
+        ("css", "CSS 文档抓取器"),
+        ("javascript", "JavaScript 文档抓取器"),
+        ("typescript", "TypeScript 文档抓取器"),
+        ("rust", "Rust 文档抓取器"),
+        ("url", "通用URL抓取器 (需要指定URL)"),
+    ];
+    ScraperListOutput {
+        scrapers: scrapers
+            .into_iter()
+            .map(|(name, description)| ScraperInfo {
+                name: name.to_string(),
+                description: description.to_string(),
+            })
+            .collect(),
+    }
+}
+
 /// 列出所有可用的抓取器
-pub fn list_scrapers() -> Result<(), Box<dyn Error>> {
-    println!("可用的文档抓取器:");
-    println!("  babel - Babel 文档抓取器");
-    println!("  html - HTML 文档抓取器");
-    println!("  css - CSS 文档抓取器");
-    println!("  javascript - JavaScript 文档抓取器");
-    println!("  typescript - TypeScript 文档抓取器");
-    println!("  rust - Rust 文档抓取器");
-    println!("  url - 通用URL抓取器 (需要指定URL)");
-    
-    Ok(())
+pub fn list_scrapers(format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    output::emit(&scraper_list(), format)
 }
 
 /// 运行指定的抓取器
-pub async fn run_scraper(name: &str, version: &str, output: Option<&str>) -> Result<(), Box<dyn Error>> {
+pub async fn run_scraper(
+    name: &str,
+    version: &str,
+    output: Option<&str>,
+    options: &ScrapeOptions,
+) -> Result<(), Box<dyn Error>> {
     println!("运行抓取器: {} (版本: {})", name, version);
     
     let output_str = output.unwrap_or("");
     
-    crate::scrape_async(name, version, output_str).await
+    crate::scrape_async_with_options(name, version, output_str, options).await
 }
@@ -1,30 +1,331 @@
 //! 应用配置模块
 
+use crate::core::format::TimeDisplay;
+use crate::core::http_fetch::TlsOptions;
+use crate::core::page_db::DbFormat;
+use crate::core::scraper::page_size::DEFAULT_MAX_PAGE_SIZE;
+use crate::core::scraper::{EntryExclusions, OversizePolicy};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 搜索历史的默认文件名
+pub const SEARCH_HISTORY_FILENAME: &str = "search_history.json";
+
+/// 抓取器的全局默认设置
+///
+/// 优先级：命令行参数 > 抓取器显式设置 > 配置 > 内置默认值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScraperDefaults {
+    /// 每分钟最大请求数
+    pub rate_limit: u32,
+    /// 最大并发请求数
+    pub concurrency: usize,
+    /// 最大重试次数
+    pub max_retries: u32,
+    /// 请求超时时间（秒）
+    pub request_timeout: u64,
+    /// 过滤后单个页面的大小限制（字节）
+    pub max_page_size: usize,
+    /// 超过大小限制的页面的处理策略
+    pub oversize_policy: OversizePolicy,
+}
+
+impl Default for ScraperDefaults {
+    fn default() -> Self {
+        Self {
+            rate_limit: 60,
+            concurrency: 1,
+            max_retries: 3,
+            request_timeout: 30,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            oversize_policy: OversizePolicy::default(),
+        }
+    }
+}
+
+/// Web 服务设置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServeSettings {
+    /// 是否禁止搜索引擎索引（发送 `X-Robots-Tag: noindex` 并禁止所有爬虫）
+    pub noindex: bool,
+    /// 是否为页面注入文档目录中的 `custom.css`（关闭时 `custom.js` 也不注入）
+    pub custom_assets: bool,
+    /// 是否注入文档目录中的 `custom.js`，需要同时开启 `custom_assets`
+    pub custom_js: bool,
+    /// 只读模式：文档由其他进程（如 `docs sync`）更新，服务器不接受任何修改请求
+    pub read_only: bool,
+    /// 搜索和联想搜索是否支持 `snippets=1` 返回页面摘要
+    pub search_snippets: bool,
+    /// 是否提供联想搜索接口（`/api/typeahead`）
+    pub typeahead: bool,
+}
+
+impl Default for ServeSettings {
+    fn default() -> Self {
+        Self {
+            noindex: true,
+            custom_assets: true,
+            custom_js: false,
+            read_only: false,
+            search_snippets: true,
+            typeahead: true,
+        }
+    }
+}
+
+/// 事件日志格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// 每个事件一行文本
+    #[default]
+    Text,
+    /// 每个事件一个 JSON 对象（JSON Lines）
+    Jsonl,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "jsonl" => Ok(LogFormat::Jsonl),
+            _ => Err(format!("未知的日志格式: {}（可选 text 或 jsonl）", s)),
+        }
+    }
+}
+
+/// 事件日志设置（对应配置中的 `[logging]`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggingSettings {
+    /// 日志文件路径，None 表示不写入文件
+    pub file: Option<PathBuf>,
+    /// 日志格式
+    pub format: LogFormat,
+    /// 日志文件超过此大小（MB）时轮转，None 表示不按大小轮转
+    pub max_size_mb: Option<u64>,
+    /// 是否在日期变化时轮转
+    pub rotate_daily: bool,
+    /// 保留的轮转文件数
+    pub keep: usize,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            file: None,
+            format: LogFormat::Text,
+            max_size_mb: None,
+            rotate_daily: false,
+            keep: 5,
+        }
+    }
+}
+
+/// 抓取时的磁盘空间检查设置（对应配置中的 `[disk_space]`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSpaceSettings {
+    /// 开始抓取前预估文档大小时，上次抓取结果大小的倍数
+    pub safety_factor: f64,
+    /// 至少保留的可用空间（MB），开始时需要预估大小加上此值，抓取过程中低于此值时中止
+    pub min_free_mb: u64,
+    /// 抓取过程中每处理多少个页面检查一次可用空间
+    pub check_interval_pages: usize,
+}
+
+impl Default for DiskSpaceSettings {
+    fn default() -> Self {
+        Self {
+            safety_factor: 2.0,
+            min_free_mb: 256,
+            check_interval_pages: 50,
+        }
+    }
+}
+
+impl DiskSpaceSettings {
+    /// 至少保留的可用空间（字节）
+    pub fn min_free_bytes(&self) -> u64 {
+        self.min_free_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// 文档质量分数的区间设置（对应配置中的 `[quality]`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualitySettings {
+    /// 分数不低于此值时为良好（绿色）
+    pub good_min: f64,
+    /// 分数不低于此值时为一般（黄色），更低时为较差（红色）
+    pub fair_min: f64,
+}
+
+impl Default for QualitySettings {
+    fn default() -> Self {
+        Self {
+            good_min: 90.0,
+            fair_min: 70.0,
+        }
+    }
+}
+
+/// 抓取的严格模式（对应配置中的 `[strict]`），用于在 CI 中检查抓取质量
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrictSettings {
+    /// 是否总是启用严格模式（命令行的 `--strict` 也会启用）
+    pub enabled: bool,
+    /// 允许过滤失败的页面数
+    pub max_filter_failures: usize,
+    /// 允许的请求错误率（0 到 1），包括连接错误和 4xx、5xx 响应
+    pub max_error_rate: f64,
+    /// 条目数相比上次抓取结果允许减少的比例（0 到 1）
+    pub max_entry_drop: f64,
+}
+
+impl Default for StrictSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_filter_failures: 0,
+            max_error_rate: 0.05,
+            max_entry_drop: 0.2,
+        }
+    }
+}
+
+/// 搜索设置（对应配置中的 `[search]`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchSettings {
+    /// 是否记录选中的搜索结果，并按使用频率和最近使用时间提升它们的排名
+    pub history: bool,
+    /// 搜索历史文件，None 表示可写文档根目录下的 `search_history.json`
+    pub history_file: Option<PathBuf>,
+    /// 最多保留的记录数，超过时丢弃最久没有选中的记录
+    pub history_max_records: usize,
+    /// 超过此天数没有选中的记录会被丢弃
+    pub history_max_age_days: u64,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            history: false,
+            history_file: None,
+            history_max_records: 1000,
+            history_max_age_days: 180,
+        }
+    }
+}
+
+/// 内存缓存设置（对应配置中的 `[cache]`）
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CacheSettings {
+    /// 缓存页面内容的内存上限（MB），None 表示不限制
+    ///
+    /// 超过上限时按最近最少使用的顺序淘汰页面内容，文档索引不计入也不会被淘汰。
+    pub max_memory_mb: Option<u64>,
+    /// 缓存页面时是否同时保存 gzip 压缩的副本，客户端接受 gzip 时直接返回
+    ///
+    /// 压缩副本计入内存上限，换取热门页面不必在每次请求时重新压缩。
+    pub precompress: bool,
+}
+
+impl CacheSettings {
+    /// 内存上限（字节）
+    pub fn max_memory_bytes(&self) -> Option<usize> {
+        self.max_memory_mb.map(|mb| (mb as usize).saturating_mul(1024 * 1024))
+    }
+}
+
 /// 应用全局配置
 #[derive(Debug, Clone)]
 pub struct Config {
-    /// 文档存储路径
-    pub docs_path: String,
-    /// 默认文档列表
+    /// 文档存储路径列表
+    ///
+    /// 第一项为可写根目录，抓取和删除文档都只作用于它；
+    /// 其余各项为只读的叠加目录，仅用于加载和浏览文档。
+    pub docs_paths: Vec<PathBuf>,
+    /// 命名空间名到文档子目录的映射（对应配置中的 `[namespaces]`）
+    ///
+    /// 子目录为相对路径时相对于可写根目录。每个命名空间的文档和清单都在自己的
+    /// 子目录中，通过 `/t/<namespace>/docs/...` 访问；命名空间中没有的文档回退到
+    /// 全局命名空间（`docs_paths` 中的文档）。
+    pub namespaces: BTreeMap<String, PathBuf>,
+    /// 默认文档列表，顺序即前端中默认文档的顺序
     pub default_docs: Vec<String>,
+    /// 搜索别名到文档别名（slug）的映射，如 `js` → `javascript`
+    pub aliases: BTreeMap<String, String>,
     /// 服务器主机名
     pub host: String,
     /// 服务器端口
     pub port: u16,
+    /// 抓取器默认设置
+    pub scraper: ScraperDefaults,
+    /// Web 服务设置
+    pub serve: ServeSettings,
+    /// 前端资源源目录
+    pub assets_source_path: PathBuf,
+    /// 编译后的前端资源目录
+    pub assets_output_path: PathBuf,
+    /// 应用于所有文档的条目排除规则
+    pub exclude_entries: EntryExclusions,
+    /// 抓取时写入的 db.json 格式
+    pub db_format: DbFormat,
+    /// 事件日志设置
+    pub logging: LoggingSettings,
+    /// 内存缓存设置
+    pub cache: CacheSettings,
+    /// 抓取时的磁盘空间检查设置
+    pub disk_space: DiskSpaceSettings,
+    /// 命令行输出中的时间按本地时区还是 UTC 显示（命令行的 `--utc` 优先）
+    pub time_display: TimeDisplay,
+    /// 文档质量分数的区间
+    pub quality: QualitySettings,
+    /// 搜索设置
+    pub search: SearchSettings,
+    /// 抓取的严格模式
+    pub strict: StrictSettings,
+    /// 抓取和下载时的 TLS 设置，与命令行的 `--ca-cert`、`--insecure` 合并
+    ///
+    /// 默认不添加 CA 证书，也不关闭证书校验。
+    pub tls: TlsOptions,
+    /// `docs-outdated` 查询到的上游最新版本的缓存时间（秒），0 表示每次都重新查询
+    pub version_check_ttl: u64,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            docs_path: "docs".to_string(),
+            docs_paths: vec![PathBuf::from("docs")],
+            namespaces: BTreeMap::new(),
             default_docs: vec![
                 "html".to_string(),
                 "css".to_string(),
                 "javascript".to_string(),
                 "rust".to_string(),
             ],
+            aliases: BTreeMap::from([
+                ("js".to_string(), "javascript".to_string()),
+                ("ts".to_string(), "typescript".to_string()),
+            ]),
             host: "127.0.0.1".to_string(),
             port: 8000,
+            scraper: ScraperDefaults::default(),
+            serve: ServeSettings::default(),
+            assets_source_path: PathBuf::from("assets"),
+            assets_output_path: PathBuf::from("public/assets"),
+            exclude_entries: EntryExclusions::new(),
+            db_format: DbFormat::default(),
+            logging: LoggingSettings::default(),
+            cache: CacheSettings::default(),
+            disk_space: DiskSpaceSettings::default(),
+            time_display: TimeDisplay::default(),
+            quality: QualitySettings::default(),
+            search: SearchSettings::default(),
+            strict: StrictSettings::default(),
+            tls: TlsOptions::default(),
+            version_check_ttl: 24 * 60 * 60,
         }
     }
 }
@@ -35,9 +336,77 @@ impl Config {
         Self::default()
     }
 
-    /// 设置文档存储路径
+    /// 获取可写的文档根目录
+    pub fn docs_path(&self) -> String {
+        self.writable_root().to_string_lossy().to_string()
+    }
+
+    /// 获取可写的文档根目录路径
+    pub fn writable_root(&self) -> &Path {
+        self.docs_paths
+            .first()
+            .map(PathBuf::as_path)
+            .unwrap_or_else(|| Path::new("docs"))
+    }
+
+    /// 获取搜索历史文件路径
+    pub fn search_history_path(&self) -> PathBuf {
+        self.search
+            .history_file
+            .clone()
+            .unwrap_or_else(|| self.writable_root().join(SEARCH_HISTORY_FILENAME))
+    }
+
+    /// 获取只读的叠加目录
+    pub fn overlay_roots(&self) -> &[PathBuf] {
+        self.docs_paths.get(1..).unwrap_or(&[])
+    }
+
+    /// 获取命名空间的文档目录，没有配置该命名空间时返回 None
+    pub fn namespace_root(&self, name: &str) -> Option<PathBuf> {
+        self.namespaces.get(name).map(|subdir| self.writable_root().join(subdir))
+    }
+
+    /// 命名空间的视图，没有配置该命名空间时返回 None
+    ///
+    /// 可写根目录为命名空间的目录，抓取、删除文档和生成清单都只作用于它；全局的
+    /// 文档目录作为只读叠加目录，命名空间中的文档优先于相同 slug 和版本的全局文档。
+    pub fn in_namespace(&self, name: &str) -> Option<Config> {
+        let root = self.namespace_root(name)?;
+        let mut config = self.clone();
+        config.docs_paths = std::iter::once(root).chain(self.docs_paths.iter().cloned()).collect();
+        config.namespaces.clear();
+        Some(config)
+    }
+
+    /// 设置可写的文档存储路径
     pub fn with_docs_path(mut self, path: &str) -> Self {
-        self.docs_path = path.to_string();
+        if self.docs_paths.is_empty() {
+            self.docs_paths.push(PathBuf::from(path));
+        } else {
+            self.docs_paths[0] = PathBuf::from(path);
+        }
+        self
+    }
+
+    /// 设置全部文档存储路径（第一项可写，其余只读）
+    pub fn with_docs_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.docs_paths = paths;
+        self
+    }
+
+    /// 添加只读的叠加目录
+    pub fn with_overlay_path(mut self, path: &str) -> Self {
+        if self.docs_paths.is_empty() {
+            self.docs_paths.push(PathBuf::from("docs"));
+        }
+        self.docs_paths.push(PathBuf::from(path));
+        self
+    }
+
+    /// 添加命名空间，文档位于可写根目录下的 `subdir` 中
+    pub fn with_namespace(mut self, name: &str, subdir: impl Into<PathBuf>) -> Self {
+        self.namespaces.insert(name.to_string(), subdir.into());
         self
     }
 
@@ -47,6 +416,12 @@ impl Config {
         self
     }
 
+    /// 添加搜索别名，`alias` 指向文档别名 `slug`
+    pub fn with_alias(mut self, alias: &str, slug: &str) -> Self {
+        self.aliases.insert(alias.to_string(), slug.to_string());
+        self
+    }
+
     /// 设置服务器主机名
     pub fn with_host(mut self, host: &str) -> Self {
         self.host = host.to_string();
@@ -58,4 +433,108 @@ impl Config {
         self.port = port;
         self
     }
+
+    /// 设置是否禁止搜索引擎索引
+    pub fn with_noindex(mut self, noindex: bool) -> Self {
+        self.serve.noindex = noindex;
+        self
+    }
+
+    /// 设置是否以只读模式运行服务器
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.serve.read_only = read_only;
+        self
+    }
+
+    /// 设置是否注入文档的自定义样式和脚本，`js` 为 true 时同时注入 `custom.js`
+    pub fn with_custom_assets(mut self, enabled: bool, js: bool) -> Self {
+        self.serve.custom_assets = enabled;
+        self.serve.custom_js = js;
+        self
+    }
+
+    /// 设置前端资源的源目录和编译输出目录
+    pub fn with_assets_paths(mut self, source: &str, output: &str) -> Self {
+        self.assets_source_path = PathBuf::from(source);
+        self.assets_output_path = PathBuf::from(output);
+        self
+    }
+
+    /// 设置应用于所有文档的条目排除规则
+    pub fn with_exclude_entries(mut self, exclusions: EntryExclusions) -> Self {
+        self.exclude_entries = exclusions;
+        self
+    }
+
+    /// 设置抓取时写入的 db.json 格式
+    pub fn with_db_format(mut self, format: DbFormat) -> Self {
+        self.db_format = format;
+        self
+    }
+
+    /// 设置抓取器默认设置
+    pub fn with_scraper_defaults(mut self, defaults: ScraperDefaults) -> Self {
+        self.scraper = defaults;
+        self
+    }
+
+    /// 设置事件日志
+    pub fn with_logging(mut self, logging: LoggingSettings) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    /// 设置缓存页面内容的内存上限（MB），None 表示不限制
+    pub fn with_cache_budget(mut self, max_memory_mb: Option<u64>) -> Self {
+        self.cache.max_memory_mb = max_memory_mb;
+        self
+    }
+
+    /// 设置是否在缓存页面时同时保存 gzip 压缩的副本
+    pub fn with_precompression(mut self, precompress: bool) -> Self {
+        self.cache.precompress = precompress;
+        self
+    }
+
+    /// 设置抓取时的磁盘空间检查
+    pub fn with_disk_space(mut self, settings: DiskSpaceSettings) -> Self {
+        self.disk_space = settings;
+        self
+    }
+
+    /// 设置命令行输出中的时间按本地时区还是 UTC 显示
+    pub fn with_time_display(mut self, display: TimeDisplay) -> Self {
+        self.time_display = display;
+        self
+    }
+
+    /// 设置文档质量分数的区间
+    pub fn with_quality(mut self, settings: QualitySettings) -> Self {
+        self.quality = settings;
+        self
+    }
+
+    /// 设置搜索选项
+    pub fn with_search(mut self, settings: SearchSettings) -> Self {
+        self.search = settings;
+        self
+    }
+
+    /// 设置抓取的严格模式
+    pub fn with_strict(mut self, settings: StrictSettings) -> Self {
+        self.strict = settings;
+        self
+    }
+
+    /// 添加抓取和下载时信任的 CA 证书
+    pub fn with_ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tls = self.tls.with_ca_cert(path);
+        self
+    }
+
+    /// 设置上游最新版本的缓存时间（秒）
+    pub fn with_version_check_ttl(mut self, seconds: u64) -> Self {
+        self.version_check_ttl = seconds;
+        self
+    }
 }
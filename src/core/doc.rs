@@ -4,10 +4,13 @@
 //! 提供文档的基本属性和操作功能
 
 use crate::core::error::Result;
-use crate::core::index_entry::{FullIndex, IndexEntry, IndexType};
+use crate::core::index_entry::{FullIndex, IndexEntry, IndexType, TypeMeta};
 use crate::storage::store::Store;
 use serde::{Deserialize, Serialize};
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
 use serde_json::json;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -15,6 +18,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub const INDEX_FILENAME: &str = "index.json";
 pub const DB_FILENAME: &str = "db.json";
 pub const META_FILENAME: &str = "meta.json";
+/// 页面路径列表，不必读取 db.json 即可判断页面是否存在
+pub const PAGES_FILENAME: &str = "pages.json";
 
 /// 文档元数据结构
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -33,6 +38,15 @@ pub struct DocMeta {
     pub mtime: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub db_size: Option<usize>,
+    /// 默认落地页面路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub landing_path: Option<String>,
+    /// 版权和许可说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+    /// 内容的许可证，已知时为 SPDX 标识（如 `CC-BY-SA-2.5`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
 }
 
 /// 页面数据库，存储页面路径和内容的映射
@@ -75,7 +89,13 @@ impl PageDb {
 pub struct EntryIndex {
     entries: Vec<IndexEntry>,
     index: HashSet<String>,
+    /// 条目的去重键，键相同的条目只保留先添加的
+    key_fn: fn(&IndexEntry) -> String,
     types: HashMap<String, IndexType>,
+    /// 抓取器提供的类型元数据
+    type_meta: HashMap<String, TypeMeta>,
+    /// 条目名称的排序语言（None 表示通用规则）
+    collation_locale: Option<String>,
 }
 
 impl EntryIndex {
@@ -84,15 +104,49 @@ impl EntryIndex {
         Self {
             entries: Vec::new(),
             index: HashSet::new(),
+            key_fn: IndexEntry::json_key,
             types: HashMap::new(),
+            type_meta: HashMap::new(),
+            collation_locale: None,
         }
     }
 
+    /// 设置条目的去重键，默认以完整的 JSON 去重
+    ///
+    /// 如使用 [`IndexEntry::name_path_key`] 时，名称和路径相同、只有类型不同的条目也算重复。
+    pub fn with_key_fn(mut self, key_fn: fn(&IndexEntry) -> String) -> Self {
+        self.key_fn = key_fn;
+        self
+    }
+
+    /// 设置条目名称的排序语言（如 `de`、`zh`），默认使用通用规则
+    pub fn with_collation_locale(mut self, locale: &str) -> Self {
+        self.set_collation_locale(Some(locale));
+        self
+    }
+
+    /// 设置条目名称的排序语言
+    pub fn set_collation_locale(&mut self, locale: Option<&str>) {
+        self.collation_locale = locale.map(String::from);
+    }
+
+    /// 设置类型的排序权重和说明
+    ///
+    /// 元数据在生成索引时与统计出的类型合并：没有条目的类型不会出现，
+    /// 没有元数据的类型使用默认权重 0。
+    pub fn with_type_meta(mut self, name: &str, weight: i32, description: Option<&str>) -> Self {
+        self.set_type_meta(name, TypeMeta::new(weight, description));
+        self
+    }
+
+    /// 设置类型的元数据
+    pub fn set_type_meta(&mut self, name: &str, meta: TypeMeta) {
+        self.type_meta.insert(name.to_string(), meta);
+    }
+
     /// 添加条目
     pub fn add(&mut self, entry: IndexEntry) {
-        let entry_json = serde_json::to_string(&entry).unwrap_or_default();
-        
-        if self.index.insert(entry_json) {
+        if self.index.insert((self.key_fn)(&entry)) {
             if let Some(entry_type) = self.types.get_mut(&entry.entry_type) {
                 entry_type.count += 1;
             } else {
@@ -102,6 +156,8 @@ impl EntryIndex {
                         name: entry.entry_type.clone(),
                         count: 1,
                         slug: entry.entry_type.to_lowercase(),
+                        weight: 0,
+                        description: None,
                     },
                 );
             }
@@ -128,12 +184,29 @@ impl EntryIndex {
 
     /// 转换为完整索引结构
     pub fn to_full_index(&mut self) -> FullIndex {
-        // 按照名称排序条目
-        self.entries.sort_by(|a, b| sort_entries(&a.name, &b.name));
+        // 按照名称排序条目，名称相同的条目按路径和类型排序，保证顺序稳定
+        let collator = EntryCollator::new(self.collation_locale.as_deref());
+        self.entries.sort_by(|a, b| {
+            collator
+                .compare(&a.name, &b.name)
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.entry_type.cmp(&b.entry_type))
+        });
         
-        // 转换类型列表并排序
-        let mut types: Vec<_> = self.types.values().cloned().collect();
-        types.sort_by(|a, b| sort_entries(&a.name, &b.name));
+        // 合并类型元数据，按权重和名称排序
+        let mut types: Vec<_> = self
+            .types
+            .values()
+            .cloned()
+            .map(|mut index_type| {
+                if let Some(meta) = self.type_meta.get(&index_type.name) {
+                    index_type.weight = meta.weight;
+                    index_type.description = meta.description.clone();
+                }
+                index_type
+            })
+            .collect();
+        types.sort_by(|a, b| a.weight.cmp(&b.weight).then_with(|| collator.compare(&a.name, &b.name)));
         
         FullIndex {
             entries: self.entries.clone(),
@@ -218,6 +291,9 @@ pub trait Doc {
             links: self.links(),
             mtime: None,
             db_size: None,
+            landing_path: None,
+            attribution: None,
+            license: None,
         }
     }
     
@@ -332,132 +408,138 @@ pub trait Doc {
         Ok(())
     }
     
-    /// 判断文档版本状态
+    /// 判断文档版本状态，见 [`crate::core::version_probe::outdated_state`]
     fn outdated_state(&self, scraper_version: &str, latest_version: &str) -> String {
-        let scraper_parts: Vec<_> = scraper_version
-            .split(|c| c == '-' || c == '.')
-            .map(|s| s.parse::<u32>().unwrap_or(0))
-            .collect();
-        
-        let latest_parts: Vec<_> = latest_version
-            .split(|c| c == '-' || c == '.')
-            .map(|s| s.parse::<u32>().unwrap_or(0))
-            .collect();
-        
-        // 只检查前两部分，第三部分是补丁更新
-        for i in 0..2 {
-            if i >= scraper_parts.len() || i >= latest_parts.len() {
-                break;
-            }
-            
-            if i == 0 && latest_parts[i] > scraper_parts[i] {
-                return "Outdated major version".to_string();
-            }
-            
-            if i == 1 && latest_parts[i] > scraper_parts[i] {
-                if (latest_parts[0] == 0 && scraper_parts[0] == 0) || 
-                   (latest_parts[0] == 1 && scraper_parts[0] == 1) {
-                    return "Outdated major version".to_string();
-                }
-                return "Outdated minor version".to_string();
-            }
-            
-            if latest_parts[i] < scraper_parts[i] {
-                return "Up-to-date".to_string();
-            }
-        }
-        
-        "Up-to-date".to_string()
+        crate::core::version_probe::outdated_state(scraper_version, latest_version).to_string()
     }
 }
 
 /// 辅助函数 - 分割整数
+///
+/// 在数字之后、数字或空白之前的 `.` 处分割，例如 `10.20.30` 分割为
+/// `["10", ".20", ".30"]`，`1. Introduction` 分割为 `["1", ". Introduction"]`。
 fn split_ints(s: &str) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
     let mut result = Vec::new();
     let mut current = String::new();
-    let mut last_was_digit = false;
-    
-    for c in s.chars() {
-        let is_digit = c.is_digit(10);
-        
-        if is_digit && !last_was_digit && !current.is_empty() {
-            result.push(current);
-            current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        let splits = c == '.'
+            && i > 0
+            && chars[i - 1].is_ascii_digit()
+            && chars.get(i + 1).is_some_and(|next| next.is_ascii_digit() || next.is_whitespace());
+        if splits && !current.is_empty() {
+            result.push(std::mem::take(&mut current));
         }
-        
         current.push(c);
-        last_was_digit = is_digit;
     }
-    
+
     if !current.is_empty() {
         result.push(current);
     }
-    
+
     result
 }
 
-/// 条目排序函数
-fn sort_entries(a: &str, b: &str) -> std::cmp::Ordering {
-    let a_first = a.chars().next().map(|c| c.is_digit(10)).unwrap_or(false);
-    let b_first = b.chars().next().map(|c| c.is_digit(10)).unwrap_or(false);
-    
-    if a_first || b_first {
-        let a_split = split_ints(a);
-        let b_split = split_ints(b);
-        
-        let a_len = a_split.len();
-        let b_len = b_split.len();
-        
-        if a_len == 1 && b_len == 1 {
-            return a.to_lowercase().cmp(&b.to_lowercase());
-        }
-        
-        if a_len == 1 {
-            return std::cmp::Ordering::Greater;
-        }
-        
-        if b_len == 1 {
-            return std::cmp::Ordering::Less;
-        }
-        
-        // 处理数值部分
-        let mut a_processed: Vec<_> = a_split.iter().enumerate()
-            .map(|(i, s)| {
-                if i == a_len - 1 {
-                    s.to_string()
-                } else {
-                    s.parse::<u32>().unwrap_or(0).to_string()
-                }
-            })
-            .collect();
-        
-        let mut b_processed: Vec<_> = b_split.iter().enumerate()
-            .map(|(i, s)| {
-                if i == b_len - 1 {
-                    s.to_string()
-                } else {
-                    s.parse::<u32>().unwrap_or(0).to_string()
-                }
-            })
-            .collect();
-        
-        // 调整长度使之相等
-        if b_len > a_len {
-            for _ in 0..(b_len - a_len) {
-                a_processed.insert(a_processed.len() - 1, "0".to_string());
-            }
-        } else if a_len > b_len {
-            for _ in 0..(a_len - b_len) {
-                b_processed.insert(b_processed.len() - 1, "0".to_string());
+/// 条目名称的排序规则
+///
+/// 名称使用 Unicode 排序规则比较，带重音的字母排在对应的基本字母附近，
+/// 本地化文档中的中日韩名称按所选语言的习惯排序（例如 `zh` 按拼音），
+/// 以符号开头的名称统一排在最前。以数字开头的名称保留按数字分段比较的规则
+/// （`1.10` 排在 `1.2` 之后）。排序规则认为相同的名称再按字节比较，
+/// 保证生成的 index.json 顺序稳定。
+pub struct EntryCollator {
+    collator: Collator,
+}
+
+impl EntryCollator {
+    /// 创建指定语言的排序规则，语言无效时使用通用（root）规则
+    pub fn new(locale: Option<&str>) -> Self {
+        let locale = locale
+            .and_then(|locale| locale.replace('_', "-").parse::<Locale>().ok())
+            .unwrap_or(Locale::UND);
+        let collator = Collator::try_new(&(&locale).into(), CollatorOptions::new())
+            .or_else(|_| Collator::try_new(&Default::default(), CollatorOptions::new()))
+            .expect("内置的 root 排序规则总是可用");
+        Self { collator }
+    }
+
+    /// 通用（root）排序规则
+    pub fn root() -> Self {
+        Self::new(None)
+    }
+
+    /// 比较两个条目名称
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let starts_with_digit = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+        let ordering = if starts_with_digit(a) || starts_with_digit(b) {
+            let a_split = split_ints(a);
+            let b_split = split_ints(b);
+            match (a_split.len(), b_split.len()) {
+                (1, 1) => self.collator.compare(a, b),
+                (1, _) => Ordering::Greater,
+                (_, 1) => Ordering::Less,
+                _ => self.compare_segments(a_split, b_split),
             }
+        } else {
+            self.collator.compare(a, b)
+        };
+        ordering.then_with(|| a.cmp(b))
+    }
+
+    /// 按分段比较，分段数不同时在最后一段之前补 0
+    fn compare_segments(&self, mut a: Vec<String>, mut b: Vec<String>) -> Ordering {
+        while a.len() < b.len() {
+            a.insert(a.len() - 1, "0".to_string());
         }
-        
-        a_processed.cmp(&b_processed)
+        while b.len() < a.len() {
+            b.insert(b.len() - 1, "0".to_string());
+        }
+
+        a.iter()
+            .zip(&b)
+            .map(|(x, y)| self.compare_segment(x, y))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// 比较单个分段：先比较开头的数字，再比较其余部分
+    fn compare_segment(&self, a: &str, b: &str) -> Ordering {
+        let (a_number, a_rest) = leading_number(a);
+        let (b_number, b_rest) = leading_number(b);
+        let numbers = match (a_number, b_number) {
+            (Some(x), Some(y)) => compare_digits(x, y),
+            (x, y) => x.is_some().cmp(&y.is_some()),
+        };
+        numbers.then_with(|| self.collator.compare(a_rest, b_rest))
+    }
+}
+
+/// 拆出分段开头（忽略分隔用的 `.`）的数字
+fn leading_number(segment: &str) -> (Option<&str>, &str) {
+    let segment = segment.strip_prefix('.').unwrap_or(segment);
+    let end = segment
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(segment.len());
+    if end == 0 {
+        (None, segment)
     } else {
-        a.to_lowercase().cmp(&b.to_lowercase())
+        (Some(&segment[..end]), &segment[end..])
     }
 }
 
+/// 按数值比较数字串，不受整数位数限制
+fn compare_digits(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// 条目排序函数，使用通用（root）排序规则
+pub fn sort_entries(a: &str, b: &str) -> Ordering {
+    EntryCollator::root().compare(a, b)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -478,6 +560,152 @@ mod tests {
         assert_eq!(sort_entries("1.item", "2.item"), std::cmp::Ordering::Less);
     }
     
+    fn sorted(collator: &EntryCollator, names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        names.sort_by(|a, b| collator.compare(a, b));
+        names
+    }
+
+    #[test]
+    fn test_sort_accented_and_symbol_names() {
+        let root = EntryCollator::root();
+        assert_eq!(
+            sorted(&root, &["cafeteria", "Café", "cafe", "Zebra", "_private", "éclair", "$", "delta"]),
+            ["_private", "$", "cafe", "Café", "cafeteria", "delta", "éclair", "Zebra"]
+        );
+        // 语言相关的规则：瑞典语中 ä 排在 z 之后
+        assert_eq!(sorted(&root, &["zeta", "äpple", "apple"]), ["apple", "äpple", "zeta"]);
+        let swedish = EntryCollator::new(Some("sv"));
+        assert_eq!(sorted(&swedish, &["zeta", "äpple", "apple"]), ["apple", "zeta", "äpple"]);
+    }
+
+    #[test]
+    fn test_sort_cjk_names() {
+        // 通用规则中汉字按码位排在拉丁字母之后；中文规则按拼音，汉字排在最前：
+        // 北京 (bei) 排在上海 (shang) 之前
+        let names = ["上海", "北京", "array", "数组"];
+        assert_eq!(sorted(&EntryCollator::root(), &names), ["array", "上海", "北京", "数组"]);
+        assert_eq!(sorted(&EntryCollator::new(Some("zh_CN")), &names), ["北京", "上海", "数组", "array"]);
+        // 无效的语言使用通用规则
+        assert_eq!(sorted(&EntryCollator::new(Some("not a locale!")), &names), ["array", "上海", "北京", "数组"]);
+    }
+
+    #[test]
+    fn test_sort_mixed_numeric_names() {
+        let root = EntryCollator::root();
+        assert_eq!(
+            sorted(&root, &["1.10 Élan", "1.2 Setup", "10. Appendix", "2. Usage", "Intro", "9. Ünits"]),
+            ["1.2 Setup", "1.10 Élan", "2. Usage", "9. Ünits", "10. Appendix", "Intro"]
+        );
+        assert_eq!(
+            sorted(&root, &["1.2.10 More", "1.2.1 Détails", "1.2.1 Details"]),
+            ["1.2.1 Details", "1.2.1 Détails", "1.2.10 More"]
+        );
+        assert_eq!(sort_entries("99999999999999999999.1", "100000000000000000000.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_index_order_is_stable() {
+        let entries = vec![
+            IndexEntry { path: "b".to_string(), ..entry("get", "Map") },
+            IndexEntry { path: "a".to_string(), ..entry("get", "Map") },
+            entry("get", "Headers"),
+            entry("Get", "Map"),
+            entry("Éclair", "Misc"),
+            entry("eclair", "Misc"),
+            entry("1.10 Notes", "Guides"),
+            entry("1.2 Notes", "Guides"),
+        ];
+
+        let json_of = |entries: Vec<IndexEntry>| {
+            let mut index = EntryIndex::new().with_collation_locale("en");
+            index.add_multiple(entries);
+            index.to_json()
+        };
+        let expected = json_of(entries.clone());
+        for rotation in 1..entries.len() {
+            let mut shuffled = entries.clone();
+            shuffled.rotate_left(rotation);
+            shuffled.reverse();
+            assert_eq!(json_of(shuffled), expected);
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&expected).unwrap();
+        let order: Vec<(&str, &str)> = json["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["name"].as_str().unwrap(), e["path"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("1.2 Notes", "1.2 notes"),
+                ("1.10 Notes", "1.10 notes"),
+                ("eclair", "eclair"),
+                ("Éclair", "éclair"),
+                ("get", "a"),
+                ("get", "b"),
+                ("get", "get"),
+                ("Get", "get"),
+            ]
+        );
+    }
+
+    fn entry(name: &str, entry_type: &str) -> IndexEntry {
+        IndexEntry {
+            name: name.to_string(),
+            path: name.to_lowercase(),
+            entry_type: entry_type.to_string(),
+            word_count: None,
+            inherited: None,
+        }
+    }
+
+    #[test]
+    fn test_types_ordered_by_weight_with_meta() {
+        let mut index = EntryIndex::new()
+            .with_type_meta("Guides", -10, Some("Tutorials and concepts"))
+            .with_type_meta("Reference", 5, None)
+            .with_type_meta("Unused", -20, Some("No entries"));
+        index.add_multiple(vec![
+            entry("Array", "Reference"),
+            entry("Intro", "Guides"),
+            entry("Zeta", "Misc"),
+            entry("Alpha", "Classes"),
+        ]);
+
+        let types = index.to_full_index().types;
+        let names: Vec<&str> = types.iter().map(|t| t.name.as_str()).collect();
+        // 未提供元数据的类型使用默认权重 0，没有条目的类型不出现
+        assert_eq!(names, ["Guides", "Classes", "Misc", "Reference"]);
+        assert_eq!(types[0].description.as_deref(), Some("Tutorials and concepts"));
+        assert_eq!((types[1].weight, types[1].description.as_deref()), (0, None));
+
+        let json: serde_json::Value = serde_json::from_str(&index.to_json()).unwrap();
+        assert_eq!(json["types"][0]["weight"], -10);
+        assert_eq!(json["types"][0]["description"], "Tutorials and concepts");
+        assert!(json["types"][1].get("weight").is_none());
+        assert!(json["types"][1].get("description").is_none());
+    }
+
+    #[test]
+    fn test_key_fn_collapses_near_duplicates() {
+        // 默认只去掉完全相同的条目
+        let mut index = EntryIndex::new();
+        index.add_multiple(vec![entry("Array", "Reference"), entry("Array", "Reference"), entry("Array", "Classes")]);
+        assert_eq!(index.len(), 2);
+
+        // 按名称和路径去重时只保留先添加的条目，类型计数随之减少
+        let mut index = EntryIndex::new().with_key_fn(IndexEntry::name_path_key);
+        index.add_multiple(vec![entry("Array", "Reference"), entry("Array", "Classes"), entry("Map", "Classes")]);
+        let full = index.to_full_index();
+        assert_eq!(full.entries.len(), 2);
+        assert_eq!(full.entries[0].entry_type, "Reference");
+        let counts: Vec<(&str, usize)> = full.types.iter().map(|t| (t.name.as_str(), t.count)).collect();
+        assert_eq!(counts, [("Classes", 1), ("Reference", 1)]);
+    }
+
     #[test]
     fn test_split_ints() {
         // 测试数字分割
@@ -1,8 +1,18 @@
 //! 错误处理模块
+//!
+//! 深层的错误（如过滤器中的“无效的选择器”）本身不说明出错的页面和过滤器。
+//! 抓取器把过滤器错误包装为 [`Error::FilterFailed`]，其他调用方可以用
+//! [`ResultExt::context`] 添加上下文（文档、页面路径、文件路径）。带上下文的
+//! 错误显示时按“外层: 内层”的顺序输出整条错误链。
+//!
+//! 抓取时的失败按来源区分：请求失败为 [`Error::Fetch`]，页面或响应内容无法解析
+//! 为 [`Error::Parse`]，读写输出文件失败为 [`Error::Storage`]，都带有出错的网址
+//! 或路径，嵌入本库的程序可以据此区分网络、内容和磁盘问题。
 
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+use std::path::PathBuf;
 
 /// 应用错误类型
 #[derive(Debug)]
@@ -27,6 +37,104 @@ pub enum Error {
     Doc(String),
     /// 通用错误消息
     Message(String),
+    /// 操作已被取消
+    Cancelled,
+    /// 可用磁盘空间不足
+    InsufficientDiskSpace {
+        /// 检查的路径
+        path: PathBuf,
+        /// 可用空间（字节）
+        available: u64,
+        /// 需要的空间（字节）
+        required: u64,
+    },
+    /// 严格模式下抓取结果没有通过检查，每项为一条没有通过的检查
+    StrictFailed(Vec<String>),
+    /// 过滤器处理页面失败
+    FilterFailed {
+        /// 过滤器名称
+        filter: String,
+        /// 页面URL
+        url: String,
+        /// 过滤器返回的错误
+        source: Box<Error>,
+    },
+    /// 请求网址失败（连接错误、超时、非成功的状态码等）
+    Fetch {
+        /// 请求的网址
+        url: String,
+        /// 原始错误
+        source: Box<Error>,
+    },
+    /// 网址返回的内容无法解析
+    Parse {
+        /// 内容的网址
+        url: String,
+        /// 无法解析的原因
+        detail: String,
+    },
+    /// 读写文件失败
+    Storage {
+        /// 文件路径
+        path: PathBuf,
+        /// 原始错误
+        source: Box<Error>,
+    },
+    /// JSON 文件无法解析（编码错误或内容无效）
+    InvalidJson {
+        /// 文件路径
+        path: PathBuf,
+        /// 出错位置在文件中的字节偏移，无法确定位置（如 UTF-16 编码）时为 None
+        offset: Option<usize>,
+        /// 无法解析的原因
+        detail: String,
+    },
+    /// 带有上下文的错误
+    Context {
+        /// 上下文说明，如出错的文档和页面
+        context: String,
+        /// 原始错误
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// 为错误添加上下文
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Error::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// 去掉所有上下文后的原始错误
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::FilterFailed { source, .. }
+            | Error::Fetch { source, .. }
+            | Error::Storage { source, .. }
+            | Error::Context { source, .. } => source.root_cause(),
+            _ => self,
+        }
+    }
+
+    /// 错误链中出错的网址（请求、解析或过滤失败的网址）
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Error::Fetch { url, .. } | Error::Parse { url, .. } | Error::FilterFailed { url, .. } => Some(url),
+            Error::Storage { source, .. } | Error::Context { source, .. } => source.url(),
+            _ => None,
+        }
+    }
+
+    /// 错误链中出错的过滤器名称
+    pub fn filter_name(&self) -> Option<&str> {
+        match self {
+            Error::FilterFailed { filter, .. } => Some(filter),
+            Error::Context { source, .. } => source.filter_name(),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -42,6 +150,34 @@ impl fmt::Display for Error {
             Error::ParseError(msg) => write!(f, "解析错误: {}", msg),
             Error::Doc(msg) => write!(f, "文档错误: {}", msg),
             Error::Message(msg) => write!(f, "{}", msg),
+            Error::Cancelled => write!(f, "操作已取消"),
+            Error::InsufficientDiskSpace { path, available, required } => write!(
+                f,
+                "磁盘空间不足: {:?} 可用 {} MB，需要 {} MB",
+                path,
+                available / (1024 * 1024),
+                required.div_ceil(1024 * 1024)
+            ),
+            Error::StrictFailed(gates) => {
+                write!(f, "严格模式检查未通过 ({} 项)", gates.len())?;
+                for gate in gates {
+                    write!(f, "\n  - {}", gate)?;
+                }
+                Ok(())
+            }
+            Error::FilterFailed { filter, url, source } => {
+                write!(f, "过滤器 {} 处理 {} 失败: {}", filter, url, source)
+            }
+            Error::Fetch { url, source } => write!(f, "请求 {} 失败: {}", url, source),
+            Error::Parse { url, detail } => write!(f, "无法解析 {}: {}", url, detail),
+            Error::Storage { path, source } => write!(f, "读写 {} 失败: {}", path.display(), source),
+            Error::InvalidJson { path, offset: Some(offset), detail } => {
+                write!(f, "无法解析 {}（字节偏移 {}）: {}", path.display(), offset, detail)
+            }
+            Error::InvalidJson { path, offset: None, detail } => {
+                write!(f, "无法解析 {}: {}", path.display(), detail)
+            }
+            Error::Context { context, source } => write!(f, "{}: {}", context, source),
         }
     }
 }
@@ -52,8 +188,14 @@ impl StdError for Error {
             Error::Io(err) => Some(err),
             Error::Http(err) => Some(err),
             Error::Json(err) => Some(err),
+            Error::FilterFailed { source, .. }
+            | Error::Fetch { source, .. }
+            | Error::Storage { source, .. }
+            | Error::Context { source, .. } => Some(source.as_ref()),
             Error::Html(_) | Error::ParseError(_) | Error::Doc(_) | Error::Message(_) | 
-            Error::HttpError(_) | Error::InvalidContentType(_) | Error::InvalidUrl(_) => None,
+            Error::HttpError(_) | Error::InvalidContentType(_) | Error::InvalidUrl(_) | Error::Cancelled |
+            Error::InsufficientDiskSpace { .. } | Error::StrictFailed(_) | Error::Parse { .. } |
+            Error::InvalidJson { .. } => None,
         }
     }
 }
@@ -96,3 +238,113 @@ impl From<Box<dyn StdError>> for Error {
 
 /// 应用结果类型
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// 逐行显示错误链，用于命令行输出
+///
+/// 第一行为最外层的错误，之后每行一个原因。本库的错误在显示时已经包含内层错误，
+/// 内容已经出现过的原因不再重复，只补充第三方错误（如 reqwest 的连接错误）内部
+/// 更深的原因。
+pub fn error_chain(error: &dyn StdError) -> String {
+    let mut shown = error.to_string();
+    let mut lines = vec![shown.clone()];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let message = cause.to_string();
+        if !shown.contains(&message) {
+            lines.push(format!("  原因: {}", message));
+            shown.push_str(&message);
+        }
+        source = cause.source();
+    }
+    lines.join("\n")
+}
+
+/// 为结果中的错误添加上下文
+pub trait ResultExt<T> {
+    /// 添加固定的上下文
+    fn context(self, context: impl Into<String>) -> Result<T>;
+
+    /// 出错时才生成上下文
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T> {
+        self.map_err(|e| e.into().context(context()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_chain_display() {
+        let error = Error::FilterFailed {
+            filter: "CssEntriesFilter".to_string(),
+            url: "https://example.com/a".to_string(),
+            source: Box::new(Error::Html("无效的选择器: h1[".to_string())),
+        };
+        let result: Result<()> = Err(error);
+        let error = result.context("文档 css 页面 a").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "文档 css 页面 a: 过滤器 CssEntriesFilter 处理 https://example.com/a 失败: HTML错误: 无效的选择器: h1["
+        );
+        assert_eq!(error.filter_name(), Some("CssEntriesFilter"));
+        assert!(matches!(error.root_cause(), Error::Html(_)));
+        assert!(StdError::source(&error).unwrap().to_string().starts_with("过滤器 CssEntriesFilter"));
+
+        let io: std::result::Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        let error = io.with_context(|| "无法读取 \"db.json\"".to_string()).unwrap_err();
+        assert_eq!(error.to_string(), "无法读取 \"db.json\": IO错误: missing");
+    }
+
+    #[test]
+    fn test_structured_errors_keep_url_and_source() {
+        let error = Error::Fetch {
+            url: "https://example.com/a".to_string(),
+            source: Box::new(Error::HttpError(503)),
+        }
+        .context("文档 css");
+        assert_eq!(error.url(), Some("https://example.com/a"));
+        assert!(matches!(error.root_cause(), Error::HttpError(503)));
+        assert_eq!(error_chain(&error), "文档 css: 请求 https://example.com/a 失败: HTTP错误代码: 503");
+
+        let error = Error::Storage {
+            path: PathBuf::from("docs/css/db.json"),
+            source: Box::new(Error::Io(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))),
+        };
+        assert_eq!(error.url(), None);
+        assert!(matches!(error.root_cause(), Error::Io(_)));
+        assert_eq!(error.to_string(), "读写 docs/css/db.json 失败: IO错误: denied");
+
+        // 第三方错误内部的原因没有出现在显示内容中时单独成行
+        #[derive(Debug)]
+        struct SendError(io::Error);
+        impl fmt::Display for SendError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "error sending request")
+            }
+        }
+        impl StdError for SendError {
+            fn source(&self) -> Option<&(dyn StdError + 'static)> {
+                Some(&self.0)
+            }
+        }
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "connection refused");
+        let error = Error::Fetch {
+            url: "https://example.com/".to_string(),
+            source: Box::new(Error::Io(io::Error::new(io::ErrorKind::Other, SendError(refused)))),
+        };
+        assert_eq!(
+            error_chain(&error),
+            "请求 https://example.com/ 失败: IO错误: error sending request\n  原因: connection refused"
+        );
+    }
+}
@@ -1,13 +1,23 @@
 //! 过滤器注册表实现
 //! 用于全局管理和访问过滤器
-
+//!
+//! 全局注册表保存所有文档共用的过滤器，[`initialize`] 注册内置过滤器。某个文档
+//! 需要换掉全局过滤器（如 Babel 用 `babel/clean` 代替 `clean_html`）时，不修改
+//! 全局注册表，而是用 [`FilterStack::with_overrides`] 创建只属于该文档的覆盖层，
+//! 见 `filter_stack` 模块。
+//!
+//! [`FilterStack::with_overrides`]: crate::core::filter_stack::FilterStack::with_overrides
+
+use crate::core::filters::{BaseCleanHtmlFilter, DefaultEntriesFilter};
 use crate::core::scraper::filter::Filter;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 /// 过滤器创建函数类型
-type FilterFactory = Box<dyn Fn() -> Box<dyn Filter> + Send + Sync>;
+///
+/// 覆盖层创建时复制工厂函数的引用，之后全局注册表的变化不影响覆盖层。
+pub type FilterFactory = Arc<dyn Fn() -> Box<dyn Filter> + Send + Sync>;
 
 /// 全局过滤器注册表
 pub struct FilterRegistry {
@@ -39,8 +49,24 @@ impl FilterRegistry {
         F: Fn() -> T + 'static + Send + Sync,
         T: Filter + 'static,
     {
-        let boxed_factory = Box::new(move || -> Box<dyn Filter> { Box::new(factory()) });
-        self.factories.insert(name.to_string(), boxed_factory);
+        let factory: FilterFactory = Arc::new(move || -> Box<dyn Filter> { Box::new(factory()) });
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    /// 注册内置过滤器，已注册的同名过滤器保留不变
+    fn register_builtins(&mut self) {
+        let builtins: [(&str, FilterFactory); 2] = [
+            ("clean_html", Arc::new(|| Box::new(BaseCleanHtmlFilter::new()) as Box<dyn Filter>)),
+            ("entries", Arc::new(|| Box::new(DefaultEntriesFilter::new()) as Box<dyn Filter>)),
+        ];
+        for (name, factory) in builtins {
+            self.factories.entry(name.to_string()).or_insert(factory);
+        }
+    }
+
+    /// 获取过滤器的工厂函数
+    pub fn factory(&self, name: &str) -> Option<FilterFactory> {
+        self.factories.get(name).cloned()
     }
 
     /// 创建过滤器实例
@@ -69,6 +95,21 @@ where
     registry.register(name, factory);
 }
 
+/// 注册内置过滤器，可以多次调用
+///
+/// 已注册的同名过滤器保留不变，因此在此之前注册的过滤器不会被内置过滤器替换，
+/// 在此之后注册的同名过滤器照常替换内置过滤器。
+pub fn initialize() {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.register_builtins();
+}
+
+/// 全局获取过滤器的工厂函数
+pub fn filter_factory(name: &str) -> Option<FilterFactory> {
+    let registry = REGISTRY.lock().unwrap();
+    registry.factory(name)
+}
+
 /// 全局创建过滤器实例
 pub fn create_filter(name: &str) -> Option<Box<dyn Filter>> {
     let registry = REGISTRY.lock().unwrap();
@@ -90,18 +131,15 @@ pub fn filter_names() -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scrapers::Filter;
+    use crate::core::error::Result;
+    use crate::core::scraper::filter::{FilterContext, FilterOutput};
     use std::any::Any;
 
     struct TestFilter;
 
     impl Filter for TestFilter {
-        fn apply(
-            &self,
-            html: &str,
-            _context: &crate::scrapers::filter::FilterContext,
-        ) -> crate::core::error::Result<String> {
-            Ok(html.to_string())
+        fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+            Ok(FilterOutput::new(html))
         }
 
         fn box_clone(&self) -> Box<dyn Filter> {
@@ -126,4 +164,19 @@ mod tests {
         let filter_names = filter_names();
         assert!(filter_names.contains(&"test_filter".to_string()));
     }
+
+    #[test]
+    fn test_initialize_keeps_earlier_registrations() {
+        // 在初始化之前注册的同名过滤器不被内置过滤器替换，初始化可以重复调用
+        let mut registry = FilterRegistry::new();
+        registry.register("clean_html", || TestFilter);
+        registry.register_builtins();
+        assert_eq!(registry.create("clean_html").unwrap().name(), "TestFilter");
+        assert_eq!(registry.create("entries").unwrap().name(), "DefaultEntriesFilter");
+
+        initialize();
+        initialize();
+        assert!(contains_filter("clean_html"));
+        assert!(contains_filter("entries"));
+    }
 }
@@ -1,15 +1,28 @@
 //! 过滤器栈实现
 //! 参考 Ruby 版本 filter_stack.rb 设计
-
+//!
+//! 按名称添加过滤器时依次查找：栈的覆盖层、栈自身注册的工厂函数、全局注册表。
+//! 覆盖层由 [`FilterStack::with_overrides`] 创建，把全局过滤器的名称映射到另一个
+//! 过滤器，只影响这一个栈（即一个文档的抓取器）。覆盖层在创建时就取得目标过滤器
+//! 的工厂函数，之后在全局注册表中注册或替换过滤器不会影响已创建的覆盖层。
+//!
+//! 栈中记录的是实际生效的名称：`clean_html` 被覆盖为 `babel/clean` 时，
+//! [`FilterStack::filter_names`] 返回 `babel/clean`；按原名称定位（如
+//! `insert_before("clean_html", ..)`）仍然可以找到它。
+
+use crate::core::filter_registry::{self, FilterFactory};
 use crate::core::scraper::filter::Filter;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// 过滤器栈，类似有序集合，支持插入、替换和追加操作
 pub struct FilterStack {
     /// 过滤器映射
     filters: Vec<(String, Box<dyn Filter>)>,
     /// 过滤器工厂函数映射
-    factories: HashMap<String, Box<dyn Fn() -> Box<dyn Filter> + Send + Sync>>,
+    factories: HashMap<String, FilterFactory>,
+    /// 覆盖层：被覆盖的名称到实际生效的名称和工厂函数
+    overrides: HashMap<String, (String, FilterFactory)>,
 }
 
 impl FilterStack {
@@ -18,7 +31,25 @@ impl FilterStack {
         Self {
             filters: Vec::new(),
             factories: HashMap::new(),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// 设置只属于这个栈的过滤器覆盖，键为被覆盖的名称，值为替代的过滤器名称
+    ///
+    /// 替代的过滤器在栈自身注册的工厂函数和全局注册表中查找，找不到时返回错误。
+    /// 对应抓取器配置中的 `override_filters` 表，如 `clean_html = "babel/clean"`。
+    pub fn with_overrides(mut self, overrides: HashMap<String, String>) -> Result<Self, String> {
+        for (name, target) in overrides {
+            let factory = self
+                .factories
+                .get(&target)
+                .cloned()
+                .or_else(|| filter_registry::filter_factory(&target))
+                .ok_or_else(|| format!("未找到过滤器: {}", target))?;
+            self.overrides.insert(name, (target, factory));
         }
+        Ok(self)
     }
 
     /// 注册过滤器工厂函数
@@ -27,67 +58,58 @@ impl FilterStack {
         F: Fn() -> T + 'static + Send + Sync,
         T: Filter + 'static,
     {
-        let boxed_factory = Box::new(move || -> Box<dyn Filter> { Box::new(factory()) });
-        self.factories.insert(name.to_string(), boxed_factory);
+        let factory: FilterFactory = Arc::new(move || -> Box<dyn Filter> { Box::new(factory()) });
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    /// 覆盖后实际生效的过滤器名称
+    pub fn effective_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.overrides.get(name).map_or(name, |(target, _)| target.as_str())
+    }
+
+    /// 按名称创建过滤器，依次查找覆盖层、栈自身的工厂函数和全局注册表
+    fn create(&self, name: &str) -> Result<(String, Box<dyn Filter>), String> {
+        if let Some((target, factory)) = self.overrides.get(name) {
+            return Ok((target.clone(), factory()));
+        }
+        let factory = self
+            .factories
+            .get(name)
+            .cloned()
+            .or_else(|| filter_registry::filter_factory(name))
+            .ok_or_else(|| format!("未找到过滤器: {}", name))?;
+        Ok((name.to_string(), factory()))
     }
 
     /// 追加过滤器到栈尾部
     pub fn push(&mut self, name: &str) -> Result<(), String> {
-        if let Some(factory) = self.factories.get(name) {
-            let filter = factory();
-            self.filters.push((name.to_string(), filter));
-            Ok(())
-        } else {
-            Err(format!("未找到过滤器: {}", name))
-        }
+        let filter = self.create(name)?;
+        self.filters.push(filter);
+        Ok(())
     }
 
     /// 在指定过滤器之前插入新过滤器
     pub fn insert_before(&mut self, index: &str, name: &str) -> Result<(), String> {
-        if let Some(factory) = self.factories.get(name) {
-            let filter = factory();
-
-            if let Some(pos) = self.find_position(index) {
-                self.filters.insert(pos, (name.to_string(), filter));
-                Ok(())
-            } else {
-                Err(format!("未找到目标过滤器: {}", index))
-            }
-        } else {
-            Err(format!("未找到过滤器: {}", name))
-        }
+        let filter = self.create(name)?;
+        let pos = self.find_position(index).ok_or_else(|| format!("未找到目标过滤器: {}", index))?;
+        self.filters.insert(pos, filter);
+        Ok(())
     }
 
     /// 在指定过滤器之后插入新过滤器
     pub fn insert_after(&mut self, index: &str, name: &str) -> Result<(), String> {
-        if let Some(factory) = self.factories.get(name) {
-            let filter = factory();
-
-            if let Some(pos) = self.find_position(index) {
-                self.filters.insert(pos + 1, (name.to_string(), filter));
-                Ok(())
-            } else {
-                Err(format!("未找到目标过滤器: {}", index))
-            }
-        } else {
-            Err(format!("未找到过滤器: {}", name))
-        }
+        let filter = self.create(name)?;
+        let pos = self.find_position(index).ok_or_else(|| format!("未找到目标过滤器: {}", index))?;
+        self.filters.insert(pos + 1, filter);
+        Ok(())
     }
 
     /// 替换指定过滤器
     pub fn replace(&mut self, index: &str, name: &str) -> Result<(), String> {
-        if let Some(factory) = self.factories.get(name) {
-            let filter = factory();
-
-            if let Some(pos) = self.find_position(index) {
-                self.filters[pos] = (name.to_string(), filter);
-                Ok(())
-            } else {
-                Err(format!("未找到目标过滤器: {}", index))
-            }
-        } else {
-            Err(format!("未找到过滤器: {}", name))
-        }
+        let filter = self.create(name)?;
+        let pos = self.find_position(index).ok_or_else(|| format!("未找到目标过滤器: {}", index))?;
+        self.filters[pos] = filter;
+        Ok(())
     }
 
     /// 直接添加命名过滤器（不通过工厂）
@@ -95,20 +117,22 @@ impl FilterStack {
         self.filters.push((name.to_string(), filter));
     }
 
-    /// 获取指定名称的过滤器
+    /// 获取指定名称的过滤器，被覆盖的名称返回替代的过滤器
     pub fn get_filter(&self, name: &str) -> Option<&Box<dyn Filter>> {
+        let name = self.effective_name(name);
         self.filters
             .iter()
             .find_map(|(n, f)| if n == name { Some(f) } else { None })
     }
 
-    /// 获取所有过滤器名称
+    /// 获取所有过滤器实际生效的名称
     pub fn filter_names(&self) -> Vec<String> {
         self.filters.iter().map(|(name, _)| name.clone()).collect()
     }
 
-    /// 查找过滤器在栈中的位置
+    /// 查找过滤器在栈中的位置，被覆盖的名称按替代的名称查找
     fn find_position(&self, name: &str) -> Option<usize> {
+        let name = self.effective_name(name);
         self.filters.iter().position(|(n, _)| n == name)
     }
 
@@ -119,7 +143,7 @@ impl FilterStack {
 
     /// 检查是否包含指定过滤器
     pub fn contains(&self, name: &str) -> bool {
-        self.filters.iter().any(|(n, _)| n == name)
+        self.find_position(name).is_some()
     }
 
     /// 清空过滤器栈
@@ -137,18 +161,16 @@ impl Default for FilterStack {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scrapers::Filter;
+    use crate::core::error::Result;
+    use crate::core::filter_registry::register_filter;
+    use crate::core::scraper::filter::{FilterContext, FilterOutput};
     use std::any::Any;
 
     struct TestFilter(String);
 
     impl Filter for TestFilter {
-        fn apply(
-            &self,
-            html: &str,
-            context: &crate::scrapers::filter::FilterContext,
-        ) -> crate::core::error::Result<String> {
-            Ok(format!("{}_{}", html, self.0))
+        fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+            Ok(FilterOutput::new(format!("{}_{}", html, self.0)))
         }
 
         fn box_clone(&self) -> Box<dyn Filter> {
@@ -160,6 +182,15 @@ mod tests {
         }
     }
 
+    /// 依次应用栈中的过滤器
+    fn run(stack: &FilterStack) -> String {
+        let context = FilterContext::new();
+        stack
+            .filters()
+            .iter()
+            .fold(String::new(), |html, filter| filter.apply_v2(&html, &context).unwrap().html)
+    }
+
     #[test]
     fn test_filter_stack() {
         let mut stack = FilterStack::new();
@@ -196,4 +227,52 @@ mod tests {
         stack.clear();
         assert_eq!(stack.filters.len(), 0);
     }
+
+    #[test]
+    fn test_overrides_shadow_global_filters() {
+        register_filter("stack_test/clean", || TestFilter("global".to_string()));
+        register_filter("stack_test/babel_clean", || TestFilter("babel".to_string()));
+        register_filter("stack_test/entries", || TestFilter("entries".to_string()));
+
+        let overrides = HashMap::from([("stack_test/clean".to_string(), "stack_test/babel_clean".to_string())]);
+        let mut babel = FilterStack::new().with_overrides(overrides).unwrap();
+        babel.push("stack_test/clean").unwrap();
+        babel.push("stack_test/entries").unwrap();
+
+        // 列出的是实际生效的名称，按原名称仍能定位
+        assert_eq!(babel.filter_names(), ["stack_test/babel_clean", "stack_test/entries"]);
+        assert!(babel.contains("stack_test/clean"));
+        babel.insert_before("stack_test/clean", "stack_test/entries").unwrap();
+        assert_eq!(babel.filter_names()[0], "stack_test/entries");
+        assert_eq!(run(&babel), "_entries_babel_entries");
+
+        // 其他文档的栈不受覆盖影响
+        let mut other = FilterStack::new();
+        other.push("stack_test/clean").unwrap();
+        assert_eq!(other.filter_names(), ["stack_test/clean"]);
+        assert_eq!(run(&other), "_global");
+    }
+
+    #[test]
+    fn test_later_global_registration_does_not_leak_into_overlay() {
+        register_filter("stack_order/clean", || TestFilter("global".to_string()));
+        register_filter("stack_order/doc_clean", || TestFilter("doc".to_string()));
+        let overrides = HashMap::from([("stack_order/clean".to_string(), "stack_order/doc_clean".to_string())]);
+        let mut stack = FilterStack::new().with_overrides(overrides).unwrap();
+
+        // 覆盖层创建之后，全局注册表中替换被覆盖的过滤器和替代的过滤器都不影响它
+        register_filter("stack_order/clean", || TestFilter("global2".to_string()));
+        register_filter("stack_order/doc_clean", || TestFilter("doc2".to_string()));
+        stack.push("stack_order/clean").unwrap();
+        assert_eq!(stack.filter_names(), ["stack_order/doc_clean"]);
+        assert_eq!(run(&stack), "_doc");
+
+        // 没有覆盖的名称照常使用全局注册表中最新的过滤器
+        stack.push("stack_order/doc_clean").unwrap();
+        assert_eq!(run(&stack), "_doc_doc2");
+
+        // 替代的过滤器不存在时创建覆盖层失败
+        let missing = HashMap::from([("stack_order/clean".to_string(), "stack_order/missing".to_string())]);
+        assert!(FilterStack::new().with_overrides(missing).is_err());
+    }
 }
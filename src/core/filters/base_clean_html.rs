@@ -3,10 +3,38 @@
 //! 提供通用的HTML清理功能，可以被特定文档类型的过滤器继承
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
-use scraper::{Html, Node, Selector};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use crate::core::filters::{selector, try_selector};
+use regex::Regex;
+use scraper::Html;
 use std::any::Any;
 
+/// 警告提示框的样式类
+pub const WARNING_CLASS: &str = "_warning";
+
+/// 实验性功能提示框的样式类
+pub const EXPERIMENTAL_CLASS: &str = "_experimental";
+
+/// 说明提示框的样式类
+pub const NOTE_CLASS: &str = "_note";
+
+/// 语法代码块（如 CSS 的形式语法）的样式类
+pub const SYNTAX_CLASS: &str = "_syntax";
+
+/// 定义表（如 CSS 属性的初始值、是否继承等）的样式类
+pub const DEFINITION_CLASS: &str = "_definition";
+
+/// 清理属性时需要保留的类名
+///
+/// 这些类名由各文档的清理过滤器注入，属于 xwdoc 自身样式表的约定
+pub const PRESERVED_CLASSES: &[&str] = &[
+    NOTE_CLASS,
+    WARNING_CLASS,
+    EXPERIMENTAL_CLASS,
+    SYNTAX_CLASS,
+    DEFINITION_CLASS,
+];
+
 /// 过滤器基础特质
 pub trait FilterBase {}
 
@@ -28,7 +56,7 @@ impl BaseCleanHtmlFilter {
         let mut result = html.to_string();
 
         for selector_str in selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
+            if let Some(selector) = try_selector(selector_str) {
                 // 查找匹配的元素并移除
                 for element in document.select(&selector) {
                     let html_fragment = element.html();
@@ -42,70 +70,84 @@ impl BaseCleanHtmlFilter {
 
     /// 处理代码块，提取语言信息和代码内容
     pub fn process_code_blocks(&self, html: &str) -> String {
-        if let Ok(selector) = Selector::parse("pre") {
-            let document = Html::parse_document(html);
-            let mut new_html = String::new();
-
-            document.select(&selector).for_each(|pre_node| {
-                let mut pre_html = pre_node.html();
-
-                // 提取语言信息
-                if let Ok(lang_selector) = Selector::parse("[class*='language-']") {
-                    if let Some(lang_node) = pre_node.select(&lang_selector).next() {
-                        let class_attr = lang_node.value().attr("class").unwrap_or("");
-                        let language = class_attr
-                            .split_whitespace()
-                            .find(|c| c.starts_with("language-"))
-                            .and_then(|c| c.strip_prefix("language-"))
-                            .unwrap_or("");
-
-                        // 添加data-language属性
-                        pre_html = pre_html
-                            .replace("<pre", &format!("<pre data-language=\"{}\"", language));
-                    }
-                }
-
-                // 提取代码内容
-                if let Ok(token_line_selector) = Selector::parse(".token-line") {
-                    let token_lines: Vec<String> = pre_node
-                        .select(&token_line_selector)
-                        .map(|node| node.text().collect::<String>())
-                        .collect();
+        let document = Html::parse_document(html);
+        let mut new_html = String::new();
+
+        document.select(&selector("pre")).for_each(|pre_node| {
+            let mut pre_html = pre_node.html();
+
+            // 提取语言信息
+            if let Some(lang_node) = pre_node.select(&selector("[class*='language-']")).next() {
+                let class_attr = lang_node.value().attr("class").unwrap_or("");
+                let language = class_attr
+                    .split_whitespace()
+                    .find(|c| c.starts_with("language-"))
+                    .and_then(|c| c.strip_prefix("language-"))
+                    .unwrap_or("");
+
+                // 添加data-language属性
+                pre_html = pre_html
+                    .replace("<pre", &format!("<pre data-language=\"{}\"", language));
+            }
 
-                    if !token_lines.is_empty() {
-                        let code_content = token_lines.join("\n");
-                        let start_pre = pre_html.find('>').map(|i| i + 1).unwrap_or(0);
-                        let end_pre = pre_html.rfind("</pre>").unwrap_or(pre_html.len());
-                        pre_html = format!(
-                            "{}{}{}",
-                            &pre_html[..start_pre],
-                            code_content,
-                            &pre_html[end_pre..]
-                        );
-                    }
-                }
+            // 提取代码内容
+            let token_lines: Vec<String> = pre_node
+                .select(&selector(".token-line"))
+                .map(|node| node.text().collect::<String>())
+                .collect();
+
+            if !token_lines.is_empty() {
+                let code_content = token_lines.join("\n");
+                let start_pre = pre_html.find('>').map(|i| i + 1).unwrap_or(0);
+                let end_pre = pre_html.rfind("</pre>").unwrap_or(pre_html.len());
+                pre_html = format!(
+                    "{}{}{}",
+                    &pre_html[..start_pre],
+                    code_content,
+                    &pre_html[end_pre..]
+                );
+            }
 
-                new_html.push_str(&pre_html);
-            });
+            new_html.push_str(&pre_html);
+        });
 
-            if !new_html.is_empty() {
-                return new_html;
-            }
+        if !new_html.is_empty() {
+            return new_html;
         }
 
         html.to_string()
     }
 
     /// 移除所有元素的class和style属性
+    ///
+    /// `class` 属性中属于 xwdoc 样式表约定的类名（见 [`PRESERVED_CLASSES`]）会被保留
     pub fn remove_attributes(&self, html: &str, attributes: &[&str]) -> String {
-        let document = Html::parse_document(html);
         let mut result = html.to_string();
 
         for attr in attributes {
-            // 使用简单的字符串替换来移除属性
-            // 注意：这是一个简化的实现，对于复杂的HTML可能不够健壮
-            let pattern = format!(r#" {}="[^"]*""#, attr);
-            result = result.replace(&pattern, "");
+            let pattern = match Regex::new(&format!(r#"\s{}="([^"]*)""#, regex::escape(attr))) {
+                Ok(pattern) => pattern,
+                Err(_) => continue,
+            };
+
+            result = pattern
+                .replace_all(&result, |caps: &regex::Captures| {
+                    if *attr != "class" {
+                        return String::new();
+                    }
+
+                    let kept: Vec<&str> = caps[1]
+                        .split_whitespace()
+                        .filter(|class| PRESERVED_CLASSES.contains(class))
+                        .collect();
+
+                    if kept.is_empty() {
+                        String::new()
+                    } else {
+                        format!(r#" class="{}""#, kept.join(" "))
+                    }
+                })
+                .to_string();
         }
 
         result
@@ -115,7 +157,7 @@ impl BaseCleanHtmlFilter {
 impl FilterBase for BaseCleanHtmlFilter {}
 
 impl Filter for BaseCleanHtmlFilter {
-    fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
         // 基本的HTML清理 - 移除脚本、样式和注释
         let selectors_to_remove = ["script", "style", "link", "comment()"];
         let html = self.remove_elements(html, &selectors_to_remove);
@@ -126,7 +168,7 @@ impl Filter for BaseCleanHtmlFilter {
         // 移除class和style属性
         let html = self.remove_attributes(&html, &["class", "style"]);
 
-        Ok(html)
+        Ok(FilterOutput::new(html))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
@@ -141,3 +183,18 @@ impl Filter for BaseCleanHtmlFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_attributes_keeps_preserved_classes() {
+        let filter = BaseCleanHtmlFilter::new();
+        let html = r#"<div class="_warning notecard" style="color: red"><p class="x">Text</p></div>"#;
+
+        let result = filter.remove_attributes(html, &["class", "style"]);
+
+        assert_eq!(result, r#"<div class="_warning"><p>Text</p></div>"#);
+    }
+}
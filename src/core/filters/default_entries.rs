@@ -0,0 +1,194 @@
+//! 默认条目过滤器
+//!
+//! 用通用的 `UrlScraper` 抓取任意网站时通常没有条目过滤器，所有页面都只能
+//! 得到以路径命名、类型为 "Other" 的条目。抓取器在其他过滤器没有为页面生成
+//! 条目时使用 [`DefaultEntriesFilter`]：条目名称取页面标题，类型取路径的
+//! 第一段（`api` → "API"，`guides` → "Guides"）。抓取结束后由
+//! [`merge_long_tail`] 限制推断出的类型数量，页面很少的类型合并为 "Other"。
+
+use crate::core::error::Result;
+use crate::core::filters::selector;
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use scraper::Html;
+use std::any::Any;
+use std::collections::HashMap;
+
+/// 无法推断类型的条目使用的类型
+pub const OTHER_TYPE: &str = "Other";
+
+/// 默认最多保留的推断类型数量（不含 "Other"）
+pub const DEFAULT_MAX_TYPES: usize = 12;
+
+/// 推断类型至少需要的条目数量，更少的类型合并为 "Other"
+const MIN_TYPE_ENTRIES: usize = 2;
+
+/// 整体大写的常见缩写
+const ACRONYMS: &[&str] = &[
+    "api", "cli", "css", "dom", "faq", "html", "http", "io", "js", "json", "sdk", "sql", "ui", "url", "xml",
+];
+
+/// 根据页面标题和路径生成条目的过滤器
+#[derive(Debug, Clone, Default)]
+pub struct DefaultEntriesFilter;
+
+impl DefaultEntriesFilter {
+    /// 创建默认条目过滤器
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 页面标题：过滤器设置的标题，其次是第一个 `<h1>`、`<title>`，都没有时为路径
+    pub fn page_title(&self, context: &FilterContext) -> String {
+        if !context.title.trim().is_empty() {
+            return context.title.trim().to_string();
+        }
+        heading_title(context.html_doc()).unwrap_or_else(|| context.current_path.clone())
+    }
+}
+
+/// 页面中第一个非空的 `<h1>` 或 `<title>` 的文本，连续的空白合并为一个空格
+pub fn heading_title(doc: &Html) -> Option<String> {
+    ["h1", "title"]
+        .iter()
+        .filter_map(|css| doc.select(&selector(css)).next())
+        .map(|element| element.text().collect::<Vec<_>>().join(" "))
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|text| !text.is_empty())
+}
+
+impl Filter for DefaultEntriesFilter {
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        // 其他过滤器没有设置标题时补上，保存页面时也会用到
+        let output = FilterOutput::new(html);
+        if context.title.trim().is_empty() {
+            return Ok(output.with_title(self.page_title(context)));
+        }
+        Ok(output)
+    }
+
+    fn box_clone(&self) -> Box<dyn Filter> {
+        Box::new(self.clone())
+    }
+
+    fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+        vec![(
+            self.page_title(context),
+            context.current_path.clone(),
+            section_type(&context.current_path),
+        )]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// 由页面路径的第一段推断条目类型，首页为 "Other"
+pub fn section_type(path: &str) -> String {
+    match path.trim_matches('/').split('/').next() {
+        Some(segment) if !segment.is_empty() && segment != "index" => humanize(segment),
+        _ => OTHER_TYPE.to_string(),
+    }
+}
+
+/// 将路径段转为可读的名称：连字符和下划线视为空格，首字母大写，常见缩写整体大写
+pub fn humanize(segment: &str) -> String {
+    let segment = segment.rsplit_once('.').map_or(segment, |(name, _)| name);
+    let words: Vec<String> = segment
+        .split(['-', '_', ' '])
+        .filter(|word| !word.is_empty())
+        .enumerate()
+        .map(|(i, word)| {
+            let lower = word.to_lowercase();
+            if ACRONYMS.contains(&lower.as_str()) {
+                lower.to_uppercase()
+            } else if i == 0 {
+                let mut chars = lower.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            } else {
+                lower
+            }
+        })
+        .collect();
+    if words.is_empty() {
+        OTHER_TYPE.to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+/// 限制推断类型的数量
+///
+/// 保留条目最多的 `max_types` 个类型（条目数相同时按名称排序，结果与抓取顺序无关），
+/// 其余类型以及条目少于两个的类型合并为 "Other"。
+pub fn merge_long_tail(entries: &mut [(String, String, String)], max_types: usize) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (_, _, entry_type) in entries.iter() {
+        *counts.entry(entry_type.as_str()).or_default() += 1;
+    }
+    let mut types: Vec<(&str, usize)> = counts
+        .into_iter()
+        .filter(|&(entry_type, count)| entry_type != OTHER_TYPE && count >= MIN_TYPE_ENTRIES)
+        .collect();
+    types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let kept: Vec<String> = types.into_iter().take(max_types).map(|(entry_type, _)| entry_type.to_string()).collect();
+
+    for (_, _, entry_type) in entries.iter_mut() {
+        if !kept.contains(entry_type) {
+            *entry_type = OTHER_TYPE.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_type() {
+        assert_eq!(section_type("api/array"), "API");
+        assert_eq!(section_type("guides/intro"), "Guides");
+        assert_eq!(section_type("getting_started/install"), "Getting started");
+        assert_eq!(section_type("web-apis/fetch.html"), "Web apis");
+        assert_eq!(section_type("index"), OTHER_TYPE);
+        assert_eq!(section_type(""), OTHER_TYPE);
+    }
+
+    #[test]
+    fn test_entry_name_from_title() {
+        let filter = DefaultEntriesFilter::new();
+        let mut context = FilterContext::new()
+            .with_html("<html><head><title>Array - Site</title></head><body><h1> Array\n  methods </h1></body></html>");
+        context.current_path = "api/array".to_string();
+        assert_eq!(
+            filter.get_entries(&context),
+            [("Array methods".to_string(), "api/array".to_string(), "API".to_string())]
+        );
+
+        let mut context = FilterContext::new().with_html("<html><body><p>No heading</p></body></html>");
+        context.current_path = "about".to_string();
+        let output = filter.apply_v2(context.html(), &context).unwrap();
+        assert_eq!(output.title.as_deref(), Some("about"));
+    }
+
+    #[test]
+    fn test_merge_long_tail() {
+        let entry = |path: &str| (path.to_string(), path.to_string(), section_type(path));
+        let mut entries: Vec<_> = ["api/a", "api/b", "api/c", "guides/a", "guides/b", "blog/a", "blog/b", "about"]
+            .into_iter()
+            .map(entry)
+            .collect();
+        merge_long_tail(&mut entries, 2);
+
+        let types: Vec<&str> = entries.iter().map(|(_, _, entry_type)| entry_type.as_str()).collect();
+        // blog 与 guides 条目数相同，按名称保留 Blog
+        assert_eq!(types, ["API", "API", "API", "Other", "Other", "Blog", "Blog", "Other"]);
+    }
+}
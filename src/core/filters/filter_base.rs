@@ -3,7 +3,8 @@
 
 use crate::core::scraper::filter::{Filter, FilterContext};
 use regex::Regex;
-use scraper::{ElementRef, Html, Selector};
+use crate::core::filters::try_selector;
+use scraper::{ElementRef, Html};
 
 /// 正则表达式常量：URL 协议模式
 const SCHEME_RGX: &str = r"\A[^:\/?#]+:";
@@ -20,17 +21,15 @@ pub trait FilterBase: Filter {
 
     /// 选择所有匹配的元素
     fn css<'a>(&self, doc: &'a Html, selector: &str) -> Vec<ElementRef<'a>> {
-        match Selector::parse(selector) {
-            Ok(selector) => doc.select(&selector).collect(),
-            Err(_) => Vec::new(),
+        match try_selector(selector) {
+            Some(selector) => doc.select(&selector).collect(),
+            None => Vec::new(),
         }
     }
 
     /// 选择第一个匹配的元素
     fn at_css<'a>(&self, doc: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
-        Selector::parse(selector)
-            .ok()
-            .and_then(|selector| doc.select(&selector).next())
+        try_selector(selector).and_then(|selector| doc.select(&selector).next())
     }
 
     /// XPath 查询
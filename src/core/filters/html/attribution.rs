@@ -0,0 +1,95 @@
+//! 归属信息过滤器
+//! 参考 Ruby 版本的 AttributionFilter，在页面末尾加入版权和许可说明以及原页面链接
+//!
+//! 抓取器设置了许可证（`UrlScraper::with_license`）时，页脚的 `data-license`
+//! 属性为许可证的 SPDX 标识，下游可以据此判断内容的许可。
+
+use crate::core::error::Result;
+use crate::core::filters::markup::escape_html;
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use std::any::Any;
+
+/// 页脚的类名
+pub const ATTRIBUTION_CLASS: &str = "_attribution";
+
+/// 在页面末尾加入归属信息的过滤器，抓取器没有设置归属信息时不修改页面
+#[derive(Debug, Clone, Default)]
+pub struct AttributionFilter;
+
+impl AttributionFilter {
+    /// 创建新的归属信息过滤器
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 生成页脚，归属信息由抓取器提供，可以包含 HTML
+    fn footer(attribution: &str, license: Option<&str>, url: &str) -> String {
+        let license = match license {
+            Some(license) => format!(" data-license=\"{}\"", escape_html(license)),
+            None => String::new(),
+        };
+        let link = if url.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<br>\n    <a href=\"{0}\" class=\"{1}-link\">{0}</a>",
+                escape_html(url),
+                ATTRIBUTION_CLASS
+            )
+        };
+        format!(
+            "\n<div class=\"{0}\"{1}>\n  <p class=\"{0}-p\">\n    {2}{3}\n  </p>\n</div>",
+            ATTRIBUTION_CLASS,
+            license,
+            attribution.trim().replace('\n', "<br>"),
+            link
+        )
+    }
+}
+
+impl Filter for AttributionFilter {
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        let Some(attribution) = context.attribution.as_deref().filter(|a| !a.trim().is_empty()) else {
+            return Ok(FilterOutput::new(html));
+        };
+        let footer = Self::footer(attribution, context.license.as_deref(), &context.current_url);
+        Ok(FilterOutput::new(format!("{}{}", html, footer)))
+    }
+
+    fn box_clone(&self) -> Box<dyn Filter> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footer_carries_license() {
+        let mut context = FilterContext::new();
+        context.current_url = "https://example.com/a?b=1&c=2".to_string();
+        context.attribution = Some("© MDN contributors.\nLicensed under CC-BY-SA v2.5.".to_string());
+        context.license = Some("CC-BY-SA-2.5".to_string());
+
+        let html = AttributionFilter::new().apply_v2("<p>Body</p>", &context).unwrap().html;
+        assert!(html.starts_with("<p>Body</p>\n<div class=\"_attribution\" data-license=\"CC-BY-SA-2.5\">"));
+        assert!(html.contains("© MDN contributors.<br>Licensed under CC-BY-SA v2.5."));
+        assert!(html.contains(r#"<a href="https://example.com/a?b=1&amp;c=2" class="_attribution-link">"#));
+
+        // 没有许可证时不加 data-license，没有归属信息时不修改页面
+        context.license = None;
+        let html = AttributionFilter::new().apply_v2("<p>Body</p>", &context).unwrap().html;
+        assert!(html.contains("<div class=\"_attribution\">"));
+        context.attribution = Some(String::new());
+        assert_eq!(AttributionFilter::new().apply_v2("<p>Body</p>", &context).unwrap().html, "<p>Body</p>");
+    }
+}
@@ -1,42 +1,198 @@
 //! 图片处理过滤器
-//! 将远程图片下载并转换为 Base64 编码的内嵌图片
+//! 将远程图片下载并转换为 Base64 编码的内嵌图片，
+//! 同时处理懒加载属性、`srcset` 和 `<picture>` 中的 `<source>`
+//!
+//! 过滤器在抓取循环中同步执行，下载图片会阻塞运行时，因此图片不在过滤阶段下载，
+//! 而是在抓取结束后由抓取器调用 [`ImagesFilter::resolve_images`] 异步下载。
+//!
+//! 下载的图片按绝对地址缓存在内存中，过滤器的所有副本共享同一个缓存；设置缓存目录后
+//! 图片还会保存到磁盘，再次抓取时不必重新下载。
 
 use crate::core::error::{Error, Result};
 use crate::core::filters::filter_base::FilterBase;
+use crate::core::filters::serialize_html;
+use crate::core::http_fetch::{HttpFetch, ReqwestFetch, TlsOptions};
+use crate::core::page_db::StoredPage;
 use crate::core::scraper::filter::Filter;
-use crate::core::scraper::filter::FilterContext;
+use crate::core::scraper::filter::{FilterContext, FilterOutput, FilterWarning};
+use crate::core::types::PageKey;
 use base64::{engine::general_purpose::STANDARD, Engine as _};
+use futures::stream::{self, StreamExt};
 use image::GenericImageView;
-use reqwest::blocking::Client;
-use scraper::{Html, Selector};
+use kuchiki::iter::NodeIterator;
+use kuchiki::traits::TendrilSink;
+use kuchiki::{Attributes, ElementData, NodeDataRef, NodeRef};
+use sha2::{Digest, Sha256};
 use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+/// 懒加载时保存真实地址的属性，按优先级排列
+const LAZY_SRC_ATTRIBUTES: &[&str] = &["data-src", "data-lazy-src"];
+
+/// 可选移除的加载行为属性
+const LOADING_ATTRIBUTES: &[&str] = &["loading", "decoding"];
+
+/// 文档中匹配选择器的元素，先全部取出，修改和移除元素时不影响遍历
+fn select_all(node: &NodeRef, selectors: &str) -> Vec<NodeDataRef<ElementData>> {
+    node.select(selectors).map(|elements| elements.collect()).unwrap_or_default()
+}
+
+/// `<picture>` 的 `<source>` 子元素
+fn picture_sources(picture: &NodeRef) -> Vec<NodeDataRef<ElementData>> {
+    picture
+        .children()
+        .elements()
+        .filter(|element| &*element.name.local == "source")
+        .collect()
+}
+
+/// `srcset` 中的候选图片
+#[derive(Debug, PartialEq)]
+struct SrcsetCandidate {
+    url: String,
+    /// 宽度描述符（如 `480w`）
+    width: Option<u32>,
+    /// 像素密度描述符（如 `2x`），默认为 1
+    density: f32,
+}
+
+/// 解析 `srcset` 属性
+///
+/// 候选地址以空白结束，因此地址中可以包含逗号（如 data URI）。
+fn parse_srcset(srcset: &str) -> Vec<SrcsetCandidate> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (url, after_url) = rest.split_at(url_end);
+
+        // 地址以逗号结尾时没有描述符
+        let (url, descriptor, remaining) = if url.ends_with(',') {
+            (url.trim_end_matches(','), "", after_url)
+        } else {
+            let descriptor_end = after_url.find(',').unwrap_or(after_url.len());
+            let (descriptor, remaining) = after_url.split_at(descriptor_end);
+            (url, descriptor.trim(), remaining)
+        };
+        rest = remaining;
+
+        let width = descriptor.strip_suffix('w').and_then(|w| w.parse().ok());
+        let density = descriptor
+            .strip_suffix('x')
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(1.0);
+        candidates.push(SrcsetCandidate {
+            url: url.to_string(),
+            width,
+            density,
+        });
+    }
+    candidates
+}
+
+/// 同时下载的图片数
+pub const DOWNLOAD_CONCURRENCY: usize = 4;
+
+/// 下载结果：图片地址到 data URI 的映射，下载失败的图片为 None
+type DownloadedImages = HashMap<String, Option<String>>;
+
+/// 图片缓存的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageCacheStats {
+    /// 从网络下载的图片数
+    pub downloads: u64,
+    /// 使用缓存图片的次数，同一张图片在多个位置出现时每处都计一次
+    pub cache_hits: u64,
+    /// 命中缓存、不必重新下载的 data URI 字节数
+    pub bytes_saved: u64,
+}
+
+impl ImageCacheStats {
+    /// 是否没有处理过图片
+    pub fn is_empty(&self) -> bool {
+        self.downloads == 0 && self.cache_hits == 0
+    }
+}
+
+/// 图片编码设置，在阻塞线程中编码图片时使用
+#[derive(Debug, Clone, Copy)]
+struct EncodeOptions {
+    /// 图片大小限制（字节）
+    max_size: usize,
+    /// 是否优化图片
+    optimize_images: bool,
+    /// 图片最大宽度
+    max_width: Option<u32>,
+}
 
 /// 图片处理过滤器
 ///
-/// 此过滤器会下载远程图片，将其转换为 Base64 编码后内嵌到 HTML 中
+/// 此过滤器会下载远程图片，将其转换为 Base64 编码后内嵌到 HTML 中。
+///
+/// 过滤阶段（[`Filter::apply_v2`]）只整理 `<img>` 标签，不发起请求；图片在抓取结束后
+/// 由 [`ImagesFilter::resolve_images`] 统一异步下载，不会阻塞抓取所在的运行时。
+/// 单独处理一段 HTML 时使用 [`ImagesFilter::apply_async`]。
+///
+/// 克隆的过滤器共享图片缓存和统计。
+#[derive(Clone)]
 pub struct ImagesFilter {
-    /// HTTP 客户端
-    client: Client,
+    /// 下载图片使用的 HTTP 实现
+    fetch: Arc<dyn HttpFetch>,
     /// 图片大小限制（字节）
     max_size: usize,
     /// 是否优化图片
     optimize_images: bool,
     /// 图片最大宽度
     max_width: Option<u32>,
+    /// 是否移除 `loading`/`decoding` 属性
+    strip_loading_attributes: bool,
+    /// 图片绝对地址到 data URI 的缓存，过滤器的副本共享
+    cache: Arc<Mutex<HashMap<String, String>>>,
+    /// 磁盘缓存目录
+    cache_dir: Option<PathBuf>,
+    /// 缓存统计，过滤器的副本共享
+    stats: Arc<Mutex<ImageCacheStats>>,
+    /// 取消下载的令牌
+    cancel: Option<CancellationToken>,
 }
 
 impl ImagesFilter {
     /// 创建新的图片处理过滤器
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            fetch: Arc::new(ReqwestFetch::new()),
             max_size: 1024 * 300, // 默认 300KB
             optimize_images: true,
             max_width: None,
+            strip_loading_attributes: true,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            cache_dir: None,
+            stats: Arc::new(Mutex::new(ImageCacheStats::default())),
+            cancel: None,
         }
     }
 
+    /// 设置下载图片使用的 HTTP 实现
+    pub fn with_fetch(mut self, fetch: Arc<dyn HttpFetch>) -> Self {
+        self.fetch = fetch;
+        self
+    }
+
+    /// 使用指定 TLS 设置的 reqwest 客户端下载图片
+    pub fn with_tls(self, tls: &TlsOptions) -> Result<Self> {
+        Ok(self.with_fetch(Arc::new(ReqwestFetch::with_client(tls.client()?))))
+    }
+
     /// 设置图片最大大小
     pub fn with_max_size(mut self, size: usize) -> Self {
         self.max_size = size;
@@ -55,116 +211,432 @@ impl ImagesFilter {
         self
     }
 
-    /// 下载图片并转换为 Base64
-    fn download_image(&self, url: &str) -> Result<String> {
-        // 发起请求下载图片
-        let mut response = self
-            .client
-            .get(url)
-            .send()
-            .map_err(|e| Error::Doc(format!("Failed to download image from {}: {}", url, e)))?;
+    /// 设置是否移除 `loading`/`decoding` 属性
+    pub fn with_strip_loading_attributes(mut self, strip: bool) -> Self {
+        self.strip_loading_attributes = strip;
+        self
+    }
 
-        // 检查状态码
-        if !response.status().is_success() {
-            return Err(Error::Doc(format!(
-                "Failed to download image from {}: HTTP {}",
-                url,
-                response.status()
-            )));
+    /// 设置磁盘缓存目录，目录中已有的图片不再下载
+    ///
+    /// 缓存文件以图片地址和编码设置的哈希命名，编码设置不同时不会互相使用。
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// 令牌取消后停止下载，尚未下载的图片保留原地址，不记录警告
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// 下载是否已取消
+    fn cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// 当前的缓存统计
+    pub fn stats(&self) -> ImageCacheStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// 取出缓存统计并清零，每次抓取结束时调用
+    pub fn take_stats(&self) -> ImageCacheStats {
+        std::mem::take(&mut *self.stats.lock().unwrap())
+    }
+
+    /// 从 `srcset` 中选择候选图片
+    ///
+    /// 有目标宽度（配置的最大宽度或图片的 `width` 属性）时选择不小于目标宽度的最小候选，
+    /// 都小于目标宽度时选择最大的；没有目标宽度时优先选择 1x，其次选择最大的宽度候选。
+    fn select_candidate<'a>(
+        &self,
+        candidates: &'a [SrcsetCandidate],
+        target_width: Option<u32>,
+    ) -> Option<&'a SrcsetCandidate> {
+        let widths = || candidates.iter().filter(|c| c.width.is_some());
+        let largest = widths().max_by_key(|c| c.width);
+
+        if let Some(target) = target_width.or(self.max_width) {
+            if let Some(adequate) = widths()
+                .filter(|c| c.width.unwrap_or(0) >= target)
+                .min_by_key(|c| c.width)
+            {
+                return Some(adequate);
+            }
+            if largest.is_some() {
+                return largest;
+            }
         }
 
-        // 获取 Content-Type
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("image/jpeg")
-            .to_string();
-
-        // 获取图片数据
-        let image_bytes = response
-            .bytes()
-            .map_err(|e| Error::Doc(format!("Failed to read image data from {}: {}", url, e)))?;
-
-        // 检查图片大小
-        if image_bytes.len() > self.max_size {
-            // 如果启用了优化，尝试压缩图片
-            if self.optimize_images {
-                return self.optimize_image(url, &image_bytes, content_type);
-            } else {
-                return Err(Error::Doc(format!(
-                    "Image too large: {} bytes (max: {} bytes)",
-                    image_bytes.len(),
-                    self.max_size
-                )));
+        candidates
+            .iter()
+            .find(|c| c.width.is_none() && c.density == 1.0)
+            .or(largest)
+            .or_else(|| candidates.first())
+    }
+
+    /// 将相对地址（包括以 `//` 开头的地址）按所在页面的地址转换为绝对地址
+    fn absolute_url(&self, src: &str, base_url: &str) -> String {
+        if !self.relative_url_string(src) {
+            return src.to_string();
+        }
+        Url::parse(base_url)
+            .and_then(|base| base.join(src))
+            .map(String::from)
+            .unwrap_or_else(|_| src.to_string())
+    }
+
+    /// 已下载图片的 data URI，下载失败时记录警告并返回 None
+    fn embed(
+        &self,
+        src: &str,
+        base_url: &str,
+        images: &DownloadedImages,
+        warnings: &mut Vec<FilterWarning>,
+    ) -> Option<String> {
+        let url = self.absolute_url(src, base_url);
+        match images.get(&url) {
+            Some(Some(data_url)) => Some(data_url.clone()),
+            // 下载已取消，没有尝试下载
+            None if self.cancelled() => None,
+            _ => {
+                warnings.push(self.warning("image download failed").with_url(&url));
+                None
             }
         }
+    }
 
-        // 对图片进行 Base64 编码
-        let base64_str = STANDARD.encode(&image_bytes);
+    /// 整理 `<img>` 的属性：提升懒加载地址，从 `srcset` 中选择一张图片，移除加载行为属性，
+    /// 有改动时返回 true
+    fn normalize_img(&self, attributes: &mut Attributes) -> bool {
+        let mut changed = false;
+
+        // 将懒加载地址提升为 src
+        for name in LAZY_SRC_ATTRIBUTES {
+            if let Some(lazy_src) = attributes.remove(*name) {
+                attributes.insert("src", lazy_src.value);
+                changed = true;
+                break;
+            }
+        }
+        for name in LAZY_SRC_ATTRIBUTES {
+            changed |= attributes.remove(*name).is_some();
+        }
+
+        // 从 srcset 中选择一张图片并合并到 src
+        if let Some(srcset) = attributes.remove("srcset") {
+            let target_width = attributes.get("width").and_then(|w| w.parse().ok());
+            if let Some(candidate) = self.select_candidate(&parse_srcset(&srcset.value), target_width) {
+                attributes.insert("src", candidate.url.clone());
+            }
+            attributes.remove("sizes");
+            changed = true;
+        }
+
+        if self.strip_loading_attributes {
+            for name in LOADING_ATTRIBUTES {
+                changed |= attributes.remove(*name).is_some();
+            }
+        }
+
+        changed
+    }
 
-        // 返回 data URI
-        Ok(format!("data:{};base64,{}", content_type, base64_str))
+    /// `<img>` 中需要下载的图片地址
+    fn img_url(&self, attributes: &Attributes, base_url: &str) -> Option<String> {
+        attributes
+            .get("src")
+            .filter(|src| !self.data_url_string(src))
+            .map(|src| self.absolute_url(src, base_url))
     }
 
-    /// 优化图片
-    fn optimize_image(
+    /// `<source>` 中选中的需要下载的图片地址
+    fn source_url(&self, attributes: &Attributes, base_url: &str) -> Option<String> {
+        let srcset = attributes.get("srcset").unwrap_or_default();
+        self.select_candidate(&parse_srcset(srcset), None)
+            .filter(|candidate| !self.data_url_string(&candidate.url))
+            .map(|candidate| self.absolute_url(&candidate.url, base_url))
+    }
+
+    /// `<picture>` 中的 `<img>` 是否已经或将会内嵌为 data URI
+    fn img_embedded(&self, picture: &NodeRef, base_url: &str, images: &DownloadedImages) -> bool {
+        picture.select_first("img").is_ok_and(|img| {
+            let attributes = img.attributes.borrow();
+            match attributes.get("src") {
+                Some(src) if self.data_url_string(src) => true,
+                Some(src) => matches!(images.get(&self.absolute_url(src, base_url)), Some(Some(_))),
+                None => false,
+            }
+        })
+    }
+
+    /// 把 `<img>` 的地址替换为已下载图片的 data URI，有改动时返回 true
+    fn embed_img(
+        &self,
+        attributes: &mut Attributes,
+        base_url: &str,
+        images: &DownloadedImages,
+        warnings: &mut Vec<FilterWarning>,
+    ) -> bool {
+        let Some(src) = attributes.get("src").map(str::to_string) else {
+            return false;
+        };
+        if self.data_url_string(&src) {
+            return false;
+        }
+        match self.embed(&src, base_url, images, warnings) {
+            Some(data_url) => {
+                attributes.insert("src", data_url);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 处理 `<picture>` 中的 `<source>`，在其中的 `<img>` 处理之后调用，有改动时返回 true
+    ///
+    /// `<img>` 已内嵌为 data URI 时移除所有 `<source>`，否则内嵌每个 `<source>`
+    /// 选中的图片，无法处理的 `<source>` 会被移除，由 `<img>` 兜底。
+    fn embed_sources(
         &self,
-        url: &str,
-        image_bytes: &[u8],
-        content_type: String,
-    ) -> Result<String> {
-        // 使用 image crate 加载图片
-        let img = image::load_from_memory(image_bytes)
-            .map_err(|e| Error::Doc(format!("Failed to decode image from {}: {}", url, e)))?;
-
-        // 获取当前尺寸
-        let (width, height) = img.dimensions();
-
-        // 根据最大宽度调整图片大小
-        let img = if let Some(max_width) = self.max_width {
-            if width > max_width {
-                let new_height = (height as f32 * (max_width as f32 / width as f32)) as u32;
-                img.resize(max_width, new_height, image::imageops::FilterType::Lanczos3)
+        picture: &NodeRef,
+        base_url: &str,
+        images: &DownloadedImages,
+        warnings: &mut Vec<FilterWarning>,
+    ) -> bool {
+        let sources = picture_sources(picture);
+        let img_embedded = picture
+            .select_first("img")
+            .is_ok_and(|img| img.attributes.borrow().get("src").is_some_and(|src| self.data_url_string(src)));
+
+        for source in &sources {
+            let data_url = if img_embedded {
+                None
             } else {
-                img
+                let srcset = source.attributes.borrow().get("srcset").unwrap_or_default().to_string();
+                self.select_candidate(&parse_srcset(&srcset), None)
+                    .and_then(|candidate| {
+                        if self.data_url_string(&candidate.url) {
+                            Some(candidate.url.clone())
+                        } else {
+                            self.embed(&candidate.url, base_url, images, warnings)
+                        }
+                    })
+            };
+            match data_url {
+                Some(data_url) => {
+                    let mut attributes = source.attributes.borrow_mut();
+                    attributes.insert("srcset", data_url);
+                    attributes.remove("sizes");
+                }
+                None => source.as_node().detach(),
             }
-        } else {
-            img
-        };
+        }
+        !sources.is_empty()
+    }
 
-        // 写入内存
-        let mut buffer = Cursor::new(Vec::new());
+    /// 把 HTML 中的图片替换为已下载图片的 data URI，没有改动时返回 None
+    fn embed_images(
+        &self,
+        html: &str,
+        base_url: &str,
+        images: &DownloadedImages,
+        warnings: &mut Vec<FilterWarning>,
+    ) -> Option<String> {
+        let document = kuchiki::parse_html().one(html);
+        let mut changed = false;
+        for img in select_all(&document, "img") {
+            changed |= self.embed_img(&mut img.attributes.borrow_mut(), base_url, images, warnings);
+        }
+        for picture in select_all(&document, "picture") {
+            changed |= self.embed_sources(picture.as_node(), base_url, images, warnings);
+        }
+        changed.then(|| serialize_html(html, &document))
+    }
+
+    /// 下载页面中的远程图片并内嵌为 data URI
+    ///
+    /// 先下载 `<img>` 的图片，再下载 `<img>` 没能内嵌的 `<picture>` 中 `<source>` 选中的
+    /// 图片。同一地址只下载一次，已缓存的图片不再下载，最多同时下载
+    /// [`DOWNLOAD_CONCURRENCY`] 张。下载失败的
+    /// 图片保留原地址，返回值为每个页面（来源 URL，没有时为页面路径）的警告。
+    ///
+    /// 图片按解析后的文档查找，注释和脚本中的标签不会被当作图片。相对地址按页面的
+    /// 来源 URL 解析，没有来源 URL 的页面使用 `base_url`。
+    pub async fn resolve_images(
+        &self,
+        pages: &mut HashMap<PageKey, StoredPage>,
+        base_url: &str,
+    ) -> Vec<(String, Vec<FilterWarning>)> {
+        // 图片地址到出现次数的映射
+        let mut urls = BTreeMap::new();
+        for page in pages.values() {
+            let page_url = page.source_url.as_deref().unwrap_or(base_url);
+            let document = kuchiki::parse_html().one(page.content.as_str());
+            for img in select_all(&document, "img") {
+                if let Some(url) = self.img_url(&img.attributes.borrow(), page_url) {
+                    *urls.entry(url).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut images = self.download_all(urls).await;
 
-        // 根据原始图片格式保存为相同格式，默认为 JPEG
-        let format = match content_type.as_str() {
-            "image/png" => image::ImageFormat::Png,
-            "image/gif" => image::ImageFormat::Gif,
-            _ => image::ImageFormat::Jpeg, // 使用 JPEG 格式
+        let mut urls = BTreeMap::new();
+        for page in pages.values() {
+            let page_url = page.source_url.as_deref().unwrap_or(base_url);
+            let document = kuchiki::parse_html().one(page.content.as_str());
+            let pictures = select_all(&document, "picture");
+            for picture in pictures.iter().filter(|picture| !self.img_embedded(picture.as_node(), page_url, &images)) {
+                for source in picture_sources(picture.as_node()) {
+                    if let Some(url) = self.source_url(&source.attributes.borrow(), page_url) {
+                        *urls.entry(url).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        urls.retain(|url, _| !images.contains_key(url));
+        images.extend(self.download_all(urls).await);
+
+        let mut warnings = Vec::new();
+        for (path, page) in pages.iter_mut() {
+            let mut page_warnings = Vec::new();
+            let page_url = page.source_url.as_deref().unwrap_or(base_url);
+            if let Some(content) = self.embed_images(&page.content, page_url, &images, &mut page_warnings) {
+                page.content = content;
+            }
+            if !page_warnings.is_empty() {
+                let url = page.source_url.clone().unwrap_or_else(|| path.to_string());
+                warnings.push((url, page_warnings));
+            }
+        }
+        warnings.sort_by(|a, b| a.0.cmp(&b.0));
+        warnings
+    }
+
+    /// 整理并内嵌一段 HTML 中的图片，相当于 [`Filter::apply_v2`] 之后立即下载图片
+    pub async fn apply_async(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        let html = self.apply_v2(html, context)?.html;
+        let mut page = StoredPage::new(html);
+        if !context.current_url.is_empty() {
+            page = page.with_source_url(&context.current_url);
+        }
+        let mut pages = HashMap::from([(PageKey::new(&context.current_path), page)]);
+        let warnings = self.resolve_images(&mut pages, &context.base_url).await;
+        let html = pages.into_values().next().map(|page| page.content).unwrap_or_default();
+        Ok(FilterOutput::new(html).with_warnings(warnings.into_iter().flat_map(|(_, w)| w)))
+    }
+
+    /// 并发下载图片，`urls` 为图片地址和在页面中出现的次数
+    ///
+    /// 取消后停止下载，正在进行的下载被丢弃，返回值中没有这些图片。
+    async fn download_all(&self, urls: BTreeMap<String, u64>) -> DownloadedImages {
+        let cancel = self.cancel.clone().unwrap_or_default();
+        stream::iter(urls)
+            .map(|(url, references)| async move {
+                let data_url = self.cached_or_download(&url, references).await;
+                (url, data_url)
+            })
+            .buffer_unordered(DOWNLOAD_CONCURRENCY)
+            .take_until(cancel.cancelled())
+            .collect()
+            .await
+    }
+
+    /// 依次从内存缓存、磁盘缓存中获取图片，都没有时下载，下载失败的图片不缓存
+    async fn cached_or_download(&self, url: &str, references: u64) -> Option<String> {
+        let cached = self.cache.lock().unwrap().get(url).cloned();
+        let cached = match cached {
+            Some(data_url) => Some(data_url),
+            None => self.read_disk_cache(url).await,
         };
 
-        // 保存为选择的格式
-        img.write_to(&mut buffer, format)
-            .map_err(|e| Error::Doc(format!("Failed to encode optimized image: {}", e)))?;
+        // 下载的图片只有第一处使用不算命中缓存
+        let (data_url, hits) = match cached {
+            Some(data_url) => (data_url, references),
+            None => match self.download_image(url).await {
+                Ok(data_url) => {
+                    self.write_disk_cache(url, &data_url).await;
+                    self.stats.lock().unwrap().downloads += 1;
+                    (data_url, references.saturating_sub(1))
+                }
+                Err(e) => {
+                    log::debug!("图片处理失败: {}", e);
+                    return None;
+                }
+            },
+        };
 
-        // 获取压缩后的图片数据
-        let optimized_bytes = buffer.into_inner();
+        self.cache.lock().unwrap().insert(url.to_string(), data_url.clone());
+        let mut stats = self.stats.lock().unwrap();
+        stats.cache_hits += hits;
+        stats.bytes_saved += hits * data_url.len() as u64;
+        Some(data_url)
+    }
 
-        // 检查是否达到目标大小
-        if optimized_bytes.len() > self.max_size {
-            // 如果还是太大，返回错误
-            return Err(Error::Doc(format!(
-                "Optimized image still too large: {} bytes (max: {} bytes)",
-                optimized_bytes.len(),
-                self.max_size
-            )));
+    /// 图片在磁盘缓存中的文件，以图片地址和编码设置的哈希命名
+    fn disk_cache_file(&self, url: &str) -> Option<PathBuf> {
+        let key = format!(
+            "{}\n{}\n{}\n{:?}",
+            url, self.max_size, self.optimize_images, self.max_width
+        );
+        let hash = format!("{:x}", Sha256::digest(key.as_bytes()));
+        self.cache_dir.as_ref().map(|dir| dir.join(hash))
+    }
+
+    /// 从磁盘缓存读取图片的 data URI
+    async fn read_disk_cache(&self, url: &str) -> Option<String> {
+        let path = self.disk_cache_file(url)?;
+        tokio::fs::read_to_string(&path)
+            .await
+            .ok()
+            .filter(|data_url| self.data_url_string(data_url))
+    }
+
+    /// 把图片的 data URI 写入磁盘缓存，失败时只记录日志
+    async fn write_disk_cache(&self, url: &str, data_url: &str) {
+        let Some(path) = self.disk_cache_file(url) else {
+            return;
+        };
+        let written = async {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&path, data_url).await
+        };
+        if let Err(e) = written.await {
+            log::debug!("无法写入图片缓存 {}: {}", path.display(), e);
         }
+    }
+
+    /// 下载图片并转换为 Base64，编码（和优化）在阻塞线程中进行
+    async fn download_image(&self, url: &str) -> Result<String> {
+        // 发起请求下载图片
+        let response = self.fetch.get(url, &HashMap::new()).await.map_err(|e| Error::Fetch {
+            url: url.to_string(),
+            source: Box::new(e),
+        })?;
 
-        // 对图片进行 Base64 编码
-        let base64_str = STANDARD.encode(&optimized_bytes);
+        // 检查状态码
+        if !response.is_success() {
+            return Err(Error::Fetch {
+                url: url.to_string(),
+                source: Box::new(Error::HttpError(response.status)),
+            });
+        }
 
-        // 返回 data URI
-        Ok(format!("data:{};base64,{}", content_type, base64_str))
+        // 获取 Content-Type
+        let content_type = response.content_type().unwrap_or("image/jpeg").to_string();
+        let options = EncodeOptions {
+            max_size: self.max_size,
+            optimize_images: self.optimize_images,
+            max_width: self.max_width,
+        };
+        let url = url.to_string();
+        tokio::task::spawn_blocking(move || encode_image(&url, response.body, content_type, options))
+            .await
+            .map_err(|e| Error::Message(format!("图片编码任务失败: {}", e)))?
     }
 
     /// 检查是否为数据URL
@@ -178,73 +650,108 @@ impl ImagesFilter {
     }
 }
 
-impl FilterBase for ImagesFilter {}
+/// 把图片转换为 data URI，超过大小限制时按设置优化或返回错误
+fn encode_image(url: &str, image_bytes: Vec<u8>, content_type: String, options: EncodeOptions) -> Result<String> {
+    // 检查图片大小
+    if image_bytes.len() > options.max_size {
+        // 如果启用了优化，尝试压缩图片
+        if options.optimize_images {
+            return optimize_image(url, &image_bytes, content_type, options);
+        } else {
+            return Err(Error::Doc(format!(
+                "Image too large: {} bytes (max: {} bytes)",
+                image_bytes.len(),
+                options.max_size
+            )));
+        }
+    }
 
-impl Filter for ImagesFilter {
-    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
-        // 解析 HTML
-        let document = Html::parse_document(html);
-
-        // 查找所有图片标签
-        let selector =
-            Selector::parse("img").map_err(|e| Error::Doc(format!("Invalid selector: {}", e)))?;
-
-        // 创建结果
-        let mut result = html.to_string();
-
-        // 处理每个图片
-        for img in document.select(&selector) {
-            // 获取图片的源 URL
-            let src = match img.value().attr("src") {
-                Some(src) => src,
-                None => continue, // 忽略没有 src 属性的图片
-            };
+    // 对图片进行 Base64 编码
+    let base64_str = STANDARD.encode(&image_bytes);
 
-            // 已经是 data URL 的跳过
-            if self.data_url_string(src) {
-                continue;
-            }
+    // 返回 data URI
+    Ok(format!("data:{};base64,{}", content_type, base64_str))
+}
 
-            // 处理相对 URL
-            let image_url = if self.relative_url_string(src) {
-                format!("{}{}", context.base_url.trim_end_matches('/'), src)
-            } else {
-                src.to_string()
-            };
+/// 优化图片
+fn optimize_image(
+    url: &str,
+    image_bytes: &[u8],
+    content_type: String,
+    options: EncodeOptions,
+) -> Result<String> {
+    // 使用 image crate 加载图片
+    let img = image::load_from_memory(image_bytes)
+        .map_err(|e| Error::Parse {
+            url: url.to_string(),
+            detail: format!("Failed to decode image: {}", e),
+        })?;
 
-            // 下载并转换图片
-            match self.download_image(&image_url) {
-                Ok(data_url) => {
-                    // 获取原始 img 标签的 HTML
-                    let img_html = img.html();
-
-                    // 创建新的 img 标签替换 src
-                    let new_img_html = img_html.replace(
-                        &format!("src=\"{}\"", src),
-                        &format!("src=\"{}\"", data_url),
-                    );
-
-                    // 替换 HTML 中的图片标签
-                    result = result.replace(&img_html, &new_img_html);
-                    println!("成功处理图片: {}", src);
-                }
-                Err(e) => {
-                    eprintln!("图片处理失败: {}", e);
-                    // 如果失败，保留原始图片
-                }
-            }
+    // 获取当前尺寸
+    let (width, height) = img.dimensions();
+
+    // 根据最大宽度调整图片大小
+    let img = if let Some(max_width) = options.max_width {
+        if width > max_width {
+            let new_height = (height as f32 * (max_width as f32 / width as f32)) as u32;
+            img.resize(max_width, new_height, image::imageops::FilterType::Lanczos3)
+        } else {
+            img
         }
+    } else {
+        img
+    };
+
+    // 写入内存
+    let mut buffer = Cursor::new(Vec::new());
 
-        Ok(result)
+    // 根据原始图片格式保存为相同格式，默认为 JPEG
+    let format = match content_type.as_str() {
+        "image/png" => image::ImageFormat::Png,
+        "image/gif" => image::ImageFormat::Gif,
+        _ => image::ImageFormat::Jpeg, // 使用 JPEG 格式
+    };
+
+    // 保存为选择的格式
+    img.write_to(&mut buffer, format)
+        .map_err(|e| Error::Doc(format!("Failed to encode optimized image: {}", e)))?;
+
+    // 获取压缩后的图片数据
+    let optimized_bytes = buffer.into_inner();
+
+    // 检查是否达到目标大小
+    if optimized_bytes.len() > options.max_size {
+        // 如果还是太大，返回错误
+        return Err(Error::Doc(format!(
+            "Optimized image still too large: {} bytes (max: {} bytes)",
+            optimized_bytes.len(),
+            options.max_size
+        )));
+    }
+
+    // 对图片进行 Base64 编码
+    let base64_str = STANDARD.encode(&optimized_bytes);
+
+    // 返回 data URI
+    Ok(format!("data:{};base64,{}", content_type, base64_str))
+}
+
+impl FilterBase for ImagesFilter {}
+
+impl Filter for ImagesFilter {
+    /// 只整理 `<img>` 标签，图片由 [`ImagesFilter::resolve_images`] 在抓取结束后下载
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+        let document = kuchiki::parse_html().one(html);
+        let mut changed = false;
+        for img in select_all(&document, "img") {
+            changed |= self.normalize_img(&mut img.attributes.borrow_mut());
+        }
+        let result = if changed { serialize_html(html, &document) } else { html.to_string() };
+        Ok(FilterOutput::new(result))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
-        Box::new(Self {
-            client: Client::new(),
-            max_size: self.max_size,
-            optimize_images: self.optimize_images,
-            max_width: self.max_width,
-        })
+        Box::new(self.clone())
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -259,14 +766,16 @@ impl Filter for ImagesFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch, MockReply};
+    use std::time::Duration;
 
     #[test]
     fn test_images_filter_data_url() {
         let filter = ImagesFilter::new();
-        let mut context = FilterContext::default();
+        let context = FilterContext::default();
 
         let html = r#"<img src="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCA...==" alt="Test">"#;
-        let result = filter.apply(html, &mut context).unwrap();
+        let result = filter.apply_v2(html, &context).unwrap().html;
 
         // 不应该修改已经是 data URL 的图片
         assert_eq!(html, result);
@@ -294,10 +803,7 @@ mod tests {
     #[test]
     fn test_relative_url_processing() {
         let filter = ImagesFilter::new();
-        let mut context = FilterContext {
-            base_url: "https://example.com".to_string(),
-            ..FilterContext::default()
-        };
+        let mut context = FilterContext::new().with_base_url("https://example.com");
 
         // 创建带有相对URL的HTML
         let html = r#"<img src="/images/test.png" alt="Test">"#;
@@ -306,4 +812,342 @@ mod tests {
         // 主要测试相对URL处理逻辑
         assert!(filter.relative_url_string("/images/test.png"));
     }
+
+    /// 1x1 的 PNG 图片
+    fn png_bytes() -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        image::RgbaImage::new(1, 1)
+            .write_to(&mut buffer, image::ImageFormat::Png)
+            .unwrap();
+        buffer.into_inner()
+    }
+
+    /// 为 `https://example.com` 下的图片路径返回 PNG 图片，返回 mock 以便检查请求次数
+    fn image_fetch(paths: &[&str]) -> Arc<MockFetch> {
+        let fetch = paths.iter().fold(MockFetch::new(), |fetch, path| {
+            fetch.with_response(&format!("https://example.com{}", path), png_response())
+        });
+        Arc::new(fetch)
+    }
+
+    fn example_context() -> FilterContext {
+        FilterContext::new().with_base_url("https://example.com")
+    }
+
+    #[tokio::test]
+    async fn test_lazy_src_is_promoted() {
+        let fetch = image_fetch(&["/img/lazy.png"]);
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+
+        let html = r#"<p><img src="data:image/gif;base64,R0lGOD" data-src="/img/lazy.png" loading="lazy" decoding="async" alt="Lazy"></p>"#;
+        let result = filter.apply_async(html, &example_context()).await.unwrap().html;
+
+        assert!(result.contains(r#"src="data:image/png;base64,"#));
+        assert!(!result.contains("data-src"));
+        assert!(!result.contains("loading="));
+        assert!(!result.contains("decoding="));
+        assert!(result.contains(r#"alt="Lazy""#));
+        assert_eq!(fetch.request_count("https://example.com/img/lazy.png"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_loading_attributes_can_be_kept() {
+        let fetch = image_fetch(&["/img/a.png"]);
+        let filter = ImagesFilter::new().with_strip_loading_attributes(false).with_fetch(fetch);
+
+        let html = r#"<img data-lazy-src="/img/a.png" loading="lazy">"#;
+        let result = filter.apply_async(html, &example_context()).await.unwrap().html;
+
+        assert!(result.contains(r#"loading="lazy""#));
+        assert!(!result.contains("data-lazy-src"));
+        assert!(result.contains("data:image/png;base64,"));
+    }
+
+    /// 返回 PNG 图片的响应
+    fn png_response() -> FetchedResponse {
+        FetchedResponse::new(200)
+            .with_header("content-type", "image/png")
+            .with_body(png_bytes())
+    }
+
+    #[tokio::test]
+    async fn test_srcset_picks_smallest_adequate_candidate() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response("https://example.com/img/s.png", png_response())
+                .with_response("https://example.com/img/m.png", png_response())
+                .with_response("https://example.com/img/l.png", png_response()),
+        );
+        let filter = ImagesFilter::new().with_max_width(500).with_fetch(fetch.clone());
+
+        let html = r#"<img src="/img/s.png" srcset="/img/s.png 300w, /img/m.png 600w, /img/l.png 1200w" sizes="50vw">"#;
+        let result = filter.apply_async(html, &example_context()).await.unwrap().html;
+
+        assert!(!result.contains("srcset"));
+        assert!(!result.contains("sizes"));
+        assert!(result.contains("data:image/png;base64,"));
+        assert_eq!(fetch.requested_urls(), ["https://example.com/img/m.png"]);
+    }
+
+    #[test]
+    fn test_parse_srcset_with_data_uri_and_densities() {
+        let candidates = parse_srcset("data:image/png;base64,AAA=, /img/b.png 2x,/img/c.png 640w");
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].url, "data:image/png;base64,AAA=");
+        assert_eq!(candidates[1].density, 2.0);
+        assert_eq!(candidates[2].width, Some(640));
+
+        let filter = ImagesFilter::new();
+        let candidates = parse_srcset("/img/a.png 1x, /img/b.png 2x");
+        assert_eq!(filter.select_candidate(&candidates, None).unwrap().url, "/img/a.png");
+    }
+
+    #[tokio::test]
+    async fn test_picture_sources_dropped_when_img_embedded() {
+        let fetch = image_fetch(&["/img/a.png", "/img/a.webp"]);
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+
+        let html = r#"<picture><source srcset="/img/a.webp" type="image/webp"><img src="/img/a.png" alt="A"></picture>"#;
+        let result = filter.apply_async(html, &example_context()).await.unwrap().html;
+
+        assert!(!result.contains("<source"));
+        let document = scraper::Html::parse_fragment(&result);
+        let img = document.select(&scraper::Selector::parse("picture > img").unwrap()).next().unwrap();
+        assert!(img.value().attr("src").unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(img.value().attr("alt"), Some("A"));
+        assert_eq!(fetch.requested_urls(), ["https://example.com/img/a.png"]);
+    }
+
+    #[tokio::test]
+    async fn test_tags_in_comments_and_scripts_are_ignored() {
+        let fetch = image_fetch(&["/img/a.png"]);
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+
+        let html = concat!(
+            r#"<!-- <img data-src="/img/comment.png"> -->"#,
+            r#"<script>const tag = '<img src="/img/script.png">';</script>"#,
+            r#"<p><img alt="a > b" data-src="/img/a.png"></p>"#,
+        );
+        let result = filter.apply_async(html, &example_context()).await.unwrap().html;
+
+        assert!(result.contains(r#"<!-- <img data-src="/img/comment.png"> -->"#));
+        assert!(result.contains(r#"'<img src="/img/script.png">'"#));
+        let document = scraper::Html::parse_fragment(&result);
+        let img = document.select(&scraper::Selector::parse("p > img").unwrap()).next().unwrap();
+        assert_eq!(img.value().attr("alt"), Some("a > b"));
+        assert!(img.value().attr("src").unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(fetch.requested_urls(), ["https://example.com/img/a.png"]);
+    }
+
+    #[tokio::test]
+    async fn test_picture_sources_processed_when_img_fails() {
+        let fetch = image_fetch(&["/img/a.webp"]);
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+
+        let html = r#"<picture><source srcset="/img/a.webp 1x, /img/a@2x.webp 2x" type="image/webp"><source srcset="/img/missing.avif" type="image/avif"><img src="/img/missing.png"></picture>"#;
+        let result = filter.apply_async(html, &example_context()).await.unwrap().html;
+
+        // 属性的顺序不重要，解析后逐个检查
+        let document = scraper::Html::parse_fragment(&result);
+        let sources: Vec<_> = document
+            .select(&scraper::Selector::parse("picture > source").unwrap())
+            .map(|source| source.value())
+            .collect();
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0].attr("srcset").unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(sources[0].attr("type"), Some("image/webp"));
+        let img = document.select(&scraper::Selector::parse("picture > img").unwrap()).next().unwrap();
+        assert_eq!(img.value().attr("src"), Some("/img/missing.png"));
+        assert_eq!(fetch.request_count("https://example.com/img/a.webp"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_broken_image_is_reported_as_warning() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response("https://example.com/img/gone.png", FetchedResponse::new(500))
+                .with_failure("https://example.com/img/reset.png", "connection reset"),
+        );
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+        let html = r#"<p>Intro</p><img src="/img/gone.png" alt="Gone"><img src="/img/reset.png">"#;
+        let output = filter.apply_async(html, &example_context()).await.unwrap();
+
+        assert_eq!(output.html, html);
+        assert_eq!(output.warnings.len(), 2);
+        assert_eq!(output.warnings[0].filter, "ImagesFilter");
+        assert_eq!(output.warnings[0].message, "image download failed");
+        assert_eq!(output.warnings[0].url.as_deref(), Some("https://example.com/img/gone.png"));
+        assert_eq!(output.warnings[1].url.as_deref(), Some("https://example.com/img/reset.png"));
+        assert_eq!(fetch.requests().len(), 2);
+    }
+
+    /// 记录同时进行的请求数的 HTTP 实现
+    #[derive(Default)]
+    struct CountingFetch {
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+        requests: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpFetch for CountingFetch {
+        async fn get(&self, _url: &str, _headers: &HashMap<String, String>) -> Result<FetchedResponse> {
+            use std::sync::atomic::Ordering;
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(png_response())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_images_resolved_after_filtering() {
+        let fetch = Arc::new(CountingFetch::default());
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+        let context = example_context();
+
+        // 过滤阶段不发起请求
+        let mut pages = HashMap::new();
+        for page in 0..5 {
+            let html: String = (0..4)
+                .map(|image| format!(r#"<img data-src="/img/{}.png"><img src="/img/shared.png">"#, page * 4 + image))
+                .collect();
+            let filtered = filter.apply_v2(&html, &context).unwrap().html;
+            let url = format!("https://example.com/page{}", page);
+            pages.insert(PageKey::new(&format!("page{}", page)), StoredPage::new(filtered).with_source_url(&url));
+        }
+        assert_eq!(fetch.requests.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        // 相同地址只下载一次，同时进行的下载不超过上限
+        let warnings = filter.resolve_images(&mut pages, &context.base_url).await;
+        assert!(warnings.is_empty());
+        assert_eq!(fetch.requests.load(std::sync::atomic::Ordering::SeqCst), 21);
+        let peak = fetch.peak.load(std::sync::atomic::Ordering::SeqCst);
+        assert!(peak > 1 && peak <= DOWNLOAD_CONCURRENCY, "peak {}", peak);
+        for page in pages.values() {
+            assert_eq!(page.content.matches("data:image/png;base64,").count(), 8);
+            assert!(!page.content.contains("/img/"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_images_keep_src_and_warn_per_page() {
+        let fetch = Arc::new(MockFetch::new().with_failure("https://example.com/img/gone.png", "timeout"));
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+        let html = r#"<img src="/img/gone.png" alt="Gone">"#;
+        let mut pages = HashMap::from([
+            (PageKey::new("a"), StoredPage::new(html.to_string()).with_source_url("https://example.com/a")),
+            (PageKey::new("b"), StoredPage::new(html.to_string())),
+        ]);
+
+        let warnings = filter.resolve_images(&mut pages, "https://example.com").await;
+        assert_eq!(fetch.request_count("https://example.com/img/gone.png"), 1);
+        assert!(pages.values().all(|page| page.content == html));
+        let pages: Vec<&str> = warnings.iter().map(|(page, _)| page.as_str()).collect();
+        assert_eq!(pages, ["b", "https://example.com/a"]);
+        assert_eq!(warnings[0].1[0].url.as_deref(), Some("https://example.com/img/gone.png"));
+    }
+
+    #[tokio::test]
+    async fn test_relative_src_is_resolved_against_page_url() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response("https://example.com/docs/guide/img/a.png", png_response())
+                .with_response("https://example.com/docs/b.png", png_response())
+                .with_response("https://cdn.example.com/c.png", png_response())
+                .with_response("https://example.com/d.png", png_response()),
+        );
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+        let html = r#"<img src="img/a.png"><img src="../b.png"><img src="//cdn.example.com/c.png"><img src="/d.png">"#;
+        let mut pages = HashMap::from([(
+            PageKey::new("guide/intro"),
+            StoredPage::new(html.to_string()).with_source_url("https://example.com/docs/guide/intro"),
+        )]);
+
+        let warnings = filter.resolve_images(&mut pages, "https://example.com/docs").await;
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(pages[&PageKey::new("guide/intro")].content.matches("data:image/png;base64,").count(), 4);
+        for url in [
+            "https://example.com/docs/guide/img/a.png",
+            "https://example.com/docs/b.png",
+            "https://cdn.example.com/c.png",
+            "https://example.com/d.png",
+        ] {
+            assert_eq!(fetch.request_count(url), 1, "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_downloads_keep_src() {
+        let fetch = Arc::new(MockFetch::new().with_reply(
+            "https://example.com/img/slow.png",
+            MockReply::respond(png_response()).after(Duration::from_secs(30)),
+        ));
+        let cancel = CancellationToken::new();
+        let filter = ImagesFilter::new().with_fetch(fetch.clone()).with_cancellation(cancel.clone());
+        let html = r#"<img src="/img/slow.png" alt="Slow">"#;
+        let mut pages = HashMap::from([(PageKey::new("a"), StoredPage::new(html.to_string()))]);
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel.cancel();
+        });
+        let started = std::time::Instant::now();
+        let warnings = filter.resolve_images(&mut pages, "https://example.com").await;
+        canceller.await.unwrap();
+
+        // 正在进行的下载被丢弃，图片保留原地址，不算下载失败
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(pages[&PageKey::new("a")].content, html);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(filter.stats().downloads, 0);
+    }
+
+    #[tokio::test]
+    async fn test_images_are_cached_across_calls() {
+        let fetch = image_fetch(&["/img/a.png"]);
+        let filter = ImagesFilter::new().with_fetch(fetch.clone());
+        let copy = filter.box_clone();
+        let copy = copy.as_any().downcast_ref::<ImagesFilter>().unwrap();
+
+        let html = r#"<img src="/img/a.png"><img src="/img/a.png">"#;
+        let first = filter.apply_async(html, &example_context()).await.unwrap().html;
+        let second = copy.apply_async(html, &example_context()).await.unwrap().html;
+
+        assert_eq!(first, second);
+        assert_eq!(fetch.request_count("https://example.com/img/a.png"), 1);
+        let stats = filter.take_stats();
+        let data_url_len = first.split('"').nth(1).unwrap().len() as u64;
+        assert_eq!(stats.downloads, 1);
+        assert_eq!(stats.cache_hits, 3);
+        assert_eq!(stats.bytes_saved, 3 * data_url_len);
+        assert!(copy.stats().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_avoids_network() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let html = r#"<img src="/img/a.png">"#;
+
+        let fetch = image_fetch(&["/img/a.png"]);
+        let filter = ImagesFilter::new().with_fetch(fetch.clone()).with_cache_dir(dir.path());
+        let first = filter.apply_async(html, &example_context()).await.unwrap().html;
+        assert_eq!(fetch.request_count("https://example.com/img/a.png"), 1);
+
+        let offline = Arc::new(MockFetch::new());
+        let filter = ImagesFilter::new().with_fetch(offline.clone()).with_cache_dir(dir.path());
+        let second = filter.apply_async(html, &example_context()).await.unwrap().html;
+        assert_eq!(first, second);
+        assert!(offline.requests().is_empty());
+        assert_eq!(filter.stats().downloads, 0);
+        assert_eq!(filter.stats().cache_hits, 1);
+
+        // 编码设置不同时不使用缓存
+        let filter = ImagesFilter::new().with_fetch(offline.clone()).with_cache_dir(dir.path()).with_max_width(10);
+        let output = filter.apply_async(html, &example_context()).await.unwrap();
+        assert_eq!(output.html, html);
+        assert_eq!(offline.request_count("https://example.com/img/a.png"), 1);
+    }
 }
@@ -1,5 +1,9 @@
 //! HTML 相关过滤器
 
+// 导出归属信息过滤器
+pub mod attribution;
+pub use attribution::AttributionFilter;
+
 // 导出图片过滤器
 pub mod images;
 pub use images::ImagesFilter;
@@ -2,7 +2,7 @@
 //! 通用HTML清理功能
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
 use scraper::{Html, Selector};
 use std::any::Any;
 
@@ -46,18 +46,22 @@ impl HtmlCleanerFilter {
 }
 
 impl Filter for HtmlCleanerFilter {
-    fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
         // 解析HTML
         let document = Html::parse_fragment(html);
         let mut result = html.to_string();
+        let mut warnings = Vec::new();
 
         // 移除指定的标签
         for tag in &self.remove_tags {
-            if let Ok(selector) = Selector::parse(tag) {
-                for element in document.select(&selector) {
-                    let html_fragment = element.html();
-                    result = result.replace(&html_fragment, "");
+            match Selector::parse(tag) {
+                Ok(selector) => {
+                    for element in document.select(&selector) {
+                        let html_fragment = element.html();
+                        result = result.replace(&html_fragment, "");
+                    }
                 }
+                Err(_) => warnings.push(self.warning("invalid selector").with_selector(tag)),
             }
         }
         
@@ -66,16 +70,19 @@ impl Filter for HtmlCleanerFilter {
             let selector_str = format!(".{}", class);
             // 创建局部变量，确保selector_str在使用时仍然存在
             let selector_result = Selector::parse(&selector_str);
-            if let Ok(selector) = selector_result {
-                for element in document.select(&selector) {
-                    let html_fragment = element.html();
-                    result = result.replace(&html_fragment, "");
+            match selector_result {
+                Ok(selector) => {
+                    for element in document.select(&selector) {
+                        let html_fragment = element.html();
+                        result = result.replace(&html_fragment, "");
+                    }
                 }
+                Err(_) => warnings.push(self.warning("invalid selector").with_selector(&selector_str)),
             }
         }
 
         // 返回处理后的HTML
-        Ok(result)
+        Ok(FilterOutput::new(result).with_warnings(warnings))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
@@ -0,0 +1,94 @@
+//! HTML 序列化的公共部分
+//!
+//! 各过滤器自己遍历 DOM 输出 HTML 时使用同一份空元素列表和转义规则，
+//! 转义规则与 html5ever 的序列化器一致，未改动的内容序列化后保持不变。
+
+/// 没有结束标签的元素
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// 内容不转义的元素
+pub(crate) const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// 转义文本节点
+pub(crate) fn escape_text(text: &str) -> String {
+    escape(text, false)
+}
+
+/// 转义双引号中的属性值
+pub(crate) fn escape_attribute(value: &str) -> String {
+    escape(value, true)
+}
+
+/// 转义生成的 HTML 中的文本，结果在文本和属性值中都可以使用
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 生成开始标签
+pub(crate) fn start_tag<'a>(name: &str, attrs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut tag = format!("<{}", name);
+    for (name, value) in attrs {
+        tag.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+    }
+    tag.push('>');
+    tag
+}
+
+/// 转义文本或属性值
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '<' if !attribute => escaped.push_str("&lt;"),
+            '>' if !attribute => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 序列化由 `html` 解析得到的文档
+///
+/// 原内容是完整文档时输出完整文档，是片段时只输出 head 和 body 中的内容，
+/// 不添加解析器补全的外层元素。
+pub(crate) fn serialize_html(html: &str, document: &kuchiki::NodeRef) -> String {
+    let trimmed = html.trim_start().to_ascii_lowercase();
+    if trimmed.starts_with("<!doctype") || trimmed.starts_with("<html") {
+        return document.to_string();
+    }
+
+    let mut output = String::new();
+    for container in ["head", "body"] {
+        if let Ok(container) = document.select_first(container) {
+            for child in container.as_node().children() {
+                output.push_str(&child.to_string());
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchiki::traits::TendrilSink;
+
+    #[test]
+    fn test_escape_matches_html5ever() {
+        let text = "a < b && \"c\"\u{a0}>";
+        let document = kuchiki::parse_html().one(format!("<p title='{0}'>{0}</p>", text));
+        let p = document.select_first("p").unwrap();
+        let expected = p.as_node().to_string();
+        let attrs = p.attributes.borrow();
+        let actual = format!("{}{}</p>", start_tag("p", [("title", attrs.get("title").unwrap())]), escape_text(text));
+        assert_eq!(actual, expected);
+    }
+}
@@ -2,13 +2,21 @@
 //! 提供与 Ruby 原版核心过滤器一致的功能
 
 mod base_clean_html;
+pub mod default_entries;
 mod filter_base;
 mod html_cleaner;
 pub mod html;
+pub(crate) mod markup;
+pub mod selector_cache;
 mod url_normalizer;
 
-pub use base_clean_html::BaseCleanHtmlFilter;
+pub use base_clean_html::{
+    BaseCleanHtmlFilter, DEFINITION_CLASS, EXPERIMENTAL_CLASS, NOTE_CLASS, SYNTAX_CLASS, WARNING_CLASS,
+};
+pub use default_entries::DefaultEntriesFilter;
 pub use filter_base::FilterBase;
 pub use html_cleaner::HtmlCleanerFilter;
-pub use html::ImagesFilter;
+pub use html::{AttributionFilter, ImagesFilter};
+pub use selector_cache::{selector, try_selector};
 pub use url_normalizer::UrlNormalizerFilter;
+pub(crate) use markup::serialize_html;
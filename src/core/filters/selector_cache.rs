@@ -0,0 +1,103 @@
+//! 解析后的选择器缓存
+//!
+//! 很多过滤器在每个页面上都用相同的字符串调用 `Selector::parse`，大规模抓取时
+//! 选择器解析在性能分析中占比明显。这里按选择器字符串缓存解析结果，整个进程
+//! 只解析一次。
+//!
+//! 代码中写死的选择器用 [`selector`] 获取，无效时在第一次使用时 panic，不会
+//! 静默地匹配不到任何元素；来自调用方或配置的选择器用 [`try_selector`]，无效时
+//! 返回 None。
+//!
+//! 缓存前后的耗时对比见 `bench_selector_cache`：
+//! `cargo test --release bench_selector_cache -- --ignored --nocapture`。
+
+use lazy_static::lazy_static;
+use scraper::Selector;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+lazy_static! {
+    /// 选择器字符串到解析结果的缓存，只缓存解析成功的选择器
+    static ref SELECTORS: RwLock<HashMap<String, Arc<Selector>>> = RwLock::new(HashMap::new());
+}
+
+/// 获取代码中写死的选择器，选择器无效时 panic
+pub fn selector(css: &str) -> Arc<Selector> {
+    match parse_cached(css) {
+        Ok(selector) => selector,
+        Err(e) => panic!("无效的选择器 {:?}: {}", css, e),
+    }
+}
+
+/// 获取来自调用方或配置的选择器，选择器无效时返回 None
+pub fn try_selector(css: &str) -> Option<Arc<Selector>> {
+    parse_cached(css).ok()
+}
+
+/// 从缓存中获取选择器，没有时解析并放入缓存
+fn parse_cached(css: &str) -> Result<Arc<Selector>, String> {
+    if let Some(selector) = SELECTORS.read().unwrap().get(css) {
+        return Ok(selector.clone());
+    }
+
+    let parsed = Arc::new(Selector::parse(css).map_err(|e| e.to_string())?);
+    let mut selectors = SELECTORS.write().unwrap();
+    Ok(selectors.entry(css.to_string()).or_insert(parsed).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// MDN 页面中使用的较长的选择器
+    const MDN_SELECTOR: &str = "#content > .main-page-content .section-content > table.properties th, .bc-table td:first-child > code, .notecard.deprecated";
+
+    #[test]
+    fn test_selector_is_parsed_once() {
+        let first = selector("a[href]");
+        let second = selector("a[href]");
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let html = scraper::Html::parse_fragment(r#"<a href="/x">x</a><a>y</a>"#);
+        assert_eq!(html.select(&first).count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "无效的选择器 \"a[href\"")]
+    fn test_malformed_selector_fails_loudly() {
+        selector("a[href");
+    }
+
+    #[test]
+    fn test_try_selector_rejects_malformed() {
+        assert!(try_selector("div >").is_none());
+        assert!(try_selector("div > p").is_some());
+    }
+
+    /// 对比每次解析和使用缓存的耗时
+    #[test]
+    #[ignore]
+    fn bench_selector_cache() {
+        const ITERATIONS: u32 = 100_000;
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(Selector::parse(MDN_SELECTOR).unwrap());
+        }
+        let parsed = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(selector(MDN_SELECTOR));
+        }
+        let cached = start.elapsed();
+
+        println!(
+            "每次解析: {:?}/次，缓存: {:?}/次",
+            parsed / ITERATIONS,
+            cached / ITERATIONS
+        );
+        assert!(cached < parsed);
+    }
+}
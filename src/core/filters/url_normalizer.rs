@@ -1,10 +1,35 @@
 //! URL规范化过滤器
-//! 
-//! 基于Ruby原版实现的URL规范化过滤器
+//!
+//! 基于Ruby原版实现的URL规范化过滤器。只处理元素的链接属性（`href`、`src`
+//! 等），代码示例中的文本和其他属性即使包含基础URL也保持不变：
+//!
+//! - 相对链接和协议相对链接（`//cdn.example.com/x.js`）按当前页面URL解析
+//! - 位于内部URL前缀之下的链接改写为 `<输出前缀><文档路径>`
+//! - 外部白名单中的链接（如同一域名下的 CDN 资源）总是保持绝对URL
+//! - 可选地将 `index.html` 结尾的链接折叠为目录
+//!
+//! 改写前的内部链接（绝对URL）记录在 `FilterContext::links` 中，抓取器据此
+//! 继续抓取，不会把改写后的文档路径当作网址。
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
+use crate::core::filters::serialize_html;
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use kuchiki::traits::TendrilSink;
 use std::any::Any;
+use url::Url;
+
+/// 需要规范化的元素和链接属性
+const LINK_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("area", "href"),
+    ("link", "href"),
+    ("img", "src"),
+    ("script", "src"),
+    ("source", "src"),
+    ("iframe", "src"),
+    ("video", "src"),
+    ("audio", "src"),
+];
 
 /// URL规范化过滤器
 pub struct UrlNormalizerFilter {
@@ -12,6 +37,12 @@ pub struct UrlNormalizerFilter {
     base_url: String,
     /// 输出URL前缀
     output_prefix: String,
+    /// 额外的内部URL前缀，基础URL总是内部前缀
+    internal_prefixes: Vec<String>,
+    /// 总是保持绝对URL的前缀
+    external_allowlist: Vec<String>,
+    /// 是否将 `index.html` 结尾的链接折叠为目录
+    collapse_index_html: bool,
 }
 
 impl UrlNormalizerFilter {
@@ -20,21 +51,126 @@ impl UrlNormalizerFilter {
         Self {
             base_url: base_url.to_string(),
             output_prefix: output_prefix.to_string(),
+            internal_prefixes: Vec::new(),
+            external_allowlist: Vec::new(),
+            collapse_index_html: false,
+        }
+    }
+
+    /// 添加内部URL前缀，其下的链接与基础URL下的链接一样改写为文档路径
+    pub fn with_internal_prefix(mut self, prefix: &str) -> Self {
+        self.internal_prefixes.push(prefix.to_string());
+        self
+    }
+
+    /// 添加外部白名单前缀，其下的链接即使位于内部前缀之下也保持绝对URL
+    pub fn with_external(mut self, prefix: &str) -> Self {
+        self.external_allowlist.push(prefix.to_string());
+        self
+    }
+
+    /// 设置是否将 `index.html` 结尾的链接折叠为目录
+    pub fn with_collapse_index_html(mut self, collapse: bool) -> Self {
+        self.collapse_index_html = collapse;
+        self
+    }
+
+    /// 规范化单个链接，不需要改写时返回 None，内部链接的绝对URL记录到 `links`
+    fn normalize(&self, link: &str, page_url: Option<&Url>, links: &mut Vec<String>) -> Option<String> {
+        let link = link.trim();
+        if link.is_empty() || link.starts_with('#') {
+            return None;
         }
+
+        let resolved = match page_url {
+            Some(page_url) => page_url.join(link).ok()?,
+            None => Url::parse(link).ok()?,
+        };
+        if !matches!(resolved.scheme(), "http" | "https") {
+            return None;
+        }
+        let resolved = resolved.to_string();
+
+        if self.external_allowlist.iter().any(|prefix| resolved.starts_with(prefix.as_str())) {
+            return Some(resolved);
+        }
+
+        let internal_prefixes = std::iter::once(&self.base_url).chain(&self.internal_prefixes);
+        for prefix in internal_prefixes {
+            if let Some(path) = strip_url_prefix(&resolved, prefix) {
+                let page = resolved.split('#').next().unwrap_or(&resolved);
+                if !links.iter().any(|link| link == page) {
+                    links.push(page.to_string());
+                }
+                let path = if self.collapse_index_html {
+                    collapse_index_html(path)
+                } else {
+                    path.to_string()
+                };
+                return Some(format!("{}{}", self.output_prefix, path));
+            }
+        }
+
+        // 外部链接使用解析后的绝对URL（协议相对链接补全协议）
+        Some(resolved)
+    }
+}
+
+/// 去掉URL前缀，返回文档路径（不含开头的斜杠），不在前缀之下时返回 None
+fn strip_url_prefix<'a>(url: &'a str, prefix: &str) -> Option<&'a str> {
+    let prefix = prefix.trim_end_matches('/');
+    let rest = url.strip_prefix(prefix)?;
+    if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+        Some(rest.trim_start_matches('/'))
+    } else {
+        None
+    }
+}
+
+/// 将路径末尾的 `index.html` 折叠为目录，保留查询和锚点
+fn collapse_index_html(path: &str) -> String {
+    let split = path.find(['?', '#']).unwrap_or(path.len());
+    let (page, suffix) = path.split_at(split);
+    match page.strip_suffix("index.html") {
+        Some(dir) if dir.is_empty() || dir.ends_with('/') => format!("{}{}", dir, suffix),
+        _ => path.to_string(),
     }
 }
 
 impl Filter for UrlNormalizerFilter {
-    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
-        // 在实际实现中，这里应该解析HTML并规范化所有URL
-        // 现在我们只返回原始HTML
-        Ok(html.to_string())
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        let page_url = [context.current_url.as_str(), self.base_url.as_str()]
+            .into_iter()
+            .find_map(|url| Url::parse(url).ok());
+
+        let document = kuchiki::parse_html().one(html);
+        let mut links = Vec::new();
+
+        for (element, attribute) in LINK_ATTRIBUTES {
+            let Ok(nodes) = document.select(&format!("{}[{}]", element, attribute)) else {
+                continue;
+            };
+            for node in nodes {
+                let mut attributes = node.attributes.borrow_mut();
+                let normalized = attributes
+                    .get(*attribute)
+                    .and_then(|link| self.normalize(link, page_url.as_ref(), &mut links));
+                if let Some(normalized) = normalized {
+                    attributes.insert(*attribute, normalized);
+                }
+            }
+        }
+
+        Ok(FilterOutput::new(serialize_html(html, &document)).with_links(links))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
         Box::new(Self {
             base_url: self.base_url.clone(),
             output_prefix: self.output_prefix.clone(),
+            internal_prefixes: self.internal_prefixes.clone(),
+            external_allowlist: self.external_allowlist.clone(),
+            collapse_index_html: self.collapse_index_html,
         })
     }
 
@@ -46,3 +182,106 @@ impl Filter for UrlNormalizerFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://example.com/docs/javascript/";
+
+    fn apply(filter: &UrlNormalizerFilter, html: &str, page: &str) -> String {
+        apply_with_context(filter, html, page).0
+    }
+
+    fn apply_with_context(filter: &UrlNormalizerFilter, html: &str, page: &str) -> (String, FilterContext) {
+        let mut context = FilterContext::new().with_base_url(BASE).with_html(html);
+        context.current_url = format!("{}{}", BASE, page);
+        let output = filter.apply_v2(html, &context).unwrap();
+        let result = output.html.clone();
+        context.merge_output(filter.name(), output);
+        (result, context)
+    }
+
+    #[test]
+    fn test_internal_links_become_doc_paths() {
+        let filter = UrlNormalizerFilter::new(BASE, "/docs/javascript/");
+        let html = concat!(
+            r#"<a href="https://example.com/docs/javascript/Global_Objects/Array#syntax">abs</a>"#,
+            r#"<a href="map">rel</a>"#,
+            r##"<a href="#examples">anchor</a>"##,
+            r#"<a href="../../../other/">outside</a>"#,
+            r#"<a href="mailto:a@example.com">mail</a>"#,
+        );
+
+        let (result, context) = apply_with_context(&filter, html, "Global_Objects/Array/");
+        assert!(result.contains(r#"href="/docs/javascript/Global_Objects/Array#syntax""#));
+        assert!(result.contains(r#"href="/docs/javascript/Global_Objects/Array/map""#));
+        assert!(result.contains(r##"href="#examples""##));
+        assert!(result.contains(r#"href="https://example.com/docs/other/""#));
+        assert!(result.contains(r#"href="mailto:a@example.com""#));
+        // 片段不会被补全为完整文档
+        assert!(!result.contains("<body>"));
+        // 改写前的内部链接留给抓取器使用
+        assert_eq!(
+            context.links,
+            [
+                "https://example.com/docs/javascript/Global_Objects/Array",
+                "https://example.com/docs/javascript/Global_Objects/Array/map",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cdn_and_protocol_relative_links() {
+        let filter = UrlNormalizerFilter::new(BASE, "/docs/javascript/")
+            .with_internal_prefix("https://mirror.example.org/js/")
+            .with_external("https://example.com/docs/javascript/static/");
+        let html = concat!(
+            r#"<img src="//cdn.example.net/logo.png">"#,
+            r#"<script src="https://example.com/docs/javascript/static/app.js"></script>"#,
+            r#"<a href="//mirror.example.org/js/Statements/if">mirror</a>"#,
+        );
+
+        let result = apply(&filter, html, "index");
+        assert!(result.contains(r#"src="https://cdn.example.net/logo.png""#));
+        assert!(result.contains(r#"src="https://example.com/docs/javascript/static/app.js""#));
+        assert!(result.contains(r#"href="/docs/javascript/Statements/if""#));
+    }
+
+    #[test]
+    fn test_collapse_index_html() {
+        let filter = UrlNormalizerFilter::new(BASE, "").with_collapse_index_html(true);
+        let html = r#"<a href="guide/index.html#start">guide</a><a href="myindex.html">other</a>"#;
+
+        let result = apply(&filter, html, "index");
+        assert!(result.contains(r##"href="guide/#start""##));
+        assert!(result.contains(r#"href="myindex.html""#));
+    }
+
+    #[test]
+    fn test_code_samples_are_not_rewritten() {
+        let filter = UrlNormalizerFilter::new(BASE, "/docs/javascript/");
+        let html = concat!(
+            r#"<pre><code data-url="https://example.com/docs/javascript/fetch">"#,
+            r#"fetch("https://example.com/docs/javascript/api") &amp;&amp; x &lt; 1</code></pre>"#,
+            r#"<input value="https://example.com/docs/javascript/x">"#,
+            r#"<a href="https://other.com/?next=https://example.com/docs/javascript/y">ext</a>"#,
+        );
+
+        let result = apply(&filter, html, "index");
+        assert!(result.contains(r#"data-url="https://example.com/docs/javascript/fetch""#));
+        assert!(result.contains(r#"fetch("https://example.com/docs/javascript/api") &amp;&amp; x &lt; 1"#));
+        assert!(result.contains(r#"value="https://example.com/docs/javascript/x""#));
+        assert!(result.contains(r#"href="https://other.com/?next=https://example.com/docs/javascript/y""#));
+    }
+
+    #[test]
+    fn test_full_document_is_preserved() {
+        let filter = UrlNormalizerFilter::new(BASE, "/docs/javascript/");
+        let html = r#"<!DOCTYPE html><html><head><title>T</title></head><body><a href="a">a</a></body></html>"#;
+
+        let result = apply(&filter, html, "index");
+        assert!(result.starts_with("<!DOCTYPE html><html><head><title>T</title></head>"));
+        assert!(result.contains(r#"<a href="/docs/javascript/a">a</a></body></html>"#));
+    }
+}
@@ -0,0 +1,158 @@
+//! 便于阅读的数值格式
+//!
+//! 命令行表格、抓取摘要和控制台事件中的字节数、时间戳和耗时都通过这里格式化：
+//! 字节数显示为 `87.3 MB`，时间戳显示为 `2024-05-01 14:02`（可附带 `3 天前`），
+//! 耗时显示为 `1m 42s`。JSON 输出保留原始数值，不经过这里。
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 时间戳按本地时区还是 UTC 显示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeDisplay {
+    /// 本地时区
+    #[default]
+    Local,
+    /// UTC
+    Utc,
+}
+
+impl FromStr for TimeDisplay {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(TimeDisplay::Local),
+            "utc" => Ok(TimeDisplay::Utc),
+            _ => Err(format!("未知的时间显示方式: {}（可选 local 或 utc）", s)),
+        }
+    }
+}
+
+/// 字节数，1024 进制，不足 1 KB 时显示整数字节
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// 耗时：不足 1 秒显示毫秒，不足 1 分钟显示一位小数的秒数，否则为 `1m 42s` 或 `1h 2m 5s`
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        return format!("{}ms", millis);
+    }
+    let secs = duration.as_secs();
+    if secs < 60 {
+        return format!("{}.{}s", secs, millis % 1000 / 100);
+    }
+    let (hours, minutes, secs) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if hours == 0 {
+        format!("{}m {}s", minutes, secs)
+    } else {
+        format!("{}h {}m {}s", hours, minutes, secs)
+    }
+}
+
+/// 时间戳（Unix 秒），精确到分钟，UTC 显示时附带 `UTC`
+pub fn format_timestamp(epoch: i64, display: TimeDisplay) -> String {
+    let Some(time) = DateTime::<Utc>::from_timestamp(epoch, 0) else {
+        return epoch.to_string();
+    };
+    match display {
+        TimeDisplay::Local => Local.from_utc_datetime(&time.naive_utc()).format("%Y-%m-%d %H:%M").to_string(),
+        TimeDisplay::Utc => time.format("%Y-%m-%d %H:%M UTC").to_string(),
+    }
+}
+
+/// 相对于 `now` 的时间（Unix 秒），如 `3 天前`，一分钟以内或晚于 `now` 时为 `刚刚`
+pub fn format_relative(epoch: i64, now: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let elapsed = now.saturating_sub(epoch);
+    if elapsed < MINUTE {
+        "刚刚".to_string()
+    } else if elapsed < HOUR {
+        format!("{} 分钟前", elapsed / MINUTE)
+    } else if elapsed < DAY {
+        format!("{} 小时前", elapsed / HOUR)
+    } else if elapsed < MONTH {
+        format!("{} 天前", elapsed / DAY)
+    } else if elapsed < YEAR {
+        format!("{} 个月前", elapsed / MONTH)
+    } else {
+        format!("{} 年前", elapsed / YEAR)
+    }
+}
+
+/// 时间戳及其相对时间，如 `2024-05-01 14:02（3 天前）`
+pub fn format_time_with_relative(epoch: i64, now: i64, display: TimeDisplay) -> String {
+    format!("{}（{}）", format_timestamp(epoch, display), format_relative(epoch, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(1024 * 1024 - 1), "1024.0 KB");
+        assert_eq!(format_bytes(1024 * 1024), "1.0 MB");
+        assert_eq!(format_bytes(91_540_000), "87.3 MB");
+        assert_eq!(format_bytes(u64::MAX), "16384.0 PB");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0ms");
+        assert_eq!(format_duration(Duration::from_millis(999)), "999ms");
+        assert_eq!(format_duration(Duration::from_millis(1000)), "1.0s");
+        assert_eq!(format_duration(Duration::from_millis(59_990)), "59.9s");
+        assert_eq!(format_duration(Duration::from_secs(102)), "1m 42s");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1h 0m 0s");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 2 * 60 + 5)), "3h 2m 5s");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(1714572120, TimeDisplay::Utc), "2024-05-01 14:02 UTC");
+        assert_eq!(format_timestamp(0, TimeDisplay::Utc), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(1714572120, TimeDisplay::Local).len(), "2024-05-01 14:02".len());
+        assert_eq!("utc".parse::<TimeDisplay>().unwrap(), TimeDisplay::Utc);
+        assert!("gmt".parse::<TimeDisplay>().is_err());
+    }
+
+    #[test]
+    fn test_format_relative_buckets() {
+        let now = 1_700_000_000;
+        assert_eq!(format_relative(now + 30, now), "刚刚");
+        assert_eq!(format_relative(now - 59, now), "刚刚");
+        assert_eq!(format_relative(now - 60, now), "1 分钟前");
+        assert_eq!(format_relative(now - 3599, now), "59 分钟前");
+        assert_eq!(format_relative(now - 3600, now), "1 小时前");
+        assert_eq!(format_relative(now - 86_399, now), "23 小时前");
+        assert_eq!(format_relative(now - 3 * 86_400, now), "3 天前");
+        assert_eq!(format_relative(now - 30 * 86_400, now), "1 个月前");
+        assert_eq!(format_relative(now - 365 * 86_400, now), "1 年前");
+        assert_eq!(
+            format_time_with_relative(now - 120, now, TimeDisplay::Utc),
+            "2023-11-14 22:11 UTC（2 分钟前）"
+        );
+    }
+}
@@ -0,0 +1,648 @@
+//! HTTP 获取接口
+//!
+//! 抓取器、图片过滤器、版本检查和请求器都通过 [`HttpFetch`] 发送 GET 请求，
+//! 默认使用基于 reqwest 的 [`ReqwestFetch`]。测试中可以换成 [`MockFetch`]：
+//! 按 URL 预先设定响应、延迟或请求失败，并在之后检查发出的请求序列，
+//! 不需要启动测试服务器。
+//!
+//! 访问使用私有 CA 的内部文档网站时，通过 [`TlsOptions`] 添加信任的 CA 证书，
+//! 或者（仅限命令行显式指定 `--insecure` 时）关闭证书校验。
+
+use crate::core::error::{Error, Result};
+use crate::core::instrumentable::instrument;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+lazy_static! {
+    /// 同步代码（如过滤器）发送请求时使用的运行时
+    static ref BLOCKING_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("http-fetch")
+        .enable_all()
+        .build()
+        .expect("无法创建 HTTP 运行时");
+}
+
+/// 关闭证书校验时发布的事件，附带 `message`
+pub const TLS_INSECURE_EVENT: &str = "warn.tls_insecure";
+
+/// 关闭证书校验时的警告
+pub const INSECURE_WARNING: &str =
+    "警告: 已关闭 TLS 证书校验（--insecure），连接可能被窃听或篡改，只应在可信的内部网络中使用";
+
+/// TLS 设置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsOptions {
+    /// 额外信任的 CA 证书（PEM 文件，可以包含多个证书）
+    pub ca_certs: Vec<PathBuf>,
+    /// 是否接受无效的证书，只应由命令行的 `--insecure` 开启
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    /// 使用系统默认的证书校验
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加信任的 CA 证书
+    pub fn with_ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certs.push(path.into());
+        self
+    }
+
+    /// 设置是否接受无效的证书
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// 合并另一组设置：CA 证书取并集，任一方接受无效证书时接受
+    pub fn merge(&mut self, other: &TlsOptions) {
+        for path in &other.ca_certs {
+            if !self.ca_certs.contains(path) {
+                self.ca_certs.push(path.clone());
+            }
+        }
+        self.accept_invalid_certs |= other.accept_invalid_certs;
+    }
+
+    /// 读取所有 CA 证书
+    pub fn certificates(&self) -> Result<Vec<reqwest::Certificate>> {
+        let mut certificates = Vec::new();
+        for path in &self.ca_certs {
+            let pem = std::fs::read(path)
+                .map_err(|e| Error::Message(format!("无法读取 CA 证书 {:?}: {}", path, e)))?;
+            let bundle = reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| Error::Message(format!("无效的 CA 证书 {:?}: {}", path, e)))?;
+            if bundle.is_empty() {
+                return Err(Error::Message(format!("CA 证书文件中没有证书: {:?}", path)));
+            }
+            certificates.extend(bundle);
+        }
+        Ok(certificates)
+    }
+
+    /// 关闭证书校验时发布 [`TLS_INSECURE_EVENT`] 事件，命令行总是输出这个警告
+    pub fn warn_if_insecure(&self) {
+        if self.accept_invalid_certs {
+            let payload = HashMap::from([("message".to_string(), INSECURE_WARNING.to_string())]);
+            instrument(TLS_INSECURE_EVENT, payload, || ());
+        }
+    }
+
+    /// 将设置应用到 reqwest 客户端构建器
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        for certificate in self.certificates()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+        self.warn_if_insecure();
+        Ok(builder.danger_accept_invalid_certs(self.accept_invalid_certs))
+    }
+
+    /// 按设置创建 reqwest 客户端
+    pub fn client(&self) -> Result<reqwest::Client> {
+        self.apply(reqwest::Client::builder())?.build().map_err(Error::Http)
+    }
+}
+
+/// 获取到的响应
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FetchedResponse {
+    /// 最终的 URL（跟随重定向之后）
+    pub url: String,
+    /// 状态码
+    pub status: u16,
+    /// 头部信息，名称为小写
+    pub headers: HashMap<String, String>,
+    /// 响应体
+    pub body: Vec<u8>,
+}
+
+impl FetchedResponse {
+    /// 创建指定状态码的空响应
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            ..Self::default()
+        }
+    }
+
+    /// 创建状态码为 200 的 HTML 响应
+    pub fn html(body: &str) -> Self {
+        Self::new(200)
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body(body)
+    }
+
+    /// 设置最终的 URL
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// 设置头部
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_ascii_lowercase(), value.to_string());
+        self
+    }
+
+    /// 设置响应体
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// 状态码是否为 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 获取头部，名称不区分大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// 内容类型
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// `Content-Length` 头部的值
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("content-length").and_then(|value| value.trim().parse().ok())
+    }
+
+    /// `Content-Type` 头部中的字符集，没有时为 None
+    pub fn charset(&self) -> Option<&str> {
+        self.content_type()?.split(';').skip(1).find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim()
+                .eq_ignore_ascii_case("charset")
+                .then(|| value.trim().trim_matches('"'))
+        })
+    }
+
+    /// 响应体文本
+    ///
+    /// 与 reqwest 相同：按 `Content-Type` 中的字符集解码，没有或无法识别时按
+    /// UTF-8 解码（BOM 优先），无效的字节替换为替换字符。
+    pub fn text(&self) -> String {
+        let encoding = self
+            .charset()
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+            .unwrap_or(encoding_rs::UTF_8);
+        let (text, _, _) = encoding.decode(&self.body);
+        text.into_owned()
+    }
+}
+
+/// 发送 HTTP 请求的接口
+#[async_trait]
+pub trait HttpFetch: Send + Sync {
+    /// 发送 GET 请求
+    async fn get(&self, url: &str, headers: &HashMap<String, String>) -> Result<FetchedResponse>;
+
+    /// 发送 HEAD 请求，默认发送 GET 请求并丢弃响应体
+    async fn head(&self, url: &str, headers: &HashMap<String, String>) -> Result<FetchedResponse> {
+        let mut response = self.get(url, headers).await?;
+        response.body.clear();
+        Ok(response)
+    }
+
+    /// 发送 GET 请求，响应体超过 `max_bytes` 字节时返回错误
+    ///
+    /// 默认读取完整的响应后再检查，[`ReqwestFetch`] 在读取过程中检查，超过限制时
+    /// 立即停止读取。
+    async fn get_limited(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        max_bytes: usize,
+    ) -> Result<FetchedResponse> {
+        let response = self.get(url, headers).await?;
+        check_body_size(&response, max_bytes)?;
+        Ok(response)
+    }
+}
+
+/// 检查 `Content-Length` 和已读取的响应体是否超过 `max_bytes` 字节
+fn check_body_size(response: &FetchedResponse, max_bytes: usize) -> Result<()> {
+    let declared = response.content_length().unwrap_or(0);
+    if declared > max_bytes as u64 || response.body.len() > max_bytes {
+        return Err(Error::Message(format!("响应超过 {} 字节的大小限制", max_bytes)));
+    }
+    Ok(())
+}
+
+/// 在同步代码中发送 GET 请求
+///
+/// 请求在单独的运行时中执行，调用方是否处于异步运行时之中都可以使用。
+pub fn fetch_blocking(
+    fetch: &Arc<dyn HttpFetch>,
+    url: &str,
+    headers: &HashMap<String, String>,
+) -> Result<FetchedResponse> {
+    let fetch = fetch.clone();
+    let url = url.to_string();
+    let headers = headers.clone();
+    let task = BLOCKING_RUNTIME.spawn(async move { fetch.get(&url, &headers).await });
+    futures::executor::block_on(task).map_err(|e| Error::Message(format!("请求任务失败: {}", e)))?
+}
+
+/// 基于 reqwest 的实现
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestFetch {
+    client: reqwest::Client,
+}
+
+impl ReqwestFetch {
+    /// 使用默认设置的客户端
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定的客户端
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// 使用指定请求超时时间的客户端
+    pub fn with_timeout(timeout: Duration) -> Result<Self> {
+        Self::with_options(timeout, &TlsOptions::default())
+    }
+
+    /// 使用指定请求超时时间和 TLS 设置的客户端
+    pub fn with_options(timeout: Duration, tls: &TlsOptions) -> Result<Self> {
+        let builder = tls.apply(reqwest::Client::builder().timeout(timeout))?;
+        Ok(Self::with_client(builder.build().map_err(Error::Http)?))
+    }
+
+    /// 发送请求，`max_bytes` 不为 None 时逐块读取响应体，超过限制时停止读取
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        headers: &HashMap<String, String>,
+        max_bytes: Option<usize>,
+    ) -> Result<FetchedResponse> {
+        let request = headers
+            .iter()
+            .fold(request, |request, (name, value)| request.header(name.as_str(), value.as_str()));
+        let mut response = request.send().await.map_err(Error::Http)?;
+
+        let mut fetched = FetchedResponse::new(response.status().as_u16()).with_url(response.url().as_str());
+        for (name, value) in response.headers() {
+            if let Ok(value) = value.to_str() {
+                fetched = fetched.with_header(name.as_str(), value);
+            }
+        }
+        let Some(max_bytes) = max_bytes else {
+            fetched.body = response.bytes().await.map_err(Error::Http)?.to_vec();
+            return Ok(fetched);
+        };
+        check_body_size(&fetched, max_bytes)?;
+        while let Some(chunk) = response.chunk().await.map_err(Error::Http)? {
+            fetched.body.extend_from_slice(&chunk);
+            check_body_size(&fetched, max_bytes)?;
+        }
+        Ok(fetched)
+    }
+}
+
+#[async_trait]
+impl HttpFetch for ReqwestFetch {
+    async fn get(&self, url: &str, headers: &HashMap<String, String>) -> Result<FetchedResponse> {
+        self.send(self.client.get(url), headers, None).await
+    }
+
+    async fn head(&self, url: &str, headers: &HashMap<String, String>) -> Result<FetchedResponse> {
+        self.send(self.client.head(url), headers, None).await
+    }
+
+    async fn get_limited(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        max_bytes: usize,
+    ) -> Result<FetchedResponse> {
+        self.send(self.client.get(url), headers, Some(max_bytes)).await
+    }
+}
+
+/// 已发出的请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchRequest {
+    /// 请求方法（`GET` 或 `HEAD`）
+    pub method: String,
+    /// 请求的 URL
+    pub url: String,
+    /// 请求头部
+    pub headers: HashMap<String, String>,
+    /// 限制读取的响应体大小（[`HttpFetch::get_limited`]），没有限制时为 None
+    pub max_bytes: Option<usize>,
+}
+
+/// 预先设定的回复
+#[derive(Debug, Clone)]
+pub struct MockReply {
+    /// 响应，None 表示请求失败
+    response: Option<FetchedResponse>,
+    /// 请求失败时的错误信息
+    error: String,
+    /// 回复前的延迟
+    delay: Option<Duration>,
+}
+
+impl MockReply {
+    /// 返回响应
+    pub fn respond(response: FetchedResponse) -> Self {
+        Self {
+            response: Some(response),
+            error: String::new(),
+            delay: None,
+        }
+    }
+
+    /// 请求失败（模拟网络错误或超时）
+    pub fn fail(message: &str) -> Self {
+        Self {
+            response: None,
+            error: message.to_string(),
+            delay: None,
+        }
+    }
+
+    /// 延迟一段时间后再回复
+    pub fn after(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// 测试用的 HTTP 获取实现
+///
+/// 每个 URL 的回复按设定的顺序依次使用，用到最后一个之后一直重复最后一个；
+/// 没有设定回复的 URL 返回 404。
+#[derive(Debug, Default)]
+pub struct MockFetch {
+    replies: Mutex<HashMap<String, VecDeque<MockReply>>>,
+    requests: Mutex<Vec<FetchRequest>>,
+}
+
+impl MockFetch {
+    /// 创建没有任何回复的实现
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为 URL 追加一个回复
+    pub fn with_reply(self, url: &str, reply: MockReply) -> Self {
+        self.replies
+            .lock()
+            .unwrap()
+            .entry(url.to_string())
+            .or_default()
+            .push_back(reply);
+        self
+    }
+
+    /// 为 URL 追加一个响应
+    pub fn with_response(self, url: &str, response: FetchedResponse) -> Self {
+        self.with_reply(url, MockReply::respond(response))
+    }
+
+    /// 为 URL 追加一次请求失败
+    pub fn with_failure(self, url: &str, message: &str) -> Self {
+        self.with_reply(url, MockReply::fail(message))
+    }
+
+    /// 已发出的请求，按发出的顺序排列
+    pub fn requests(&self) -> Vec<FetchRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 已请求的 URL，按请求的顺序排列
+    pub fn requested_urls(&self) -> Vec<String> {
+        self.requests().into_iter().map(|request| request.url).collect()
+    }
+
+    /// URL 被请求的次数
+    pub fn request_count(&self, url: &str) -> usize {
+        self.requests.lock().unwrap().iter().filter(|request| request.url == url).count()
+    }
+
+    async fn reply(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &HashMap<String, String>,
+        max_bytes: Option<usize>,
+    ) -> Result<FetchedResponse> {
+        self.requests.lock().unwrap().push(FetchRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers.clone(),
+            max_bytes,
+        });
+
+        let reply = {
+            let mut replies = self.replies.lock().unwrap();
+            match replies.get_mut(url) {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        };
+        let Some(reply) = reply else {
+            return Ok(FetchedResponse::new(404).with_url(url));
+        };
+        if let Some(delay) = reply.delay {
+            tokio::time::sleep(delay).await;
+        }
+        match reply.response {
+            Some(response) if response.url.is_empty() => Ok(response.with_url(url)),
+            Some(response) => Ok(response),
+            None => Err(Error::Message(format!("请求失败: {}", reply.error))),
+        }
+    }
+}
+
+#[async_trait]
+impl HttpFetch for MockFetch {
+    async fn get(&self, url: &str, headers: &HashMap<String, String>) -> Result<FetchedResponse> {
+        self.reply("GET", url, headers, None).await
+    }
+
+    async fn head(&self, url: &str, headers: &HashMap<String, String>) -> Result<FetchedResponse> {
+        let mut response = self.reply("HEAD", url, headers, None).await?;
+        response.body.clear();
+        Ok(response)
+    }
+
+    async fn get_limited(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        max_bytes: usize,
+    ) -> Result<FetchedResponse> {
+        let response = self.reply("GET", url, headers, Some(max_bytes)).await?;
+        check_body_size(&response, max_bytes)?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_replies_in_order() {
+        let mock = MockFetch::new()
+            .with_response("https://e.com/a", FetchedResponse::new(429).with_header("Retry-After", "1"))
+            .with_failure("https://e.com/a", "connection reset")
+            .with_response("https://e.com/a", FetchedResponse::html("<p>A</p>"));
+        let headers = HashMap::from([("User-Agent".to_string(), "test".to_string())]);
+
+        let first = mock.get("https://e.com/a", &headers).await.unwrap();
+        assert_eq!(first.status, 429);
+        assert_eq!(first.header("retry-after"), Some("1"));
+        assert!(mock.get("https://e.com/a", &headers).await.is_err());
+        // 最后一个回复一直重复
+        for _ in 0..2 {
+            let response = mock.get("https://e.com/a", &headers).await.unwrap();
+            assert_eq!(response.text(), "<p>A</p>");
+            assert_eq!(response.url, "https://e.com/a");
+        }
+        assert_eq!(mock.get("https://e.com/missing", &headers).await.unwrap().status, 404);
+        assert!(mock.head("https://e.com/a", &headers).await.unwrap().body.is_empty());
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 6);
+        assert_eq!(requests[0].headers["User-Agent"], "test");
+        assert_eq!(requests[5].method, "HEAD");
+        assert_eq!(mock.request_count("https://e.com/a"), 5);
+    }
+
+    #[tokio::test]
+    async fn test_limited_read_stops_at_max_bytes() {
+        let mut server = mockito::Server::new_async().await;
+        let body = vec![b'x'; 64 * 1024];
+        let _large = server.mock("GET", "/large").with_body(&body).create_async().await;
+        let _small = server.mock("GET", "/small").with_body("ok").create_async().await;
+        let fetch = ReqwestFetch::new();
+        let headers = HashMap::new();
+
+        let error = fetch.get_limited(&format!("{}/large", server.url()), &headers, 1024).await.unwrap_err();
+        assert!(error.to_string().contains("1024 字节"), "{}", error);
+        let response = fetch.get_limited(&format!("{}/small", server.url()), &headers, 1024).await.unwrap();
+        assert_eq!(response.text(), "ok");
+        assert_eq!(fetch.get(&format!("{}/large", server.url()), &headers).await.unwrap().body, body);
+    }
+
+    #[test]
+    fn test_text_is_decoded_with_charset() {
+        let fixture = |name: &str| std::fs::read(format!("test_docs/encoding/{}", name)).unwrap();
+
+        let response = FetchedResponse::new(200)
+            .with_header("Content-Type", "text/html; charset=Shift_JIS")
+            .with_body(fixture("shift_jis.html"));
+        assert_eq!(response.charset(), Some("Shift_JIS"));
+        assert_eq!(response.text(), "<p>配列 - Array.prototype.map()</p>\n");
+
+        let response = FetchedResponse::new(200)
+            .with_header("Content-Type", "text/html;charset=\"windows-1252\"")
+            .with_body(fixture("windows-1252.html"));
+        assert_eq!(response.text(), "<p>Café “naïve” résumé</p>\n");
+
+        // 没有字符集或无法识别时按 UTF-8 解码
+        let response = FetchedResponse::new(200)
+            .with_header("Content-Type", "text/html; charset=unknown")
+            .with_body("<p>Café</p>");
+        assert_eq!(response.text(), "<p>Café</p>");
+        assert_eq!(FetchedResponse::new(200).with_body(vec![b'a', 0xff]).text(), "a\u{fffd}");
+    }
+
+    #[test]
+    fn test_fetch_blocking_with_delay() {
+        let mock = MockFetch::new().with_reply(
+            "https://e.com/slow",
+            MockReply::respond(FetchedResponse::new(200)).after(Duration::from_millis(20)),
+        );
+        let fetch: Arc<dyn HttpFetch> = Arc::new(mock);
+
+        let started = std::time::Instant::now();
+        let response = fetch_blocking(&fetch, "https://e.com/slow", &HashMap::new()).unwrap();
+        assert!(response.is_success());
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    const TLS_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_docs/tls");
+
+    /// 使用测试 CA 签发的证书的 HTTPS 服务器，返回根地址
+    async fn tls_fixture_server() -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let cert = std::fs::read(format!("{}/server.pem", TLS_FIXTURES)).unwrap();
+        let key = std::fs::read(format!("{}/server.key", TLS_FIXTURES)).unwrap();
+        let identity = native_tls::Identity::from_pkcs8(&cert, &key).unwrap();
+        let acceptor = tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // 客户端拒绝证书时握手失败，忽略即可
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut buffer = [0; 4096];
+                    let _ = stream.read(&mut buffer).await;
+                    let body = "<p>internal</p>";
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        format!("https://localhost:{}/", port)
+    }
+
+    #[tokio::test]
+    async fn test_tls_options_with_private_ca() {
+        let url = tls_fixture_server().await;
+        let headers = HashMap::new();
+        let timeout = Duration::from_secs(5);
+
+        // 默认只信任系统证书
+        let fetch = ReqwestFetch::with_timeout(timeout).unwrap();
+        assert!(fetch.get(&url, &headers).await.is_err());
+
+        let tls = TlsOptions::new().with_ca_cert(format!("{}/ca.pem", TLS_FIXTURES));
+        let fetch = ReqwestFetch::with_options(timeout, &tls).unwrap();
+        assert_eq!(fetch.get(&url, &headers).await.unwrap().text(), "<p>internal</p>");
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let warnings_clone = warnings.clone();
+        crate::core::instrumentable::subscribe(TLS_INSECURE_EVENT, move |info| {
+            warnings_clone.lock().unwrap().push(info.payload["message"].clone());
+        });
+        let tls = TlsOptions::new().with_accept_invalid_certs(true);
+        let fetch = ReqwestFetch::with_options(timeout, &tls).unwrap();
+        assert!(fetch.get(&url, &headers).await.unwrap().is_success());
+        assert!(warnings.lock().unwrap().iter().any(|message| message == INSECURE_WARNING));
+
+        // 证书文件无效时在创建客户端时报错
+        let tls = TlsOptions::new().with_ca_cert(format!("{}/server.key", TLS_FIXTURES));
+        assert!(tls.client().is_err());
+    }
+}
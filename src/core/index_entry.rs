@@ -1,4 +1,7 @@
+use crate::core::doc::sort_entries;
+use crate::core::filters::default_entries::OTHER_TYPE;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
 pub struct IndexEntry {
@@ -6,6 +9,26 @@ pub struct IndexEntry {
     pub path: String,
     #[serde(rename = "type")]
     pub entry_type: String,
+    /// 目标页面的字数（仅在启用统计时输出）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_count: Option<usize>,
+    /// 条目描述的属性是否继承（目前只有 CSS 属性页面会设置）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherited: Option<bool>,
+}
+
+impl IndexEntry {
+    /// 以完整的 JSON 为去重键，所有字段都相同才算重复（[`EntryIndex`] 的默认键）
+    ///
+    /// [`EntryIndex`]: crate::core::doc::EntryIndex
+    pub fn json_key(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// 以名称和路径为去重键，只有类型或附加信息不同的条目算作重复
+    pub fn name_path_key(&self) -> String {
+        format!("{}\0{}", self.name, self.path)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -13,6 +36,35 @@ pub struct IndexType {
     pub name: String,
     pub count: usize,
     pub slug: String,
+    /// 类型列表中的排序权重，越小越靠前，相同时按名称排序
+    #[serde(default, skip_serializing_if = "is_default_weight")]
+    pub weight: i32,
+    /// 类型说明，在类型列表中作为副标题显示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// 抓取器提供的类型元数据
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TypeMeta {
+    /// 排序权重，未指定的类型为 0
+    pub weight: i32,
+    /// 类型说明
+    pub description: Option<String>,
+}
+
+impl TypeMeta {
+    /// 创建类型元数据
+    pub fn new(weight: i32, description: Option<&str>) -> Self {
+        Self {
+            weight,
+            description: description.map(str::to_string),
+        }
+    }
+}
+
+fn is_default_weight(weight: &i32) -> bool {
+    *weight == 0
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -20,3 +72,95 @@ pub struct FullIndex {
     pub entries: Vec<IndexEntry>,
     pub types: Vec<IndexType>,
 }
+
+/// 写入 entries.json 和 index.json 之前规范化条目
+///
+/// 多个过滤器（默认条目、附加条目）可能为同一页面生成只差空白或末尾斜杠的条目。
+/// 这里去掉名称、路径和类型首尾的空白，以及路径中页面部分末尾的斜杠；名称（不区分
+/// 大小写）和路径相同的条目只保留一个，名称使用先出现的写法，类型取更具体的（见
+/// [`type_specificity`]），同样具体时保留先出现的；最后按名称（[`sort_entries`]）、
+/// 路径和类型排序。
+pub fn normalize_entries(entries: &mut Vec<(String, String, String)>) {
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+    let mut normalized: Vec<(String, String, String)> = Vec::with_capacity(entries.len());
+    for (name, path, entry_type) in entries.drain(..) {
+        let name = name.trim().to_string();
+        let path = normalize_entry_path(&path);
+        let entry_type = entry_type.trim().to_string();
+        let key = (name.to_lowercase(), path.clone());
+        match positions.get(&key) {
+            Some(&i) => {
+                if type_specificity(&entry_type) > type_specificity(&normalized[i].2) {
+                    normalized[i].2 = entry_type;
+                }
+            }
+            None => {
+                positions.insert(key, normalized.len());
+                normalized.push((name, path, entry_type));
+            }
+        }
+    }
+    normalized.sort_by(|a, b| {
+        sort_entries(&a.0, &b.0)
+            .then_with(|| a.1.cmp(&b.1))
+            .then_with(|| a.2.cmp(&b.2))
+    });
+    *entries = normalized;
+}
+
+/// 去掉条目路径首尾的空白和页面部分末尾的斜杠，锚点保持不变
+fn normalize_entry_path(path: &str) -> String {
+    let path = path.trim();
+    match path.split_once('#') {
+        Some((page, fragment)) => format!("{}#{}", page.trim_end_matches('/'), fragment),
+        None => path.trim_end_matches('/').to_string(),
+    }
+}
+
+/// 类型的具体程度：空类型最低，其次是由推断类型合并成的 "Other"，其余类型相同
+fn type_specificity(entry_type: &str) -> u8 {
+    match entry_type {
+        "" => 0,
+        OTHER_TYPE => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, path: &str, entry_type: &str) -> (String, String, String) {
+        (name.to_string(), path.to_string(), entry_type.to_string())
+    }
+
+    #[test]
+    fn test_normalize_entries() {
+        let mut entries = vec![
+            entry("Map", "api/map/", "Other"),
+            entry(" Array ", "api/array", "API"),
+            entry("Map", "api/map", "API"),
+            entry("Map", " api/map/#size ", "API"),
+            entry("Map", "api/map#size", ""),
+            entry("array", "api/array", "API"),
+        ];
+        normalize_entries(&mut entries);
+
+        // 只差末尾斜杠或名称大小写的条目合并，名称保留先出现的写法，类型取更具体的
+        assert_eq!(
+            entries,
+            [
+                entry("Array", "api/array", "API"),
+                entry("Map", "api/map", "API"),
+                entry("Map", "api/map#size", "API"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_keeps_first_of_equally_specific_types() {
+        let mut entries = vec![entry("Guide", "guide", "Guides"), entry("Guide", "guide/", "Tutorials")];
+        normalize_entries(&mut entries);
+        assert_eq!(entries, [entry("Guide", "guide", "Guides")]);
+    }
+}
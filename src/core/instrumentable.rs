@@ -21,6 +21,9 @@ pub struct InstrumentInfo {
     pub payload: HashMap<String, String>,
 }
 
+/// 消息事件中给用户看的文字在有效负载中的键
+pub const MESSAGE_KEY: &str = "message";
+
 /// 事件监听回调函数类型
 type NotificationCallback = Box<dyn Fn(&InstrumentInfo) -> () + Send + Sync>;
 
@@ -88,13 +91,6 @@ where
 {
     let start_time = Instant::now();
 
-    let info = InstrumentInfo {
-        name: name.to_string(),
-        start_time,
-        duration: None,
-        payload,
-    };
-
     let result = action();
 
     let duration = start_time.elapsed();
@@ -110,6 +106,25 @@ where
     result
 }
 
+/// 发布一个不需要计时的事件
+pub fn notify(name: &str, payload: HashMap<String, String>) {
+    let info = InstrumentInfo {
+        name: name.to_string(),
+        start_time: Instant::now(),
+        duration: None,
+        payload,
+    };
+    NOTIFICATION_CENTER.publish(&info);
+}
+
+/// 发布一条给用户看的消息，文字放在有效负载的 `message` 中
+///
+/// 没有订阅者时不输出任何内容，命令行通过
+/// [`ConsoleSubscriber`](crate::core::subscriber::ConsoleSubscriber) 显示这些消息。
+pub fn message<S: Into<String>>(name: &str, message: S) {
+    notify(name, HashMap::from([(MESSAGE_KEY.to_string(), message.into())]));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,4 +159,17 @@ mod tests {
         assert_eq!(result, 42);
         assert_eq!(*counter.lock().unwrap(), 1);
     }
+
+    #[test]
+    fn test_message_event() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let received = messages.clone();
+        subscribe("test_message.scraper", move |info| {
+            assert!(info.duration.is_none());
+            received.lock().unwrap().push(info.payload[MESSAGE_KEY].clone());
+        });
+
+        message("test_message.scraper", format!("爬取: {}", "https://example.com/"));
+        assert_eq!(*messages.lock().unwrap(), ["爬取: https://example.com/"]);
+    }
 }
@@ -0,0 +1,458 @@
+//! 把存储的 HTML 页面转换为 Markdown
+//!
+//! 转换直接遍历解析后的 DOM：标题、段落、（嵌套）列表、表格（转为管道表格）、
+//! 代码块（带 `data-language` 的围栏代码块）、行内代码、链接和图片有对应的
+//! Markdown 写法，链接按页面的源 URL 解析为绝对地址。其余不支持的元素退化为
+//! 它们的文本内容，不会报错。
+
+use scraper::node::Node;
+use scraper::{ElementRef, Html};
+use url::Url;
+
+/// 不输出任何内容的元素
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "title", "meta", "link", "script", "style", "noscript", "template", "iframe", "svg",
+    "button", "input", "select", "textarea",
+];
+
+/// 块级元素，它们之前的行内内容单独成段
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "caption", "dd", "details", "dialog",
+    "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3",
+    "h4", "h5", "h6", "header", "hgroup", "hr", "html", "li", "main", "nav", "ol", "p", "pre",
+    "section", "summary", "table", "tbody", "td", "tfoot", "th", "thead", "tr", "ul",
+];
+
+/// 转换出的一个块
+struct Block {
+    /// Markdown 文本，可能有多行
+    text: String,
+    /// 是否为列表，列表项里的子列表紧跟在文本后面
+    list: bool,
+}
+
+impl Block {
+    fn new(text: String) -> Self {
+        Self { text, list: false }
+    }
+}
+
+/// HTML 到 Markdown 的转换器
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownConverter {
+    /// 解析相对链接的基础 URL
+    base_url: Option<Url>,
+}
+
+impl MarkdownConverter {
+    /// 创建新的转换器，链接保持原样
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按页面的源 URL 把相对链接解析为绝对地址，无法解析的 URL 被忽略
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Url::parse(base_url).ok();
+        self
+    }
+
+    /// 转换 HTML 片段或完整文档
+    pub fn convert(&self, html: &str) -> String {
+        let document = Html::parse_fragment(html);
+        let mut blocks = Vec::new();
+        self.blocks(document.root_element(), &mut blocks);
+        let markdown = join_blocks(&blocks, false);
+        if markdown.is_empty() {
+            markdown
+        } else {
+            markdown + "\n"
+        }
+    }
+
+    /// 把元素的子节点转换为块，连续的行内内容合并为一个段落
+    fn blocks(&self, element: ElementRef, out: &mut Vec<Block>) {
+        let mut inline = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(&collapse_whitespace(text)),
+                Node::Element(value) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    let name = value.name();
+                    if SKIPPED_ELEMENTS.contains(&name) {
+                        continue;
+                    }
+                    if BLOCK_ELEMENTS.contains(&name) {
+                        flush_paragraph(&mut inline, out);
+                        self.block(child, out);
+                    } else {
+                        inline.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        flush_paragraph(&mut inline, out);
+    }
+
+    /// 转换一个块级元素
+    fn block(&self, element: ElementRef, out: &mut Vec<Block>) {
+        let name = element.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = self.inline_text(element);
+                if !text.is_empty() {
+                    out.push(Block::new(format!("{} {}", "#".repeat(level), text)));
+                }
+            }
+            "p" => {
+                let text = self.inline_text(element);
+                if !text.is_empty() {
+                    out.push(Block::new(text));
+                }
+            }
+            "pre" => out.push(Block::new(code_block(element))),
+            "ul" | "ol" => {
+                let text = self.list(element);
+                if !text.is_empty() {
+                    out.push(Block { text, list: true });
+                }
+            }
+            "table" => {
+                if let Some(text) = self.table(element) {
+                    out.push(Block::new(text));
+                }
+            }
+            "blockquote" => {
+                let mut inner = Vec::new();
+                self.blocks(element, &mut inner);
+                let quoted = join_blocks(&inner, false)
+                    .lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if !quoted.is_empty() {
+                    out.push(Block::new(quoted));
+                }
+            }
+            "hr" => out.push(Block::new("---".to_string())),
+            // 其余块级容器只保留内容
+            _ => self.blocks(element, out),
+        }
+    }
+
+    /// 转换列表，嵌套列表按列表标记的宽度缩进
+    fn list(&self, list: ElementRef) -> String {
+        let ordered = list.value().name() == "ol";
+        let start = list
+            .value()
+            .attr("start")
+            .and_then(|start| start.trim().parse::<usize>().ok())
+            .unwrap_or(1);
+
+        let items = list
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|item| item.value().name() == "li")
+            .enumerate()
+            .map(|(index, item)| {
+                let marker = if ordered {
+                    format!("{}. ", start + index)
+                } else {
+                    "- ".to_string()
+                };
+                let mut blocks = Vec::new();
+                self.blocks(item, &mut blocks);
+                let content = join_blocks(&blocks, true);
+                let indent = " ".repeat(marker.len());
+
+                let mut lines = content.lines();
+                let mut text = format!("{}{}", marker, lines.next().unwrap_or_default())
+                    .trim_end()
+                    .to_string();
+                for line in lines {
+                    text.push('\n');
+                    if !line.is_empty() {
+                        text.push_str(&indent);
+                        text.push_str(line);
+                    }
+                }
+                text
+            })
+            .collect::<Vec<_>>();
+        items.join("\n")
+    }
+
+    /// 转换为管道表格，没有表头时第一行作为表头
+    fn table(&self, table: ElementRef) -> Option<String> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for child in table.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "thead" | "tbody" | "tfoot" => {
+                    for row in child.children().filter_map(ElementRef::wrap) {
+                        if row.value().name() == "tr" {
+                            rows.push(self.table_row(row));
+                        }
+                    }
+                }
+                "tr" => rows.push(self.table_row(child)),
+                _ => {}
+            }
+        }
+        rows.retain(|row| !row.is_empty());
+
+        let columns = rows.iter().map(Vec::len).max()?;
+        let mut lines = Vec::with_capacity(rows.len() + 1);
+        for (index, row) in rows.iter().enumerate() {
+            let cells = (0..columns)
+                .map(|column| row.get(column).map(String::as_str).unwrap_or_default())
+                .collect::<Vec<_>>();
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// 表格的一行，单元格里的块合并为一行
+    fn table_row(&self, row: ElementRef) -> Vec<String> {
+        row.children()
+            .filter_map(ElementRef::wrap)
+            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+            .map(|cell| {
+                let mut blocks = Vec::new();
+                self.blocks(cell, &mut blocks);
+                blocks
+                    .iter()
+                    .map(|block| block.text.replace("\\\n", " ").replace('\n', " "))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .replace('|', "\\|")
+            })
+            .collect()
+    }
+
+    /// 元素的行内内容，块级子元素也按行内处理
+    fn inline_text(&self, element: ElementRef) -> String {
+        normalize_inline(&self.inline_children(element))
+    }
+
+    /// 子节点的行内 Markdown
+    fn inline_children(&self, element: ElementRef) -> String {
+        let mut out = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&collapse_whitespace(text)),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        out.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+
+    /// 转换一个行内元素
+    fn inline(&self, element: ElementRef) -> String {
+        let name = element.value().name();
+        if SKIPPED_ELEMENTS.contains(&name) {
+            return String::new();
+        }
+        match name {
+            "strong" | "b" => wrap_inline(&self.inline_children(element), "**"),
+            "em" | "i" => wrap_inline(&self.inline_children(element), "*"),
+            "del" | "s" => wrap_inline(&self.inline_children(element), "~~"),
+            "code" | "kbd" | "samp" | "tt" => {
+                inline_code(&collapse_whitespace(&element.text().collect::<String>()))
+            }
+            "br" => "\n".to_string(),
+            "a" => {
+                let text = self.inline_children(element);
+                match element.value().attr("href").map(str::trim) {
+                    Some(href) if !href.is_empty() && !href.starts_with("javascript:") => {
+                        if text.trim().is_empty() {
+                            return String::new();
+                        }
+                        format!("[{}]({})", text.trim(), self.resolve(href))
+                    }
+                    _ => text,
+                }
+            }
+            "img" => match element.value().attr("src") {
+                Some(src) if !src.trim().is_empty() => format!(
+                    "![{}]({})",
+                    element.value().attr("alt").unwrap_or_default(),
+                    self.resolve(src.trim())
+                ),
+                _ => String::new(),
+            },
+            // 不支持的元素退化为文本内容
+            _ => self.inline_children(element),
+        }
+    }
+
+    /// 按基础 URL 解析链接，没有基础 URL 或解析失败时保持原样
+    fn resolve(&self, href: &str) -> String {
+        self.base_url
+            .as_ref()
+            .and_then(|base| base.join(href).ok())
+            .map(|url| url.to_string())
+            .unwrap_or_else(|| href.to_string())
+    }
+}
+
+/// 把 HTML 转换为 Markdown，`base_url` 为页面的源 URL
+pub fn html_to_markdown(html: &str, base_url: Option<&str>) -> String {
+    let converter = MarkdownConverter::new();
+    match base_url {
+        Some(base_url) => converter.with_base_url(base_url).convert(html),
+        None => converter.convert(html),
+    }
+}
+
+/// 连接块，列表项里的子列表紧跟在前面的文本后面
+fn join_blocks(blocks: &[Block], tight: bool) -> String {
+    let mut out = String::new();
+    for (index, block) in blocks.iter().enumerate() {
+        if index > 0 {
+            out.push_str(if tight && block.list { "\n" } else { "\n\n" });
+        }
+        out.push_str(&block.text);
+    }
+    out
+}
+
+/// 把累积的行内内容作为一个段落输出
+fn flush_paragraph(inline: &mut String, out: &mut Vec<Block>) {
+    let text = normalize_inline(inline);
+    if !text.is_empty() {
+        out.push(Block::new(text));
+    }
+    inline.clear();
+}
+
+/// 合并行内内容中多余的空白，`<br>` 留下的换行转为硬换行
+fn normalize_inline(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.split(' ').filter(|word| !word.is_empty()).collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+/// 把连续空白合并为一个空格
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// 用强调标记包裹内容，首尾空白留在标记外
+fn wrap_inline(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = if text.starts_with(' ') { " " } else { "" };
+    let trailing = if text.ends_with(' ') { " " } else { "" };
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+/// 行内代码，内容包含反引号时使用更长的反引号序列
+fn inline_code(code: &str) -> String {
+    let code = code.trim();
+    if code.is_empty() {
+        return String::new();
+    }
+    let fence = "`".repeat(longest_backtick_run(code) + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+/// 围栏代码块，语言取自 `data-language` 或 `language-*` 类名
+fn code_block(pre: ElementRef) -> String {
+    let code = pre
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == "code");
+    let language = [Some(pre), code]
+        .into_iter()
+        .flatten()
+        .find_map(|element| {
+            element.value().attr("data-language").map(str::to_string).or_else(|| {
+                element.value().classes().find_map(|class| {
+                    class
+                        .strip_prefix("language-")
+                        .or_else(|| class.strip_prefix("lang-"))
+                        .map(str::to_string)
+                })
+            })
+        })
+        .unwrap_or_default();
+
+    let text = pre.text().collect::<String>();
+    let text = text.strip_prefix('\n').unwrap_or(&text).trim_end();
+    let fence = "`".repeat(longest_backtick_run(text).max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language.trim(), text, fence)
+}
+
+/// 最长的连续反引号数
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 与 `test_docs/markdown` 下的期望输出逐字比较
+    fn assert_golden(name: &str, base_url: Option<&str>) {
+        let html = std::fs::read_to_string(format!("test_docs/markdown/{}.html", name)).unwrap();
+        let expected = std::fs::read_to_string(format!("test_docs/markdown/{}.md", name)).unwrap();
+        assert_eq!(html_to_markdown(&html, base_url), expected, "{}", name);
+    }
+
+    #[test]
+    fn test_golden_reference_page() {
+        assert_golden("reference", Some("https://developer.mozilla.org/en-US/docs/Web/API/Dialog"));
+    }
+
+    #[test]
+    fn test_golden_guide_page() {
+        assert_golden("guide", Some("https://babeljs.io/docs/options"));
+    }
+
+    #[test]
+    fn test_nested_lists_and_inline_code() {
+        let html = "<ul><li>One <code>a`b</code><ol><li>Inner</li><li><p>Para</p></li></ol></li><li>Two</li></ul>";
+        assert_eq!(
+            html_to_markdown(html, None),
+            "- One ``a`b``\n  1. Inner\n  2. Para\n- Two\n"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_elements_degrade_to_text() {
+        let html = "<p>Press <custom-key>Ctrl</custom-key> <abbr title='x'>now</abbr><script>alert(1)</script></p><dl><dt>Term</dt><dd>Definition</dd></dl>";
+        assert_eq!(html_to_markdown(html, None), "Press Ctrl now\n\nTerm\n\nDefinition\n");
+    }
+}
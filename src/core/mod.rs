@@ -6,9 +6,13 @@ pub mod error;
 pub mod filter_registry;
 pub mod filter_stack;
 pub mod filters;
+pub mod format;
+pub mod hash;
+pub mod http_fetch;
 pub mod index_entry;
 pub mod instrumentable;
 pub mod manifest;
+pub mod markdown;
 pub mod page_db;
 pub mod parser;
 pub mod request;
@@ -18,3 +22,4 @@ pub mod scraper;
 pub mod subscriber;
 pub mod types;
 pub mod url;
+pub mod version_probe;
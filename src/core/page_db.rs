@@ -2,15 +2,111 @@
 //!
 //! 参考原始 Ruby 项目中的 page_db.rb 实现
 //! 提供页面内容的存储和检索功能
+//!
+//! db.json 有两种格式：
+//! - v1：`{"path": "<html>"}`，与 devdocs 相同，值为页面 HTML
+//! - v2：`{"__meta": {"format": 2}, "pages": {"path": {"content": "...", ...}}}`，
+//!   每个页面为一个对象，可附带标题、来源地址和目录
+//!
+//! 读取时自动识别格式，写入时使用数据库设置的格式。读取时文件中的路径
+//! 转为 [`PageKey`]，与抓取器和网页路由使用相同的规范化规则。
 
+use crate::core::error::{Error, Result};
+use crate::core::types::PageKey;
+use crate::storage::json;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+
+/// v2 格式中存放格式信息的键
+pub const META_KEY: &str = "__meta";
+
+/// db.json 格式版本
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum DbFormat {
+    /// 值为页面 HTML 字符串（devdocs 格式）
+    #[default]
+    V1,
+    /// 值为页面对象
+    V2,
+}
+
+impl DbFormat {
+    /// 获取格式版本号
+    pub fn version(self) -> u64 {
+        match self {
+            DbFormat::V1 => 1,
+            DbFormat::V2 => 2,
+        }
+    }
+
+    /// 根据版本号获取格式
+    pub fn from_version(version: u64) -> Option<Self> {
+        match version {
+            1 => Some(DbFormat::V1),
+            2 => Some(DbFormat::V2),
+            _ => None,
+        }
+    }
+}
+
+/// 数据库中保存的单个页面
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredPage {
+    /// 页面 HTML
+    pub content: String,
+    /// 页面标题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 页面来源地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// 页面目录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toc: Vec<serde_json::Value>,
+}
+
+impl StoredPage {
+    /// 创建只有内容的页面
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..Self::default()
+        }
+    }
+
+    /// 设置页面标题，空标题视为未设置
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = if title.is_empty() { None } else { Some(title.to_string()) };
+        self
+    }
+
+    /// 设置页面来源地址
+    pub fn with_source_url(mut self, url: &str) -> Self {
+        self.source_url = Some(url.to_string());
+        self
+    }
+
+    /// 设置页面目录
+    pub fn with_toc(mut self, toc: Vec<serde_json::Value>) -> Self {
+        self.toc = toc;
+        self
+    }
+}
+
+impl From<String> for StoredPage {
+    fn from(content: String) -> Self {
+        Self::new(content)
+    }
+}
 
 /// 页面数据库结构体
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug)]
 pub struct PageDb {
-    /// 页面映射，键为页面路径，值为页面内容
-    pages: HashMap<String, String>,
+    /// 页面映射，键为页面路径，值为页面
+    pages: HashMap<PageKey, StoredPage>,
+    /// 写入时使用的格式
+    format: DbFormat,
 }
 
 impl PageDb {
@@ -18,38 +114,139 @@ impl PageDb {
     pub fn new() -> Self {
         Self {
             pages: HashMap::new(),
+            format: DbFormat::default(),
         }
     }
 
+    /// 设置写入时使用的格式
+    pub fn with_format(mut self, format: DbFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// 获取写入时使用的格式（从文件读取时为文件原有格式）
+    pub fn format(&self) -> DbFormat {
+        self.format
+    }
+
     /// 添加页面到数据库
-    pub fn add(&mut self, path: String, content: String) {
-        self.pages.insert(path, content);
+    pub fn add(&mut self, path: PageKey, content: String) {
+        self.pages.insert(path, StoredPage::new(content));
+    }
+
+    /// 添加带元数据的页面到数据库
+    pub fn insert(&mut self, path: PageKey, page: StoredPage) {
+        self.pages.insert(path, page);
     }
 
     /// 从数据库中获取页面内容
-    pub fn get(&self, path: &str) -> Option<&String> {
+    pub fn get(&self, path: &PageKey) -> Option<&String> {
+        self.pages.get(path).map(|page| &page.content)
+    }
+
+    /// 从数据库中获取页面
+    pub fn page(&self, path: &PageKey) -> Option<&StoredPage> {
         self.pages.get(path)
     }
 
     /// 检查页面是否存在
-    pub fn has(&self, path: &str) -> bool {
+    pub fn has(&self, path: &PageKey) -> bool {
         self.pages.contains_key(path)
     }
 
-    /// 从 JSON 字符串加载页面数据库
+    /// 从 JSON 字符串加载页面数据库，自动识别 v1 和 v2 格式
     pub fn from_json(json: &str) -> serde_json::Result<Self> {
-        let pages: HashMap<String, String> = serde_json::from_str(json)?;
-        Ok(Self { pages })
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// 从 JSON 值加载页面数据库，自动识别 v1 和 v2 格式
+    pub fn from_value(mut value: serde_json::Value) -> serde_json::Result<Self> {
+        let version = match value.get(META_KEY) {
+            Some(meta) => meta.get("format").and_then(|f| f.as_u64()).unwrap_or(0),
+            None => 1,
+        };
+
+        match DbFormat::from_version(version) {
+            Some(DbFormat::V1) => {
+                let pages: HashMap<String, String> = serde_json::from_value(value)?;
+                Ok(Self {
+                    pages: pages
+                        .into_iter()
+                        .map(|(path, content)| (PageKey::new(&path), content.into()))
+                        .collect(),
+                    format: DbFormat::V1,
+                })
+            }
+            Some(DbFormat::V2) => {
+                let pages = value
+                    .get_mut("pages")
+                    .map(serde_json::Value::take)
+                    .unwrap_or_else(|| serde_json::json!({}));
+                let pages: HashMap<String, StoredPage> = serde_json::from_value(pages)?;
+                Ok(Self {
+                    pages: pages.into_iter().map(|(path, page)| (PageKey::new(&path), page)).collect(),
+                    format: DbFormat::V2,
+                })
+            }
+            None => Err(serde::de::Error::custom(format!(
+                "不支持的 db.json 格式版本: {}",
+                version
+            ))),
+        }
+    }
+
+    /// 按指定格式转换为 JSON 值
+    pub fn to_value(&self, format: DbFormat) -> serde_json::Value {
+        match format {
+            DbFormat::V1 => serde_json::json!(self
+                .pages
+                .iter()
+                .map(|(path, page)| (path.as_str(), &page.content))
+                .collect::<HashMap<_, _>>()),
+            DbFormat::V2 => serde_json::json!({
+                META_KEY: { "format": DbFormat::V2.version() },
+                "pages": self
+                    .pages
+                    .iter()
+                    .map(|(path, page)| (path.as_str(), page))
+                    .collect::<HashMap<_, _>>(),
+            }),
+        }
     }
 
     /// 将页面数据库转换为 JSON 字符串
     pub fn to_json(&self) -> serde_json::Result<String> {
-        serde_json::to_string(&self.pages)
+        serde_json::to_string(&self.to_value(self.format))
     }
 
     /// 将页面数据库转换为美化的 JSON 字符串
     pub fn to_json_pretty(&self) -> serde_json::Result<String> {
-        serde_json::to_string_pretty(&self.pages)
+        serde_json::to_string_pretty(&self.to_value(self.format))
+    }
+
+    /// 从 db.json 文件加载页面数据库，文件可以带有 UTF-8 BOM
+    pub fn load(path: &Path) -> Result<Self> {
+        let value = json::read_json(path)?;
+        Self::from_value(value)
+            .map_err(|e| Error::Doc(format!("无法读取 {:?}: {}", path, e)))
+    }
+
+    /// 将页面数据库写入 db.json 文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json_pretty()?)?;
+        Ok(())
+    }
+
+    /// 将 db.json 文件原地升级为 v2 格式
+    ///
+    /// 返回文件是否被改写，已是 v2 格式的文件保持不变。
+    pub fn upgrade_file(path: &Path) -> Result<bool> {
+        let db = Self::load(path)?;
+        if db.format == DbFormat::V2 {
+            return Ok(false);
+        }
+        db.with_format(DbFormat::V2).save(path)?;
+        Ok(true)
     }
 
     /// 检查数据库是否为空
@@ -63,27 +260,34 @@ impl PageDb {
     }
 
     /// 获取所有页面路径
-    pub fn paths(&self) -> Vec<&String> {
+    pub fn paths(&self) -> Vec<&PageKey> {
         self.pages.keys().collect()
     }
 
+    /// 按路径排序的页面列表（JSON 数组），写入 pages.json
+    pub fn to_page_list(&self) -> String {
+        let mut paths: Vec<&str> = self.pages.keys().map(PageKey::as_str).collect();
+        paths.sort_unstable();
+        serde_json::to_string(&paths).unwrap_or_else(|_| "[]".to_string())
+    }
+
     /// 获取所有页面内容
     pub fn contents(&self) -> Vec<&String> {
-        self.pages.values().collect()
+        self.pages.values().map(|page| &page.content).collect()
     }
 
-    /// 获取所有页面的键值对
-    pub fn entries(&self) -> impl Iterator<Item = (&String, &String)> {
-        self.pages.iter()
+    /// 获取所有页面路径和内容
+    pub fn entries(&self) -> impl Iterator<Item = (&PageKey, &String)> {
+        self.pages.iter().map(|(path, page)| (path, &page.content))
     }
 
     /// 获取可变的页面映射
-    pub fn pages_mut(&mut self) -> &mut HashMap<String, String> {
+    pub fn pages_mut(&mut self) -> &mut HashMap<PageKey, StoredPage> {
         &mut self.pages
     }
 
     /// 获取不可变的页面映射
-    pub fn pages(&self) -> &HashMap<String, String> {
+    pub fn pages(&self) -> &HashMap<PageKey, StoredPage> {
         &self.pages
     }
 
@@ -93,7 +297,7 @@ impl PageDb {
     }
 
     /// 移除页面
-    pub fn remove(&mut self, path: &str) -> Option<String> {
+    pub fn remove(&mut self, path: &PageKey) -> Option<StoredPage> {
         self.pages.remove(path)
     }
 }
@@ -107,29 +311,107 @@ mod tests {
         let mut db = PageDb::new();
         
         // 添加页面
-        db.add("path/to/page".to_string(), "content".to_string());
+        let key = PageKey::new("path/to/page");
+        db.add(key.clone(), "content".to_string());
         
         // 测试获取
-        assert_eq!(db.get("path/to/page"), Some(&"content".to_string()));
-        assert_eq!(db.has("path/to/page"), true);
-        assert_eq!(db.has("nonexistent"), false);
+        assert_eq!(db.get(&key), Some(&"content".to_string()));
+        assert_eq!(db.has(&key), true);
+        assert_eq!(db.has(&PageKey::new("nonexistent")), false);
         
         // 测试长度
         assert_eq!(db.len(), 1);
         assert_eq!(db.is_empty(), false);
         
         // 测试路径和内容
-        assert_eq!(db.paths(), vec![&"path/to/page".to_string()]);
+        assert_eq!(db.paths(), vec![&key]);
         assert_eq!(db.contents(), vec![&"content".to_string()]);
         
         // 测试 JSON 序列化和反序列化
         let json = db.to_json().unwrap();
         let loaded = PageDb::from_json(&json).unwrap();
-        assert_eq!(loaded.get("path/to/page"), Some(&"content".to_string()));
+        assert_eq!(loaded.get(&key), Some(&"content".to_string()));
         
         // 测试移除
-        db.remove("path/to/page");
-        assert_eq!(db.has("path/to/page"), false);
+        db.remove(&key);
+        assert_eq!(db.has(&key), false);
         assert_eq!(db.is_empty(), true);
     }
+
+    fn sample_page() -> StoredPage {
+        StoredPage::new("<h1>Array</h1>")
+            .with_title("Array")
+            .with_source_url("https://example.com/array")
+            .with_toc(vec![serde_json::json!({"id": "syntax", "title": "Syntax"})])
+    }
+
+    #[test]
+    fn test_v2_round_trip() {
+        let mut db = PageDb::new().with_format(DbFormat::V2);
+        db.insert(PageKey::new("array"), sample_page());
+
+        let json = db.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[META_KEY]["format"], 2);
+        assert_eq!(value["pages"]["array"]["title"], "Array");
+
+        let loaded = PageDb::from_json(&json).unwrap();
+        assert_eq!(loaded.format(), DbFormat::V2);
+        assert_eq!(loaded.page(&PageKey::new("array")), Some(&sample_page()));
+    }
+
+    #[test]
+    fn test_v1_values_read_as_stored_pages() {
+        let db = PageDb::from_json(r#"{"array": "<h1>Array</h1>"}"#).unwrap();
+
+        assert_eq!(db.format(), DbFormat::V1);
+        assert_eq!(db.page(&PageKey::new("array")), Some(&StoredPage::new("<h1>Array</h1>")));
+    }
+
+    #[test]
+    fn test_v2_down_converts_to_v1() {
+        let mut db = PageDb::new().with_format(DbFormat::V2);
+        db.insert(PageKey::new("array"), sample_page());
+
+        let v1 = db.to_value(DbFormat::V1);
+        assert_eq!(v1, serde_json::json!({"array": "<h1>Array</h1>"}));
+
+        // v1 中只保留内容
+        let loaded = PageDb::from_json(&v1.to_string()).unwrap();
+        assert_eq!(loaded.page(&PageKey::new("array")), Some(&StoredPage::new("<h1>Array</h1>")));
+    }
+
+    #[test]
+    fn test_unknown_format_is_rejected() {
+        let json = r#"{"__meta": {"format": 3}, "pages": {}}"#;
+        let error = PageDb::from_json(json).unwrap_err();
+        assert!(error.to_string().contains("3"));
+    }
+
+    #[test]
+    fn test_upgrade_file_in_place() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("db.json");
+        std::fs::write(&path, r#"{"array": "<h1>Array</h1>", "index": ""}"#).unwrap();
+
+        assert!(PageDb::upgrade_file(&path).unwrap());
+        let upgraded = PageDb::load(&path).unwrap();
+        assert_eq!(upgraded.format(), DbFormat::V2);
+        assert_eq!(upgraded.len(), 2);
+        assert_eq!(upgraded.get(&PageKey::new("array")), Some(&"<h1>Array</h1>".to_string()));
+
+        // 已是 v2 的文件不会被改写
+        assert!(!PageDb::upgrade_file(&path).unwrap());
+    }
+
+    #[test]
+    fn test_paths_normalized_on_read() {
+        let db = PageDb::from_json(r#"{"/guide/": "<h1>Guide</h1>", "": "<h1>Home</h1>"}"#).unwrap();
+        assert_eq!(db.get(&PageKey::new("guide")), Some(&"<h1>Guide</h1>".to_string()));
+        assert!(db.has(&PageKey::new("index")));
+
+        let v2 = r#"{"__meta": {"format": 2}, "pages": {"api/Array/": {"content": "x"}}}"#;
+        let db = PageDb::from_json(v2).unwrap();
+        assert_eq!(db.to_value(DbFormat::V1), serde_json::json!({"api/Array": "x"}));
+    }
 }
@@ -4,11 +4,13 @@
 //! 提供发送 HTTP 请求的功能
 
 use crate::core::error::{Error, Result};
+use crate::core::http_fetch::TlsOptions;
 use crate::core::instrumentable;
 use crate::core::response::Response;
 use crate::core::url::DocUrl;
 use reqwest::{blocking, header};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// 默认的用户代理
@@ -28,6 +30,8 @@ pub struct RequestOptions {
     pub connect_timeout: u64,
     /// 请求超时时间（秒）
     pub timeout: Option<u64>,
+    /// TLS 设置
+    pub tls: TlsOptions,
 }
 
 impl Default for RequestOptions {
@@ -40,10 +44,25 @@ impl Default for RequestOptions {
             headers,
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             timeout: None,
+            tls: TlsOptions::default(),
         }
     }
 }
 
+impl RequestOptions {
+    /// 添加信任的 CA 证书
+    pub fn with_ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tls = self.tls.with_ca_cert(path);
+        self
+    }
+
+    /// 设置是否接受无效的证书（不安全）
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.tls = self.tls.with_accept_invalid_certs(accept);
+        self
+    }
+}
+
 /// HTTP 请求结构体
 #[derive(Debug)]
 pub struct Request {
@@ -75,13 +94,16 @@ impl Request {
         request.run()
     }
 
-    /// 执行请求
+    /// 执行请求，请求失败时的错误带有请求的网址
     fn execute(&self) -> Result<Response> {
         let client = self.build_client()?;
         let response = client
             .get(self.url.to_string())
             .send()
-            .map_err(Error::Http)?;
+            .map_err(|e| Error::Fetch {
+                url: self.url.to_string(),
+                source: Box::new(Error::Http(e)),
+            })?;
 
         Response::from_reqwest(response, &self.url)
     }
@@ -100,6 +122,13 @@ impl Request {
             builder = builder.timeout(Duration::from_secs(timeout));
         }
 
+        // TLS 设置
+        for certificate in self.options.tls.certificates()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+        self.options.tls.warn_if_insecure();
+        builder = builder.danger_accept_invalid_certs(self.options.tls.accept_invalid_certs);
+
         // 添加头部信息
         let mut headers = header::HeaderMap::new();
         for (key, value) in &self.options.headers {
@@ -145,4 +174,16 @@ mod tests {
             Some(&"value".to_string())
         );
     }
+
+    #[test]
+    fn test_failed_fetch_reports_url() {
+        // 本机的 1 号端口没有服务，连接被拒绝
+        let mut options = RequestOptions::default();
+        options.connect_timeout = 2;
+        let error = Request::run_once("http://127.0.0.1:1/missing", Some(options)).unwrap_err();
+
+        assert_eq!(error.url(), Some("http://127.0.0.1:1/missing"));
+        assert!(error.to_string().starts_with("请求 http://127.0.0.1:1/missing 失败"), "{}", error);
+        assert!(matches!(error.root_cause(), Error::Http(_)));
+    }
 }
@@ -3,13 +3,16 @@
 //! 参考原始 Ruby 项目中的 requester.rb 实现
 //! 提供批量发送 HTTP 请求的功能
 
-use crate::core::error::Result;
+use crate::core::error::{Error, Result};
+use crate::core::http_fetch::{self, HttpFetch, ReqwestFetch};
 use crate::core::instrumentable;
-use crate::core::request::{Request, RequestOptions};
+use crate::core::request::RequestOptions;
 use crate::core::response::Response;
+use crate::core::url::DocUrl;
 use futures::stream::{FuturesUnordered, StreamExt};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 /// HTTP 请求器结构体
@@ -20,6 +23,8 @@ pub struct Requester {
     max_concurrency: usize,
     /// 响应回调函数
     on_response: Vec<Box<dyn Fn(&Response) -> Option<Vec<String>> + Send + Sync>>,
+    /// 发送请求使用的 HTTP 实现（None 表示按请求选项创建 reqwest 客户端）
+    fetch: Option<Arc<dyn HttpFetch>>,
 }
 
 impl Requester {
@@ -29,9 +34,16 @@ impl Requester {
             request_options: options.unwrap_or_default(),
             max_concurrency: max_concurrency.unwrap_or(20),
             on_response: Vec::new(),
+            fetch: None,
         }
     }
 
+    /// 设置发送请求使用的 HTTP 实现
+    pub fn with_fetch(mut self, fetch: Arc<dyn HttpFetch>) -> Self {
+        self.fetch = Some(fetch);
+        self
+    }
+
     /// 静态方法，创建请求器并运行请求
     pub fn run<F>(urls: Vec<String>, max_concurrency: Option<usize>, options: Option<RequestOptions>, callback: F) -> Result<Self>
     where
@@ -55,6 +67,10 @@ impl Requester {
     pub fn request(&self, urls: Vec<String>) -> Result<()> {
         // 创建异步运行时
         let rt = Runtime::new().unwrap();
+        let fetch = match &self.fetch {
+            Some(fetch) => fetch.clone(),
+            None => Arc::new(Self::default_fetch(&self.request_options)?),
+        };
         
         // 创建队列和已处理 URL 集合
         let queue: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(urls));
@@ -72,6 +88,7 @@ impl Requester {
                         let processed_clone = processed.clone();
                         let queue_clone = queue.clone();
                         let on_response = self.on_response.clone();
+                        let fetch = fetch.clone();
                         
                         // 创建异步任务
                         futures.push(tokio::spawn(async move {
@@ -79,7 +96,7 @@ impl Requester {
                             processed_clone.lock().unwrap().insert(url.clone(), true);
                             
                             // 发送请求
-                            match Self::send_request(&url, &request_options) {
+                            match Self::send_request(&fetch, &url, &request_options) {
                                 Ok(response) => {
                                     // 调用回调处理响应
                                     for callback in &on_response {
@@ -91,7 +108,7 @@ impl Requester {
                                     }
                                 }
                                 Err(err) => {
-                                    eprintln!("Error fetching {}: {}", url, err);
+                                    instrumentable::message("error.requester", format!("Error fetching {}: {}", url, err));
                                 }
                             }
                         }));
@@ -124,13 +141,30 @@ impl Requester {
         Some(q.remove(index))
     }
 
+    /// 按请求选项创建 reqwest 客户端
+    fn default_fetch(options: &RequestOptions) -> Result<ReqwestFetch> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(options.connect_timeout))
+            .redirect(if options.follow_redirects {
+                reqwest::redirect::Policy::limited(10)
+            } else {
+                reqwest::redirect::Policy::none()
+            });
+        if let Some(timeout) = options.timeout {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+        let builder = options.tls.apply(builder)?;
+        Ok(ReqwestFetch::with_client(builder.build().map_err(Error::Http)?))
+    }
+
     /// 发送单个请求
-    fn send_request(url: &str, options: &RequestOptions) -> Result<Response> {
+    fn send_request(fetch: &Arc<dyn HttpFetch>, url: &str, options: &RequestOptions) -> Result<Response> {
         let payload = HashMap::from([("url".to_string(), url.to_string())]);
         
         instrumentable::instrument("handle_request.requester", payload, || {
-            let request = Request::new(url, Some(options.clone()))?;
-            request.run()
+            let doc_url = DocUrl::parse(url)?;
+            let response = http_fetch::fetch_blocking(fetch, url, &options.headers)?;
+            Response::from_fetched(response, &doc_url)
         })
     }
 }
@@ -138,11 +172,28 @@ impl Requester {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch};
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_requester_callbacks() {
-        // 跳过实际网络请求的测试
-        // 这里应该使用模拟（mock）对象
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response("https://example.com/", FetchedResponse::html(r#"<a href="/a">a</a>"#))
+                .with_response("https://example.com/a", FetchedResponse::html("<p>A</p>")),
+        );
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut requester = Requester::new(Some(1), None).with_fetch(fetch.clone());
+        requester.on_response(move |response| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            (response.path() == "/").then(|| vec!["https://example.com/a".to_string()])
+        });
+        requester.request(vec!["https://example.com/".to_string()]).unwrap();
+
+        // 回调返回的链接继续请求，请求头来自请求选项
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fetch.requested_urls(), ["https://example.com/", "https://example.com/a"]);
+        assert_eq!(fetch.requests()[0].headers["User-Agent"], "DevDocs Rust");
     }
 }
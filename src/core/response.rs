@@ -4,6 +4,7 @@
 //! 提供 HTTP 响应处理功能
 
 use crate::core::error::Result;
+use crate::core::http_fetch::FetchedResponse;
 use crate::core::url::DocUrl;
 use reqwest::blocking;
 use reqwest::header::{HeaderMap, HeaderValue};
@@ -51,6 +52,24 @@ impl Response {
         })
     }
 
+    /// 从 [`FetchedResponse`] 创建
+    pub fn from_fetched(response: FetchedResponse, url: &DocUrl) -> Result<Self> {
+        let effective_url = if response.url.is_empty() {
+            url.clone()
+        } else {
+            DocUrl::parse(&response.url)?
+        };
+
+        Ok(Self {
+            code: response.status,
+            body: response.text(),
+            headers: response.headers,
+            url: url.clone(),
+            effective_url,
+            timed_out: false,
+        })
+    }
+
     /// 将 reqwest 头部映射转换为哈希映射
     fn convert_headers(headers: &HeaderMap<HeaderValue>) -> HashMap<String, String> {
         let mut result = HashMap::new();
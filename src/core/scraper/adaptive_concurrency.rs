@@ -0,0 +1,266 @@
+//! 自适应并发控制
+//!
+//! 固定的并发数对响应快的站点太慢，对响应慢的站点又太激进。自适应模式从
+//! 一个请求开始，每批请求的 p95 延迟和错误率都低于阈值时并发数加一；遇到
+//! 429/5xx 或延迟突增时并发数减半（AIMD），并发数不超过配置的最大值。
+
+use serde::Serialize;
+use std::time::Duration;
+
+/// 默认的 p95 延迟阈值
+pub const DEFAULT_LATENCY_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// 默认的错误率阈值
+pub const DEFAULT_ERROR_RATE_THRESHOLD: f64 = 0.05;
+
+/// 单个请求的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// 请求成功（包括 404 等不需要重试的响应）
+    Ok,
+    /// 服务器要求降低请求速度（429 或 5xx）
+    Throttled,
+    /// 网络错误或超时
+    Error,
+}
+
+impl RequestOutcome {
+    /// 根据 HTTP 状态码判断请求结果
+    pub fn from_status(status: u16) -> Self {
+        if status == 429 || status >= 500 {
+            RequestOutcome::Throttled
+        } else {
+            RequestOutcome::Ok
+        }
+    }
+}
+
+/// 并发数调整的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustReason {
+    /// 延迟和错误率都在阈值内
+    Healthy,
+    /// 收到 429 或 5xx
+    Throttled,
+    /// 错误率超过阈值
+    Errors,
+    /// p95 延迟超过阈值
+    Latency,
+}
+
+/// 一次并发数调整，记录在抓取报告中
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConcurrencyChange {
+    /// 调整时已发出的请求数
+    pub requests: usize,
+    /// 调整后的并发数
+    pub level: usize,
+    /// 调整原因
+    pub reason: AdjustReason,
+    /// 本批请求的 p95 延迟，单位毫秒
+    pub p95_ms: u64,
+    /// 本批请求的错误率
+    pub error_rate: f64,
+}
+
+/// AIMD 并发控制器
+///
+/// 控制器不涉及网络，调用方每完成一批请求后用 [`record`](Self::record) 记录结果，
+/// 再用 [`adjust`](Self::adjust) 得到下一批的并发数。
+#[derive(Debug, Clone)]
+pub struct AdaptiveConcurrency {
+    level: usize,
+    max: usize,
+    latency_threshold: Duration,
+    error_rate_threshold: f64,
+    samples: Vec<(Duration, RequestOutcome)>,
+    requests: usize,
+    history: Vec<ConcurrencyChange>,
+}
+
+impl AdaptiveConcurrency {
+    /// 创建控制器，并发数从 1 开始，不超过 `max`
+    pub fn new(max: usize) -> Self {
+        Self {
+            level: 1,
+            max: max.max(1),
+            latency_threshold: DEFAULT_LATENCY_THRESHOLD,
+            error_rate_threshold: DEFAULT_ERROR_RATE_THRESHOLD,
+            samples: Vec::new(),
+            requests: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// 设置 p95 延迟阈值
+    pub fn with_latency_threshold(mut self, threshold: Duration) -> Self {
+        self.latency_threshold = threshold;
+        self
+    }
+
+    /// 设置错误率阈值
+    pub fn with_error_rate_threshold(mut self, threshold: f64) -> Self {
+        self.error_rate_threshold = threshold;
+        self
+    }
+
+    /// 当前并发数
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// 并发数的调整记录
+    pub fn history(&self) -> &[ConcurrencyChange] {
+        &self.history
+    }
+
+    /// 记录一个请求的延迟和结果
+    pub fn record(&mut self, latency: Duration, outcome: RequestOutcome) {
+        self.samples.push((latency, outcome));
+        self.requests += 1;
+    }
+
+    /// 根据上次调整以来的请求调整并发数，返回新的并发数
+    ///
+    /// 收到 429/5xx、错误率或 p95 延迟超过阈值时并发数减半，否则加一。
+    /// 并发数变化时记录到调整记录中。
+    pub fn adjust(&mut self) -> usize {
+        if self.samples.is_empty() {
+            return self.level;
+        }
+
+        let p95 = self.p95();
+        let error_rate = self
+            .samples
+            .iter()
+            .filter(|(_, outcome)| *outcome != RequestOutcome::Ok)
+            .count() as f64
+            / self.samples.len() as f64;
+
+        let reason = if self.samples.iter().any(|(_, outcome)| *outcome == RequestOutcome::Throttled) {
+            AdjustReason::Throttled
+        } else if error_rate > self.error_rate_threshold {
+            AdjustReason::Errors
+        } else if p95 > self.latency_threshold {
+            AdjustReason::Latency
+        } else {
+            AdjustReason::Healthy
+        };
+        self.samples.clear();
+
+        let level = match reason {
+            AdjustReason::Healthy => (self.level + 1).min(self.max),
+            _ => (self.level / 2).max(1),
+        };
+        if level != self.level {
+            self.level = level;
+            self.history.push(ConcurrencyChange {
+                requests: self.requests,
+                level,
+                reason,
+                p95_ms: p95.as_millis() as u64,
+                error_rate,
+            });
+        }
+        self.level
+    }
+
+    /// 上次调整以来请求延迟的 p95
+    fn p95(&self) -> Duration {
+        let mut latencies: Vec<Duration> = self.samples.iter().map(|(latency, _)| *latency).collect();
+        latencies.sort();
+        let index = (latencies.len() * 95).div_ceil(100).saturating_sub(1);
+        latencies[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// 以当前并发数发出一批请求，返回调整后的并发数
+    fn batch(controller: &mut AdaptiveConcurrency, latency: Duration, outcome: RequestOutcome) -> usize {
+        for _ in 0..controller.level() {
+            controller.record(latency, outcome);
+        }
+        controller.adjust()
+    }
+
+    #[test]
+    fn test_increases_additively_up_to_max() {
+        let mut controller = AdaptiveConcurrency::new(4);
+        assert_eq!(controller.level(), 1);
+
+        let levels: Vec<usize> = (0..5)
+            .map(|_| batch(&mut controller, ms(100), RequestOutcome::Ok))
+            .collect();
+        assert_eq!(levels, vec![2, 3, 4, 4, 4]);
+        assert_eq!(controller.history().len(), 3);
+        assert!(controller.history().iter().all(|c| c.reason == AdjustReason::Healthy));
+    }
+
+    #[test]
+    fn test_backs_off_multiplicatively() {
+        let mut controller = AdaptiveConcurrency::new(16);
+        for _ in 0..7 {
+            batch(&mut controller, ms(100), RequestOutcome::Ok);
+        }
+        assert_eq!(controller.level(), 8);
+
+        // 一批中只要有一个 429 就减半
+        for _ in 0..7 {
+            controller.record(ms(100), RequestOutcome::Ok);
+        }
+        controller.record(ms(100), RequestOutcome::Throttled);
+        assert_eq!(controller.adjust(), 4);
+        assert_eq!(controller.history().last().unwrap().reason, AdjustReason::Throttled);
+
+        assert_eq!(batch(&mut controller, ms(100), RequestOutcome::Error), 2);
+        assert_eq!(controller.history().last().unwrap().reason, AdjustReason::Errors);
+        assert_eq!(batch(&mut controller, ms(100), RequestOutcome::Throttled), 1);
+        assert_eq!(batch(&mut controller, ms(100), RequestOutcome::Throttled), 1);
+    }
+
+    #[test]
+    fn test_latency_spike_backs_off() {
+        let mut controller = AdaptiveConcurrency::new(8).with_latency_threshold(ms(500));
+        for _ in 0..3 {
+            batch(&mut controller, ms(100), RequestOutcome::Ok);
+        }
+        assert_eq!(controller.level(), 4);
+
+        // 个别慢请求不影响 p95，整批变慢时才减半
+        for _ in 0..19 {
+            controller.record(ms(100), RequestOutcome::Ok);
+        }
+        controller.record(ms(3000), RequestOutcome::Ok);
+        assert_eq!(controller.adjust(), 5);
+
+        assert_eq!(batch(&mut controller, ms(900), RequestOutcome::Ok), 2);
+        let change = controller.history().last().unwrap();
+        assert_eq!(change.reason, AdjustReason::Latency);
+        assert_eq!(change.p95_ms, 900);
+    }
+
+    #[test]
+    fn test_occasional_errors_under_threshold() {
+        let mut controller = AdaptiveConcurrency::new(64).with_error_rate_threshold(0.1);
+        for _ in 0..3 {
+            batch(&mut controller, ms(50), RequestOutcome::Ok);
+        }
+        for _ in 0..19 {
+            controller.record(ms(50), RequestOutcome::Ok);
+        }
+        controller.record(ms(50), RequestOutcome::Error);
+        assert_eq!(controller.adjust(), 5);
+
+        // 没有新的请求时不调整
+        assert_eq!(controller.adjust(), 5);
+        assert_eq!(controller.history().last().unwrap().requests, 26);
+    }
+}
@@ -3,6 +3,8 @@
 
 use crate::core::error::Result;
 use crate::core::scraper::filter::Filter;
+use crate::core::scraper::url_scraper::UrlScraper;
+use crate::core::version_probe::VersionProbe;
 use async_trait::async_trait;
 use std::collections::HashMap;
 
@@ -17,6 +19,16 @@ pub trait Scraper: Send + Sync {
 
     /// 运行抓取器
     async fn run(&mut self) -> Result<()>;
+
+    /// 获取底层的URL抓取器，用于统一应用抓取选项
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        None
+    }
+
+    /// 查询上游最新版本的方式，`docs-outdated` 用它检查文档是否过时
+    fn version_probe(&self) -> Option<Box<dyn VersionProbe>> {
+        None
+    }
 }
 
 /// 基础抓取器配置
@@ -0,0 +1,177 @@
+//! 抓取事件
+//!
+//! 调整跳过规则时需要知道抓取器考虑过哪些链接。抓取器在链接入队和筛选时
+//! 发布以下事件，载荷包含规范化后的 URL 和页面路径：
+//!
+//! - `queue.enqueued`：链接加入抓取队列
+//! - `queue.deduped`：链接已经访问过，没有重复入队
+//! - `url.skipped`：链接被跳过，`reason` 说明原因（见 [`SkipReason`]）
+//!
+//! 大型文档会产生数十万个这类事件，发布到事件总线时按事件名称和原因分别
+//! 采样，每 N 个事件只发布一个（第一个总是发布）。指定跟踪文件时，全部事件
+//! 不经采样写入该文件，供离线分析。
+
+use crate::core::instrumentable::{self, InstrumentInfo};
+use crate::core::subscriber::{FileSubscriber, Subscriber};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// 链接加入抓取队列
+pub const QUEUE_ENQUEUED_EVENT: &str = "queue.enqueued";
+/// 链接已访问过，没有重复入队
+pub const QUEUE_DEDUPED_EVENT: &str = "queue.deduped";
+/// 链接被跳过
+pub const URL_SKIPPED_EVENT: &str = "url.skipped";
+
+/// 默认的采样率：每个事件名称（和原因）每 10 个事件发布一个
+pub const DEFAULT_EVENT_SAMPLE_RATE: u64 = 10;
+
+/// 链接被跳过的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipReason {
+    /// 不在任何基础URL之下
+    External,
+    /// 被抓取器的 skip_link 回调排除
+    SkipLink,
+    /// 位于 skip_paths 之下
+    SkipPath,
+    /// 匹配 skip_patterns
+    SkipPattern,
+    /// 不在 only 或 only_patterns 之内
+    OnlyMiss,
+    /// 已经访问过（队列中的重复链接）
+    Visited,
+}
+
+impl SkipReason {
+    /// 事件载荷中的原因名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::External => "external",
+            SkipReason::SkipLink => "skip_link",
+            SkipReason::SkipPath => "skip_path",
+            SkipReason::SkipPattern => "skip_pattern",
+            SkipReason::OnlyMiss => "only-miss",
+            SkipReason::Visited => "visited",
+        }
+    }
+}
+
+/// 抓取事件的发布器
+pub struct CrawlEvents {
+    /// 采样率，1 表示发布全部事件
+    sample_rate: u64,
+    /// 每个事件名称和原因已产生的事件数
+    counts: HashMap<(&'static str, &'static str), u64>,
+    /// 接收全部事件的跟踪文件
+    trace: Option<FileSubscriber>,
+}
+
+impl CrawlEvents {
+    /// 创建发布器，`sample_rate` 为 0 时按 1 处理
+    pub fn new(sample_rate: u64) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            counts: HashMap::new(),
+            trace: None,
+        }
+    }
+
+    /// 将全部事件（不采样）写入跟踪文件
+    pub fn with_trace(mut self, trace: FileSubscriber) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// 链接加入抓取队列
+    pub fn enqueued(&mut self, url: &str, path: &str, depth: usize) {
+        self.emit(QUEUE_ENQUEUED_EVENT, None, url, path, Some(depth));
+    }
+
+    /// 链接已访问过，没有重复入队
+    pub fn deduped(&mut self, url: &str, path: &str) {
+        self.emit(QUEUE_DEDUPED_EVENT, None, url, path, None);
+    }
+
+    /// 链接被跳过
+    pub fn skipped(&mut self, url: &str, path: &str, reason: SkipReason) {
+        self.emit(URL_SKIPPED_EVENT, Some(reason), url, path, None);
+    }
+
+    /// 写入跟踪文件并按采样率发布事件，返回是否发布到事件总线
+    fn emit(
+        &mut self,
+        name: &'static str,
+        reason: Option<SkipReason>,
+        url: &str,
+        path: &str,
+        depth: Option<usize>,
+    ) -> bool {
+        let mut payload = HashMap::from([
+            ("url".to_string(), url.to_string()),
+            ("path".to_string(), path.to_string()),
+        ]);
+        if let Some(reason) = reason {
+            payload.insert("reason".to_string(), reason.as_str().to_string());
+        }
+        if let Some(depth) = depth {
+            payload.insert("depth".to_string(), depth.to_string());
+        }
+
+        if let Some(trace) = &self.trace {
+            trace.handle_event(&InstrumentInfo {
+                name: name.to_string(),
+                start_time: Instant::now(),
+                duration: None,
+                payload: payload.clone(),
+            });
+        }
+
+        let count = self
+            .counts
+            .entry((name, reason.map_or("", |reason| reason.as_str())))
+            .or_insert(0);
+        let sampled = count.is_multiple_of(self.sample_rate);
+        *count += 1;
+        if sampled {
+            payload.insert("sample_rate".to_string(), self.sample_rate.to_string());
+            instrumentable::instrument(name, payload, || ());
+        }
+        sampled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::LogFormat;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sampling_keeps_trace_complete() {
+        let dir = TempDir::new().unwrap();
+        let trace_path = dir.path().join("trace.jsonl");
+        let mut events = CrawlEvents::new(3)
+            .with_trace(FileSubscriber::new(&trace_path.to_string_lossy()).with_format(LogFormat::Jsonl));
+
+        let published: Vec<bool> = (0..7)
+            .map(|i| events.emit(QUEUE_ENQUEUED_EVENT, None, &format!("https://e.com/{}", i), "", Some(1)))
+            .collect();
+        assert_eq!(published, [true, false, false, true, false, false, true]);
+
+        // 每种原因单独采样，少见的原因不会被常见原因淹没
+        assert!(events.emit(URL_SKIPPED_EVENT, Some(SkipReason::External), "https://x.com/", "", None));
+        assert!(events.emit(URL_SKIPPED_EVENT, Some(SkipReason::SkipPath), "https://e.com/api", "api", None));
+        assert!(!events.emit(URL_SKIPPED_EVENT, Some(SkipReason::External), "https://y.com/", "", None));
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&trace_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[6]["payload"]["url"], "https://e.com/6");
+        assert_eq!(lines[8]["payload"]["reason"], "skip_path");
+        assert!(lines[0]["payload"].get("sample_rate").is_none());
+    }
+}
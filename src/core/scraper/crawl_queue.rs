@@ -0,0 +1,179 @@
+//! 抓取队列
+//!
+//! 广度优先的 `VecDeque` 在限制页面数时可能被大量自动生成的深层页面占满，
+//! 重要的顶层参考页面反而抓不到。抓取队列按优先级出队，依次比较：
+//!
+//! 1. 匹配的优先模式（`with_priority_patterns`，越靠前优先级越高，不匹配的最后）
+//! 2. 链接深度（初始路径为 0）
+//! 3. 是否位于某个初始路径之下
+//! 4. 路径长度
+//!
+//! 优先级相同时按入队顺序出队，保证抓取顺序确定。
+
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// 队列中的 URL，字段顺序即比较顺序
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct QueuedUrl {
+    /// 匹配的第一个优先模式的序号，不匹配时为模式数量
+    pattern_rank: usize,
+    /// 链接深度
+    depth: usize,
+    /// 不在任何初始路径之下
+    outside_initial: bool,
+    /// 路径长度
+    path_len: usize,
+    /// 入队序号
+    seq: u64,
+    /// URL
+    url: String,
+}
+
+/// 按优先级出队的抓取队列
+#[derive(Debug, Default)]
+pub struct CrawlQueue {
+    heap: BinaryHeap<Reverse<QueuedUrl>>,
+    initial_prefixes: Vec<String>,
+    priority_patterns: Vec<Regex>,
+    next_seq: u64,
+}
+
+impl CrawlQueue {
+    /// 创建抓取队列
+    ///
+    /// `initial_paths` 为抓取器的初始路径，`priority_patterns` 为优先抓取的路径
+    /// 正则表达式，无效的模式会被忽略。
+    pub fn new(initial_paths: &[String], priority_patterns: &[String]) -> Self {
+        let priority_patterns = priority_patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!("忽略无效的优先模式 {}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            heap: BinaryHeap::new(),
+            initial_prefixes: initial_paths
+                .iter()
+                .map(|path| path.trim_matches('/').to_string())
+                .collect(),
+            priority_patterns,
+            next_seq: 0,
+        }
+    }
+
+    /// 添加 URL，`path` 为 URL 对应的文档路径，`depth` 为链接深度
+    pub fn push(&mut self, url: String, path: &str, depth: usize) {
+        let pattern_rank = self
+            .priority_patterns
+            .iter()
+            .position(|pattern| pattern.is_match(path))
+            .unwrap_or(self.priority_patterns.len());
+        let outside_initial = !self.initial_prefixes.iter().any(|prefix| {
+            prefix.is_empty()
+                || path == prefix
+                || path.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.starts_with('/'))
+        });
+
+        self.heap.push(Reverse(QueuedUrl {
+            pattern_rank,
+            depth,
+            outside_initial,
+            path_len: path.len(),
+            seq: self.next_seq,
+            url,
+        }));
+        self.next_seq += 1;
+    }
+
+    /// 取出优先级最高的 URL 及其链接深度
+    pub fn pop(&mut self) -> Option<(String, usize)> {
+        self.heap.pop().map(|Reverse(queued)| (queued.url, queued.depth))
+    }
+
+    /// 队列中的所有 URL 及其链接深度，按出队顺序排列，不改变队列
+    pub fn pending(&self) -> Vec<(String, usize)> {
+        let mut queued: Vec<&QueuedUrl> = self.heap.iter().map(|Reverse(queued)| queued).collect();
+        queued.sort();
+        queued.into_iter().map(|queued| (queued.url.clone(), queued.depth)).collect()
+    }
+
+    /// 队列是否为空
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// 队列中的 URL 数量（可能包含重复的 URL）
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_all(queue: &mut CrawlQueue, paths: &[(&str, usize)]) {
+        for (path, depth) in paths {
+            queue.push(format!("https://example.com/{}", path), path, *depth);
+        }
+    }
+
+    fn drain(queue: &mut CrawlQueue) -> Vec<String> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|(url, _)| url.trim_start_matches("https://example.com/").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_shallow_and_initial_paths_first() {
+        let mut queue = CrawlQueue::new(&["Reference".to_string()], &[]);
+        push_all(
+            &mut queue,
+            &[
+                ("Guide/a/b/c", 3),
+                ("Guide/intro", 1),
+                ("Reference/Array", 1),
+                ("Reference/Array/map", 2),
+                ("Reference", 0),
+            ],
+        );
+
+        // pending 不改变队列，顺序与出队顺序一致
+        let pending = queue.pending();
+        assert_eq!(pending.len(), 5);
+        assert_eq!(pending[0], ("https://example.com/Reference".to_string(), 0));
+        assert_eq!(
+            drain(&mut queue),
+            ["Reference", "Reference/Array", "Guide/intro", "Reference/Array/map", "Guide/a/b/c"]
+        );
+    }
+
+    #[test]
+    fn test_priority_patterns_and_insertion_order() {
+        let patterns = vec!["^Reference/".to_string(), "^Guide/".to_string(), "(".to_string()];
+        let mut queue = CrawlQueue::new(&["/".to_string()], &patterns);
+        push_all(
+            &mut queue,
+            &[
+                ("Other/x", 1),
+                ("Guide/b", 1),
+                ("Guide/a", 1),
+                ("Reference/deep/er/page", 4),
+                ("Reference/x", 1),
+            ],
+        );
+
+        // 同一优先级内按深度和路径长度，完全相同时按入队顺序
+        assert_eq!(
+            drain(&mut queue),
+            ["Reference/x", "Reference/deep/er/page", "Guide/b", "Guide/a", "Other/x"]
+        );
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! 抓取报告
+//!
+//! 记录一次抓取中成功和失败的页面以及过滤器警告，失败的页面不会中断整个抓取。
+//! 抓取结束后校验链接锚点，找不到的锚点也记录在报告中
+
+use super::adaptive_concurrency::ConcurrencyChange;
+use super::filter::FilterWarning;
+use super::fragment_links::BrokenFragment;
+use super::head_check::HeadSkip;
+use super::near_duplicates::NearDuplicate;
+use super::page_size::OversizedPage;
+use crate::core::error::Error;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// 单个页面的过滤失败记录
+#[derive(Debug, Clone, Serialize)]
+pub struct PageFailure {
+    /// 页面URL
+    pub url: String,
+    /// 页面路径
+    pub path: String,
+    /// 出错的过滤器名称
+    pub filter: String,
+    /// 完整的错误信息，包括过滤器、页面URL和原始错误
+    pub error: String,
+    /// 原始错误信息
+    pub cause: String,
+    /// 是否保存了未过滤的HTML
+    pub stored_unfiltered: bool,
+}
+
+impl PageFailure {
+    /// 由过滤错误创建失败记录
+    pub fn new(url: &str, path: &str, error: &Error, stored_unfiltered: bool) -> Self {
+        Self {
+            url: url.to_string(),
+            path: path.to_string(),
+            filter: error.filter_name().unwrap_or_default().to_string(),
+            error: error.to_string(),
+            cause: error.root_cause().to_string(),
+            stored_unfiltered,
+        }
+    }
+}
+
+/// 抓取报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrawlReport {
+    /// 成功处理的页面数
+    pub pages_ok: usize,
+    /// 发出的页面请求数（包括重试）
+    pub requests: usize,
+    /// 出错的请求数：连接错误、超时和 4xx、5xx 响应
+    pub request_errors: usize,
+    /// 过滤失败的页面
+    pub failures: Vec<PageFailure>,
+    /// 过滤器警告
+    pub warnings: Vec<FilterWarning>,
+    /// 按排除规则去掉的条目数
+    pub excluded_entries: usize,
+    /// 近似重复的页面
+    pub near_duplicates: Vec<NearDuplicate>,
+    /// 自适应并发模式下并发数的调整记录
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub concurrency_changes: Vec<ConcurrencyChange>,
+    /// 超过大小限制的页面及采取的处理
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub oversized_pages: Vec<OversizedPage>,
+    /// 通过 HEAD 预检跳过、没有下载的链接
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub head_skips: Vec<HeadSkip>,
+    /// 指向不存在的锚点的内部链接
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub broken_fragments: Vec<BrokenFragment>,
+}
+
+impl CrawlReport {
+    /// 创建新的抓取报告
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录成功处理的页面
+    pub fn record_ok(&mut self) {
+        self.pages_ok += 1;
+    }
+
+    /// 记录一次页面请求
+    pub fn record_request(&mut self, ok: bool) {
+        self.requests += 1;
+        if !ok {
+            self.request_errors += 1;
+        }
+    }
+
+    /// 请求错误率
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.request_errors as f64 / self.requests as f64
+        }
+    }
+
+    /// 记录过滤失败的页面
+    pub fn record_failure(&mut self, failure: PageFailure) {
+        self.failures.push(failure);
+    }
+
+    /// 记录页面的过滤器警告，未指定URL的警告使用页面URL
+    pub fn record_warnings(&mut self, url: &str, warnings: Vec<FilterWarning>) {
+        for mut warning in warnings {
+            if warning.url.is_none() {
+                warning.url = Some(url.to_string());
+            }
+            warning.page = Some(url.to_string());
+            self.warnings.push(warning);
+        }
+    }
+
+    /// 按过滤器和警告信息统计警告数量
+    pub fn warning_counts(&self) -> BTreeMap<(String, String), usize> {
+        let mut counts = BTreeMap::new();
+        for warning in &self.warnings {
+            *counts
+                .entry((warning.filter.clone(), warning.message.clone()))
+                .or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// 已处理的页面总数
+    pub fn pages_processed(&self) -> usize {
+        self.pages_ok + self.failures.len()
+    }
+
+    /// 失败率
+    pub fn failure_rate(&self) -> f64 {
+        let total = self.pages_processed();
+        if total == 0 {
+            0.0
+        } else {
+            self.failures.len() as f64 / total as f64
+        }
+    }
+
+    /// 查找指定路径的失败记录
+    pub fn failure_for(&self, path: &str) -> Option<&PageFailure> {
+        self.failures.iter().find(|f| f.path == path)
+    }
+}
@@ -0,0 +1,104 @@
+//! 抓取状态
+//!
+//! 抓取日志（[`super::page_log`]）只保存处理完成的页面及其条目，恢复抓取时要从这些
+//! 页面重新提取链接，请求失败或跳过的页面会重新请求。抓取过程中定期将已访问的 URL、
+//! 待抓取队列、已收集的条目和重定向写入文档目录中的 `crawl_state.json`，恢复抓取时
+//! 与抓取日志中的页面一起从中断处继续。
+//!
+//! 状态每隔 [`SAVE_INTERVAL`] 才保存一次，崩溃时可能落后于日志。之后才写入日志的
+//! 页面仍在恢复的队列中，出队时从日志记录中取回条目，因此不会丢失。
+//! 抓取成功完成后状态文件随日志一起删除。
+
+use crate::core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// 抓取状态文件名
+pub const STATE_FILENAME: &str = "crawl_state.json";
+
+/// 抓取过程中保存状态的最小间隔
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 中断时的抓取状态，页面内容保存在抓取日志中
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlState {
+    /// 抓取的根 URL，与当前抓取不一致时不恢复
+    pub base_url: String,
+    /// 已访问的 URL
+    pub visited: Vec<String>,
+    /// 待抓取的 URL 及其链接深度，按出队顺序排列
+    pub pending: Vec<(String, usize)>,
+    /// 过滤器生成的条目
+    pub entries: Vec<(String, String, String)>,
+    /// 由页面标题和路径推断的条目
+    pub fallback_entries: Vec<(String, String, String)>,
+    /// 原始 URL 到重定向后 URL 的映射
+    pub redirections: HashMap<String, String>,
+}
+
+impl CrawlState {
+    /// 读取文档目录中的抓取状态
+    ///
+    /// 文件不存在、无法解析或属于其他根 URL 时返回 None。
+    pub fn load(doc_dir: &Path, base_url: &str) -> Option<Self> {
+        let json = fs::read_to_string(doc_dir.join(STATE_FILENAME)).ok()?;
+        match serde_json::from_str::<Self>(&json) {
+            Ok(state) if state.base_url == base_url => Some(state),
+            Ok(state) => {
+                log::warn!("抓取状态属于 {}，不用于恢复 {}", state.base_url, base_url);
+                None
+            }
+            Err(e) => {
+                log::warn!("无法解析抓取状态，已忽略: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 保存到文档目录，先写入临时文件再替换，不会留下写了一半的状态
+    pub fn save(&self, doc_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        let tmp = doc_dir.join(format!("{}.tmp", STATE_FILENAME));
+        fs::write(&tmp, json).map_err(|e| Error::Message(format!("无法写入抓取状态: {}", e)))?;
+        fs::rename(&tmp, doc_dir.join(STATE_FILENAME))?;
+        Ok(())
+    }
+
+    /// 删除文档目录中的抓取状态
+    pub fn remove(doc_dir: &Path) -> Result<()> {
+        let path = doc_dir.join(STATE_FILENAME);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| Error::Message(format!("无法删除抓取状态 {:?}: {}", path, e)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let state = CrawlState {
+            base_url: "https://example.com".to_string(),
+            visited: vec!["https://example.com/".to_string()],
+            pending: vec![("https://example.com/a".to_string(), 1)],
+            entries: vec![("A".to_string(), "a".to_string(), "Guide".to_string())],
+            ..CrawlState::default()
+        };
+        state.save(dir.path()).unwrap();
+        assert!(!dir.path().join("crawl_state.json.tmp").exists());
+
+        assert_eq!(CrawlState::load(dir.path(), "https://example.com"), Some(state));
+        assert_eq!(CrawlState::load(dir.path(), "https://other.com"), None);
+
+        CrawlState::remove(dir.path()).unwrap();
+        assert_eq!(CrawlState::load(dir.path(), "https://example.com"), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! 抓取时的磁盘空间检查
+//!
+//! 写到一半时磁盘写满会留下损坏的 JSON 文件，还可能拖垮整个系统。开始抓取前
+//! 按上次抓取结果的大小（乘以安全系数）或抓取器给出的预估大小检查文档目录所在
+//! 文件系统的可用空间，不足时拒绝开始；抓取过程中每处理一定数量的页面重新检查，
+//! 低于下限时保存恢复所需的状态后中止，释放空间后可以用 `--resume` 继续。
+
+use crate::core::config::DiskSpaceSettings;
+use crate::core::error::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 查询文件系统可用空间
+pub trait DiskSpace: Send + Sync {
+    /// `path` 所在文件系统的可用空间（字节），无法查询时返回 None
+    fn available(&self, path: &Path) -> Option<u64>;
+}
+
+/// 通过 `statvfs` 查询可用空间，其他平台上不检查
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemDiskSpace;
+
+impl DiskSpace for SystemDiskSpace {
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_cast)] // 字段类型随平台不同
+    fn available(&self, path: &Path) -> Option<u64> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+    }
+
+    #[cfg(not(unix))]
+    fn available(&self, _path: &Path) -> Option<u64> {
+        None
+    }
+}
+
+/// 目录中所有文件的总大小（字节），目录不存在时为 0
+pub fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// 开始抓取所需的可用空间：预估的文档大小加上保留的可用空间
+///
+/// 抓取器给出了预估大小时使用该值，否则使用上次抓取结果的大小乘以安全系数。
+pub fn required_space(previous_size: u64, hint: Option<u64>, settings: &DiskSpaceSettings) -> u64 {
+    let estimate = hint.unwrap_or_else(|| (previous_size as f64 * settings.safety_factor).ceil() as u64);
+    estimate.saturating_add(settings.min_free_bytes())
+}
+
+/// 检查 `path` 所在文件系统至少有 `required` 字节可用，无法查询时视为足够
+pub fn ensure_available(probe: &dyn DiskSpace, path: &Path, required: u64) -> Result<()> {
+    match probe.available(path) {
+        Some(available) if available < required => Err(Error::InsufficientDiskSpace {
+            path: path.to_path_buf(),
+            available,
+            required,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// 抓取过程中定期检查可用空间
+pub struct SpaceMonitor {
+    probe: Arc<dyn DiskSpace>,
+    path: PathBuf,
+    min_free: u64,
+    interval: usize,
+    last_checked: usize,
+}
+
+impl SpaceMonitor {
+    /// 检查 `path` 所在的文件系统，每处理 `settings.check_interval_pages` 个页面检查一次
+    pub fn new(probe: Arc<dyn DiskSpace>, path: &Path, settings: &DiskSpaceSettings) -> Self {
+        Self {
+            probe,
+            path: path.to_path_buf(),
+            min_free: settings.min_free_bytes(),
+            interval: settings.check_interval_pages.max(1),
+            last_checked: 0,
+        }
+    }
+
+    /// 已处理 `processed` 个页面，距上次检查达到间隔时检查可用空间是否低于下限
+    pub fn check(&mut self, processed: usize) -> Result<()> {
+        if processed < self.last_checked + self.interval {
+            return Ok(());
+        }
+        self.last_checked = processed;
+        ensure_available(&*self.probe, &self.path, self.min_free)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    struct Fixed(u64);
+
+    impl DiskSpace for Fixed {
+        fn available(&self, _path: &Path) -> Option<u64> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_required_space_prefers_hint() {
+        let settings = DiskSpaceSettings {
+            safety_factor: 2.0,
+            min_free_mb: 1,
+            check_interval_pages: 10,
+        };
+        assert_eq!(required_space(1000, None, &settings), 2000 + 1024 * 1024);
+        assert_eq!(required_space(1000, Some(10), &settings), 10 + 1024 * 1024);
+
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("guide")).unwrap();
+        std::fs::write(dir.path().join("db.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("guide").join("index.html"), "<p></p>").unwrap();
+        assert_eq!(dir_size(dir.path()), 9);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_monitor_checks_at_interval() {
+        let settings = DiskSpaceSettings {
+            min_free_mb: 1,
+            check_interval_pages: 5,
+            ..DiskSpaceSettings::default()
+        };
+        let mut monitor = SpaceMonitor::new(Arc::new(Fixed(10)), Path::new("."), &settings);
+        assert!(monitor.check(4).is_ok());
+        assert!(matches!(monitor.check(5), Err(Error::InsufficientDiskSpace { available: 10, .. })));
+    }
+}
@@ -0,0 +1,132 @@
+//! 条目排除规则
+//!
+//! 过滤器生成条目后、写入索引前，去掉不希望出现在搜索中的条目，
+//! 例如指南索引页、贡献者页面或已废弃的别名。
+
+use regex::Regex;
+use serde::Serialize;
+
+/// 条目排除规则，任一规则匹配即排除
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EntryExclusions {
+    /// 条目名称的正则表达式
+    pub name_patterns: Vec<String>,
+    /// 条目类型名称
+    pub types: Vec<String>,
+    /// 条目路径前缀
+    pub path_prefixes: Vec<String>,
+}
+
+impl EntryExclusions {
+    /// 创建空的排除规则
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 排除名称匹配正则表达式的条目
+    pub fn with_name_pattern(mut self, pattern: &str) -> Self {
+        self.name_patterns.push(pattern.to_string());
+        self
+    }
+
+    /// 排除指定类型的条目
+    pub fn with_type(mut self, entry_type: &str) -> Self {
+        self.types.push(entry_type.to_string());
+        self
+    }
+
+    /// 排除路径以指定前缀开头的条目
+    pub fn with_path_prefix(mut self, prefix: &str) -> Self {
+        self.path_prefixes
+            .push(prefix.trim_start_matches('/').to_string());
+        self
+    }
+
+    /// 合并另一组规则
+    pub fn merge(&mut self, other: &EntryExclusions) {
+        self.name_patterns.extend(other.name_patterns.iter().cloned());
+        self.types.extend(other.types.iter().cloned());
+        self.path_prefixes.extend(other.path_prefixes.iter().cloned());
+    }
+
+    /// 是否没有任何规则
+    pub fn is_empty(&self) -> bool {
+        self.name_patterns.is_empty() && self.types.is_empty() && self.path_prefixes.is_empty()
+    }
+
+    /// 去掉匹配规则的条目（名称、路径、类型），返回被排除的数量
+    ///
+    /// 无效的正则表达式会被忽略。
+    pub fn apply(&self, entries: &mut Vec<(String, String, String)>) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+
+        let patterns: Vec<Regex> = self
+            .name_patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!("无效的条目排除规则 {}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        let before = entries.len();
+        entries.retain(|(name, path, entry_type)| {
+            let path = path.trim_start_matches('/');
+            !(patterns.iter().any(|regex| regex.is_match(name))
+                || self.types.iter().any(|t| t == entry_type)
+                || self.path_prefixes.iter().any(|prefix| path.starts_with(prefix.as_str())))
+        });
+        before - entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, path: &str, entry_type: &str) -> (String, String, String) {
+        (name.to_string(), path.to_string(), entry_type.to_string())
+    }
+
+    #[test]
+    fn test_each_rule_kind() {
+        let mut entries = vec![
+            entry("Array", "global_objects/array", "Global Objects"),
+            entry("Guide index", "guide/index", "Guides"),
+            entry("Deprecated: escape()", "global_objects/escape", "Global Objects"),
+            entry("Contributors", "contributors/alice", "Other"),
+            entry("for...of", "statements/for...of", "Statements"),
+            entry("Grammar", "guide/grammar", "Miscellaneous"),
+        ];
+
+        let exclusions = EntryExclusions::new()
+            .with_name_pattern("^Deprecated:")
+            .with_type("Guides")
+            .with_path_prefix("/contributors/");
+
+        assert_eq!(exclusions.apply(&mut entries), 3);
+        assert_eq!(
+            entries,
+            vec![
+                entry("Array", "global_objects/array", "Global Objects"),
+                entry("for...of", "statements/for...of", "Statements"),
+                entry("Grammar", "guide/grammar", "Miscellaneous"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_and_invalid_pattern() {
+        let mut exclusions = EntryExclusions::new().with_name_pattern("(");
+        exclusions.merge(&EntryExclusions::new().with_type("Other"));
+
+        let mut entries = vec![entry("a", "a", "Other"), entry("(b", "b", "Methods")];
+        assert_eq!(exclusions.apply(&mut entries), 1);
+        assert_eq!(entries, vec![entry("(b", "b", "Methods")]);
+    }
+}
@@ -0,0 +1,106 @@
+//! 网站图标
+//!
+//! 从任意网址抓取的文档没有图标。抓取结束后依次尝试根页面中 `<link rel="icon">`
+//! 指向的图片和网站根目录下的 `/favicon.ico`，将第一个能解码的图片缩放为
+//! 32×32 的 PNG，保存为文档目录中的 `icon.png`。图标只是锦上添花，请求失败、
+//! 不是图片或无法解码时都不影响抓取结果。
+
+use crate::core::http_fetch::HttpFetch;
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::io::Cursor;
+use url::Url;
+
+/// 文档图标的文件名
+pub const ICON_FILENAME: &str = "icon.png";
+
+/// 文档图标的边长（像素）
+pub const ICON_SIZE: u32 = 32;
+
+/// 图标的候选地址：根页面中 rel 含 `icon` 的 `<link>`，其次是 `/favicon.ico`
+pub fn icon_urls(page_url: &str, html: Option<&str>) -> Vec<String> {
+    let Ok(page_url) = Url::parse(page_url) else {
+        return Vec::new();
+    };
+    let mut urls = Vec::new();
+    if let (Some(html), Ok(selector)) = (html, Selector::parse("link[rel][href]")) {
+        let document = Html::parse_document(html);
+        for link in document.select(&selector) {
+            let rel = link.value().attr("rel").unwrap_or_default();
+            if !rel.split_whitespace().any(|token| token.eq_ignore_ascii_case("icon")) {
+                continue;
+            }
+            let href = link.value().attr("href").unwrap_or_default();
+            if let Ok(url) = page_url.join(href) {
+                if matches!(url.scheme(), "http" | "https") {
+                    urls.push(url.to_string());
+                }
+            }
+        }
+    }
+    if let Ok(url) = page_url.join("/favicon.ico") {
+        urls.push(url.to_string());
+    }
+    urls.dedup();
+    urls
+}
+
+/// 将图片转为 32×32 的 PNG，无法解码时返回 None
+pub fn icon_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let icon = image.resize_exact(ICON_SIZE, ICON_SIZE, image::imageops::FilterType::Lanczos3);
+    let mut buffer = Cursor::new(Vec::new());
+    icon.write_to(&mut buffer, image::ImageFormat::Png).ok()?;
+    Some(buffer.into_inner())
+}
+
+/// 依次请求候选地址，返回第一个能转为图标的图片
+pub async fn fetch_icon(
+    fetch: &dyn HttpFetch,
+    page_url: &str,
+    html: Option<&str>,
+    headers: &HashMap<String, String>,
+) -> Option<Vec<u8>> {
+    for url in icon_urls(page_url, html) {
+        let Ok(response) = fetch.get(&url, headers).await else {
+            continue;
+        };
+        if !response.is_success() {
+            continue;
+        }
+        if let Some(icon) = icon_png(&response.body) {
+            return Some(icon);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_urls() {
+        let html = r#"<head>
+            <link rel="stylesheet" href="/style.css">
+            <link rel="shortcut icon" href="static/logo.png">
+            <link rel="icon" href="data:image/png;base64,AAAA">
+        </head>"#;
+        assert_eq!(
+            icon_urls("https://example.com/docs/", Some(html)),
+            ["https://example.com/docs/static/logo.png", "https://example.com/favicon.ico"]
+        );
+        assert_eq!(icon_urls("https://example.com/docs/", None), ["https://example.com/favicon.ico"]);
+    }
+
+    #[test]
+    fn test_icon_png_resizes() {
+        let mut buffer = Cursor::new(Vec::new());
+        image::RgbaImage::new(64, 48)
+            .write_to(&mut buffer, image::ImageFormat::Png)
+            .unwrap();
+        let icon = image::load_from_memory(&icon_png(buffer.get_ref()).unwrap()).unwrap();
+        assert_eq!((icon.width(), icon.height()), (ICON_SIZE, ICON_SIZE));
+        assert!(icon_png(b"<html>not an image</html>").is_none());
+    }
+}
@@ -0,0 +1,165 @@
+//! 请求失败时的重试
+//!
+//! 连接错误、超时、429 和 5xx 响应视为暂时性的失败：页面重新放回抓取队列，
+//! 再次请求前按指数退避等待，每个 URL 最多重试 `max_retries` 次。重试用完后
+//! 仍然失败的页面记录在文档目录的 `failed_urls.json` 中，之后可以只重新抓取
+//! 这些页面。其他 4xx 响应不会重试。
+
+use crate::core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// 重试用完后仍然失败的页面列表文件名
+pub const FAILED_URLS_FILENAME: &str = "failed_urls.json";
+
+/// 第一次重试前的等待时间，之后每次加倍
+pub const BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// 重试前最长的等待时间
+pub const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// 状态码是否表示暂时性的失败
+///
+/// 501 表示服务器不支持该请求，重试也不会成功，不算在内。
+pub fn is_transient_status(status: u16) -> bool {
+    status == 429 || ((500..600).contains(&status) && status != 501)
+}
+
+/// 第 `retry` 次重试（从 1 开始）前的等待时间
+pub fn backoff_delay(retry: u32) -> Duration {
+    let factor = 1u32 << retry.saturating_sub(1).min(16);
+    BASE_DELAY.saturating_mul(factor).min(MAX_DELAY)
+}
+
+/// 重试用完后仍然失败的页面
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedUrl {
+    /// 页面URL
+    pub url: String,
+    /// 页面路径
+    pub path: String,
+    /// 请求的总次数
+    pub attempts: u32,
+    /// 最后一次失败的原因
+    pub error: String,
+}
+
+/// 每个 URL 的失败次数和最终失败的页面
+#[derive(Debug, Default)]
+pub struct FetchRetries {
+    /// 每个 URL 最多重试的次数
+    max_retries: u32,
+    /// 每个 URL 已经失败的次数
+    failures: HashMap<String, u32>,
+    /// 重试用完后仍然失败的页面
+    failed: Vec<FailedUrl>,
+}
+
+impl FetchRetries {
+    /// 创建每个 URL 最多重试 `max_retries` 次的记录
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// 再次请求 URL 前需要等待的时间，还没有失败过时为 None
+    pub fn delay(&self, url: &str) -> Option<Duration> {
+        self.failures.get(url).map(|&failures| backoff_delay(failures))
+    }
+
+    /// 记录一次暂时性的失败，还可以重试时返回 true，否则记录为最终失败
+    pub fn record_failure(&mut self, url: &str, path: &str, error: &str) -> bool {
+        let failures = self.failures.entry(url.to_string()).or_insert(0);
+        *failures += 1;
+        if *failures <= self.max_retries {
+            return true;
+        }
+        self.failed.push(FailedUrl {
+            url: url.to_string(),
+            path: path.to_string(),
+            attempts: *failures,
+            error: error.to_string(),
+        });
+        false
+    }
+
+    /// 重试用完后仍然失败的页面
+    pub fn failed(&self) -> &[FailedUrl] {
+        &self.failed
+    }
+
+    /// 将最终失败的页面写入 `failed_urls.json`，没有失败的页面时删除上次留下的文件
+    pub fn save(&self, doc_dir: &Path) -> Result<()> {
+        let path = doc_dir.join(FAILED_URLS_FILENAME);
+        if self.failed.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&self.failed)
+            .map_err(|e| Error::Message(format!("无法序列化失败的页面列表: {}", e)))?;
+        std::fs::write(&path, json)
+            .map_err(|e| Error::Message(format!("无法写入 {} 文件: {}", FAILED_URLS_FILENAME, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_transient_statuses() {
+        for status in [429, 500, 502, 503, 504, 599] {
+            assert!(is_transient_status(status), "{}", status);
+        }
+        for status in [200, 301, 400, 403, 404, 410, 501] {
+            assert!(!is_transient_status(status), "{}", status);
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        assert_eq!(backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3), Duration::from_secs(2));
+        assert_eq!(backoff_delay(10), MAX_DELAY);
+        assert_eq!(backoff_delay(u32::MAX), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_failures_counted_per_url() {
+        let dir = TempDir::new().unwrap();
+        let mut retries = FetchRetries::new(2);
+        assert_eq!(retries.delay("https://example.com/a"), None);
+
+        assert!(retries.record_failure("https://example.com/a", "a", "HTTP 503"));
+        assert_eq!(retries.delay("https://example.com/a"), Some(BASE_DELAY));
+        assert!(retries.record_failure("https://example.com/b", "b", "HTTP 503"));
+        assert!(retries.record_failure("https://example.com/a", "a", "HTTP 503"));
+        assert!(!retries.record_failure("https://example.com/a", "a", "请求失败: timeout"));
+        assert_eq!(
+            retries.failed(),
+            [FailedUrl {
+                url: "https://example.com/a".to_string(),
+                path: "a".to_string(),
+                attempts: 3,
+                error: "请求失败: timeout".to_string(),
+            }]
+        );
+
+        retries.save(dir.path()).unwrap();
+        let saved: Vec<FailedUrl> =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(FAILED_URLS_FILENAME)).unwrap()).unwrap();
+        assert_eq!(saved, retries.failed());
+
+        // 之后没有失败的抓取删除旧的列表
+        FetchRetries::new(2).save(dir.path()).unwrap();
+        assert!(!dir.path().join(FAILED_URLS_FILENAME).exists());
+    }
+}
@@ -1,11 +1,74 @@
 //! HTML过滤器定义
 //! 参考 Ruby 版本 filter.rb 重新设计
+//!
+//! 过滤器通过 [`Filter::apply_v2`] 读取只读的上下文，把新的 HTML 和对上下文的
+//! 修改放在 [`FilterOutput`] 中返回，由 [`FilterContext::merge_output`] 按固定规则
+//! 合并，避免一个过滤器覆盖另一个过滤器设置的字段。直接修改上下文的
+//! [`Filter::apply`] 已弃用：只实现了它的过滤器由 `apply_v2` 的默认实现在
+//! 上下文的副本上调用，只有副本中的变化会被合并回来。
 
 use crate::core::error::Result;
-use scraper::{ElementRef, Html, Selector};
+use crate::core::filters::try_selector;
+use scraper::{ElementRef, Html};
+use serde::Serialize;
 use std::any::Any;
+use std::cell::{OnceCell, RefCell};
 use std::collections::HashMap;
 
+thread_local! {
+    /// 正在通过默认的 `apply` 调用 `apply_v2` 的过滤器，用于发现两个方法都没有实现的情况
+    static DEFAULT_APPLY: RefCell<Vec<(usize, &'static str)>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(test)]
+thread_local! {
+    /// 测试中统计 `html_doc` 实际解析页面的次数
+    pub(crate) static PARSE_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// 过滤器发现的问题，不影响页面继续处理
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterWarning {
+    /// 过滤器名称
+    pub filter: String,
+    /// 警告信息
+    pub message: String,
+    /// 相关的选择器
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// 相关的URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 产生警告的页面URL，由抓取报告记录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+}
+
+impl FilterWarning {
+    /// 创建新的警告
+    pub fn new(filter: &str, message: &str) -> Self {
+        Self {
+            filter: filter.to_string(),
+            message: message.to_string(),
+            selector: None,
+            url: None,
+            page: None,
+        }
+    }
+
+    /// 设置相关的选择器
+    pub fn with_selector(mut self, selector: &str) -> Self {
+        self.selector = Some(selector.to_string());
+        self
+    }
+
+    /// 设置相关的URL
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+}
+
 /// 过滤器上下文，包含过滤时需要的上下文信息
 #[derive(Default, Clone)]
 pub struct FilterContext {
@@ -13,7 +76,7 @@ pub struct FilterContext {
     pub options: HashMap<String, String>,
     /// 基础URL
     pub base_url: String,
-    /// 链接列表
+    /// 链接列表（过滤器收集的绝对URL，非空时抓取器用它代替从过滤后页面中提取的链接）
     pub links: Vec<String>,
 
     /// 根URL
@@ -34,15 +97,25 @@ pub struct FilterContext {
     pub current_url: String,
     /// 归属信息
     pub attribution: Option<String>,
+    /// 内容的许可证（SPDX 标识）
+    pub license: Option<String>,
 
-    /// 当前页面的HTML内容
-    pub html: String,
+    /// 当前页面的HTML内容，通过 `html()` 读取、`set_html()` 修改
+    html: String,
     /// 页面标题
     pub title: String,
     /// 处理后的内容
     pub content: String,
     /// 附加条目
     pub additional_entries: Vec<(String, String, String)>,
+    /// 页面目录，保存到页面的 `toc` 中
+    pub toc: Vec<serde_json::Value>,
+    /// 页面描述的属性是否继承（由条目过滤器读取，写入页面条目的 `inherited`）
+    pub inherited: Option<bool>,
+    /// 过滤过程中收集的警告
+    pub warnings: Vec<FilterWarning>,
+    /// `html` 的解析结果，由 `html_doc()` 在首次访问时生成，`set_html()` 修改内容时丢弃
+    parsed_html: OnceCell<Html>,
 }
 
 impl FilterContext {
@@ -61,28 +134,229 @@ impl FilterContext {
     pub fn get_option(&self, key: &str) -> Option<&String> {
         self.options.get(key)
     }
+
+    /// 设置基础URL
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// 设置当前HTML
+    pub fn with_html(mut self, html: &str) -> Self {
+        self.set_html(html.to_string());
+        self
+    }
+
+    /// 获取当前HTML
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+
+    /// 获取当前HTML的解析结果
+    ///
+    /// 同一份HTML只解析一次，之后的过滤器共享同一个DOM。
+    pub fn html_doc(&self) -> &Html {
+        self.parsed_html.get_or_init(|| {
+            #[cfg(test)]
+            PARSE_COUNT.with(|count| count.set(count.get() + 1));
+            Html::parse_document(&self.html)
+        })
+    }
+
+    /// 更新当前HTML，内容变化时丢弃缓存的解析结果
+    pub fn set_html(&mut self, html: String) {
+        if html != self.html {
+            self.html = html;
+            self.parsed_html = OnceCell::new();
+        }
+    }
+
+    /// 合并过滤器 `filter` 的输出
+    ///
+    /// - HTML 替换为输出中的 HTML
+    /// - 条目、警告和链接追加在已有内容之后
+    /// - 标题、内容、目录和属性继承由最后一个设置的过滤器决定；标题覆盖了
+    ///   之前过滤器设置的不同标题时记录一条警告
+    pub fn merge_output(&mut self, filter: &str, output: FilterOutput) {
+        self.set_html(output.html);
+        if let Some(title) = output.title {
+            if !self.title.is_empty() && self.title != title {
+                self.warnings.push(FilterWarning::new(
+                    filter,
+                    &format!("标题 {:?} 被覆盖为 {:?}", self.title, title),
+                ));
+            }
+            self.title = title;
+        }
+        if let Some(content) = output.content {
+            self.content = content;
+        }
+        if let Some(toc) = output.toc {
+            self.toc = toc;
+        }
+        if output.inherited.is_some() {
+            self.inherited = output.inherited;
+        }
+        self.additional_entries.extend(output.entries);
+        self.warnings.extend(output.warnings);
+        self.links.extend(output.links);
+    }
+}
+
+/// 过滤器的输出：新的 HTML 和需要合并到上下文中的修改
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterOutput {
+    /// 过滤后的 HTML
+    pub html: String,
+    /// 页面标题，None 表示不修改
+    pub title: Option<String>,
+    /// 页面目录，None 表示不修改
+    pub toc: Option<Vec<serde_json::Value>>,
+    /// 页面内容，None 表示不修改
+    pub content: Option<String>,
+    /// 页面描述的属性是否继承，None 表示不修改
+    pub inherited: Option<bool>,
+    /// 新增的警告
+    pub warnings: Vec<FilterWarning>,
+    /// 新增的附加条目（名称、路径、类型）
+    pub entries: Vec<(String, String, String)>,
+    /// 新增的链接
+    pub links: Vec<String>,
+}
+
+impl FilterOutput {
+    /// 只修改 HTML 的输出
+    pub fn new(html: impl Into<String>) -> Self {
+        Self {
+            html: html.into(),
+            ..Self::default()
+        }
+    }
+
+    /// 设置页面标题
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// 设置页面目录
+    pub fn with_toc(mut self, toc: Vec<serde_json::Value>) -> Self {
+        self.toc = Some(toc);
+        self
+    }
+
+    /// 设置页面内容
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// 设置属性是否继承
+    pub fn with_inherited(mut self, inherited: Option<bool>) -> Self {
+        self.inherited = inherited;
+        self
+    }
+
+    /// 添加警告
+    pub fn with_warnings(mut self, warnings: impl IntoIterator<Item = FilterWarning>) -> Self {
+        self.warnings.extend(warnings);
+        self
+    }
+
+    /// 添加附加条目
+    pub fn with_entries(mut self, entries: impl IntoIterator<Item = (String, String, String)>) -> Self {
+        self.entries.extend(entries);
+        self
+    }
+
+    /// 添加链接
+    pub fn with_links(mut self, links: impl IntoIterator<Item = String>) -> Self {
+        self.links.extend(links);
+        self
+    }
+
+    /// 比较旧版过滤器运行前后的上下文，生成等价的输出
+    ///
+    /// 列表只取新增的部分，对其他字段（如 `current_path`）的修改被丢弃。
+    fn from_changes(before: &FilterContext, mut after: FilterContext, html: String) -> Self {
+        fn added<T>(before: &[T], after: &mut Vec<T>) -> Vec<T> {
+            after.split_off(before.len().min(after.len()))
+        }
+        Self {
+            html,
+            title: (after.title != before.title).then(|| std::mem::take(&mut after.title)),
+            toc: (after.toc != before.toc).then(|| std::mem::take(&mut after.toc)),
+            content: (after.content != before.content).then(|| std::mem::take(&mut after.content)),
+            inherited: after.inherited.filter(|_| after.inherited != before.inherited),
+            warnings: added(&before.warnings, &mut after.warnings),
+            entries: added(&before.additional_entries, &mut after.additional_entries),
+            links: added(&before.links, &mut after.links),
+        }
+    }
 }
 
 /// 对HTML内容进行过滤函数的特质
+///
+/// 过滤器至少要实现 [`Filter::apply_v2`] 和 [`Filter::apply`] 中的一个，
+/// 两者的默认实现互相调用，都不实现时在第一次应用过滤器时 panic。
 pub trait Filter: Send + Sync + 'static {
-    /// 应用过滤器到HTML内容
-    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String>;
+    /// 应用过滤器，返回新的 HTML 和对上下文的修改
+    ///
+    /// 默认实现在上下文的副本上调用旧版的 [`Filter::apply`]，把副本中的变化转换为输出
+    /// （见 [`FilterOutput`]）：列表只取新增的部分，对其他字段的修改被丢弃。
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        let id = (self as *const Self as *const () as usize, std::any::type_name::<Self>());
+        assert!(
+            !DEFAULT_APPLY.with(|active| active.borrow().contains(&id)),
+            "过滤器 {} 必须实现 apply_v2 或 apply",
+            self.name()
+        );
+        let mut scratch = context.clone();
+        #[allow(deprecated)]
+        let html = self.apply(html, &mut scratch)?;
+        Ok(FilterOutput::from_changes(context, scratch, html))
+    }
+
+    /// 应用过滤器到HTML内容，直接修改上下文
+    ///
+    /// 默认实现调用 [`Filter::apply_v2`] 并将输出合并到上下文中。
+    #[deprecated(note = "请实现 apply_v2，通过 FilterOutput 返回对上下文的修改")]
+    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+        let id = (self as *const Self as *const () as usize, std::any::type_name::<Self>());
+        DEFAULT_APPLY.with(|active| active.borrow_mut().push(id));
+        let output = self.apply_v2(html, context);
+        DEFAULT_APPLY.with(|active| active.borrow_mut().pop());
+        let output = output?;
+        let html = output.html.clone();
+        context.merge_output(self.name(), output);
+        Ok(html)
+    }
 
     /// 创建过滤器的克隆
     fn box_clone(&self) -> Box<dyn Filter>;
 
+    /// 获取过滤器名称，用于日志和抓取报告
+    fn name(&self) -> &'static str {
+        let full_name = std::any::type_name::<Self>();
+        let base_name = full_name.split('<').next().unwrap_or(full_name);
+        base_name.rsplit("::").next().unwrap_or(base_name)
+    }
+
+    /// 创建以当前过滤器命名的警告
+    fn warning(&self, message: &str) -> FilterWarning {
+        FilterWarning::new(self.name(), message)
+    }
+
     /// 获取所有匹配的元素
     fn css<'a>(&self, doc: &'a Html, selector: &str) -> Vec<ElementRef<'a>> {
-        Selector::parse(selector)
+        try_selector(selector)
             .map(|selector| doc.select(&selector).collect())
             .unwrap_or_default()
     }
 
     /// 获取第一个匹配的元素
     fn at_css<'a>(&self, doc: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
-        Selector::parse(selector)
-            .ok()
-            .and_then(move |selector| doc.select(&selector).next())
+        try_selector(selector).and_then(move |selector| doc.select(&selector).next())
     }
 
     /// 获取当前URL的子路径
@@ -144,10 +418,11 @@ pub trait Filter: Send + Sync + 'static {
     /// 获取条目信息
     /// 默认返回空集合，但子类可以重写以提供条目信息
     ///
-    /// # 参数
+    /// 页面HTML可通过 `context.html_doc()` 获取，无需再次解析。
     ///
-    /// * `html` - 要处理的HTML内容字符串
-    /// * `context` - 过滤器上下文，包含URL、路径等信息
+    /// 不兼容的修改：旧版签名 `get_entries(&self, html, context)` 已移除，Rust 的特质
+    /// 方法不能重载，无法在同一名称下保留旧签名。实现旧签名的过滤器去掉 `html` 参数，
+    /// 改用 `context.html()` 或 `context.html_doc()`。
     ///
     /// # 返回
     ///
@@ -155,7 +430,7 @@ pub trait Filter: Send + Sync + 'static {
     /// * 条目名称
     /// * 条目路径
     /// * 条目类型
-    fn get_entries(&self, _html: &str, _context: &FilterContext) -> Vec<(String, String, String)> {
+    fn get_entries(&self, _context: &FilterContext) -> Vec<(String, String, String)> {
         Vec::new()
     }
 
@@ -178,8 +453,8 @@ impl<T: Clone + 'static> DefaultFilter<T> {
 }
 
 impl<T: Clone + 'static + Send + Sync> Filter for DefaultFilter<T> {
-    fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
-        Ok(html.to_string())
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+        Ok(FilterOutput::new(html))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
@@ -1,12 +1,32 @@
 //! 修复重定向行为
 //! 参考 Ruby 版本的 FixRedirectionsBehavior 模块实现
+//!
+//! 抓取时记录每个网址的重定向，结束后页面移到最终的路径，页面路径的重定向
+//! 保存在文档目录的 `redirections.json` 中。页面中指向重定向前路径的内部链接
+//! 和 entries.json、index.json 中的条目路径都改为最终路径，否则浏览文档时
+//! 这些链接会找不到页面。对已抓取的文档可以用 `docs fix-redirects` 单独执行。
 
+use crate::core::doc::{DB_FILENAME, INDEX_FILENAME, META_FILENAME};
 use crate::core::error::{Error, Result};
+use crate::core::filters::serialize_html;
+use crate::core::page_db::PageDb;
+use crate::core::types::{DocSlug, PageKey};
+use kuchiki::traits::TendrilSink;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use url::Url;
 
+/// 文档目录中记录页面路径重定向的文件名
+pub const REDIRECTIONS_FILENAME: &str = "redirections.json";
+
+/// 条目列表文件名
+const ENTRIES_FILENAME: &str = "entries.json";
+
+/// 解析相对链接时使用的主机，只用于得到链接的路径
+const LINK_BASE: &str = "http://doc.invalid";
+
 /// 重定向映射类型
 pub type Redirections = Arc<Mutex<HashMap<String, String>>>;
 
@@ -84,3 +104,276 @@ impl FixRedirections {
         Ok(())
     }
 }
+
+/// 页面路径的重定向：重定向前的页面路径到最终路径
+pub type PathRedirections = HashMap<PageKey, PageKey>;
+
+/// 对已保存的文档应用重定向的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixSummary {
+    /// 移到最终路径的页面数
+    pub moved_pages: usize,
+    /// 改写了链接的页面数
+    pub rewritten_pages: usize,
+    /// 改写了路径的条目数
+    pub rewritten_entries: usize,
+}
+
+/// 按重定向查找页面的最终路径，先按原样查找，再按小写查找
+fn redirect_target<'a>(redirects: &'a PathRedirections, path: &PageKey) -> Option<&'a PageKey> {
+    redirects
+        .get(path)
+        .or_else(|| redirects.get(&PageKey::new(&path.as_str().to_lowercase())))
+}
+
+/// 条目路径中的页面部分指向重定向前的路径时返回改写后的路径，锚点保持不变
+pub fn redirect_entry_path(path: &str, redirects: &PathRedirections) -> Option<String> {
+    let (page, fragment) = match path.split_once('#') {
+        Some((page, fragment)) => (page, Some(fragment)),
+        None => (path, None),
+    };
+    redirect_target(redirects, &PageKey::new(page)).map(|target| target.entry_path(fragment))
+}
+
+/// 改写页面中指向重定向前路径的内部链接，页面有改动时返回新的 HTML
+///
+/// 链接按页面的地址 `<doc_prefix><page_path>` 解析，只改写解析后位于 `doc_prefix`
+/// 之下的链接，改为 `<doc_prefix><最终路径>`，锚点保持不变。指向其他网站的链接不变。
+pub fn rewrite_links(html: &str, page_path: &str, doc_prefix: &str, redirects: &PathRedirections) -> Option<String> {
+    if redirects.is_empty() || !html.contains("href") {
+        return None;
+    }
+    let page_url = Url::parse(&format!("{}{}{}", LINK_BASE, doc_prefix, page_path)).ok()?;
+
+    let document = kuchiki::parse_html().one(html);
+    let mut changed = false;
+    if let Ok(nodes) = document.select("a[href], area[href]") {
+        for node in nodes {
+            let mut attributes = node.attributes.borrow_mut();
+            let Some(href) = attributes.get("href").map(str::to_string) else {
+                continue;
+            };
+            if href.starts_with('#') {
+                continue;
+            }
+            let Ok(url) = page_url.join(&href) else {
+                continue;
+            };
+            if url.origin() != page_url.origin() {
+                continue;
+            }
+            let Some(target) = url.path().strip_prefix(doc_prefix) else {
+                continue;
+            };
+            let Some(target) = redirect_target(redirects, &PageKey::new(target)) else {
+                continue;
+            };
+            let rewritten = match url.fragment() {
+                Some(fragment) => format!("{}{}#{}", doc_prefix, target, fragment),
+                None => format!("{}{}", doc_prefix, target),
+            };
+            attributes.insert("href", rewritten);
+            changed = true;
+        }
+    }
+
+    changed.then(|| serialize_html(html, &document))
+}
+
+/// 将页面路径的重定向写入 `redirections.json`，没有重定向时删除上次留下的文件
+pub fn save_redirections(doc_dir: &Path, redirects: &PathRedirections) -> Result<()> {
+    let path = doc_dir.join(REDIRECTIONS_FILENAME);
+    if redirects.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let sorted: std::collections::BTreeMap<&str, &str> = redirects
+        .iter()
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+    let json = serde_json::to_string_pretty(&sorted)
+        .map_err(|e| Error::Message(format!("无法序列化重定向: {}", e)))?;
+    std::fs::write(&path, json)
+        .map_err(|e| Error::Message(format!("无法写入 {} 文件: {}", REDIRECTIONS_FILENAME, e)))
+}
+
+/// 读取 `redirections.json`，文件不存在时返回空的映射
+pub fn load_redirections(doc_dir: &Path) -> Result<PathRedirections> {
+    let path = doc_dir.join(REDIRECTIONS_FILENAME);
+    if !path.exists() {
+        return Ok(PathRedirections::new());
+    }
+    let json = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&json).map_err(|e| Error::Doc(format!("无法读取 {:?}: {}", path, e)))
+}
+
+/// 对已保存的文档应用 `redirections.json` 中记录的重定向
+///
+/// 仍在重定向前路径下的页面移到最终路径，改写 db.json 中页面的内部链接，以及
+/// entries.json 和 index.json 中的条目路径。只有内容变化的文件会被改写。
+pub fn fix_doc_dir(doc_dir: &Path) -> Result<FixSummary> {
+    let redirects = load_redirections(doc_dir)?;
+    let mut summary = FixSummary::default();
+    if redirects.is_empty() {
+        return Ok(summary);
+    }
+    let doc_prefix = doc_prefix(doc_dir);
+
+    let db_path = doc_dir.join(DB_FILENAME);
+    let mut db = PageDb::load(&db_path)?;
+    let pages = db.pages_mut();
+    for (from, to) in &redirects {
+        if pages.contains_key(to) {
+            continue;
+        }
+        if let Some(page) = pages.remove(from) {
+            pages.insert(to.clone(), page);
+            summary.moved_pages += 1;
+        }
+    }
+    for (path, page) in pages.iter_mut() {
+        if let Some(content) = rewrite_links(&page.content, path.as_str(), &doc_prefix, &redirects) {
+            page.content = content;
+            summary.rewritten_pages += 1;
+        }
+    }
+    if summary.moved_pages > 0 || summary.rewritten_pages > 0 {
+        db.save(&db_path)?;
+    }
+
+    // entries.json 为 [名称, 路径, 类型] 的列表，index.json 中的条目为带 path 字段的对象
+    let entries_path = doc_dir.join(ENTRIES_FILENAME);
+    if entries_path.exists() {
+        let mut entries: Vec<(String, String, String)> = read_json(&entries_path)?;
+        let mut changed = false;
+        for (_, path, _) in entries.iter_mut() {
+            if let Some(target) = redirect_entry_path(path, &redirects) {
+                *path = target;
+                changed = true;
+            }
+        }
+        if changed {
+            write_json(&entries_path, &entries)?;
+        }
+    }
+
+    let index_path = doc_dir.join(INDEX_FILENAME);
+    if index_path.exists() {
+        let mut index: serde_json::Value = read_json(&index_path)?;
+        let mut changed = false;
+        if let Some(entries) = index.get_mut("entries").and_then(|entries| entries.as_array_mut()) {
+            for entry in entries {
+                let Some(path) = entry.get("path").and_then(|path| path.as_str()) else {
+                    continue;
+                };
+                if let Some(target) = redirect_entry_path(path, &redirects) {
+                    entry["path"] = target.into();
+                    summary.rewritten_entries += 1;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            write_json(&index_path, &index)?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// 文档页面的地址前缀，slug 取自 meta.json，没有记录时取目录名中 `~` 之前的部分
+fn doc_prefix(doc_dir: &Path) -> String {
+    let slug = std::fs::read_to_string(doc_dir.join(META_FILENAME))
+        .ok()
+        .and_then(|meta| serde_json::from_str::<serde_json::Value>(&meta).ok())
+        .and_then(|meta| meta["slug"].as_str().map(str::to_string))
+        .unwrap_or_else(|| {
+            let dir_name = doc_dir.file_name().unwrap_or_default().to_string_lossy();
+            dir_name.split(['~', '@']).next().unwrap_or_default().to_string()
+        });
+    DocSlug::new(&slug).docs_prefix()
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let json = std::fs::read_to_string(path)?;
+    serde_json::from_str(&json).map_err(|e| Error::Doc(format!("无法读取 {:?}: {}", path, e)))
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| Error::Message(format!("无法序列化 {:?}: {}", path, e)))?;
+    std::fs::write(path, json).map_err(|e| Error::Message(format!("无法写入 {:?}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn redirects() -> PathRedirections {
+        HashMap::from([(PageKey::new("guide/old"), PageKey::new("guide/new"))])
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let html = concat!(
+            r#"<p><a href="/docs/css/guide/old#x">a</a> <a href="old">b</a> "#,
+            r#"<a href="/docs/html/guide/old">c</a> <a href="https://example.com/guide/old">d</a> "#,
+            r#"<a href="#old">e</a></p>"#,
+        );
+        assert_eq!(
+            rewrite_links(html, "guide/intro", "/docs/css/", &redirects()).unwrap(),
+            concat!(
+                r#"<p><a href="/docs/css/guide/new#x">a</a> <a href="/docs/css/guide/new">b</a> "#,
+                r#"<a href="/docs/html/guide/old">c</a> <a href="https://example.com/guide/old">d</a> "#,
+                r#"<a href="#old">e</a></p>"#,
+            )
+        );
+        assert_eq!(rewrite_links(r#"<a href="new">n</a>"#, "guide/intro", "/docs/css/", &redirects()), None);
+        assert_eq!(redirect_entry_path("guide/old#top", &redirects()).as_deref(), Some("guide/new#top"));
+        assert_eq!(redirect_entry_path("guide/new", &redirects()), None);
+    }
+
+    #[test]
+    fn test_fix_doc_dir() {
+        let dir = TempDir::new().unwrap();
+        let doc_dir = dir.path().join("css~3");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join(META_FILENAME), r#"{"slug":"css"}"#).unwrap();
+        let mut db = PageDb::new();
+        db.add(PageKey::new("index"), r#"<a href="guide/old#x">Old</a>"#.to_string());
+        db.add(PageKey::new("guide/old"), "<p>Moved</p>".to_string());
+        db.save(&doc_dir.join(DB_FILENAME)).unwrap();
+        std::fs::write(doc_dir.join(ENTRIES_FILENAME), r#"[["Old","guide/old#x","Guide"]]"#).unwrap();
+        std::fs::write(
+            doc_dir.join(INDEX_FILENAME),
+            r#"{"entries":[{"name":"Old","path":"guide/old#x","type":"Guide"}],"types":[]}"#,
+        )
+        .unwrap();
+
+        // 没有记录重定向时不做任何改动
+        assert_eq!(fix_doc_dir(&doc_dir).unwrap(), FixSummary::default());
+
+        save_redirections(&doc_dir, &redirects()).unwrap();
+        assert_eq!(
+            fix_doc_dir(&doc_dir).unwrap(),
+            FixSummary {
+                moved_pages: 1,
+                rewritten_pages: 1,
+                rewritten_entries: 1,
+            }
+        );
+        let db = PageDb::load(&doc_dir.join(DB_FILENAME)).unwrap();
+        assert_eq!(db.get(&PageKey::new("index")).unwrap(), r#"<a href="/docs/css/guide/new#x">Old</a>"#);
+        assert!(db.has(&PageKey::new("guide/new")));
+        let entries: Vec<(String, String, String)> = read_json(&doc_dir.join(ENTRIES_FILENAME)).unwrap();
+        assert_eq!(entries[0].1, "guide/new#x");
+        let index: serde_json::Value = read_json(&doc_dir.join(INDEX_FILENAME)).unwrap();
+        assert_eq!(index["entries"][0]["path"], "guide/new#x");
+
+        // 再次执行时已没有需要改写的内容
+        assert_eq!(fix_doc_dir(&doc_dir).unwrap(), FixSummary::default());
+    }
+}
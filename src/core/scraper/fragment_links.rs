@@ -0,0 +1,309 @@
+//! 链接锚点校验
+//!
+//! 内部链接改写为文档路径后，`page#Fragment` 中的锚点仍然沿用原网站的写法，
+//! 常常与目标页面中元素的 id 在大小写或编码上不一致（`#Syntax` 与 `id="syntax"`、
+//! `#Return%20value` 与 `id="Return_value"`）。抓取结束后收集每个页面的 id
+//! （元素的 `id`、`<a name>` 以及页面目录中的 id），按规范化后的写法查找并修正
+//! 链接中的锚点。找不到的锚点记录在抓取报告中，可以选择从链接中去掉。
+
+use crate::core::filters::serialize_html;
+use crate::core::page_db::StoredPage;
+use crate::core::types::PageKey;
+use kuchiki::traits::TendrilSink;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// 指向不存在的锚点的链接
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenFragment {
+    /// 链接所在页面的路径
+    pub page: String,
+    /// 链接原来的 href
+    pub href: String,
+    /// 链接指向的页面路径
+    pub target: String,
+    /// 找不到的锚点
+    pub fragment: String,
+    /// 是否已从链接中去掉锚点
+    pub stripped: bool,
+}
+
+/// 锚点的查找结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FragmentMatch {
+    /// 与页面中的 id 完全一致
+    Exact,
+    /// 规范化后与页面中的 id 一致，应改为该 id
+    Corrected(String),
+    /// 页面中没有对应的 id
+    Missing,
+    /// 目标页面不在本次抓取的页面中，无法校验
+    UnknownPage,
+}
+
+/// 单个页面的 id
+#[derive(Debug, Clone, Default)]
+struct PageAnchors {
+    ids: HashSet<String>,
+    /// 规范化后的写法到页面中第一个对应 id 的映射
+    normalized: HashMap<String, String>,
+}
+
+/// 所有页面的 id 索引
+#[derive(Debug, Clone, Default)]
+pub struct FragmentIndex {
+    pages: HashMap<String, PageAnchors>,
+}
+
+impl FragmentIndex {
+    /// 创建空的索引
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从页面内容和页面目录中收集 id
+    pub fn from_pages(pages: &HashMap<PageKey, StoredPage>) -> Self {
+        let mut index = Self::new();
+        for (path, page) in pages {
+            let toc_ids = page
+                .toc
+                .iter()
+                .filter_map(|item| item.get("id").and_then(|id| id.as_str()))
+                .map(str::to_string);
+            index.add_page(path.as_str(), element_ids(&page.content).into_iter().chain(toc_ids));
+        }
+        index
+    }
+
+    /// 添加页面及其 id
+    pub fn add_page<I: IntoIterator<Item = String>>(&mut self, path: &str, ids: I) {
+        let anchors = self.pages.entry(path.to_string()).or_default();
+        for id in ids {
+            if id.is_empty() {
+                continue;
+            }
+            anchors.normalized.entry(normalize_fragment(&id)).or_insert_with(|| id.clone());
+            anchors.ids.insert(id);
+        }
+    }
+
+    /// 在页面中查找锚点，`fragment` 为链接中原样的写法（可能经过百分号编码）
+    pub fn find(&self, path: &str, fragment: &str) -> FragmentMatch {
+        let Some(anchors) = self.pages.get(path) else {
+            return FragmentMatch::UnknownPage;
+        };
+        if anchors.ids.contains(fragment) {
+            return FragmentMatch::Exact;
+        }
+        let decoded = percent_decode(fragment);
+        if anchors.ids.contains(&decoded) {
+            return FragmentMatch::Exact;
+        }
+        match anchors.normalized.get(&normalize_fragment(&decoded)) {
+            Some(id) => FragmentMatch::Corrected(id.clone()),
+            None => FragmentMatch::Missing,
+        }
+    }
+}
+
+/// 锚点的规范化写法：解码百分号编码，转为小写，空白和下划线视为连字符
+pub fn normalize_fragment(fragment: &str) -> String {
+    let decoded = percent_decode(fragment);
+    let mut normalized = String::with_capacity(decoded.len());
+    for c in decoded.trim().chars() {
+        if c.is_whitespace() || c == '_' || c == '-' {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.extend(c.to_lowercase());
+        }
+    }
+    normalized
+}
+
+/// 收集页面中元素的 `id` 和 `<a name>`
+pub fn element_ids(html: &str) -> Vec<String> {
+    let document = kuchiki::parse_html().one(html);
+    let mut ids = Vec::new();
+    if let Ok(nodes) = document.select("[id], a[name]") {
+        for node in nodes {
+            let attributes = node.attributes.borrow();
+            ids.extend(attributes.get("id").map(str::to_string));
+            if &*node.name.local == "a" {
+                ids.extend(attributes.get("name").map(str::to_string));
+            }
+        }
+    }
+    ids
+}
+
+/// 校验并修正页面中内部链接的锚点
+///
+/// `resolve` 将链接中 `#` 之前的部分解析为页面路径，不是内部链接时返回 None；
+/// 只有锚点的链接（`#x`）指向当前页面。页面有改动时返回新的 HTML。
+pub fn fix_fragments<F>(
+    html: &str,
+    page_path: &str,
+    index: &FragmentIndex,
+    strip_broken: bool,
+    resolve: F,
+) -> (Option<String>, Vec<BrokenFragment>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut broken = Vec::new();
+    // 大多数页面没有需要校验的锚点，避免无谓地重新序列化
+    if !html.contains('#') {
+        return (None, broken);
+    }
+
+    let document = kuchiki::parse_html().one(html);
+    let mut changed = false;
+    if let Ok(nodes) = document.select("a[href], area[href]") {
+        for node in nodes {
+            let mut attributes = node.attributes.borrow_mut();
+            let Some(href) = attributes.get("href").map(str::to_string) else {
+                continue;
+            };
+            let Some((link, fragment)) = href.split_once('#') else {
+                continue;
+            };
+            if fragment.is_empty() {
+                continue;
+            }
+            let target = if link.is_empty() {
+                Some(page_path.to_string())
+            } else {
+                resolve(link)
+            };
+            let Some(target) = target else {
+                continue;
+            };
+
+            match index.find(&target, fragment) {
+                FragmentMatch::Exact | FragmentMatch::UnknownPage => {}
+                FragmentMatch::Corrected(id) => {
+                    attributes.insert("href", format!("{}#{}", link, encode_fragment(&id)));
+                    changed = true;
+                }
+                FragmentMatch::Missing => {
+                    if strip_broken {
+                        let stripped = if link.is_empty() { "#" } else { link };
+                        attributes.insert("href", stripped.to_string());
+                        changed = true;
+                    }
+                    broken.push(BrokenFragment {
+                        page: page_path.to_string(),
+                        href: href.clone(),
+                        target,
+                        fragment: percent_decode(fragment),
+                        stripped: strip_broken,
+                    });
+                }
+            }
+        }
+    }
+
+    let html = changed.then(|| serialize_html(html, &document));
+    (html, broken)
+}
+
+/// 解码百分号编码，无效的编码和解码后不是 UTF-8 的内容保持原样
+fn percent_decode(value: &str) -> String {
+    if !value.contains('%') {
+        return value.to_string();
+    }
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| value.to_string())
+}
+
+/// 编码锚点中不能直接出现在 href 里的字符
+fn encode_fragment(id: &str) -> String {
+    let mut encoded = String::with_capacity(id.len());
+    for c in id.chars() {
+        if c.is_whitespace() || matches!(c, '"' | '<' | '>' | '`' | '%') {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> FragmentIndex {
+        let mut index = FragmentIndex::new();
+        index.add_page(
+            "guide",
+            element_ids(r#"<h2 id="Return_value">R</h2><h2 id="syntax">S</h2><a name="Legacy"></a>"#),
+        );
+        index
+    }
+
+    #[test]
+    fn test_find_fragment() {
+        let index = index();
+        assert_eq!(index.find("guide", "syntax"), FragmentMatch::Exact);
+        assert_eq!(index.find("guide", "Legacy"), FragmentMatch::Exact);
+        assert_eq!(index.find("guide", "Syntax"), FragmentMatch::Corrected("syntax".to_string()));
+        assert_eq!(
+            index.find("guide", "return%20value"),
+            FragmentMatch::Corrected("Return_value".to_string())
+        );
+        assert_eq!(index.find("guide", "examples"), FragmentMatch::Missing);
+        assert_eq!(index.find("other", "syntax"), FragmentMatch::UnknownPage);
+    }
+
+    #[test]
+    fn test_fix_fragments() {
+        let index = index();
+        let html = concat!(
+            r##"<a href="/docs/x/guide#Syntax">a</a>"##,
+            r##"<a href="/docs/x/guide#Examples">b</a>"##,
+            r##"<a href="/docs/x/guide#syntax">c</a>"##,
+            r##"<a href="https://other.com/#Anything">d</a>"##,
+        );
+        let resolve = |link: &str| link.strip_prefix("/docs/x/").map(str::to_string);
+
+        let (fixed, broken) = fix_fragments(html, "index", &index, false, resolve);
+        let fixed = fixed.unwrap();
+        assert!(fixed.contains(r##"href="/docs/x/guide#syntax">a"##));
+        assert!(fixed.contains(r##"href="/docs/x/guide#Examples">b"##));
+        assert!(fixed.contains(r##"href="https://other.com/#Anything""##));
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].target, "guide");
+        assert_eq!(broken[0].fragment, "Examples");
+
+        let (fixed, broken) = fix_fragments(html, "index", &index, true, resolve);
+        assert!(fixed.unwrap().contains(r#"href="/docs/x/guide">b"#));
+        assert!(broken[0].stripped);
+
+        // 没有锚点的页面不重新序列化
+        assert_eq!(fix_fragments("<p>plain</p>", "index", &index, true, resolve), (None, vec![]));
+    }
+}
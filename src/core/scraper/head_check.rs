@@ -0,0 +1,93 @@
+//! HEAD 预检
+//!
+//! 扩展名或路径看起来像二进制文件（压缩包、安装包、视频等）的链接，在完整下载
+//! 之前先发送 HEAD 请求，根据 Content-Type 和 Content-Length 判断是否跳过，
+//! 避免为了内容类型检查下载巨大的文件。HEAD 请求失败或返回非成功状态时照常
+//! 使用 GET 请求，部分服务器不能正确处理 HEAD，可以按主机关闭预检。
+
+use serde::Serialize;
+use url::Url;
+
+/// 提示二进制内容的扩展名
+const BINARY_EXTENSIONS: &[&str] = &[
+    "7z", "apk", "bin", "bz2", "deb", "dmg", "exe", "gz", "iso", "jar", "mp3", "mp4", "msi", "ogg",
+    "pdf", "pkg", "rar", "rpm", "tar", "tgz", "wav", "webm", "whl", "xz", "zip",
+];
+
+/// 提示下载内容的路径片段
+const BINARY_PATH_SEGMENTS: &[&str] = &["download", "downloads", "releases"];
+
+/// 通过 HEAD 预检跳过的链接
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeadSkip {
+    /// 链接URL
+    pub url: String,
+    /// HEAD 响应的 Content-Type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// HEAD 响应的 Content-Length
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+}
+
+/// 链接的扩展名或路径是否提示二进制内容
+pub fn hints_binary(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    let Some(segments) = url.path_segments() else {
+        return false;
+    };
+    let segments: Vec<&str> = segments.collect();
+
+    let extension = segments
+        .last()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    if extension.as_deref().is_some_and(|extension| BINARY_EXTENSIONS.contains(&extension)) {
+        return true;
+    }
+
+    // 下载目录下没有扩展名的链接通常也是文件
+    segments.len() > 1
+        && extension.is_none()
+        && segments[..segments.len() - 1]
+            .iter()
+            .any(|segment| BINARY_PATH_SEGMENTS.contains(&segment.to_ascii_lowercase().as_str()))
+}
+
+/// 根据 HEAD 响应判断是否跳过下载
+///
+/// Content-Type 不是 HTML 时跳过；没有 Content-Type 时，Content-Length 超过
+/// `max_bytes` 的也跳过。
+pub fn should_skip(content_type: Option<&str>, content_length: Option<u64>, max_bytes: u64) -> bool {
+    match content_type {
+        Some(content_type) => !content_type.contains("text/html"),
+        None => content_length.is_some_and(|length| length > max_bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_hints() {
+        assert!(hints_binary("https://example.com/files/release-1.0.tar.gz"));
+        assert!(hints_binary("https://example.com/docs/Manual.PDF"));
+        assert!(hints_binary("https://example.com/downloads/latest"));
+        assert!(!hints_binary("https://example.com/docs/zip"));
+        assert!(!hints_binary("https://example.com/downloads/index.html"));
+        assert!(!hints_binary("https://example.com/docs/Array.prototype.map"));
+        assert!(!hints_binary("not a url"));
+    }
+
+    #[test]
+    fn test_should_skip() {
+        assert!(should_skip(Some("application/zip"), None, 1024));
+        assert!(!should_skip(Some("text/html; charset=utf-8"), Some(1 << 30), 1024));
+        assert!(should_skip(None, Some(4096), 1024));
+        assert!(!should_skip(None, Some(512), 1024));
+        assert!(!should_skip(None, None, 1024));
+    }
+}
@@ -0,0 +1,197 @@
+//! 抓取任务
+//!
+//! 记录当前正在运行的抓取任务数量，供健康检查等场景读取。服务器中的后台
+//! 抓取任务通过 [`JobRegistry`] 启动，每个任务带有取消令牌，可以查询状态
+//! 或在运行中取消。
+
+use crate::core::error::{Error, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// 正在运行的抓取任务，释放时自动减少计数
+pub struct ScrapeJob {
+    _private: (),
+}
+
+impl ScrapeJob {
+    /// 开始一个抓取任务
+    pub fn start() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+        Self { _private: () }
+    }
+}
+
+impl Drop for ScrapeJob {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 获取正在运行的抓取任务数量
+pub fn in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+/// 后台任务的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    /// 正在运行
+    Running,
+    /// 已成功完成
+    Completed,
+    /// 运行失败
+    Failed,
+    /// 已被取消
+    Cancelled,
+}
+
+/// 后台任务的状态信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobStatus {
+    /// 任务编号
+    pub id: u64,
+    /// 抓取的文档
+    pub doc: String,
+    /// 当前状态
+    pub state: JobState,
+    /// 失败时的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 开始时间（Unix 时间戳）
+    pub started_at: i64,
+    /// 结束时间（Unix 时间戳），取消时为收到取消请求的时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+}
+
+/// 后台抓取任务的注册表
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, (JobStatus, CancellationToken)>>,
+}
+
+impl JobRegistry {
+    /// 创建空的注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在后台运行任务，返回任务编号
+    ///
+    /// `task` 接收任务的取消令牌，返回 [`Error::Cancelled`] 时任务记为已取消。
+    pub fn spawn<F, Fut>(self: &Arc<Self>, doc: &str, task: F) -> u64
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let cancel = CancellationToken::new();
+        let status = JobStatus {
+            id,
+            doc: doc.to_string(),
+            state: JobState::Running,
+            error: None,
+            started_at: chrono::Utc::now().timestamp(),
+            finished_at: None,
+        };
+        self.jobs.lock().unwrap().insert(id, (status, cancel.clone()));
+
+        let future = task(cancel);
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let _job = ScrapeJob::start();
+            let result = future.await;
+            registry.finish(id, result);
+        });
+        id
+    }
+
+    /// 记录任务结束，已取消的任务保持取消状态
+    fn finish(&self, id: u64, result: Result<()>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some((status, _)) = jobs.get_mut(&id) else {
+            return;
+        };
+        if status.state == JobState::Cancelled {
+            return;
+        }
+        status.state = match &result {
+            Ok(()) => JobState::Completed,
+            Err(Error::Cancelled) => JobState::Cancelled,
+            Err(_) => JobState::Failed,
+        };
+        status.error = result.err().filter(|e| !matches!(e, Error::Cancelled)).map(|e| e.to_string());
+        status.finished_at = Some(chrono::Utc::now().timestamp());
+    }
+
+    /// 取消正在运行的任务
+    ///
+    /// 任务不存在时返回 None；已经结束的任务不受影响，返回 `Err` 和当前状态。
+    pub fn cancel(&self, id: u64) -> Option<std::result::Result<JobStatus, JobStatus>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let (status, cancel) = jobs.get_mut(&id)?;
+        if status.state != JobState::Running {
+            return Some(Err(status.clone()));
+        }
+        cancel.cancel();
+        status.state = JobState::Cancelled;
+        status.finished_at = Some(chrono::Utc::now().timestamp());
+        Some(Ok(status.clone()))
+    }
+
+    /// 任务的状态
+    pub fn status(&self, id: u64) -> Option<JobStatus> {
+        self.jobs.lock().unwrap().get(&id).map(|(status, _)| status.clone())
+    }
+
+    /// 所有任务的状态，按编号排序
+    pub fn list(&self) -> Vec<JobStatus> {
+        let mut jobs: Vec<JobStatus> = self.jobs.lock().unwrap().values().map(|(status, _)| status.clone()).collect();
+        jobs.sort_by_key(|status| status.id);
+        jobs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_running_job() {
+        let registry = Arc::new(JobRegistry::new());
+        let (stopped_tx, stopped_rx) = tokio::sync::oneshot::channel();
+        let id = registry.spawn("fixture", |cancel| async move {
+            cancel.cancelled().await;
+            let _ = stopped_tx.send(());
+            Err(Error::Cancelled)
+        });
+        let done = registry.spawn("other", |_| async { Ok(()) });
+
+        assert_eq!(registry.status(id).unwrap().state, JobState::Running);
+        let cancelled = registry.cancel(id).unwrap().unwrap();
+        assert_eq!(cancelled.state, JobState::Cancelled);
+        tokio::time::timeout(Duration::from_secs(1), stopped_rx).await.unwrap().unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(registry.status(id).unwrap().state, JobState::Cancelled);
+
+        // 已结束的任务不能取消，不存在的任务返回 None
+        while registry.status(done).unwrap().state == JobState::Running {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(registry.cancel(done).unwrap().unwrap_err().state, JobState::Completed);
+        assert!(registry.cancel(99).is_none());
+
+        let json = serde_json::to_value(registry.list()).unwrap();
+        assert_eq!(json[0]["state"], "cancelled");
+        assert_eq!(json[1]["state"], "completed");
+    }
+}
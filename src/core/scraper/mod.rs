@@ -2,14 +2,51 @@
 //! 
 //! 提供文档抓取的基础功能
 
+pub mod adaptive_concurrency;
 pub mod base;
+pub mod crawl_events;
+pub mod crawl_queue;
+pub mod crawl_report;
+pub mod crawl_state;
+pub mod disk_space;
+pub mod entry_exclusions;
+pub mod favicon;
+pub mod fetch_retry;
 pub mod filter;
+pub mod fragment_links;
+pub mod head_check;
+pub mod jobs;
+pub mod near_duplicates;
+pub mod page_log;
+pub mod page_size;
+pub mod page_stats;
+pub mod quality;
+pub mod sitemap;
+pub mod strict;
 pub mod url_scraper;
 pub mod fix_redirections;
 pub mod rate_limiter;
+pub mod raw_pages;
 
+pub use adaptive_concurrency::{AdaptiveConcurrency, ConcurrencyChange};
 pub use base::{Scraper, ScraperConfig, BaseScraper};
-pub use filter::{Filter, FilterContext};
+pub use crawl_events::{CrawlEvents, SkipReason};
+pub use crawl_queue::CrawlQueue;
+pub use crawl_report::{CrawlReport, PageFailure};
+pub use crawl_state::CrawlState;
+pub use disk_space::{DiskSpace, SystemDiskSpace};
+pub use entry_exclusions::EntryExclusions;
+pub use fetch_retry::{FailedUrl, FetchRetries};
+pub use filter::{Filter, FilterContext, FilterOutput, FilterWarning};
+pub use fragment_links::{BrokenFragment, FragmentIndex};
+pub use head_check::HeadSkip;
+pub use jobs::{JobRegistry, JobState, JobStatus, ScrapeJob};
+pub use near_duplicates::{NearDuplicate, NearDuplicateDetector};
+pub use page_size::{OversizePolicy, OversizedPage};
+pub use page_stats::DocStats;
+pub use quality::{QualityBand, QualityScore, Severity};
+pub use strict::GateFailure;
 pub use url_scraper::UrlScraper;
-pub use fix_redirections::{FixRedirections, Redirections};
+pub use fix_redirections::{FixRedirections, FixSummary, PathRedirections, Redirections};
 pub use rate_limiter::RateLimiter;
+pub use raw_pages::{RawPage, RawPages};
@@ -0,0 +1,174 @@
+//! 近似重复页面检测
+//!
+//! 打印视图、`?tab=` 等变体页面的内容几乎相同，会让文档变大并干扰搜索。
+//! 抓取时为每个页面计算 simhash 签名（基于连续三个词的片段），两个签名的
+//! 汉明距离越小页面越相似，超过阈值的页面对记录在抓取报告中。
+
+use crate::core::hash::fnv1a;
+use serde::Serialize;
+
+/// 默认的相似度阈值
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// 每个片段包含的词数
+const SHINGLE_SIZE: usize = 3;
+
+/// 词数少于此值的页面不参与检测，过短的页面签名不可靠
+const MIN_WORDS: usize = 20;
+
+/// 一对近似重复的页面
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NearDuplicate {
+    /// 路径较短的页面（去重时保留）
+    pub path: String,
+    /// 路径较长的页面
+    pub other_path: String,
+    /// 相似度（0 到 1）
+    pub similarity: f64,
+    /// 是否已去掉路径较长的页面
+    pub dropped: bool,
+}
+
+/// 计算词序列的 simhash 签名
+pub fn simhash(words: &[&str]) -> u64 {
+    let mut weights = [0i32; 64];
+    for shingle in words.windows(SHINGLE_SIZE.min(words.len()).max(1)) {
+        let hash = shingle_hash(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if (hash >> bit) & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |signature, (bit, _)| signature | (1 << bit))
+}
+
+/// 根据两个签名的汉明距离计算相似度
+pub fn similarity(a: u64, b: u64) -> f64 {
+    1.0 - (a ^ b).count_ones() as f64 / 64.0
+}
+
+/// 片段的 FNV-1a 哈希，不区分大小写，结果在不同平台和版本间保持一致
+fn shingle_hash(shingle: &[&str]) -> u64 {
+    fnv1a(shingle.join(" ").to_ascii_lowercase().as_bytes())
+}
+
+/// 近似重复页面检测器
+#[derive(Debug, Clone)]
+pub struct NearDuplicateDetector {
+    threshold: f64,
+    signatures: Vec<(String, u64)>,
+}
+
+impl Default for NearDuplicateDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIMILARITY_THRESHOLD)
+    }
+}
+
+impl NearDuplicateDetector {
+    /// 创建使用指定相似度阈值的检测器
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// 记录页面的词序列，过短的页面会被忽略
+    pub fn add(&mut self, path: &str, words: &[&str]) {
+        if words.len() >= MIN_WORDS {
+            self.signatures.push((path.to_string(), simhash(words)));
+        }
+    }
+
+    /// 找出所有相似度达到阈值的页面对，按路径排序
+    pub fn pairs(&self) -> Vec<NearDuplicate> {
+        let mut pairs = Vec::new();
+        for (i, (a, a_hash)) in self.signatures.iter().enumerate() {
+            for (b, b_hash) in &self.signatures[i + 1..] {
+                let similarity = similarity(*a_hash, *b_hash);
+                if similarity < self.threshold {
+                    continue;
+                }
+                let (path, other_path) = if (a.len(), a) <= (b.len(), b) { (a, b) } else { (b, a) };
+                pairs.push(NearDuplicate {
+                    path: path.clone(),
+                    other_path: other_path.clone(),
+                    similarity,
+                    dropped: false,
+                });
+            }
+        }
+        pairs.sort_by(|x, y| (&x.path, &x.other_path).cmp(&(&y.path, &y.other_path)));
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP_PAGE: &str = "The map method creates a new array populated with the results of calling \
+        a provided function on every element in the calling array. The callback function is invoked \
+        with three arguments: the value of the element, the index of the element, and the array object \
+        being traversed. If a thisArg parameter is provided, it will be used as the this value for each \
+        invocation of the callback. The map method is a copying method. It does not alter the original \
+        array. However, the function provided as callback can mutate the array. Note, however, that the \
+        length of the array is saved before the first invocation of callback. Elements that are appended \
+        to the array after the call to map begins will not be visited by the callback. Since map builds \
+        a new array, calling it without using the returned array is an anti-pattern; use forEach or for \
+        of instead. Browser compatibility tables are shown below for every major engine.";
+
+    const FILTER_PAGE: &str = "The filter method creates a shallow copy of a portion of a given array, \
+        filtered down to just the elements from the given array that pass the test implemented by the \
+        provided function. It calls a provided callbackFn function once for each element in an array, \
+        and constructs a new array of all the values for which callbackFn returns a truthy value. Array \
+        elements which do not pass the callbackFn test are not included in the new array. The callbackFn \
+        is invoked only for array indexes which have assigned values. It is not invoked for empty slots \
+        in sparse arrays. The filter method is a copying method and does not alter this.";
+
+    fn print_view() -> String {
+        MAP_PAGE.replace(
+            "Browser compatibility tables are shown below for every major engine.",
+            "Printable view generated for offline reading of this page.",
+        )
+    }
+
+    fn words(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+
+    #[test]
+    fn test_similar_pages_are_detected() {
+        let print = print_view();
+        let mut detector = NearDuplicateDetector::default();
+        detector.add("array/map/print", &words(&print));
+        detector.add("array/map", &words(MAP_PAGE));
+
+        let pairs = detector.pairs();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].path, "array/map");
+        assert_eq!(pairs[0].other_path, "array/map/print");
+        assert!(pairs[0].similarity >= DEFAULT_SIMILARITY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_distinct_and_short_pages_are_not_flagged() {
+        let mut detector = NearDuplicateDetector::default();
+        detector.add("array/map", &words(MAP_PAGE));
+        detector.add("array/filter", &words(FILTER_PAGE));
+        detector.add("a", &words("Page A"));
+        detector.add("b", &words("Page A"));
+
+        assert!(detector.pairs().is_empty());
+        assert!(similarity(simhash(&words(MAP_PAGE)), simhash(&words(FILTER_PAGE))) < 0.7);
+    }
+}
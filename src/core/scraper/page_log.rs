@@ -0,0 +1,263 @@
+//! 抓取输出的预写日志
+//!
+//! 每个处理完成的页面先将内容追加到 `pages.log`，再向 `pages.wal` 追加一条记录
+//! （URL、路径、内容哈希、在 `pages.log` 中的偏移和长度，以及过滤器为该页面生成的
+//! 条目）。每条记录带有校验和，
+//! 崩溃时写了一半的记录会在恢复时被丢弃，因此最多只丢失正在处理的页面。
+//! 追加的记录先写入操作系统的缓冲区，每批页面处理完后调用 [`PageLog::sync`]
+//! 写入磁盘，系统崩溃或断电时最多丢失最后一批页面。
+//! 抓取成功完成后页面写入 db.json，日志随之删除。
+
+use crate::core::error::{Error, Result};
+use crate::core::hash::fnv1a;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// 页面内容日志文件名
+pub const CONTENT_FILENAME: &str = "pages.log";
+
+/// 预写日志文件名
+pub const WAL_FILENAME: &str = "pages.wal";
+
+/// 预写日志中的一条记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PageRecord {
+    url: String,
+    path: String,
+    hash: u64,
+    offset: u64,
+    len: u64,
+    /// 过滤器生成的条目，旧版本的日志中没有
+    #[serde(default)]
+    entries: Vec<(String, String, String)>,
+}
+
+/// 从日志中恢复的页面
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveredPage {
+    /// 页面URL
+    pub url: String,
+    /// 页面内容
+    pub content: String,
+    /// 过滤器为该页面生成的条目
+    pub entries: Vec<(String, String, String)>,
+}
+
+/// 抓取输出的预写日志
+pub struct PageLog {
+    content: File,
+    wal: File,
+    offset: u64,
+}
+
+impl PageLog {
+    /// 打开文档目录中的日志，不存在时创建
+    pub fn open(doc_dir: &Path) -> Result<Self> {
+        let mut content = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(doc_dir.join(CONTENT_FILENAME))?;
+        let wal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(doc_dir.join(WAL_FILENAME))?;
+        let offset = content.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            content,
+            wal,
+            offset,
+        })
+    }
+
+    /// 追加一个处理完成的页面和过滤器为它生成的条目
+    ///
+    /// 条目与页面在同一条记录中，崩溃后恢复的页面不会丢失条目。
+    pub fn append(&mut self, url: &str, path: &str, content: &str, entries: &[(String, String, String)]) -> Result<()> {
+        let bytes = content.as_bytes();
+        self.content.write_all(bytes)?;
+        self.content.flush()?;
+
+        let record = PageRecord {
+            url: url.to_string(),
+            path: path.to_string(),
+            hash: fnv1a(bytes),
+            offset: self.offset,
+            len: bytes.len() as u64,
+            entries: entries.to_vec(),
+        };
+        let json = serde_json::to_string(&record)?;
+        writeln!(self.wal, "{:016x} {}", fnv1a(json.as_bytes()), json)?;
+        self.wal.flush()?;
+
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// 把已追加的内容和记录写入磁盘，先写内容再写记录
+    pub fn sync(&self) -> Result<()> {
+        self.content.sync_data()?;
+        self.wal.sync_data()?;
+        Ok(())
+    }
+
+    /// 从日志中恢复页面，按路径索引
+    ///
+    /// 遇到校验失败或不完整的记录时停止，之后的记录都视为无效。
+    pub fn recover(doc_dir: &Path) -> Result<HashMap<String, RecoveredPage>> {
+        let mut pages = HashMap::new();
+        let wal = match fs::read_to_string(doc_dir.join(WAL_FILENAME)) {
+            Ok(wal) => wal,
+            Err(_) => return Ok(pages),
+        };
+        let mut content = Vec::new();
+        if let Ok(mut file) = File::open(doc_dir.join(CONTENT_FILENAME)) {
+            file.read_to_end(&mut content)?;
+        }
+
+        for line in wal.split_inclusive('\n') {
+            let record = match Self::parse_record(line) {
+                Some(record) => record,
+                None => {
+                    log::warn!("预写日志记录不完整，已丢弃之后的记录");
+                    break;
+                }
+            };
+
+            let start = record.offset as usize;
+            let end = start + record.len as usize;
+            let page = match content.get(start..end) {
+                Some(bytes) if fnv1a(bytes) == record.hash => bytes,
+                _ => {
+                    log::warn!("页面 {} 的内容不完整，已丢弃", record.path);
+                    break;
+                }
+            };
+
+            pages.insert(
+                record.path,
+                RecoveredPage {
+                    url: record.url,
+                    content: String::from_utf8_lossy(page).into_owned(),
+                    entries: record.entries,
+                },
+            );
+        }
+
+        Ok(pages)
+    }
+
+    /// 解析一行记录，校验失败时返回 None
+    fn parse_record(line: &str) -> Option<PageRecord> {
+        let line = line.strip_suffix('\n')?;
+        let (checksum, json) = line.split_once(' ')?;
+        if u64::from_str_radix(checksum, 16).ok()? != fnv1a(json.as_bytes()) {
+            return None;
+        }
+        serde_json::from_str(json).ok()
+    }
+
+    /// 删除文档目录中的日志
+    pub fn remove(doc_dir: &Path) -> Result<()> {
+        for name in [CONTENT_FILENAME, WAL_FILENAME] {
+            let path: PathBuf = doc_dir.join(name);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| Error::Message(format!("无法删除日志文件 {:?}: {}", path, e)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_pages(dir: &Path) {
+        let mut log = PageLog::open(dir).unwrap();
+        let entries = [("A".to_string(), "a".to_string(), "Custom".to_string())];
+        log.append("https://example.com/a", "a", "<p>Page A</p>", &entries).unwrap();
+        log.append("https://example.com/b", "b", "<p>Page B\nline 2</p>", &[]).unwrap();
+        log.append("https://example.com/c", "c", "<p>Page C</p>", &[]).unwrap();
+        log.sync().unwrap();
+    }
+
+    fn truncate(path: &Path, by: u64) {
+        let file = OpenOptions::new().write(true).open(path).unwrap();
+        let len = file.metadata().unwrap().len();
+        file.set_len(len - by).unwrap();
+    }
+
+    #[test]
+    fn test_recover_all_pages() {
+        let dir = TempDir::new().unwrap();
+        write_pages(dir.path());
+
+        let pages = PageLog::recover(dir.path()).unwrap();
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages["b"].content, "<p>Page B\nline 2</p>");
+        assert_eq!(pages["c"].url, "https://example.com/c");
+        assert_eq!(pages["a"].entries, [("A".to_string(), "a".to_string(), "Custom".to_string())]);
+        assert!(pages["b"].entries.is_empty());
+    }
+
+    #[test]
+    fn test_record_without_entries() {
+        let dir = TempDir::new().unwrap();
+        let json = format!(
+            r#"{{"url":"https://example.com/a","path":"a","hash":{},"offset":0,"len":0}}"#,
+            fnv1a(b"")
+        );
+        fs::write(dir.path().join(WAL_FILENAME), format!("{:016x} {}\n", fnv1a(json.as_bytes()), json)).unwrap();
+        fs::write(dir.path().join(CONTENT_FILENAME), "").unwrap();
+
+        // 旧版本日志中的记录没有条目
+        let pages = PageLog::recover(dir.path()).unwrap();
+        assert_eq!(pages["a"].content, "");
+        assert!(pages["a"].entries.is_empty());
+    }
+
+    #[test]
+    fn test_torn_wal_record_is_dropped() {
+        let dir = TempDir::new().unwrap();
+        write_pages(dir.path());
+        truncate(&dir.path().join(WAL_FILENAME), 10);
+
+        let pages = PageLog::recover(dir.path()).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert!(pages.contains_key("a"));
+        assert!(pages.contains_key("b"));
+        assert!(!pages.contains_key("c"));
+    }
+
+    #[test]
+    fn test_torn_content_is_dropped() {
+        let dir = TempDir::new().unwrap();
+        write_pages(dir.path());
+        truncate(&dir.path().join(CONTENT_FILENAME), 3);
+
+        let pages = PageLog::recover(dir.path()).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert!(!pages.contains_key("c"));
+    }
+
+    #[test]
+    fn test_append_after_recovery_and_remove() {
+        let dir = TempDir::new().unwrap();
+        write_pages(dir.path());
+
+        let mut log = PageLog::open(dir.path()).unwrap();
+        log.append("https://example.com/d", "d", "<p>Page D</p>", &[]).unwrap();
+        let pages = PageLog::recover(dir.path()).unwrap();
+        assert_eq!(pages["d"].content, "<p>Page D</p>");
+        assert_eq!(pages["a"].content, "<p>Page A</p>");
+
+        PageLog::remove(dir.path()).unwrap();
+        assert!(!dir.path().join(WAL_FILENAME).exists());
+        assert!(!dir.path().join(CONTENT_FILENAME).exists());
+    }
+}
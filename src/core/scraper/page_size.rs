@@ -0,0 +1,179 @@
+//! 页面大小限制
+//!
+//! 个别文档站点会返回异常巨大的页面（例如自动生成的几十 MB 的更新日志），
+//! 它们会让 db.json 难以使用。过滤后的页面超过大小限制时按策略处理：跳过、
+//! 在元素边界处截断并附加提示，或者照常保存。处理结果记录在抓取报告中。
+//!
+//! 截断通过 DOM 完成：完整放得下的节点原样输出，放不下的元素只保留开始标签、
+//! 能放下的子节点和结束标签，之后的节点全部丢弃，因此结果总是闭合的 HTML，
+//! 也不会截断在实体或标签中间。
+
+use crate::core::filters::markup::{escape_text, start_tag, RAW_TEXT_ELEMENTS, VOID_ELEMENTS};
+use scraper::node::Node;
+use scraper::{ElementRef, Html};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// 默认的页面大小限制（过滤后，字节）
+pub const DEFAULT_MAX_PAGE_SIZE: usize = 5 * 1024 * 1024;
+
+/// 超过大小限制的页面的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizePolicy {
+    /// 不保存页面，记录警告
+    Skip,
+    /// 在元素边界处截断，附加截断提示
+    #[default]
+    Truncate,
+    /// 照常保存
+    Keep,
+}
+
+impl FromStr for OversizePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(OversizePolicy::Skip),
+            "truncate" => Ok(OversizePolicy::Truncate),
+            "keep" => Ok(OversizePolicy::Keep),
+            _ => Err(format!("未知的超大页面策略: {}（可选 skip、truncate 或 keep）", s)),
+        }
+    }
+}
+
+/// 抓取报告中的超大页面记录
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OversizedPage {
+    /// 页面URL
+    pub url: String,
+    /// 页面路径
+    pub path: String,
+    /// 过滤后的大小（字节）
+    pub size: usize,
+    /// 采取的处理策略
+    pub action: OversizePolicy,
+    /// 截断后的大小（仅截断时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stored_size: Option<usize>,
+}
+
+/// 将 HTML 截断到不超过 `max_bytes` 字节（含截断提示）
+pub fn truncate_html(html: &str, max_bytes: usize) -> String {
+    let notice = truncation_notice(html.len(), max_bytes);
+    let mut budget = max_bytes.saturating_sub(notice.len());
+    let mut output = String::new();
+
+    let trimmed = html.trim_start().to_ascii_lowercase();
+    if trimmed.starts_with("<!doctype") || trimmed.starts_with("<html") {
+        let document = Html::parse_document(html);
+        for node in document.tree.root().children() {
+            if let Node::Doctype(doctype) = node.value() {
+                let doctype = format!("<!DOCTYPE {}>", doctype.name());
+                budget = budget.saturating_sub(doctype.len());
+                output.push_str(&doctype);
+            }
+        }
+        write_element(document.root_element(), &mut budget, &mut output);
+    } else {
+        let fragment = Html::parse_fragment(html);
+        write_children(fragment.root_element(), &mut budget, &mut output);
+    }
+
+    output.push_str(&notice);
+    output
+}
+
+/// 附加在截断页面末尾的提示
+fn truncation_notice(original_size: usize, max_bytes: usize) -> String {
+    format!(
+        r#"<div class="_warning"><p>This page was truncated: its content ({} bytes) exceeds the stored size limit of {} bytes.</p></div>"#,
+        original_size, max_bytes
+    )
+}
+
+/// 输出元素，放不下时只保留开始和结束标签以及能放下的子节点，返回是否完整输出
+fn write_element(element: ElementRef, budget: &mut usize, output: &mut String) -> bool {
+    let html = element.html();
+    if html.len() <= *budget {
+        *budget -= html.len();
+        output.push_str(&html);
+        return true;
+    }
+
+    let name = element.value().name();
+    if !VOID_ELEMENTS.contains(&name) {
+        let start = start_tag(name, element.value().attrs());
+        let end = format!("</{}>", name);
+        if start.len() + end.len() <= *budget {
+            *budget -= start.len() + end.len();
+            output.push_str(&start);
+            write_children(element, budget, output);
+            output.push_str(&end);
+        }
+    }
+    false
+}
+
+/// 依次输出子节点，遇到放不下的节点后停止，返回是否全部输出
+fn write_children(parent: ElementRef, budget: &mut usize, output: &mut String) -> bool {
+    let raw_text = RAW_TEXT_ELEMENTS.contains(&parent.value().name());
+    for child in parent.children() {
+        if let Some(element) = ElementRef::wrap(child) {
+            if !write_element(element, budget, output) {
+                return false;
+            }
+            continue;
+        }
+
+        let html = match child.value() {
+            Node::Text(text) if raw_text => text.to_string(),
+            Node::Text(text) => escape_text(text),
+            Node::Comment(comment) => format!("<!--{}-->", &**comment),
+            _ => String::new(),
+        };
+        if html.len() > *budget {
+            return false;
+        }
+        *budget -= html.len();
+        output.push_str(&html);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_closes_open_elements() {
+        let rows: String = (0..200)
+            .map(|i| format!("<li>Item {} &amp; more</li>", i))
+            .collect();
+        let html = format!("<h1>Changelog</h1><section><ul>{}</ul></section><p>tail</p>", rows);
+
+        let truncated = truncate_html(&html, 1000);
+        assert!(truncated.len() <= 1000);
+        assert!(truncated.starts_with("<h1>Changelog</h1><section><ul><li>Item 0 &amp; more</li>"));
+        assert!(truncated.contains("</li></ul></section><div class=\"_warning\">"));
+        assert!(!truncated.contains("tail"));
+
+        // 截断结果重新解析后不变，说明标签都已闭合、实体完整
+        let reparsed = Html::parse_fragment(&truncated).root_element().inner_html();
+        assert_eq!(reparsed, truncated);
+    }
+
+    #[test]
+    fn test_truncate_full_document() {
+        let body = "<p>paragraph</p>".repeat(100);
+        let html = format!("<!DOCTYPE html><html><head><title>Big</title></head><body>{}</body></html>", body);
+
+        let truncated = truncate_html(&html, 600);
+        assert!(truncated.len() <= 600);
+        assert!(truncated.starts_with("<!DOCTYPE html><html><head><title>Big</title></head><body><p>paragraph</p>"));
+        assert!(truncated.contains("</p></body></html>"));
+        assert_eq!("skip".parse::<OversizePolicy>(), Ok(OversizePolicy::Skip));
+        assert!("drop".parse::<OversizePolicy>().is_err());
+    }
+}
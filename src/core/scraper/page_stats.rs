@@ -0,0 +1,93 @@
+//! 页面统计模块
+//!
+//! 统计页面字数，供界面展示条目的大致篇幅。提取的词序列同时用于近似重复页面检测。
+
+use crate::core::types::PageKey;
+use scraper::{ElementRef, Html, Node};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 不计入字数的元素
+const SKIPPED_ELEMENTS: &[&str] = &["pre", "code", "script", "style"];
+
+/// 统计已解析文档的字数（不包括代码块）
+pub fn count_words(document: &Html) -> usize {
+    page_words(document).len()
+}
+
+/// 提取已解析文档中的词（不包括代码块）
+pub fn page_words(document: &Html) -> Vec<&str> {
+    let mut words = Vec::new();
+    collect_element_words(document.root_element(), &mut words);
+    words
+}
+
+fn collect_element_words<'a>(element: ElementRef<'a>, words: &mut Vec<&'a str>) {
+    if SKIPPED_ELEMENTS.contains(&element.value().name()) {
+        return;
+    }
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => words.extend(text.split_whitespace()),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_element_words(child, words);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 文档的统计汇总，写入 stats.json
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DocStats {
+    /// 页面数量
+    pub page_count: usize,
+    /// 条目数量
+    pub entry_count: usize,
+    /// 总字数
+    pub total_words: usize,
+}
+
+impl DocStats {
+    /// 根据每个页面的字数生成统计汇总
+    pub fn from_word_counts(word_counts: &HashMap<PageKey, usize>, entry_count: usize) -> Self {
+        Self {
+            page_count: word_counts.len(),
+            entry_count,
+            total_words: word_counts.values().sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_words_excludes_code_blocks() {
+        let document = Html::parse_document(
+            r#"<html><body>
+                <h1>Array map</h1>
+                <p>Creates a new <code>Array</code> populated with results.</p>
+                <pre>const doubled = numbers.map((x) => x * 2);</pre>
+                <p>Returns  a   new array.</p>
+            </body></html>"#,
+        );
+
+        // "Array map" + "Creates a new populated with results." + "Returns a new array."
+        assert_eq!(count_words(&document), 2 + 6 + 4);
+    }
+
+    #[test]
+    fn test_doc_stats_totals() {
+        let word_counts = HashMap::from([(PageKey::new("a"), 10), (PageKey::new("b"), 5)]);
+        let stats = DocStats::from_word_counts(&word_counts, 3);
+
+        assert_eq!(stats.page_count, 2);
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.total_words, 15);
+    }
+}
@@ -0,0 +1,292 @@
+//! 文档质量分数
+//!
+//! 抓取结束后根据抓取报告中的问题（过滤失败、过滤器警告、找不到的锚点）计算
+//! 文档的质量分数：每个有问题的页面按其中最严重的问题计入权重，分数为
+//! `100 × (1 - 权重之和 / 页面总数)`。分数保存在 meta.json 的 `quality` 中，
+//! `docs-list` 和 `/docs.json` 据此标出需要修正抓取器的文档。
+
+use super::crawl_report::CrawlReport;
+use crate::core::config::QualitySettings;
+use crate::core::doc::META_FILENAME;
+use crate::core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 抓取报告文件名
+pub const REPORT_FILENAME: &str = "report.json";
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// 不影响阅读，如图片下载失败、锚点找不到
+    Low,
+    /// 部分内容或条目可能缺失
+    Medium,
+    /// 页面内容可能不正确，如找不到内容容器、过滤失败
+    High,
+}
+
+impl Severity {
+    /// 有此问题的页面计入的权重
+    pub fn weight(self) -> f64 {
+        match self {
+            Severity::Low => 0.25,
+            Severity::Medium => 0.5,
+            Severity::High => 1.0,
+        }
+    }
+}
+
+/// 过滤器警告的严重程度，按警告信息判断，未知的警告按 [`Severity::Medium`] 计算
+pub fn warning_severity(message: &str) -> Severity {
+    match message {
+        "missing container" => Severity::High,
+        "image download failed" | "cleared" => Severity::Low,
+        message if message.contains("被覆盖为") => Severity::Low,
+        _ => Severity::Medium,
+    }
+}
+
+/// 文档的质量分数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualityScore {
+    /// 分数，0 到 100，保留一位小数
+    pub score: f64,
+    /// 页面总数
+    pub pages: usize,
+    /// 有问题的页面数
+    pub pages_with_warnings: usize,
+}
+
+/// 分数所在的区间，区间的下限见 [`QualitySettings`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityBand {
+    /// 良好
+    Good,
+    /// 一般
+    Fair,
+    /// 较差，需要修正抓取器
+    Poor,
+}
+
+impl QualityScore {
+    /// 由页面总数和各页面的问题计算分数，同一页面的多个问题只按最严重的计算
+    pub fn compute<I, S>(pages: usize, issues: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Severity)>,
+        S: Into<String>,
+    {
+        let mut worst: HashMap<String, Severity> = HashMap::new();
+        for (page, severity) in issues {
+            let entry = worst.entry(page.into()).or_insert(severity);
+            *entry = (*entry).max(severity);
+        }
+        // 问题可能出现在没有保存的页面上，页面总数至少为有问题的页面数
+        let pages = pages.max(worst.len());
+        let penalty: f64 = worst.values().map(|severity| severity.weight()).sum();
+        let score = if pages == 0 {
+            100.0
+        } else {
+            (100.0 * (1.0 - penalty / pages as f64)).clamp(0.0, 100.0)
+        };
+        Self {
+            score: (score * 10.0).round() / 10.0,
+            pages,
+            pages_with_warnings: worst.len(),
+        }
+    }
+
+    /// 由抓取报告计算分数
+    pub fn from_report(report: &CrawlReport) -> Self {
+        let failures = report.failures.iter().map(|failure| (failure.url.clone(), Severity::High));
+        let warnings = report.warnings.iter().map(|warning| {
+            let page = warning.page.clone().or_else(|| warning.url.clone()).unwrap_or_default();
+            (page, warning_severity(&warning.message))
+        });
+        let fragments = report
+            .broken_fragments
+            .iter()
+            .map(|fragment| (fragment.page.clone(), Severity::Low));
+        Self::compute(report.pages_processed(), failures.chain(warnings).chain(fragments))
+    }
+
+    /// 由保存的 report.json 计算分数
+    ///
+    /// 旧的报告中警告没有记录所在页面，按警告的 URL 区分页面。
+    pub fn from_report_json(report: &serde_json::Value) -> Self {
+        let list = |key: &str| report[key].as_array().cloned().unwrap_or_default();
+        let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+
+        let failures = list("failures");
+        let mut issues: Vec<(String, Severity)> = failures
+            .iter()
+            .map(|failure| (text(&failure["url"]), Severity::High))
+            .collect();
+        for warning in list("warnings") {
+            let page = if warning["page"].is_string() { &warning["page"] } else { &warning["url"] };
+            issues.push((text(page), warning_severity(warning["message"].as_str().unwrap_or_default())));
+        }
+        for fragment in list("broken_fragments") {
+            issues.push((text(&fragment["page"]), Severity::Low));
+        }
+        let pages = report["pages_ok"].as_u64().unwrap_or(0) as usize + failures.len();
+        Self::compute(pages, issues)
+    }
+
+    /// 分数所在的区间
+    pub fn band(&self, settings: &QualitySettings) -> QualityBand {
+        if self.score >= settings.good_min {
+            QualityBand::Good
+        } else if self.score >= settings.fair_min {
+            QualityBand::Fair
+        } else {
+            QualityBand::Poor
+        }
+    }
+}
+
+/// 由文档目录中保存的抓取报告重新计算分数并写入 meta.json，没有抓取报告时返回 None
+pub fn recompute(doc_dir: &Path) -> Result<Option<QualityScore>> {
+    let report_path = doc_dir.join(REPORT_FILENAME);
+    if !report_path.exists() {
+        return Ok(None);
+    }
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&report_path)?)
+        .map_err(|e| Error::Doc(format!("无法读取 {:?}: {}", report_path, e)))?;
+    let score = QualityScore::from_report_json(&report);
+    write_to_meta(doc_dir, &score)?;
+    Ok(Some(score))
+}
+
+/// 将分数写入 meta.json 的 `quality`，保留其他字段
+pub fn write_to_meta(doc_dir: &Path, score: &QualityScore) -> Result<()> {
+    let meta_path = doc_dir.join(META_FILENAME);
+    let mut meta = std::fs::read_to_string(&meta_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&content).ok())
+        .unwrap_or_default();
+    meta.insert("quality".to_string(), serde_json::json!(score));
+    let json = serde_json::to_string_pretty(&meta)
+        .map_err(|e| Error::Message(format!("无法序列化元数据: {}", e)))?;
+    std::fs::write(&meta_path, json).map_err(|e| Error::Message(format!("无法写入 meta.json 文件: {}", e)))
+}
+
+/// 读取 meta.json 中保存的分数
+pub fn read_from_meta(meta: &serde_json::Value) -> Option<QualityScore> {
+    serde_json::from_value(meta.get("quality")?.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::scraper::filter::FilterWarning;
+    use crate::core::scraper::fragment_links::BrokenFragment;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_score_weights_worst_issue_per_page() {
+        assert_eq!(
+            QualityScore::compute(0, Vec::<(String, Severity)>::new()),
+            QualityScore { score: 100.0, pages: 0, pages_with_warnings: 0 }
+        );
+
+        // a 只按最严重的问题计算：(1.0 + 0.5 + 0.25) / 10
+        let score = QualityScore::compute(
+            10,
+            [
+                ("a", Severity::Low),
+                ("a", Severity::High),
+                ("b", Severity::Medium),
+                ("c", Severity::Low),
+                ("c", Severity::Low),
+            ],
+        );
+        assert_eq!(score, QualityScore { score: 82.5, pages: 10, pages_with_warnings: 3 });
+
+        // 所有页面都有严重问题时为 0，问题页面多于页面总数时按问题页面数计算
+        assert_eq!(QualityScore::compute(2, [("a", Severity::High), ("b", Severity::High)]).score, 0.0);
+        assert_eq!(QualityScore::compute(0, [("a", Severity::Medium)]).score, 50.0);
+        assert_eq!(QualityScore::compute(3, [("a", Severity::High)]).score, 66.7);
+    }
+
+    #[test]
+    fn test_score_from_report() {
+        let mut report = CrawlReport::new();
+        for _ in 0..4 {
+            report.record_ok();
+        }
+        report.record_warnings(
+            "https://example.com/a",
+            vec![
+                FilterWarning::new("CleanHtml", "missing container"),
+                FilterWarning::new("ImagesFilter", "image download failed").with_url("https://example.com/x.png"),
+            ],
+        );
+        report.record_warnings(
+            "https://example.com/b",
+            vec![FilterWarning::new("ImagesFilter", "image download failed").with_url("https://example.com/y.png")],
+        );
+        report.broken_fragments.push(BrokenFragment {
+            page: "c".to_string(),
+            href: "d#x".to_string(),
+            target: "d".to_string(),
+            fragment: "x".to_string(),
+            stripped: false,
+        });
+
+        // a: 1.0，b: 0.25，c: 0.25
+        let score = QualityScore::from_report(&report);
+        assert_eq!(score, QualityScore { score: 62.5, pages: 4, pages_with_warnings: 3 });
+        assert_eq!(score.band(&QualitySettings::default()), QualityBand::Poor);
+
+        // 保存后的报告得到相同的分数
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(QualityScore::from_report_json(&json), score);
+    }
+
+    #[test]
+    fn test_score_from_old_report_json() {
+        let report = json!({
+            "pages_ok": 9,
+            "failures": [{ "url": "https://example.com/x" }],
+            "warnings": [{ "filter": "F", "message": "missing entry name", "url": "https://example.com/y" }],
+        });
+        assert_eq!(
+            QualityScore::from_report_json(&report),
+            QualityScore { score: 85.0, pages: 10, pages_with_warnings: 2 }
+        );
+    }
+
+    #[test]
+    fn test_bands() {
+        let settings = QualitySettings::default();
+        let band = |score| QualityScore { score, pages: 1, pages_with_warnings: 0 }.band(&settings);
+        assert_eq!(band(100.0), QualityBand::Good);
+        assert_eq!(band(settings.good_min), QualityBand::Good);
+        assert_eq!(band(settings.good_min - 0.1), QualityBand::Fair);
+        assert_eq!(band(settings.fair_min), QualityBand::Fair);
+        assert_eq!(band(0.0), QualityBand::Poor);
+    }
+
+    #[test]
+    fn test_meta_round_trip() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(META_FILENAME), r#"{"name":"CSS","slug":"css"}"#).unwrap();
+        assert_eq!(recompute(dir.path()).unwrap(), None);
+
+        let report = json!({ "pages_ok": 3, "failures": [{ "url": "https://example.com/x" }] });
+        std::fs::write(dir.path().join(REPORT_FILENAME), report.to_string()).unwrap();
+        let score = recompute(dir.path()).unwrap().unwrap();
+        assert_eq!(score, QualityScore { score: 75.0, pages: 4, pages_with_warnings: 1 });
+
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(META_FILENAME)).unwrap()).unwrap();
+        assert_eq!(meta["slug"], "css");
+        assert_eq!(meta["quality"], json!({ "score": 75.0, "pages": 4, "pages_with_warnings": 1 }));
+        assert_eq!(read_from_meta(&meta), Some(score));
+        assert_eq!(read_from_meta(&json!({})), None);
+    }
+}
@@ -1,81 +1,158 @@
 //! 速率限制器
-//! 参考 Ruby 版本的 RateLimiter 类实现
+//!
+//! 令牌桶：每分钟补充 `limit` 个令牌，桶中最多存放 `burst` 个，每个请求取走
+//! 一个，没有令牌时等待。限制器通过 `&self` 使用，并发的请求共用同一个
+//! 限制器（`Arc<RateLimiter>`），总速率不会因为并发而成倍增加。
+//!
+//! 服务器返回 429 时调用 [`RateLimiter::backoff`]，速率减半并保持一段时间，
+//! 期间没有再收到 429 时恢复为配置的速率。
 
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
+/// 退避后恢复原速率前等待的时间
+pub const BACKOFF_PERIOD: Duration = Duration::from_secs(60);
+
 /// 速率限制器，限制每分钟的请求数量
+#[derive(Debug)]
 pub struct RateLimiter {
-    /// 限制值（每分钟的最大请求数）
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// 配置的速率（每分钟请求数）
     limit: u32,
-    /// 当前分钟
-    current_minute: u32,
-    /// 当前计数
-    counter: u32,
-    /// 上次请求时间
-    last_request_time: Option<Instant>,
+    /// 当前生效的速率，退避期间低于 `limit`
+    current: u32,
+    /// 桶的容量
+    burst: u32,
+    /// 桶中的令牌数
+    tokens: f64,
+    /// 上次补充令牌的时间
+    refilled_at: Instant,
+    /// 退避结束的时间
+    backoff_until: Option<Instant>,
+}
+
+impl LimiterState {
+    /// 按经过的时间补充令牌，退避期结束时恢复配置的速率
+    fn refill(&mut self, now: Instant) {
+        if self.backoff_until.is_some_and(|until| now >= until) {
+            self.backoff_until = None;
+            self.current = self.limit;
+        }
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.current as f64 / 60.0).min(self.burst as f64);
+        self.refilled_at = now;
+    }
 }
 
 impl RateLimiter {
-    /// 创建新的速率限制器
+    /// 创建新的速率限制器，每分钟最多 `limit` 个请求，不允许突发
     pub fn new(limit: u32) -> Self {
+        let limit = limit.max(1);
         Self {
-            limit,
-            current_minute: Self::current_minute(),
-            counter: 0,
-            last_request_time: None,
+            state: Mutex::new(LimiterState {
+                limit,
+                current: limit,
+                burst: 1,
+                tokens: 1.0,
+                refilled_at: Instant::now(),
+                backoff_until: None,
+            }),
         }
     }
 
-    /// 获取当前分钟
-    fn current_minute() -> u32 {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        (now.as_secs() / 60) as u32
+    /// 设置桶的容量：空闲后最多可以连续发送 `burst` 个请求
+    pub fn with_burst(self, burst: u32) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.burst = burst.max(1);
+            state.tokens = state.burst as f64;
+        }
+        self
+    }
+
+    /// 设置限制值，同时结束退避
+    pub fn set_limit(&self, limit: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = limit.max(1);
+        state.current = state.limit;
+        state.backoff_until = None;
+    }
+
+    /// 配置的速率（每分钟请求数）
+    pub fn limit(&self) -> u32 {
+        self.state.lock().unwrap().limit
     }
 
-    /// 设置限制值
-    pub fn set_limit(&mut self, limit: u32) {
-        self.limit = limit;
+    /// 当前生效的速率（每分钟请求数），退避期间低于配置的速率
+    pub fn current_limit(&self) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        state.refill(Instant::now());
+        state.current
     }
 
     /// 等待请求，确保不超过速率限制
-    pub async fn wait(&mut self) {
-        // 检查是否进入新的一分钟
-        let current_minute = Self::current_minute();
-        if current_minute != self.current_minute {
-            self.current_minute = current_minute;
-            self.counter = 0;
+    pub async fn wait(&self) {
+        loop {
+            let delay = {
+                let mut state = self.state.lock().unwrap();
+                state.refill(Instant::now());
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - state.tokens) * 60.0 / state.current as f64)
+            };
+            sleep(delay).await;
         }
+    }
 
-        // 增加计数
-        self.counter += 1;
-
-        // 如果达到限制，等待到下一分钟开始
-        if self.counter >= self.limit {
-            // 计算需要等待的时间：当前分钟剩余秒数 + 1秒
-            let current_seconds = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs() % 60;
-            
-            let wait_seconds = 61 - current_seconds as u64;
-            println!("达到速率限制 ({}次/分钟)，等待 {} 秒...", self.limit, wait_seconds);
-            
-            sleep(Duration::from_secs(wait_seconds)).await;
-            
-            // 重置计数器
-            self.current_minute = Self::current_minute();
-            self.counter = 1;
-        }
-        // 否则，如果不是第一次请求，添加一个小延迟以避免服务器过载
-        else if let Some(last_time) = self.last_request_time {
-            let elapsed = last_time.elapsed();
-            if elapsed < Duration::from_millis(100) {
-                sleep(Duration::from_millis(100) - elapsed).await;
-            }
+    /// 服务器要求降低请求速率（429）：当前速率减半，[`BACKOFF_PERIOD`] 后恢复
+    ///
+    /// 退避期间再次调用时继续减半，并重新计算恢复时间。返回减半后的速率。
+    pub fn backoff(&self) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.refill(now);
+        state.current = (state.current / 2).max(1);
+        state.backoff_until = Some(now + BACKOFF_PERIOD);
+        state.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_burst_then_steady_rate() {
+        let limiter = RateLimiter::new(600).with_burst(3);
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
         }
-        
-        // 更新上次请求时间
-        self.last_request_time = Some(Instant::now());
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        // 桶空后每 100 毫秒一个请求
+        limiter.wait().await;
+        limiter.wait().await;
+        assert!(started.elapsed() >= Duration::from_millis(190));
+    }
+
+    #[test]
+    fn test_backoff_halves_rate_until_period_ends() {
+        let limiter = RateLimiter::new(30);
+        assert_eq!(limiter.backoff(), 15);
+        assert_eq!(limiter.backoff(), 7);
+        assert_eq!(limiter.current_limit(), 7);
+        assert_eq!(limiter.limit(), 30);
+
+        // 退避期结束后恢复配置的速率
+        limiter.state.lock().unwrap().backoff_until = Some(Instant::now());
+        assert_eq!(limiter.current_limit(), 30);
     }
 }
@@ -0,0 +1,184 @@
+//! 过滤前的原始页面
+//!
+//! 使用 `--keep-raw` 抓取时，每个页面在应用过滤器之前的 HTML 以 gzip 压缩后
+//! 保存到 `<slug>/_raw/`，文件名为页面路径的哈希。每保存一个页面就向
+//! `manifest.jsonl` 追加一行记录（页面路径、URL 和文件名），抓取中断或崩溃时
+//! 已保存的页面仍在清单中，同一路径以最后一条记录为准。修复过滤器后可以用这些
+//! 页面重新过滤（`docs refilter`），不需要重新访问网络。
+
+use crate::core::error::{Error, Result};
+use crate::core::hash::fnv1a;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 原始页面目录名
+pub const RAW_DIR: &str = "_raw";
+
+/// 原始页面清单文件名，每行一条记录
+pub const MANIFEST_FILENAME: &str = "manifest.jsonl";
+
+/// 清单中的一个页面
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RawRecord {
+    path: String,
+    url: String,
+    file: String,
+}
+
+/// 保存的原始页面
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawPage {
+    /// 页面路径
+    pub path: String,
+    /// 页面URL
+    pub url: String,
+    /// 过滤前的 HTML
+    pub html: String,
+}
+
+/// 文档目录中的原始页面
+pub struct RawPages {
+    dir: PathBuf,
+    manifest: File,
+}
+
+impl RawPages {
+    /// 打开文档目录中的原始页面目录
+    ///
+    /// `keep_existing` 为 false 时清空之前保存的页面（重新抓取），为 true 时
+    /// 保留它们（从中断处恢复的抓取不会重新获取已恢复的页面）。
+    pub fn open(doc_dir: &Path, keep_existing: bool) -> Result<Self> {
+        let dir = doc_dir.join(RAW_DIR);
+        if !keep_existing && dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        let manifest = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(MANIFEST_FILENAME))?;
+        Ok(Self { dir, manifest })
+    }
+
+    /// 文档目录中是否保存了原始页面
+    pub fn exists(doc_dir: &Path) -> bool {
+        doc_dir.join(RAW_DIR).join(MANIFEST_FILENAME).is_file()
+    }
+
+    /// 保存一个页面的原始 HTML，并向清单追加一条记录
+    ///
+    /// 先写入页面文件再追加记录，清单中的记录总有对应的文件。
+    pub fn save(&mut self, path: &str, url: &str, html: &str) -> Result<()> {
+        let file = format!("{:016x}.html.gz", fnv1a(path.as_bytes()));
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(html.as_bytes())?;
+        fs::write(self.dir.join(&file), encoder.finish()?)?;
+
+        let record = RawRecord {
+            path: path.to_string(),
+            url: url.to_string(),
+            file,
+        };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        self.manifest.write_all(line.as_bytes())?;
+        self.manifest.flush()?;
+        Ok(())
+    }
+
+    /// 读取文档目录中保存的所有原始页面，按路径排序
+    pub fn load(doc_dir: &Path) -> Result<Vec<RawPage>> {
+        let dir = doc_dir.join(RAW_DIR);
+        let records = Self::read_manifest(&dir)?;
+        let mut pages = Vec::with_capacity(records.len());
+        for (path, record) in records {
+            let mut html = String::new();
+            GzDecoder::new(fs::File::open(dir.join(&record.file))?)
+                .read_to_string(&mut html)
+                .map_err(|e| Error::Message(format!("无法读取原始页面 {}: {}", path, e)))?;
+            pages.push(RawPage {
+                path,
+                url: record.url,
+                html,
+            });
+        }
+        Ok(pages)
+    }
+
+    /// 读取清单，同一路径以最后一条记录为准
+    ///
+    /// 崩溃时写了一半的最后一行会被丢弃。
+    fn read_manifest(dir: &Path) -> Result<BTreeMap<String, RawRecord>> {
+        let content = fs::read_to_string(dir.join(MANIFEST_FILENAME))?;
+        let mut records = BTreeMap::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<RawRecord>(line) {
+                Ok(record) => {
+                    records.insert(record.path.clone(), record);
+                }
+                Err(_) => {
+                    log::warn!("原始页面清单的记录不完整，已丢弃之后的记录");
+                    break;
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let doc_dir = TempDir::new().unwrap();
+        assert!(!RawPages::exists(doc_dir.path()));
+
+        let mut raw = RawPages::open(doc_dir.path(), false).unwrap();
+        raw.save("guide/intro", "https://example.com/guide/intro", "<p>intro</p>").unwrap();
+        raw.save("index", "https://example.com/", "<p>index</p>").unwrap();
+        assert!(RawPages::exists(doc_dir.path()));
+
+        let pages = RawPages::load(doc_dir.path()).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].path, "guide/intro");
+        assert_eq!(pages[0].url, "https://example.com/guide/intro");
+        assert_eq!(pages[0].html, "<p>intro</p>");
+
+        // 恢复抓取时保留已有页面，重新抓取时清空
+        let mut raw = RawPages::open(doc_dir.path(), true).unwrap();
+        raw.save("other", "https://example.com/other", "<p>other</p>").unwrap();
+        assert_eq!(RawPages::load(doc_dir.path()).unwrap().len(), 3);
+
+        RawPages::open(doc_dir.path(), false).unwrap();
+        assert!(RawPages::load(doc_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pages_survive_interrupted_crawl() {
+        let doc_dir = TempDir::new().unwrap();
+        let mut raw = RawPages::open(doc_dir.path(), false).unwrap();
+        raw.save("index", "https://example.com/", "<p>old</p>").unwrap();
+        raw.save("guide", "https://example.com/guide", "<p>guide</p>").unwrap();
+        raw.save("index", "https://example.com/", "<p>new</p>").unwrap();
+
+        // 进程在写入下一条记录时崩溃，不调用任何收尾方法
+        let manifest = doc_dir.path().join(RAW_DIR).join(MANIFEST_FILENAME);
+        let mut file = OpenOptions::new().append(true).open(&manifest).unwrap();
+        file.write_all(br#"{"path":"broken","url":"#).unwrap();
+        drop(raw);
+
+        let pages = RawPages::load(doc_dir.path()).unwrap();
+        let paths: Vec<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+        assert_eq!(paths, ["guide", "index"]);
+        assert_eq!(pages[1].html, "<p>new</p>");
+    }
+}
@@ -0,0 +1,255 @@
+//! 站点地图
+//!
+//! 有些文档网站（如按符号生成页面的 API 参考）无法从根页面沿链接找到所有页面。
+//! 这类网站可以指定站点地图：抓取开始前读取 `sitemap.xml`，把其中的网址加入
+//! 初始队列。站点地图索引（`<sitemapindex>`）引用的子站点地图会依次读取，
+//! gzip 压缩的站点地图（`sitemap.xml.gz`）按内容自动解压。
+
+use crate::core::error::{Error, Result};
+use crate::core::http_fetch::HttpFetch;
+use flate2::read::GzDecoder;
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Read;
+use xml5ever::driver::parse_document;
+use xml5ever::tendril::TendrilSink;
+
+/// 最多读取的站点地图数（包括索引引用的子站点地图），防止互相引用时无限读取
+pub const MAX_SITEMAPS: usize = 1000;
+
+/// gzip 数据的开头
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 一个站点地图中的网址
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sitemap {
+    /// 页面网址（`<urlset>` 中的 `<url>`）
+    pub urls: Vec<String>,
+    /// 子站点地图的网址（`<sitemapindex>` 中的 `<sitemap>`）
+    pub sitemaps: Vec<String>,
+}
+
+/// 解析站点地图的 XML
+///
+/// 实体和 CDATA 由 XML 解析器处理，注释中的元素不会被读取。
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let dom = parse_document(RcDom::default(), Default::default()).one(xml);
+    let mut sitemap = Sitemap::default();
+    collect_locs(&dom.document, &mut sitemap);
+    sitemap
+}
+
+/// 收集 `<url>` 和 `<sitemap>` 元素中 `<loc>` 的网址
+fn collect_locs(node: &Handle, sitemap: &mut Sitemap) {
+    for child in node.children.borrow().iter() {
+        match element_name(child) {
+            Some("url") => sitemap.urls.extend(loc(child)),
+            Some("sitemap") => sitemap.sitemaps.extend(loc(child)),
+            _ => collect_locs(child, sitemap),
+        }
+    }
+}
+
+/// 元素的本地名称（不含命名空间前缀），不是元素时返回 None
+fn element_name(node: &Handle) -> Option<&str> {
+    match &node.data {
+        NodeData::Element { name, .. } => Some(&*name.local),
+        _ => None,
+    }
+}
+
+/// 元素中第一个 `<loc>` 子元素的文本，为空时返回 None
+fn loc(node: &Handle) -> Option<String> {
+    let loc = node
+        .children
+        .borrow()
+        .iter()
+        .find(|child| element_name(child) == Some("loc"))
+        .cloned()?;
+    let mut text = String::new();
+    append_text(&loc, &mut text);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// 追加节点中所有文本
+fn append_text(node: &Handle, text: &mut String) {
+    for child in node.children.borrow().iter() {
+        match &child.data {
+            NodeData::Text { contents } => text.push_str(&contents.borrow()),
+            _ => append_text(child, text),
+        }
+    }
+}
+
+/// 站点地图的文本，gzip 压缩的内容先解压
+pub fn decode_body(body: &[u8]) -> Result<String> {
+    if !body.starts_with(&GZIP_MAGIC) {
+        return Ok(String::from_utf8_lossy(body).into_owned());
+    }
+    let mut bytes = Vec::new();
+    GzDecoder::new(body)
+        .read_to_end(&mut bytes)
+        .map_err(|e| Error::Message(format!("无法解压站点地图: {}", e)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// 读取站点地图及其引用的子站点地图，按出现的顺序返回去重后的页面网址
+///
+/// 指定的站点地图无法读取时返回错误；子站点地图无法读取时只输出警告。
+/// 每次请求前调用 `before_request`，用于限制请求速率。
+pub async fn fetch_sitemap_urls<F, Fut>(
+    fetch: &dyn HttpFetch,
+    sitemap_url: &str,
+    headers: &HashMap<String, String>,
+    before_request: F,
+) -> Result<Vec<String>>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut pending = VecDeque::from([sitemap_url.to_string()]);
+    let mut seen_sitemaps = HashSet::from([sitemap_url.to_string()]);
+    let mut seen_urls = HashSet::new();
+    let mut urls = Vec::new();
+    let mut fetched = 0;
+
+    while let Some(url) = pending.pop_front() {
+        if fetched >= MAX_SITEMAPS {
+            log::warn!("站点地图超过 {} 个，忽略其余的站点地图", MAX_SITEMAPS);
+            break;
+        }
+        fetched += 1;
+        before_request().await;
+
+        let result = match fetch.get(&url, headers).await {
+            Ok(response) if response.is_success() => decode_body(&response.body),
+            Ok(response) => Err(Error::Message(format!("HTTP {}", response.status))),
+            Err(e) => Err(e),
+        };
+        let body = match result {
+            Ok(body) => body,
+            Err(e) if url == sitemap_url => {
+                return Err(Error::Message(format!("无法读取站点地图 {}: {}", url, e)));
+            }
+            Err(e) => {
+                log::warn!("无法读取站点地图 {}: {}", url, e);
+                continue;
+            }
+        };
+
+        let sitemap = parse_sitemap(&body);
+        for child in sitemap.sitemaps {
+            if seen_sitemaps.insert(child.clone()) {
+                pending.push_back(child);
+            }
+        }
+        for page in sitemap.urls {
+            if seen_urls.insert(page.clone()) {
+                urls.push(page);
+            }
+        }
+    }
+    Ok(urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const URLSET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/api/Map</loc><lastmod>2024-05-01</lastmod></url>
+  <url>
+    <loc>
+      https://example.com/api/search?q=a&amp;b=1
+    </loc>
+  </url>
+  <url><loc><![CDATA[https://example.com/api/Set]]></loc></url>
+  <url><loc></loc></url>
+</urlset>"#;
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_urlset_and_index() {
+        let sitemap = parse_sitemap(URLSET);
+        assert_eq!(
+            sitemap.urls,
+            [
+                "https://example.com/api/Map",
+                "https://example.com/api/search?q=a&b=1",
+                "https://example.com/api/Set",
+            ]
+        );
+        assert!(sitemap.sitemaps.is_empty());
+
+        let index = parse_sitemap(
+            r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <sitemap><loc>https://example.com/sitemap-1.xml.gz</loc></sitemap>
+              <sitemap><loc>https://example.com/sitemap-2.xml</loc></sitemap>
+            </sitemapindex>"#,
+        );
+        assert!(index.urls.is_empty());
+        assert_eq!(index.sitemaps, ["https://example.com/sitemap-1.xml.gz", "https://example.com/sitemap-2.xml"]);
+    }
+
+    #[test]
+    fn test_comments_and_other_locs_are_ignored() {
+        let sitemap = parse_sitemap(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                       xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+              <!-- <url><loc>https://example.com/api/Old</loc></url> -->
+              <url data-note="a > b">
+                <loc>https://example.com/api/Map</loc>
+                <image:image><image:loc>https://example.com/img/map.png</image:loc></image:image>
+              </url>
+            </urlset>"#,
+        );
+        assert_eq!(sitemap.urls, ["https://example.com/api/Map"]);
+        assert!(sitemap.sitemaps.is_empty());
+    }
+
+    #[test]
+    fn test_decode_gzipped_body() {
+        assert_eq!(decode_body(&gzip(URLSET)).unwrap(), URLSET);
+        assert_eq!(decode_body(URLSET.as_bytes()).unwrap(), URLSET);
+        assert!(decode_body(&[0x1f, 0x8b, 0x00]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_index_with_child_sitemaps() {
+        let fetch = MockFetch::new()
+            .with_response(
+                "https://example.com/sitemap.xml",
+                FetchedResponse::new(200).with_body(
+                    r#"<sitemapindex>
+                      <sitemap><loc>https://example.com/sitemap-1.xml.gz</loc></sitemap>
+                      <sitemap><loc>https://example.com/missing.xml</loc></sitemap>
+                      <sitemap><loc>https://example.com/sitemap.xml</loc></sitemap>
+                    </sitemapindex>"#,
+                ),
+            )
+            .with_response("https://example.com/sitemap-1.xml.gz", FetchedResponse::new(200).with_body(gzip(URLSET)));
+
+        let urls = fetch_sitemap_urls(&fetch, "https://example.com/sitemap.xml", &HashMap::new(), || async {})
+            .await
+            .unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(urls[0], "https://example.com/api/Map");
+        // 引用自身的索引只读取一次，子站点地图缺失不影响其余的网址
+        assert_eq!(fetch.request_count("https://example.com/sitemap.xml"), 1);
+        assert_eq!(fetch.request_count("https://example.com/missing.xml"), 1);
+
+        let missing = fetch_sitemap_urls(&fetch, "https://example.com/missing.xml", &HashMap::new(), || async {}).await;
+        assert!(missing.is_err());
+    }
+}
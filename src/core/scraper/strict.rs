@@ -0,0 +1,226 @@
+//! 严格模式
+//!
+//! 在 CI 中定期抓取文档时，需要在抓取质量下降时让命令失败。严格模式在抓取
+//! 结束、结果写入之后依次检查：
+//!
+//! - 过滤失败的页面数不超过 `max_filter_failures`；
+//! - 请求错误率（连接错误、超时和 4xx、5xx 响应）不超过 `max_error_rate`；
+//! - 抓取器声明的每种预期条目类型（[`UrlScraper::with_expected_types`]）至少有一个条目；
+//! - 条目数相比上次抓取结果的减少比例不超过 `max_entry_drop`。
+//!
+//! 任何一项没有通过时抓取返回 [`Error::StrictFailed`]，列出所有没有通过的检查
+//! 和对应的数字，命令行以 [`EXIT_STRICT`](crate::cli::EXIT_STRICT) 退出。
+//!
+//! [`UrlScraper::with_expected_types`]: super::UrlScraper::with_expected_types
+//! [`Error::StrictFailed`]: crate::core::error::Error::StrictFailed
+
+use super::crawl_report::CrawlReport;
+use crate::core::config::StrictSettings;
+use crate::core::doc::INDEX_FILENAME;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// 没有通过的检查
+#[derive(Debug, Clone, PartialEq)]
+pub enum GateFailure {
+    /// 过滤失败的页面过多
+    FilterFailures {
+        /// 过滤失败的页面数
+        failed: usize,
+        /// 允许的页面数
+        max: usize,
+    },
+    /// 请求错误率过高
+    ErrorRate {
+        /// 出错的请求数
+        errors: usize,
+        /// 请求总数
+        requests: usize,
+        /// 允许的错误率
+        max: f64,
+    },
+    /// 预期的条目类型没有任何条目
+    MissingType {
+        /// 条目类型
+        entry_type: String,
+    },
+    /// 条目数相比上次抓取结果减少过多
+    EntryDrop {
+        /// 上次抓取的条目数
+        previous: usize,
+        /// 本次抓取的条目数
+        current: usize,
+        /// 允许减少的比例
+        max: f64,
+    },
+}
+
+impl fmt::Display for GateFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GateFailure::FilterFailures { failed, max } => {
+                write!(f, "过滤失败: {} 个页面过滤失败，最多允许 {} 个", failed, max)
+            }
+            GateFailure::ErrorRate { errors, requests, max } => write!(
+                f,
+                "请求错误率: {}/{} 个请求出错 ({})，最多允许 {}",
+                errors,
+                requests,
+                percent(*errors as f64 / *requests as f64),
+                percent(*max)
+            ),
+            GateFailure::MissingType { entry_type } => {
+                write!(f, "预期的条目类型: {} 没有任何条目", entry_type)
+            }
+            GateFailure::EntryDrop { previous, current, max } => write!(
+                f,
+                "条目数减少: 从 {} 个减少到 {} 个 (-{})，最多允许减少 {}",
+                previous,
+                current,
+                percent(1.0 - *current as f64 / *previous as f64),
+                percent(*max)
+            ),
+        }
+    }
+}
+
+/// 依次检查抓取结果，返回所有没有通过的检查
+///
+/// `entry_types` 为本次抓取生成的每个条目的类型，`previous_entries` 为上次抓取
+/// 结果的条目数（没有上次的结果时为 None，不检查条目数的变化）。
+pub fn check<'a, I>(
+    settings: &StrictSettings,
+    report: &CrawlReport,
+    expected_types: &[String],
+    entry_types: I,
+    previous_entries: Option<usize>,
+) -> Vec<GateFailure>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut failures = Vec::new();
+
+    if report.failures.len() > settings.max_filter_failures {
+        failures.push(GateFailure::FilterFailures {
+            failed: report.failures.len(),
+            max: settings.max_filter_failures,
+        });
+    }
+
+    if report.requests > 0 && report.error_rate() > settings.max_error_rate {
+        failures.push(GateFailure::ErrorRate {
+            errors: report.request_errors,
+            requests: report.requests,
+            max: settings.max_error_rate,
+        });
+    }
+
+    let mut current = 0;
+    let mut types = HashSet::new();
+    for entry_type in entry_types {
+        current += 1;
+        types.insert(entry_type);
+    }
+    for expected in expected_types {
+        if !types.contains(expected.as_str()) {
+            failures.push(GateFailure::MissingType {
+                entry_type: expected.clone(),
+            });
+        }
+    }
+
+    if let Some(previous) = previous_entries.filter(|&previous| previous > 0) {
+        let drop = 1.0 - current as f64 / previous as f64;
+        if drop > settings.max_entry_drop {
+            failures.push(GateFailure::EntryDrop {
+                previous,
+                current,
+                max: settings.max_entry_drop,
+            });
+        }
+    }
+
+    failures
+}
+
+/// 文档目录中已有的 index.json 的条目数，没有或无法解析时返回 None
+pub fn previous_entry_count(doc_dir: &Path) -> Option<usize> {
+    let content = fs::read_to_string(doc_dir.join(INDEX_FILENAME)).ok()?;
+    let index: serde_json::Value = serde_json::from_str(&content).ok()?;
+    index.get("entries").and_then(|entries| entries.as_array()).map(Vec::len)
+}
+
+/// 比例显示为百分比，保留一位小数
+fn percent(ratio: f64) -> String {
+    format!("{:.1}%", ratio * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::Error;
+    use crate::core::scraper::PageFailure;
+
+    fn report(pages_ok: usize, failed: usize, requests: usize, errors: usize) -> CrawlReport {
+        let mut report = CrawlReport::new();
+        for _ in 0..pages_ok {
+            report.record_ok();
+        }
+        for i in 0..failed {
+            let error = Error::Message("invalid selector".to_string());
+            report.record_failure(PageFailure::new(&format!("https://example.com/{}", i), "p", &error, false));
+        }
+        for i in 0..requests {
+            report.record_request(i >= errors);
+        }
+        report
+    }
+
+    #[test]
+    fn test_clean_crawl_passes() {
+        let settings = StrictSettings::default();
+        let failures = check(&settings, &report(10, 0, 10, 0), &["Methods".to_string()], ["Methods"; 10], Some(10));
+        assert!(failures.is_empty());
+        // 没有上次的结果或请求时不检查对应的项
+        assert!(check(&settings, &report(0, 0, 0, 0), &[], Vec::<&str>::new(), None).is_empty());
+    }
+
+    #[test]
+    fn test_each_gate_reports_numbers() {
+        let settings = StrictSettings::default();
+
+        let failures = check(&settings, &report(9, 1, 10, 0), &[], Vec::<&str>::new(), None);
+        assert_eq!(failures, [GateFailure::FilterFailures { failed: 1, max: 0 }]);
+        assert_eq!(failures[0].to_string(), "过滤失败: 1 个页面过滤失败，最多允许 0 个");
+
+        let failures = check(&settings, &report(10, 0, 20, 2), &[], Vec::<&str>::new(), None);
+        assert_eq!(failures, [GateFailure::ErrorRate { errors: 2, requests: 20, max: 0.05 }]);
+        assert_eq!(failures[0].to_string(), "请求错误率: 2/20 个请求出错 (10.0%)，最多允许 5.0%");
+
+        let expected = vec!["Methods".to_string(), "Classes".to_string()];
+        let failures = check(&settings, &report(1, 0, 1, 0), &expected, ["Methods"], None);
+        assert_eq!(failures, [GateFailure::MissingType { entry_type: "Classes".to_string() }]);
+        assert_eq!(failures[0].to_string(), "预期的条目类型: Classes 没有任何条目");
+
+        let failures = check(&settings, &report(1, 0, 1, 0), &[], ["Methods"; 70], Some(100));
+        assert_eq!(failures, [GateFailure::EntryDrop { previous: 100, current: 70, max: 0.2 }]);
+        assert_eq!(failures[0].to_string(), "条目数减少: 从 100 个减少到 70 个 (-30.0%)，最多允许减少 20.0%");
+        // 减少的比例在范围内、或条目数增加时通过
+        assert!(check(&settings, &report(1, 0, 1, 0), &[], ["Methods"; 80], Some(100)).is_empty());
+        assert!(check(&settings, &report(1, 0, 1, 0), &[], ["Methods"; 120], Some(100)).is_empty());
+    }
+
+    #[test]
+    fn test_thresholds_are_configurable() {
+        let settings = StrictSettings {
+            max_filter_failures: 2,
+            max_error_rate: 0.5,
+            max_entry_drop: 0.5,
+            ..StrictSettings::default()
+        };
+        assert!(check(&settings, &report(8, 2, 10, 5), &[], ["Methods"; 50], Some(100)).is_empty());
+        assert_eq!(check(&settings, &report(7, 3, 10, 6), &[], ["Methods"; 40], Some(100)).len(), 3);
+    }
+}
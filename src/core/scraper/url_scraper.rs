@@ -1,19 +1,114 @@
 //! URL 爬虫实现
 
+use super::adaptive_concurrency::{AdaptiveConcurrency, RequestOutcome};
 use super::base::Scraper;
+use super::crawl_events::{CrawlEvents, SkipReason, DEFAULT_EVENT_SAMPLE_RATE};
+use super::crawl_queue::CrawlQueue;
+use super::crawl_report::{CrawlReport, PageFailure};
+use super::crawl_state::{self, CrawlState};
+use super::disk_space::{self, DiskSpace, SpaceMonitor, SystemDiskSpace};
+use super::entry_exclusions::EntryExclusions;
+use super::favicon;
+use super::fetch_retry::{self, FetchRetries};
 use super::filter::{Filter, FilterContext};
+use super::fix_redirections::{self, FixRedirections};
+use super::fragment_links::{self, FragmentIndex};
+use super::head_check::{self, HeadSkip};
+use super::near_duplicates::NearDuplicateDetector;
+use super::page_log::PageLog;
+use super::page_size::{self, OversizePolicy, OversizedPage};
+use super::page_stats::{self, DocStats};
+use super::quality::{self, QualityScore};
+use super::rate_limiter::RateLimiter;
+use super::raw_pages::RawPages;
+use super::sitemap;
+use super::strict;
+use crate::core::config::{DiskSpaceSettings, LogFormat, ScraperDefaults, StrictSettings};
+use crate::core::doc::{EntryIndex, INDEX_FILENAME, META_FILENAME, PAGES_FILENAME};
 use crate::core::error::{Error, Result};
+use crate::core::filters::default_entries::{self, DefaultEntriesFilter};
+use crate::core::filters::html::ImagesFilter;
+use crate::core::filters::selector;
+use crate::core::format;
+use crate::core::http_fetch::{FetchedResponse, HttpFetch, ReqwestFetch, TlsOptions};
+use crate::core::index_entry::{self, IndexEntry, TypeMeta};
+use crate::core::instrumentable::{self, instrument};
+use crate::core::page_db::{DbFormat, PageDb, StoredPage};
+use crate::core::subscriber::FileSubscriber;
+use crate::core::types::{DocSlug, KeyCase, PageKey};
+use crate::storage::Store;
 use regex::Regex;
-use reqwest::Client;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::Path;
+use scraper::Html;
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::fs;
-use tokio::sync::Mutex;
-use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 use url::Url;
 
+/// 过滤失败率检查前至少需要处理的页面数
+const MIN_PAGES_FOR_FAILURE_RATE: usize = 10;
+
+/// 默认允许的最大过滤失败率
+const DEFAULT_MAX_FAILURE_RATE: f64 = 0.5;
+
+/// 抓取结束时每种过滤器警告发布一次的事件，附带 `doc`、`filter`、`message` 和 `count`
+pub const WARN_DOC_EVENT: &str = "warn.doc";
+
+/// 开始抓取时发布的消息事件（包括从站点地图加入的网址数），附带 `message`
+pub const START_SCRAPER_EVENT: &str = "start.scraper";
+
+/// 每次请求页面前发布的消息事件，附带 `message`
+pub const FETCH_SCRAPER_EVENT: &str = "fetch.scraper";
+
+/// 请求失败、稍后重试时发布的消息事件，附带 `message`
+pub const RETRY_SCRAPER_EVENT: &str = "retry.scraper";
+
+/// 从抓取日志和抓取状态恢复时发布的消息事件，附带 `message`
+pub const RESUME_SCRAPER_EVENT: &str = "resume.scraper";
+
+/// 抓取结果保存后发布的消息事件，附带 `message`
+pub const SAVE_SCRAPER_EVENT: &str = "save.scraper";
+
+/// 抓取过程中的警告（过滤失败、放弃重试、取消等），附带 `message`
+pub const WARN_SCRAPER_EVENT: &str = "warn.scraper";
+
+/// 图片下载完成后发布的统计事件，附带 `doc`、`downloads`、`cache_hits`、`bytes_saved` 和 `message`
+pub const IMAGES_SCRAPER_EVENT: &str = "images.scraper";
+
+/// 抓取器请求使用的用户代理
+const USER_AGENT: &str = "DevDocs Rust Scraper";
+
+/// 抓取器请求的头部
+fn request_headers() -> HashMap<String, String> {
+    HashMap::from([("User-Agent".to_string(), USER_AGENT.to_string())])
+}
+
+/// 读写输出文件失败时的错误，带有文件路径
+fn storage_error(path: &Path, error: std::io::Error) -> Error {
+    Error::Storage {
+        path: path.to_path_buf(),
+        source: Box::new(Error::Io(error)),
+    }
+}
+
+/// 过滤的最后一步：提取页面内容和标题
+///
+/// 大多数过滤器只改写 HTML，不设置 `content`。没有过滤器设置内容时以过滤后的
+/// HTML 作为页面内容；没有过滤器设置标题时取第一个 `<h1>` 或 `<title>`。
+fn extract_content(context: &mut FilterContext) {
+    if context.content.is_empty() {
+        context.content = context.html().to_string();
+    }
+    if context.title.trim().is_empty() {
+        if let Some(title) = default_entries::heading_title(context.html_doc()) {
+            context.title = title;
+        }
+    }
+}
+
 /// 从网络地址爬取文档的爬虫
 pub struct UrlScraper {
     /// 文档名称
@@ -34,6 +129,8 @@ pub struct UrlScraper {
     pub release: String,
     /// 初始访问路径
     pub initial_paths: Vec<String>,
+    /// 站点地图，其中符合抓取规则的网址在开始时加入队列
+    pub sitemap_urls: Vec<String>,
     /// 需要跳过的路径
     pub skip_paths: Vec<String>,
     /// 需要跳过的模式
@@ -42,18 +139,103 @@ pub struct UrlScraper {
     pub only: Option<Vec<String>>,
     /// 只处理匹配这些模式的路径
     pub only_patterns: Option<Vec<String>>,
+    /// 优先抓取匹配这些模式的路径，越靠前优先级越高
+    pub priority_patterns: Vec<String>,
+    /// 最多抓取的页面数（None 表示不限制）
+    pub max_pages: Option<usize>,
     /// 是否在路径末尾添加斜杠
     pub trailing_slash: bool,
     /// 文档根标题
     pub root_title: String,
     /// 许可和版权信息
     pub attribution: String,
+    /// 内容的许可证（已知时为 SPDX 标识，如 `CC-BY-SA-2.5`），写入 meta.json
+    pub license: Option<String>,
     /// 相关链接
     pub links: Vec<(String, String)>,
     /// 过滤器列表
     pub filters: Vec<Box<dyn Filter>>,
     /// 跳过链接函数
     pub skip_link: Option<Box<dyn Fn(&str) -> bool + Send + Sync>>,
+    /// 过滤失败时是否保存未过滤的HTML
+    pub store_on_filter_error: bool,
+    /// 允许的最大过滤失败率，超过后中止抓取
+    pub max_failure_rate: f64,
+    /// 最近一次抓取的报告
+    pub report: CrawlReport,
+    /// 每分钟最大请求数（None 表示使用默认设置）
+    pub rate_limit: Option<u32>,
+    /// 空闲后最多可以连续发送的请求数（None 表示不允许突发）
+    pub burst: Option<u32>,
+    /// 共用的速率限制器（None 表示按 `rate_limit` 和 `burst` 为每次抓取创建）
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// 最大并发请求数（None 表示使用默认设置）
+    pub concurrency: Option<usize>,
+    /// 最大重试次数（None 表示使用默认设置）
+    pub max_retries: Option<u32>,
+    /// 请求超时时间，单位秒（None 表示使用默认设置）
+    pub request_timeout: Option<u64>,
+    /// 过滤后单个页面的大小限制，单位字节（None 表示使用默认设置）
+    pub max_page_size: Option<usize>,
+    /// 超过大小限制的页面的处理策略（None 表示使用默认设置）
+    pub oversize_policy: Option<OversizePolicy>,
+    /// 是否在 index.json 中输出页面字数并生成 stats.json
+    pub include_stats: bool,
+    /// 是否从上次中断的抓取日志和抓取状态中恢复
+    pub resume: bool,
+    /// 文档默认落地页面路径（None 表示显示类型列表）
+    pub landing_path: Option<String>,
+    /// 条目排除规则
+    pub exclude_entries: EntryExclusions,
+    /// db.json 格式（None 表示使用默认的 v1 格式）
+    pub db_format: Option<DbFormat>,
+    /// 是否去掉近似重复的页面（保留路径较短的页面）
+    pub drop_near_duplicates: bool,
+    /// 是否根据延迟和错误率自动调整并发数（不超过最大并发请求数）
+    pub adaptive_concurrency: bool,
+    /// 类型的排序权重和说明，写入 index.json 的类型列表
+    pub type_meta: HashMap<String, TypeMeta>,
+    /// 条目名称的排序语言（None 表示通用规则）
+    pub collation_locale: Option<String>,
+    /// 是否在 `_raw/` 中保存过滤前的页面，供 `refilter` 离线重新过滤
+    pub keep_raw: bool,
+    /// 是否对看起来像二进制文件的链接先发送 HEAD 请求
+    pub head_checks: bool,
+    /// 不发送 HEAD 预检的主机（这些服务器不能正确处理 HEAD）
+    pub head_check_disabled_hosts: HashSet<String>,
+    /// 抓取事件（入队、去重、跳过）发布到事件总线时的采样率，1 表示全部发布
+    pub event_sample_rate: u64,
+    /// 将全部抓取事件（不采样）以 JSON Lines 格式写入此文件
+    pub trace_urls: Option<PathBuf>,
+    /// 是否从链接中去掉找不到的锚点（默认保留，只记录在抓取报告中）
+    pub strip_broken_fragments: bool,
+    /// 发送请求使用的 HTTP 实现（None 表示按请求超时时间创建 reqwest 客户端）
+    pub fetch: Option<Arc<dyn HttpFetch>>,
+    /// 取消抓取的令牌，取消后在处理完当前页面时停止，保留抓取日志供恢复
+    pub cancel: CancellationToken,
+    /// 页面键的大小写规则，网站路径不区分大小写时设为 [`KeyCase::Lower`]
+    pub key_case: KeyCase,
+    /// 是否在抓取结束后下载网站图标作为文档图标
+    pub favicon: bool,
+    /// 创建 reqwest 客户端时使用的 TLS 设置（设置了 `fetch` 时不使用）
+    pub tls: TlsOptions,
+    /// 磁盘空间检查设置
+    pub disk_space: DiskSpaceSettings,
+    /// 查询可用空间的实现（None 表示使用 `statvfs`）
+    pub disk_probe: Option<Arc<dyn DiskSpace>>,
+    /// 抓取器预估的文档大小（字节），None 表示按上次抓取结果的大小估算
+    pub estimated_size: Option<u64>,
+    /// 是否跳过开始抓取前的磁盘空间检查（抓取过程中的检查仍然进行）
+    pub ignore_disk_space: bool,
+    /// 抓取结果中应当出现的条目类型，严格模式下缺少任何一种时抓取失败
+    pub expected_types: Vec<String>,
+    /// 严格模式的检查设置，None 表示不检查
+    pub strict: Option<StrictSettings>,
+    /// 试运行时写入输出文件的存储，None 表示写入文档目录
+    ///
+    /// 指定后抓取不在磁盘上创建或修改任何文件：不写抓取日志、抓取状态和原始页面，
+    /// 也不能恢复抓取，输出文件写入存储中的 `<doc_dir_name>/<文件名>`。
+    pub store: Option<Arc<dyn Store + Send + Sync>>,
 }
 
 impl UrlScraper {
@@ -66,19 +248,61 @@ impl UrlScraper {
             base_urls: None,
             output_path: output_path.to_string(),
             root_path: "/".to_string(),
-            slug: name.to_lowercase().replace(' ', "_"),
+            slug: DocSlug::new(name).into_string(),
             release: version.to_string(),
             initial_paths: vec!["/".to_string()],
+            sitemap_urls: Vec::new(),
             skip_paths: Vec::new(),
             skip_patterns: Vec::new(),
             only: None,
             only_patterns: None,
+            priority_patterns: Vec::new(),
+            max_pages: None,
             trailing_slash: false,
             root_title: name.to_string(),
             attribution: String::new(),
+            license: None,
             links: Vec::new(),
             filters: Vec::new(),
             skip_link: None,
+            store_on_filter_error: false,
+            max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+            report: CrawlReport::new(),
+            rate_limit: None,
+            burst: None,
+            rate_limiter: None,
+            concurrency: None,
+            max_retries: None,
+            request_timeout: None,
+            max_page_size: None,
+            oversize_policy: None,
+            include_stats: false,
+            resume: false,
+            landing_path: None,
+            exclude_entries: EntryExclusions::new(),
+            db_format: None,
+            drop_near_duplicates: false,
+            adaptive_concurrency: false,
+            type_meta: HashMap::new(),
+            collation_locale: None,
+            keep_raw: false,
+            head_checks: true,
+            head_check_disabled_hosts: HashSet::new(),
+            event_sample_rate: DEFAULT_EVENT_SAMPLE_RATE,
+            trace_urls: None,
+            strip_broken_fragments: false,
+            fetch: None,
+            cancel: CancellationToken::new(),
+            key_case: KeyCase::default(),
+            favicon: true,
+            tls: TlsOptions::default(),
+            disk_space: DiskSpaceSettings::default(),
+            disk_probe: None,
+            estimated_size: None,
+            ignore_disk_space: false,
+            expected_types: Vec::new(),
+            strict: None,
+            store: None,
         }
     }
 
@@ -94,9 +318,9 @@ impl UrlScraper {
         self
     }
 
-    /// 设置文档别名
+    /// 设置文档别名，按 [`DocSlug`] 的规则规范化
     pub fn with_slug(mut self, slug: &str) -> Self {
-        self.slug = slug.to_string();
+        self.slug = DocSlug::new(slug).into_string();
         self
     }
 
@@ -112,6 +336,34 @@ impl UrlScraper {
         self
     }
 
+    /// 添加站点地图（可以是站点地图索引或 gzip 压缩的站点地图）
+    ///
+    /// 用于无法从根页面沿链接找到所有页面的网站。站点地图中的网址与页面中的
+    /// 链接一样按抓取规则过滤。
+    pub fn with_sitemap_url(mut self, url: &str) -> Self {
+        self.sitemap_urls.push(url.to_string());
+        self
+    }
+
+    /// 设置抓取结果中应当出现的条目类型，用于严格模式的检查
+    pub fn with_expected_types(mut self, types: Vec<&str>) -> Self {
+        self.expected_types = types.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// 开启严格模式：抓取结束后按设置检查抓取质量，没有通过时返回
+    /// [`Error::StrictFailed`]
+    pub fn with_strict(mut self, settings: StrictSettings) -> Self {
+        self.strict = Some(settings);
+        self
+    }
+
+    /// 试运行：输出文件写入 `store` 而不是文档目录
+    pub fn with_store(mut self, store: Arc<dyn Store + Send + Sync>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
     /// 设置需要跳过的路径
     pub fn with_skip_paths(mut self, paths: Vec<String>) -> Self {
         self.skip_paths = paths;
@@ -136,6 +388,18 @@ impl UrlScraper {
         self
     }
 
+    /// 优先抓取匹配指定模式的路径（如 MDN 的 `^Reference/` 优先于 `^Guide/`）
+    pub fn with_priority_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.priority_patterns = patterns;
+        self
+    }
+
+    /// 设置最多抓取的页面数
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
     /// 设置是否在路径末尾添加斜杠
     pub fn with_trailing_slash(mut self, should_add: bool) -> Self {
         self.trailing_slash = should_add;
@@ -154,6 +418,12 @@ impl UrlScraper {
         self
     }
 
+    /// 设置内容的许可证，已知时使用 SPDX 标识（如 `CC-BY-SA-2.5`、`MIT`）
+    pub fn with_license(mut self, license: &str) -> Self {
+        self.license = Some(license.trim().to_string()).filter(|license| !license.is_empty());
+        self
+    }
+
     /// 设置相关链接
     pub fn with_links(mut self, links: Vec<(&str, &str)>) -> Self {
         self.links = links
@@ -178,6 +448,259 @@ impl UrlScraper {
         self
     }
 
+    /// 设置过滤失败时是否保存未过滤的HTML
+    pub fn with_store_on_filter_error(mut self, store: bool) -> Self {
+        self.store_on_filter_error = store;
+        self
+    }
+
+    /// 设置允许的最大过滤失败率
+    pub fn with_max_failure_rate(mut self, rate: f64) -> Self {
+        self.max_failure_rate = rate;
+        self
+    }
+
+    /// 设置每分钟最大请求数
+    pub fn with_rate_limit(mut self, rate_limit: u32) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// 设置空闲后最多可以连续发送的请求数
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = Some(burst);
+        self
+    }
+
+    /// 使用共用的速率限制器，多个抓取器访问同一网站时共用总速率
+    ///
+    /// 设置后 `rate_limit` 和 `burst` 不再生效。
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// 设置最大并发请求数
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// 设置最大重试次数
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// 设置请求超时时间（秒）
+    pub fn with_request_timeout(mut self, seconds: u64) -> Self {
+        self.request_timeout = Some(seconds);
+        self
+    }
+
+    /// 设置过滤后单个页面的大小限制（字节）
+    pub fn with_max_page_size(mut self, bytes: usize) -> Self {
+        self.max_page_size = Some(bytes);
+        self
+    }
+
+    /// 设置超过大小限制的页面的处理策略
+    pub fn with_oversize_policy(mut self, policy: OversizePolicy) -> Self {
+        self.oversize_policy = Some(policy);
+        self
+    }
+
+    /// 设置是否输出页面统计信息
+    pub fn with_include_stats(mut self, include_stats: bool) -> Self {
+        self.include_stats = include_stats;
+        self
+    }
+
+    /// 设置是否从上次中断的抓取日志中恢复
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// 设置文档默认落地页面路径，写入 meta.json
+    pub fn with_landing_path(mut self, path: &str) -> Self {
+        self.landing_path = Some(path.trim_matches('/').to_string());
+        self
+    }
+
+    /// 设置条目排除规则
+    pub fn with_exclude_entries(mut self, exclusions: EntryExclusions) -> Self {
+        self.exclude_entries = exclusions;
+        self
+    }
+
+    /// 设置 db.json 格式
+    pub fn with_db_format(mut self, format: DbFormat) -> Self {
+        self.db_format = Some(format);
+        self
+    }
+
+    /// 设置是否去掉近似重复的页面
+    pub fn with_drop_near_duplicates(mut self, drop: bool) -> Self {
+        self.drop_near_duplicates = drop;
+        self
+    }
+
+    /// 设置是否根据延迟和错误率自动调整并发数
+    pub fn with_adaptive_concurrency(mut self, adaptive: bool) -> Self {
+        self.adaptive_concurrency = adaptive;
+        self
+    }
+
+    /// 设置类型的排序权重（越小越靠前）和说明
+    ///
+    /// 没有设置的类型使用默认权重 0，按名称排在同权重的类型中。
+    pub fn with_type_meta(mut self, name: &str, weight: i32, description: Option<&str>) -> Self {
+        self.type_meta.insert(name.to_string(), TypeMeta::new(weight, description));
+        self
+    }
+
+    /// 设置条目名称的排序语言（如 `de`、`zh`），本地化文档按该语言的习惯排序
+    pub fn with_collation_locale(mut self, locale: &str) -> Self {
+        self.collation_locale = Some(locale.to_string());
+        self
+    }
+
+    /// 设置抓取事件发布到事件总线时的采样率（每 N 个事件发布一个）
+    pub fn with_event_sample_rate(mut self, sample_rate: u64) -> Self {
+        self.event_sample_rate = sample_rate;
+        self
+    }
+
+    /// 将全部抓取事件写入跟踪文件
+    pub fn with_trace_urls<P: Into<PathBuf>>(mut self, file: P) -> Self {
+        self.trace_urls = Some(file.into());
+        self
+    }
+
+    /// 设置是否从链接中去掉找不到的锚点
+    pub fn with_strip_broken_fragments(mut self, strip: bool) -> Self {
+        self.strip_broken_fragments = strip;
+        self
+    }
+
+    /// 添加信任的 CA 证书，用于使用私有 CA 的内部文档网站
+    pub fn with_ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tls = self.tls.with_ca_cert(path);
+        self
+    }
+
+    /// 设置是否接受无效的证书（对应命令行的 `--insecure`）
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.tls = self.tls.with_accept_invalid_certs(accept);
+        self
+    }
+
+    /// 设置发送请求使用的 HTTP 实现，测试中可以换成 `MockFetch`
+    pub fn with_fetch(mut self, fetch: Arc<dyn HttpFetch>) -> Self {
+        self.fetch = Some(fetch);
+        self
+    }
+
+    /// 发送请求使用的 HTTP 实现，没有设置时按 TLS 设置和请求超时创建 reqwest 客户端
+    fn http_fetch(&self) -> Result<Arc<dyn HttpFetch>> {
+        Ok(match &self.fetch {
+            Some(fetch) => fetch.clone(),
+            None => Arc::new(ReqwestFetch::with_options(
+                Duration::from_secs(self.effective_settings().request_timeout),
+                &self.tls,
+            )?),
+        })
+    }
+
+    /// 设置磁盘空间检查
+    pub fn with_disk_space(mut self, settings: DiskSpaceSettings) -> Self {
+        self.disk_space = settings;
+        self
+    }
+
+    /// 设置查询可用空间的实现
+    pub fn with_disk_probe(mut self, probe: Arc<dyn DiskSpace>) -> Self {
+        self.disk_probe = Some(probe);
+        self
+    }
+
+    /// 设置预估的文档大小（字节），用于开始抓取前的磁盘空间检查
+    pub fn with_estimated_size(mut self, bytes: u64) -> Self {
+        self.estimated_size = Some(bytes);
+        self
+    }
+
+    /// 设置是否跳过开始抓取前的磁盘空间检查
+    pub fn with_ignore_disk_space(mut self, ignore: bool) -> Self {
+        self.ignore_disk_space = ignore;
+        self
+    }
+
+    /// 设置取消抓取的令牌
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 设置页面键的大小写规则
+    pub fn with_key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    /// 设置是否下载网站图标作为文档图标
+    pub fn with_favicon(mut self, favicon: bool) -> Self {
+        self.favicon = favicon;
+        self
+    }
+
+    /// 设置是否保存过滤前的页面
+    pub fn with_keep_raw(mut self, keep_raw: bool) -> Self {
+        self.keep_raw = keep_raw;
+        self
+    }
+
+    /// 设置是否对看起来像二进制文件的链接先发送 HEAD 请求
+    pub fn with_head_checks(mut self, enabled: bool) -> Self {
+        self.head_checks = enabled;
+        self
+    }
+
+    /// 不对指定主机发送 HEAD 预检
+    pub fn with_head_check_disabled_for(mut self, host: &str) -> Self {
+        self.head_check_disabled_hosts.insert(host.to_lowercase());
+        self
+    }
+
+    /// 应用默认设置，已显式设置的字段保持不变
+    pub fn apply_defaults(&mut self, defaults: &ScraperDefaults) {
+        self.rate_limit.get_or_insert(defaults.rate_limit);
+        self.concurrency.get_or_insert(defaults.concurrency);
+        self.max_retries.get_or_insert(defaults.max_retries);
+        self.request_timeout.get_or_insert(defaults.request_timeout);
+        self.max_page_size.get_or_insert(defaults.max_page_size);
+        self.oversize_policy.get_or_insert(defaults.oversize_policy);
+    }
+
+    /// 获取实际生效的设置，未设置的字段使用内置默认值
+    pub fn effective_settings(&self) -> ScraperDefaults {
+        let builtin = ScraperDefaults::default();
+        ScraperDefaults {
+            rate_limit: self.rate_limit.unwrap_or(builtin.rate_limit),
+            concurrency: self.concurrency.unwrap_or(builtin.concurrency),
+            max_retries: self.max_retries.unwrap_or(builtin.max_retries),
+            request_timeout: self.request_timeout.unwrap_or(builtin.request_timeout),
+            max_page_size: self.max_page_size.unwrap_or(builtin.max_page_size),
+            oversize_policy: self.oversize_policy.unwrap_or(builtin.oversize_policy),
+        }
+    }
+
+    /// 获取最近一次抓取的报告
+    pub fn report(&self) -> &CrawlReport {
+        &self.report
+    }
+
     /// 设置多基础URL
     pub fn with_base_urls(mut self, urls: Vec<String>) -> Self {
         if !urls.is_empty() {
@@ -218,21 +741,26 @@ impl UrlScraper {
 
     /// 检查URL是否应该处理
     fn should_process_url(&self, url: &str) -> bool {
+        self.skip_reason(url).is_none()
+    }
+
+    /// 检查URL是否应该跳过，返回跳过的原因
+    pub fn skip_reason(&self, url: &str) -> Option<SkipReason> {
         // 从多个基础URL中检查
         let base_urls = self.get_base_urls();
         if !base_urls.iter().any(|base| url.starts_with(base)) {
-            return false;
+            return Some(SkipReason::External);
         }
 
         // 检查skip_link回调
         if let Some(ref skip_fn) = self.skip_link {
             if skip_fn(url) {
-                return false;
+                return Some(SkipReason::SkipLink);
             }
         }
 
         // 检查跳过路径和模式
-        let path = self.url_to_path(url);
+        let path = self.url_to_path(url).into_string();
 
         // 检查跳过路径
         if self
@@ -240,14 +768,14 @@ impl UrlScraper {
             .iter()
             .any(|p| path == *p || path.starts_with(&format!("{}/", p)))
         {
-            return false;
+            return Some(SkipReason::SkipPath);
         }
 
         // 检查跳过模式
         for pattern in &self.skip_patterns {
             if let Ok(regex) = Regex::new(pattern) {
                 if regex.is_match(&path) {
-                    return false;
+                    return Some(SkipReason::SkipPattern);
                 }
             }
         }
@@ -258,7 +786,7 @@ impl UrlScraper {
                 .iter()
                 .any(|p| path == *p || path.starts_with(&format!("{}/", p)))
             {
-                return false;
+                return Some(SkipReason::OnlyMiss);
             }
         }
 
@@ -273,11 +801,11 @@ impl UrlScraper {
                 }
             }
             if !match_any {
-                return false;
+                return Some(SkipReason::OnlyMiss);
             }
         }
 
-        true
+        None
     }
 
     /// 规范化URL
@@ -301,78 +829,138 @@ impl UrlScraper {
         Ok(format!("{}{}", base, normalized_path))
     }
 
-    /// 从URL中提取路径
-    fn url_to_path(&self, url: &str) -> String {
+    /// 从URL中提取页面键
+    fn url_to_path(&self, url: &str) -> PageKey {
         // 查找匹配的基础URL
         let base_urls = self.get_base_urls();
         for base_url in &base_urls {
             if url.starts_with(base_url) {
-                let path = url.trim_start_matches(base_url).trim_start_matches('/');
-                if path.is_empty() {
-                    return "index".to_string();
-                } else {
-                    return path.to_string();
-                }
+                return self.page_key(url.trim_start_matches(base_url));
             }
         }
 
         // 如果没有匹配的基础URL，尝试解析URL
         match Url::parse(url) {
-            Ok(parsed_url) => {
-                let path = parsed_url.path().trim_start_matches('/');
-                if path.is_empty() {
-                    "index".to_string()
-                } else {
-                    path.to_string()
-                }
-            }
-            Err(_) => "unknown".to_string(),
+            Ok(parsed_url) => self.page_key(parsed_url.path()),
+            Err(_) => self.page_key("unknown"),
+        }
+    }
+
+    /// 文档的目录名，指定了版本（`latest` 除外）时为 `<slug>~<version>`
+    pub fn doc_dir_name(&self) -> String {
+        DocSlug::new(&self.slug).dir_name(&self.version)
+    }
+
+    /// 文档的输出目录
+    fn doc_dir(&self) -> PathBuf {
+        Path::new(&self.output_path).join(self.doc_dir_name())
+    }
+
+    /// 按文档的大小写规则创建页面键
+    fn page_key(&self, path: &str) -> PageKey {
+        PageKey::with_case(path, self.key_case)
+    }
+
+    /// 条目路径中的页面部分按页面键规范化，指向重定向前路径的条目改为最终路径，锚点保持不变
+    fn normalize_entry_paths(
+        &self,
+        entries: &mut [(String, String, String)],
+        redirects: &HashMap<PageKey, PageKey>,
+    ) {
+        for (_, path, _) in entries.iter_mut() {
+            let (page, fragment) = match path.split_once('#') {
+                Some((page, fragment)) => (page, Some(fragment)),
+                None => (path.as_str(), None),
+            };
+            let key = self.page_key(page);
+            let key = redirects.get(&key).unwrap_or(&key);
+            *path = key.entry_path(fragment);
         }
     }
 
+    /// 由抓取时记录的网址重定向得到页面键的重定向，两端相同的忽略
+    fn path_redirections(&self, redirects: &HashMap<String, String>) -> HashMap<PageKey, PageKey> {
+        redirects
+            .iter()
+            .map(|(from_url, to_url)| (self.url_to_path(from_url), self.url_to_path(to_url)))
+            .filter(|(from_path, to_path)| from_path != to_path)
+            .collect()
+    }
+
     /// 发送HTTP请求获取URL内容
-    async fn fetch_url(&self, client: &Client, url: &str) -> Result<reqwest::Response> {
-        client
-            .get(url)
-            .header("User-Agent", "DevDocs Rust Scraper")
-            .send()
+    async fn fetch_url(&self, fetch: &dyn HttpFetch, url: &str) -> Result<FetchedResponse> {
+        fetch
+            .get(url, &request_headers())
             .await
-            .map_err(|e| Error::Message(format!("请求失败: {}", e)))
+            .map_err(|e| Error::Fetch {
+                url: url.to_string(),
+                source: Box::new(e),
+            })
     }
 
     /// 检查响应是否应该处理
-    fn should_process_response(&self, response: &reqwest::Response, url: &str) -> Result<bool> {
+    ///
+    /// 初始URL总是处理，不受 skip/only 规则限制（与 devdocs 一致）。
+    /// 对看起来像二进制文件的链接发送 HEAD 请求，需要跳过下载时返回跳过记录
+    ///
+    /// HEAD 请求失败或返回非成功状态时返回 None，照常发送 GET 请求。
+    async fn head_check(&self, fetch: &dyn HttpFetch, url: &str) -> Option<HeadSkip> {
+        if !self.head_checks || !head_check::hints_binary(url) {
+            return None;
+        }
+        let host = Url::parse(url).ok()?.host_str()?.to_lowercase();
+        if self.head_check_disabled_hosts.contains(&host) {
+            return None;
+        }
+
+        let response = fetch
+            .head(url, &request_headers())
+            .await
+            .ok()
+            .filter(|response| response.is_success())?;
+        let content_type = response.content_type().map(str::to_string);
+        let content_length = response.content_length();
+        let max_bytes = self.effective_settings().max_page_size as u64;
+
+        head_check::should_skip(content_type.as_deref(), content_length, max_bytes).then(|| HeadSkip {
+            url: url.to_string(),
+            content_type,
+            content_length,
+        })
+    }
+
+    fn should_process_response(
+        &self,
+        response: &FetchedResponse,
+        url: &str,
+        is_initial: bool,
+    ) -> Result<bool> {
         // 检查状态码
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Ok(false);
         }
 
         // 检查内容类型
-        if let Some(content_type) = response.headers().get(reqwest::header::CONTENT_TYPE) {
-            if let Ok(content_type_str) = content_type.to_str() {
-                if !content_type_str.contains("text/html") {
-                    return Ok(false);
-                }
+        if let Some(content_type) = response.content_type() {
+            if !content_type.contains("text/html") {
+                return Ok(false);
             }
         }
 
         // 检查URL
-        Ok(self.should_process_url(url))
+        Ok(is_initial || self.should_process_url(url))
     }
 
     /// 从HTML中提取链接
-    fn extract_links(&self, html: &str, base_url: &str) -> Result<Vec<String>> {
+    fn extract_links(&self, document: &Html, base_url: &str) -> Result<Vec<String>> {
         let mut urls = Vec::new();
-        let document = scraper::Html::parse_document(html);
 
         // 查找所有链接
-        if let Ok(selector) = scraper::Selector::parse("a[href]") {
-            for element in document.select(&selector) {
-                if let Some(href) = element.value().attr("href") {
-                    // 规范化URL
-                    if let Ok(normalized) = self.normalize_url(base_url, href) {
-                        urls.push(normalized);
-                    }
+        for element in document.select(&selector("a[href]")) {
+            if let Some(href) = element.value().attr("href") {
+                // 规范化URL
+                if let Ok(normalized) = self.normalize_url(base_url, href) {
+                    urls.push(normalized);
                 }
             }
         }
@@ -380,219 +968,3106 @@ impl UrlScraper {
         Ok(urls)
     }
 
-    /// 创建条目
-    fn create_entry(&self, path: &str) -> (String, String, String) {
-        // 使用路径作为标题
-        let title = path.to_string();
+    /// 记录近似重复的页面，启用去重时去掉路径较长的页面及其条目
+    fn resolve_near_duplicates(
+        &mut self,
+        near_duplicates: &NearDuplicateDetector,
+        pages: &mut HashMap<PageKey, StoredPage>,
+        entries: &mut Vec<(String, String, String)>,
+        word_counts: &mut HashMap<PageKey, usize>,
+    ) {
+        let mut duplicate_pairs = near_duplicates.pairs();
+        if self.drop_near_duplicates {
+            let mut dropped = HashSet::new();
+            for pair in &mut duplicate_pairs {
+                if dropped.contains(&self.page_key(&pair.path)) || dropped.contains(&self.page_key(&pair.other_path)) {
+                    continue;
+                }
+                let other = self.page_key(&pair.other_path);
+                pages.remove(&other);
+                dropped.insert(other);
+                pair.dropped = true;
+            }
+            entries.retain(|(_, path, _)| !dropped.contains(&self.page_key(path)));
+            word_counts.retain(|path, _| !dropped.contains(path));
+        }
+        for pair in &duplicate_pairs {
+            instrumentable::message(
+                WARN_SCRAPER_EVENT,
+                format!(
+                    "近似重复页面: {} 与 {}（相似度 {:.2}）{}",
+                    pair.path,
+                    pair.other_path,
+                    pair.similarity,
+                    if pair.dropped { "，已去掉后者" } else { "" }
+                ),
+            );
+        }
+        self.report.near_duplicates = duplicate_pairs;
+    }
 
-        // 条目路径
-        let entry_path = path.to_string();
+    /// 按大小限制处理过滤后的页面，返回要保存的内容，跳过页面时返回 None
+    fn limit_page_size(&mut self, url: &str, path: &str, content: String) -> Option<String> {
+        let settings = self.effective_settings();
+        if content.len() <= settings.max_page_size {
+            return Some(content);
+        }
 
-        // 条目类型 (默认为 "其他")
-        let entry_type = "Other".to_string();
+        let action = settings.oversize_policy;
+        let stored = match action {
+            OversizePolicy::Skip => None,
+            OversizePolicy::Truncate => Some(page_size::truncate_html(&content, settings.max_page_size)),
+            OversizePolicy::Keep => Some(content.clone()),
+        };
+        instrumentable::message(
+            WARN_SCRAPER_EVENT,
+            format!(
+                "页面 {} 过大（{}，限制 {}），{}",
+                url,
+                format::format_bytes(content.len() as u64),
+                format::format_bytes(settings.max_page_size as u64),
+                match action {
+                    OversizePolicy::Skip => "已跳过",
+                    OversizePolicy::Truncate => "已截断",
+                    OversizePolicy::Keep => "照常保存",
+                }
+            ),
+        );
+        self.report.oversized_pages.push(OversizedPage {
+            url: url.to_string(),
+            path: path.to_string(),
+            size: content.len(),
+            action,
+            stored_size: (action == OversizePolicy::Truncate).then(|| stored.as_ref().map_or(0, String::len)),
+        });
+        stored
+    }
 
-        (title, entry_path, entry_type)
+    /// 创建页面的过滤上下文
+    fn filter_context(&self, url: &str, path: &str, html: &str) -> FilterContext {
+        let mut context = FilterContext::new().with_base_url(&self.base_url).with_html(html);
+        context.root_url = self.base_url.clone();
+        context.root_path = self.root_path.clone();
+        context.version = self.version.clone();
+        context.release = self.release.clone();
+        context.initial_paths = self.initial_paths.clone();
+        context.slug = self.slug.clone();
+        context.current_path = path.to_string();
+        context.current_url = url.to_string();
+        context.attribution = Some(self.attribution.clone());
+        context.license = self.license.clone();
+        context
     }
 
-    // 更多实现方法...
-}
+    /// 依次应用所有过滤器，最后提取页面内容和标题（见 [`extract_content`]）
+    ///
+    /// 过滤器返回错误或发生 panic 时返回 [`Error::FilterFailed`]，带有过滤器名称和
+    /// 页面 URL，由调用方记录到抓取报告中并继续抓取其他页面。
+    fn apply_filters(&self, context: &mut FilterContext) -> Result<()> {
+        for filter in &self.filters {
+            // 过滤器只读取上下文，输出按固定规则合并，同时捕获 panic
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                filter.apply_v2(context.html(), context)
+            }));
+
+            let error = match outcome {
+                Ok(Ok(output)) => {
+                    context.merge_output(filter.name(), output);
+                    continue;
+                }
+                Ok(Err(e)) => e,
+                Err(panic) => {
+                    let message = panic
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "unknown panic".to_string());
+                    Error::Message(format!("panic: {}", message))
+                }
+            };
+            return Err(Error::FilterFailed {
+                filter: filter.name().to_string(),
+                url: context.current_url.clone(),
+                source: Box::new(error),
+            });
+        }
 
-#[async_trait::async_trait]
-impl Scraper for UrlScraper {
-    fn name(&self) -> &str {
-        &self.name
+        extract_content(context);
+        Ok(())
     }
 
-    fn version(&self) -> &str {
-        &self.version
+    /// 校验并修正页面中内部链接的锚点，找不到的锚点记录在抓取报告中
+    fn check_fragments(&mut self, pages: &mut HashMap<PageKey, StoredPage>) {
+        let index = FragmentIndex::from_pages(pages);
+        let base_urls = self.get_base_urls();
+        let doc_prefix = DocSlug::new(&self.slug).docs_prefix();
+
+        for (path, page) in pages.iter_mut() {
+            let page_url = page
+                .source_url
+                .as_deref()
+                .and_then(|url| Url::parse(url).ok())
+                .or_else(|| Url::parse(&self.base_url).ok());
+            // 内部链接可能已改写为 `/docs/<slug>/` 下的文档路径，也可能仍是原网站的网址
+            let resolve = |link: &str| {
+                if let Some(doc_path) = link.strip_prefix(&doc_prefix) {
+                    return Some(self.page_key(doc_path).into_string());
+                }
+                let url = page_url.as_ref()?.join(link).ok()?.to_string();
+                base_urls
+                    .iter()
+                    .any(|base| url.starts_with(base.as_str()))
+                    .then(|| self.url_to_path(&url).into_string())
+            };
+            let (content, broken) = fragment_links::fix_fragments(
+                &page.content,
+                path.as_str(),
+                &index,
+                self.strip_broken_fragments,
+                resolve,
+            );
+            if let Some(content) = content {
+                page.content = content;
+            }
+            self.report.broken_fragments.extend(broken);
+        }
+        self.report.broken_fragments.sort_by(|a, b| (&a.page, &a.href).cmp(&(&b.page, &b.href)));
     }
 
-    async fn run(&mut self) -> Result<()> {
-        println!("Running URL scraper for: {}", self.base_url);
+    /// 下载 [`ImagesFilter`] 留下的远程图片并内嵌到页面中，警告记录到抓取报告中
+    ///
+    /// 过滤器在抓取循环中同步执行，不能在其中等待下载，图片在所有页面过滤完成后统一下载。
+    ///
+    /// 图片和页面使用同一个 HTTP 实现下载，遵循抓取器的 TLS 设置和请求超时。
+    async fn resolve_images(&mut self, fetch: &Arc<dyn HttpFetch>, pages: &mut HashMap<PageKey, StoredPage>) {
+        let Some(filter) = self
+            .filters
+            .iter()
+            .find_map(|filter| filter.as_any().downcast_ref::<ImagesFilter>())
+        else {
+            return;
+        };
+        let filter = filter.clone().with_fetch(fetch.clone()).with_cancellation(self.cancel.clone());
+        let warnings = filter.resolve_images(pages, &self.base_url).await;
+        let stats = filter.take_stats();
+        for (url, warnings) in warnings {
+            self.report.record_warnings(&url, warnings);
+        }
 
-        // 确保输出目录存在
-        let doc_dir = Path::new(&self.output_path).join(&self.slug);
-        fs::create_dir_all(&doc_dir)
-            .await
-            .map_err(|e| Error::Message(format!("无法创建输出目录 {:?}: {}", doc_dir, e)))?;
+        if !stats.is_empty() {
+            let payload = HashMap::from([
+                ("doc".to_string(), self.slug.clone()),
+                ("downloads".to_string(), stats.downloads.to_string()),
+                ("cache_hits".to_string(), stats.cache_hits.to_string()),
+                ("bytes_saved".to_string(), stats.bytes_saved.to_string()),
+                (
+                    instrumentable::MESSAGE_KEY.to_string(),
+                    format!(
+                        "Images: {} downloaded, {} cache hits, {} bytes saved",
+                        stats.downloads, stats.cache_hits, stats.bytes_saved
+                    ),
+                ),
+            ]);
+            instrumentable::notify(IMAGES_SCRAPER_EVENT, payload);
+        }
+    }
 
-        // 创建空的 entries.json 文件以便索引生成可以进行
-        let entries_file = doc_dir.join("entries.json");
-        fs::write(&entries_file, "[]")
-            .await
-            .map_err(|e| Error::Message(format!("无法创建 entries.json 文件: {}", e)))?;
+    /// 保存抓取报告
+    async fn write_report(&self, doc_dir: &Path) -> Result<()> {
+        let report_json = serde_json::to_string_pretty(&self.report)
+            .map_err(|e| Error::Message(format!("无法序列化抓取报告: {}", e)))?;
+        self.write_output(doc_dir, quality::REPORT_FILENAME, report_json.as_bytes()).await
+    }
 
-        // 创建基本的 db.json 文件
-        let db_file = doc_dir.join("db.json");
-        fs::write(&db_file, "{}")
-            .await
-            .map_err(|e| Error::Message(format!("无法创建 db.json 文件: {}", e)))?;
+    /// 写入文档目录中的一个输出文件，试运行时写入 `store`
+    async fn write_output(&self, doc_dir: &Path, file: &str, content: &[u8]) -> Result<()> {
+        let (path, written) = match &self.store {
+            Some(store) => {
+                let path = format!("{}/{}", self.doc_dir_name(), file);
+                let written = store.write_bytes(&path, content);
+                (PathBuf::from(path), written)
+            }
+            None => {
+                let path = doc_dir.join(file);
+                let written = fs::write(&path, content).await.map_err(Error::Io);
+                (path, written)
+            }
+        };
+        written.map_err(|e| Error::Storage {
+            path,
+            source: Box::new(e),
+        })
+    }
 
-        // 实现完整的抓取逻辑
-        let client = Client::new();
-        let mut visited = HashSet::new();
-        let mut queue = VecDeque::new();
-        let mut entries = Vec::new();
-        let mut pages = HashMap::new();
-        let redirections: Arc<Mutex<HashMap<String, String>>> =
-            Arc::new(Mutex::new(HashMap::new()));
+    /// 生成 index.json，启用统计时附带每个条目目标页面的字数
+    ///
+    /// 条目已经过 [`index_entry::normalize_entries`] 规范化，索引按名称和路径去重。
+    /// `inherited` 中记录的页面属性只写入指向页面本身（不带锚点）的条目。
+    async fn write_index(
+        &self,
+        doc_dir: &Path,
+        entries: &[(String, String, String)],
+        word_counts: &HashMap<PageKey, usize>,
+        inherited: &HashMap<PageKey, bool>,
+    ) -> Result<()> {
+        let mut index = EntryIndex::new().with_key_fn(IndexEntry::name_path_key);
+        index.set_collation_locale(self.collation_locale.as_deref());
+        for (name, meta) in &self.type_meta {
+            index.set_type_meta(name, meta.clone());
+        }
+        for (name, path, entry_type) in entries {
+            let page = self.page_key(path);
+            index.add(IndexEntry {
+                name: name.clone(),
+                path: path.clone(),
+                entry_type: entry_type.clone(),
+                word_count: if self.include_stats {
+                    word_counts.get(&page).copied()
+                } else {
+                    None
+                },
+                inherited: if path.contains('#') { None } else { inherited.get(&page).copied() },
+            });
+        }
 
-        // 是否限制速率（默认每分钟60次请求）
-        let rate_limit = 60;
-        let mut last_request_time = Instant::now();
+        self.write_output(doc_dir, INDEX_FILENAME, index.to_json().as_bytes()).await?;
 
-        // 初始化要访问的URL
-        let initial_urls = self.get_initial_urls()?;
-        for url in initial_urls {
-            queue.push_back(url);
+        if self.include_stats {
+            let stats = DocStats::from_word_counts(word_counts, entries.len());
+            let stats_json = serde_json::to_string_pretty(&stats)
+                .map_err(|e| Error::Message(format!("无法序列化统计信息: {}", e)))?;
+            self.write_output(doc_dir, "stats.json", stats_json.as_bytes()).await?;
         }
 
-        // 广度优先搜索抓取页面
-        while let Some(url) = queue.pop_front() {
-            if visited.contains(&url) {
-                continue;
-            }
+        Ok(())
+    }
 
-            // 检查是否应该处理该URL
-            if !self.should_process_url(&url) {
-                continue;
-            }
+    /// 为每种过滤器警告发布一次 `warn.doc` 事件
+    fn publish_warnings(&self) {
+        for ((filter, message), count) in self.report.warning_counts() {
+            let payload = HashMap::from([
+                ("doc".to_string(), self.slug.clone()),
+                ("filter".to_string(), filter),
+                ("message".to_string(), message),
+                ("count".to_string(), count.to_string()),
+            ]);
+            instrument(WARN_DOC_EVENT, payload, || ());
+        }
+    }
 
-            println!("爬取: {}", url);
-            visited.insert(url.clone());
+    /// 保存抓取来源信息，包括实际生效的抓取设置
+    async fn write_provenance(&self, doc_dir: &Path) -> Result<()> {
+        let provenance = serde_json::json!({
+            "name": self.name,
+            "slug": self.slug,
+            "version": self.version,
+            "release": self.release,
+            "base_url": self.base_url,
+            "scraped_at": chrono::Utc::now().timestamp(),
+            "settings": self.effective_settings(),
+        });
+        let provenance_json = serde_json::to_string_pretty(&provenance)
+            .map_err(|e| Error::Message(format!("无法序列化抓取来源信息: {}", e)))?;
+        self.write_output(doc_dir, "scraper.json", provenance_json.as_bytes()).await
+    }
 
-            // 实现简单的速率限制
-            let elapsed = last_request_time.elapsed();
-            if elapsed < Duration::from_millis(1000 * 60 / rate_limit as u64) {
-                // 等待，确保不超过速率限制
-                sleep(Duration::from_millis(1000 * 60 / rate_limit as u64) - elapsed).await;
+    /// 下载网站图标保存为 `icon.png`，返回文档目录中是否有图标
+    ///
+    /// `root_page` 为根页面的网址和过滤前的 HTML，从中查找 `<link rel="icon">`；
+    /// 找不到可用的图标时不报错，已有的图标保持不变。
+    async fn store_icon(&self, fetch: &dyn HttpFetch, doc_dir: &Path, root_page: Option<&(String, String)>) -> bool {
+        if self.favicon {
+            let (page_url, html) = match root_page {
+                Some((url, html)) => (url.as_str(), Some(html.as_str())),
+                None => (self.base_url.as_str(), None),
+            };
+            if let Some(icon) = favicon::fetch_icon(fetch, page_url, html, &request_headers()).await {
+                if let Err(e) = self.write_output(doc_dir, favicon::ICON_FILENAME, &icon).await {
+                    instrumentable::message(WARN_SCRAPER_EVENT, format!("无法保存网站图标: {}", e));
+                }
             }
-            last_request_time = Instant::now();
+        }
+        match &self.store {
+            Some(store) => store
+                .exists(&format!("{}/{}", self.doc_dir_name(), favicon::ICON_FILENAME))
+                .unwrap_or(false),
+            None => doc_dir.join(favicon::ICON_FILENAME).is_file(),
+        }
+    }
 
-            // 发送HTTP请求
-            match self.fetch_url(&client, &url).await {
-                Ok(response) => {
-                    // 更新重定向映射
-                    let effective_url = response.url().to_string();
-                    if effective_url != url {
-                        let mut redirects = redirections.lock().await;
-                        redirects.insert(url.clone(), effective_url.clone());
-                    }
+    /// 更新 meta.json，保留其中手动设置的其他字段
+    ///
+    /// `has_icon` 为 None 时保留 meta.json 中原有的值。
+    async fn write_meta(&self, doc_dir: &Path, has_icon: Option<bool>) -> Result<()> {
+        let meta_path = doc_dir.join(META_FILENAME);
+        let mut meta = match fs::read_to_string(&meta_path).await {
+            Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
+                Ok(serde_json::Value::Object(map)) => map,
+                _ => serde_json::Map::new(),
+            },
+            Err(_) => serde_json::Map::new(),
+        };
 
-                    // 检查响应是否应该处理
-                    if !self.should_process_response(&response, &url)? {
-                        continue;
-                    }
+        meta.insert("name".to_string(), self.name.clone().into());
+        meta.insert("slug".to_string(), self.slug.clone().into());
+        meta.insert("version".to_string(), self.version.clone().into());
+        meta.insert("release".to_string(), self.release.clone().into());
+        if self.attribution.is_empty() {
+            meta.remove("attribution");
+        } else {
+            meta.insert("attribution".to_string(), self.attribution.clone().into());
+        }
+        match &self.license {
+            Some(license) => meta.insert("license".to_string(), license.clone().into()),
+            None => meta.remove("license"),
+        };
+        match &self.landing_path {
+            Some(path) => meta.insert("landing_path".to_string(), path.clone().into()),
+            None => meta.remove("landing_path"),
+        };
+        if let Some(has_icon) = has_icon {
+            meta.insert("has_icon".to_string(), has_icon.into());
+        }
+        meta.insert("quality".to_string(), serde_json::json!(QualityScore::from_report(&self.report)));
 
-                    // 处理响应内容
-                    let html = response
-                        .text()
-                        .await
-                        .map_err(|e| Error::Message(format!("无法获取响应内容: {}", e)))?;
-
-                    // 创建过滤上下文
-                    let mut context = FilterContext {
-                        options: HashMap::new(),
-                        base_url: self.base_url.clone(),
-                        links: Vec::new(),
-                        root_url: self.base_url.clone(),
-                        root_path: self.root_path.clone(),
-                        version: self.version.clone(),
-                        release: self.release.clone(),
-                        initial_paths: self.initial_paths.clone(),
-                        slug: self.slug.clone(),
-                        current_path: self.url_to_path(&url),
-                        current_url: url.clone(),
-                        attribution: Some(self.attribution.clone()),
-                        html: html.clone(),
-                        title: String::new(),
-                        content: String::new(),
-                        additional_entries: Vec::new(),
-                    };
+        let meta_json = serde_json::to_string_pretty(&meta)
+            .map_err(|e| Error::Message(format!("无法序列化元数据: {}", e)))?;
+        self.write_output(doc_dir, META_FILENAME, meta_json.as_bytes()).await
+    }
+
+    /// 收集页面的条目：过滤器生成的条目和附加条目
+    ///
+    /// 都没有时由 [`DefaultEntriesFilter`] 根据页面标题和路径生成，放入 `fallback`，
+    /// 抓取结束后再由 [`default_entries::merge_long_tail`] 限制推断出的类型数量。
+    fn collect_entries(
+        &self,
+        context: &mut FilterContext,
+        entries: &mut Vec<(String, String, String)>,
+        fallback: &mut Vec<(String, String, String)>,
+    ) {
+        let mut page_entries: Vec<_> = self.filters.iter().flat_map(|filter| filter.get_entries(context)).collect();
+        page_entries.append(&mut context.additional_entries);
+        if page_entries.is_empty() {
+            fallback.extend(self.fallback_entries(context));
+        } else {
+            entries.extend(page_entries);
+        }
+    }
+
+    /// 由 [`DefaultEntriesFilter`] 生成页面的条目，页面没有标题时补上
+    fn fallback_entries(&self, context: &mut FilterContext) -> Vec<(String, String, String)> {
+        let filter = DefaultEntriesFilter::new();
+        if context.title.trim().is_empty() {
+            context.title = filter.page_title(context);
+        }
+        filter.get_entries(context)
+    }
+
+    /// 当前的抓取状态
+    ///
+    /// `in_flight` 为已出队但还没有处理完的 URL，恢复抓取时重新请求。
+    fn crawl_state(
+        &self,
+        visited: &HashSet<String>,
+        queue: &CrawlQueue,
+        in_flight: &[(String, bool, usize)],
+        entries: &[(String, String, String)],
+        fallback_entries: &[(String, String, String)],
+        redirections: &HashMap<String, String>,
+    ) -> CrawlState {
+        let mut visited: Vec<String> = visited
+            .iter()
+            .filter(|url| !in_flight.iter().any(|(in_flight, _, _)| in_flight == *url))
+            .cloned()
+            .collect();
+        visited.sort();
+        let mut pending: Vec<(String, usize)> = in_flight.iter().map(|(url, _, depth)| (url.clone(), *depth)).collect();
+        pending.extend(queue.pending());
+        CrawlState {
+            base_url: self.base_url.clone(),
+            visited,
+            pending,
+            entries: entries.to_vec(),
+            fallback_entries: fallback_entries.to_vec(),
+            redirections: redirections.clone(),
+        }
+    }
+
+    /// 抓取被取消时保存恢复所需的状态并返回 [`Error::Cancelled`]
+    ///
+    /// 抓取日志、抓取状态和已保存的原始页面保留在文档目录中，之后可以用 `--resume`
+    /// 从中断处继续；db.json 等输出文件保持上次完整抓取的结果。
+    async fn cancelled(&self, doc_dir: &Path, state: CrawlState) -> Result<()> {
+        let message = if self.store.is_some() {
+            "试运行已取消"
+        } else {
+            "抓取已取消，已处理的页面保存在抓取日志中，可使用 --resume 继续"
+        };
+        instrumentable::message(WARN_SCRAPER_EVENT, message);
+        self.interrupted(doc_dir, state, Error::Cancelled).await
+    }
+
+    /// 中止抓取：保存恢复所需的状态和抓取报告后返回 `error`
+    async fn interrupted(
+        &self,
+        doc_dir: &Path,
+        state: CrawlState,
+        error: Error,
+    ) -> Result<()> {
+        // 试运行没有可以恢复的抓取日志，不保存任何状态
+        if self.store.is_some() {
+            return Err(error);
+        }
+        state.save(doc_dir)?;
+        self.write_report(doc_dir).await?;
+        Err(error)
+    }
+
+    /// 用当前的过滤器重新过滤 `_raw/` 中保存的页面，不重新请求页面
+    ///
+    /// 配置了 [`ImagesFilter`] 时仍会下载页面中的图片（设置了缓存目录时优先使用缓存），
+    /// 取消后停止下载。
+    ///
+    /// 新的 db.json、index.json、entries.json、meta.json 等文件先写入
+    /// `<output>/.<slug>.refilter`，全部生成后再逐个替换文档目录中的文件，
+    /// 过滤中途出错时文档保持不变。
+    pub async fn refilter(&mut self) -> Result<()> {
+        let doc_dir = self.doc_dir();
+        if !RawPages::exists(&doc_dir) {
+            return Err(Error::Doc(format!(
+                "{} 没有保存过滤前的页面，无法离线重新过滤。请使用 --keep-raw 重新抓取一次，之后修改过滤器时即可使用 refilter",
+                self.slug
+            )));
+        }
+        let raw_pages = RawPages::load(&doc_dir)?;
+
+        self.report = CrawlReport::new();
+        let mut entries = Vec::new();
+        let mut fallback_entries = Vec::new();
+        let mut pages = HashMap::new();
+        let mut word_counts = HashMap::new();
+        let mut inherited = HashMap::new();
+        let mut near_duplicates = NearDuplicateDetector::default();
+
+        for raw in raw_pages {
+            let path = self.page_key(&raw.path);
+            let mut context = self.filter_context(&raw.url, path.as_str(), &raw.html);
+            let filter_result = self.apply_filters(&mut context);
+            self.report
+                .record_warnings(&raw.url, std::mem::take(&mut context.warnings));
+            if let Err(error) = filter_result {
+                instrumentable::message(WARN_SCRAPER_EVENT, error.to_string());
+                if self.store_on_filter_error {
+                    let mut page_context = self.filter_context(&raw.url, path.as_str(), &raw.html);
+                    fallback_entries.extend(self.fallback_entries(&mut page_context));
+                    pages.insert(path.clone(), StoredPage::new(raw.html).with_source_url(&raw.url));
+                }
+                self.report.record_failure(PageFailure::new(
+                    &raw.url,
+                    path.as_str(),
+                    &error,
+                    self.store_on_filter_error,
+                ));
+                continue;
+            }
+            self.report.record_ok();
+
+            let content = if context.content.is_empty() {
+                None
+            } else {
+                self.limit_page_size(&raw.url, path.as_str(), std::mem::take(&mut context.content))
+            };
+            if let Some(content) = content {
+                self.collect_entries(&mut context, &mut entries, &mut fallback_entries);
+                let words = page_stats::page_words(context.html_doc());
+                near_duplicates.add(path.as_str(), &words);
+                if self.include_stats {
+                    word_counts.insert(path.clone(), words.len());
+                }
+                if let Some(value) = context.inherited {
+                    inherited.insert(path.clone(), value);
+                }
+                let page = StoredPage::new(content)
+                    .with_title(&context.title)
+                    .with_toc(std::mem::take(&mut context.toc))
+                    .with_source_url(&raw.url);
+                pages.insert(path, page);
+            }
+            entries.append(&mut context.additional_entries);
+        }
+        default_entries::merge_long_tail(&mut fallback_entries, default_entries::DEFAULT_MAX_TYPES);
+        entries.append(&mut fallback_entries);
+        let fetch = self.http_fetch()?;
+        self.resolve_images(&fetch, &mut pages).await;
+
+        self.normalize_entry_paths(&mut entries, &HashMap::new());
+        index_entry::normalize_entries(&mut entries);
+        self.resolve_near_duplicates(&near_duplicates, &mut pages, &mut entries, &mut word_counts);
+        self.report.excluded_entries = self.exclude_entries.apply(&mut entries);
+        self.check_fragments(&mut pages);
+
+        // 在暂存目录中生成所有文件，meta.json 从现有文件开始以保留手动设置的字段
+        let staging = Path::new(&self.output_path).join(format!(".{}.refilter", self.doc_dir_name()));
+        if staging.exists() {
+            fs::remove_dir_all(&staging).await?;
+        }
+        fs::create_dir_all(&staging).await?;
+        if doc_dir.join(META_FILENAME).is_file() {
+            fs::copy(doc_dir.join(META_FILENAME), staging.join(META_FILENAME)).await?;
+        }
+
+        let entries_json = serde_json::to_string_pretty(&entries)
+            .map_err(|e| Error::Message(format!("无法序列化条目数据: {}", e)))?;
+        fs::write(staging.join("entries.json"), entries_json).await?;
+        self.write_index(&staging, &entries, &word_counts, &inherited).await?;
+
+        let mut db = PageDb::new().with_format(self.db_format.unwrap_or_default());
+        db.pages_mut().extend(pages);
+        let db_json = db
+            .to_json_pretty()
+            .map_err(|e| Error::Message(format!("无法序列化页面数据: {}", e)))?;
+        fs::write(staging.join("db.json"), db_json).await?;
+        fs::write(staging.join(PAGES_FILENAME), db.to_page_list()).await?;
+        self.write_report(&staging).await?;
+        self.write_meta(&staging, None).await?;
+
+        // 逐个替换文档目录中的文件
+        let mut staged = fs::read_dir(&staging).await?;
+        while let Some(file) = staged.next_entry().await? {
+            fs::rename(file.path(), doc_dir.join(file.file_name())).await?;
+        }
+        fs::remove_dir(&staging).await?;
+        self.publish_warnings();
+
+        instrumentable::message(
+            SAVE_SCRAPER_EVENT,
+            format!(
+                "已重新过滤 {} 个页面，生成了 {} 个条目（排除 {} 个），{} 个页面过滤失败",
+                db.len(),
+                entries.len(),
+                self.report.excluded_entries,
+                self.report.failures.len()
+            ),
+        );
+        Ok(())
+    }
+
+    // 更多实现方法...
+}
+
+#[async_trait::async_trait]
+impl Scraper for UrlScraper {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        instrumentable::message(START_SCRAPER_EVENT, format!("Running URL scraper for: {}", self.base_url));
+        let started = Instant::now();
+
+        // 试运行时输出文件写入存储，不在磁盘上创建或修改任何文件
+        let dry_run = self.store.is_some();
+        let resume = self.resume && !dry_run;
+
+        // 确保输出目录存在
+        let doc_dir = self.doc_dir();
+        if !dry_run {
+            fs::create_dir_all(&doc_dir).await.map_err(|e| storage_error(&doc_dir, e))?;
+        }
+
+        // 可用空间不足以容纳预估的文档大小时不开始抓取
+        let disk_probe = self
+            .disk_probe
+            .clone()
+            .unwrap_or_else(|| Arc::new(SystemDiskSpace) as Arc<dyn DiskSpace>);
+        if !self.ignore_disk_space && !dry_run {
+            let required =
+                disk_space::required_space(disk_space::dir_size(&doc_dir), self.estimated_size, &self.disk_space);
+            disk_space::ensure_available(&*disk_probe, &doc_dir, required)
+                .map_err(|e| e.context("开始抓取前的磁盘空间检查未通过（可使用 --ignore-disk-space 跳过）"))?;
+        }
+        let mut space_monitor = (!dry_run).then(|| SpaceMonitor::new(disk_probe, &doc_dir, &self.disk_space));
+
+        // 严格模式下与上次抓取结果的条目数比较，需要在 index.json 被替换前读取
+        let previous_entries = self.strict.as_ref().and_then(|_| strict::previous_entry_count(&doc_dir));
+
+        // 创建空的 entries.json 文件以便索引生成可以进行，已有的文件在抓取完成后才替换
+        let entries_file = doc_dir.join("entries.json");
+        if !entries_file.exists() && !dry_run {
+            fs::write(&entries_file, "[]").await.map_err(|e| storage_error(&entries_file, e))?;
+        }
+
+        // 创建基本的 db.json 文件，抓取被取消时保留上次完整抓取的结果
+        let db_file = doc_dir.join("db.json");
+        if !db_file.exists() && !dry_run {
+            fs::write(&db_file, "{}").await.map_err(|e| storage_error(&db_file, e))?;
+        }
+
+        // 实现完整的抓取逻辑
+        self.report = CrawlReport::new();
+        let settings = self.effective_settings();
+        let fetch = self.http_fetch()?;
+        let mut visited = HashSet::new();
+        let mut queue = CrawlQueue::new(&self.initial_paths, &self.priority_patterns);
+        let mut entries = Vec::new();
+        let mut fallback_entries = Vec::new();
+        let mut pages = HashMap::new();
+        let mut word_counts = HashMap::new();
+        let mut inherited = HashMap::new();
+        let mut near_duplicates = NearDuplicateDetector::default();
+        // 第一个初始页面的网址和过滤前的 HTML，用于查找网站图标
+        let mut root_page: Option<(String, String)> = None;
+        let fix_redirections = FixRedirections::new();
+        let redirections = fix_redirections.redirections();
+
+        // 恢复上次中断时已处理的页面，并用有效记录重建日志
+        let recovered: HashMap<PageKey, _> = if resume {
+            PageLog::recover(&doc_dir)?
+                .into_iter()
+                .map(|(path, page)| (self.page_key(&path), page))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let mut page_log = if dry_run {
+            None
+        } else {
+            PageLog::remove(&doc_dir)?;
+            Some(PageLog::open(&doc_dir)?)
+        };
+        if let Some(page_log) = page_log.as_mut() {
+            for (path, page) in &recovered {
+                page_log.append(&page.url, path.as_str(), &page.content, &page.entries)?;
+            }
+            page_log.sync()?;
+        }
+        if !recovered.is_empty() {
+            let message = format!("从抓取日志中恢复了 {} 个页面", recovered.len());
+            instrumentable::message(RESUME_SCRAPER_EVENT, message);
+        }
+
+        // 恢复已访问的 URL、待抓取队列、条目和重定向。已访问的页面直接取自抓取日志，
+        // 之后才处理完的页面仍在队列中，出队时按恢复的页面处理
+        let state = if resume {
+            CrawlState::load(&doc_dir, &self.base_url)
+        } else {
+            if !dry_run {
+                CrawlState::remove(&doc_dir)?;
+            }
+            None
+        };
+        if let Some(state) = state {
+            instrumentable::message(
+                RESUME_SCRAPER_EVENT,
+                format!(
+                    "从抓取状态中恢复了 {} 个已访问的 URL，{} 个待抓取的 URL",
+                    state.visited.len(),
+                    state.pending.len()
+                ),
+            );
+            visited.extend(state.visited);
+            entries = state.entries;
+            fallback_entries = state.fallback_entries;
+            redirections.lock().await.extend(state.redirections);
+            for (url, depth) in state.pending {
+                let path = self.url_to_path(&url);
+                queue.push(url, path.as_str(), depth);
+            }
+            for (path, page) in &recovered {
+                if !visited.contains(&page.url) {
+                    continue;
+                }
+                let context = self.filter_context(&page.url, path.as_str(), &page.content);
+                let words = page_stats::page_words(context.html_doc());
+                near_duplicates.add(path.as_str(), &words);
+                if self.include_stats {
+                    word_counts.insert(path.clone(), words.len());
+                }
+                let stored = StoredPage::new(page.content.clone())
+                    .with_title(&DefaultEntriesFilter::new().page_title(&context))
+                    .with_source_url(&page.url);
+                pages.insert(path.clone(), stored);
+                self.report.record_ok();
+            }
+        }
+        let mut last_state_save = Instant::now();
+
+        // 恢复抓取时保留已保存的原始页面，已恢复的页面不会重新获取
+        let mut raw_pages = if self.keep_raw && !dry_run {
+            Some(RawPages::open(&doc_dir, resume)?)
+        } else {
+            None
+        };
+
+        // 限制速率（每分钟请求数），同一批的并发请求共用一个限制器
+        let limiter = self.rate_limiter.clone().unwrap_or_else(|| {
+            Arc::new(RateLimiter::new(settings.rate_limit).with_burst(self.burst.unwrap_or(1)))
+        });
+
+        // 自适应模式从一个请求开始，最多为配置的并发数
+        let mut adaptive = self
+            .adaptive_concurrency
+            .then(|| AdaptiveConcurrency::new(settings.concurrency));
+
+        // 入队、去重和跳过事件，指定跟踪文件时全部写入该文件
+        let mut events = CrawlEvents::new(self.event_sample_rate);
+        if let Some(trace) = &self.trace_urls {
+            if trace.exists() {
+                fs::remove_file(trace).await?;
+            }
+            events = events.with_trace(FileSubscriber::new(&trace.to_string_lossy()).with_format(LogFormat::Jsonl));
+        }
+
+        // 暂时性的请求失败重新放回队列，按指数退避重试
+        let mut retries = FetchRetries::new(settings.max_retries);
+
+        // 初始化要访问的URL
+        let initial = self.get_initial_urls()?;
+        let initial_urls: HashSet<String> = initial.iter().cloned().collect();
+        for url in initial {
+            let path = self.url_to_path(&url);
+            events.enqueued(&url, path.as_str(), 0);
+            queue.push(url, path.as_str(), 0);
+        }
+
+        // 站点地图中的网址与初始页面一起加入队列，恢复抓取时已访问的网址不再加入
+        let mut seeded = initial_urls.clone();
+        for sitemap_url in &self.sitemap_urls {
+            let urls = sitemap::fetch_sitemap_urls(fetch.as_ref(), sitemap_url, &request_headers(), || limiter.wait())
+                .await?;
+            let total = urls.len();
+            let mut added = 0;
+            for url in urls {
+                if !self.should_process_url(&url) || visited.contains(&url) || !seeded.insert(url.clone()) {
+                    continue;
+                }
+                let path = self.url_to_path(&url);
+                events.enqueued(&url, path.as_str(), 0);
+                queue.push(url, path.as_str(), 0);
+                added += 1;
+            }
+            instrumentable::message(
+                START_SCRAPER_EVENT,
+                format!("站点地图 {} 中有 {} 个网址，加入队列 {} 个", sitemap_url, total, added),
+            );
+        }
+
+        // 按优先级抓取页面（浅层和初始路径下的页面优先），每批并发请求多个页面，再依次处理
+        let max_pages = self.max_pages;
+        let reached_max_pages = |visited: &HashSet<String>| max_pages.is_some_and(|max| visited.len() >= max);
+        while !queue.is_empty() && !reached_max_pages(&visited) {
+            // 可用空间低于下限时按取消处理，释放空间后可以恢复抓取
+            if let Some(Err(e)) = space_monitor.as_mut().map(|monitor| monitor.check(visited.len())) {
+                instrumentable::message(
+                    WARN_SCRAPER_EVENT,
+                    "可用磁盘空间不足，已处理的页面保存在抓取日志中，释放空间后可使用 --resume 继续",
+                );
+                let state = self.crawl_state(
+                    &visited,
+                    &queue,
+                    &[],
+                    &entries,
+                    &fallback_entries,
+                    &*redirections.lock().await,
+                );
+                return self.interrupted(&doc_dir, state, e).await;
+            }
+
+            // 取消后停止抓取，已处理的页面保留在抓取日志中
+            if self.cancel.is_cancelled() || last_state_save.elapsed() >= crawl_state::SAVE_INTERVAL {
+                let state = self.crawl_state(
+                    &visited,
+                    &queue,
+                    &[],
+                    &entries,
+                    &fallback_entries,
+                    &*redirections.lock().await,
+                );
+                if self.cancel.is_cancelled() {
+                    return self.cancelled(&doc_dir, state).await;
+                }
+                if !dry_run {
+                    state.save(&doc_dir)?;
+                }
+                last_state_save = Instant::now();
+            }
 
-                    // 应用所有过滤器
-                    for filter in &self.filters {
-                        // 从context获取当前HTML
-                        let current_html = context.html.clone();
-                        // 应用过滤器
-                        let filtered_html = filter.apply(&current_html, &mut context)?;
-                        // 更新context中的HTML
-                        context.html = filtered_html;
+            let batch_size = adaptive
+                .as_ref()
+                .map_or(settings.concurrency, |controller| controller.level())
+                .max(1);
+            let mut batch = Vec::new();
+            while batch.len() < batch_size && !reached_max_pages(&visited) {
+                let Some((url, depth)) = queue.pop() else { break };
+                if visited.contains(&url) {
+                    events.skipped(&url, self.url_to_path(&url).as_str(), SkipReason::Visited);
+                    continue;
+                }
+
+                // 检查是否应该处理该URL，初始URL总是抓取
+                let is_initial = initial_urls.contains(&url);
+                if !is_initial {
+                    if let Some(reason) = self.skip_reason(&url) {
+                        events.skipped(&url, self.url_to_path(&url).as_str(), reason);
+                        continue;
                     }
+                }
 
-                    // 提取新链接添加到队列
-                    let new_urls = self.extract_links(&context.html, &url)?;
-                    for new_url in new_urls {
-                        if !visited.contains(&new_url) {
-                            queue.push_back(new_url);
+                visited.insert(url.clone());
+
+                // 已恢复的页面不再抓取和过滤，只从保存的内容中提取链接
+                let path = self.url_to_path(&url);
+                if let Some(page) = recovered.get(&path) {
+                    let document = Html::parse_document(&page.content);
+                    for new_url in self.extract_links(&document, &url)? {
+                        let new_path = self.url_to_path(&new_url);
+                        if visited.contains(&new_url) {
+                            events.deduped(&new_url, new_path.as_str());
+                        } else {
+                            events.enqueued(&new_url, new_path.as_str(), depth + 1);
+                            queue.push(new_url, new_path.as_str(), depth + 1);
                         }
                     }
+                    let mut context = self.filter_context(&page.url, path.as_str(), &page.content);
+                    // 与 collect_entries 相同，页面没有过滤器生成的条目时才使用推断的条目
+                    let fallback = self.fallback_entries(&mut context);
+                    if page.entries.is_empty() {
+                        fallback_entries.extend(fallback);
+                    } else {
+                        entries.extend(page.entries.iter().cloned());
+                    }
+                    let words = page_stats::page_words(&document);
+                    near_duplicates.add(path.as_str(), &words);
+                    if self.include_stats {
+                        word_counts.insert(path.clone(), words.len());
+                    }
+                    let stored = StoredPage::new(page.content.clone())
+                        .with_title(&context.title)
+                        .with_source_url(&page.url);
+                    pages.insert(path, stored);
+                    self.report.record_ok();
+                    continue;
+                }
+
+                batch.push((url, is_initial, depth));
+            }
+            if batch.is_empty() {
+                continue;
+            }
+
+            for (url, _, _) in &batch {
+                let message = match &adaptive {
+                    Some(controller) => format!("爬取 [并发 {}]: {}", controller.level(), url),
+                    None => format!("爬取: {}", url),
+                };
+                instrumentable::message(FETCH_SCRAPER_EVENT, message);
+            }
+
+            // 并发发送HTTP请求，每个请求先从限制器取得许可，记录每个请求的延迟，
+            // 看起来像二进制文件的链接先做 HEAD 预检
+            let scraper = &*self;
+            let requests = futures::future::join_all(batch.iter().map(|(url, _, _)| {
+                let fetch = &*fetch;
+                let limiter = &*limiter;
+                let retry_delay = retries.delay(url);
+                async move {
+                    if let Some(delay) = retry_delay {
+                        tokio::time::sleep(delay).await;
+                    }
+                    limiter.wait().await;
+                    if let Some(skip) = scraper.head_check(fetch, url).await {
+                        return Err(skip);
+                    }
+                    let started = Instant::now();
+                    let response = scraper.fetch_url(fetch, url).await;
+                    Ok((response, started.elapsed()))
+                }
+            }));
+            // 取消时放弃等待中和正在进行的请求，这些页面恢复抓取时重新获取
+            let responses = tokio::select! {
+                responses = requests => responses,
+                _ = self.cancel.cancelled() => {
+                    let state = self.crawl_state(
+                        &visited,
+                        &queue,
+                        &batch,
+                        &entries,
+                        &fallback_entries,
+                        &*redirections.lock().await,
+                    );
+                    return self.cancelled(&doc_dir, state).await;
+                }
+            };
+
+            for ((url, is_initial, depth), outcome) in batch.into_iter().zip(responses) {
+                let (response, latency) = match outcome {
+                    Ok(outcome) => outcome,
+                    Err(skip) => {
+                        instrumentable::message(
+                            FETCH_SCRAPER_EVENT,
+                            format!(
+                                "跳过 {}（HEAD: {}）",
+                                url,
+                                skip.content_type.as_deref().unwrap_or("未知类型")
+                            ),
+                        );
+                        self.report.head_skips.push(skip);
+                        continue;
+                    }
+                };
+                if let Some(controller) = adaptive.as_mut() {
+                    let outcome = match &response {
+                        Ok(response) => RequestOutcome::from_status(response.status),
+                        Err(_) => RequestOutcome::Error,
+                    };
+                    controller.record(latency, outcome);
+                }
+                self.report
+                    .record_request(matches!(&response, Ok(response) if response.status < 400));
+                // 服务器要求降低速率时暂时减半
+                if matches!(&response, Ok(response) if response.status == 429) {
+                    let current = limiter.backoff();
+                    instrumentable::message(WARN_SCRAPER_EVENT, format!("{} 返回 429，请求速率暂时降为每分钟 {} 个", url, current));
+                }
 
-                    // 保存处理后的页面
-                    if !context.content.is_empty() {
+                // 连接错误、超时、429 和 5xx 重新放回队列，重试用完后记录到 failed_urls.json
+                let response = match response {
+                    Ok(response) if !fetch_retry::is_transient_status(response.status) => response,
+                    failed => {
+                        let error = match failed {
+                            Ok(response) => format!("HTTP {}", response.status),
+                            Err(e) => e.root_cause().to_string(),
+                        };
                         let path = self.url_to_path(&url);
-                        let entry = self.create_entry(&path);
-                        entries.push(entry);
-                        pages.insert(path, context.content);
+                        if retries.record_failure(&url, path.as_str(), &error) {
+                            instrumentable::message(RETRY_SCRAPER_EVENT, format!("访问 {} 失败: {}，稍后重试", url, error));
+                            visited.remove(&url);
+                            queue.push(url, path.as_str(), depth);
+                        } else {
+                            instrumentable::message(WARN_SCRAPER_EVENT, format!("访问 {} 失败: {}，已放弃重试", url, error));
+                        }
+                        continue;
                     }
+                };
 
-                    // 处理附加条目
-                    for additional_entry in context.additional_entries {
-                        entries.push(additional_entry);
+                // 更新重定向映射
+                let effective_url = response.url.clone();
+                if effective_url != url {
+                    fix_redirections.add_redirection(&url, &effective_url).await;
+                }
+
+                // 检查响应是否应该处理
+                if !self.should_process_response(&response, &url, is_initial)? {
+                    continue;
+                }
+
+                // 处理响应内容
+                let html = response.text();
+                if is_initial && root_page.is_none() {
+                    root_page = Some((effective_url.clone(), html.clone()));
+                }
+
+                if let Some(raw_pages) = raw_pages.as_mut() {
+                    raw_pages.save(self.url_to_path(&url).as_str(), &url, &html)?;
+                }
+
+                // 创建过滤上下文
+                let mut context = self.filter_context(&url, self.url_to_path(&url).as_str(), &html);
+
+                // 应用所有过滤器，单个页面出错不会中断整个抓取
+                let filter_result = self.apply_filters(&mut context);
+                self.report
+                    .record_warnings(&url, std::mem::take(&mut context.warnings));
+                if let Err(error) = filter_result {
+                    let path = self.url_to_path(&url);
+                    instrumentable::message(WARN_SCRAPER_EVENT, error.to_string());
+
+                    if self.store_on_filter_error {
+                        let mut page_context = self.filter_context(&url, path.as_str(), &html);
+                        fallback_entries.extend(self.fallback_entries(&mut page_context));
+                        if let Some(page_log) = page_log.as_mut() {
+                            page_log.append(&url, path.as_str(), &html, &[])?;
+                        }
+                        pages.insert(path.clone(), StoredPage::new(html.clone()).with_source_url(&url));
+                    }
+
+                    self.report.record_failure(PageFailure::new(
+                        &url,
+                        path.as_str(),
+                        &error,
+                        self.store_on_filter_error,
+                    ));
+
+                    // 失败率过高时中止抓取
+                    if self.report.pages_processed() >= MIN_PAGES_FOR_FAILURE_RATE
+                        && self.report.failure_rate() > self.max_failure_rate
+                    {
+                        self.write_report(&doc_dir).await?;
+                        return Err(Error::Message(format!(
+                            "过滤失败率过高 ({}/{} 个页面)，中止抓取",
+                            self.report.failures.len(),
+                            self.report.pages_processed()
+                        )));
                     }
+                    continue;
                 }
-                Err(e) => {
-                    println!("访问 {} 失败: {}", url, e);
+                self.report.record_ok();
+
+                // 过滤后的页面已解析过时直接复用，同时用于提取链接和统计字数
+                let document = context.html_doc();
+
+                // 提取新链接添加到队列，过滤器已改写链接时使用它收集的原始链接
+                let new_urls = if context.links.is_empty() {
+                    self.extract_links(document, &url)?
+                } else {
+                    context.links.clone()
+                };
+                for new_url in new_urls {
+                    let new_path = self.url_to_path(&new_url);
+                    if visited.contains(&new_url) {
+                        events.deduped(&new_url, new_path.as_str());
+                    } else {
+                        events.enqueued(&new_url, new_path.as_str(), depth + 1);
+                        queue.push(new_url, new_path.as_str(), depth + 1);
+                    }
+                }
+
+                // 保存处理后的页面，超过大小限制时按策略跳过或截断
+                let path = self.url_to_path(&url);
+                let content = if context.content.is_empty() {
+                    None
+                } else {
+                    self.limit_page_size(&url, path.as_str(), std::mem::take(&mut context.content))
+                };
+                if let Some(content) = content {
+                    // 过滤器为该页面生成的条目与页面写入同一条日志记录
+                    let first_entry = entries.len();
+                    self.collect_entries(&mut context, &mut entries, &mut fallback_entries);
+                    let words = page_stats::page_words(context.html_doc());
+                    near_duplicates.add(path.as_str(), &words);
+                    if self.include_stats {
+                        word_counts.insert(path.clone(), words.len());
+                    }
+                    if let Some(value) = context.inherited {
+                        inherited.insert(path.clone(), value);
+                    }
+                    if let Some(page_log) = page_log.as_mut() {
+                        page_log.append(&url, path.as_str(), &content, &entries[first_entry..])?;
+                    }
+                    let page = StoredPage::new(content)
+                        .with_title(&context.title)
+                        .with_toc(std::mem::take(&mut context.toc))
+                        .with_source_url(&url);
+                    pages.insert(path, page);
                 }
+
+                // 页面没有保存时仍保留过滤器的附加条目
+                entries.append(&mut context.additional_entries);
+            }
+
+            // 每批页面处理完后把抓取日志写入磁盘
+            if let Some(page_log) = &page_log {
+                page_log.sync()?;
+            }
+
+            if let Some(controller) = adaptive.as_mut() {
+                controller.adjust();
+            }
+        }
+        if let Some(controller) = &adaptive {
+            self.report.concurrency_changes = controller.history().to_vec();
+        }
+
+        // 之后是写入阶段，不再检查取消，保证输出完整
+
+        // 所有页面过滤完成后下载图片，取消时停止下载，未下载的图片保留原地址
+        self.resolve_images(&fetch, &mut pages).await;
+
+        // 限制由路径推断出的条目类型数量
+        default_entries::merge_long_tail(&mut fallback_entries, default_entries::DEFAULT_MAX_TYPES);
+        entries.append(&mut fallback_entries);
+
+        // 按重定向把页面移到最终的路径。重定向两端、页面映射和条目路径都由 url_to_path
+        // 或 page_key 生成，大小写规则一致，直接按键查找
+        let path_redirections = self.path_redirections(&*redirections.lock().await);
+        for (from_path, to_path) in &path_redirections {
+            if let Some(page) = pages.remove(from_path) {
+                pages.insert(to_path.clone(), page);
+            }
+            if let Some(count) = word_counts.remove(from_path) {
+                word_counts.insert(to_path.clone(), count);
+            }
+            if let Some(value) = inherited.remove(from_path) {
+                inherited.insert(to_path.clone(), value);
+            }
+        }
+        self.normalize_entry_paths(&mut entries, &path_redirections);
+        // 同一页面可能既由自身生成条目，又出现在其他页面（如插件列表）生成的条目中
+        index_entry::normalize_entries(&mut entries);
+
+        // 页面中指向重定向前路径的内部链接改为最终路径
+        let doc_prefix = DocSlug::new(&self.slug).docs_prefix();
+        for (path, page) in pages.iter_mut() {
+            if let Some(content) =
+                fix_redirections::rewrite_links(&page.content, path.as_str(), &doc_prefix, &path_redirections)
+            {
+                page.content = content;
             }
         }
 
+        // 记录近似重复的页面，启用去重时去掉路径较长的页面及其条目
+        self.resolve_near_duplicates(&near_duplicates, &mut pages, &mut entries, &mut word_counts);
+
         // 保存条目到文件
+        // 去掉匹配排除规则的条目
+        self.report.excluded_entries = self.exclude_entries.apply(&mut entries);
+
         let entries_json = serde_json::to_string_pretty(&entries)
             .map_err(|e| Error::Message(format!("无法序列化条目数据: {}", e)))?;
-        fs::write(&entries_file, entries_json)
-            .await
-            .map_err(|e| Error::Message(format!("无法写入 entries.json 文件: {}", e)))?;
+        self.write_output(&doc_dir, "entries.json", entries_json.as_bytes()).await?;
 
-        // 应用重定向修复到路径映射
-        // 在这里，我们检查所有重定向，并更新页面路径映射
-        let redirects = redirections.lock().await;
-        let mut path_redirections = HashMap::new();
+        // 生成索引和统计信息
+        self.write_index(&doc_dir, &entries, &word_counts, &inherited).await?;
 
-        // 处理重定向映射
-        for (from_url, to_url) in redirects.iter() {
-            // 从URL获取路径
-            let from_path = self.url_to_path(from_url);
-            let to_path = self.url_to_path(to_url);
+        // 页面路径确定后校验链接锚点
+        self.check_fragments(&mut pages);
 
-            // 只有当路径不同时才添加重定向
-            if from_path != to_path {
-                path_redirections.insert(from_path.to_lowercase(), to_path);
+        // 保存页面内容到数据库文件，先写入临时文件再替换，不会留下写了一半的 db.json
+        let mut db = PageDb::new().with_format(self.db_format.unwrap_or_default());
+        db.pages_mut().extend(pages);
+        let db_json = db
+            .to_json_pretty()
+            .map_err(|e| Error::Message(format!("无法序列化页面数据: {}", e)))?;
+        let db_size = db_json.len() as u64;
+        if dry_run {
+            self.write_output(&doc_dir, "db.json", db_json.as_bytes()).await?;
+        } else {
+            let db_tmp = doc_dir.join("db.json.tmp");
+            fs::write(&db_tmp, db_json).await.map_err(|e| storage_error(&db_tmp, e))?;
+            fs::rename(&db_tmp, &db_file).await.map_err(|e| storage_error(&db_file, e))?;
+
+            // 页面已写入 db.json，不再需要日志和抓取状态
+            drop(page_log);
+            PageLog::remove(&doc_dir)?;
+            CrawlState::remove(&doc_dir)?;
+        }
+        self.write_output(&doc_dir, PAGES_FILENAME, db.to_page_list().as_bytes()).await?;
+
+        // 保存抓取报告、最终失败的页面、重定向和来源信息
+        self.write_report(&doc_dir).await?;
+        if !dry_run {
+            retries.save(&doc_dir)?;
+            fix_redirections::save_redirections(&doc_dir, &path_redirections)?;
+        }
+        self.write_provenance(&doc_dir).await?;
+        let has_icon = self.store_icon(&*fetch, &doc_dir, root_page.as_ref()).await;
+        self.write_meta(&doc_dir, Some(has_icon)).await?;
+        self.publish_warnings();
+
+        instrumentable::message(
+            SAVE_SCRAPER_EVENT,
+            format!(
+                "已完成抓取，处理了 {} 个页面，生成了 {} 个条目（排除 {} 个），{} 个页面过滤失败，{} 个警告，db.json {}，耗时 {}",
+                db.len(),
+                entries.len(),
+                self.report.excluded_entries,
+                self.report.failures.len(),
+                self.report.warnings.len(),
+                format::format_bytes(db_size),
+                format::format_duration(started.elapsed())
+            ),
+        );
+        if dry_run {
+            instrumentable::message(SAVE_SCRAPER_EVENT, format!("试运行，没有写入 {:?}", doc_dir));
+        } else {
+            instrumentable::message(SAVE_SCRAPER_EVENT, format!("保存结果到: {:?}", doc_dir));
+        }
+
+        if let Some(settings) = &self.strict {
+            let entry_types = entries.iter().map(|(_, _, entry_type)| entry_type.as_str());
+            let failures = strict::check(settings, &self.report, &self.expected_types, entry_types, previous_entries);
+            if !failures.is_empty() {
+                return Err(Error::StrictFailed(failures.iter().map(ToString::to_string).collect()));
             }
         }
+        Ok(())
+    }
 
-        // 更新路径映射
-        for (path, _) in pages.clone().iter() {
-            if let Some(redirect_path) = path_redirections.get(&path.to_lowercase()) {
-                if let Some(content) = pages.remove(path) {
-                    pages.insert(redirect_path.clone(), content);
-                }
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::filters::UrlNormalizerFilter;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch, MockReply};
+    use crate::core::scraper::filter::FilterOutput;
+    use crate::web::page_cache::PageCache;
+    use std::any::Any;
+    use tempfile::TempDir;
+
+    /// 在指定路径上返回错误的过滤器
+    #[derive(Clone)]
+    struct FailOnPath(&'static str);
+
+    impl Filter for FailOnPath {
+        fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+            if context.current_path == self.0 {
+                return Err(Error::Html(format!("malformed page: {}", self.0)));
             }
+            context.content = html.to_string();
+            Ok(html.to_string())
         }
 
-        // 保存页面内容到数据库文件
-        let db_json = serde_json::to_string_pretty(&pages)
-            .map_err(|e| Error::Message(format!("无法序列化页面数据: {}", e)))?;
-        fs::write(&db_file, db_json)
-            .await
-            .map_err(|e| Error::Message(format!("无法写入 db.json 文件: {}", e)))?;
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    async fn fixture_server() -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        let pages = [
+            ("/", r#"<html><body><a href="/a">A</a><a href="/b">B</a></body></html>"#),
+            ("/a", "<html><body><p>Page A has five words</p><pre>let ignored = 1;</pre></body></html>"),
+            ("/b", "<html><body><p>Page B</p></body></html>"),
+        ];
+        for (path, body) in pages {
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(body)
+                .create_async()
+                .await;
+        }
+        server
+    }
+
+    const MOCK_BASE: &str = "https://example.com";
+
+    /// 与 `fixture_server` 相同的页面，由 `MockFetch` 提供
+    fn fixture_fetch() -> MockFetch {
+        MockFetch::new()
+            .with_response(
+                "https://example.com/",
+                FetchedResponse::html(
+                    r#"<html><body><a href="https://example.com/a">A</a><a href="https://example.com/b">B</a></body></html>"#,
+                ),
+            )
+            .with_response(
+                "https://example.com/a",
+                FetchedResponse::html("<html><body><p>Page A has five words</p></body></html>"),
+            )
+            .with_response(
+                "https://example.com/b",
+                FetchedResponse::html("<html><body><p>Page B</p></body></html>"),
+            )
+    }
+
+    fn read_db(dir: &TempDir) -> HashMap<String, String> {
+        let db = std::fs::read_to_string(dir.path().join("fixture~1").join("db.json")).unwrap();
+        serde_json::from_str(&db).unwrap()
+    }
 
-        println!(
-            "已完成抓取，处理了 {} 个页面，生成了 {} 个条目",
-            pages.len(),
-            entries.len()
+    #[tokio::test]
+    async fn test_filter_error_does_not_abort_crawl() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("b")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        assert!(pages.contains_key("index"));
+        assert!(pages.contains_key("a"));
+        assert!(!pages.contains_key("b"));
+
+        let report = scraper.report();
+        assert_eq!(report.pages_ok, 2);
+        let failure = report.failure_for("b").unwrap();
+        assert_eq!(failure.filter, "FailOnPath");
+        // 报告中的错误带有过滤器和页面 URL
+        assert_eq!(
+            failure.error,
+            format!("过滤器 FailOnPath 处理 {}/b 失败: {}", server.url(), failure.cause)
         );
-        println!("保存结果到: {:?}", doc_dir);
-        Ok(())
+        assert!(failure.cause.contains("malformed page"));
+        assert!(output.path().join("fixture~1").join("report.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_store_unfiltered_html_on_filter_error() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("b")))
+            .with_store_on_filter_error(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        assert!(pages["b"].contains("Page B"));
+        assert!(scraper.report().failure_for("b").unwrap().stored_unfiltered);
+    }
+
+    #[test]
+    fn test_settings_precedence() {
+        let config_defaults = ScraperDefaults {
+            rate_limit: 30,
+            concurrency: 4,
+            max_retries: 5,
+            request_timeout: 30,
+            ..ScraperDefaults::default()
+        };
+
+        // 未设置时使用内置默认值
+        let scraper = UrlScraper::new("Fixture", "1", "https://example.com", "docs");
+        assert_eq!(scraper.effective_settings(), ScraperDefaults::default());
+
+        // 配置覆盖内置默认值
+        let mut scraper = UrlScraper::new("Fixture", "1", "https://example.com", "docs");
+        scraper.apply_defaults(&config_defaults);
+        assert_eq!(scraper.effective_settings(), config_defaults);
+
+        // 抓取器显式设置覆盖配置
+        let mut scraper = UrlScraper::new("Fixture", "1", "https://example.com", "docs")
+            .with_rate_limit(10)
+            .with_request_timeout(5);
+        scraper.apply_defaults(&config_defaults);
+        let settings = scraper.effective_settings();
+        assert_eq!(settings.rate_limit, 10);
+        assert_eq!(settings.request_timeout, 5);
+        assert_eq!(settings.concurrency, 4);
+        assert_eq!(settings.max_retries, 5);
+    }
+
+    #[tokio::test]
+    async fn test_provenance_includes_effective_settings() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_rate_limit(6000)
+            .with_max_retries(7);
+        scraper.run().await.unwrap();
+
+        let provenance = std::fs::read_to_string(output.path().join("fixture~1").join("scraper.json")).unwrap();
+        let provenance: serde_json::Value = serde_json::from_str(&provenance).unwrap();
+        assert_eq!(provenance["settings"]["rate_limit"], 6000);
+        assert_eq!(provenance["settings"]["max_retries"], 7);
+        assert_eq!(provenance["settings"]["concurrency"], 1);
+    }
+
+    fn read_index_entries(dir: &TempDir) -> Vec<serde_json::Value> {
+        let index = std::fs::read_to_string(dir.path().join("fixture~1").join("index.json")).unwrap();
+        let index: serde_json::Value = serde_json::from_str(&index).unwrap();
+        index["entries"].as_array().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_index_includes_word_counts_when_enabled() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_include_stats(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let entries = read_index_entries(&output);
+        let page_a = entries.iter().find(|entry| entry["path"] == "a").unwrap();
+        assert_eq!(page_a["word_count"], 5);
+        let page_b = entries.iter().find(|entry| entry["path"] == "b").unwrap();
+        assert_eq!(page_b["word_count"], 2);
+
+        let stats = std::fs::read_to_string(output.path().join("fixture~1").join("stats.json")).unwrap();
+        let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+        assert_eq!(stats["page_count"], 3);
+        assert_eq!(stats["total_words"], 2 + 5 + 2);
+    }
+
+    #[tokio::test]
+    async fn test_index_omits_word_counts_by_default() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let entries = read_index_entries(&output);
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|entry| entry.get("word_count").is_none()));
+        assert!(!output.path().join("fixture~1").join("stats.json").exists());
+    }
+
+    /// 返回包含一对近似重复页面（正文 95% 相同）和一个不同页面的服务器
+    async fn near_duplicate_server() -> mockito::ServerGuard {
+        let words = |prefix: &str, changed: &str| {
+            (0..100)
+                .map(|i| if i < 95 { format!("{}{}", prefix, i) } else { format!("{}{}", changed, i) })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut server = mockito::Server::new_async().await;
+        let pages = [
+            ("/", r#"<a href="/guide">Guide</a><a href="/guide/print">Print</a><a href="/other">Other</a>"#.to_string()),
+            ("/guide", format!("<p>{}</p>", words("term", "term"))),
+            ("/guide/print", format!("<p>{}</p>", words("term", "print"))),
+            ("/other", format!("<p>{}</p>", words("topic", "topic"))),
+        ];
+        for (path, body) in pages {
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(format!("<html><body>{}</body></html>", body))
+                .create_async()
+                .await;
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn test_near_duplicates_are_reported() {
+        let server = near_duplicate_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let near_duplicates = &scraper.report().near_duplicates;
+        assert_eq!(near_duplicates.len(), 1);
+        assert_eq!(near_duplicates[0].path, "guide");
+        assert_eq!(near_duplicates[0].other_path, "guide/print");
+        assert!(!near_duplicates[0].dropped);
+
+        // 默认只报告，不去掉页面
+        let pages = read_db(&output);
+        assert!(pages.contains_key("guide/print"));
+        assert!(pages.contains_key("other"));
+
+        let report = std::fs::read_to_string(output.path().join("fixture~1").join("report.json")).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report["near_duplicates"][0]["other_path"], "guide/print");
+    }
+
+    #[tokio::test]
+    async fn test_drop_near_duplicates_keeps_shorter_path() {
+        let server = near_duplicate_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_drop_near_duplicates(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        assert!(scraper.report().near_duplicates[0].dropped);
+        let pages = read_db(&output);
+        assert!(pages.contains_key("guide"));
+        assert!(!pages.contains_key("guide/print"));
+        assert!(pages.contains_key("other"));
+
+        let entries = read_index_entries(&output);
+        assert!(entries.iter().all(|entry| entry["path"] != "guide/print"));
+    }
+
+    #[tokio::test]
+    async fn test_page_named_like_docset_file() {
+        let mut server = mockito::Server::new_async().await;
+        for (path, body) in [
+            ("/", r#"<html><body><a href="/meta">Meta</a><a href="/index">Index</a></body></html>"#),
+            ("/meta", "<html><body><p>Meta tags</p></body></html>"),
+            ("/index", "<html><body><p>Index of terms</p></body></html>"),
+        ] {
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(body)
+                .create_async()
+                .await;
+        }
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        // 重新生成单个页面时页面文件与文档文件位于同一目录
+        let doc_dir = output.path().join("fixture~1");
+        let meta_page = crate::docs::store_page(output.path(), "fixture~1", "meta", "<p>Meta tags</p>").unwrap();
+        assert!(meta_page.starts_with(doc_dir.join("_p")));
+
+        let meta = std::fs::read_to_string(doc_dir.join(META_FILENAME)).unwrap();
+        let meta: serde_json::Value = serde_json::from_str(&meta).unwrap();
+        assert_eq!(meta["name"], "Fixture");
+        assert!(crate::storage::page_paths::find_collisions(&doc_dir).unwrap().is_empty());
+
+        let mut registry = crate::docs::DocRegistry::new();
+        registry.load_from_disk(&[output.path()]).unwrap();
+        let doc = registry.find("fixture").unwrap();
+        assert_eq!(std::fs::read_to_string(doc.page_file("meta")).unwrap(), "<p>Meta tags</p>");
+        let page_cache = PageCache::new();
+        assert!(doc.stored_page(&page_cache, &PageKey::new("meta")).unwrap().content.contains("Meta tags"));
+        assert!(doc.stored_page(&page_cache, &PageKey::new("index")).unwrap().content.contains("Index of terms"));
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_concurrency_is_reported() {
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(Arc::new(fixture_fetch()))
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_concurrency(4)
+            .with_adaptive_concurrency(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        // 第一批只有首页，第二批并发请求 a 和 b
+        let levels: Vec<usize> = scraper.report().concurrency_changes.iter().map(|c| c.level).collect();
+        assert_eq!(levels, vec![2, 3]);
+        assert_eq!(read_db(&output).len(), 3);
+
+        let report = std::fs::read_to_string(output.path().join("fixture~1").join("report.json")).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report["concurrency_changes"][0]["reason"], "healthy");
+        assert_eq!(report["concurrency_changes"][1]["requests"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_sitemap_urls_seed_queue() {
+        let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://example.com/</loc></url>
+              <url><loc>https://example.com/api/map</loc></url>
+              <url><loc>https://example.com/api/set</loc></url>
+              <url><loc>https://example.com/private/keys</loc></url>
+              <url><loc>https://other.example.com/api/map</loc></url>
+            </urlset>"#;
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response("https://example.com/", FetchedResponse::html("<p>No links</p>"))
+                .with_response("https://example.com/sitemap.xml", FetchedResponse::new(200).with_body(sitemap))
+                .with_response("https://example.com/api/map", FetchedResponse::html("<p>Map</p>"))
+                .with_response("https://example.com/api/set", FetchedResponse::html("<p>Set</p>")),
+        );
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_sitemap_url("https://example.com/sitemap.xml")
+            .with_skip_paths(vec!["private".to_string()])
+            .with_favicon(false)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let mut pages: Vec<String> = read_db(&output).into_keys().collect();
+        pages.sort();
+        assert_eq!(pages, ["api/map", "api/set", "index"]);
+        // 根页面只请求一次，跳过的和外部的网址不请求
+        assert_eq!(fetch.request_count("https://example.com/"), 1);
+        assert_eq!(fetch.request_count("https://example.com/private/keys"), 0);
+        assert_eq!(fetch.request_count("https://other.example.com/api/map"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_missing_sitemap_fails_scrape() {
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(Arc::new(fixture_fetch()))
+            .with_sitemap_url("https://example.com/sitemap.xml")
+            .with_favicon(false)
+            .with_rate_limit(6000);
+        let error = scraper.run().await.unwrap_err();
+        assert!(error.to_string().contains("sitemap.xml"), "{}", error);
+    }
+
+    /// 以严格模式抓取，返回没有通过的检查
+    async fn strict_gates(scraper: UrlScraper) -> Vec<String> {
+        let mut scraper = scraper.with_strict(StrictSettings::default()).with_favicon(false).with_rate_limit(6000);
+        match scraper.run().await {
+            Ok(()) => Vec::new(),
+            Err(Error::StrictFailed(gates)) => gates,
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_gates() {
+        // 每次抓取使用单独的输出目录，避免与上次的结果比较条目数
+        let outputs: Vec<TempDir> = (0..5).map(|_| TempDir::new().unwrap()).collect();
+        let new_scraper = |output: &TempDir, fetch: MockFetch| {
+            UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap()).with_fetch(Arc::new(fetch))
+        };
+
+        // 没有问题的抓取通过
+        assert!(strict_gates(new_scraper(&outputs[0], fixture_fetch())).await.is_empty());
+
+        let scraper = new_scraper(&outputs[1], fixture_fetch()).with_filter(Box::new(FailOnPath("b")));
+        assert_eq!(strict_gates(scraper).await, ["过滤失败: 1 个页面过滤失败，最多允许 0 个"]);
+
+        let fetch = MockFetch::new()
+            .with_response(
+                "https://example.com/",
+                FetchedResponse::html(r#"<a href="https://example.com/a">A</a><a href="https://example.com/gone">Gone</a>"#),
+            )
+            .with_response("https://example.com/a", FetchedResponse::html("<p>Page A</p>"))
+            .with_response("https://example.com/gone", FetchedResponse::new(404));
+        assert_eq!(
+            strict_gates(new_scraper(&outputs[2], fetch)).await,
+            ["请求错误率: 1/3 个请求出错 (33.3%)，最多允许 5.0%"]
+        );
+
+        let scraper = new_scraper(&outputs[3], fixture_fetch()).with_expected_types(vec!["Methods"]);
+        assert_eq!(strict_gates(scraper).await, ["预期的条目类型: Methods 没有任何条目"]);
+
+        // 上次的结果有 10 个条目，这次只有 3 个
+        let doc_dir = outputs[4].path().join("fixture~1");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        let entries: Vec<serde_json::Value> =
+            (0..10).map(|i| serde_json::json!({"name": i.to_string(), "path": i.to_string(), "type": "Other"})).collect();
+        std::fs::write(doc_dir.join(INDEX_FILENAME), serde_json::json!({ "entries": entries }).to_string()).unwrap();
+        assert_eq!(
+            strict_gates(new_scraper(&outputs[4], fixture_fetch())).await,
+            ["条目数减少: 从 10 个减少到 3 个 (-70.0%)，最多允许减少 20.0%"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strict_failure_lists_every_gate() {
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(Arc::new(fixture_fetch()))
+            .with_filter(Box::new(FailOnPath("b")))
+            .with_expected_types(vec!["Methods", "Classes"])
+            .with_strict(StrictSettings::default())
+            .with_favicon(false)
+            .with_rate_limit(6000);
+        let error = scraper.run().await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "严格模式检查未通过 (3 项)\n  - 过滤失败: 1 个页面过滤失败，最多允许 0 个\n  - 预期的条目类型: Methods 没有任何条目\n  - 预期的条目类型: Classes 没有任何条目"
+        );
+        // 结果照常写入，便于查看
+        assert!(read_db(&output).contains_key("a"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_only_to_store() {
+        let output = TempDir::new().unwrap();
+        let store = Arc::new(crate::storage::MemoryStore::new());
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(Arc::new(fixture_fetch()))
+            .with_store(store.clone());
+        scraper.keep_raw = true;
+        scraper.run().await.unwrap();
+
+        // 文档目录没有创建，输出文件都在存储中
+        assert!(!output.path().join("fixture~1").exists());
+        let files: Vec<String> = store.files().into_iter().map(|(path, _)| path).collect();
+        for file in ["db.json", "entries.json", "index.json", "meta.json", "scraper.json", "report.json"] {
+            assert!(files.contains(&format!("fixture~1/{}", file)), "{} 没有写入存储", file);
+        }
+        let db: HashMap<String, String> = serde_json::from_str(&store.read("fixture~1/db.json").unwrap()).unwrap();
+        assert!(db.contains_key("index"));
+        assert!(db.contains_key("a"));
+        assert!(db.contains_key("b"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_rate_limit() {
+        let output = TempDir::new().unwrap();
+        let fetch = Arc::new(fixture_fetch());
+
+        // 每 100 毫秒一个请求，同一批并发的 a 和 b 也要依次等待
+        let started = Instant::now();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_concurrency(4)
+            .with_favicon(false)
+            .with_rate_limit(600);
+        scraper.run().await.unwrap();
+        assert_eq!(fetch.requests().len(), 3);
+        assert!(started.elapsed() >= Duration::from_millis(190), "{:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_page_halves_concurrency() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response(
+                    "https://example.com/",
+                    FetchedResponse::html(
+                        r#"<a href="https://example.com/a">A</a><a href="https://example.com/b">B</a>"#,
+                    ),
+                )
+                .with_reply(
+                    "https://example.com/a",
+                    MockReply::respond(FetchedResponse::new(429).with_header("Retry-After", "1"))
+                        .after(Duration::from_millis(10)),
+                )
+                .with_response("https://example.com/b", FetchedResponse::html("<p>Page B</p>")),
+        );
+        let output = TempDir::new().unwrap();
+
+        let limiter = Arc::new(RateLimiter::new(6000));
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_concurrency(4)
+            .with_adaptive_concurrency(true)
+            .with_rate_limiter(limiter.clone())
+            .with_max_retries(0);
+        scraper.run().await.unwrap();
+        assert_eq!(limiter.current_limit(), 3000);
+
+        // 429 的页面不保存，这一批之后并发数减半
+        let pages = read_db(&output);
+        assert!(!pages.contains_key("a"));
+        assert!(pages.contains_key("b"));
+        let changes = &scraper.report().concurrency_changes;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].level, 1);
+        assert_eq!(fetch.request_count("https://example.com/a"), 1);
+        let requests = fetch.requests();
+        assert_eq!(requests[0].url, "https://example.com/");
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].headers["User-Agent"], USER_AGENT);
+    }
+
+    #[tokio::test]
+    async fn test_redirects_and_entries_use_page_keys() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response(
+                    "https://example.com/",
+                    FetchedResponse::html(
+                        r#"<a href="https://example.com/Old">Old</a><a href="https://example.com/Guide/">Guide</a><a href="/docs/fixture/Old#top">Top</a>"#,
+                    ),
+                )
+                .with_response(
+                    "https://example.com/Old",
+                    FetchedResponse::html("<p>Moved</p>").with_url("https://example.com/NEW/"),
+                )
+                .with_response("https://example.com/Guide/", FetchedResponse::html("<p>Guide</p>")),
+        );
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch)
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_key_case(KeyCase::Lower)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        // 重定向前后的路径大小写不同，仍然按同一规则生成的键移动页面，条目随之指向最终路径
+        let mut keys: Vec<String> = read_db(&output).into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["guide", "index", "new"]);
+        let entries = std::fs::read_to_string(output.path().join("fixture~1").join("entries.json")).unwrap();
+        let entries: Vec<(String, String, String)> = serde_json::from_str(&entries).unwrap();
+        let mut paths: Vec<&str> = entries.iter().map(|(_, path, _)| path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["guide", "index", "new"]);
+
+        // 文档内的链接改为最终路径，重定向记录在 redirections.json 中
+        assert!(read_db(&output)["index"].contains(r#"href="/docs/fixture/new#top""#));
+        let redirects =
+            fix_redirections::load_redirections(&output.path().join("fixture~1")).unwrap();
+        assert_eq!(redirects, HashMap::from([(PageKey::new("old"), PageKey::new("new"))]));
+    }
+
+    #[tokio::test]
+    async fn test_images_are_downloaded_after_crawl() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::new(1, 1).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response(
+                    "https://example.com/",
+                    FetchedResponse::html(r#"<p>Logo</p><img src="/img/logo.png"><img src="/img/missing.png">"#),
+                )
+                .with_response(
+                    "https://example.com/img/logo.png",
+                    FetchedResponse::new(200)
+                        .with_header("content-type", "image/png")
+                        .with_body(png.into_inner()),
+                ),
+        );
+        let output = TempDir::new().unwrap();
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        crate::core::instrumentable::subscribe(IMAGES_SCRAPER_EVENT, move |info| {
+            if info.payload.get("doc").map(String::as_str) == Some("fixture") {
+                events_clone.lock().unwrap().push(info.payload.clone());
+            }
+        });
+
+        // 过滤器在运行时中执行，下载图片不能阻塞也不能 panic；图片通过抓取器的 HTTP 实现下载
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_filter(Box::new(ImagesFilter::new()))
+            .with_rate_limit(6000)
+            .with_favicon(false);
+        scraper.run().await.unwrap();
+
+        let index = &read_db(&output)["index"];
+        assert!(index.contains(r#"<img src="data:image/png;base64,"#), "{}", index);
+        assert!(index.contains(r#"<img src="/img/missing.png">"#), "{}", index);
+        assert_eq!(fetch.request_count("https://example.com/img/logo.png"), 1);
+        let warnings = &scraper.report().warnings;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].url.as_deref(), Some("https://example.com/img/missing.png"));
+        assert_eq!(warnings[0].page.as_deref(), Some("https://example.com/"));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["downloads"], "1");
+        assert_eq!(events[0]["cache_hits"], "0");
+    }
+
+    #[tokio::test]
+    async fn test_network_failure_does_not_abort_crawl() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response(
+                    "https://example.com/",
+                    FetchedResponse::html(
+                        r#"<a href="https://example.com/a">A</a><a href="https://example.com/b">B</a>"#,
+                    ),
+                )
+                .with_response("https://example.com/a", FetchedResponse::html("<p>Page A</p>"))
+                .with_failure("https://example.com/b", "connection reset"),
+        );
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000)
+            .with_max_retries(1)
+            .with_favicon(false);
+        scraper.run().await.unwrap();
+
+        let mut paths: Vec<String> = read_db(&output).into_keys().collect();
+        paths.sort();
+        assert_eq!(paths, ["a", "index"]);
+        assert_eq!(
+            fetch.requested_urls(),
+            [
+                "https://example.com/",
+                "https://example.com/a",
+                "https://example.com/b",
+                "https://example.com/b"
+            ]
+        );
+
+        // 重试用完后记录在 failed_urls.json 中
+        let failed = std::fs::read_to_string(output.path().join("fixture~1").join(fetch_retry::FAILED_URLS_FILENAME));
+        let failed: Vec<fetch_retry::FailedUrl> = serde_json::from_str(&failed.unwrap()).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].url, "https://example.com/b");
+        assert_eq!(failed[0].path, "b");
+        assert_eq!(failed[0].attempts, 2);
+        assert!(failed[0].error.contains("connection reset"), "{}", failed[0].error);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_error_carries_url() {
+        let fetch = MockFetch::new().with_failure("https://example.com/b", "connection reset");
+        let scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, "");
+
+        let error = scraper.fetch_url(&fetch, "https://example.com/b").await.unwrap_err();
+        assert!(matches!(error, Error::Fetch { .. }));
+        assert_eq!(error.url(), Some("https://example.com/b"));
+        assert!(error.to_string().contains("https://example.com/b"), "{}", error);
+        assert!(error.root_cause().to_string().contains("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_retried_with_backoff() {
+        let fetch = Arc::new(
+            MockFetch::new()
+                .with_response(
+                    "https://example.com/",
+                    FetchedResponse::html(
+                        r#"<a href="https://example.com/a">A</a><a href="https://example.com/gone">Gone</a>"#,
+                    ),
+                )
+                .with_response("https://example.com/a", FetchedResponse::new(503))
+                .with_response("https://example.com/a", FetchedResponse::new(502))
+                .with_response("https://example.com/a", FetchedResponse::html("<p>Page A</p>"))
+                .with_response("https://example.com/gone", FetchedResponse::new(404)),
+        );
+        let output = TempDir::new().unwrap();
+
+        let started = Instant::now();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000)
+            .with_max_retries(3)
+            .with_favicon(false);
+        scraper.run().await.unwrap();
+
+        // 两次失败后分别等待 0.5 秒和 1 秒，第三次请求成功；404 不重试
+        assert!(read_db(&output).contains_key("a"));
+        assert_eq!(fetch.request_count("https://example.com/a"), 3);
+        assert_eq!(fetch.request_count("https://example.com/gone"), 1);
+        assert!(started.elapsed() >= Duration::from_millis(1500), "{:?}", started.elapsed());
+        assert!(!output.path().join("fixture~1").join(fetch_retry::FAILED_URLS_FILENAME).exists());
+    }
+
+    #[tokio::test]
+    async fn test_max_pages_covers_priority_subtree_first() {
+        let mut server = mockito::Server::new_async().await;
+        let pages = [
+            ("/", r#"<a href="/Guide/a">a</a><a href="/Guide/b">b</a><a href="/Reference/x">x</a>"#),
+            ("/Guide/a", r#"<a href="/Guide/a/deep">deep</a>"#),
+            ("/Guide/b", "<p>Guide b</p>"),
+            ("/Guide/a/deep", "<p>Deep guide</p>"),
+            ("/Reference/x", r#"<a href="/Reference/x/y">y</a><a href="/Reference/x/z">z</a>"#),
+            ("/Reference/x/y", "<p>y</p>"),
+            ("/Reference/x/z", "<p>z</p>"),
+        ];
+        // 链接使用完整地址，页面间的相对链接与本测试无关
+        let base = server.url();
+        for (path, body) in pages {
+            let body = body.replace("href=\"/", &format!("href=\"{}/", base));
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(format!("<html><body>{}</body></html>", body))
+                .create_async()
+                .await;
+        }
+
+        // 优先模式下，更深的 Reference 页面先于 Guide 页面抓取
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_priority_patterns(vec!["^Reference/".to_string()])
+            .with_max_pages(4)
+            .with_concurrency(1)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let mut paths: Vec<String> = read_db(&output).into_keys().collect();
+        paths.sort();
+        assert_eq!(paths, ["Reference/x", "Reference/x/y", "Reference/x/z", "index"]);
+
+        // 没有优先模式时浅层页面优先，深层的 Guide/a/deep 最后
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_max_pages(4)
+            .with_concurrency(1)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let mut paths: Vec<String> = read_db(&output).into_keys().collect();
+        paths.sort();
+        assert_eq!(paths, ["Guide/a", "Guide/b", "Reference/x", "index"]);
+    }
+
+    #[tokio::test]
+    async fn test_type_meta_written_to_index() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_type_meta("Other", -5, Some("Every crawled page"))
+            .with_type_meta("Guides", -10, None)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let index = std::fs::read_to_string(output.path().join("fixture~1").join(INDEX_FILENAME)).unwrap();
+        let index: serde_json::Value = serde_json::from_str(&index).unwrap();
+        // 没有条目的 Guides 不出现
+        assert_eq!(
+            index["types"],
+            serde_json::json!([{
+                "name": "Other",
+                "count": 3,
+                "slug": "other",
+                "weight": -5,
+                "description": "Every crawled page"
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initial_paths_bypass_only_rules() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        // 默认初始路径为根页面，不在 only 范围内
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_only(vec!["a".to_string()])
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        assert!(pages.contains_key("index"));
+        assert!(pages.contains_key("a"));
+        assert!(!pages.contains_key("b"));
+    }
+
+    /// 对每个页面都发出警告的过滤器
+    #[derive(Clone)]
+    struct WarnOnEveryPage;
+
+    impl Filter for WarnOnEveryPage {
+        fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+            context
+                .warnings
+                .push(self.warning("missing container").with_selector("#content"));
+            context.content = html.to_string();
+            Ok(html.to_string())
+        }
+
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_warnings_are_aggregated() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        crate::core::instrumentable::subscribe(WARN_DOC_EVENT, move |info| {
+            if info.payload.get("doc").map(String::as_str) == Some("warnings_fixture") {
+                events_clone.lock().unwrap().push(info.payload.clone());
+            }
+        });
+
+        let mut scraper = UrlScraper::new("Warnings Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(WarnOnEveryPage))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let report = scraper.report();
+        assert_eq!(report.warnings.len(), 3);
+        assert!(report.warnings.iter().all(|w| w.filter == "WarnOnEveryPage"));
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.url.as_deref() == Some(&format!("{}/a", server.url()))));
+
+        let report_json = std::fs::read_to_string(output.path().join("warnings_fixture~1").join("report.json")).unwrap();
+        let report_json: serde_json::Value = serde_json::from_str(&report_json).unwrap();
+        assert_eq!(report_json["warnings"][0]["selector"], "#content");
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["message"], "missing container");
+        assert_eq!(events[0]["count"], "3");
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_page_log() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+
+        // 模拟上次抓取在处理完页面 a 后中断
+        let mut log = PageLog::open(&doc_dir).unwrap();
+        log.append(&format!("{}/a", server.url()), "a", "<p>Recovered A</p>", &[]).unwrap();
+        drop(log);
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_resume(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        assert_eq!(pages["a"], "<p>Recovered A</p>");
+        assert!(pages["b"].contains("Page B"));
+        assert!(!doc_dir.join(crate::core::scraper::page_log::WAL_FILENAME).exists());
+    }
+
+    /// 处理到指定路径时取消抓取的过滤器
+    #[derive(Clone)]
+    struct CancelOnPath(&'static str, CancellationToken);
+
+    impl Filter for CancelOnPath {
+        fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+            if context.current_path == self.0 {
+                self.1.cancel();
+            }
+            context.content = html.to_string();
+            Ok(html.to_string())
+        }
+
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_crawl_keeps_page_log() {
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("db.json"), r#"{"old": "<p>Previous crawl</p>"}"#).unwrap();
+        let fetch = Arc::new(fixture_fetch());
+
+        // 处理完页面 a 后取消，页面 b 不会被请求
+        let cancel = CancellationToken::new();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_filter(Box::new(CancelOnPath("a", cancel.clone())))
+            .with_fetch(fetch.clone())
+            .with_cancellation(cancel)
+            .with_concurrency(1)
+            .with_rate_limit(6000);
+        assert!(matches!(scraper.run().await, Err(Error::Cancelled)));
+        assert_eq!(fetch.request_count("https://example.com/b"), 0);
+
+        // 抓取日志保留了已处理的页面，上次的 db.json 没有被替换
+        let wal = doc_dir.join(crate::core::scraper::page_log::WAL_FILENAME);
+        assert!(wal.exists());
+        let recovered = PageLog::recover(&doc_dir).unwrap();
+        assert!(recovered["a"].content.contains("Page A"));
+        assert_eq!(read_db(&output).keys().collect::<Vec<_>>(), ["old"]);
+        assert!(!doc_dir.join("db.json.tmp").exists());
+
+        // 恢复抓取时不再请求页面 a
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_fetch(fetch.clone())
+            .with_resume(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+        assert_eq!(fetch.request_count("https://example.com/a"), 1);
+        let pages = read_db(&output);
+        assert!(pages["a"].contains("Page A"));
+        assert!(pages["b"].contains("Page B"));
+        assert!(!wal.exists());
+    }
+
+    /// 依次返回给定可用空间的磁盘，最后一个值一直重复
+    struct ScriptedSpace(std::sync::Mutex<Vec<u64>>);
+
+    impl ScriptedSpace {
+        fn new(values: &[u64]) -> Arc<Self> {
+            Arc::new(Self(std::sync::Mutex::new(values.to_vec())))
+        }
+    }
+
+    impl DiskSpace for ScriptedSpace {
+        fn available(&self, _path: &Path) -> Option<u64> {
+            let mut values = self.0.lock().unwrap();
+            if values.len() > 1 {
+                Some(values.remove(0))
+            } else {
+                values.first().copied()
+            }
+        }
+    }
+
+    const MB: u64 = 1024 * 1024;
+
+    fn disk_settings() -> DiskSpaceSettings {
+        DiskSpaceSettings {
+            safety_factor: 2.0,
+            min_free_mb: 1,
+            check_interval_pages: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_low_disk_space_refuses_to_start() {
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("db.json"), r#"{"old": "<p>Previous crawl</p>"}"#).unwrap();
+        let fetch = Arc::new(fixture_fetch());
+
+        // 只有保留空间，放不下上次抓取结果两倍大小的新文档
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_disk_space(disk_settings())
+            .with_disk_probe(ScriptedSpace::new(&[MB]))
+            .with_rate_limit(6000);
+        let error = scraper.run().await.unwrap_err();
+        assert!(matches!(error.root_cause(), Error::InsufficientDiskSpace { available: MB, .. }));
+        assert!(error.to_string().contains("--ignore-disk-space"));
+        assert_eq!(fetch.request_count("https://example.com/"), 0);
+        assert_eq!(read_db(&output).keys().collect::<Vec<_>>(), ["old"]);
+
+        // 跳过检查后照常抓取，抓取过程中可用空间没有低于下限
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_disk_space(disk_settings())
+            .with_disk_probe(ScriptedSpace::new(&[MB]))
+            .with_ignore_disk_space(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+        assert!(read_db(&output)["b"].contains("Page B"));
+    }
+
+    #[tokio::test]
+    async fn test_low_disk_space_mid_crawl_saves_state_for_resume() {
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        let fetch = Arc::new(fixture_fetch());
+
+        // 开始时空间充足，处理完根页面后低于下限
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_disk_space(disk_settings())
+            .with_disk_probe(ScriptedSpace::new(&[100 * MB, MB / 2]))
+            .with_concurrency(1)
+            .with_rate_limit(6000);
+        let error = scraper.run().await.unwrap_err();
+        assert!(matches!(error, Error::InsufficientDiskSpace { .. }));
+        assert_eq!(fetch.request_count("https://example.com/a"), 0);
+
+        let state = CrawlState::load(&doc_dir, MOCK_BASE).unwrap();
+        assert_eq!(state.visited, ["https://example.com/"]);
+        assert_eq!(state.pending.len(), 2);
+        assert!(doc_dir.join(crate::core::scraper::page_log::WAL_FILENAME).exists());
+
+        // 释放空间后恢复抓取，根页面不再请求
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_fetch(fetch.clone())
+            .with_disk_space(disk_settings())
+            .with_disk_probe(ScriptedSpace::new(&[100 * MB]))
+            .with_resume(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+        assert_eq!(fetch.request_count("https://example.com/"), 1);
+        assert!(read_db(&output)["a"].contains("Page A"));
+    }
+
+    /// 为每个页面生成一个 "Custom" 类型条目的过滤器
+    #[derive(Clone)]
+    struct CustomEntries;
+
+    impl Filter for CustomEntries {
+        fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
+            Ok(html.to_string())
+        }
+
+        fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+            vec![(
+                format!("Custom {}", context.current_path),
+                context.current_path.clone(),
+                "Custom".to_string(),
+            )]
+        }
+
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_restores_crawl_state() {
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        let fetch = Arc::new(fixture_fetch());
+
+        let cancel = CancellationToken::new();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_filter(Box::new(CancelOnPath("a", cancel.clone())))
+            .with_filter(Box::new(CustomEntries))
+            .with_fetch(fetch.clone())
+            .with_cancellation(cancel)
+            .with_concurrency(1)
+            .with_rate_limit(6000);
+        assert!(matches!(scraper.run().await, Err(Error::Cancelled)));
+
+        // 取消时保存了已访问的 URL、待抓取队列和过滤器生成的条目
+        let state = CrawlState::load(&doc_dir, MOCK_BASE).unwrap();
+        assert_eq!(state.visited, ["https://example.com/", "https://example.com/a"]);
+        assert_eq!(state.pending, [("https://example.com/b".to_string(), 1)]);
+        assert_eq!(state.entries.len(), 2);
+
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_filter(Box::new(CustomEntries))
+            .with_fetch(fetch.clone())
+            .with_resume(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        // 已访问的页面不再请求，恢复的页面保留过滤器生成的条目
+        assert_eq!(fetch.request_count("https://example.com/"), 1);
+        assert_eq!(fetch.request_count("https://example.com/a"), 1);
+        assert!(read_db(&output)["a"].contains("Page A"));
+        let entries = std::fs::read_to_string(doc_dir.join("entries.json")).unwrap();
+        let entries: Vec<(String, String, String)> = serde_json::from_str(&entries).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|(name, _, entry_type)| name.starts_with("Custom") && entry_type == "Custom"));
+        assert!(!doc_dir.join(crawl_state::STATE_FILENAME).exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_crash_keeps_filter_entries() {
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        let fetch = Arc::new(fixture_fetch());
+
+        let cancel = CancellationToken::new();
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_filter(Box::new(CancelOnPath("a", cancel.clone())))
+            .with_filter(Box::new(CustomEntries))
+            .with_fetch(fetch.clone())
+            .with_cancellation(cancel)
+            .with_concurrency(1)
+            .with_rate_limit(6000);
+        assert!(matches!(scraper.run().await, Err(Error::Cancelled)));
+
+        // 模拟在保存抓取状态之前崩溃：只剩下抓取日志
+        CrawlState::remove(&doc_dir).unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", MOCK_BASE, output.path().to_str().unwrap())
+            .with_filter(Box::new(CustomEntries))
+            .with_fetch(fetch.clone())
+            .with_resume(true)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        // 日志中的页面不再请求，条目从日志记录中恢复
+        assert_eq!(fetch.request_count("https://example.com/"), 1);
+        assert_eq!(fetch.request_count("https://example.com/a"), 1);
+        let entries = std::fs::read_to_string(doc_dir.join("entries.json")).unwrap();
+        let entries: Vec<(String, String, String)> = serde_json::from_str(&entries).unwrap();
+        let mut names: Vec<&str> = entries.iter().map(|(name, _, _)| name.as_str()).collect();
+        names.sort();
+        assert_eq!(names.len(), 3, "{:?}", names);
+        assert!(names.contains(&"Custom a"), "{:?}", names);
+    }
+
+    #[tokio::test]
+    async fn test_landing_path_written_to_meta() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+        let doc_dir = output.path().join("fixture~1");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join(META_FILENAME), r#"{"noindex":false}"#).unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_landing_path("/a/")
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let meta = std::fs::read_to_string(doc_dir.join(META_FILENAME)).unwrap();
+        let meta: serde_json::Value = serde_json::from_str(&meta).unwrap();
+        assert_eq!(meta["landing_path"], "a");
+        assert_eq!(meta["name"], "Fixture");
+        assert_eq!(meta["noindex"], false);
+    }
+
+    #[test]
+    fn test_filter_chain_parses_page_once() {
+        use crate::core::scraper::filter::PARSE_COUNT;
+        use crate::docs::babel::BabelEntriesFilter;
+        use crate::docs::html::HtmlEntriesFilter;
+        use crate::docs::javascript::JavaScriptEntriesFilter;
+
+        let scraper = UrlScraper::new("Chain", "1", "https://example.com", "unused")
+            .with_filter(Box::new(BabelEntriesFilter::new()))
+            .with_filter(Box::new(HtmlEntriesFilter::new()))
+            .with_filter(Box::new(JavaScriptEntriesFilter::new()));
+        let mut context = FilterContext::new().with_html("<html><body><h1>Array</h1><p>Page</p></body></html>");
+        context.current_path = "array".to_string();
+
+        PARSE_COUNT.with(|count| count.set(0));
+        scraper.apply_filters(&mut context).unwrap();
+        let entries: Vec<_> = scraper
+            .filters
+            .iter()
+            .flat_map(|filter| filter.get_entries(&context))
+            .collect();
+        scraper.extract_links(context.html_doc(), "https://example.com/array").unwrap();
+
+        assert!(entries.iter().any(|(name, _, _)| name == "Array"));
+        assert_eq!(PARSE_COUNT.with(|count| count.get()), 1);
+    }
+
+    /// 设置标题并添加一个条目的过滤器
+    #[derive(Clone)]
+    struct TitleAndEntry(&'static str);
+
+    impl Filter for TitleAndEntry {
+        fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+            Ok(FilterOutput::new(html)
+                .with_title(self.0)
+                .with_entries([(self.0.to_string(), self.0.to_lowercase(), "Guides".to_string())]))
+        }
+
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    /// 直接修改上下文的旧版过滤器，清空之前的附加条目
+    #[derive(Clone)]
+    struct ClearEntries;
+
+    impl Filter for ClearEntries {
+        fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+            context.additional_entries.clear();
+            context.current_path = "changed".to_string();
+            context.warnings.push(self.warning("cleared"));
+            Ok(html.to_string())
+        }
+
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_filter_outputs_are_merged_in_order() {
+        let scraper = UrlScraper::new("Merge", "1", "https://example.com", "unused")
+            .with_filter(Box::new(TitleAndEntry("First")))
+            .with_filter(Box::new(ClearEntries))
+            .with_filter(Box::new(TitleAndEntry("Second")));
+        let mut context = FilterContext::new().with_html("<p>Page</p>");
+        context.current_path = "page".to_string();
+        scraper.apply_filters(&mut context).unwrap();
+
+        // 条目按过滤器顺序追加，旧版过滤器清空列表不影响之前的条目
+        let names: Vec<&str> = context.additional_entries.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, ["First", "Second"]);
+        assert_eq!(context.current_path, "page");
+
+        // 标题以最后一个过滤器为准，覆盖不同的标题时记录警告
+        assert_eq!(context.title, "Second");
+        let warnings: Vec<(&str, &str)> = context
+            .warnings
+            .iter()
+            .map(|warning| (warning.filter.as_str(), warning.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [("ClearEntries", "cleared"), ("TitleAndEntry", "标题 \"First\" 被覆盖为 \"Second\"")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_excluded_entries_are_counted() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_exclude_entries(EntryExclusions::new().with_path_prefix("b"))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        assert_eq!(scraper.report().excluded_entries, 1);
+        let entries = std::fs::read_to_string(output.path().join("fixture~1").join("entries.json")).unwrap();
+        let entries: Vec<(String, String, String)> = serde_json::from_str(&entries).unwrap();
+        assert!(entries.iter().all(|(_, path, _)| path != "b"));
+        assert!(entries.iter().any(|(_, path, _)| path == "a"));
+    }
+
+    #[tokio::test]
+    async fn test_db_format_v2_stores_page_metadata() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_db_format(DbFormat::V2)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let db = PageDb::load(&output.path().join("fixture~1").join("db.json")).unwrap();
+        assert_eq!(db.format(), DbFormat::V2);
+        let page = db.page(&PageKey::new("a")).unwrap();
+        assert!(page.content.contains("Page A"));
+        assert_eq!(page.source_url.as_deref(), Some(format!("{}/a", server.url()).as_str()));
+
+        // pages.json 列出 db.json 中的页面
+        let pages = std::fs::read_to_string(output.path().join("fixture~1").join(PAGES_FILENAME)).unwrap();
+        let pages: Vec<String> = serde_json::from_str(&pages).unwrap();
+        assert_eq!(pages.len(), db.len());
+        assert!(pages.contains(&"a".to_string()));
+        assert!(pages.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    /// 在页面末尾加上标记的过滤器，用于检查重新过滤使用的是新的过滤器参数
+    #[derive(Clone)]
+    struct AppendMarker(&'static str);
+
+    impl Filter for AppendMarker {
+        fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+            let html = format!("{}<!-- {} -->", html, self.0);
+            context.content = html.clone();
+            Ok(html)
+        }
+
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_legacy_filter_implementing_only_apply() {
+        let scraper = UrlScraper::new("Legacy", "1", "https://example.com", "unused")
+            .with_filter(Box::new(AppendMarker("legacy")));
+        let mut context = FilterContext::new().with_html("<p>Page</p>");
+        scraper.apply_filters(&mut context).unwrap();
+
+        assert_eq!(context.html(), "<p>Page</p><!-- legacy -->");
+        assert_eq!(context.content, "<p>Page</p><!-- legacy -->");
+        let filter = &scraper.filters[0];
+        assert_eq!(filter.name(), "AppendMarker");
+        assert!(filter.as_any().downcast_ref::<AppendMarker>().is_some());
+        let output = filter.box_clone().apply_v2("<p>A</p>", &FilterContext::new()).unwrap();
+        assert_eq!(output.html, "<p>A</p><!-- legacy -->");
+    }
+
+    /// 既没有实现 apply_v2 也没有实现 apply 的过滤器
+    #[derive(Clone)]
+    struct Unimplemented;
+
+    impl Filter for Unimplemented {
+        fn box_clone(&self) -> Box<dyn Filter> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "必须实现 apply_v2 或 apply")]
+    fn test_filter_without_apply_panics_instead_of_recursing() {
+        let _ = Unimplemented.apply_v2("<p>Page</p>", &FilterContext::new());
+    }
+
+    #[tokio::test]
+    async fn test_refilter_raw_pages_offline() {
+        let output = TempDir::new().unwrap();
+        let output_path = output.path().to_str().unwrap().to_string();
+        {
+            let server = fixture_server().await;
+            let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), &output_path)
+                .with_filter(Box::new(AppendMarker("marker-v1")))
+                .with_keep_raw(true)
+                .with_rate_limit(6000);
+            scraper.run().await.unwrap();
+        }
+        assert!(read_db(&output)["a"].contains("marker-v1"));
+
+        // 手动设置的 meta.json 字段在重新过滤后保留
+        let meta_path = output.path().join("fixture~1").join(META_FILENAME);
+        let mut meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        meta["custom"] = "kept".into();
+        std::fs::write(&meta_path, meta.to_string()).unwrap();
+
+        // 服务器已关闭，重新过滤只使用保存的原始页面
+        let mut scraper = UrlScraper::new("Fixture", "1", "http://127.0.0.1:9", &output_path)
+            .with_filter(Box::new(AppendMarker("marker-v2")))
+            .with_include_stats(true);
+        scraper.refilter().await.unwrap();
+
+        let db = read_db(&output);
+        assert_eq!(db.len(), 3);
+        assert!(db["a"].contains("marker-v2"));
+        assert!(!db["a"].contains("marker-v1"));
+        assert_eq!(scraper.report().pages_processed(), 3);
+        assert!(read_index_entries(&output).iter().any(|entry| entry["path"] == "a"));
+        assert!(output.path().join("fixture~1").join("stats.json").is_file());
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        assert_eq!(meta["custom"], "kept");
+        assert!(!output.path().join(".fixture~1.refilter").exists());
+    }
+
+    #[tokio::test]
+    async fn test_refilter_without_raw_pages_explains() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let error = scraper.refilter().await.unwrap_err().to_string();
+        assert!(error.contains("--keep-raw"));
+        assert_eq!(read_db(&output).len(), 3);
+    }
+
+    /// 包含一个超大页面（约 30KB 的更新日志）的站点
+    async fn oversized_server() -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        let rows: String = (0..1000).map(|i| format!("<li>Release {} &amp; notes</li>", i)).collect();
+        let pages = [
+            ("/", r#"<html><body><a href="/changelog">Changelog</a><a href="/small">Small</a></body></html>"#.to_string()),
+            ("/changelog", format!("<html><body><h1>Changelog</h1><ul>{}</ul></body></html>", rows)),
+            ("/small", "<html><body><p>Small page</p></body></html>".to_string()),
+        ];
+        for (path, body) in pages {
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(body)
+                .create_async()
+                .await;
+        }
+        server
+    }
+
+    async fn scrape_oversized(policy: OversizePolicy) -> (TempDir, UrlScraper) {
+        let server = oversized_server().await;
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_max_page_size(4096)
+            .with_oversize_policy(policy)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+        (output, scraper)
+    }
+
+    #[tokio::test]
+    async fn test_oversized_page_policies() {
+        // 跳过：页面和条目都不保存
+        let (output, scraper) = scrape_oversized(OversizePolicy::Skip).await;
+        let db = read_db(&output);
+        assert!(!db.contains_key("changelog"));
+        assert!(db.contains_key("small"));
+        assert!(read_index_entries(&output).iter().all(|entry| entry["path"] != "changelog"));
+        let oversized = &scraper.report().oversized_pages;
+        assert_eq!(oversized.len(), 1);
+        assert_eq!(oversized[0].path, "changelog");
+        assert_eq!(oversized[0].action, OversizePolicy::Skip);
+        assert!(oversized[0].size > 4096);
+
+        // 截断：在元素边界处截断并附加提示，结果仍是完整的 HTML
+        let (output, scraper) = scrape_oversized(OversizePolicy::Truncate).await;
+        let changelog = &read_db(&output)["changelog"];
+        assert!(changelog.len() <= 4096);
+        assert!(changelog.contains("<li>Release 0 &amp; notes</li>"));
+        assert!(changelog.contains("</li></ul></body></html><div class=\"_warning\">"));
+        assert!(!changelog.contains("Release 999"));
+        let oversized = &scraper.report().oversized_pages[0];
+        assert_eq!(oversized.action, OversizePolicy::Truncate);
+        assert_eq!(oversized.stored_size, Some(changelog.len()));
+        let report = std::fs::read_to_string(output.path().join("fixture~1").join("report.json")).unwrap();
+        assert!(report.contains("\"action\": \"truncate\""));
+
+        // 保留：照常保存，只记录在报告中
+        let (output, scraper) = scrape_oversized(OversizePolicy::Keep).await;
+        assert!(read_db(&output)["changelog"].contains("Release 999"));
+        assert_eq!(scraper.report().oversized_pages[0].action, OversizePolicy::Keep);
+        assert_eq!(scraper.report().oversized_pages[0].stored_size, None);
+    }
+
+    /// 链接到大文件的站点，`head_status` 为大文件 HEAD 请求的状态码
+    async fn binary_link_server(
+        head_status: usize,
+        expected_heads: usize,
+        expected_gets: usize,
+    ) -> (mockito::ServerGuard, mockito::Mock, mockito::Mock) {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body(r#"<html><body><a href="/files/sdk.zip">SDK</a><a href="/a">A</a></body></html>"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/a")
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body("<html><body><p>Page A</p></body></html>")
+            .create_async()
+            .await;
+        let head = server
+            .mock("HEAD", "/files/sdk.zip")
+            .with_status(head_status)
+            .with_header("content-type", "application/zip")
+            .expect(expected_heads)
+            .create_async()
+            .await;
+        let get = server
+            .mock("GET", "/files/sdk.zip")
+            .with_status(200)
+            .with_header("content-type", "application/zip")
+            .with_body(vec![0u8; 64 * 1024])
+            .expect(expected_gets)
+            .create_async()
+            .await;
+        (server, head, get)
+    }
+
+    fn head_check_scraper(server: &mockito::ServerGuard, output: &TempDir) -> UrlScraper {
+        UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000)
+    }
+
+    #[tokio::test]
+    async fn test_head_check_skips_binary_without_download() {
+        let (server, head, get) = binary_link_server(200, 1, 0).await;
+        let output = TempDir::new().unwrap();
+        let mut scraper = head_check_scraper(&server, &output);
+        scraper.run().await.unwrap();
+
+        head.assert_async().await;
+        get.assert_async().await;
+        let skips = &scraper.report().head_skips;
+        assert_eq!(skips.len(), 1);
+        assert!(skips[0].url.ends_with("/files/sdk.zip"));
+        assert_eq!(skips[0].content_type.as_deref(), Some("application/zip"));
+        assert!(read_db(&output).contains_key("a"));
+    }
+
+    #[tokio::test]
+    async fn test_head_check_falls_back_to_get() {
+        // 服务器不支持 HEAD 时照常发送 GET
+        let (server, head, get) = binary_link_server(405, 1, 1).await;
+        let output = TempDir::new().unwrap();
+        let mut scraper = head_check_scraper(&server, &output);
+        scraper.run().await.unwrap();
+        head.assert_async().await;
+        get.assert_async().await;
+        assert!(scraper.report().head_skips.is_empty());
+
+        // 按主机关闭预检时不发送 HEAD
+        let (server, head, get) = binary_link_server(200, 0, 1).await;
+        let host = Url::parse(&server.url()).unwrap().host_str().unwrap().to_string();
+        let output = TempDir::new().unwrap();
+        let mut scraper = head_check_scraper(&server, &output).with_head_check_disabled_for(&host);
+        scraper.run().await.unwrap();
+        head.assert_async().await;
+        get.assert_async().await;
+        assert!(scraper.report().head_skips.is_empty());
+    }
+
+    #[test]
+    fn test_skip_reasons() {
+        let scraper = UrlScraper::new("Fixture", "1", "https://example.com/docs/", "/tmp")
+            .with_base_urls(vec![
+                "https://example.com/docs/".to_string(),
+                "https://example.com/guide/".to_string(),
+            ])
+            .with_skip_paths(vec!["api".to_string()])
+            .with_skip_patterns(vec![r"^legacy/", r"\.pdf$"])
+            .with_only_patterns(vec!["^reference".to_string(), "^index$".to_string()])
+            .with_skip_link(|url| url.contains("?print"));
+
+        let cases = [
+            ("https://example.com/docs/", None),
+            ("https://example.com/docs/reference/map", None),
+            ("https://example.com/guide/", None),
+            ("https://other.com/docs/reference", Some(SkipReason::External)),
+            ("https://example.com/blog/", Some(SkipReason::External)),
+            ("https://example.com/docs/reference?print", Some(SkipReason::SkipLink)),
+            ("https://example.com/docs/api", Some(SkipReason::SkipPath)),
+            ("https://example.com/docs/api/v2/map", Some(SkipReason::SkipPath)),
+            ("https://example.com/docs/apiary", Some(SkipReason::OnlyMiss)),
+            ("https://example.com/docs/legacy/old", Some(SkipReason::SkipPattern)),
+            ("https://example.com/docs/manual.pdf", Some(SkipReason::SkipPattern)),
+            ("https://example.com/docs/tutorial", Some(SkipReason::OnlyMiss)),
+        ];
+        for (url, reason) in cases {
+            assert_eq!(scraper.skip_reason(url), reason, "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_urls_records_crawl_events() {
+        let mut server = mockito::Server::new_async().await;
+        let home = format!(r#"<a href="{}/">Home</a><p>Page A</p>"#, server.url());
+        let pages = [
+            ("/", r#"<a href="/a">A</a><a href="/a">A again</a><a href="/private/x">P</a><a href="https://other.com/">O</a>"#),
+            ("/a", home.as_str()),
+        ];
+        for (path, body) in pages {
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(format!("<html><body>{}</body></html>", body))
+                .create_async()
+                .await;
+        }
+        let output = TempDir::new().unwrap();
+        let trace = output.path().join("trace.jsonl");
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_skip_paths(vec!["private".to_string()])
+            .with_rate_limit(6000)
+            .with_trace_urls(&trace);
+        scraper.run().await.unwrap();
+
+        let events: Vec<(String, String, String)> = std::fs::read_to_string(&trace)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                let payload = &event["payload"];
+                (
+                    event["name"].as_str().unwrap().to_string(),
+                    payload["path"].as_str().unwrap().to_string(),
+                    payload["reason"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect();
+        let event = |name: &str, path: &str, reason: &str| (name.to_string(), path.to_string(), reason.to_string());
+
+        assert_eq!(events.iter().filter(|e| e.0 == "queue.enqueued").count(), 5);
+        assert!(events.contains(&event("url.skipped", "private/x", "skip_path")));
+        assert!(events.contains(&event("url.skipped", "index", "external")));
+        assert!(events.contains(&event("url.skipped", "a", "visited")));
+        assert!(events.contains(&event("queue.deduped", "index", "")));
+        assert_eq!(events.iter().filter(|e| e.0 == "url.skipped").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fragment_links_are_corrected() {
+        let mut server = mockito::Server::new_async().await;
+        let index = format!(
+            r##"<a href="{0}/guide#Syntax">S</a><a href="{0}/guide#Return%20Value">R</a><a href="{0}/guide#Examples">E</a>"##,
+            server.url()
+        );
+        let guide = r##"<h2 id="syntax">Syntax</h2><h2 id="return_value">Return value</h2><a href="#SYNTAX">up</a>"##;
+        for (path, body) in [("/", index.as_str()), ("/guide", guide)] {
+            server
+                .mock("GET", path)
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(format!("<html><body>{}</body></html>", body))
+                .create_async()
+                .await;
+        }
+        let url = server.url();
+
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &url, output.path().to_str().unwrap())
+            .with_filter(Box::new(UrlNormalizerFilter::new(&url, "/docs/fixture/")))
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        assert!(pages["index"].contains(r##"href="/docs/fixture/guide#syntax""##));
+        assert!(pages["index"].contains(r##"href="/docs/fixture/guide#return_value""##));
+        assert!(pages["index"].contains(r##"href="/docs/fixture/guide#Examples""##));
+        assert!(pages["guide"].contains(r##"href="#syntax""##));
+
+        let broken = &scraper.report().broken_fragments;
+        assert_eq!(broken.len(), 1);
+        assert_eq!((broken[0].page.as_str(), broken[0].target.as_str()), ("index", "guide"));
+        assert_eq!(broken[0].fragment, "Examples");
+        assert!(!broken[0].stripped);
+        let report = std::fs::read_to_string(output.path().join("fixture~1").join("report.json")).unwrap();
+        assert!(report.contains("broken_fragments"));
+
+        // 选择去掉找不到的锚点
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &url, output.path().to_str().unwrap())
+            .with_filter(Box::new(UrlNormalizerFilter::new(&url, "/docs/fixture/")))
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000)
+            .with_strip_broken_fragments(true);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        assert!(pages["index"].contains(r#"href="/docs/fixture/guide">E"#));
+        assert!(scraper.report().broken_fragments[0].stripped);
+    }
+
+    #[tokio::test]
+    async fn test_favicon_stored_as_doc_icon() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body(r#"<html><head><link rel="icon" href="/static/logo.png"></head><body><p>Home</p></body></html>"#)
+            .create_async()
+            .await;
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::new(16, 16)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        server
+            .mock("GET", "/static/logo.png")
+            .with_status(200)
+            .with_header("content-type", "image/png")
+            .with_body(png.into_inner())
+            .create_async()
+            .await;
+
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let doc_dir = output.path().join("fixture~1");
+        let icon = image::open(doc_dir.join(favicon::ICON_FILENAME)).unwrap();
+        assert_eq!((icon.width(), icon.height()), (favicon::ICON_SIZE, favicon::ICON_SIZE));
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(doc_dir.join(META_FILENAME)).unwrap()).unwrap();
+        assert_eq!(meta["has_icon"], true);
+
+        let mut registry = crate::docs::DocRegistry::new();
+        registry.load_from_disk(&[output.path()]).unwrap();
+        assert!(registry.find("fixture").unwrap().has_icon);
+
+        // 关闭后不下载图标，没有图标时 has_icon 为 false
+        let output = TempDir::new().unwrap();
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000)
+            .with_favicon(false);
+        scraper.run().await.unwrap();
+
+        let doc_dir = output.path().join("fixture~1");
+        assert!(!doc_dir.join(favicon::ICON_FILENAME).exists());
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(doc_dir.join(META_FILENAME)).unwrap()).unwrap();
+        assert_eq!(meta["has_icon"], false);
+    }
+
+    #[tokio::test]
+    async fn test_default_entries_from_titles_and_sections() {
+        let mut server = mockito::Server::new_async().await;
+        let links = ["api/array", "api/map", "guides/intro", "guides/install", "tutorials/first", "tutorials/second", "about"]
+            .map(|path| format!(r#"<a href="/{}">{}</a>"#, path, path))
+            .concat();
+        let pages = [
+            ("/".to_string(), format!("<html><head><title>Home</title></head><body>{}</body></html>", links)),
+            ("/api/array".to_string(), "<html><body><h1>Array</h1></body></html>".to_string()),
+            ("/api/map".to_string(), "<html><head><title>Map | Site</title></head><body><p>Map</p></body></html>".to_string()),
+            ("/guides/intro".to_string(), "<html><body><h1>Introduction</h1></body></html>".to_string()),
+            ("/guides/install".to_string(), "<html><body><h1>Installation</h1></body></html>".to_string()),
+            ("/tutorials/first".to_string(), "<html><body><h1>First steps</h1></body></html>".to_string()),
+            ("/tutorials/second".to_string(), "<html><body><h1>Next steps</h1></body></html>".to_string()),
+            ("/about".to_string(), "<html><body><h1>About</h1></body></html>".to_string()),
+        ];
+        for (path, body) in pages {
+            server
+                .mock("GET", path.as_str())
+                .with_status(200)
+                .with_header("content-type", "text/html")
+                .with_body(body)
+                .create_async()
+                .await;
+        }
+        let output = TempDir::new().unwrap();
+
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_filter(Box::new(FailOnPath("none")))
+            .with_rate_limit(6000)
+            .with_favicon(false);
+        scraper.run().await.unwrap();
+
+        let entries = std::fs::read_to_string(output.path().join("fixture~1").join("entries.json")).unwrap();
+        let mut entries: Vec<(String, String, String)> = serde_json::from_str(&entries).unwrap();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        let expected = [
+            ("About", "about", "Other"),
+            ("Array", "api/array", "API"),
+            ("Map | Site", "api/map", "API"),
+            ("Installation", "guides/install", "Guides"),
+            ("Introduction", "guides/intro", "Guides"),
+            ("Home", "index", "Other"),
+            ("First steps", "tutorials/first", "Tutorials"),
+            ("Next steps", "tutorials/second", "Tutorials"),
+        ]
+        .map(|(name, path, entry_type)| (name.to_string(), path.to_string(), entry_type.to_string()));
+        assert_eq!(entries, expected);
+
+        let index = std::fs::read_to_string(output.path().join("fixture~1").join(INDEX_FILENAME)).unwrap();
+        let index: serde_json::Value = serde_json::from_str(&index).unwrap();
+        let types: Vec<&str> = index["types"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(types.len(), 4);
+        for name in ["API", "Guides", "Tutorials", "Other"] {
+            assert!(types.contains(&name));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filtered_html_stored_without_content_filter() {
+        let server = fixture_server().await;
+        let output = TempDir::new().unwrap();
+
+        // 没有任何过滤器设置 content
+        let mut scraper = UrlScraper::new("Fixture", "1", &server.url(), output.path().to_str().unwrap())
+            .with_rate_limit(6000)
+            .with_favicon(false);
+        scraper.run().await.unwrap();
+
+        let pages = read_db(&output);
+        let mut paths: Vec<&str> = pages.keys().map(String::as_str).collect();
+        paths.sort();
+        assert_eq!(paths, ["a", "b", "index"]);
+        assert!(pages["a"].contains("Page A has five words"));
+
+        let db = PageDb::load(&output.path().join("fixture~1").join("db.json")).unwrap();
+        assert!(db.page(&PageKey::new("b")).unwrap().content.contains("Page B"));
+        let mut context = FilterContext::new().with_html("<html><head><title>Doc</title></head><body><h1>Heading</h1></body></html>");
+        extract_content(&mut context);
+        assert_eq!(context.title, "Heading");
+        assert!(context.content.contains("<h1>Heading</h1>"));
     }
 }
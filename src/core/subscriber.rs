@@ -3,8 +3,14 @@
 //! 参考原始 Ruby 项目中的 subscriber.rb 实现
 //! 提供事件订阅和日志记录功能
 
-use crate::core::instrumentable::InstrumentInfo;
+use crate::core::config::{LogFormat, LoggingSettings};
+use crate::core::format::{format_bytes, format_duration};
+use crate::core::instrumentable::{InstrumentInfo, MESSAGE_KEY};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use textwrap::fill;
@@ -21,14 +27,49 @@ pub trait Subscriber {
     fn handle_event(&self, info: &InstrumentInfo);
 }
 
+/// 控制台输出的详细程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// 只输出警告和错误（`--quiet`）
+    Quiet,
+    /// 另外输出各步骤的进度和结果
+    #[default]
+    Normal,
+    /// 另外输出每个请求的进度和没有消息的内部事件（`--verbose`）
+    Verbose,
+}
+
+impl Verbosity {
+    /// 事件最低在哪个详细程度下输出，按事件名称中第一个 `.` 之前的类别判断
+    ///
+    /// `warn.*` 和 `error.*` 总是输出；每个请求的进度（`fetch.scraper`、
+    /// `response.request` 等）和没有 `message` 的内部事件只在 `--verbose` 时输出。
+    pub fn of_event(info: &InstrumentInfo) -> Self {
+        match info.name.split('.').next().unwrap_or_default() {
+            "warn" | "error" => Verbosity::Quiet,
+            "fetch" | "retry" | "response" | "handle_response" | "handle_request" | "index" | "db" => {
+                Verbosity::Verbose
+            }
+            _ if info.payload.contains_key(MESSAGE_KEY) => Verbosity::Normal,
+            _ => Verbosity::Verbose,
+        }
+    }
+}
+
 /// 控制台订阅者，将事件输出到终端
+///
+/// 警告和错误输出到标准错误，其余事件输出到标准输出。
 pub struct ConsoleSubscriber {
     /// 是否使用彩色输出
     use_color: bool,
     /// 终端宽度
     terminal_width: Option<usize>,
+    /// 输出的详细程度
+    verbosity: Verbosity,
     /// 输出流
     output: Arc<Mutex<StandardStream>>,
+    /// 警告和错误的输出流
+    errors: Arc<Mutex<StandardStream>>,
 }
 
 impl Default for ConsoleSubscriber {
@@ -46,13 +87,42 @@ impl ConsoleSubscriber {
             ColorChoice::Never
         };
 
+        let error_color_choice = if atty::is(atty::Stream::Stderr) {
+            ColorChoice::Auto
+        } else {
+            ColorChoice::Never
+        };
+
         Self {
             use_color: color_choice != ColorChoice::Never,
             terminal_width: Self::get_terminal_width(),
+            verbosity: Verbosity::default(),
             output: Arc::new(Mutex::new(StandardStream::stdout(color_choice))),
+            errors: Arc::new(Mutex::new(StandardStream::stderr(error_color_choice))),
         }
     }
 
+    /// 设置输出的详细程度
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// 当前的详细程度下是否输出该事件
+    pub fn should_print(&self, info: &InstrumentInfo) -> bool {
+        Verbosity::of_event(info) <= self.verbosity
+    }
+
+    /// 消息事件输出的文字，`warn.*` 和 `error.*` 带有前缀
+    fn format_message(&self, info: &InstrumentInfo) -> Option<String> {
+        let message = info.payload.get(MESSAGE_KEY)?;
+        Some(match info.name.split('.').next().unwrap_or_default() {
+            "warn" => format!("警告: {}", message),
+            "error" => format!("错误: {}", message),
+            _ => message.clone(),
+        })
+    }
+
     /// 获取终端宽度
     fn get_terminal_width() -> Option<usize> {
         // 首先检查环境变量
@@ -80,7 +150,7 @@ impl ConsoleSubscriber {
     fn format_path(&self, path: &str) -> String {
         // 获取当前目录
         if let Ok(current_dir) = std::env::current_dir() {
-            if let Ok(current_path) = current_dir.to_str() {
+            if let Some(current_path) = current_dir.to_str() {
                 return path.replace(current_path, "");
             }
         }
@@ -148,6 +218,22 @@ impl ConsoleSubscriber {
         let _ = output.flush();
     }
 
+    /// 输出消息，不截断也不填充（消息可能比终端宽，且包含多字节字符）
+    ///
+    /// 输出流不是终端时 `StandardStream` 忽略颜色设置。
+    fn print(&self, message: &str, color: Option<Color>, to_stderr: bool) {
+        let stream = if to_stderr { &self.errors } else { &self.output };
+        let mut output = stream.lock().unwrap();
+        if let Some(color) = color {
+            let _ = output.set_color(ColorSpec::new().set_fg(Some(color)));
+        }
+        let _ = writeln!(output, "{}", message);
+        if color.is_some() {
+            let _ = output.reset();
+        }
+        let _ = output.flush();
+    }
+
     /// 更改输出颜色
     fn set_color(&self, color: Option<Color>) {
         if self.use_color {
@@ -173,6 +259,9 @@ impl ConsoleSubscriber {
 
 impl Subscriber for ConsoleSubscriber {
     fn handle_event(&self, info: &InstrumentInfo) {
+        if !self.should_print(info) {
+            return;
+        }
         match info.name.as_str() {
             "response.request" => {
                 if let Some(url) = info.payload.get("url") {
@@ -219,10 +308,10 @@ impl Subscriber for ConsoleSubscriber {
 
                         // 输出日志
                         self.log(&format!(
-                            "{}: {} -> {} bytes [{}%]",
+                            "{}: {} -> {} [{}%]",
                             event_type,
-                            before_size,
-                            after_size,
+                            format_bytes(before_size as u64),
+                            format_bytes(after_size as u64),
                             if before_size > 0 {
                                 ((after_size as f64 - before_size as f64) / before_size as f64
                                     * 100.0)
@@ -238,15 +327,18 @@ impl Subscriber for ConsoleSubscriber {
                 }
             }
             "warn.doc" => {
-                if let Some(msg) = info.payload.get("msg") {
-                    // 设置颜色
-                    self.set_color(Some(Color::Red));
-
-                    // 输出日志
-                    self.log(&format!("Warning: {}", msg));
-
-                    // 重置颜色
-                    self.reset_color();
+                if let Some(message) = info.payload.get(MESSAGE_KEY) {
+                    let filter = info.payload.get("filter").map(String::as_str).unwrap_or_default();
+                    let count = info.payload.get("count").map(String::as_str).unwrap_or("1");
+                    self.print(&format!("警告: [{}] {} ({} 次)", filter, message, count), Some(Color::Yellow), true);
+                }
+            }
+            _ if info.payload.contains_key(MESSAGE_KEY) => {
+                let message = self.format_message(info).unwrap_or_default();
+                match Verbosity::of_event(info) {
+                    Verbosity::Quiet => self.print(&message, Some(Color::Yellow), true),
+                    Verbosity::Normal => self.print(&message, None, false),
+                    Verbosity::Verbose => self.print(&message, Some(Color::Cyan), false),
                 }
             }
             _ => {
@@ -255,7 +347,7 @@ impl Subscriber for ConsoleSubscriber {
 
                 // 添加持续时间（如果有）
                 if let Some(duration) = info.duration {
-                    message.push_str(&format!(" [{:.2}ms]", duration.as_millis()));
+                    message.push_str(&format!(" [{}]", format_duration(duration)));
                 }
 
                 // 输出日志
@@ -266,51 +358,216 @@ impl Subscriber for ConsoleSubscriber {
 }
 
 /// 文件订阅者，将事件记录到文件
+///
+/// 支持按大小或日期轮转：当前文件改名为 `<file>.1`，原有的 `<file>.1` 改名为
+/// `<file>.2`，依此类推，只保留最近的若干个文件。订阅者会在多个任务中被调用，
+/// 写入和轮转都在同一把锁中进行。
 pub struct FileSubscriber {
     /// 日志文件路径
-    file_path: String,
+    file_path: PathBuf,
+    /// 日志格式
+    format: LogFormat,
+    /// 文件超过此大小（字节）时轮转
+    max_size: Option<u64>,
+    /// 是否在日期变化时轮转
+    rotate_daily: bool,
+    /// 保留的轮转文件数
+    keep: usize,
+    /// 当前打开的日志文件
+    state: Mutex<Option<LogFile>>,
+}
+
+/// 当前打开的日志文件
+struct LogFile {
+    file: File,
+    /// 已写入的字节数
+    size: u64,
+    /// 最后写入的日期
+    date: NaiveDate,
 }
 
 impl FileSubscriber {
-    /// 创建新的文件订阅者
+    /// 创建新的文件订阅者（文本格式，不轮转）
     pub fn new(file_path: &str) -> Self {
         Self {
-            file_path: file_path.to_string(),
+            file_path: PathBuf::from(file_path),
+            format: LogFormat::Text,
+            max_size: None,
+            rotate_daily: false,
+            keep: LoggingSettings::default().keep,
+            state: Mutex::new(None),
         }
     }
-}
 
-impl Subscriber for FileSubscriber {
-    fn handle_event(&self, info: &InstrumentInfo) {
-        // 格式化日志消息
-        let mut message = format!(
-            "[{}] {}",
-            info.name,
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-        );
-
-        // 添加持续时间（如果有）
-        if let Some(duration) = info.duration {
-            message.push_str(&format!(" [{:.2}ms]", duration.as_millis()));
+    /// 根据配置创建文件订阅者，未配置日志文件时返回 None
+    pub fn from_config(settings: &LoggingSettings) -> Option<Self> {
+        let file = settings.file.as_ref()?;
+        let mut subscriber = Self::new(&file.to_string_lossy())
+            .with_format(settings.format)
+            .with_daily_rotation(settings.rotate_daily)
+            .with_keep(settings.keep);
+        if let Some(max_size_mb) = settings.max_size_mb {
+            subscriber = subscriber.with_max_size(max_size_mb * 1024 * 1024);
         }
+        Some(subscriber)
+    }
 
-        // 添加有效负载
-        if !info.payload.is_empty() {
-            message.push_str(": ");
-            for (key, value) in &info.payload {
-                message.push_str(&format!("{}={}, ", key, value));
+    /// 设置日志格式
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// 设置按大小轮转的阈值（字节）
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// 设置是否在日期变化时轮转
+    pub fn with_daily_rotation(mut self, rotate_daily: bool) -> Self {
+        self.rotate_daily = rotate_daily;
+        self
+    }
+
+    /// 设置保留的轮转文件数
+    pub fn with_keep(mut self, keep: usize) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// 第 n 个轮转文件的路径
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.file_path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    /// 格式化一个事件，不含换行符
+    fn format_event(&self, info: &InstrumentInfo) -> String {
+        match self.format {
+            LogFormat::Text => {
+                let mut message = format!(
+                    "[{}] {}",
+                    info.name,
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+                );
+
+                // 添加持续时间（如果有）
+                if let Some(duration) = info.duration {
+                    message.push_str(&format!(" [{}]", format_duration(duration)));
+                }
+
+                // 添加有效负载
+                if !info.payload.is_empty() {
+                    let payload: BTreeMap<_, _> = info.payload.iter().collect();
+                    let fields: Vec<String> = payload
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect();
+                    message.push_str(": ");
+                    message.push_str(&fields.join(", "));
+                }
+                message
+            }
+            LogFormat::Jsonl => {
+                let payload: BTreeMap<_, _> = info.payload.iter().collect();
+                serde_json::json!({
+                    "name": info.name,
+                    "timestamp": chrono::Local::now().to_rfc3339(),
+                    "duration_ms": info.duration.map(|d| d.as_secs_f64() * 1000.0),
+                    "payload": payload,
+                })
+                .to_string()
             }
-            message.pop(); // 移除最后的逗号
-            message.pop(); // 移除最后的空格
         }
+    }
 
-        // 追加到文件
-        if let Ok(mut file) = std::fs::OpenOptions::new()
+    /// 打开日志文件，已有文件的日期取最后修改时间
+    fn open(&self) -> io::Result<LogFile> {
+        if let Some(parent) = self.file_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open(&self.file_path)
+            .open(&self.file_path)?;
+        let metadata = file.metadata()?;
+        let date = metadata
+            .modified()
+            .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).date_naive())
+            .unwrap_or_else(|_| chrono::Local::now().date_naive());
+        Ok(LogFile {
+            file,
+            size: metadata.len(),
+            date,
+        })
+    }
+
+    /// 写入这一行前是否需要轮转
+    fn needs_rotation(&self, log_file: &LogFile, line_len: u64, today: NaiveDate) -> bool {
+        if log_file.size == 0 {
+            return false;
+        }
+        let too_large = self
+            .max_size
+            .is_some_and(|max_size| log_file.size + line_len > max_size);
+        let new_day = self.rotate_daily && log_file.date != today;
+        too_large || new_day
+    }
+
+    /// 轮转日志文件，只保留最近的 `keep` 个轮转文件
+    fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return std::fs::remove_file(&self.file_path);
+        }
+        let oldest = self.rotated_path(self.keep);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for n in (1..self.keep).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.file_path, self.rotated_path(1))
+    }
+
+    /// 写入一行，必要时先轮转
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let line_len = line.len() as u64 + 1;
+        let today = chrono::Local::now().date_naive();
+
+        if state.is_none() {
+            *state = Some(self.open()?);
+        }
+        if state
+            .as_ref()
+            .is_some_and(|log_file| self.needs_rotation(log_file, line_len, today))
         {
-            let _ = writeln!(file, "{}", message);
+            // 先关闭当前文件再改名
+            *state = None;
+            self.rotate()?;
+            *state = Some(self.open()?);
+        }
+
+        let log_file = state.as_mut().unwrap();
+        writeln!(log_file.file, "{}", line)?;
+        log_file.size += line_len;
+        log_file.date = today;
+        Ok(())
+    }
+}
+
+impl Subscriber for FileSubscriber {
+    fn handle_event(&self, info: &InstrumentInfo) {
+        let line = self.format_event(info);
+        if let Err(e) = self.write_line(&line) {
+            log::warn!("无法写入事件日志 {:?}: {}", self.file_path, e);
         }
     }
 }
@@ -338,6 +595,31 @@ mod tests {
         assert!(justified.contains("[INFO]"));
     }
 
+    #[test]
+    fn test_console_subscriber_verbosity() {
+        let fetch = event("fetch.scraper", &[(MESSAGE_KEY, "爬取: https://example.com/")]);
+        let save = event("save.scraper", &[(MESSAGE_KEY, "保存结果到: \"docs/css\"")]);
+        let warn = event("warn.scraper", &[(MESSAGE_KEY, "可用磁盘空间不足")]);
+        let internal = event("queue.enqueued", &[("url", "https://example.com/")]);
+
+        let printed = |verbosity| {
+            let subscriber = ConsoleSubscriber::new().with_verbosity(verbosity);
+            [&fetch, &save, &warn, &internal]
+                .iter()
+                .filter(|info| subscriber.should_print(info))
+                .map(|info| info.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(printed(Verbosity::Quiet), ["warn.scraper"]);
+        assert_eq!(printed(Verbosity::Normal), ["save.scraper", "warn.scraper"]);
+        assert_eq!(printed(Verbosity::Verbose), ["fetch.scraper", "save.scraper", "warn.scraper", "queue.enqueued"]);
+
+        let subscriber = ConsoleSubscriber::new();
+        assert_eq!(subscriber.format_message(&warn).unwrap(), "警告: 可用磁盘空间不足");
+        assert_eq!(subscriber.format_message(&fetch).unwrap(), "爬取: https://example.com/");
+        assert!(subscriber.format_message(&internal).is_none());
+    }
+
     #[test]
     fn test_file_subscriber() {
         // 创建临时文件路径
@@ -374,4 +656,124 @@ mod tests {
         // 清理临时文件
         fs::remove_file(file_path).unwrap();
     }
+
+    fn event(name: &str, payload: &[(&str, &str)]) -> InstrumentInfo {
+        InstrumentInfo {
+            name: name.to_string(),
+            start_time: std::time::Instant::now(),
+            duration: Some(std::time::Duration::from_micros(1500)),
+            payload: payload
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn read_lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_file_subscriber_jsonl() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file_path = dir.path().join("logs").join("events.jsonl");
+        let settings = LoggingSettings {
+            file: Some(file_path.clone()),
+            format: "jsonl".parse().unwrap(),
+            ..LoggingSettings::default()
+        };
+        let subscriber = FileSubscriber::from_config(&settings).unwrap();
+
+        subscriber.handle_event(&event("page.updated", &[("doc", "css"), ("path", "a \"quoted\" path")]));
+        subscriber.handle_event(&event("warn.doc", &[]));
+
+        let lines = read_lines(&file_path);
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(first["name"], "page.updated");
+        assert_eq!(first["duration_ms"], 1.5);
+        assert_eq!(first["payload"]["path"], "a \"quoted\" path");
+        assert!(chrono::DateTime::parse_from_rfc3339(first["timestamp"].as_str().unwrap()).is_ok());
+        let second: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert!(second["payload"].as_object().unwrap().is_empty());
+
+        assert!("yaml".parse::<LogFormat>().is_err());
+        assert!(FileSubscriber::from_config(&LoggingSettings::default()).is_none());
+    }
+
+    #[test]
+    fn test_file_subscriber_rotates_by_size() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file_path = dir.path().join("events.log");
+        let subscriber = FileSubscriber::new(file_path.to_str().unwrap())
+            .with_format(LogFormat::Jsonl)
+            .with_max_size(400)
+            .with_keep(2);
+
+        for i in 0..40 {
+            subscriber.handle_event(&event("page.updated", &[("path", &format!("page{}", i))]));
+        }
+
+        let rotated_1 = dir.path().join("events.log.1");
+        let rotated_2 = dir.path().join("events.log.2");
+        assert!(rotated_1.exists());
+        assert!(rotated_2.exists());
+        assert!(!dir.path().join("events.log.3").exists());
+        for path in [&file_path, &rotated_1, &rotated_2] {
+            assert!(fs::metadata(path).unwrap().len() <= 400);
+        }
+
+        // 最新的事件在当前文件中，较早的事件在编号较小的轮转文件中
+        let last: serde_json::Value = serde_json::from_str(read_lines(&file_path).last().unwrap()).unwrap();
+        assert_eq!(last["payload"]["path"], "page39");
+        let newest_rotated = read_lines(&rotated_1);
+        let oldest_rotated = read_lines(&rotated_2);
+        let path_of = |line: &String| {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            value["payload"]["path"].as_str().unwrap()[4..].parse::<usize>().unwrap()
+        };
+        assert!(path_of(oldest_rotated.last().unwrap()) < path_of(&newest_rotated[0]));
+    }
+
+    #[test]
+    fn test_file_subscriber_concurrent_rotation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file_path = dir.path().join("events.log");
+        let subscriber = Arc::new(
+            FileSubscriber::new(file_path.to_str().unwrap())
+                .with_format(LogFormat::Jsonl)
+                .with_max_size(2048)
+                .with_keep(100),
+        );
+
+        let handles: Vec<_> = (0..8)
+            .map(|thread| {
+                let subscriber = subscriber.clone();
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        let id = format!("{}-{}", thread, i);
+                        subscriber.handle_event(&event("page.updated", &[("id", &id)]));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // 每个事件只出现一次，并且每一行都是完整的 JSON
+        let mut ids = std::collections::HashSet::new();
+        for entry in fs::read_dir(dir.path()).unwrap() {
+            for line in read_lines(&entry.unwrap().path()) {
+                let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+                assert!(ids.insert(value["payload"]["id"].as_str().unwrap().to_string()));
+            }
+        }
+        assert_eq!(ids.len(), 400);
+        assert!(dir.path().join("events.log.1").exists());
+    }
 }
@@ -1,5 +1,8 @@
 //! 核心类型定义
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
 /// 标识资源的唯一 ID
 pub type ResourceId = String;
 
@@ -16,4 +19,213 @@ pub type Release = String;
 pub type ModifiedTime = u64;
 
 /// 大小（以字节为单位）
-pub type Size = usize;
\ No newline at end of file
+pub type Size = usize;
+
+/// 页面键的大小写规则，每个文档单独设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyCase {
+    /// 保留原有的大小写
+    #[default]
+    Preserve,
+    /// 转为小写，用于不区分大小写的网站
+    Lower,
+}
+
+/// 文档内的页面路径
+///
+/// 页面映射、重定向映射和页面数据库都以它为键。只能通过构造函数创建，
+/// 构造时统一规范化：去掉锚点、首尾的斜杠，空路径为 `index`，并按文档的
+/// 大小写规则处理。网址和条目路径（可能带锚点）仍是字符串，需要显式转换。
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PageKey(String);
+
+impl PageKey {
+    /// 保留大小写的页面键
+    pub fn new(path: &str) -> Self {
+        Self::with_case(path, KeyCase::Preserve)
+    }
+
+    /// 按指定的大小写规则创建页面键
+    pub fn with_case(path: &str, case: KeyCase) -> Self {
+        let path = path.split('#').next().unwrap_or_default().trim().trim_matches('/');
+        let path = if path.is_empty() { "index" } else { path };
+        match case {
+            KeyCase::Preserve => Self(path.to_string()),
+            KeyCase::Lower => Self(path.to_lowercase()),
+        }
+    }
+
+    /// 页面路径
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 转为字符串
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// 指向页面（或页面中锚点）的条目路径
+    pub fn entry_path(&self, fragment: Option<&str>) -> String {
+        match fragment {
+            Some(fragment) if !fragment.is_empty() => format!("{}#{}", self.0, fragment),
+            _ => self.0.clone(),
+        }
+    }
+}
+
+impl fmt::Display for PageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<PageKey> for String {
+    fn from(key: PageKey) -> Self {
+        key.0
+    }
+}
+
+impl Serialize for PageKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// 反序列化时同样规范化，用于请求路径等外部输入
+impl<'de> Deserialize<'de> for PageKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|path| Self::new(&path))
+    }
+}
+
+/// 文档标识的最大长度（字符数）
+pub const MAX_SLUG_LEN: usize = 64;
+
+/// 文档标识（如 `css`、`react~18`）
+///
+/// 文档标识同时用于地址和目录名，所有来源（抓取器、目录名、请求路径）都经过
+/// [`DocSlug::new`] 规范化：去掉首尾的空白和斜杠，转为小写，字母、数字和
+/// `.`、`-`、`~` 以外的字符（空白、斜杠等）替换为下划线，连续的下划线合并，
+/// 最多保留 [`MAX_SLUG_LEN`] 个字符。大小写不同的名称（`Babel~7` 与
+/// `babel~7`）因此对应同一个文档，在不区分大小写的文件系统上也不会冲突。
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DocSlug(String);
+
+impl DocSlug {
+    /// 由文档名称或目录名创建
+    pub fn new(name: &str) -> Self {
+        let mut slug = String::with_capacity(name.len());
+        for c in name.trim().trim_matches('/').chars().flat_map(char::to_lowercase) {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '~') {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('_') {
+                slug.push('_');
+            }
+        }
+        let slug: String = slug.chars().take(MAX_SLUG_LEN).collect();
+        Self(slug.trim_end_matches('_').to_string())
+    }
+
+    /// 名称是否已经是规范的文档标识
+    pub fn is_normalized(name: &str) -> bool {
+        Self::new(name).0 == name
+    }
+
+    /// 文档标识
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 转为字符串
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// 文档页面的地址前缀 `/docs/<slug>/`
+    pub fn docs_prefix(&self) -> String {
+        format!("/docs/{}/", self.0)
+    }
+
+    /// 文档目录名：指定了版本（`latest` 除外）时为 `<slug>~<version>`，否则为 slug
+    pub fn dir_name(&self, version: &str) -> String {
+        let version = version.trim();
+        if version.is_empty() || version == "latest" {
+            self.0.clone()
+        } else {
+            format!("{}~{}", self.0, version)
+        }
+    }
+}
+
+impl fmt::Display for DocSlug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<DocSlug> for String {
+    fn from(slug: DocSlug) -> Self {
+        slug.0
+    }
+}
+
+impl Serialize for DocSlug {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for DocSlug {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Self::new(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_key_normalization() {
+        assert_eq!(PageKey::new("/guide/").as_str(), "guide");
+        assert_eq!(PageKey::new("guide#Syntax"), PageKey::new("guide"));
+        assert_eq!(PageKey::new("api/Array/#methods").as_str(), "api/Array");
+        assert_eq!(PageKey::new("").as_str(), "index");
+        assert_eq!(PageKey::new("/#top").as_str(), "index");
+        assert_ne!(PageKey::new("Array"), PageKey::new("array"));
+        assert_eq!(PageKey::with_case("/API/Array/", KeyCase::Lower), PageKey::new("api/array"));
+
+        // 规范化是幂等的
+        for path in ["/Guide/Intro/#x", "", "a//b/"] {
+            for case in [KeyCase::Preserve, KeyCase::Lower] {
+                let key = PageKey::with_case(path, case);
+                assert_eq!(PageKey::with_case(key.as_str(), case), key);
+            }
+        }
+
+        assert_eq!(PageKey::new("guide").entry_path(Some("syntax")), "guide#syntax");
+        assert_eq!(PageKey::new("guide").entry_path(Some("")), "guide");
+        let key: PageKey = serde_json::from_str(r#""/guide/""#).unwrap();
+        assert_eq!(serde_json::to_string(&key).unwrap(), r#""guide""#);
+    }
+
+    #[test]
+    fn test_doc_slug_normalization() {
+        assert_eq!(DocSlug::new(" Fixture Docs ").as_str(), "fixture_docs");
+        assert_eq!(DocSlug::new("/React~18/").as_str(), "react~18");
+        assert_eq!(DocSlug::new("css").docs_prefix(), "/docs/css/");
+        assert_eq!(DocSlug::new("Babel").dir_name("7"), "babel~7");
+        assert_eq!(DocSlug::new("babel").dir_name("latest"), "babel");
+        assert_eq!(DocSlug::new("babel").dir_name(""), "babel");
+        let slug: DocSlug = serde_json::from_str(r#""Node~20_LTS""#).unwrap();
+        assert_eq!(slug, DocSlug::new("node~20_lts"));
+
+        // 斜杠和其他符号替换为下划线，长度受限
+        assert_eq!(DocSlug::new("My Docs/API (v2)").as_str(), "my_docs_api_v2");
+        assert_eq!(DocSlug::new("Babel~7.21.4").as_str(), "babel~7.21.4");
+        assert_eq!(DocSlug::new(&"a".repeat(100)).as_str().len(), MAX_SLUG_LEN);
+        assert!(DocSlug::is_normalized("react~18"));
+        assert!(!DocSlug::is_normalized("Babel~7"));
+    }
+}
@@ -0,0 +1,505 @@
+//! 上游最新版本的查询
+//!
+//! 抓取器不再各自抓取上游页面、手工解析版本号，而是声明一个 [`VersionProbe`]：
+//! 从哪个地址、按什么规则读出版本号。内置三种探针：GitHub 的最新发布
+//! （[`GitHubLatestRelease`]）、JSON 中的字段（[`JsonFieldProbe`]）和正则表达式
+//! （[`RegexProbe`]）。查询统一经过 [`latest_version`]，带有超时和响应大小限制，
+//! 失败时返回带地址的 [`Error::Fetch`] 或 [`Error::Parse`]。
+//!
+//! `docs-outdated` 把查询结果保存在 [`VersionCache`] 中，有效期内不重复请求上游。
+
+use crate::core::error::{Error, Result};
+use crate::core::http_fetch::{FetchedResponse, HttpFetch};
+use crate::storage::json;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+
+/// 查询的默认超时时间
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 响应的默认大小限制（字节）
+pub const DEFAULT_PROBE_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// GitHub API 的地址
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// 版本查询结果的缓存文件，位于可写的文档根目录中
+pub const VERSION_CACHE_FILENAME: &str = "version_checks.json";
+
+/// 读出版本号之后的转换，如由发布日期得到版本名，返回 None 表示无法转换
+pub type VersionTransform = fn(&str) -> Option<String>;
+
+/// 查询的超时时间和响应大小限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeLimits {
+    /// 请求的超时时间
+    pub timeout: Duration,
+    /// 响应体的最大字节数
+    pub max_bytes: usize,
+}
+
+impl Default for ProbeLimits {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_PROBE_TIMEOUT,
+            max_bytes: DEFAULT_PROBE_MAX_BYTES,
+        }
+    }
+}
+
+/// 上游最新版本的查询方式
+pub trait VersionProbe: Send + Sync {
+    /// 查询的地址
+    fn url(&self) -> &str;
+
+    /// 从响应中读出版本号，失败时返回说明
+    fn parse(&self, response: &FetchedResponse) -> std::result::Result<String, String>;
+
+    /// 请求的头部
+    fn headers(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    /// 超时时间和响应大小限制
+    fn limits(&self) -> ProbeLimits {
+        ProbeLimits::default()
+    }
+}
+
+/// 按探针查询最新版本
+pub async fn latest_version(probe: &dyn VersionProbe, fetch: &dyn HttpFetch) -> Result<String> {
+    let url = probe.url();
+    let limits = probe.limits();
+    let fetch_error = |source| Error::Fetch {
+        url: url.to_string(),
+        source: Box::new(source),
+    };
+
+    // 响应体超过大小限制时在读取过程中停止，不会先读入整个响应
+    let headers = probe.headers();
+    let request = fetch.get_limited(url, &headers, limits.max_bytes);
+    let response = match tokio::time::timeout(limits.timeout, request).await {
+        Ok(response) => response.map_err(fetch_error)?,
+        Err(_) => {
+            return Err(fetch_error(Error::Message(format!("请求超时（{:?}）", limits.timeout))));
+        }
+    };
+    if !response.is_success() {
+        return Err(fetch_error(Error::HttpError(response.status)));
+    }
+
+    probe.parse(&response).map_err(|detail| Error::Parse {
+        url: url.to_string(),
+        detail,
+    })
+}
+
+/// 对读出的版本号应用转换
+fn transformed(version: &str, transform: Option<VersionTransform>) -> std::result::Result<String, String> {
+    match transform {
+        Some(transform) => transform(version).ok_or_else(|| format!("无法转换版本号 {:?}", version)),
+        None => Ok(version.to_string()),
+    }
+}
+
+/// GitHub 仓库的最新发布，版本号为发布标签去掉开头的 `v`
+pub struct GitHubLatestRelease {
+    /// 仓库（`<owner>/<repo>`）
+    repo: String,
+    /// 最新发布的 API 地址
+    url: String,
+    /// 超时时间和大小限制
+    limits: ProbeLimits,
+}
+
+impl GitHubLatestRelease {
+    /// 查询 `<owner>/<repo>` 的最新发布
+    pub fn new(repo: &str) -> Self {
+        Self {
+            repo: repo.to_string(),
+            url: format!("{}/repos/{}/releases/latest", GITHUB_API_URL, repo),
+            limits: ProbeLimits::default(),
+        }
+    }
+
+    /// 设置超时时间和大小限制
+    pub fn with_limits(mut self, limits: ProbeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl VersionProbe for GitHubLatestRelease {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn parse(&self, response: &FetchedResponse) -> std::result::Result<String, String> {
+        let release: serde_json::Value = serde_json::from_slice(&response.body).map_err(|e| e.to_string())?;
+        let tag = release["tag_name"]
+            .as_str()
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .ok_or_else(|| format!("{} 的最新发布没有标签", self.repo))?;
+        Ok(tag.strip_prefix('v').unwrap_or(tag).to_string())
+    }
+
+    fn headers(&self) -> HashMap<String, String> {
+        // GitHub API 要求请求带有 User-Agent
+        HashMap::from([
+            ("Accept".to_string(), "application/vnd.github+json".to_string()),
+            ("User-Agent".to_string(), "xwdoc".to_string()),
+        ])
+    }
+
+    fn limits(&self) -> ProbeLimits {
+        self.limits
+    }
+}
+
+/// JSON 文档中的字段，用 JSON Pointer（如 `/dist-tags/latest`）指定
+pub struct JsonFieldProbe {
+    /// 查询的地址
+    url: String,
+    /// 字段的 JSON Pointer
+    pointer: String,
+    /// 读出字段之后的转换
+    transform: Option<VersionTransform>,
+    /// 超时时间和大小限制
+    limits: ProbeLimits,
+}
+
+impl JsonFieldProbe {
+    /// 读取 `url` 返回的 JSON 中 `pointer` 指向的字段，字段为字符串或数字
+    pub fn new(url: &str, pointer: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            pointer: pointer.to_string(),
+            transform: None,
+            limits: ProbeLimits::default(),
+        }
+    }
+
+    /// 设置读出字段之后的转换
+    pub fn with_transform(mut self, transform: VersionTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// 设置超时时间和大小限制
+    pub fn with_limits(mut self, limits: ProbeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl VersionProbe for JsonFieldProbe {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn parse(&self, response: &FetchedResponse) -> std::result::Result<String, String> {
+        let document: serde_json::Value = serde_json::from_slice(&response.body).map_err(|e| e.to_string())?;
+        let version = match document.pointer(&self.pointer) {
+            Some(serde_json::Value::String(version)) => version.trim().to_string(),
+            Some(serde_json::Value::Number(version)) => version.to_string(),
+            _ => String::new(),
+        };
+        if version.is_empty() {
+            return Err(format!("JSON 中没有字段 {}", self.pointer));
+        }
+        transformed(&version, self.transform)
+    }
+
+    fn limits(&self) -> ProbeLimits {
+        self.limits
+    }
+}
+
+/// 响应文本中第一个匹配正则表达式的位置，版本号为第一个捕获组（没有捕获组时为整个匹配）
+pub struct RegexProbe {
+    /// 查询的地址
+    url: String,
+    /// 匹配版本号的正则表达式
+    pattern: Regex,
+    /// 读出版本号之后的转换
+    transform: Option<VersionTransform>,
+    /// 超时时间和大小限制
+    limits: ProbeLimits,
+}
+
+impl RegexProbe {
+    /// 在 `url` 返回的文本中匹配 `pattern`
+    pub fn new(url: &str, pattern: Regex) -> Self {
+        Self {
+            url: url.to_string(),
+            pattern,
+            transform: None,
+            limits: ProbeLimits::default(),
+        }
+    }
+
+    /// 设置读出版本号之后的转换
+    pub fn with_transform(mut self, transform: VersionTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// 设置超时时间和大小限制
+    pub fn with_limits(mut self, limits: ProbeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl VersionProbe for RegexProbe {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn parse(&self, response: &FetchedResponse) -> std::result::Result<String, String> {
+        let text = response.text();
+        let captures = self
+            .pattern
+            .captures(&text)
+            .ok_or_else(|| format!("没有匹配 {} 的内容", self.pattern))?;
+        let version = captures.get(1).or_else(|| captures.get(0)).map_or("", |m| m.as_str().trim());
+        transformed(version, self.transform)
+    }
+
+    fn limits(&self) -> ProbeLimits {
+        self.limits
+    }
+}
+
+/// 比较当前版本和最新版本
+///
+/// 只比较前两段，第三段是补丁更新。每段去掉开头的非数字字符后按数值比较，
+/// `ES6` 和 `ES14` 这样的版本名也能比较。主版本号为 0 或 1 时次版本号的变化
+/// 也视为主版本的更新。
+pub fn outdated_state(current: &str, latest: &str) -> &'static str {
+    let parts = |version: &str| -> Vec<u32> {
+        version
+            .split(['-', '.'])
+            .map(|part| part.trim_start_matches(|c: char| !c.is_ascii_digit()).parse().unwrap_or(0))
+            .collect()
+    };
+    let current = parts(current);
+    let latest = parts(latest);
+
+    for i in 0..2 {
+        if i >= current.len() || i >= latest.len() {
+            break;
+        }
+        if latest[i] > current[i] {
+            if i == 0 || latest[0] <= 1 {
+                return "Outdated major version";
+            }
+            return "Outdated minor version";
+        }
+        if latest[i] < current[i] {
+            break;
+        }
+    }
+    "Up-to-date"
+}
+
+/// 缓存的查询结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedVersion {
+    /// 查询到的最新版本
+    pub version: String,
+    /// 查询时间（Unix 秒）
+    pub checked_at: i64,
+}
+
+/// 版本查询结果的缓存，键为抓取器名称
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VersionCache {
+    versions: BTreeMap<String, CachedVersion>,
+}
+
+impl VersionCache {
+    /// 读取缓存文件，文件不存在或无法读取时为空缓存
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        json::read_json(path).unwrap_or_else(|e| {
+            log::warn!("版本查询缓存无法读取，已忽略: {}", e);
+            Self::default()
+        })
+    }
+
+    /// 写入缓存文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content).map_err(|e| Error::Storage {
+            path: path.to_path_buf(),
+            source: Box::new(Error::Io(e)),
+        })
+    }
+
+    /// 有效期内的查询结果
+    pub fn fresh(&self, name: &str, now: i64, ttl: Duration) -> Option<&str> {
+        self.versions
+            .get(name)
+            .filter(|cached| now.saturating_sub(cached.checked_at) < ttl.as_secs() as i64)
+            .map(|cached| cached.version.as_str())
+    }
+
+    /// 记录查询结果
+    pub fn insert(&mut self, name: &str, version: &str, now: i64) {
+        self.versions.insert(
+            name.to_string(),
+            CachedVersion {
+                version: version.to_string(),
+                checked_at: now,
+            },
+        );
+    }
+
+    /// 有效期内使用缓存的结果，否则查询上游并记录结果；查询失败时不记录
+    pub async fn latest_version(
+        &mut self,
+        name: &str,
+        probe: &dyn VersionProbe,
+        fetch: &dyn HttpFetch,
+        now: i64,
+        ttl: Duration,
+    ) -> Result<String> {
+        if let Some(version) = self.fresh(name, now, ttl) {
+            return Ok(version.to_string());
+        }
+        let version = latest_version(probe, fetch).await?;
+        self.insert(name, &version, now);
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_fetch::{MockFetch, MockReply};
+
+    const RELEASE_URL: &str = "https://api.github.com/repos/babel/babel/releases/latest";
+
+    #[tokio::test]
+    async fn test_github_latest_release() {
+        let fetch = MockFetch::new().with_response(
+            RELEASE_URL,
+            FetchedResponse::new(200).with_body(r#"{"tag_name": "v7.23.2", "name": "v7.23.2"}"#),
+        );
+        let probe = GitHubLatestRelease::new("babel/babel");
+        assert_eq!(latest_version(&probe, &fetch).await.unwrap(), "7.23.2");
+        assert_eq!(fetch.requests()[0].headers["User-Agent"], "xwdoc");
+
+        // 没有发布时 GitHub 返回 404
+        let error = latest_version(&probe, &MockFetch::new()).await.unwrap_err();
+        assert_eq!(error.url(), Some(RELEASE_URL));
+        assert!(matches!(error, Error::Fetch { .. }), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn test_json_field_probe() {
+        let url = "https://registry.npmjs.org/typescript";
+        let fetch = MockFetch::new()
+            .with_response(url, FetchedResponse::new(200).with_body(r#"{"dist-tags": {"latest": "5.3.3"}}"#));
+        let probe = JsonFieldProbe::new(url, "/dist-tags/latest");
+        assert_eq!(latest_version(&probe, &fetch).await.unwrap(), "5.3.3");
+
+        let probe = JsonFieldProbe::new(url, "/dist-tags/next");
+        let error = latest_version(&probe, &fetch).await.unwrap_err();
+        assert!(matches!(error, Error::Parse { .. }), "{:?}", error);
+        assert!(error.to_string().contains("/dist-tags/next"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_regex_probe_with_transform() {
+        let url = "https://example.com/changelog";
+        let fetch = MockFetch::new().with_response(url, FetchedResponse::html("<h2>Release 2023 (v4.2)</h2>"));
+        let probe = RegexProbe::new(url, Regex::new(r"Release (\d{4})").unwrap())
+            .with_transform(|year| year.parse::<i32>().ok().map(|year| format!("ES{}", year - 2009)));
+        assert_eq!(latest_version(&probe, &fetch).await.unwrap(), "ES14");
+
+        let probe = RegexProbe::new(url, Regex::new(r"v\d+\.\d+").unwrap());
+        assert_eq!(latest_version(&probe, &fetch).await.unwrap(), "v4.2");
+
+        let probe = RegexProbe::new(url, Regex::new(r"Version (\d+)").unwrap());
+        assert!(matches!(latest_version(&probe, &fetch).await, Err(Error::Parse { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_size_limit() {
+        let url = "https://example.com/version.json";
+        let fetch = MockFetch::new().with_reply(
+            url,
+            MockReply::respond(FetchedResponse::new(200).with_body(r#"{"version": "1.0"}"#)).after(Duration::from_millis(200)),
+        );
+        let limits = ProbeLimits {
+            timeout: Duration::from_millis(20),
+            ..ProbeLimits::default()
+        };
+        let probe = JsonFieldProbe::new(url, "/version").with_limits(limits);
+        let error = latest_version(&probe, &fetch).await.unwrap_err();
+        assert!(error.to_string().contains("超时"), "{}", error);
+
+        let fetch = MockFetch::new().with_response(url, FetchedResponse::new(200).with_body(vec![b' '; 64]));
+        let limits = ProbeLimits {
+            max_bytes: 32,
+            ..ProbeLimits::default()
+        };
+        let probe = JsonFieldProbe::new(url, "/version").with_limits(limits);
+        let error = latest_version(&probe, &fetch).await.unwrap_err();
+        assert!(error.to_string().contains("32 字节"), "{}", error);
+        // 请求时即限制读取的大小
+        assert_eq!(fetch.requests()[0].max_bytes, Some(32));
+
+        // 声明的长度超过限制时不读取响应体
+        let fetch = MockFetch::new().with_response(
+            url,
+            FetchedResponse::new(200)
+                .with_header("Content-Length", "4096")
+                .with_body(r#"{"version": "1.0"}"#),
+        );
+        let error = latest_version(&probe, &fetch).await.unwrap_err();
+        assert!(error.to_string().contains("32 字节"), "{}", error);
+    }
+
+    #[test]
+    fn test_outdated_state() {
+        assert_eq!(outdated_state("1.74.0", "1.75.0"), "Outdated major version");
+        assert_eq!(outdated_state("2.3.0", "2.4.1"), "Outdated minor version");
+        assert_eq!(outdated_state("2.4.0", "2.4.1"), "Up-to-date");
+        assert_eq!(outdated_state("3.0", "2.9"), "Up-to-date");
+        assert_eq!(outdated_state("ES6", "ES14"), "Outdated major version");
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(VERSION_CACHE_FILENAME);
+        let fetch = MockFetch::new().with_response(
+            RELEASE_URL,
+            FetchedResponse::new(200).with_body(r#"{"tag_name": "v7.23.2"}"#),
+        );
+        let probe = GitHubLatestRelease::new("babel/babel");
+        let ttl = Duration::from_secs(3600);
+
+        let mut cache = VersionCache::load(&path);
+        assert_eq!(cache.latest_version("babel", &probe, &fetch, 1000, ttl).await.unwrap(), "7.23.2");
+        cache.save(&path).unwrap();
+
+        // 有效期内不再请求上游
+        let mut cache = VersionCache::load(&path);
+        assert_eq!(cache.latest_version("babel", &probe, &fetch, 4000, ttl).await.unwrap(), "7.23.2");
+        assert_eq!(fetch.request_count(RELEASE_URL), 1);
+
+        cache.latest_version("babel", &probe, &fetch, 4600, ttl).await.unwrap();
+        assert_eq!(fetch.request_count(RELEASE_URL), 2);
+        assert_eq!(cache.fresh("babel", 4600, ttl), Some("7.23.2"));
+    }
+}
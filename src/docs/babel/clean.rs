@@ -3,10 +3,13 @@
 
 use crate::core::error::Result;
 use crate::core::filters::BaseCleanHtmlFilter;
-use crate::core::scraper::filter::{Filter, FilterContext};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
 use scraper::{Html, Selector};
 use std::any::Any;
 
+/// 主文档内容的容器
+const CONTAINER_SELECTOR: &str = ".theme-doc-markdown";
+
 /// Babel 文档 HTML 清理过滤器
 /// 参考 Ruby 原版 babel/clean_html.rb 实现
 pub struct BabelCleanHtmlFilter {
@@ -24,11 +27,12 @@ impl BabelCleanHtmlFilter {
 }
 
 impl Filter for BabelCleanHtmlFilter {
-    fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
         let mut document = Html::parse_document(html);
+        let mut warnings = Vec::new();
 
         // 获取主文档内容 - 对应原版的 @doc = at_css('.theme-doc-markdown')
-        if let Ok(selector) = Selector::parse(".theme-doc-markdown") {
+        if let Ok(selector) = Selector::parse(CONTAINER_SELECTOR) {
             if let Some(main_content) = document.select(&selector).next() {
                 // 创建一个新的HTML文档仅包含主内容
                 let main_html = main_content.html();
@@ -36,6 +40,12 @@ impl Filter for BabelCleanHtmlFilter {
                     "<html><head></head><body>{}</body></html>",
                     main_html
                 ));
+            } else {
+                // 找不到容器时继续处理整个页面
+                warnings.push(
+                    self.warning("missing container")
+                        .with_selector(CONTAINER_SELECTOR),
+                );
             }
         }
 
@@ -66,7 +76,7 @@ impl Filter for BabelCleanHtmlFilter {
             .base_filter
             .remove_attributes(&html, &["class", "style"]);
 
-        Ok(html)
+        Ok(FilterOutput::new(html).with_warnings(warnings))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
@@ -81,3 +91,35 @@ impl Filter for BabelCleanHtmlFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_container_is_reported_as_warning() {
+        let filter = BabelCleanHtmlFilter::new();
+        let context = FilterContext::default();
+
+        let html = "<html><body><main><h1>Options</h1><p>Text</p></main></body></html>";
+        let output = filter.apply_v2(html, &context).unwrap();
+
+        assert!(output.html.contains("<h1>Options</h1>"));
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].filter, "BabelCleanHtmlFilter");
+        assert_eq!(output.warnings[0].message, "missing container");
+        assert_eq!(output.warnings[0].selector.as_deref(), Some(CONTAINER_SELECTOR));
+    }
+
+    #[test]
+    fn test_container_present_has_no_warning() {
+        let filter = BabelCleanHtmlFilter::new();
+        let context = FilterContext::default();
+
+        let html = r#"<html><body><nav>Menu</nav><div class="theme-doc-markdown"><h1>Options</h1></div></body></html>"#;
+        let output = filter.apply_v2(html, &context).unwrap();
+
+        assert!(!output.html.contains("Menu"));
+        assert!(output.warnings.is_empty());
+    }
+}
@@ -2,10 +2,11 @@
 //! 简化版 Babel 条目过滤器实现
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
+use crate::core::filters::selector;
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
 use lazy_static::lazy_static;
-use scraper::{Html, Selector};
-use std::collections::HashMap;
+use scraper::Html;
+use std::collections::{HashMap, HashSet};
 use std::any::Any;
 
 // 定义常量 ENTRIES，对应原Ruby版本的常量定义
@@ -49,6 +50,35 @@ lazy_static! {
     };
 }
 
+/// 为每个选项生成锚点条目的页面
+const OPTIONS_PAGES: &[&str] = &["options", "config-files", "configuration"];
+
+/// 选项页面中不作为条目的通用标题
+const OPTION_HEADING_STOPLIST: &[&str] = &[
+    "history",
+    "examples",
+    "usage",
+    "advanced usage",
+    "output targets",
+    "options concepts",
+];
+
+/// 列出所有插件的索引页面
+const PLUGIN_INDEX_PAGES: &[&str] = &["plugins", "plugins-list"];
+
+/// 插件页面路径的前缀
+const PLUGIN_PAGE_PREFIX: &str = "babel-plugin-";
+
+/// 插件包名前缀对应的类型，与插件索引页面中的分节标题一致
+const PLUGIN_TYPES: &[(&str, &str)] = &[
+    ("transform-", "Transform Plugins"),
+    ("syntax-", "Syntax Plugins"),
+    ("proposal-", "Proposal Plugins"),
+];
+
+/// 不属于以上分类的插件的类型
+const OTHER_PLUGINS: &str = "Other Plugins";
+
 /// Babel 文档条目过滤器
 /// 参考 Ruby 原版 babel/entries.rb 实现
 pub struct BabelEntriesFilter {}
@@ -61,12 +91,10 @@ impl BabelEntriesFilter {
 
     /// 获取文档名称 - 对应Ruby原版的get_name方法
     fn get_name(&self, document: &Html) -> String {
-        if let Ok(selector) = Selector::parse("h1") {
-            if let Some(heading) = document.select(&selector).next() {
-                return heading.text().collect::<Vec<_>>().join("");
-            }
+        match document.select(&selector("h1")).next() {
+            Some(heading) => heading.text().collect::<Vec<_>>().join(""),
+            None => String::new(),
         }
-        String::new()
     }
 
     /// 获取文档类型 - 对应Ruby原版的get_type方法
@@ -78,36 +106,125 @@ impl BabelEntriesFilter {
             }
         }
 
-        // 检查是否为插件
+        // 检查是否为插件，按包名归入与插件索引页面相同的分类
+        if let Some(package) = plugin_package(subpath) {
+            return Some(plugin_type(&package).to_string());
+        }
         if subpath.contains("babel-plugin") {
-            return Some("Other Plugins".to_string());
+            return Some(OTHER_PLUGINS.to_string());
         }
 
         None
     }
+
+    /// 是否为插件索引页面
+    fn is_plugin_index(&self, subpath: &str) -> bool {
+        PLUGIN_INDEX_PAGES.contains(&last_segment(subpath))
+    }
+
+    /// 插件索引页面中每个插件链接的条目
+    ///
+    /// 条目名称为插件的包名，类型为链接所在分节的 `<h2>` 标题，路径为插件页面，
+    /// 插件页面本身的标题无法解析时也有条目。同一插件只保留第一次出现的链接。
+    fn plugin_index_entries(&self, document: &Html) -> Vec<(String, String, String)> {
+        let selector = selector("h2, a[href]");
+
+        let mut section: Option<String> = None;
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        for element in document.select(&selector) {
+            if element.value().name() == "h2" {
+                let title = element.text().collect::<String>().replace('\u{200b}', "");
+                section = Some(title.trim().to_string()).filter(|title| !title.is_empty());
+                continue;
+            }
+            let href = element.value().attr("href").unwrap_or_default();
+            let path = href.split(['#', '?']).next().unwrap_or_default();
+            let Some(package) = plugin_package(path) else {
+                continue;
+            };
+            let page = last_segment(path).to_string();
+            if !seen.insert(page.clone()) {
+                continue;
+            }
+            let entry_type = section.clone().unwrap_or_else(|| plugin_type(&package).to_string());
+            entries.push((package, page, entry_type));
+        }
+        entries
+    }
+
+    /// 是否为选项页面
+    fn is_options_page(&self, subpath: &str) -> bool {
+        let page = subpath.trim_matches('/').rsplit('/').next().unwrap_or("");
+        OPTIONS_PAGES.contains(&page)
+    }
+
+    /// 为选项页面中带 id 的标题生成锚点条目
+    ///
+    /// 标题中的分组名（如 "Primary options"）和通用标题不生成条目。
+    fn option_entries(&self, document: &Html, subpath: &str) -> Vec<(String, String, String)> {
+        let selector = selector("h2[id], h3[id]");
+
+        document
+            .select(&selector)
+            .filter_map(|heading| {
+                let id = heading.value().attr("id")?;
+                let name = heading
+                    .text()
+                    .collect::<String>()
+                    .replace('\u{200b}', "")
+                    .trim()
+                    .to_string();
+                let lower = name.to_lowercase();
+                if name.is_empty()
+                    || lower.ends_with(" options")
+                    || OPTION_HEADING_STOPLIST.contains(&lower.as_str())
+                {
+                    return None;
+                }
+                Some((name, format!("{}#{}", subpath, id), "Options".to_string()))
+            })
+            .collect()
+    }
 }
 
 impl Filter for BabelEntriesFilter {
-    fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
-        // 条目过滤器不修改HTML内容
-        Ok(html.to_string())
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        // 条目过滤器不修改HTML内容，只检查能否获取条目名称
+        let mut output = FilterOutput::new(html);
+        if self.get_name(context.html_doc()).trim().is_empty() {
+            output = output.with_warnings([self.warning("missing entry name").with_selector("h1")]);
+        }
+        Ok(output)
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
         Box::new(Self::new())
     }
 
-    fn get_entries(&self, html: &str, context: &FilterContext) -> Vec<(String, String, String)> {
-        let document = Html::parse_document(html);
-        let name = self.get_name(&document);
+    fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+        let mut name = self.get_name(context.html_doc());
+        // 插件页面的标题无法解析时使用包名，与插件索引页面中的条目一致
+        if name.trim().is_empty() {
+            if let Some(package) = plugin_package(&context.current_path) {
+                name = package;
+            }
+        }
 
         // 获取类型
         let entry_type = self
             .get_type(&name, &context.current_path)
             .unwrap_or_else(|| "Miscellaneous".to_string());
 
-        // 创建条目 - 简单地返回当前页面作为唯一条目
-        vec![(name, context.current_path.clone(), entry_type)]
+        // 当前页面作为条目，选项页面再为每个选项添加锚点条目
+        let mut entries = vec![(name, context.current_path.clone(), entry_type)];
+        if self.is_options_page(&context.current_path) {
+            entries.extend(self.option_entries(context.html_doc(), &context.current_path));
+        }
+        if self.is_plugin_index(&context.current_path) {
+            entries.extend(self.plugin_index_entries(context.html_doc()));
+        }
+        entries
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -118,3 +235,104 @@ impl Filter for BabelEntriesFilter {
         self
     }
 }
+
+/// 路径的最后一段
+fn last_segment(path: &str) -> &str {
+    path.trim_matches('/').rsplit('/').next().unwrap_or("")
+}
+
+/// 插件页面路径对应的包名，如 `babel-plugin-transform-classes` 对应
+/// `@babel/plugin-transform-classes`，不是插件页面时返回 None
+fn plugin_package(path: &str) -> Option<String> {
+    let plugin = last_segment(path).strip_prefix(PLUGIN_PAGE_PREFIX)?;
+    (!plugin.is_empty()).then(|| format!("@babel/plugin-{}", plugin))
+}
+
+/// 由包名推断插件的类型
+fn plugin_type(package: &str) -> &'static str {
+    let plugin = package.trim_start_matches("@babel/plugin-");
+    PLUGIN_TYPES
+        .iter()
+        .find(|(prefix, _)| plugin.starts_with(prefix))
+        .map_or(OTHER_PLUGINS, |(_, entry_type)| entry_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries_for(path: &str, html: &str) -> Vec<(String, String, String)> {
+        let mut context = FilterContext::new().with_html(html);
+        context.current_path = path.to_string();
+        BabelEntriesFilter::new().get_entries(&context)
+    }
+
+    #[test]
+    fn test_options_page_fixture() {
+        let html = std::fs::read_to_string("test_docs/babel_original/options.html").unwrap();
+        let entries = entries_for("options", &html);
+
+        // 页面条目保持不变
+        assert_eq!(entries[0], ("Options".to_string(), "options".to_string(), "Usage".to_string()));
+
+        let options: Vec<_> = entries[1..].iter().collect();
+        assert!(options.len() >= 15);
+        assert!(options.iter().all(|(_, path, entry_type)| path.starts_with("options#") && entry_type == "Options"));
+        assert!(options.contains(&&("presets".to_string(), "options#presets".to_string(), "Options".to_string())));
+        assert!(options.iter().any(|(name, path, _)| name == "sourceMaps" && path == "options#sourcemaps"));
+        assert!(!options.iter().any(|(name, _, _)| name == "Primary options" || name == "Advanced Usage"));
+    }
+
+    #[test]
+    fn test_stoplist_and_other_pages() {
+        let html = r#"<h1>Options</h1><h2 id="history">History</h2><h3 id="targets">targets</h3>"#;
+        let entries = entries_for("options", html);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].0, "targets");
+
+        // 其他页面只有页面条目
+        assert_eq!(entries_for("plugins", html).len(), 1);
+    }
+
+    #[test]
+    fn test_plugin_index_fixture() {
+        let html = std::fs::read_to_string("test_docs/babel_original/plugins-list.html").unwrap();
+        let entries = entries_for("plugins-list", &html);
+        assert_eq!(entries[0].1, "plugins-list");
+
+        let plugins = &entries[1..];
+        assert!(plugins.len() >= 10);
+        let entry = |name: &str, path: &str, entry_type: &str| (name.to_string(), path.to_string(), entry_type.to_string());
+        assert!(plugins.contains(&entry(
+            "@babel/plugin-transform-class-properties",
+            "babel-plugin-transform-class-properties",
+            "Transform Plugins"
+        )));
+        assert!(plugins.contains(&entry("@babel/plugin-syntax-bigint", "babel-plugin-syntax-bigint", "Syntax Plugins")));
+        assert!(plugins.contains(&entry(
+            "@babel/plugin-proposal-decorators",
+            "babel-plugin-proposal-decorators",
+            "Proposal Plugins"
+        )));
+
+        // 分节内的 h3 不影响类型，重复的链接和非插件链接不生成条目
+        assert!(plugins.iter().all(|(_, _, entry_type)| entry_type.ends_with(" Plugins")));
+        let paths: HashSet<&str> = plugins.iter().map(|(_, path, _)| path.as_str()).collect();
+        assert_eq!(paths.len(), plugins.len());
+    }
+
+    #[test]
+    fn test_plugin_page_matches_index_entry() {
+        // 插件页面的条目与索引页面中的条目相同，保存时去重
+        let entries = entries_for(
+            "babel-plugin-syntax-bigint",
+            "<h1>@babel/plugin-syntax-bigint</h1>",
+        );
+        assert_eq!(entries[0].2, "Syntax Plugins");
+
+        // 标题无法解析时使用包名
+        let entries = entries_for("babel-plugin-transform-classes", "<div>No heading</div>");
+        assert_eq!(entries[0].0, "@babel/plugin-transform-classes");
+        assert_eq!(entries[0].2, "Transform Plugins");
+    }
+}
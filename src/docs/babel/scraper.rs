@@ -4,10 +4,19 @@
 //! 参考文件: lib/docs/scrapers/babel.rb
 
 use crate::core::error::Result;
+use crate::core::filters::AttributionFilter;
+use crate::core::http_fetch::{HttpFetch, ReqwestFetch};
+use crate::core::instrumentable;
+use crate::core::scraper::url_scraper::START_SCRAPER_EVENT;
 use crate::core::scraper::base::Scraper;
 use crate::core::scraper::url_scraper::UrlScraper;
+use crate::core::version_probe::{self, JsonFieldProbe, VersionProbe};
 use crate::docs::babel::{BabelCleanHtmlFilter, BabelEntriesFilter};
 use async_trait::async_trait;
+use std::sync::Arc;
+
+/// npm 上 @babel/core 最新发布的信息
+const NPM_LATEST_URL: &str = "https://registry.npmjs.org/@babel/core/latest";
 
 /// Babel documentation scraper
 ///
@@ -15,6 +24,8 @@ use async_trait::async_trait;
 pub struct BabelScraper {
     /// The underlying URL scraper
     scraper: UrlScraper,
+    /// 查询版本使用的 HTTP 实现
+    fetch: Arc<dyn HttpFetch>,
 }
 
 impl BabelScraper {
@@ -41,6 +52,7 @@ impl BabelScraper {
         scraper = scraper.with_attribution(
             "© 2014-present Sebastian McKenzie<br>Licensed under the MIT License.",
         );
+        scraper = scraper.with_license("MIT");
 
         // 设置相关链接
         scraper = scraper.with_string_links(vec![
@@ -54,6 +66,9 @@ impl BabelScraper {
         // 配置路径末尾斜杠
         scraper = scraper.with_trailing_slash(true);
 
+        // babeljs.io 是静态站点，可以比 MDN 抓取得快一些
+        scraper = scraper.with_rate_limit(120).with_burst(5);
+
         // 配置跳过模式 - 直接从原始Ruby代码复制
         scraper = scraper.with_skip_patterns(vec![
             "usage/.*",
@@ -75,18 +90,32 @@ impl BabelScraper {
         // 添加过滤器 - 严格按照原始Ruby代码的顺序
         scraper = scraper.with_filter(Box::new(BabelCleanHtmlFilter::new()));
         scraper = scraper.with_filter(Box::new(BabelEntriesFilter::new()));
+        scraper = scraper.with_filter(Box::new(AttributionFilter::new()));
+
+        Self {
+            scraper,
+            fetch: Arc::new(ReqwestFetch::new()),
+        }
+    }
 
-        Self { scraper }
+    /// 设置查询版本和抓取页面使用的 HTTP 实现
+    pub fn with_fetch(mut self, fetch: Arc<dyn HttpFetch>) -> Self {
+        self.scraper = self.scraper.with_fetch(fetch.clone());
+        self.fetch = fetch;
+        self
     }
 
-    /// 获取最新版本（类似于Ruby原版的get_latest_version方法）
+    /// 获取最新版本（类似于Ruby原版的get_latest_version方法，读取 npm 上 @babel/core 的版本）
     pub async fn get_latest_version(&self) -> Result<String> {
-        // 具体实现可以使用 GitHub API 获取最新发布版本
-        // 这里暂时返回一个固定版本作为示例
-        Ok("7.21.4".to_string())
+        version_probe::latest_version(&version_probe(), self.fetch.as_ref()).await
     }
 }
 
+/// npm 上 @babel/core 的最新版本
+fn version_probe() -> JsonFieldProbe {
+    JsonFieldProbe::new(NPM_LATEST_URL, "/version")
+}
+
 #[async_trait]
 impl Scraper for BabelScraper {
     fn name(&self) -> &str {
@@ -98,7 +127,33 @@ impl Scraper for BabelScraper {
     }
 
     async fn run(&mut self) -> Result<()> {
-        println!("Starting Babel documentation scraping...");
+        instrumentable::message(START_SCRAPER_EVENT, "Starting Babel documentation scraping...");
         self.scraper.run().await
     }
+
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(&mut self.scraper)
+    }
+
+    fn version_probe(&self) -> Option<Box<dyn VersionProbe>> {
+        Some(Box::new(version_probe()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch};
+
+    #[tokio::test]
+    async fn test_latest_version_from_npm() {
+        let fetch = Arc::new(MockFetch::new().with_response(
+            NPM_LATEST_URL,
+            FetchedResponse::new(200).with_body(r#"{"name":"@babel/core","version":"7.24.5"}"#),
+        ));
+        let scraper = BabelScraper::new("", "").with_fetch(fetch.clone());
+
+        assert_eq!(scraper.get_latest_version().await.unwrap(), "7.24.5");
+        assert_eq!(fetch.requested_urls(), [NPM_LATEST_URL]);
+    }
 }
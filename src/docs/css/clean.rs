@@ -0,0 +1,312 @@
+//! CSS文档HTML清理过滤器
+//!
+//! MDN 属性页中的“Formal syntax”代码块和定义表（初始值、是否继承、计算值等）
+//! 依赖 MDN 自身的类名排版，去掉类名后会变成难以阅读的连续文本。清理时先把
+//! 它们转换为稳定的语义化标记（`<pre class="_syntax">` 和
+//! `<table class="_definition">`），这两个类名在清理属性时会被保留。
+
+use crate::core::error::Result;
+use crate::core::filters::markup::escape_text;
+use crate::core::filters::{serialize_html, BaseCleanHtmlFilter, DEFINITION_CLASS, SYNTAX_CLASS};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use kuchiki::iter::NodeIterator;
+use kuchiki::traits::TendrilSink;
+use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use scraper::{ElementRef, Html, Selector};
+use std::any::Any;
+
+/// 主文档内容的容器
+const CONTAINER_SELECTOR: &str = "article.main-page-content, #content";
+
+/// 形式语法代码块（新版 MDN 使用 `css-formal-syntax`，旧版使用 `syntaxbox`）
+pub const FORMAL_SYNTAX_SELECTOR: &str = "pre.css-formal-syntax, pre.syntaxbox";
+
+/// 属性定义表
+pub const DEFINITION_TABLE_SELECTOR: &str = "table.properties";
+
+/// 需要移除的元素，包括 MDN 页面中文档内容以外的部分（操作按钮、面包屑、修改时间等）
+const REMOVED_ELEMENTS: &[&str] = &[
+    "script",
+    "style",
+    "link",
+    ".example-header",
+    ".bc-data",
+    ".article-actions",
+    ".breadcrumbs-container",
+    ".metadata",
+    ".sidebar",
+    ".section-edit",
+];
+
+/// CSS 文档 HTML 清理过滤器
+pub struct CssCleanHtmlFilter {
+    /// 基础清理过滤器
+    base_filter: BaseCleanHtmlFilter,
+}
+
+impl Default for CssCleanHtmlFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CssCleanHtmlFilter {
+    /// 创建新的 CSS HTML 清理过滤器
+    pub fn new() -> Self {
+        Self {
+            base_filter: BaseCleanHtmlFilter::new(),
+        }
+    }
+
+    /// 将形式语法代码块转换为纯文本的 `<pre class="_syntax">`
+    ///
+    /// `<br>` 转换为换行，链接和高亮标记只保留文字，行尾空白和末尾空行会被去掉。
+    fn syntax_block(pre: &NodeRef) -> String {
+        let mut text = String::new();
+        for node in pre.descendants() {
+            if let Some(t) = node.as_text() {
+                text.push_str(&t.borrow());
+            } else if node.as_element().is_some_and(|el| &*el.name.local == "br") {
+                text.push('\n');
+            }
+        }
+
+        let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+        let syntax = lines.join("\n");
+        format!(
+            r#"<pre class="{}">{}</pre>"#,
+            SYNTAX_CLASS,
+            escape_text(syntax.trim_matches('\n'))
+        )
+    }
+
+    /// 将定义表转换为 `<table class="_definition">`
+    ///
+    /// 表头单元格只保留规范化后的文字（如 `Computed value`），数据单元格保留原有内容。
+    fn definition_table(table: &NodeRef) -> String {
+        let mut rows = String::new();
+        for row in select_all(table, "tr") {
+            let mut header = None;
+            let mut value = None;
+            for cell in row.as_node().children().elements() {
+                match &*cell.name.local {
+                    "th" if header.is_none() => header = Some(normalize_header(&cell.text_contents())),
+                    "td" if value.is_none() => {
+                        let inner: String = cell.as_node().children().map(|child| child.to_string()).collect();
+                        value = Some(inner.trim().to_string());
+                    }
+                    _ => {}
+                }
+            }
+            if let (Some(header), Some(value)) = (header, value) {
+                rows.push_str(&format!(
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    escape_text(&header),
+                    value
+                ));
+            }
+        }
+        format!(r#"<table class="{}"><tbody>{}</tbody></table>"#, DEFINITION_CLASS, rows)
+    }
+
+    /// 转换形式语法代码块和定义表
+    ///
+    /// 在解析后的文档中选出这些元素并替换为转换后的节点，文档中其他位置相同的
+    /// HTML 不受影响。
+    fn convert_structures(html: &str) -> String {
+        let document = kuchiki::parse_html().one(html);
+        let mut changed = false;
+
+        for pre in select_all(&document, FORMAL_SYNTAX_SELECTOR) {
+            replace_with_html(pre.as_node(), &Self::syntax_block(pre.as_node()));
+            changed = true;
+        }
+        for table in select_all(&document, DEFINITION_TABLE_SELECTOR) {
+            replace_with_html(table.as_node(), &Self::definition_table(table.as_node()));
+            changed = true;
+        }
+
+        if changed {
+            serialize_html(html, &document)
+        } else {
+            html.to_string()
+        }
+    }
+}
+
+/// 文档中匹配选择器的元素，先全部取出，替换元素时不影响遍历
+fn select_all(node: &NodeRef, selectors: &str) -> Vec<NodeDataRef<ElementData>> {
+    node.select(selectors).map(|elements| elements.collect()).unwrap_or_default()
+}
+
+/// 用一段 HTML 解析出的节点替换元素
+fn replace_with_html(node: &NodeRef, html: &str) {
+    let fragment = kuchiki::parse_html().one(format!("<body>{}</body>", html));
+    if let Ok(body) = fragment.select_first("body") {
+        for child in body.as_node().children().collect::<Vec<_>>() {
+            node.insert_before(child);
+        }
+    }
+    node.detach();
+}
+
+/// 规范化定义表的表头文字：合并空白并去掉末尾的冒号
+pub fn definition_header(cell: ElementRef) -> String {
+    normalize_header(&cell.text().collect::<String>())
+}
+
+/// 合并空白并去掉末尾的冒号
+fn normalize_header(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    text.trim_end_matches(':').trim_end().to_string()
+}
+
+impl Filter for CssCleanHtmlFilter {
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+        let document = Html::parse_document(html);
+        let mut warnings = Vec::new();
+
+        // 只保留主文档内容
+        let container = Selector::parse(CONTAINER_SELECTOR).unwrap();
+        let html = match document.select(&container).next() {
+            Some(main_content) => main_content.html(),
+            None => {
+                warnings.push(
+                    self.warning("missing container")
+                        .with_selector(CONTAINER_SELECTOR),
+                );
+                document.html()
+            }
+        };
+
+        let html = self.base_filter.remove_elements(&html, REMOVED_ELEMENTS);
+
+        // 在去掉类名之前转换依赖类名的结构
+        let html = Self::convert_structures(&html);
+
+        // 移除class和style属性，保留 `_syntax` 和 `_definition`
+        let html = self
+            .base_filter
+            .remove_attributes(&html, &["class", "style"]);
+
+        Ok(FilterOutput::new(html).with_warnings(warnings))
+    }
+
+    fn box_clone(&self) -> Box<dyn Filter> {
+        Box::new(Self::new())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean_fixture(name: &str) -> String {
+        let html = std::fs::read_to_string(format!("test_docs/css_mdn/{}.html", name)).unwrap();
+        let context = FilterContext::new().with_html(&html);
+        let output = CssCleanHtmlFilter::new().apply_v2(&html, &context).unwrap();
+        assert!(output.warnings.is_empty());
+        output.html
+    }
+
+    fn select_one(html: &str, selector: &str) -> String {
+        let document = Html::parse_fragment(html);
+        let selector = Selector::parse(selector).unwrap();
+        let mut matches = document.select(&selector);
+        let element = matches.next().expect("selector should match one element");
+        assert!(matches.next().is_none());
+        element.html()
+    }
+
+    fn headers(html: &str) -> Vec<String> {
+        let document = Html::parse_fragment(html);
+        let selector = Selector::parse("table._definition th").unwrap();
+        document
+            .select(&selector)
+            .map(|th| th.text().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_margin_structures_survive_cleaning() {
+        let cleaned = clean_fixture("margin");
+
+        let syntax = select_one(&cleaned, "pre._syntax");
+        let expected = [
+            "margin =",
+            "  &lt;'margin-top'&gt;{1,4}",
+            "",
+            "&lt;margin-top&gt; =",
+            "  &lt;length-percentage&gt;  |",
+            "  auto                  |",
+            "  &lt;anchor-size()&gt;",
+        ]
+        .join("\n");
+        assert_eq!(syntax, format!("<pre class=\"_syntax\">{}</pre>", expected));
+
+        let table = select_one(&cleaned, "table._definition");
+        assert_eq!(
+            headers(&table),
+            ["Initial value", "Applies to", "Inherited", "Percentages", "Computed value", "Animation type"]
+        );
+        assert!(table.contains("<th>Inherited</th><td>no</td>"));
+        assert!(table.contains("<li><a href=\"/en-US/docs/Web/CSS/margin-bottom\"><code>margin-bottom</code></a>: <code>0</code></li>"));
+        assert!(!table.contains("scope="));
+
+        // 其余类名被去掉，普通代码块不受影响
+        assert!(!cleaned.contains("css-formal-syntax"));
+        assert!(!cleaned.contains("class=\"properties\""));
+        assert!(!cleaned.contains("main-page-content"));
+        assert!(cleaned.contains("margin: 5% auto;"));
+        assert!(!cleaned.contains("MDN footer"));
+    }
+
+    #[test]
+    fn test_color_structures_survive_cleaning() {
+        let cleaned = clean_fixture("color");
+
+        assert_eq!(
+            select_one(&cleaned, "pre._syntax"),
+            "<pre class=\"_syntax\">color =\n  &lt;color&gt;</pre>"
+        );
+        let table = select_one(&cleaned, "table._definition");
+        assert_eq!(
+            headers(&table),
+            ["Initial value", "Applies to", "Inherited", "Computed value", "Animation type"]
+        );
+        assert!(table.contains("<th>Initial value</th><td><code>canvastext</code></td>"));
+        assert!(table.contains("<th>Inherited</th><td>yes</td>"));
+    }
+
+    #[test]
+    fn test_mdn_chrome_is_removed() {
+        for name in ["hover", "media", "calc", "color_value", "Attribute_selectors"] {
+            let cleaned = clean_fixture(name);
+            assert!(cleaned.contains("<h1>"), "{}", name);
+            assert!(cleaned.contains("class=\"_syntax\""), "{}", name);
+            for chrome in ["Toggle sidebar", "last modified", "Loading…", "MDN Web Docs", "MDN footer"] {
+                assert!(!cleaned.contains(chrome), "{} contains {}", name, chrome);
+            }
+        }
+    }
+
+    #[test]
+    fn test_legacy_syntaxbox_and_missing_container() {
+        let html = r#"<html><body><pre class="syntaxbox">a &lt;b&gt;<br>  | c   </pre><table class="properties"><tr><th>Inherited:</th><td>yes</td></tr></table></body></html>"#;
+        let context = FilterContext::new().with_html(html);
+        let output = CssCleanHtmlFilter::new().apply_v2(html, &context).unwrap();
+
+        assert!(output.html.contains("<pre class=\"_syntax\">a &lt;b&gt;\n  | c</pre>"));
+        assert!(output.html.contains("<table class=\"_definition\"><tbody><tr><th>Inherited</th><td>yes</td></tr></tbody></table>"));
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].message, "missing container");
+    }
+}
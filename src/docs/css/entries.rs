@@ -0,0 +1,220 @@
+//! CSS文档条目过滤器
+//!
+//! 页面条目的类型根据标题、页面路径和定义表确定：`:hover` 为伪类，`@media`
+//! 为 at-rule，`calc()` 为函数，`<color>`（路径 `color_value`）为数据类型，
+//! 有定义表的页面为属性。标题缺失时由路径推断名称。定义表中的“Inherited”
+//! 一行用于标记属性是否继承（写入条目的 `inherited`）。
+
+use crate::core::error::Result;
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use crate::docs::css::clean::definition_header;
+use crate::core::filters::selector;
+use scraper::{ElementRef, Html};
+use std::any::Any;
+
+/// 定义表，清理前后的类名都可以识别
+const DEFINITION_SELECTOR: &str = "table._definition, table.properties";
+
+/// 数据类型页面路径的后缀，如 `color_value` 对应 `<color>`
+const DATA_TYPE_SUFFIX: &str = "_value";
+
+/// 选择器页面名称的结尾（小写）
+const SELECTOR_SUFFIXES: &[&str] = &["selector", "selectors", "combinator", "selector list"];
+
+/// CSS 文档条目过滤器
+#[derive(Default)]
+pub struct CssEntriesFilter;
+
+impl CssEntriesFilter {
+    /// 创建新的条目过滤器
+    pub fn new() -> Self {
+        CssEntriesFilter
+    }
+
+    /// 从页面标题获取条目名称，没有标题时由路径的最后一段推断
+    fn get_name(&self, doc: &Html, path: &str) -> String {
+        let title = doc
+            .select(&selector("h1"))
+            .next()
+            .map(|h1| h1.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+        if !title.is_empty() {
+            return title;
+        }
+
+        let slug = last_segment(path);
+        match slug.strip_suffix(DATA_TYPE_SUFFIX) {
+            Some(data_type) if !data_type.is_empty() => format!("<{}>", data_type),
+            _ => slug.replace('_', " "),
+        }
+    }
+
+    /// 根据名称、页面路径和定义表确定条目类型
+    fn get_type(&self, doc: &Html, name: &str, path: &str) -> String {
+        let slug = last_segment(path);
+        let lower_name = name.to_lowercase();
+        let entry_type = if name.starts_with("::") || slug.starts_with("::") {
+            "Pseudo-elements"
+        } else if name.starts_with(':') || slug.starts_with(':') {
+            "Pseudo-classes"
+        } else if name.starts_with('@') || slug.starts_with('@') {
+            "At-rules"
+        } else if name.ends_with("()") || slug.ends_with("()") {
+            "Functions"
+        } else if name.starts_with('<') || slug.ends_with(DATA_TYPE_SUFFIX) {
+            "Data types"
+        } else if SELECTOR_SUFFIXES.iter().any(|suffix| lower_name.ends_with(suffix)) {
+            "Selectors"
+        } else if definition_table(doc).is_some() {
+            "Properties"
+        } else {
+            "Miscellaneous"
+        };
+        entry_type.to_string()
+    }
+
+    /// 从定义表中读取属性是否继承，没有定义表或无法识别时返回 None
+    pub fn inherited(&self, doc: &Html) -> Option<bool> {
+        let table = definition_table(doc)?;
+        for row in table.select(&selector("tr")) {
+            let mut cells = row.children().filter_map(ElementRef::wrap);
+            let Some(header) = cells.find(|cell| cell.value().name() == "th") else {
+                continue;
+            };
+            if !definition_header(header).eq_ignore_ascii_case("inherited") {
+                continue;
+            }
+            let value = cells.find(|cell| cell.value().name() == "td")?;
+            let value = value.text().collect::<String>().trim().to_lowercase();
+            return if value.starts_with("yes") {
+                Some(true)
+            } else if value.starts_with("no") {
+                Some(false)
+            } else {
+                None
+            };
+        }
+        None
+    }
+}
+
+/// 页面路径的最后一段
+fn last_segment(path: &str) -> &str {
+    path.trim_matches('/').rsplit('/').next().unwrap_or_default()
+}
+
+/// 查找页面中的定义表
+fn definition_table(doc: &Html) -> Option<ElementRef<'_>> {
+    doc.select(&selector(DEFINITION_SELECTOR)).next()
+}
+
+impl Filter for CssEntriesFilter {
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        // 条目过滤器不修改HTML内容，只记录属性是否继承
+        Ok(FilterOutput::new(html).with_inherited(self.inherited(context.html_doc())))
+    }
+
+    fn box_clone(&self) -> Box<dyn Filter> {
+        Box::new(Self::new())
+    }
+
+    fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+        let doc = context.html_doc();
+        let name = self.get_name(doc, &context.current_path);
+        if name.is_empty() {
+            return Vec::new();
+        }
+        let entry_type = self.get_type(doc, &name, &context.current_path);
+        vec![(name, context.current_path.clone(), entry_type)]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::css::CssCleanHtmlFilter;
+
+    fn cleaned_context(name: &str) -> FilterContext {
+        let html = std::fs::read_to_string(format!("test_docs/css_mdn/{}.html", name)).unwrap();
+        let mut context = FilterContext::new().with_html(&html);
+        context.current_path = name.to_string();
+        let cleaned = CssCleanHtmlFilter::new().apply_v2(&html, &context).unwrap();
+        context.set_html(cleaned.html);
+        context
+    }
+
+    #[test]
+    fn test_inherited_from_definition_table() {
+        let filter = CssEntriesFilter::new();
+        for (name, inherited) in [("margin", false), ("color", true)] {
+            let context = cleaned_context(name);
+            let output = filter.apply_v2(context.html(), &context).unwrap();
+            assert_eq!(output.inherited, Some(inherited), "{}", name);
+            assert_eq!(
+                filter.get_entries(&context),
+                vec![(name.to_string(), name.to_string(), "Properties".to_string())]
+            );
+        }
+
+        // 清理前的定义表也可以识别
+        let raw = Html::parse_document(&std::fs::read_to_string("test_docs/css_mdn/color.html").unwrap());
+        assert_eq!(filter.inherited(&raw), Some(true));
+    }
+
+    #[test]
+    fn test_entry_categories_from_fixtures() {
+        let filter = CssEntriesFilter::new();
+        for (fixture, name, entry_type) in [
+            ("hover", ":hover", "Pseudo-classes"),
+            ("media", "@media", "At-rules"),
+            ("calc", "calc()", "Functions"),
+            ("color_value", "<color>", "Data types"),
+            ("Attribute_selectors", "Attribute selectors", "Selectors"),
+            ("margin", "margin", "Properties"),
+        ] {
+            let context = cleaned_context(fixture);
+            assert_eq!(
+                filter.get_entries(&context),
+                vec![(name.to_string(), fixture.to_string(), entry_type.to_string())],
+                "{}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn test_entry_from_path_without_heading() {
+        let filter = CssEntriesFilter::new();
+        for (path, name, entry_type) in [
+            (":focus-visible", ":focus-visible", "Pseudo-classes"),
+            ("@font-face", "@font-face", "At-rules"),
+            ("transform-function/rotate()", "rotate()", "Functions"),
+            ("length_value", "<length>", "Data types"),
+        ] {
+            let mut context = FilterContext::new().with_html("<p>No heading.</p>");
+            context.current_path = path.to_string();
+            assert_eq!(
+                filter.get_entries(&context),
+                vec![(name.to_string(), path.to_string(), entry_type.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_pages_without_definition_table() {
+        let filter = CssEntriesFilter::new();
+        let context = FilterContext::new().with_html("<h1>::before</h1><p>Pseudo-element.</p>");
+        let output = filter.apply_v2(context.html(), &context).unwrap();
+
+        assert_eq!(output.inherited, None);
+        assert_eq!(filter.get_entries(&context)[0].2, "Pseudo-elements");
+    }
+}
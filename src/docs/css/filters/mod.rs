@@ -1,3 +0,0 @@
-//! CSS过滤器模块
-
-// 在此处添加特定CSS过滤器模块导出
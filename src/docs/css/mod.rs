@@ -2,6 +2,10 @@
 //!
 //! 包含 CSS 文档的抓取器和过滤器实现
 
+pub mod clean;
+pub mod entries;
 mod scraper;
 
+pub use clean::CssCleanHtmlFilter;
+pub use entries::CssEntriesFilter;
 pub use scraper::CssScraper;
@@ -4,10 +4,27 @@
 //! 参考文件: lib/docs/scrapers/mdn/css.rb
 
 use crate::core::error::Result;
-use crate::core::filters::{HtmlCleanerFilter, UrlNormalizerFilter};
+use crate::core::instrumentable;
+use crate::core::scraper::url_scraper::START_SCRAPER_EVENT;
+use crate::core::filters::{AttributionFilter, HtmlCleanerFilter, UrlNormalizerFilter};
 use crate::core::scraper::{Scraper as CoreScraper, UrlScraper};
+use crate::docs::css::{CssCleanHtmlFilter, CssEntriesFilter};
 use async_trait::async_trait;
 
+const LINKS: [(&str, &str); 2] = [
+    ("home", "https://developer.mozilla.org/en-US/docs/Web/CSS"),
+    ("code", "https://github.com/mdn/content/tree/main/files/en-us/web/css"),
+];
+
+const ROOT_TITLE: &str = "CSS";
+const ATTRIBUTION: &str = "© 2005–2023 MDN contributors.\nLicensed under the Creative Commons Attribution-ShareAlike License v2.5 or later.";
+
+/// 跳过的路径（已废弃的旧版页面）
+const SKIP_PATHS: &[&str] = &["CSS3", "Media/Visual", "Media/TV", "Media/Tactile", "paged_media"];
+
+/// 跳过的模式（浏览器私有扩展和工具页面）
+const SKIP_PATTERNS: &[&str] = &[r"(^|/)-(moz|webkit|ms)-", r"(?i)_Extensions(/|$)", r"(^|/)Tools(/|$)"];
+
 /// CSS文档爬虫
 pub struct CssScraper {
     /// 基础爬虫
@@ -18,7 +35,16 @@ impl CssScraper {
     /// 创建新的CSS文档爬虫
     pub fn new(version: &str, output_path: &str) -> Self {
         let base_url = "https://developer.mozilla.org/en-US/docs/Web/CSS";
-        let mut scraper = UrlScraper::new("CSS", version, base_url, output_path);
+        let mut scraper = UrlScraper::new("CSS", version, base_url, output_path)
+            .with_root_title(ROOT_TITLE)
+            .with_attribution(ATTRIBUTION)
+            .with_license(crate::docs::MDN_LICENSE)
+            .with_string_links(
+                LINKS
+                    .iter()
+                    .map(|&(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
 
         // 添加初始路径
         let initial_paths = vec![
@@ -37,9 +63,15 @@ impl CssScraper {
 
         // 添加过滤器和初始路径
         scraper = scraper
+            .with_rate_limit(crate::docs::MDN_RATE_LIMIT)
             .with_initial_paths(initial_paths)
+            .with_skip_paths(SKIP_PATHS.iter().map(|p| p.to_string()).collect())
+            .with_skip_patterns(SKIP_PATTERNS.to_vec())
             .with_filter(html_cleaner)
-            .with_filter(url_normalizer);
+            .with_filter(Box::new(CssCleanHtmlFilter::new()))
+            .with_filter(Box::new(CssEntriesFilter::new()))
+            .with_filter(url_normalizer)
+            .with_filter(Box::new(AttributionFilter::new()));
 
         Self { scraper }
     }
@@ -56,7 +88,35 @@ impl CoreScraper for CssScraper {
     }
 
     async fn run(&mut self) -> Result<()> {
-        println!("开始抓取CSS文档...");
+        instrumentable::message(START_SCRAPER_EVENT, "开始抓取CSS文档...");
         self.scraper.run().await
     }
+
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(&mut self.scraper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::scraper::SkipReason;
+
+    #[test]
+    fn test_obsolete_pages_are_skipped() {
+        let mut scraper = CssScraper::new("", "");
+        let scraper = scraper.url_scraper_mut().unwrap();
+        let url = |path: &str| format!("https://developer.mozilla.org/en-US/docs/Web/CSS/{}", path);
+
+        for path in ["color", ":hover", "@media", "calc", "Reference", "Attribute_selectors"] {
+            assert_eq!(scraper.skip_reason(&url(path)), None, "{}", path);
+        }
+        assert_eq!(scraper.skip_reason(&url("CSS3")), Some(SkipReason::SkipPath));
+        assert_eq!(scraper.skip_reason(&url("Media/TV")), Some(SkipReason::SkipPath));
+        for path in ["-moz-orient", "-webkit-line-clamp", "Mozilla_Extensions", "WebKit_Extensions", "Tools/Linear-gradient_Generator"] {
+            assert_eq!(scraper.skip_reason(&url(path)), Some(SkipReason::SkipPattern), "{}", path);
+        }
+        assert_eq!(scraper.attribution, ATTRIBUTION);
+        assert_eq!(scraper.license.as_deref(), Some("CC-BY-SA-2.5"));
+    }
 }
@@ -1,6 +1,23 @@
 //! 提供单个文档的结构
 
-use crate::core::types::{ModifiedTime, Release, Size, Slug, Version};
+use crate::core::doc::{DB_FILENAME, PAGES_FILENAME};
+use crate::core::index_entry::{IndexEntry, IndexType};
+use crate::core::page_db::StoredPage;
+use crate::core::types::{DocSlug, ModifiedTime, PageKey, Release, Size, Slug, Version};
+use crate::storage::{json, page_paths};
+use crate::web::page_cache::PageCache;
+use std::path::PathBuf;
+
+/// 未标注语言的文档使用的默认语言
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// 缓存中区分文档的键，`doc_dir` 为规范的文档目录名，见 [`Documentation::cache_key`]
+pub fn cache_key(namespace: Option<&str>, doc_dir: &str) -> String {
+    match namespace {
+        Some(namespace) => format!("t/{}/{}", namespace, doc_dir),
+        None => doc_dir.to_string(),
+    }
+}
 
 /// 表示单个文档
 pub struct Documentation {
@@ -18,6 +35,36 @@ pub struct Documentation {
     pub db_size: Size,
     /// 索引大小
     pub index_size: Size,
+    /// 文档所在的根目录
+    pub root: PathBuf,
+    /// 是否来自只读的叠加目录
+    pub read_only: bool,
+    /// 是否带有图标
+    pub has_icon: bool,
+    /// 索引条目
+    pub entries: Vec<IndexEntry>,
+    /// 索引中的类型，保留抓取器提供的排序权重和说明
+    pub types: Vec<IndexType>,
+    /// 是否禁止搜索引擎索引（None 表示使用全局设置）
+    pub noindex: Option<bool>,
+    /// 默认落地页面路径（None 表示显示类型列表）
+    pub landing_path: Option<String>,
+    /// 文档语言（None 表示默认语言），对应目录名中的 `@<locale>` 后缀
+    pub locale: Option<String>,
+    /// 磁盘上的目录名不是规范写法（如 `Babel~7`）时的实际目录名
+    pub dir_name: Option<String>,
+    /// 抓取时根据警告计算的质量分数（0 到 100），旧的文档没有
+    pub quality: Option<f64>,
+    /// 所属的命名空间（None 表示全局命名空间），对应 `/t/<namespace>` 下的路由
+    pub namespace: Option<String>,
+    /// 加载时无法读取的文件（如无法解析的 index.json、meta.json）的错误说明
+    pub load_errors: Vec<String>,
+    /// 文档类型（meta.json 中的 `type`，即生成文档的抓取器类型），旧的文档没有
+    pub doc_type: Option<String>,
+    /// 版权和许可说明（meta.json 中的 `attribution`）
+    pub attribution: Option<String>,
+    /// 内容的许可证（meta.json 中的 `license`，已知时为 SPDX 标识），旧的文档没有
+    pub license: Option<String>,
 }
 
 impl Documentation {
@@ -31,18 +78,206 @@ impl Documentation {
             mtime: 0,
             db_size: 0,
             index_size: 0,
+            root: PathBuf::new(),
+            read_only: false,
+            has_icon: false,
+            entries: Vec::new(),
+            types: Vec::new(),
+            noindex: None,
+            landing_path: None,
+            locale: None,
+            dir_name: None,
+            quality: None,
+            namespace: None,
+            load_errors: Vec::new(),
+            doc_type: None,
+            attribution: None,
+            license: None,
         }
     }
 
     /// 获取文档的完整路径
     pub fn path(&self) -> String {
-        if self.version.is_empty() {
+        let path = if self.version.is_empty() {
             self.slug.clone()
         } else {
             format!("{}~{}", self.slug, self.version)
+        };
+        match &self.locale {
+            Some(locale) => format!("{}@{}", path, locale),
+            None => path,
         }
     }
 
+    /// 获取文档语言，未标注时为默认语言
+    pub fn locale_or_default(&self) -> &str {
+        self.locale.as_deref().unwrap_or(DEFAULT_LOCALE)
+    }
+
+    /// 设置文档语言
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        self.locale = if locale.is_empty() { None } else { Some(locale.to_string()) };
+        self
+    }
+
+    /// 获取文档在磁盘上的目录
+    pub fn dir(&self) -> PathBuf {
+        match &self.dir_name {
+            Some(dir_name) => self.root.join(dir_name),
+            None => self.root.join(self.path()),
+        }
+    }
+
+    /// 设置磁盘上的目录名，与 [`Documentation::path`] 相同时不记录
+    pub fn with_dir_name(mut self, dir_name: &str) -> Self {
+        self.dir_name = (dir_name != self.path()).then(|| dir_name.to_string());
+        self
+    }
+
+    /// 读取文档目录中的文件
+    pub fn read_file(&self, name: &str) -> std::io::Result<String> {
+        std::fs::read_to_string(self.dir().join(name))
+    }
+
+    /// 获取页面文件在磁盘上的位置
+    ///
+    /// 页面目录中的 `index.html` 优先，其次为 `<page>.html`。与保留名称冲突的
+    /// 页面位于 `_p/` 下。
+    pub fn page_file(&self, page_path: &str) -> PathBuf {
+        page_paths::page_file(&self.dir(), page_path)
+    }
+
+    /// 从 db.json 中读取页面，支持 v1 和 v2 格式
+    ///
+    /// db.json 通过页面缓存读取，只在文件修改后重新解析；无法读取时记录警告并返回 None。
+    pub fn stored_page(&self, page_cache: &PageCache, page_path: &PageKey) -> Option<StoredPage> {
+        let db = match page_cache.get_or_load_db(&self.cache_key(), &self.dir().join(DB_FILENAME)) {
+            Ok(db) => db?,
+            Err(e) => {
+                log::warn!("无法读取文档 {} 的 db.json: {}", self.path(), e);
+                return None;
+            }
+        };
+        db.page(page_path).cloned()
+    }
+
+    /// 文档中是否有该页面，带 `.html` 后缀和不带后缀的路径视为同一页面
+    ///
+    /// 只查找 pages.json 中的页面列表和页面文件，不读取 db.json。没有
+    /// pages.json 的旧文档只能找到保存为页面文件的页面。
+    pub fn has_page(&self, page_path: &PageKey) -> bool {
+        let path = page_path.as_str();
+        let bare = path.strip_suffix(".html").unwrap_or(path);
+        let candidates = [bare.to_string(), format!("{}.html", bare)];
+        if candidates.iter().any(|candidate| self.page_file(candidate).is_file()) {
+            return true;
+        }
+        json::read_json::<Vec<String>>(&self.dir().join(PAGES_FILENAME))
+            .is_ok_and(|pages| pages.iter().any(|page| candidates.contains(page)))
+    }
+
+    /// 设置文档所在的根目录
+    pub fn with_root<P: Into<PathBuf>>(mut self, root: P, read_only: bool) -> Self {
+        self.root = root.into();
+        self.read_only = read_only;
+        self
+    }
+
+    /// 设置索引条目
+    pub fn with_entries(mut self, entries: Vec<IndexEntry>) -> Self {
+        self.entries = entries;
+        self
+    }
+
+    /// 设置索引中的类型
+    pub fn with_types(mut self, types: Vec<IndexType>) -> Self {
+        self.types = types;
+        self
+    }
+
+    /// 设置是否禁止搜索引擎索引，覆盖全局设置
+    pub fn with_noindex(mut self, noindex: bool) -> Self {
+        self.noindex = Some(noindex);
+        self
+    }
+
+    /// 设置默认落地页面路径
+    pub fn with_landing_path(mut self, path: &str) -> Self {
+        let path = path.trim_matches('/');
+        self.landing_path = if path.is_empty() { None } else { Some(path.to_string()) };
+        self
+    }
+
+    /// 获取文档落地页的URL
+    ///
+    /// 设置了落地页面时指向该页面，否则指向文档首页（类型列表）。
+    pub fn landing_url(&self) -> String {
+        match &self.landing_path {
+            Some(path) => format!("{}/{}/{}", self.docs_prefix(), self.route_slug(), path),
+            None => format!("{}/{}", self.docs_prefix(), self.route_slug()),
+        }
+    }
+
+    /// 设置所属的命名空间
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// 文档路由的前缀，命名空间中的文档为 `/t/<namespace>/docs`
+    pub fn docs_prefix(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("/t/{}/docs", namespace),
+            None => "/docs".to_string(),
+        }
+    }
+
+    /// 缓存中区分文档的键，命名空间中的文档带有命名空间，不会与同名的全局文档混淆
+    pub fn cache_key(&self) -> String {
+        cache_key(self.namespace.as_deref(), &self.path())
+    }
+
+    /// 文档在 `/docs/` 下的标识，带有版本时为 `<slug>~<version>`，不含语言
+    pub fn route_slug(&self) -> String {
+        DocSlug::new(&self.slug).dir_name(&self.version)
+    }
+
+    /// 设置是否带有图标
+    pub fn with_icon(mut self, has_icon: bool) -> Self {
+        self.has_icon = has_icon;
+        self
+    }
+
+    /// 设置质量分数
+    pub fn with_quality(mut self, score: f64) -> Self {
+        self.quality = Some(score);
+        self
+    }
+
+    /// 记录加载时的错误
+    pub fn with_load_error(mut self, error: impl ToString) -> Self {
+        self.load_errors.push(error.to_string());
+        self
+    }
+
+    /// 设置文档类型
+    pub fn with_doc_type(mut self, doc_type: &str) -> Self {
+        self.doc_type = Some(doc_type.to_string()).filter(|t| !t.is_empty());
+        self
+    }
+
+    /// 设置版权和许可说明
+    pub fn with_attribution(mut self, attribution: &str) -> Self {
+        self.attribution = Some(attribution.to_string()).filter(|a| !a.trim().is_empty());
+        self
+    }
+
+    /// 设置内容的许可证
+    pub fn with_license(mut self, license: &str) -> Self {
+        self.license = Some(license.trim().to_string()).filter(|l| !l.is_empty());
+        self
+    }
+
     /// 设置发布版本
     pub fn with_release(mut self, release: &str) -> Self {
         self.release = release.to_string();
@@ -67,6 +302,21 @@ impl Documentation {
         self
     }
 
+    /// 索引条目和类型在内存中大约占用的字节数
+    pub fn index_memory(&self) -> usize {
+        let entries: usize = self
+            .entries
+            .iter()
+            .map(|entry| std::mem::size_of::<IndexEntry>() + entry.name.len() + entry.path.len() + entry.entry_type.len())
+            .sum();
+        let types: usize = self
+            .types
+            .iter()
+            .map(|index_type| std::mem::size_of::<IndexType>() + index_type.name.len() + index_type.slug.len())
+            .sum();
+        entries + types
+    }
+
     /// 获取完整名称（包含版本）
     pub fn full_name(&self) -> String {
         if self.version.is_empty() {
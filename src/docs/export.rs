@@ -0,0 +1,1342 @@
+//! 文档导出
+//!
+//! 将已安装的文档导出为 EPUB 或静态 HTML 站点，供离线阅读。
+//!
+//! EPUB 供电子阅读器使用，每种条目类型为一章，
+//! 章内页面按索引顺序排列。页面 HTML 经过简化（去掉脚本、表单等交互元素）
+//! 后转换为 XHTML，文档内链接改写为章节锚点，data URI 和文档目录中的图片
+//! 作为文件嵌入。
+//!
+//! 文档目录中的 `custom.css` 作为样式表随书导出；`custom.js` 不导出，
+//! 页面中的脚本在简化时同样会被去掉。
+//!
+//! EPUB 容器（zip）由本模块直接写入，不依赖额外的库：`mimetype` 不压缩且
+//! 位于最前，其余文件使用 deflate 压缩。
+//!
+//! 静态站点不需要服务器，直接用 `file://` 打开即可浏览：每个页面一个 HTML 文件，
+//! 按页面路径分目录（文件名的规则与文档目录中的页面文件相同，见
+//! [`page_paths::escape_page_path`]），`index.html` 为可以在浏览器中过滤的条目
+//! 列表。文档内链接改写为相对的文件路径，页面原样保留，`ImagesFilter` 内联的
+//! data URI 图片不做任何改动，文档目录中的图片复制到站点中。
+
+use super::Documentation;
+use crate::core::doc::DB_FILENAME;
+use crate::core::error::{Error, Result};
+use crate::core::filters::markup::VOID_ELEMENTS;
+use crate::core::filters::serialize_html;
+use crate::core::page_db::PageDb;
+use crate::core::types::PageKey;
+use crate::storage::page_paths;
+use crate::web::custom_assets::CUSTOM_CSS;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use kuchiki::traits::TendrilSink;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Component, Path};
+use url::Url;
+
+/// EPUB 的 MIME 类型
+pub const EPUB_MIME_TYPE: &str = "application/epub+zip";
+
+/// 简化页面时去掉的元素（连同其内容）
+const STRIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "link", "meta", "noscript", "template", "iframe", "frame", "object", "embed",
+    "form", "button", "input", "select", "textarea", "canvas", "video", "audio", "svg", "math",
+];
+
+/// 简化页面时去掉的属性（`on*` 事件属性总是去掉）
+const STRIPPED_ATTRIBUTES: &[&str] = &["tabindex", "contenteditable", "draggable", "hidden"];
+
+/// 解析页面内相对链接时使用的虚拟地址
+const LINK_BASE: &str = "xwdoc://doc/";
+
+/// 静态站点的首页（条目列表）
+pub const SITE_INDEX: &str = "index.html";
+
+/// 静态站点首页中过滤条目列表的脚本
+const SITE_SEARCH_SCRIPT: &str = r#"var input = document.getElementById('search');
+var items = document.querySelectorAll('#entries li');
+input.addEventListener('input', function () {
+  var query = input.value.trim().toLowerCase();
+  for (var i = 0; i < items.length; i++) {
+    items[i].hidden = query !== '' && items[i].getAttribute('data-name').indexOf(query) === -1;
+  }
+});"#;
+
+/// EPUB 中的一章，对应一种条目类型
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    /// 章节标题（条目类型）
+    pub title: String,
+    /// 章节文件名
+    pub file: String,
+    /// 章节中的页面
+    pub pages: Vec<ChapterPage>,
+    /// 转换后的 XHTML 正文
+    pub body: String,
+}
+
+/// 章节中的页面
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapterPage {
+    /// 页面路径
+    pub path: String,
+    /// 页面标题（第一个指向该页面的条目名称）
+    pub title: String,
+    /// 页面在章节文件中的锚点
+    pub anchor: String,
+}
+
+/// EPUB 中嵌入的图片
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpubImage {
+    /// 图片文件名（相对于内容目录）
+    pub file: String,
+    /// 图片的 MIME 类型
+    pub media_type: String,
+    /// 图片内容
+    pub data: Vec<u8>,
+}
+
+/// 待写入的 EPUB
+#[derive(Debug, Clone, Default)]
+pub struct EpubBook {
+    /// 书名
+    pub title: String,
+    /// 唯一标识
+    pub identifier: String,
+    /// 语言
+    pub language: String,
+    /// 修改时间（`YYYY-MM-DDThh:mm:ssZ`）
+    pub modified: String,
+    /// 章节
+    pub chapters: Vec<Chapter>,
+    /// 嵌入的图片
+    pub images: Vec<EpubImage>,
+    /// 文档的自定义样式（`custom.css`），每个 XHTML 文件都会引用
+    pub stylesheet: Option<String>,
+}
+
+impl EpubBook {
+    /// 页面数量
+    pub fn page_count(&self) -> usize {
+        self.chapters.iter().map(|chapter| chapter.pages.len()).sum()
+    }
+}
+
+/// 根据文档生成 EPUB 内容
+///
+/// `types` 不为空时只导出这些类型（不区分大小写）的条目。
+pub fn build_book(doc: &Documentation, types: &[String]) -> Result<EpubBook> {
+    let stored = PageDb::load(&doc.dir().join(DB_FILENAME)).unwrap_or_default();
+
+    // 按条目类型分章，类型和页面都按在索引中第一次出现的顺序排列，每个页面只导出一次
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut targets: HashMap<String, (String, String)> = HashMap::new();
+    for entry in &doc.entries {
+        if !types.is_empty() && !types.iter().any(|t| t.eq_ignore_ascii_case(&entry.entry_type)) {
+            continue;
+        }
+        let path = page_path(&entry.path);
+        if targets.contains_key(path) {
+            continue;
+        }
+
+        let index = match chapters.iter().position(|c| c.title == entry.entry_type) {
+            Some(index) => index,
+            None => {
+                chapters.push(Chapter {
+                    title: entry.entry_type.clone(),
+                    file: format!("chapter-{}.xhtml", chapters.len() + 1),
+                    pages: Vec::new(),
+                    body: String::new(),
+                });
+                chapters.len() - 1
+            }
+        };
+        let chapter = &mut chapters[index];
+        let anchor = format!("p{}", targets.len() + 1);
+        targets.insert(path.to_string(), (chapter.file.clone(), anchor.clone()));
+        chapter.pages.push(ChapterPage {
+            path: path.to_string(),
+            title: entry.name.clone(),
+            anchor,
+        });
+    }
+
+    if chapters.is_empty() {
+        return Err(Error::Doc(format!("文档 {} 没有可导出的页面", doc.path())));
+    }
+
+    let mut writer = XhtmlWriter {
+        doc_dir: doc.dir(),
+        targets: &targets,
+        images: Vec::new(),
+        image_files: HashMap::new(),
+    };
+    for chapter in &mut chapters {
+        let mut body = format!("<h1>{}</h1>\n", escape(&chapter.title));
+        for page in &chapter.pages {
+            let html = match std::fs::read_to_string(doc.page_file(&page.path)) {
+                Ok(html) => html,
+                Err(_) => match stored.get(&PageKey::new(&page.path)) {
+                    Some(html) => html.clone(),
+                    None => {
+                        log::warn!("页面不存在，已跳过: {}", page.path);
+                        continue;
+                    }
+                },
+            };
+            let _ = writeln!(body, "<section id=\"{}\">", page.anchor);
+            body.push_str(&writer.convert_page(&html, &page.path, &page.anchor));
+            body.push_str("</section>\n");
+        }
+        chapter.body = body;
+    }
+
+    Ok(EpubBook {
+        title: doc.full_name(),
+        identifier: format!("urn:xwdoc:{}", doc.path()),
+        language: doc.locale_or_default().to_string(),
+        modified: chrono::DateTime::from_timestamp(doc.mtime as i64, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string(),
+        chapters,
+        images: writer.images,
+        stylesheet: std::fs::read_to_string(doc.dir().join(CUSTOM_CSS)).ok(),
+    })
+}
+
+/// 生成 OPF 包文件（content.opf）
+pub fn content_opf(book: &EpubBook) -> String {
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    manifest.push_str("    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n");
+    manifest.push_str("    <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n");
+    for (i, chapter) in book.chapters.iter().enumerate() {
+        let _ = writeln!(
+            manifest,
+            "    <item id=\"chapter-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+            i + 1,
+            chapter.file
+        );
+        let _ = writeln!(spine, "    <itemref idref=\"chapter-{}\"/>", i + 1);
+    }
+    if book.stylesheet.is_some() {
+        let _ = writeln!(manifest, "    <item id=\"style\" href=\"{}\" media-type=\"text/css\"/>", CUSTOM_CSS);
+    }
+    for (i, image) in book.images.iter().enumerate() {
+        let _ = writeln!(
+            manifest,
+            "    <item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>",
+            i + 1,
+            image.file,
+            image.media_type
+        );
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>{}</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine toc="ncx">
+{}  </spine>
+</package>
+"#,
+        escape(&book.identifier),
+        escape(&book.title),
+        escape(&book.language),
+        book.modified,
+        manifest,
+        spine
+    )
+}
+
+/// 生成 EPUB 3 导航文件（nav.xhtml）
+pub fn nav_xhtml(book: &EpubBook) -> String {
+    let mut items = String::new();
+    for chapter in &book.chapters {
+        let _ = writeln!(items, "      <li><a href=\"{}\">{}</a>", chapter.file, escape(&chapter.title));
+        items.push_str("        <ol>\n");
+        for page in &chapter.pages {
+            let _ = writeln!(
+                items,
+                "          <li><a href=\"{}#{}\">{}</a></li>",
+                chapter.file,
+                page.anchor,
+                escape(&page.title)
+            );
+        }
+        items.push_str("        </ol>\n      </li>\n");
+    }
+
+    xhtml_document(
+        book,
+        &book.title,
+        &format!(
+            "  <nav epub:type=\"toc\" id=\"toc\">\n    <h1>{}</h1>\n    <ol>\n{}    </ol>\n  </nav>\n",
+            escape(&book.title),
+            items
+        ),
+    )
+}
+
+/// 生成 EPUB 2 目录文件（toc.ncx），供旧阅读器使用
+pub fn toc_ncx(book: &EpubBook) -> String {
+    let mut points = String::new();
+    let mut order = 0;
+    for (i, chapter) in book.chapters.iter().enumerate() {
+        order += 1;
+        let _ = write!(
+            points,
+            "    <navPoint id=\"chapter-{}\" playOrder=\"{}\">\n      <navLabel><text>{}</text></navLabel>\n      <content src=\"{}\"/>\n",
+            i + 1,
+            order,
+            escape(&chapter.title),
+            chapter.file
+        );
+        for page in &chapter.pages {
+            order += 1;
+            let _ = write!(
+                points,
+                "      <navPoint id=\"{}\" playOrder=\"{}\">\n        <navLabel><text>{}</text></navLabel>\n        <content src=\"{}#{}\"/>\n      </navPoint>\n",
+                page.anchor,
+                order,
+                escape(&page.title),
+                chapter.file,
+                page.anchor
+            );
+        }
+        points.push_str("    </navPoint>\n");
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <head>
+    <meta name="dtb:uid" content="{}"/>
+  </head>
+  <docTitle><text>{}</text></docTitle>
+  <navMap>
+{}  </navMap>
+</ncx>
+"#,
+        escape(&book.identifier),
+        escape(&book.title),
+        points
+    )
+}
+
+/// 将 EPUB 写入文件
+pub fn write_epub(book: &EpubBook, output: &Path) -> Result<()> {
+    let mut zip = ZipWriter::default();
+    zip.add("mimetype", EPUB_MIME_TYPE.as_bytes(), false)?;
+    zip.add(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#,
+        true,
+    )?;
+    zip.add("OEBPS/content.opf", content_opf(book).as_bytes(), true)?;
+    zip.add("OEBPS/nav.xhtml", nav_xhtml(book).as_bytes(), true)?;
+    zip.add("OEBPS/toc.ncx", toc_ncx(book).as_bytes(), true)?;
+    for chapter in &book.chapters {
+        let xhtml = xhtml_document(book, &chapter.title, &chapter.body);
+        zip.add(&format!("OEBPS/{}", chapter.file), xhtml.as_bytes(), true)?;
+    }
+    for image in &book.images {
+        zip.add(&format!("OEBPS/{}", image.file), &image.data, false)?;
+    }
+    if let Some(stylesheet) = &book.stylesheet {
+        zip.add(&format!("OEBPS/{}", CUSTOM_CSS), stylesheet.as_bytes(), true)?;
+    }
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output, zip.finish()?)?;
+    Ok(())
+}
+
+/// 去掉条目路径中的锚点
+fn page_path(path: &str) -> &str {
+    path.split('#').next().unwrap_or(path).trim_matches('/')
+}
+
+/// 生成完整的 XHTML 文件
+fn xhtml_document(book: &EpubBook, title: &str, body: &str) -> String {
+    let stylesheet = match book.stylesheet {
+        Some(_) => format!("\n  <link rel=\"stylesheet\" type=\"text/css\" href=\"{}\"/>", CUSTOM_CSS),
+        None => String::new(),
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{0}" lang="{0}">
+<head>
+  <meta charset="UTF-8"/>
+  <title>{1}</title>{3}
+</head>
+<body>
+{2}</body>
+</html>
+"#,
+        escape(&book.language),
+        escape(title),
+        body,
+        stylesheet
+    )
+}
+
+/// 转义 XML 文本和属性值
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // XML 1.0 不允许的控制字符
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 是否为合法的 XML 名称（只接受 HTML 中常见的形式）
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// 将页面中的 id 转换为章节内唯一的锚点
+fn page_anchor(page_anchor: &str, id: &str) -> String {
+    let id: String = id
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    format!("{}-{}", page_anchor, id)
+}
+
+/// 根据文件扩展名或 data URI 中的类型获取 EPUB 支持的图片类型
+fn image_media_type(kind: &str) -> Option<(&'static str, &'static str)> {
+    match kind.to_ascii_lowercase().as_str() {
+        "png" | "image/png" => Some(("image/png", "png")),
+        "jpg" | "jpeg" | "image/jpeg" => Some(("image/jpeg", "jpg")),
+        "gif" | "image/gif" => Some(("image/gif", "gif")),
+        "svg" | "image/svg+xml" => Some(("image/svg+xml", "svg")),
+        "webp" | "image/webp" => Some(("image/webp", "webp")),
+        _ => None,
+    }
+}
+
+/// 将页面 HTML 转换为简化的 XHTML
+struct XhtmlWriter<'a> {
+    /// 文档目录，用于读取相对路径的图片
+    doc_dir: std::path::PathBuf,
+    /// 页面路径到章节文件和锚点的映射
+    targets: &'a HashMap<String, (String, String)>,
+    /// 已嵌入的图片
+    images: Vec<EpubImage>,
+    /// 图片来源到文件名的映射，避免重复嵌入
+    image_files: HashMap<String, String>,
+}
+
+impl XhtmlWriter<'_> {
+    /// 转换单个页面
+    fn convert_page(&mut self, html: &str, path: &str, anchor: &str) -> String {
+        let document = Html::parse_document(html);
+        let body = Selector::parse("body")
+            .ok()
+            .and_then(|selector| document.select(&selector).next())
+            .unwrap_or_else(|| document.root_element());
+
+        let base = Url::parse(LINK_BASE).and_then(|base| base.join(path)).ok();
+        let mut out = String::new();
+        self.write_children(body, base.as_ref(), anchor, &mut out);
+        out
+    }
+
+    fn write_children(&mut self, element: ElementRef, base: Option<&Url>, anchor: &str, out: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&escape(text)),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.write_element(child, base, anchor, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_element(&mut self, element: ElementRef, base: Option<&Url>, anchor: &str, out: &mut String) {
+        let name = element.value().name();
+        if STRIPPED_ELEMENTS.contains(&name) {
+            return;
+        }
+        if !is_xml_name(name) {
+            self.write_children(element, base, anchor, out);
+            return;
+        }
+
+        let mut attributes = String::new();
+        for (attr, value) in element.value().attrs() {
+            if attr.starts_with("on") || STRIPPED_ATTRIBUTES.contains(&attr) || !is_xml_name(attr) {
+                continue;
+            }
+            let value = match (name, attr) {
+                (_, "id") => Some(page_anchor(anchor, value)),
+                ("a", "href") => self.rewrite_link(value, base, anchor),
+                ("img", "src") => match self.embed_image(value, base) {
+                    Some(file) => Some(file),
+                    None => {
+                        // 无法嵌入的图片使用替代文本
+                        out.push_str(&escape(element.value().attr("alt").unwrap_or("")));
+                        return;
+                    }
+                },
+                ("img", "srcset") | ("source", "srcset") => None,
+                _ => Some(value.to_string()),
+            };
+            if let Some(value) = value {
+                let _ = write!(attributes, " {}=\"{}\"", attr, escape(&value));
+            }
+        }
+
+        if VOID_ELEMENTS.contains(&name) {
+            let _ = write!(out, "<{}{}/>", name, attributes);
+        } else {
+            let _ = write!(out, "<{}{}>", name, attributes);
+            self.write_children(element, base, anchor, out);
+            let _ = write!(out, "</{}>", name);
+        }
+    }
+
+    /// 将文档内链接改写为章节锚点，外部链接保持不变，指向未导出页面的链接去掉
+    fn rewrite_link(&self, href: &str, base: Option<&Url>, anchor: &str) -> Option<String> {
+        if let Some(fragment) = href.strip_prefix('#') {
+            return Some(format!("#{}", page_anchor(anchor, fragment)));
+        }
+        if let Ok(url) = Url::parse(href) {
+            return (url.scheme() != "javascript").then(|| href.to_string());
+        }
+
+        let resolved = base?.join(href).ok()?;
+        let path = resolved.path().trim_matches('/');
+        let path = path.strip_suffix(".html").unwrap_or(path);
+        let (file, target) = self
+            .targets
+            .get(path)
+            .or_else(|| self.targets.get(page_path(href)))?;
+        Some(match resolved.fragment() {
+            Some(fragment) if !fragment.is_empty() => format!("{}#{}", file, page_anchor(target, fragment)),
+            _ => format!("{}#{}", file, target),
+        })
+    }
+
+    /// 嵌入 data URI 或文档目录中的图片，返回图片文件名
+    fn embed_image(&mut self, src: &str, base: Option<&Url>) -> Option<String> {
+        let (key, kind, data) = if let Some(data_uri) = src.strip_prefix("data:") {
+            let (mime, encoded) = data_uri.split_once(";base64,")?;
+            (src.to_string(), mime.to_string(), STANDARD.decode(encoded.trim()).ok()?)
+        } else {
+            if Url::parse(src).is_ok() {
+                return None;
+            }
+            let resolved = base?.join(src).ok()?;
+            let path = resolved.path().trim_matches('/').to_string();
+            if let Some(file) = self.image_files.get(&path) {
+                return Some(file.clone());
+            }
+            let extension = Path::new(&path).extension()?.to_str()?.to_string();
+            let data = std::fs::read(self.doc_dir.join(&path)).ok()?;
+            (path, extension, data)
+        };
+
+        if let Some(file) = self.image_files.get(&key) {
+            return Some(file.clone());
+        }
+        let (media_type, extension) = image_media_type(&kind)?;
+        let file = format!("images/image-{}.{}", self.images.len() + 1, extension);
+        self.images.push(EpubImage {
+            file: file.clone(),
+            media_type: media_type.to_string(),
+            data,
+        });
+        self.image_files.insert(key, file.clone());
+        Some(file)
+    }
+}
+
+/// 静态站点中的页面
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitePage {
+    /// 页面路径
+    pub path: String,
+    /// 页面文件（相对于站点目录）
+    pub file: String,
+    /// 页面标题（第一个指向该页面的条目名称，没有条目时为页面路径）
+    pub title: String,
+    /// 链接已改写的页面 HTML
+    pub html: String,
+}
+
+/// 静态站点首页中的条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteEntry {
+    /// 条目名称
+    pub name: String,
+    /// 条目类型
+    pub entry_type: String,
+    /// 条目的相对地址（页面文件和锚点）
+    pub href: String,
+}
+
+/// 复制到静态站点中的文件，如文档目录中的图片
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteAsset {
+    /// 文件路径（相对于站点目录）
+    pub file: String,
+    /// 文件内容
+    pub data: Vec<u8>,
+}
+
+/// 待写入的静态站点
+#[derive(Debug, Clone, Default)]
+pub struct StaticSite {
+    /// 站点标题
+    pub title: String,
+    /// 语言
+    pub language: String,
+    /// 首页列出的条目，按索引顺序
+    pub entries: Vec<SiteEntry>,
+    /// 页面
+    pub pages: Vec<SitePage>,
+    /// 复制的文件
+    pub assets: Vec<SiteAsset>,
+    /// 文档的自定义样式（`custom.css`），每个页面都会引用
+    pub stylesheet: Option<String>,
+}
+
+/// 根据文档生成静态站点
+///
+/// 导出 index.json 中条目指向的页面和 db.json 中的其余页面；`types` 不为空时
+/// 只导出这些类型（不区分大小写）的条目指向的页面。
+pub fn build_site(doc: &Documentation, types: &[String]) -> Result<StaticSite> {
+    let stored = PageDb::load(&doc.dir().join(DB_FILENAME)).unwrap_or_default();
+    let entries: Vec<_> = doc
+        .entries
+        .iter()
+        .filter(|entry| types.is_empty() || types.iter().any(|t| t.eq_ignore_ascii_case(&entry.entry_type)))
+        .collect();
+
+    // 页面按条目在索引中第一次出现的顺序排列，没有条目的页面按路径排在后面
+    let mut titles: HashMap<String, String> = HashMap::new();
+    let mut paths: Vec<String> = Vec::new();
+    for entry in &entries {
+        let path = PageKey::new(&entry.path).into_string();
+        if !titles.contains_key(&path) {
+            titles.insert(path.clone(), entry.name.clone());
+            paths.push(path);
+        }
+    }
+    if types.is_empty() {
+        let mut rest: Vec<String> = stored
+            .paths()
+            .into_iter()
+            .map(|key| key.as_str().to_string())
+            .filter(|path| !titles.contains_key(path))
+            .collect();
+        rest.sort();
+        paths.extend(rest);
+    }
+
+    let mut sources: Vec<(String, String)> = Vec::new();
+    for path in paths {
+        let html = match std::fs::read_to_string(doc.page_file(&path)) {
+            Ok(html) => html,
+            Err(_) => match stored.get(&PageKey::new(&path)) {
+                Some(html) => html.clone(),
+                None => {
+                    log::warn!("页面不存在，已跳过: {}", path);
+                    continue;
+                }
+            },
+        };
+        sources.push((path, html));
+    }
+    let files: HashMap<String, String> = sources
+        .iter()
+        .filter_map(|(path, _)| match site_file(path) {
+            Some(file) => Some((path.clone(), file)),
+            None => {
+                log::warn!("页面路径会离开输出目录，已跳过: {}", path);
+                None
+            }
+        })
+        .collect();
+    sources.retain(|(path, _)| files.contains_key(path));
+    if sources.is_empty() {
+        return Err(Error::Doc(format!("文档 {} 没有可导出的页面", doc.path())));
+    }
+
+    let mut writer = SiteWriter {
+        doc_dir: doc.dir(),
+        files: &files,
+        assets: Vec::new(),
+    };
+    let pages = sources
+        .into_iter()
+        .map(|(path, html)| {
+            let file = files[&path].clone();
+            SitePage {
+                html: writer.rewrite_page(&html, &path, &file),
+                title: titles.get(&path).cloned().unwrap_or_else(|| path.clone()),
+                path,
+                file,
+            }
+        })
+        .collect();
+
+    let entries = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let file = files.get(PageKey::new(&entry.path).as_str())?;
+            let href = match entry.path.split_once('#') {
+                Some((_, fragment)) if !fragment.is_empty() => format!("{}#{}", file, fragment),
+                _ => file.clone(),
+            };
+            Some(SiteEntry {
+                name: entry.name.clone(),
+                entry_type: entry.entry_type.clone(),
+                href,
+            })
+        })
+        .collect();
+
+    Ok(StaticSite {
+        title: doc.full_name(),
+        language: doc.locale_or_default().to_string(),
+        entries,
+        pages,
+        assets: writer.assets,
+        stylesheet: std::fs::read_to_string(doc.dir().join(CUSTOM_CSS)).ok(),
+    })
+}
+
+/// 生成静态站点的首页：可以过滤的条目列表
+pub fn site_index_html(site: &StaticSite) -> String {
+    let mut items = String::new();
+    for entry in &site.entries {
+        let _ = writeln!(
+            items,
+            "    <li data-name=\"{}\"><a href=\"{}\">{}</a> <small>{}</small></li>",
+            escape(&entry.name.to_lowercase()),
+            escape(&entry.href),
+            escape(&entry.name),
+            escape(&entry.entry_type)
+        );
+    }
+    let body = format!(
+        "<h1>{}</h1>\n<input id=\"search\" type=\"search\" placeholder=\"Search\" autofocus>\n<ul id=\"entries\">\n{}</ul>\n<script>\n{}\n</script>\n",
+        escape(&site.title),
+        items,
+        SITE_SEARCH_SCRIPT
+    );
+    site_document(site, &site.title, SITE_INDEX, &body)
+}
+
+/// 生成静态站点中的页面文件
+pub fn site_page_html(site: &StaticSite, page: &SitePage) -> String {
+    let body = format!(
+        "<nav><a href=\"{}\">{}</a></nav>\n<main>\n{}\n</main>\n",
+        relative_href(&page.file, SITE_INDEX),
+        escape(&site.title),
+        page.html
+    );
+    site_document(site, &page.title, &page.file, &body)
+}
+
+/// 将静态站点写入目录
+///
+/// 页面和文件的路径会离开 `output` 时（包含 `..` 或为绝对路径）返回错误。
+pub fn write_site(site: &StaticSite, output: &Path) -> Result<()> {
+    let write = |file: &str, content: &[u8]| -> Result<()> {
+        if !is_site_relative(file) {
+            return Err(Error::Doc(format!("站点中的文件路径无效: {}", file)));
+        }
+        let path = output.join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content).map_err(|e| Error::Storage {
+            path,
+            source: Box::new(Error::Io(e)),
+        })
+    };
+
+    write(SITE_INDEX, site_index_html(site).as_bytes())?;
+    for page in &site.pages {
+        write(&page.file, site_page_html(site, page).as_bytes())?;
+    }
+    for asset in &site.assets {
+        write(&asset.file, &asset.data)?;
+    }
+    if let Some(stylesheet) = &site.stylesheet {
+        write(CUSTOM_CSS, stylesheet.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// 页面在静态站点中的文件，与文档目录中页面文件的转义规则相同
+///
+/// 导出的页面键来自已保存的 index.json 和 db.json，是抓取器的 `url_to_path`
+/// 已经生成的结果，这里只需按存储层的规则转义（`url_to_path` 依赖抓取器的根 URL，
+/// 导出时没有）。页面键中有 `..` 或以 `/` 开头时返回 None。
+fn site_file(path: &str) -> Option<String> {
+    let file = format!("{}.html", page_paths::escape_page_path(path));
+    (is_site_relative(path) && is_site_relative(&file)).then_some(file)
+}
+
+/// 站点中的相对路径是否不会离开站点目录：不为空，每一段都是普通的名称
+fn is_site_relative(file: &str) -> bool {
+    !file.is_empty()
+        && !file.contains('\\')
+        && Path::new(file)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// 从站点中的一个文件指向另一个文件的相对地址
+fn relative_href(from_file: &str, to_file: &str) -> String {
+    format!("{}{}", "../".repeat(from_file.matches('/').count()), to_file)
+}
+
+/// 生成静态站点中的完整 HTML 文件
+fn site_document(site: &StaticSite, title: &str, file: &str, body: &str) -> String {
+    let stylesheet = match site.stylesheet {
+        Some(_) => format!("\n  <link rel=\"stylesheet\" href=\"{}\">", relative_href(file, CUSTOM_CSS)),
+        None => String::new(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n  <meta charset=\"UTF-8\">\n  <title>{}</title>{}\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(&site.language),
+        escape(title),
+        stylesheet,
+        body
+    )
+}
+
+/// 改写静态站点页面中的链接和图片地址
+struct SiteWriter<'a> {
+    /// 文档目录，用于读取相对路径的图片
+    doc_dir: std::path::PathBuf,
+    /// 页面路径到页面文件的映射
+    files: &'a HashMap<String, String>,
+    /// 已复制的文件
+    assets: Vec<SiteAsset>,
+}
+
+impl SiteWriter<'_> {
+    /// 改写单个页面，其余内容保持不变
+    fn rewrite_page(&mut self, html: &str, path: &str, file: &str) -> String {
+        let Ok(base) = Url::parse(LINK_BASE).and_then(|base| base.join(path)) else {
+            return html.to_string();
+        };
+        let document = kuchiki::parse_html().one(html);
+
+        if let Ok(nodes) = document.select("a[href], area[href]") {
+            for node in nodes {
+                let mut attributes = node.attributes.borrow_mut();
+                let Some(href) = attributes.get("href").map(str::to_string) else {
+                    continue;
+                };
+                if href.starts_with('#') || Url::parse(&href).is_ok() {
+                    continue;
+                }
+                // 指向未导出页面的链接只保留文字
+                match self.link_target(&base, &href, file) {
+                    Some(target) => attributes.insert("href", target),
+                    None => attributes.remove("href"),
+                };
+            }
+        }
+        if let Ok(nodes) = document.select("img[src]") {
+            for node in nodes {
+                let mut attributes = node.attributes.borrow_mut();
+                let Some(src) = attributes.get("src").map(str::to_string) else {
+                    continue;
+                };
+                // data URI 和远程图片保持原样
+                if src.starts_with("data:") || Url::parse(&src).is_ok() {
+                    continue;
+                }
+                if let Some(target) = self.copy_asset(&base, &src, file) {
+                    attributes.insert("src", target);
+                }
+            }
+        }
+
+        serialize_html(html, &document)
+    }
+
+    /// 文档内链接对应的相对文件地址，锚点保持不变
+    fn link_target(&self, base: &Url, href: &str, from_file: &str) -> Option<String> {
+        let resolved = base.join(href).ok()?;
+        let path = resolved.path().trim_matches('/');
+        let path = path.strip_suffix(".html").unwrap_or(path);
+        let target = self.files.get(PageKey::new(path).as_str())?;
+        let target = relative_href(from_file, target);
+        Some(match resolved.fragment() {
+            Some(fragment) if !fragment.is_empty() => format!("{}#{}", target, fragment),
+            _ => target,
+        })
+    }
+
+    /// 复制文档目录中的文件到站点中相同的位置，返回相对地址
+    fn copy_asset(&mut self, base: &Url, src: &str, from_file: &str) -> Option<String> {
+        let resolved = base.join(src).ok()?;
+        let path = resolved.path().trim_matches('/').to_string();
+        if !is_site_relative(&path) {
+            return None;
+        }
+        if !self.assets.iter().any(|asset| asset.file == path) {
+            let data = std::fs::read(self.doc_dir.join(&path)).ok()?;
+            self.assets.push(SiteAsset { file: path.clone(), data });
+        }
+        Some(relative_href(from_file, &path))
+    }
+}
+
+/// 最简单的 zip 写入器，只支持不压缩和 deflate 两种方式
+///
+/// 不写 ZIP64 记录：文件或整个 zip 超过 4 GiB、文件数超过 65535 时返回错误。
+#[derive(Default)]
+struct ZipWriter {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    count: u16,
+}
+
+impl ZipWriter {
+    /// 添加文件
+    fn add(&mut self, name: &str, content: &[u8], compress: bool) -> Result<()> {
+        let mut crc = Crc::new();
+        crc.update(content);
+        let (method, body): (u16, Vec<u8>) = if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content)?;
+            (8, encoder.finish()?)
+        } else {
+            (0, content.to_vec())
+        };
+        let offset = zip_u32(self.data.len(), name)?;
+        let count = self.count.checked_add(1).filter(|count| *count != u16::MAX).ok_or_else(|| {
+            Error::Doc(format!("EPUB 中的文件超过 {} 个，不支持 ZIP64", u16::MAX - 1))
+        })?;
+
+        // 本地文件头（修改时间固定为 1980-01-01，保证输出可重现）
+        let mut header = Vec::new();
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&method.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&0x21u16.to_le_bytes());
+        header.extend_from_slice(&crc.sum().to_le_bytes());
+        header.extend_from_slice(&zip_u32(body.len(), name)?.to_le_bytes());
+        header.extend_from_slice(&zip_u32(content.len(), name)?.to_le_bytes());
+        let name_len = u16::try_from(name.len()).map_err(|_| Error::Doc(format!("zip 中的文件名过长: {}", name)))?;
+        header.extend_from_slice(&name_len.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+
+        self.data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.data.extend_from_slice(&header);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(&body);
+
+        // 中央目录记录
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        self.central.extend_from_slice(&header);
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // 注释长度
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // 磁盘编号
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // 内部属性
+        self.central.extend_from_slice(&0u32.to_le_bytes()); // 外部属性
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+        self.count = count;
+        Ok(())
+    }
+
+    /// 写入中央目录并返回完整的 zip 内容
+    fn finish(mut self) -> Result<Vec<u8>> {
+        let central_offset = zip_u32(self.data.len(), "中央目录")?;
+        let central_size = zip_u32(self.central.len(), "中央目录")?;
+        self.data.append(&mut self.central);
+        self.data.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&self.count.to_le_bytes());
+        self.data.extend_from_slice(&self.count.to_le_bytes());
+        self.data.extend_from_slice(&central_size.to_le_bytes());
+        self.data.extend_from_slice(&central_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        Ok(self.data)
+    }
+}
+
+/// zip 头中的 32 位大小或偏移，`0xFFFFFFFF` 表示 ZIP64，也不能使用
+fn zip_u32(value: usize, name: &str) -> Result<u32> {
+    u32::try_from(value)
+        .ok()
+        .filter(|value| *value != u32::MAX)
+        .ok_or_else(|| Error::Doc(format!("EPUB 超过 4 GiB（{}），不支持 ZIP64", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::index_entry::IndexEntry;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    fn entry(name: &str, path: &str, entry_type: &str) -> IndexEntry {
+        IndexEntry {
+            name: name.to_string(),
+            path: path.to_string(),
+            entry_type: entry_type.to_string(),
+            word_count: None,
+            inherited: None,
+        }
+    }
+
+    /// 创建包含两种类型、三个页面的文档
+    fn fixture_doc(root: &Path) -> Documentation {
+        let dir = root.join("fixture");
+        fs::create_dir_all(dir.join("guide")).unwrap();
+        fs::create_dir_all(dir.join("_images")).unwrap();
+        fs::write(dir.join("_images").join("logo.png"), STANDARD.decode(PIXEL_PNG).unwrap()).unwrap();
+        fs::write(
+            dir.join("guide").join("intro.html"),
+            format!(
+                r##"<h1 id="intro">Intro</h1>
+                <p onclick="track()">See <a href="../api/map#syntax">map</a> &amp; <a href="#usage">usage</a>.</p>
+                <h2 id="usage">Usage</h2><script>track()</script><button>Copy</button>
+                <img src="data:image/png;base64,{}" alt="pixel"><img src="../_images/logo.png" alt="logo">
+                <img src="https://example.com/remote.png" alt="remote"><br>"##,
+                PIXEL_PNG
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(DB_FILENAME),
+            r#"{"api/map": "<h1>map</h1><h2 id=\"syntax\">Syntax</h2><a href=\"../missing\">gone</a>",
+                "api/filter": "<h1>filter</h1><p>Back to <a href=\"../guide/intro\">intro</a></p>"}"#,
+        )
+        .unwrap();
+
+        Documentation::new("Fixture", "fixture", "")
+            .with_root(root, false)
+            .with_entries(vec![
+                entry("Intro", "guide/intro", "Guides"),
+                entry("Array.prototype.map()", "api/map", "Array"),
+                entry("map syntax", "api/map#syntax", "Array"),
+                entry("Array.prototype.filter()", "api/filter", "Array"),
+            ])
+    }
+
+    fn sample_book() -> EpubBook {
+        EpubBook {
+            title: "Fixture & Co".to_string(),
+            identifier: "urn:xwdoc:fixture".to_string(),
+            language: "en".to_string(),
+            modified: "2024-01-01T00:00:00Z".to_string(),
+            chapters: vec![
+                Chapter {
+                    title: "Guides".to_string(),
+                    file: "chapter-1.xhtml".to_string(),
+                    pages: vec![ChapterPage {
+                        path: "guide/intro".to_string(),
+                        title: "Intro".to_string(),
+                        anchor: "p1".to_string(),
+                    }],
+                    body: String::new(),
+                },
+                Chapter {
+                    title: "Array".to_string(),
+                    file: "chapter-2.xhtml".to_string(),
+                    pages: vec![ChapterPage {
+                        path: "api/map".to_string(),
+                        title: "map".to_string(),
+                        anchor: "p2".to_string(),
+                    }],
+                    body: String::new(),
+                },
+            ],
+            images: vec![EpubImage {
+                file: "images/image-1.png".to_string(),
+                media_type: "image/png".to_string(),
+                data: Vec::new(),
+            }],
+            stylesheet: None,
+        }
+    }
+
+    #[test]
+    fn test_content_opf_manifest_and_spine() {
+        let opf = content_opf(&sample_book());
+
+        assert!(opf.contains(r#"<dc:title>Fixture &amp; Co</dc:title>"#));
+        assert!(opf.contains(r#"<dc:identifier id="book-id">urn:xwdoc:fixture</dc:identifier>"#));
+        assert!(opf.contains(r#"<meta property="dcterms:modified">2024-01-01T00:00:00Z</meta>"#));
+        assert!(opf.contains(r#"href="nav.xhtml" media-type="application/xhtml+xml" properties="nav""#));
+        assert!(opf.contains(r#"<item id="chapter-2" href="chapter-2.xhtml" media-type="application/xhtml+xml"/>"#));
+        assert!(opf.contains(r#"<item id="image-1" href="images/image-1.png" media-type="image/png"/>"#));
+
+        // 章节按顺序进入阅读顺序
+        let first = opf.find(r#"<itemref idref="chapter-1"/>"#).unwrap();
+        let second = opf.find(r#"<itemref idref="chapter-2"/>"#).unwrap();
+        assert!(first < second);
+    }
+
+    #[test]
+    fn test_navigation_lists_chapters_and_pages() {
+        let book = sample_book();
+
+        let nav = nav_xhtml(&book);
+        assert!(nav.contains(r#"<nav epub:type="toc" id="toc">"#));
+        assert!(nav.contains(r#"<a href="chapter-2.xhtml#p2">map</a>"#));
+
+        let ncx = toc_ncx(&book);
+        assert!(ncx.contains(r#"<navPoint id="chapter-2" playOrder="3">"#));
+        assert!(ncx.contains(r#"<content src="chapter-1.xhtml#p1"/>"#));
+    }
+
+    #[test]
+    fn test_build_book_groups_pages_by_type() {
+        let docs = TempDir::new().unwrap();
+        let book = build_book(&fixture_doc(docs.path()), &[]).unwrap();
+
+        assert_eq!(book.chapters.len(), 2);
+        assert_eq!(book.chapters[0].title, "Guides");
+        let array = &book.chapters[1];
+        assert_eq!(array.title, "Array");
+        // 带锚点的条目不会重复导出页面
+        let paths: Vec<&str> = array.pages.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["api/map", "api/filter"]);
+        assert_eq!(book.page_count(), 3);
+
+        let intro = &book.chapters[0].body;
+        // 交互元素和事件属性被去掉
+        assert!(!intro.contains("script") && !intro.contains("button") && !intro.contains("onclick"));
+        // 链接改写为章节锚点
+        assert!(intro.contains(r##"<a href="chapter-2.xhtml#p2-syntax">map</a>"##));
+        assert!(intro.contains(r##"<a href="#p1-usage">usage</a>"##));
+        assert!(intro.contains(r#"<h2 id="p1-usage">"#));
+        assert!(intro.contains("<br/>"));
+        // 两张本地图片嵌入，远程图片替换为替代文本
+        assert_eq!(book.images.len(), 2);
+        assert!(intro.contains(r#"<img alt="pixel" src="images/image-1.png"/>"#));
+        assert!(intro.contains(r#"<img alt="logo" src="images/image-2.png"/>"#));
+        assert!(intro.contains("remote") && !intro.contains("example.com"));
+
+        // 指向未导出页面的链接只保留文字
+        assert!(array.body.contains("<a>gone</a>"));
+        assert!(array.body.contains(r##"<a href="chapter-1.xhtml#p1">intro</a>"##));
+    }
+
+    #[test]
+    fn test_build_book_restricts_types() {
+        let docs = TempDir::new().unwrap();
+        let doc = fixture_doc(docs.path());
+
+        let book = build_book(&doc, &["array".to_string()]).unwrap();
+        assert_eq!(book.chapters.len(), 1);
+        // 未导出类型的页面链接只保留文字
+        assert!(book.chapters[0].body.contains("Back to <a>intro</a>"));
+
+        assert!(build_book(&doc, &["Missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_write_epub_container_layout() {
+        let docs = TempDir::new().unwrap();
+        let book = build_book(&fixture_doc(docs.path()), &[]).unwrap();
+        let output = docs.path().join("out").join("fixture.epub");
+        write_epub(&book, &output).unwrap();
+
+        let data = fs::read(&output).unwrap();
+        // mimetype 必须是第一个文件且不压缩
+        assert_eq!(&data[0..4], b"PK\x03\x04");
+        assert_eq!(u16::from_le_bytes([data[8], data[9]]), 0);
+        assert_eq!(&data[30..38], b"mimetype");
+        assert_eq!(&data[38..58], EPUB_MIME_TYPE.as_bytes());
+        // 结束记录中的文件数：mimetype、container、opf、nav、ncx、两章、两张图片
+        let end = data.len() - 22;
+        assert_eq!(&data[end..end + 4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([data[end + 10], data[end + 11]]), 9);
+    }
+
+    #[test]
+    fn test_custom_stylesheet_is_exported() {
+        let docs = TempDir::new().unwrap();
+        let doc = fixture_doc(docs.path());
+        assert_eq!(build_book(&doc, &[]).unwrap().stylesheet, None);
+
+        fs::write(doc.dir().join(CUSTOM_CSS), "table { border: 1px solid; }").unwrap();
+        fs::write(doc.dir().join("custom.js"), "track()").unwrap();
+        let book = build_book(&doc, &[]).unwrap();
+        assert_eq!(book.stylesheet.as_deref(), Some("table { border: 1px solid; }"));
+        assert!(content_opf(&book).contains(r#"<item id="style" href="custom.css" media-type="text/css"/>"#));
+        assert!(nav_xhtml(&book).contains(r#"<link rel="stylesheet" type="text/css" href="custom.css"/>"#));
+
+        // 多出 custom.css 一个文件，脚本不导出
+        let output = docs.path().join("fixture.epub");
+        write_epub(&book, &output).unwrap();
+        let data = fs::read(&output).unwrap();
+        let end = data.len() - 22;
+        assert_eq!(u16::from_le_bytes([data[end + 10], data[end + 11]]), 10);
+    }
+
+    #[test]
+    fn test_build_site_rewrites_links_to_files() {
+        let docs = TempDir::new().unwrap();
+        let site = build_site(&fixture_doc(docs.path()), &[]).unwrap();
+
+        let files: Vec<&str> = site.pages.iter().map(|page| page.file.as_str()).collect();
+        assert_eq!(files, vec!["guide/intro.html", "api/map.html", "api/filter.html"]);
+        assert_eq!(site.entries[2].href, "api/map.html#syntax");
+
+        let intro = &site.pages[0].html;
+        // 文档内链接改为相对的文件路径，页面内锚点不变
+        assert!(intro.contains(r##"<a href="../api/map.html#syntax">map</a>"##), "{}", intro);
+        assert!(intro.contains(r##"<a href="#usage">usage</a>"##));
+        // 内联的 data URI 原样保留，文档目录中的图片复制到站点中
+        assert!(intro.contains(&format!(r#"src="data:image/png;base64,{}""#, PIXEL_PNG)));
+        assert!(intro.contains(r#"src="../_images/logo.png""#));
+        assert!(intro.contains(r#"src="https://example.com/remote.png""#));
+        assert_eq!(site.assets.len(), 1);
+        assert_eq!(site.assets[0].file, "_images/logo.png");
+        assert_eq!(site.assets[0].data, STANDARD.decode(PIXEL_PNG).unwrap());
+
+        // 指向未导出页面的链接只保留文字
+        assert!(site.pages[1].html.contains("<a>gone</a>"));
+        assert!(site.pages[2].html.contains(r#"<a href="../guide/intro.html">intro</a>"#));
+
+        // 只导出指定类型时，其他页面的链接同样只保留文字
+        let site = build_site(&fixture_doc(docs.path()), &["array".to_string()]).unwrap();
+        assert_eq!(site.pages.len(), 2);
+        assert!(site.pages[1].html.contains("Back to <a>intro</a>"));
+    }
+
+    #[test]
+    fn test_write_site_layout() {
+        let docs = TempDir::new().unwrap();
+        let doc = fixture_doc(docs.path());
+        fs::write(doc.dir().join(CUSTOM_CSS), "table { border: 1px solid; }").unwrap();
+        let site = build_site(&doc, &[]).unwrap();
+        let output = docs.path().join("site");
+        write_site(&site, &output).unwrap();
+
+        let index = fs::read_to_string(output.join(SITE_INDEX)).unwrap();
+        assert!(index.contains(r#"<li data-name="array.prototype.map()"><a href="api/map.html">Array.prototype.map()</a>"#));
+        assert!(index.contains(r#"<link rel="stylesheet" href="custom.css">"#));
+        assert!(index.contains("getElementById('search')"));
+
+        let map = fs::read_to_string(output.join("api").join("map.html")).unwrap();
+        assert!(map.contains(r#"<nav><a href="../index.html">"#));
+        assert!(map.contains(r#"<link rel="stylesheet" href="../custom.css">"#));
+        assert!(map.contains("<title>Array.prototype.map()</title>"));
+        assert_eq!(fs::read(output.join("_images").join("logo.png")).unwrap(), STANDARD.decode(PIXEL_PNG).unwrap());
+        assert!(output.join(CUSTOM_CSS).is_file());
+
+        // 与首页或保留名称同名的页面放在 `_p/` 下
+        assert_eq!(site_file("index").as_deref(), Some("_p/index.html"));
+        assert_eq!(relative_href("_p/index.html", SITE_INDEX), "../index.html");
+    }
+
+    #[test]
+    fn test_site_files_stay_in_output() {
+        assert_eq!(site_file("api/map").as_deref(), Some("api/map.html"));
+        assert_eq!(site_file("../outside"), None);
+        assert_eq!(site_file("api/../../outside"), None);
+        assert_eq!(site_file("/etc/passwd"), None);
+        assert_eq!(site_file("api\\..\\outside"), None);
+
+        let output = TempDir::new().unwrap();
+        let site_dir = output.path().join("site");
+        for file in ["../outside.html", "/tmp/outside.html"] {
+            let site = StaticSite {
+                pages: vec![SitePage {
+                    path: "outside".to_string(),
+                    file: file.to_string(),
+                    title: "Outside".to_string(),
+                    html: String::new(),
+                }],
+                ..StaticSite::default()
+            };
+            assert!(write_site(&site, &site_dir).is_err(), "{}", file);
+        }
+        assert!(!output.path().join("outside.html").exists());
+    }
+
+    #[test]
+    fn test_zip_limits_are_errors() {
+        let mut zip = ZipWriter::default();
+        assert!(zip.add(&"a".repeat(u16::MAX as usize + 1), b"", false).is_err());
+        zip.add("a.txt", b"a", false).unwrap();
+        assert_eq!(zip.count, 1);
+
+        let mut full = ZipWriter {
+            count: u16::MAX - 1,
+            ..ZipWriter::default()
+        };
+        assert!(full.add("b.txt", b"b", false).is_err());
+        assert_eq!(full.count, u16::MAX - 1);
+
+        assert!(zip_u32(u32::MAX as usize - 1, "a.txt").is_ok());
+        assert!(zip_u32(u32::MAX as usize, "a.txt").is_err());
+        assert!(zip_u32(u32::MAX as usize + 1, "a.txt").is_err());
+    }
+
+    /// 安装了 epubcheck 时校验导出结果，否则跳过
+    #[test]
+    fn test_epubcheck_accepts_export() {
+        if std::process::Command::new("epubcheck").arg("--version").output().is_err() {
+            eprintln!("未安装 epubcheck，跳过");
+            return;
+        }
+
+        let docs = TempDir::new().unwrap();
+        let book = build_book(&fixture_doc(docs.path()), &[]).unwrap();
+        let output = docs.path().join("fixture.epub");
+        write_epub(&book, &output).unwrap();
+
+        let status = std::process::Command::new("epubcheck").arg(&output).status().unwrap();
+        assert!(status.success());
+    }
+}
@@ -2,10 +2,24 @@
 //! 严格按照原版Ruby实现
 
 use crate::core::error::Result;
-use crate::core::filters::FilterBase;
-use crate::core::scraper::filter::{Filter, FilterContext};
-use scraper::{Html, Selector};
+use crate::core::filters::markup::{escape_text, start_tag, RAW_TEXT_ELEMENTS, VOID_ELEMENTS};
+use crate::core::filters::{FilterBase, EXPERIMENTAL_CLASS, NOTE_CLASS, WARNING_CLASS};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use scraper::{ElementRef, Html, Selector};
 use std::any::Any;
+use std::collections::{HashMap, HashSet};
+
+/// MDN 提示框到 xwdoc 样式类的映射
+///
+/// 类名属于 xwdoc 自身样式表的约定（见 `PRESERVED_CLASSES`），清理属性时会被保留
+const CALLOUTS: &[(&str, &str)] = &[
+    (".notecard.warning, .notecard.deprecated", WARNING_CLASS),
+    (".notecard.experimental", EXPERIMENTAL_CLASS),
+    (".notecard.note, .notecard.callout, .callout", NOTE_CLASS),
+];
+
+/// 提示框中的纯装饰性元素
+const DECORATIVE_ICONS: &str = ".icon, svg, [aria-hidden='true']";
 
 /// 清理HTML的过滤器，移除不必要的元素和属性
 pub struct CleanHtmlFilter;
@@ -15,12 +29,92 @@ impl CleanHtmlFilter {
     pub fn new() -> Self {
         Self
     }
+
+    /// 将 MDN 的提示框转换为语义化的包装元素，保留其内容并去掉装饰性图标
+    ///
+    /// JavaScript 文档的清理过滤器也使用这一转换。
+    pub(crate) fn normalize_callouts(&self, html: &str) -> String {
+        let document = Html::parse_fragment(html);
+
+        let mut callouts = HashMap::new();
+        for (selector, class) in CALLOUTS {
+            for node in FilterBase::css(self, &document, selector) {
+                callouts.entry(node.id()).or_insert(*class);
+            }
+        }
+        if callouts.is_empty() {
+            return html.to_string();
+        }
+
+        let icons: HashSet<_> = FilterBase::css(self, &document, DECORATIVE_ICONS)
+            .into_iter()
+            .filter(|icon| icon.ancestors().any(|a| callouts.contains_key(&a.id())))
+            .map(|icon| icon.id())
+            .collect();
+
+        let action = |element: ElementRef| {
+            if icons.contains(&element.id()) {
+                Callout::Drop
+            } else if let Some(class) = callouts.get(&element.id()) {
+                Callout::Wrap(class)
+            } else {
+                Callout::Keep
+            }
+        };
+        let mut output = String::new();
+        write_callouts(document.root_element(), &action, &mut output);
+        output
+    }
+}
+
+/// 序列化提示框时对元素的处理
+enum Callout {
+    /// 原样保留
+    Keep,
+    /// 去掉元素及其内容
+    Drop,
+    /// 替换为带样式类的 `<div>`，保留内容
+    Wrap(&'static str),
+}
+
+/// 序列化元素的子节点，提示框改写为带样式类的 `<div>`，跳过其中的装饰性图标
+///
+/// 遍历解析后的 DOM 而不是替换原始字符串，代码示例等文本中相同的内容不受影响。
+fn write_callouts(element: ElementRef, action: &dyn Fn(ElementRef) -> Callout, output: &mut String) {
+    let raw_text = RAW_TEXT_ELEMENTS.contains(&element.value().name());
+    for child in element.children() {
+        if let Some(child) = ElementRef::wrap(child) {
+            let name = match action(child) {
+                Callout::Drop => continue,
+                Callout::Wrap(class) => {
+                    output.push_str(&start_tag("div", [("class", class)]));
+                    write_callouts(child, action, output);
+                    output.push_str("</div>");
+                    continue;
+                }
+                Callout::Keep => child.value().name(),
+            };
+            output.push_str(&start_tag(name, child.value().attrs()));
+            if !VOID_ELEMENTS.contains(&name) {
+                write_callouts(child, action, output);
+                output.push_str(&format!("</{}>", name));
+            }
+        } else if let Some(text) = child.value().as_text() {
+            if raw_text {
+                output.push_str(text);
+            } else {
+                output.push_str(&escape_text(text));
+            }
+        } else if let Some(comment) = child.value().as_comment() {
+            output.push_str(&format!("<!--{}-->", &**comment));
+        }
+    }
 }
 
 impl FilterBase for CleanHtmlFilter {}
 
 impl Filter for CleanHtmlFilter {
-    fn apply(&self, html: &str, _context: &mut FilterContext) -> Result<String> {
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
         // Babel的HTML是由minify压缩的，使用Fragment解析效果更好
         let document = Html::parse_fragment(html);
         let mut output = String::new();
@@ -44,7 +138,9 @@ impl Filter for CleanHtmlFilter {
         if output.is_empty() {
             output = html.to_string();
         }
-        Ok(output)
+
+        // 规范化提示框
+        Ok(FilterOutput::new(self.normalize_callouts(&output)))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
@@ -59,3 +155,51 @@ impl Filter for CleanHtmlFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_notecard_survives() {
+        let filter = CleanHtmlFilter::new();
+        let context = FilterContext::default();
+
+        let html = r#"<p>Intro</p><div class="notecard warning"><span class="icon icon-warning"></span><p><strong>Warning:</strong> Do not use this in production.</p></div>"#;
+        let result = filter.apply_v2(html, &context).unwrap().html;
+
+        assert!(result.contains(r#"<div class="_warning">"#));
+        assert!(result.contains("<strong>Warning:</strong> Do not use this in production."));
+        assert!(!result.contains("notecard"));
+        assert!(!result.contains("icon-warning"));
+    }
+
+    #[test]
+    fn test_note_and_experimental_notecards() {
+        let filter = CleanHtmlFilter::new();
+        let context = FilterContext::default();
+
+        let html = r#"<div class="notecard note"><p>A note.</p></div><div class="notecard experimental"><p>Experimental.</p></div>"#;
+        let result = filter.apply_v2(html, &context).unwrap().html;
+
+        assert!(result.contains(r#"<div class="_note"><p>A note.</p></div>"#));
+        assert!(result.contains(r#"<div class="_experimental"><p>Experimental.</p></div>"#));
+    }
+
+    #[test]
+    fn test_mdn_fixture_callouts() {
+        let html = std::fs::read_to_string("test_docs/html_test/dialog_original.html").unwrap();
+        let result = CleanHtmlFilter::new().apply_v2(&html, &FilterContext::default()).unwrap().html;
+
+        assert!(result.contains(r#"<div class="_warning"><p><strong>Warning:</strong> The <code>tabindex</code>"#));
+        assert!(result.contains(r#"<div class="_experimental"><p>This attribute is experimental"#));
+        assert!(result.contains(r#"<div class="_note"><p><strong>Note:</strong> While you can toggle"#));
+        assert!(result.contains(r#"<div class="_note"><p>Provide a visible close button"#));
+        assert!(!result.contains("notecard"));
+        assert!(!result.contains("icon-warning"));
+        assert!(!result.contains("<svg"));
+
+        // 提示框以外的图标不受影响
+        assert!(result.contains("icon-experimental"));
+    }
+}
@@ -2,8 +2,9 @@
 //! 严格按照原版Ruby实现
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
-use scraper::{Html, Selector, Element};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use crate::core::filters::selector;
+use scraper::{ElementRef, Html};
 use regex::Regex;
 use std::any::Any;
 
@@ -44,10 +45,8 @@ impl HtmlEntriesFilter {
             return Some("Miscellaneous".to_string());
         }
 
-        if let Ok(selector) = Selector::parse(".deprecated, .non-standard, .obsolete") {
-            if doc.select(&selector).next().is_some() {
-                return Some("Obsolete".to_string());
-            }
+        if doc.select(&selector(".deprecated, .non-standard, .obsolete")).next().is_some() {
+            return Some("Obsolete".to_string());
         }
 
         if slug.starts_with("Global_attr") {
@@ -64,12 +63,10 @@ impl HtmlEntriesFilter {
             return false;
         }
 
-        if let Ok(selector) = Selector::parse(".overheadIndicator, .blockIndicator") {
-            if let Some(node) = doc.select(&selector).next() {
-                let content = node.text().collect::<String>();
-                if content.contains("not on a standards track") {
-                    return false;
-                }
+        if let Some(node) = doc.select(&selector(".overheadIndicator, .blockIndicator")).next() {
+            let content = node.text().collect::<String>();
+            if content.contains("not on a standards track") {
+                return false;
             }
         }
         true
@@ -86,33 +83,13 @@ impl HtmlEntriesFilter {
         }
 
         if slug == "Attributes" {
-            let mut entries = Vec::new();
-            if let Ok(selector) = Selector::parse(".standard-table td:first-child") {
-                for node in doc.select(&selector) {
-                    let next_content = node.next_sibling_element().map_or("".to_string(), |e| e.text().collect());
-                    if next_content.contains("Global attribute") {
-                        continue;
-                    }
-
-                    let mut name = if let Some(code) = node.select(&Selector::parse("code").unwrap()).next() {
-                        code.text().collect::<String>().trim().to_string()
-                    } else {
-                        node.text().collect::<String>().trim().to_string()
-                    };
-                    name.push_str(" (attribute)");
-                    let id = name.to_lowercase().replace(' ', "-");
-                    entries.push((name, id, "Attributes".to_string()));
-                }
-            }
-            entries
+            attribute_table_entries(doc)
         } else if slug == "Link_types" {
             let mut entries = Vec::new();
-            if let Ok(selector) = Selector::parse(".standard-table td:first-child > code") {
-                for node in doc.select(&selector) {
-                    let name = format!("rel: {}", node.text().collect::<String>().trim());
-                    let id = name.to_lowercase().replace(' ', "-");
-                    entries.push((name, id, "Attributes".to_string()));
-                }
+            for node in doc.select(&selector(".standard-table td:first-child > code")) {
+                let name = format!("rel: {}", node.text().collect::<String>().trim());
+                let id = name.to_lowercase().replace(' ', "-");
+                entries.push((name, id, "Attributes".to_string()));
             }
             entries
         } else {
@@ -135,30 +112,99 @@ impl HtmlEntriesFilter {
     }
 }
 
+/// 属性列表页面中的属性条目
+///
+/// 按行和列的位置读取表格，不依赖单元格之间的兄弟关系：只处理表头行中有
+/// "Elements" 列的表格，列的位置按表头行的所有单元格计算，数据行同样按所有
+/// 单元格（`th` 和 `td`）取列。第一列为属性名，"Elements" 列指向全局属性的行跳过。
+fn attribute_table_entries(doc: &Html) -> Vec<(String, String, String)> {
+    let table_selector = selector("table");
+    let row_selector = selector("tr");
+    let code_selector = selector("code");
+
+    let mut entries = Vec::new();
+    for table in doc.select(&table_selector) {
+        let rows: Vec<ElementRef> = table.select(&row_selector).collect();
+        let Some((header_row, elements_column)) = rows.iter().enumerate().find_map(|(i, row)| {
+            row_cells(*row)
+                .iter()
+                .position(|cell| cell.value().name() == "th" && normalized_text(*cell).eq_ignore_ascii_case("Elements"))
+                .map(|column| (i, column))
+        }) else {
+            continue;
+        };
+
+        for row in &rows[header_row + 1..] {
+            let cells = row_cells(*row);
+            let (Some(name_cell), Some(elements_cell)) = (cells.first(), cells.get(elements_column)) else {
+                continue;
+            };
+            if is_global_attribute_cell(elements_cell) {
+                continue;
+            }
+
+            let name = match name_cell.select(&code_selector).next() {
+                Some(code) => normalized_text(code),
+                None => normalized_text(*name_cell),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let name = format!("{} (attribute)", name);
+            let id = name.to_lowercase().replace(' ', "-");
+            entries.push((name, id, "Attributes".to_string()));
+        }
+    }
+    entries
+}
+
+/// 表格行中的单元格（`th` 和 `td`）
+fn row_cells(row: ElementRef) -> Vec<ElementRef> {
+    row.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|cell| matches!(cell.value().name(), "th" | "td"))
+        .collect()
+}
+
+/// "Elements" 列是否表示全局属性：链接到全局属性页面，或者内容就是 "Global attribute"
+fn is_global_attribute_cell(cell: &ElementRef) -> bool {
+    let links_to_global = cell.select(&selector("a[href]")).any(|link| {
+        let href = link.value().attr("href").unwrap_or_default();
+        let path = href.split('#').next().unwrap_or_default().trim_end_matches('/');
+        path.to_ascii_lowercase().ends_with("global_attributes")
+    });
+    links_to_global || normalized_text(*cell).eq_ignore_ascii_case("Global attribute")
+}
+
+/// 元素的文本，连续的空白合并为一个空格
+fn normalized_text(element: ElementRef) -> String {
+    element.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 impl Filter for HtmlEntriesFilter {
-    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
-        Ok(html.to_string())
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+        Ok(FilterOutput::new(html))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
         Box::new(HtmlEntriesFilter::new())
     }
 
-    fn get_entries(&self, html: &str, context: &FilterContext) -> Vec<(String, String, String)> {
+    fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+        // 条目以页面路径为准，`context.slug` 是整个文档的标识
         let slug = &context.current_path;
-        let is_root = slug.is_empty() || slug == "/" || slug == &context.root_path;
 
         let mut entries = Vec::new();
-        let doc = Html::parse_document(html);
+        let doc = context.html_doc();
 
-        if self.include_default_entry(slug, &doc) {
-            let name = self.get_name(&doc, slug);
-            if let Some(entry_type) = self.get_type(&doc, slug) {
+        if self.include_default_entry(slug, doc) {
+            let name = self.get_name(doc, slug);
+            if let Some(entry_type) = self.get_type(doc, slug) {
                 entries.push((name, slug.to_string(), entry_type));
             }
         }
 
-        entries.extend(self.additional_entries(&doc, slug));
+        entries.extend(self.additional_entries(doc, slug));
         entries
     }
 
@@ -170,3 +216,87 @@ impl Filter for HtmlEntriesFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_context(html: &str, path: &str) -> FilterContext {
+        let mut context = FilterContext::new().with_html(html);
+        // 抓取器把文档标识写入 slug，把页面路径写入 current_path
+        context.slug = "html".to_string();
+        context.current_path = path.to_string();
+        context
+    }
+
+    #[test]
+    fn test_entries_use_page_path() {
+        let html = std::fs::read_to_string("test_docs/html_test/dialog_original.html").unwrap();
+        let context = page_context(&html, "Element/dialog");
+
+        assert_eq!(
+            HtmlEntriesFilter::new().get_entries(&context),
+            vec![("dialog".to_string(), "Element/dialog".to_string(), "Elements".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_heading_elements_expand_to_tags() {
+        let context = page_context("<h1>Heading elements</h1>", "Element/Heading_Elements");
+        let entries = HtmlEntriesFilter::new().get_entries(&context);
+
+        let names: Vec<&str> = entries.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, ["h1", "h2", "h3", "h4", "h5", "h6"]);
+    }
+
+    fn attribute_names(fixture: &str) -> Vec<String> {
+        let html = std::fs::read_to_string(format!("test_docs/html_test/{}.html", fixture)).unwrap();
+        let context = page_context(&html, "Attributes");
+        HtmlEntriesFilter::new()
+            .get_entries(&context)
+            .into_iter()
+            .filter(|(_, _, entry_type)| entry_type == "Attributes")
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_attribute_entries_across_markup_generations() {
+        let expected = ["accept (attribute)", "align (attribute)", "autocomplete (attribute)"];
+        // 当前的 MDN：单元格中有换行、包裹元素和图标，全局属性用链接表示
+        assert_eq!(attribute_names("attributes_current"), expected);
+        // 旧版的 MDN：表头在 tbody 中，全局属性为纯文本
+        assert_eq!(attribute_names("attributes_legacy"), expected);
+    }
+
+    #[test]
+    fn test_attribute_columns_follow_header_row() {
+        // 属性名为行表头（th），表格前还有说明行，"Elements" 列按表头行的位置读取
+        let html = concat!(
+            "<h1>Attributes</h1><table>",
+            "<tr><th colspan=\"3\">HTML attributes</th></tr>",
+            "<tr><th>Attribute name</th><th>Elements</th><th>Description</th></tr>",
+            "<tr><th scope=\"row\"><code>accept</code></th><td><code>&lt;input&gt;</code></td><td>Hint</td></tr>",
+            "<tr><th scope=\"row\"><code>class</code></th><td><a href=\"/en-US/docs/Web/HTML/Global_attributes\">Global attribute</a></td><td>Classes</td></tr>",
+            "</table>",
+        );
+        let entries = HtmlEntriesFilter::new().get_entries(&page_context(html, "Attributes"));
+        let names: Vec<&str> = entries
+            .iter()
+            .filter(|(_, _, entry_type)| entry_type == "Attributes")
+            .map(|(name, _, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["accept (attribute)"]);
+    }
+
+    #[test]
+    fn test_attribute_entry_ids() {
+        let html = std::fs::read_to_string("test_docs/html_test/attributes_current.html").unwrap();
+        let entries = HtmlEntriesFilter::new().get_entries(&page_context(&html, "Attributes"));
+        assert!(entries.contains(&(
+            "accept (attribute)".to_string(),
+            "accept-(attribute)".to_string(),
+            "Attributes".to_string()
+        )));
+    }
+}
@@ -1,5 +0,0 @@
-//! HTML过滤器模块
-
-mod entries;
-
-pub use entries::HtmlEntriesFilter;
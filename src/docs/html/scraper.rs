@@ -4,8 +4,10 @@
 //! 参考文件: lib/docs/scrapers/mdn/html.rb
 
 use crate::core::error::Result;
+use crate::core::instrumentable;
+use crate::core::scraper::url_scraper::START_SCRAPER_EVENT;
 use crate::core::scraper::{Scraper as CoreScraper, UrlScraper};
-use crate::docs::html::HtmlEntriesFilter;
+use crate::docs::html::{CleanHtmlFilter, HtmlEntriesFilter};
 use crate::core::filters::{HtmlCleanerFilter, UrlNormalizerFilter};
 use async_trait::async_trait;
 
@@ -31,15 +33,19 @@ impl HtmlScraper {
         // 过滤器：
         // 1. 清理无用标签
         let html_cleaner = Box::new(HtmlCleanerFilter::new());
-        // 2. 只允许页面和图片链接
+        // 2. 规范化 MDN 提示框等页面结构
+        let mdn_cleaner = Box::new(CleanHtmlFilter::new());
+        // 3. 只允许页面和图片链接
         let html_entries = Box::new(HtmlEntriesFilter::new());
-        // 3. 规范化链接
+        // 4. 规范化链接
         let url_normalizer = Box::new(UrlNormalizerFilter::new(base_url, "/docs/html/"));
 
         // 组合过滤器和初始路径
         scraper = scraper
+            .with_rate_limit(crate::docs::MDN_RATE_LIMIT)
             .with_initial_paths(initial_paths)
             .with_filter(html_cleaner)
+            .with_filter(mdn_cleaner)
             .with_filter(url_normalizer)
             .with_filter(html_entries);
 
@@ -58,7 +64,11 @@ impl CoreScraper for HtmlScraper {
     }
 
     async fn run(&mut self) -> Result<()> {
-        println!("开始抓取HTML文档...");
+        instrumentable::message(START_SCRAPER_EVENT, "开始抓取HTML文档...");
         self.scraper.run().await
     }
+
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(&mut self.scraper)
+    }
 }
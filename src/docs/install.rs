@@ -0,0 +1,223 @@
+//! 从远程文档目录安装单个文档
+//!
+//! 远程地址（HTTP 地址或本地目录）的布局与文档根目录相同：根目录下有
+//! `docs manifest` 生成的 manifest.json，每个文档的文件位于 `<doc_dir>/` 下。
+//! 安装时先读取 manifest.json 找到文档，再下载 index.json、db.json 和
+//! meta.json（可选），逐个与清单中的大小比较。文件先写入
+//! `.<doc_dir>.partial`，全部校验通过后才重命名为文档目录，下载中断或校验
+//! 失败不会留下只安装了一半的文档。
+
+use super::mirror::fetch;
+use super::package::{checked_dirname, reset_dir};
+use super::registry::MANIFEST_FILENAME;
+use crate::core::doc::{DB_FILENAME, INDEX_FILENAME, META_FILENAME};
+use crate::core::error::{Error, Result};
+use crate::storage::json;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// 清单中的单个文档
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ManifestDoc {
+    /// 文档名称
+    pub name: String,
+    /// 文档别名
+    pub slug: String,
+    /// 文档版本
+    #[serde(default)]
+    pub version: String,
+    /// 文档语言，默认语言为空
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// index.json 的大小（字节）
+    pub index_size: u64,
+    /// db.json 的大小（字节）
+    pub db_size: u64,
+}
+
+impl ManifestDoc {
+    /// 文档目录名（`slug[~version][@locale]`）
+    pub fn doc_dir(&self) -> String {
+        let dir = if self.version.is_empty() {
+            self.slug.clone()
+        } else {
+            format!("{}~{}", self.slug, self.version)
+        };
+        match &self.locale {
+            Some(locale) => format!("{}@{}", dir, locale),
+            None => dir,
+        }
+    }
+}
+
+/// manifest.json 的内容
+#[derive(Debug, Deserialize)]
+struct RemoteManifest {
+    docs: Vec<ManifestDoc>,
+}
+
+/// 在清单中查找文档
+///
+/// `slug` 可以是别名或完整的目录名。指定 `version` 时只匹配该版本；
+/// 未指定时优先匹配目录名完全相同的文档，仍有多个候选时返回错误并列出可选版本。
+pub fn find_manifest_doc<'a>(
+    docs: &'a [ManifestDoc],
+    slug: &str,
+    version: Option<&str>,
+) -> Result<&'a ManifestDoc> {
+    let candidates: Vec<&ManifestDoc> = docs
+        .iter()
+        .filter(|doc| match version {
+            Some(version) => doc.slug == slug && doc.version == version,
+            None => doc.slug == slug || doc.doc_dir() == slug,
+        })
+        .collect();
+
+    if let Some(doc) = candidates.iter().find(|doc| doc.doc_dir() == slug) {
+        return Ok(doc);
+    }
+    match candidates.as_slice() {
+        [] => Err(Error::Doc(match version {
+            Some(version) => format!("清单中没有文档 {} 的版本 {}", slug, version),
+            None => format!("清单中没有文档 {}", slug),
+        })),
+        [doc] => Ok(doc),
+        _ => Err(Error::Doc(format!(
+            "清单中有多个 {}，请用 --doc-version 指定版本: {}",
+            slug,
+            candidates
+                .iter()
+                .map(|doc| doc.doc_dir())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// 从远程文档目录安装文档，返回清单中的文档
+///
+/// 已安装的同名文档在新文档全部下载并校验后才被替换。
+pub async fn install_from_manifest(
+    base: &str,
+    docs_root: &Path,
+    slug: &str,
+    version: Option<&str>,
+) -> Result<ManifestDoc> {
+    let manifest: RemoteManifest = json::parse_json(&fetch(base, MANIFEST_FILENAME).await?, Path::new(MANIFEST_FILENAME))?;
+    let doc = find_manifest_doc(&manifest.docs, slug, version)?.clone();
+    let doc_dir = checked_dirname(&doc.doc_dir())?;
+
+    fs::create_dir_all(docs_root)?;
+    let staging = docs_root.join(format!(".{}.partial", doc_dir));
+    reset_dir(&staging)?;
+    if let Err(e) = download_files(base, &doc, &doc_dir, &staging).await {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    let target = docs_root.join(&doc_dir);
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::rename(&staging, &target)?;
+    Ok(doc)
+}
+
+/// 下载文档的文件到 `staging` 并校验大小
+async fn download_files(base: &str, doc: &ManifestDoc, doc_dir: &str, staging: &Path) -> Result<()> {
+    for (file, size) in [(INDEX_FILENAME, doc.index_size), (DB_FILENAME, doc.db_size)] {
+        let bytes = fetch(base, &format!("{}/{}", doc_dir, file)).await?;
+        if bytes.len() as u64 != size {
+            return Err(Error::Doc(format!(
+                "{}/{} 大小不符: 应为 {}，实际为 {}",
+                doc_dir,
+                file,
+                size,
+                bytes.len()
+            )));
+        }
+        fs::write(staging.join(file), bytes)?;
+    }
+
+    // meta.json 不是必需的，清单中也没有记录它的大小
+    match fetch(base, &format!("{}/{}", doc_dir, META_FILENAME)).await {
+        Ok(bytes) => {
+            json::parse_json::<serde_json::Value>(&bytes, &Path::new(doc_dir).join(META_FILENAME))?;
+            fs::write(staging.join(META_FILENAME), bytes)?;
+        }
+        Err(Error::HttpError(404)) => {}
+        Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::DocRegistry;
+    use tempfile::TempDir;
+
+    const INDEX: &str = r#"{"entries":[{"name":"Guide","path":"guide","type":"Guides"}],"types":[]}"#;
+    const DB: &str = r#"{"guide": "<p>guide</p>"}"#;
+
+    fn manifest(db_size: usize) -> String {
+        format!(
+            r#"{{"docs":[
+                {{"name":"Babel","slug":"babel","version":"6","locale":null,"db_size":{db},"index_size":{index}}},
+                {{"name":"Babel","slug":"babel","version":"7","locale":null,"db_size":{db},"index_size":{index}}}
+            ]}}"#,
+            db = db_size,
+            index = INDEX.len()
+        )
+    }
+
+    async fn mock_doc(server: &mut mockito::Server, db_size: usize) {
+        server.mock("GET", "/manifest.json").with_body(manifest(db_size)).create_async().await;
+        server.mock("GET", "/babel~7/index.json").with_body(INDEX).create_async().await;
+        server.mock("GET", "/babel~7/db.json").with_body(DB).create_async().await;
+        server.mock("GET", "/babel~7/meta.json").with_status(404).create_async().await;
+    }
+
+    #[test]
+    fn test_find_manifest_doc() {
+        let docs: RemoteManifest = serde_json::from_str(&manifest(DB.len())).unwrap();
+        assert!(find_manifest_doc(&docs.docs, "babel", None).is_err());
+        assert_eq!(find_manifest_doc(&docs.docs, "babel", Some("7")).unwrap().doc_dir(), "babel~7");
+        assert_eq!(find_manifest_doc(&docs.docs, "babel~6", None).unwrap().version, "6");
+        assert!(find_manifest_doc(&docs.docs, "babel", Some("8")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_install_from_manifest() {
+        let mut server = mockito::Server::new_async().await;
+        mock_doc(&mut server, DB.len()).await;
+
+        let root = TempDir::new().unwrap();
+        let doc = install_from_manifest(&server.url(), root.path(), "babel", Some("7")).await.unwrap();
+        assert_eq!(doc.doc_dir(), "babel~7");
+        assert!(!root.path().join(".babel~7.partial").exists());
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[root.path()]).unwrap();
+        assert_eq!(registry.find_with_version("babel", "7").unwrap().entries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_size_mismatch_keeps_installed_doc() {
+        let mut server = mockito::Server::new_async().await;
+        mock_doc(&mut server, DB.len() + 1).await;
+
+        let root = TempDir::new().unwrap();
+        let installed = root.path().join("babel~7");
+        fs::create_dir_all(&installed).unwrap();
+        fs::write(installed.join(INDEX_FILENAME), r#"{"entries":[],"types":[]}"#).unwrap();
+        fs::write(installed.join(DB_FILENAME), "{}").unwrap();
+
+        let error = install_from_manifest(&server.url(), root.path(), "babel~7", None).await.unwrap_err();
+        assert!(error.to_string().contains("大小不符"), "{}", error);
+        assert_eq!(fs::read_to_string(installed.join(DB_FILENAME)).unwrap(), "{}");
+        assert!(!root.path().join(".babel~7.partial").exists());
+    }
+}
@@ -2,7 +2,8 @@
 //! 严格按照原版Ruby实现
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use crate::docs::html::CleanHtmlFilter;
 use scraper::Html;
 use std::any::Any;
 
@@ -17,7 +18,7 @@ impl JavaScriptCleanHtmlFilter {
 }
 
 impl Filter for JavaScriptCleanHtmlFilter {
-    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
         let document = Html::parse_fragment(html);
         let mut output = String::new();
 
@@ -45,7 +46,9 @@ impl Filter for JavaScriptCleanHtmlFilter {
         if output.is_empty() {
             output = html.to_string();
         }
-        Ok(output)
+
+        // 3. 规范化提示框
+        Ok(FilterOutput::new(CleanHtmlFilter::new().normalize_callouts(&output)))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
@@ -60,3 +63,22 @@ impl Filter for JavaScriptCleanHtmlFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notecards_survive() {
+        let html = r#"<div class="section"><p>Intro</p><div class="notecard warning"><span class="icon icon-warning"></span><p><strong>Warning:</strong> Never use <code>eval()</code>!</p></div></div>"#;
+        let result = JavaScriptCleanHtmlFilter::new()
+            .apply_v2(html, &FilterContext::default())
+            .unwrap()
+            .html;
+
+        assert_eq!(
+            result,
+            r#"<p>Intro</p><div class="_warning"><p><strong>Warning:</strong> Never use <code>eval()</code>!</p></div>"#
+        );
+    }
+}
@@ -2,7 +2,7 @@
 //! 严格按照原版Ruby实现
 
 use crate::core::error::Result;
-use crate::core::scraper::filter::{Filter, FilterContext};
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
 use scraper::Html;
 use std::any::Any;
 
@@ -51,18 +51,19 @@ impl JavaScriptEntriesFilter {
 }
 
 impl Filter for JavaScriptEntriesFilter {
-    fn apply(&self, html: &str, context: &mut FilterContext) -> Result<String> {
-        Ok(html.to_string())
+    fn apply_v2(&self, html: &str, _context: &FilterContext) -> Result<FilterOutput> {
+        Ok(FilterOutput::new(html))
     }
 
     fn box_clone(&self) -> Box<dyn Filter> {
-        Box::new(Self::new())
+        Box::new(Self { path_prefix: self.path_prefix.clone() })
     }
 
-    fn get_entries(&self, html: &str, context: &FilterContext) -> Vec<(String, String, String)> {
-        let doc = Html::parse_document(html);
-        let name = self.get_name(&doc, &context.current_path);
-        let entry_type = self.get_type(&doc);
+    fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+        // 条目以页面路径为准，`context.slug` 是整个文档的标识
+        let doc = context.html_doc();
+        let name = self.get_name(doc, &context.current_path);
+        let entry_type = self.get_type(doc);
         
         vec![(name, context.current_path.clone(), entry_type)]
     }
@@ -75,3 +76,34 @@ impl Filter for JavaScriptEntriesFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_use_page_path() {
+        let html = std::fs::read_to_string("test_docs/javascript_test/array.html").unwrap();
+        let mut context = FilterContext::new().with_html(&html);
+        // 抓取器把文档标识写入 slug，把页面路径写入 current_path
+        context.slug = "javascript".to_string();
+        context.current_path = "Reference/Global_Objects/Array".to_string();
+
+        assert_eq!(
+            JavaScriptEntriesFilter::new().get_entries(&context),
+            vec![(
+                "Array".to_string(),
+                "Reference/Global_Objects/Array".to_string(),
+                "Objects".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_box_clone_keeps_path_prefix() {
+        let filter = JavaScriptEntriesFilter::with_path_prefix("/docs/js/".to_string());
+        let cloned = filter.box_clone();
+        let cloned = cloned.as_any().downcast_ref::<JavaScriptEntriesFilter>().unwrap();
+        assert_eq!(cloned.path_prefix, "/docs/js/");
+    }
+}
@@ -1,7 +0,0 @@
-//! JavaScript过滤器模块
-
-mod clean_html;
-mod entries;
-
-pub use clean_html::JavaScriptCleanHtmlFilter;
-pub use entries::JavaScriptEntriesFilter;
@@ -3,20 +3,32 @@
 //! 严格按照原版 Ruby 实现转换的 JavaScript 文档抓取器
 //! 参考文件: lib/docs/scrapers/mdn/javaScript.rb
 
-use crate::core::error::Result;
-use crate::core::filters::{HtmlCleanerFilter, UrlNormalizerFilter};
+use crate::core::error::{Error, Result};
+use crate::core::instrumentable;
+use crate::core::scraper::url_scraper::START_SCRAPER_EVENT;
+use crate::core::filters::{AttributionFilter, HtmlCleanerFilter, UrlNormalizerFilter};
+use crate::core::http_fetch::{HttpFetch, ReqwestFetch};
 use crate::core::scraper::{Scraper as CoreScraper, UrlScraper};
+use crate::core::version_probe::{self, RegexProbe, VersionProbe};
 use crate::docs::javascript::JavaScriptEntriesFilter;
 use async_trait::async_trait;
-use chrono::{Datelike, NaiveDateTime};
-use reqwest::Client;
+use regex::Regex;
+use std::sync::Arc;
 
 /// JavaScript文档爬虫
 pub struct JavaScriptScraper {
     /// 基础爬虫
     scraper: UrlScraper,
+    /// 查询最新版本使用的 HTTP 实现
+    fetch: Arc<dyn HttpFetch>,
 }
 
+/// 查询最新版本的页面
+const VERSION_URL: &str = "https://developer.mozilla.org/en-US/docs/Web/JavaScript";
+
+/// 页面中没有更新时间时的版本
+const DEFAULT_VERSION: &str = "ES6";
+
 const LINKS: [(&str, &str); 2] = [
     (
         "home",
@@ -39,6 +51,7 @@ impl JavaScriptScraper {
         let mut scraper = UrlScraper::new("JavaScript", version, base_url, output_path)
             .with_root_title(ROOT_TITLE)
             .with_attribution(ATTRIBUTION)
+            .with_license(crate::docs::MDN_LICENSE)
             .with_string_links(
                 LINKS
                     .iter()
@@ -98,41 +111,44 @@ impl JavaScriptScraper {
 
         // 配置抓取器
         scraper = scraper
+            .with_rate_limit(crate::docs::MDN_RATE_LIMIT)
             .with_skip_paths(skip_paths)
             .with_skip_patterns(skip_patterns)
             .with_filter(html_cleaner)
             .with_filter(url_normalizer)
-            .with_filter(entries_filter);
+            .with_filter(entries_filter)
+            .with_filter(Box::new(AttributionFilter::new()));
+
+        Self {
+            scraper,
+            fetch: Arc::new(ReqwestFetch::new()),
+        }
+    }
 
-        Self { scraper }
+    /// 设置查询版本和抓取页面使用的 HTTP 实现
+    pub fn with_fetch(mut self, fetch: Arc<dyn HttpFetch>) -> Self {
+        self.scraper = self.scraper.with_fetch(fetch.clone());
+        self.fetch = fetch;
+        self
     }
 
     /// 获取最新版本
     pub async fn get_latest_version(&self) -> Result<String> {
-        // 获取MDN最新更新时间
-        let client = Client::new();
-        let response = client
-            .get("https://developer.mozilla.org/en-US/docs/Web/JavaScript")
-            .send()
-            .await?;
-
-        let html = response.text().await?;
-
-        // 简单解析最后更新时间
-        if let Some(pos) = html.find("\"dateModified\":") {
-            if let Some(end_pos) = html[pos..].find(",") {
-                let date_str = html[pos + 16..pos + end_pos - 1].trim_matches('"');
-                if let Ok(date) = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%dT%H:%M:%S%.fZ") {
-                    return Ok(format!("ES{}", date.year() - 2015 + 6));
-                }
-            }
+        match version_probe::latest_version(&version_probe(), self.fetch.as_ref()).await {
+            // 页面中没有更新时间时使用默认版本，请求失败时报错
+            Err(Error::Parse { .. }) => Ok(DEFAULT_VERSION.to_string()),
+            result => result,
         }
-
-        // 默认返回ES6
-        Ok("ES6".to_string())
     }
 }
 
+/// 由 MDN 页面的最后更新时间（`dateModified`）得到 ECMAScript 版本，ES6 发布于 2015 年
+fn version_probe() -> RegexProbe {
+    let pattern = Regex::new(r#""dateModified"\s*:\s*"(\d{4})-"#).unwrap();
+    RegexProbe::new(VERSION_URL, pattern)
+        .with_transform(|year| year.parse::<i32>().ok().map(|year| format!("ES{}", year - 2015 + 6)))
+}
+
 #[async_trait]
 impl CoreScraper for JavaScriptScraper {
     fn name(&self) -> &str {
@@ -144,7 +160,42 @@ impl CoreScraper for JavaScriptScraper {
     }
 
     async fn run(&mut self) -> Result<()> {
-        println!("开始抓取JavaScript文档...");
+        instrumentable::message(START_SCRAPER_EVENT, "开始抓取JavaScript文档...");
         self.scraper.run().await
     }
+
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(&mut self.scraper)
+    }
+
+    fn version_probe(&self) -> Option<Box<dyn VersionProbe>> {
+        Some(Box::new(version_probe()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch};
+
+    #[tokio::test]
+    async fn test_latest_version_from_date_modified() {
+        let fetch = Arc::new(MockFetch::new().with_response(
+            VERSION_URL,
+            FetchedResponse::html(r#"<script>{"dateModified":"2023-05-01T10:20:30.000Z","locale":"en-US"}</script>"#),
+        ));
+        let scraper = JavaScriptScraper::new("", "").with_fetch(fetch.clone());
+
+        assert_eq!(scraper.get_latest_version().await.unwrap(), "ES14");
+        assert_eq!(fetch.requested_urls(), [VERSION_URL]);
+
+        // 页面中没有更新时间时使用默认版本
+        let fetch = MockFetch::new().with_response(VERSION_URL, FetchedResponse::html("<h1>JavaScript</h1>"));
+        let scraper = JavaScriptScraper::new("", "").with_fetch(Arc::new(fetch));
+        assert_eq!(scraper.get_latest_version().await.unwrap(), DEFAULT_VERSION);
+
+        // 页面无法访问时报错，不会当作默认版本
+        let scraper = JavaScriptScraper::new("", "").with_fetch(Arc::new(MockFetch::new()));
+        assert_eq!(scraper.get_latest_version().await.unwrap_err().url(), Some(VERSION_URL));
+    }
 }
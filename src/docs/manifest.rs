@@ -0,0 +1,276 @@
+//! 文档清单的重新生成
+//!
+//! manifest.json 会在生成、下载、删除文档后重新生成，这些调用可能同时发生。
+//! 所有调用都通过 [`regenerate`] 进行：同一清单文件的生成由异步互斥锁串行化，
+//! 等待锁期间到达的请求合并为一次写入。写入本身通过临时文件和重命名完成
+//! （见 [`DocRegistry::refresh_manifest`]），服务器和命令行同时写入时，
+//! 清单文件也总是完整的。
+
+use super::DocRegistry;
+use crate::core::error::Result;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    /// 按清单目录区分的写入器
+    static ref WRITERS: Mutex<HashMap<PathBuf, Arc<ManifestWriter>>> = Mutex::new(HashMap::new());
+}
+
+/// 清单生成时间的来源，返回 UNIX 时间戳（秒），测试中可以替换为固定的时间
+pub type Clock = fn() -> i64;
+
+/// 使用系统时间的 [`Clock`]
+pub fn system_clock() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// manifest.json 的内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// 清单中的文档
+    pub docs: Vec<ManifestEntry>,
+    /// 生成时间（UNIX 时间戳）
+    pub generated_at: i64,
+}
+
+impl Manifest {
+    /// 所有文档的条目总数
+    pub fn entry_count(&self) -> usize {
+        self.docs.iter().map(|doc| doc.entry_count).sum()
+    }
+}
+
+/// 清单中的单个文档
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// 文档名称
+    pub name: String,
+    /// 文档别名
+    pub slug: String,
+    /// 文档版本
+    pub version: String,
+    /// 文档语言，默认语言为空
+    pub locale: Option<String>,
+    /// 文档发布版本
+    pub release: String,
+    /// 上次修改时间（UNIX 时间戳）
+    pub mtime: u64,
+    /// db.json 的大小（字节）
+    pub db_size: usize,
+    /// index.json 的大小（字节）
+    pub index_size: usize,
+    /// 索引中的条目数
+    pub entry_count: usize,
+    /// 每种类型的条目数，按类型名称排序
+    pub types: Vec<TypeCount>,
+    /// 索引无法统计时的说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// 一种类型的条目数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeCount {
+    /// 类型名称
+    pub name: String,
+    /// 条目数
+    pub count: usize,
+}
+
+/// 一次重新生成的结果
+#[derive(Debug, Clone)]
+pub struct ManifestRefresh {
+    /// 当前的清单，请求被合并时为合并后写入的清单
+    pub manifest: Manifest,
+    /// 本次调用是否实际写入
+    pub written: bool,
+}
+
+/// 单个清单文件的写入器
+#[derive(Debug, Default)]
+pub struct ManifestWriter {
+    /// 串行化写入
+    lock: tokio::sync::Mutex<()>,
+    /// 收到的请求数
+    requested: AtomicU64,
+    /// 已经由写入覆盖的请求数
+    completed: AtomicU64,
+    /// 最近一次写入的清单
+    latest: Mutex<Option<Manifest>>,
+}
+
+impl ManifestWriter {
+    /// 创建新的写入器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从 `roots` 加载文档并将清单写入 `output`
+    ///
+    /// 等待期间已有其他调用在本次请求之后完成写入时直接返回那次写入的清单，
+    /// 否则写入清单。
+    pub async fn regenerate<P: AsRef<Path>>(
+        &self,
+        roots: &[P],
+        output: &Path,
+        clock: Clock,
+    ) -> Result<ManifestRefresh> {
+        let ticket = self.requested.fetch_add(1, Ordering::SeqCst) + 1;
+        let _guard = self.lock.lock().await;
+        if self.completed.load(Ordering::SeqCst) >= ticket {
+            if let Some(manifest) = self.latest.lock().unwrap().clone() {
+                return Ok(ManifestRefresh { manifest, written: false });
+            }
+        }
+
+        // 在加载文档之前记下请求数，这些请求都能看到本次加载的结果
+        let covered = self.requested.load(Ordering::SeqCst);
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(roots)?;
+        let manifest = registry.refresh_manifest(output, clock)?;
+        *self.latest.lock().unwrap() = Some(manifest.clone());
+        self.completed.store(covered, Ordering::SeqCst);
+        Ok(ManifestRefresh { manifest, written: true })
+    }
+}
+
+/// 获取清单目录对应的写入器，同一目录总是返回同一个写入器
+pub fn writer_for(output: &Path) -> Arc<ManifestWriter> {
+    WRITERS
+        .lock()
+        .unwrap()
+        .entry(output.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+/// 重新生成 `output` 下的清单
+pub async fn regenerate<P: AsRef<Path>>(roots: &[P], output: &Path, clock: Clock) -> Result<ManifestRefresh> {
+    writer_for(output).regenerate(roots, output, clock).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::registry::MANIFEST_FILENAME;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_regenerations() {
+        let docs = TempDir::new().unwrap();
+        for dir in ["css", "html", "react~18"] {
+            let dir = docs.path().join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+            fs::write(dir.join("db.json"), "{}").unwrap();
+        }
+        let roots = vec![docs.path().to_path_buf()];
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let roots = roots.clone();
+                let output = docs.path().to_path_buf();
+                tokio::spawn(async move { regenerate(&roots, &output, system_clock).await.unwrap().written })
+            })
+            .collect();
+        let mut writes = 0;
+        for task in tasks {
+            writes += task.await.unwrap() as usize;
+        }
+        assert!((1..=10).contains(&writes));
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(docs.path().join(MANIFEST_FILENAME)).unwrap()).unwrap();
+        let mut slugs: Vec<&str> = manifest["docs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|doc| doc["slug"].as_str().unwrap())
+            .collect();
+        slugs.sort();
+        assert_eq!(slugs, ["css", "html", "react"]);
+
+        // 没有遗留的临时文件
+        let leftovers: Vec<_> = fs::read_dir(docs.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_waiting_requests_are_coalesced() {
+        let docs = TempDir::new().unwrap();
+        let roots = vec![docs.path().to_path_buf()];
+        let writer = ManifestWriter::new();
+
+        // 持有锁期间到达的请求由锁释放后的第一次写入一并完成
+        let guard = writer.lock.lock().await;
+        let first = writer.regenerate(&roots, docs.path(), system_clock);
+        let second = writer.regenerate(&roots, docs.path(), system_clock);
+        let third = writer.regenerate(&roots, docs.path(), system_clock);
+        let pending = async {
+            tokio::task::yield_now().await;
+            drop(guard);
+        };
+        let (_, first, second, third) = tokio::join!(pending, first, second, third);
+        let (first, second, third) = (first.unwrap(), second.unwrap(), third.unwrap());
+        assert_eq!([first.written, second.written, third.written], [true, false, false]);
+        // 合并的请求得到合并后写入的清单
+        assert_eq!(second.manifest, first.manifest);
+        assert_eq!(third.manifest, first.manifest);
+
+        // 之后的请求照常写入
+        assert!(writer.regenerate(&roots, docs.path(), system_clock).await.unwrap().written);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_preserves_manifest_contents() {
+        let docs = TempDir::new().unwrap();
+        let dir = docs.path().join("css");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("index.json"),
+            r#"{"entries":[{"name":"color","path":"color","type":"Properties"}],"types":[]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("db.json"), "{}").unwrap();
+        fs::write(dir.join("meta.json"), r#"{"name":"CSS"}"#).unwrap();
+        let roots = vec![docs.path().to_path_buf()];
+
+        // 命令行 `docs manifest` 和安装、删除后的更新都经过 regenerate
+        let refresh = regenerate(&roots, docs.path(), || 1_700_000_000).await.unwrap();
+        assert!(refresh.written);
+        assert_eq!(refresh.manifest.generated_at, 1_700_000_000);
+        assert_eq!(refresh.manifest.docs.len(), 1);
+        assert_eq!(refresh.manifest.entry_count(), 1);
+
+        // 写入的文件与返回的清单一致，字段与之前的清单相同
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(docs.path().join(MANIFEST_FILENAME)).unwrap()).unwrap();
+        assert_eq!(written, serde_json::to_value(&refresh.manifest).unwrap());
+        let css = &written["docs"][0];
+        assert_eq!(css["name"], "CSS");
+        assert_eq!(css["slug"], "css");
+        assert_eq!(css["version"], "");
+        assert!(css["locale"].is_null());
+        assert_eq!(css["entry_count"], 1);
+        assert_eq!(css["types"], serde_json::json!([{"name": "Properties", "count": 1}]));
+        assert!(css.get("warning").is_none());
+        for field in ["release", "mtime", "db_size", "index_size"] {
+            assert!(css.get(field).is_some(), "{}", field);
+        }
+        assert_eq!(written["generated_at"], 1_700_000_000);
+
+        // 远程安装读取的是同样的清单
+        let installable: Vec<crate::docs::install::ManifestDoc> =
+            serde_json::from_value(written["docs"].clone()).unwrap();
+        assert_eq!(installable[0].doc_dir(), "css");
+    }
+}
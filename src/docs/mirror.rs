@@ -0,0 +1,691 @@
+//! 文档镜像
+//!
+//! 将已安装的文档打包为 `<doc>.tar.gz`，并生成 `SHA256SUMS` 和描述每个
+//! 归档的 `mirror.json`。其他人可以从镜像地址（HTTP 或本地目录）读取
+//! `mirror.json`，校验摘要后再解压安装文档。
+//!
+//! 只读服务器使用 [`sync_from_mirror`] 同步：每个版本解压到
+//! `.releases/<doc>.<digest>`，文档目录是指向当前版本的符号链接，所有文档
+//! 解压完成后再逐个替换链接。替换是原子的，服务器在替换前后都能读到完整的文档。
+
+use super::package::checked_dirname;
+use super::{DocRegistry, Documentation};
+use crate::core::error::{Error, Result};
+use crate::core::scraper::page_log::WAL_FILENAME;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 镜像索引文件名
+pub const MIRROR_INDEX_FILENAME: &str = "mirror.json";
+
+/// 校验和文件名
+pub const CHECKSUMS_FILENAME: &str = "SHA256SUMS";
+
+/// 同步的文档版本所在的目录（位于文档根目录下）
+pub const RELEASES_DIRNAME: &str = ".releases";
+
+/// 每个文档保留的版本数（当前版本和上一个版本）
+const KEPT_RELEASES: usize = 2;
+
+/// 版本目录名中摘要的长度
+const RELEASE_DIGEST_LEN: usize = 16;
+
+/// 镜像中的单个文档归档
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorArchive {
+    /// 文档别名
+    pub slug: String,
+    /// 文档版本
+    pub version: String,
+    /// 文档语言（None 表示默认语言）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// 归档文件名
+    pub file: String,
+    /// 归档大小（字节）
+    pub size: u64,
+    /// 归档的 SHA-256 摘要（十六进制）
+    pub digest: String,
+    /// 文档修改时间
+    pub mtime: u64,
+}
+
+impl MirrorArchive {
+    /// 文档目录名（`slug[~version][@locale]`）
+    pub fn doc_dir(&self) -> String {
+        let dir = if self.version.is_empty() {
+            self.slug.clone()
+        } else {
+            format!("{}~{}", self.slug, self.version)
+        };
+        match &self.locale {
+            Some(locale) => format!("{}@{}", dir, locale),
+            None => dir,
+        }
+    }
+
+    /// 检查来自远程 mirror.json 的目录名和归档文件名，返回文档目录名
+    ///
+    /// 目录名的规则见 [`checked_dirname`]，归档文件名不能包含路径分隔符或 `..`。
+    /// 在下载和改动文件系统之前调用，镜像中的名称不能指向文档根目录之外。
+    pub fn checked_doc_dir(&self) -> Result<String> {
+        if self.file.is_empty() || self.file.contains(['/', '\\']) || self.file.contains("..") {
+            return Err(Error::Doc(format!("无效的归档文件名: {:?}", self.file)));
+        }
+        checked_dirname(&self.doc_dir())
+    }
+}
+
+/// 镜像索引
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorIndex {
+    /// 生成时间
+    pub generated_at: i64,
+    /// 所有归档
+    pub archives: Vec<MirrorArchive>,
+}
+
+/// 单个文档处理失败的记录
+#[derive(Debug, Clone)]
+pub struct MirrorFailure {
+    /// 文档目录名
+    pub doc: String,
+    /// 错误信息
+    pub error: String,
+}
+
+/// 生成或安装镜像的结果
+#[derive(Debug, Default)]
+pub struct MirrorReport {
+    /// 成功处理的归档
+    pub archives: Vec<MirrorArchive>,
+    /// 处理失败的文档
+    pub failures: Vec<MirrorFailure>,
+    /// 同步时已是最新版本而跳过的归档
+    pub unchanged: Vec<MirrorArchive>,
+}
+
+/// 计算数据的 SHA-256 摘要
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// 将注册表中的所有文档打包到输出目录
+///
+/// 单个文档打包失败不会中断其他文档，失败记录在返回结果中。
+/// 只有成功打包的文档会写入 `SHA256SUMS` 和 `mirror.json`。
+pub fn build_mirror(registry: &DocRegistry, output: &Path) -> Result<MirrorReport> {
+    fs::create_dir_all(output)?;
+
+    let mut report = MirrorReport::default();
+    for doc in registry.all() {
+        match package_archive(doc, output) {
+            Ok(archive) => report.archives.push(archive),
+            Err(e) => report.failures.push(MirrorFailure {
+                doc: doc.path(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    report.archives.sort_by(|a, b| a.file.cmp(&b.file));
+
+    let checksums: String = report
+        .archives
+        .iter()
+        .map(|archive| format!("{}  {}\n", archive.digest, archive.file))
+        .collect();
+    fs::write(output.join(CHECKSUMS_FILENAME), checksums)?;
+
+    let index = MirrorIndex {
+        generated_at: chrono::Utc::now().timestamp(),
+        archives: report.archives.clone(),
+    };
+    fs::write(
+        output.join(MIRROR_INDEX_FILENAME),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+
+    Ok(report)
+}
+
+/// 将单个文档目录打包为 `<doc>.tar.gz`
+pub fn package_archive(doc: &Documentation, output: &Path) -> Result<MirrorArchive> {
+    let doc_dir = doc.dir();
+    if doc_dir.join(WAL_FILENAME).exists() {
+        return Err(Error::Doc(format!("抓取尚未结束: {:?}", doc_dir)));
+    }
+
+    let file = format!("{}.tar.gz", doc.path());
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    builder.append_dir_all(".", &doc_dir)?;
+    let bytes = builder.into_inner()?.finish()?;
+
+    fs::write(output.join(&file), &bytes)?;
+
+    Ok(MirrorArchive {
+        slug: doc.slug.clone(),
+        version: doc.version.clone(),
+        locale: doc.locale.clone(),
+        file,
+        size: bytes.len() as u64,
+        digest: sha256_hex(&bytes),
+        mtime: doc.mtime,
+    })
+}
+
+/// 从镜像安装文档到文档根目录
+///
+/// `docs` 为空时安装镜像中的所有文档，否则只安装别名或目录名匹配的文档。
+/// 每个归档在解压前校验大小和摘要，单个文档失败不会中断其他文档。
+pub async fn install_from_mirror(
+    mirror: &str,
+    docs_root: &Path,
+    docs: &[String],
+) -> Result<MirrorReport> {
+    let index: MirrorIndex = serde_json::from_slice(&fetch(mirror, MIRROR_INDEX_FILENAME).await?)?;
+    fs::create_dir_all(docs_root)?;
+
+    let mut report = MirrorReport::default();
+    for archive in index.archives {
+        let wanted = docs.is_empty()
+            || docs
+                .iter()
+                .any(|doc| *doc == archive.slug || *doc == archive.doc_dir());
+        if !wanted {
+            continue;
+        }
+
+        let result = match archive.checked_doc_dir() {
+            Ok(_) => match fetch(mirror, &archive.file).await {
+                Ok(bytes) => install_archive(&archive, &bytes, docs_root),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => report.archives.push(archive),
+            Err(e) => report.failures.push(MirrorFailure {
+                doc: archive.doc_dir(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// 校验归档后解压到文档目录，替换已安装的同名文档
+pub fn install_archive(archive: &MirrorArchive, bytes: &[u8], docs_root: &Path) -> Result<()> {
+    let doc_dir = archive.checked_doc_dir()?;
+    verify_archive(archive, bytes)?;
+    unpack_archive(bytes, docs_root, &doc_dir)
+}
+
+/// 校验归档的大小和摘要
+fn verify_archive(archive: &MirrorArchive, bytes: &[u8]) -> Result<()> {
+    if bytes.len() as u64 != archive.size {
+        return Err(Error::Doc(format!(
+            "{} 大小不符: 应为 {}，实际为 {}",
+            archive.file,
+            archive.size,
+            bytes.len()
+        )));
+    }
+    let digest = sha256_hex(bytes);
+    if digest != archive.digest {
+        return Err(Error::Doc(format!(
+            "{} 摘要不符: 应为 {}，实际为 {}",
+            archive.file, archive.digest, digest
+        )));
+    }
+    Ok(())
+}
+
+/// 从镜像同步文档，正在运行的服务器在切换前一直使用旧版本
+///
+/// `docs` 为空时同步镜像中的所有文档。摘要与当前版本相同的归档跳过下载；
+/// 其余归档校验后解压到 `.releases` 中，全部解压完成后才替换文档目录的链接，
+/// 并删除除当前和上一个版本之外的旧版本。单个文档失败不会中断其他文档。
+pub async fn sync_from_mirror(
+    mirror: &str,
+    docs_root: &Path,
+    docs: &[String],
+) -> Result<MirrorReport> {
+    let index: MirrorIndex = serde_json::from_slice(&fetch(mirror, MIRROR_INDEX_FILENAME).await?)?;
+    let releases = docs_root.join(RELEASES_DIRNAME);
+    fs::create_dir_all(&releases)?;
+
+    let mut report = MirrorReport::default();
+    let mut staged = Vec::new();
+    for archive in index.archives {
+        let wanted = docs.is_empty()
+            || docs
+                .iter()
+                .any(|doc| *doc == archive.slug || *doc == archive.doc_dir());
+        if !wanted {
+            continue;
+        }
+
+        let (doc_dir, release) = match archive
+            .checked_doc_dir()
+            .and_then(|doc_dir| release_name(&archive, &doc_dir).map(|release| (doc_dir, release)))
+        {
+            Ok(names) => names,
+            Err(e) => {
+                report.failures.push(MirrorFailure {
+                    doc: archive.doc_dir(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if current_release(docs_root, &doc_dir).as_deref() == Some(release.as_str()) {
+            report.unchanged.push(archive);
+            continue;
+        }
+
+        let result = match fetch(mirror, &archive.file).await {
+            Ok(bytes) => verify_archive(&archive, &bytes)
+                .and_then(|()| unpack_archive(&bytes, &releases, &release))
+                // 解压出的目录保留了归档中的修改时间，改为解压时间以便按新旧清理
+                .and_then(|()| {
+                    fs::File::open(releases.join(&release))?.set_modified(std::time::SystemTime::now())?;
+                    Ok(())
+                }),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => staged.push((archive, doc_dir, release)),
+            Err(e) => report.failures.push(MirrorFailure {
+                doc: doc_dir,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    // 所有文档解压完成后再切换，减少新旧版本混用的时间
+    for (archive, doc_dir, release) in staged {
+        match promote_release(docs_root, &doc_dir, &release) {
+            Ok(()) => {
+                if let Err(e) = prune_releases(docs_root, &doc_dir) {
+                    log::warn!("无法清理 {} 的旧版本: {}", doc_dir, e);
+                }
+                report.archives.push(archive);
+            }
+            Err(e) => report.failures.push(MirrorFailure {
+                doc: doc_dir,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// 归档对应的版本目录名：`<doc_dir>.<摘要前 16 位>`
+///
+/// `doc_dir` 应已经过 [`MirrorArchive::checked_doc_dir`] 检查；摘要来自远程镜像，
+/// 不是 64 位十六进制时返回错误。解压前还会用 [`verify_archive`] 核对摘要。
+fn release_name(archive: &MirrorArchive, doc_dir: &str) -> Result<String> {
+    if archive.digest.len() != 64 || !archive.digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::Doc(format!("{} 的摘要无效: {:?}", archive.file, archive.digest)));
+    }
+    Ok(format!("{}.{}", doc_dir, &archive.digest[..RELEASE_DIGEST_LEN]))
+}
+
+/// 文档目录当前指向的版本目录名，不是符号链接时返回 None
+fn current_release(docs_root: &Path, doc_dir: &str) -> Option<String> {
+    let target = fs::read_link(docs_root.join(doc_dir)).ok()?;
+    target.file_name()?.to_str().map(str::to_string)
+}
+
+/// 将文档目录切换到指定版本
+///
+/// 先在文档根目录中创建指向新版本的临时链接，再重命名覆盖文档目录，
+/// 读取文档目录的进程只会看到旧版本或新版本。文档目录是普通目录时
+/// （之前用 `docs download` 安装），先把它移到 `.releases` 中作为旧版本，
+/// 这一次切换期间文档会短暂不可用。
+#[cfg(unix)]
+fn promote_release(docs_root: &Path, doc_dir: &str, release: &str) -> Result<()> {
+    let link = docs_root.join(doc_dir);
+    let temp_link = docs_root.join(format!(".{}.link", doc_dir));
+    if fs::symlink_metadata(&temp_link).is_ok() {
+        fs::remove_file(&temp_link)?;
+    }
+    std::os::unix::fs::symlink(Path::new(RELEASES_DIRNAME).join(release), &temp_link)?;
+
+    let is_plain_dir = fs::symlink_metadata(&link).is_ok_and(|m| m.file_type().is_dir());
+    if is_plain_dir {
+        let legacy = docs_root.join(RELEASES_DIRNAME).join(format!("{}.legacy", doc_dir));
+        log::warn!("{:?} 不是符号链接，移到 {:?} 后切换", link, legacy);
+        if legacy.exists() {
+            fs::remove_dir_all(&legacy)?;
+        }
+        fs::rename(&link, &legacy)?;
+    }
+    fs::rename(&temp_link, &link)?;
+    Ok(())
+}
+
+/// 不支持符号链接的平台直接替换文档目录
+#[cfg(not(unix))]
+fn promote_release(docs_root: &Path, doc_dir: &str, release: &str) -> Result<()> {
+    let release = docs_root.join(RELEASES_DIRNAME).join(release);
+    let link = docs_root.join(doc_dir);
+    if link.exists() {
+        fs::remove_dir_all(&link)?;
+    }
+    fs::rename(&release, &link)?;
+    Ok(())
+}
+
+/// 删除文档除当前和上一个版本之外的旧版本，按修改时间判断新旧
+fn prune_releases(docs_root: &Path, doc_dir: &str) -> Result<()> {
+    let releases = docs_root.join(RELEASES_DIRNAME);
+    let current = current_release(docs_root, doc_dir);
+    let prefix = format!("{}.", doc_dir);
+    let mut old: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(&releases)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                name.strip_prefix(&prefix)
+                    .is_some_and(|rest| !rest.contains('.') && Some(name) != current.as_deref())
+            })
+        })
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    old.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in old.into_iter().skip(KEPT_RELEASES - 1) {
+        fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+/// 将 tar.gz 归档解压为文档根目录下的 `doc_dir`，替换已安装的同名文档
+pub(crate) fn unpack_archive(bytes: &[u8], docs_root: &Path, doc_dir: &str) -> Result<()> {
+    // 先解压到临时目录，成功后再替换，避免留下解压一半的文档
+    let staging = docs_root.join(format!(".{}.partial", doc_dir));
+    let doc_dir = docs_root.join(doc_dir);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    if let Err(e) = tar::Archive::new(GzDecoder::new(bytes)).unpack(&staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e.into());
+    }
+
+    if doc_dir.exists() {
+        fs::remove_dir_all(&doc_dir)?;
+    }
+    fs::rename(&staging, &doc_dir)?;
+    Ok(())
+}
+
+/// 读取镜像中的文件，支持 HTTP 地址和本地目录
+pub(super) async fn fetch(mirror: &str, file: &str) -> Result<Vec<u8>> {
+    if mirror.starts_with("http://") || mirror.starts_with("https://") {
+        let url = format!("{}/{}", mirror.trim_end_matches('/'), file);
+        let response = reqwest::get(&url).await?;
+        if !response.status().is_success() {
+            return Err(Error::HttpError(response.status().as_u16()));
+        }
+        Ok(response.bytes().await?.to_vec())
+    } else {
+        let path: PathBuf = Path::new(mirror.trim_start_matches("file://")).join(file);
+        Ok(fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::registry::write_doc;
+    use tempfile::TempDir;
+
+    /// 生成带有一个页面的文档目录
+    fn write_guide_doc(root: &Path, dirname: &str, name: &str) {
+        write_doc(root, dirname, name);
+        let dir = root.join(dirname);
+        fs::create_dir_all(dir.join("guide")).unwrap();
+        fs::write(dir.join("db.json"), r#"{"guide": "<p>guide</p>"}"#).unwrap();
+        fs::write(dir.join("guide").join("index.html"), format!("<h1>{}</h1>", name)).unwrap();
+    }
+
+    /// 用两个文档生成镜像目录
+    fn fixture_mirror() -> (TempDir, TempDir) {
+        let docs = TempDir::new().unwrap();
+        write_guide_doc(docs.path(), "babel~7", "Babel");
+        write_guide_doc(docs.path(), "css", "CSS");
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+
+        let mirror = TempDir::new().unwrap();
+        let report = build_mirror(&registry, mirror.path()).unwrap();
+        assert_eq!(report.archives.len(), 2);
+        assert!(report.failures.is_empty());
+        (docs, mirror)
+    }
+
+    #[test]
+    fn test_build_mirror_writes_checksums_and_index() {
+        let (_docs, mirror) = fixture_mirror();
+
+        let checksums = fs::read_to_string(mirror.path().join(CHECKSUMS_FILENAME)).unwrap();
+        let lines: Vec<&str> = checksums.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let (digest, file) = line.split_once("  ").unwrap();
+            let bytes = fs::read(mirror.path().join(file)).unwrap();
+            assert_eq!(sha256_hex(&bytes), digest);
+        }
+
+        let index: MirrorIndex =
+            serde_json::from_str(&fs::read_to_string(mirror.path().join(MIRROR_INDEX_FILENAME)).unwrap())
+                .unwrap();
+        let babel = index.archives.iter().find(|a| a.slug == "babel").unwrap();
+        assert_eq!(babel.version, "7");
+        assert_eq!(babel.file, "babel~7.tar.gz");
+        assert_eq!(babel.size, fs::metadata(mirror.path().join(&babel.file)).unwrap().len());
+    }
+
+    #[test]
+    fn test_failed_doc_does_not_abort_packaging() {
+        let docs = TempDir::new().unwrap();
+        write_guide_doc(docs.path(), "babel~7", "Babel");
+        write_guide_doc(docs.path(), "css", "CSS");
+        fs::write(docs.path().join("css").join(WAL_FILENAME), "").unwrap();
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+
+        let mirror = TempDir::new().unwrap();
+        let report = build_mirror(&registry, mirror.path()).unwrap();
+        assert_eq!(report.archives.len(), 1);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].doc, "css");
+        assert!(!mirror.path().join("css.tar.gz").exists());
+    }
+
+    #[tokio::test]
+    async fn test_install_from_local_mirror() {
+        let (_docs, mirror) = fixture_mirror();
+        let target = TempDir::new().unwrap();
+
+        let report = install_from_mirror(mirror.path().to_str().unwrap(), target.path(), &[])
+            .await
+            .unwrap();
+        assert_eq!(report.archives.len(), 2);
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[target.path()]).unwrap();
+        assert_eq!(registry.find_with_version("babel", "7").unwrap().name, "Babel");
+        assert_eq!(
+            fs::read_to_string(target.path().join("css").join("guide").join("index.html")).unwrap(),
+            "<h1>CSS</h1>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_install_from_http_mirror_verifies_digest() {
+        let (_docs, mirror) = fixture_mirror();
+        let mut server = mockito::Server::new_async().await;
+        for file in [MIRROR_INDEX_FILENAME, "babel~7.tar.gz"] {
+            server
+                .mock("GET", format!("/{}", file).as_str())
+                .with_body(fs::read(mirror.path().join(file)).unwrap())
+                .create_async()
+                .await;
+        }
+        // css 归档在传输中被篡改
+        let mut tampered = fs::read(mirror.path().join("css.tar.gz")).unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        server
+            .mock("GET", "/css.tar.gz")
+            .with_body(tampered)
+            .create_async()
+            .await;
+
+        let target = TempDir::new().unwrap();
+        let report = install_from_mirror(&server.url(), target.path(), &[]).await.unwrap();
+
+        assert_eq!(report.archives.len(), 1);
+        assert!(target.path().join("babel~7").join("db.json").exists());
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].doc, "css");
+        assert!(report.failures[0].error.contains("摘要不符"));
+        assert!(!target.path().join("css").exists());
+    }
+
+    /// 用一个名为 `name` 的 css 文档生成镜像
+    fn css_mirror(name: &str) -> (TempDir, TempDir) {
+        let docs = TempDir::new().unwrap();
+        write_guide_doc(docs.path(), "css", name);
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+        let mirror = TempDir::new().unwrap();
+        build_mirror(&registry, mirror.path()).unwrap();
+        (docs, mirror)
+    }
+
+    fn css_guide(root: &Path) -> String {
+        fs::read_to_string(root.join("css").join("guide").join("index.html")).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sync_promotes_and_prunes_releases() {
+        let target = TempDir::new().unwrap();
+        // 之前用 docs download 安装的普通目录
+        write_guide_doc(target.path(), "css", "Legacy");
+
+        let mut mirrors = Vec::new();
+        for name in ["CSS 1", "CSS 2", "CSS 3"] {
+            let (_docs, mirror) = css_mirror(name);
+            let url = mirror.path().to_str().unwrap().to_string();
+            let report = sync_from_mirror(&url, target.path(), &[]).await.unwrap();
+            assert_eq!(report.archives.len(), 1, "{}", name);
+            assert!(report.failures.is_empty());
+            assert!(fs::symlink_metadata(target.path().join("css")).unwrap().file_type().is_symlink());
+            assert_eq!(css_guide(target.path()), format!("<h1>{}</h1>", name));
+            mirrors.push(mirror);
+        }
+
+        // 摘要相同的版本不重复下载
+        let url = mirrors[2].path().to_str().unwrap();
+        let report = sync_from_mirror(url, target.path(), &[]).await.unwrap();
+        assert!(report.archives.is_empty());
+        assert_eq!(report.unchanged.len(), 1);
+
+        // 只保留当前和上一个版本，暂存的链接和目录不会留下
+        let mut releases: Vec<String> = fs::read_dir(target.path().join(RELEASES_DIRNAME))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        releases.sort();
+        assert_eq!(releases.len(), KEPT_RELEASES, "{:?}", releases);
+        assert!(releases.iter().all(|name| name.starts_with("css.") && name != "css.legacy"));
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[target.path()]).unwrap();
+        assert_eq!(registry.all().len(), 1);
+        assert_eq!(registry.all()[0].name, "CSS 3");
+    }
+
+    #[tokio::test]
+    async fn test_sync_keeps_current_version_on_digest_mismatch() {
+        let target = TempDir::new().unwrap();
+        let (_docs, v1) = css_mirror("CSS 1");
+        sync_from_mirror(v1.path().to_str().unwrap(), target.path(), &[]).await.unwrap();
+
+        let (_docs, v2) = css_mirror("CSS 2");
+        let archive = v2.path().join("css.tar.gz");
+        let mut bytes = fs::read(&archive).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&archive, bytes).unwrap();
+
+        let report = sync_from_mirror(v2.path().to_str().unwrap(), target.path(), &[]).await.unwrap();
+        assert_eq!(report.failures.len(), 1);
+        assert!(report.failures[0].error.contains("摘要不符"));
+        assert_eq!(css_guide(target.path()), "<h1>CSS 1</h1>");
+    }
+
+    #[tokio::test]
+    async fn test_mirror_names_cannot_escape_docs_root() {
+        let (_docs, mirror) = fixture_mirror();
+        let index_path = mirror.path().join(MIRROR_INDEX_FILENAME);
+        let mut index: MirrorIndex = serde_json::from_str(&fs::read_to_string(&index_path).unwrap()).unwrap();
+        index.archives[0].slug = "../escaped".to_string();
+        index.archives[1].file = "../css.tar.gz".to_string();
+        fs::write(&index_path, serde_json::to_string(&index).unwrap()).unwrap();
+
+        let parent = TempDir::new().unwrap();
+        let target = parent.path().join("docs");
+        let url = mirror.path().to_str().unwrap();
+        for report in [
+            install_from_mirror(url, &target, &[]).await.unwrap(),
+            sync_from_mirror(url, &target, &[]).await.unwrap(),
+        ] {
+            assert!(report.archives.is_empty());
+            assert_eq!(report.failures.len(), 2);
+        }
+        let entries: Vec<_> = fs::read_dir(parent.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(entries, ["docs"]);
+        assert!(!target.join("css").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sync_rejects_invalid_digest() {
+        let (_docs, mirror) = css_mirror("CSS");
+        let index_path = mirror.path().join(MIRROR_INDEX_FILENAME);
+        let mut index: MirrorIndex = serde_json::from_str(&fs::read_to_string(&index_path).unwrap()).unwrap();
+        index.archives[0].digest = "摘要摘要摘要摘要摘要摘要".to_string();
+        fs::write(&index_path, serde_json::to_string(&index).unwrap()).unwrap();
+
+        let target = TempDir::new().unwrap();
+        let report = sync_from_mirror(mirror.path().to_str().unwrap(), target.path(), &[]).await.unwrap();
+        assert!(report.archives.is_empty());
+        assert!(report.failures[0].error.contains("摘要无效"));
+        assert_eq!(fs::read_dir(target.path().join(RELEASES_DIRNAME)).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_install_selected_docs_only() {
+        let (_docs, mirror) = fixture_mirror();
+        let target = TempDir::new().unwrap();
+
+        let report = install_from_mirror(mirror.path().to_str().unwrap(), target.path(), &["babel".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(report.archives.len(), 1);
+        assert!(target.path().join("babel~7").exists());
+        assert!(!target.path().join("css").exists());
+    }
+}
@@ -3,21 +3,80 @@
 pub mod babel;
 pub mod css;
 pub mod documentation;
+pub mod export;
 pub mod html;
+pub mod install;
 pub mod javascript;
+pub mod manifest;
+pub mod mirror;
+pub mod package;
+pub mod prebuilt;
 pub mod registry;
 pub mod rust;
+pub mod search;
+pub mod search_history;
 pub mod typescript;
 
 use crate::docs::babel::BabelScraper;
 pub use documentation::Documentation;
+pub use package::PackageFormat;
 pub use registry::DocRegistry;
 
 use crate::core::config::Config;
-use crate::core::scraper::Scraper;
+use crate::core::http_fetch::TlsOptions;
+use crate::core::doc::DB_FILENAME;
+use crate::core::instrumentable::{self, instrument};
+use crate::core::page_db::PageDb;
+use crate::core::types::{DocSlug, PageKey};
+use crate::core::scraper::{fix_redirections, quality, QualityScore, Scraper};
+use crate::storage::{json, page_paths};
+use crate::web::page_cache::PageCache;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// MDN 文档（HTML、CSS、JavaScript）的请求速率（每分钟），MDN 对频繁的请求返回 429
+pub const MDN_RATE_LIMIT: u32 = 30;
+
+/// MDN 文档内容的许可证（SPDX 标识）
+pub const MDN_LICENSE: &str = "CC-BY-SA-2.5";
+
+/// 页面重新生成后发布的事件，附带 `doc` 和 `path`
+pub const PAGE_UPDATED_EVENT: &str = "page.updated";
+
+// 文档管理各步骤发布的消息事件，附带 `message`，由命令行的控制台订阅者输出
+
+/// 下载文档
+pub const DOWNLOAD_DOCS_EVENT: &str = "download.docs";
+/// 安装文档（镜像、远程文档目录或离线包）
+pub const INSTALL_DOCS_EVENT: &str = "install.docs";
+/// 从镜像同步文档
+pub const SYNC_DOCS_EVENT: &str = "sync.docs";
+/// 生成文档、索引和单个页面
+pub const GENERATE_DOCS_EVENT: &str = "generate.docs";
+/// 生成单个页面时请求页面（只在 `--verbose` 时输出）
+pub const FETCH_DOCS_EVENT: &str = "fetch.docs";
+/// 打包文档和生成镜像
+pub const PACKAGE_DOCS_EVENT: &str = "package.docs";
+/// 升级 db.json 格式
+pub const UPGRADE_DOCS_EVENT: &str = "upgrade.docs";
+/// 应用记录的重定向
+pub const FIX_DOCS_EVENT: &str = "fix.docs";
+/// 检查页面路径冲突
+pub const VALIDATE_DOCS_EVENT: &str = "validate.docs";
+/// 导出文档
+pub const EXPORT_DOCS_EVENT: &str = "export.docs";
+/// 清理文档包和临时文件
+pub const CLEAN_DOCS_EVENT: &str = "clean.docs";
+/// 删除文档
+pub const REMOVE_DOCS_EVENT: &str = "remove.docs";
+/// 生成文档清单
+pub const MANIFEST_DOCS_EVENT: &str = "manifest.docs";
+/// 文档管理中不影响结果的警告
+pub const WARN_DOCS_EVENT: &str = "warn.docs";
+/// 批量操作中单个文档失败
+pub const ERROR_DOCS_EVENT: &str = "error.docs";
 
 /// 获取可用文档列表
 pub fn get_available_docs() -> Vec<String> {
@@ -31,52 +90,172 @@ pub fn get_available_docs() -> Vec<String> {
     ]
 }
 
-/// 下载所有文档
-pub async fn download_all_docs() -> Result<(), Box<dyn Error>> {
-    println!("下载所有文档");
+/// 单个文档的下载结果
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DownloadOutcome {
+    /// 文档名称
+    pub doc: String,
+    /// 文档版本
+    pub version: String,
+    /// 是否下载成功
+    pub ok: bool,
+    /// 失败时的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
 
-    // 获取所有可用文档并下载
-    let docs = get_available_docs();
-    for doc in docs {
-        download_doc(&doc, "latest").await?;
+/// 依次下载文档，某个文档失败时记录错误并继续下载其余的文档
+async fn download_each<F, Fut>(docs: Vec<(String, String)>, mut download: F) -> Vec<DownloadOutcome>
+where
+    F: FnMut(String, String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let mut outcomes = Vec::with_capacity(docs.len());
+    for (doc, version) in docs {
+        let started = std::time::Instant::now();
+        let result = download(doc.clone(), version.clone()).await;
+        outcomes.push(DownloadOutcome {
+            doc,
+            version,
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
     }
+    outcomes
+}
 
-    Ok(())
+/// 下载文档列表，返回每个文档的结果
+async fn download_docs(docs: Vec<(String, String)>) -> Vec<DownloadOutcome> {
+    download_each(docs, |doc, version| async move { download_doc(&doc, &version).await }).await
 }
 
-/// 下载默认文档集
-pub async fn download_default_docs() -> Result<(), Box<dyn Error>> {
-    println!("下载默认文档");
+/// 下载所有文档
+pub async fn download_all_docs() -> Vec<DownloadOutcome> {
+    let docs = get_available_docs();
+    download_docs(docs.into_iter().map(|doc| (doc, "latest".to_string())).collect()).await
+}
 
+/// 下载默认文档集
+pub async fn download_default_docs() -> Vec<DownloadOutcome> {
     // 默认文档列表
-    let default_docs = vec!["babel"];
+    let default_docs = ["babel"];
+    download_docs(default_docs.iter().map(|doc| (doc.to_string(), "latest".to_string())).collect()).await
+}
+
+/// 更新已安装的文档
+pub async fn download_installed_docs() -> Vec<DownloadOutcome> {
+    download_docs(get_installed_docs()).await
+}
 
-    for doc in default_docs {
-        download_doc(doc, "latest").await?;
+/// 下载指定的文档列表
+pub async fn download_specific_docs(docs: &[String]) -> Vec<DownloadOutcome> {
+    download_docs(docs.iter().map(|doc| (doc.clone(), "latest".to_string())).collect()).await
+}
+
+/// 有下载失败的文档时返回错误，供命令行在输出结果之后设置退出码
+pub fn downloads_result(outcomes: &[DownloadOutcome]) -> Result<(), Box<dyn Error>> {
+    let failed = outcomes.iter().filter(|outcome| !outcome.ok).count();
+    if failed > 0 {
+        return Err(format!("{} 个文档下载失败", failed).into());
+    }
+    Ok(())
+}
+
+/// 从镜像下载文档
+///
+/// 镜像可以是 HTTP 地址或本地目录，`docs` 为空时安装镜像中的所有文档。
+pub async fn download_from_mirror(mirror_url: &str, docs: &[String]) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(DOWNLOAD_DOCS_EVENT, format!("从镜像下载文档: {}", mirror_url));
+
+    let config = Config::default();
+    let report = mirror::install_from_mirror(mirror_url, config.writable_root(), docs).await?;
+
+    for archive in &report.archives {
+        instrumentable::message(INSTALL_DOCS_EVENT, format!("  已安装 {} ({})", archive.doc_dir(), archive.digest));
+    }
+    for failure in &report.failures {
+        instrumentable::message(ERROR_DOCS_EVENT, format!("安装 {} 失败: {}", failure.doc, failure.error));
+    }
+    if !report.archives.is_empty() {
+        refresh_manifest(&config).await?;
+    }
+    if !report.failures.is_empty() {
+        return Err(format!("{} 个文档安装失败", report.failures.len()).into());
     }
 
     Ok(())
 }
 
-/// 更新已安装的文档
-pub async fn download_installed_docs() -> Result<(), Box<dyn Error>> {
-    println!("更新已安装的文档");
+/// 从远程文档目录安装单个文档
+///
+/// `from` 下应有 manifest.json 和 `<doc_dir>/` 下的文档文件。文件全部下载
+/// 并与清单中的大小比较后才替换已安装的文档，随后更新本地文档清单。
+pub async fn install_doc(from: &str, slug: &str, version: Option<&str>) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(INSTALL_DOCS_EVENT, format!("从 {} 安装文档: {}", from, slug));
 
-    // 获取已安装的文档
-    let installed_docs = get_installed_docs();
-    for (doc, version) in installed_docs {
-        download_doc(&doc, &version).await?;
+    let config = Config::default();
+    let doc = install::install_from_manifest(from, config.writable_root(), slug, version).await?;
+    refresh_manifest(&config).await?;
+
+    instrumentable::message(INSTALL_DOCS_EVENT, format!("已安装文档: {}", doc.doc_dir()));
+    Ok(())
+}
+
+/// 从构建机的镜像同步文档
+///
+/// 与 [`download_from_mirror`] 不同，新版本全部校验并解压后才原子地切换，
+/// 以 `--read-only` 运行的服务器在切换前继续使用旧版本，切换后自动重新加载。
+pub async fn sync_docs(from: &str, docs: &[String]) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(SYNC_DOCS_EVENT, format!("从镜像同步文档: {}", from));
+
+    let config = Config::default();
+    let report = mirror::sync_from_mirror(from, config.writable_root(), docs).await?;
+
+    for archive in &report.unchanged {
+        instrumentable::message(SYNC_DOCS_EVENT, format!("  {} 已是最新", archive.doc_dir()));
+    }
+    for archive in &report.archives {
+        instrumentable::message(SYNC_DOCS_EVENT, format!("  已切换 {} ({})", archive.doc_dir(), archive.digest));
+    }
+    for failure in &report.failures {
+        instrumentable::message(ERROR_DOCS_EVENT, format!("同步 {} 失败: {}", failure.doc, failure.error));
+    }
+    if !report.archives.is_empty() {
+        refresh_manifest(&config).await?;
+    }
+    if !report.failures.is_empty() {
+        return Err(format!("{} 个文档同步失败", report.failures.len()).into());
     }
 
     Ok(())
 }
 
-/// 下载指定的文档列表
-pub async fn download_specific_docs(docs: &[String]) -> Result<(), Box<dyn Error>> {
-    println!("下载指定文档");
+/// 下载 devdocs 的预构建文档
+///
+/// 与已安装文档相同的跳过下载，上次中断的下载从中断处续传，
+/// 每个文档输出跳过（skipped）、更新（updated）或续传（resumed）。
+///
+/// `tls` 为命令行指定的 TLS 设置，与配置中的 CA 证书合并。
+pub async fn download_prebuilt_docs(docs: &[String], tls: &TlsOptions) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(DOWNLOAD_DOCS_EVENT, "下载预构建文档");
+
+    let config = Config::default();
+    let mut tls = tls.clone();
+    tls.merge(&config.tls);
+    let source = prebuilt::PrebuiltSource::default().with_tls(tls);
+    let report = prebuilt::download_prebuilt(&source, config.writable_root(), docs).await?;
 
-    for doc in docs {
-        download_doc(doc, "latest").await?;
+    for outcome in &report.outcomes {
+        instrumentable::message(DOWNLOAD_DOCS_EVENT, format!("  {} {}", outcome.status.as_str(), outcome.doc));
+    }
+    for failure in &report.failures {
+        instrumentable::message(ERROR_DOCS_EVENT, format!("下载 {} 失败: {}", failure.doc, failure.error));
+    }
+    if !report.failures.is_empty() {
+        return Err(format!("{} 个文档下载失败", report.failures.len()).into());
     }
 
     Ok(())
@@ -84,19 +263,20 @@ pub async fn download_specific_docs(docs: &[String]) -> Result<(), Box<dyn Error
 
 /// 下载单个文档
 pub async fn download_doc(doc_name: &str, version: &str) -> Result<(), Box<dyn Error>> {
-    println!("下载文档: {} (版本: {})", doc_name, version);
+    instrumentable::message(DOWNLOAD_DOCS_EVENT, format!("下载文档: {} (版本: {})", doc_name, version));
 
     let config = Config::default();
+    let (doc_name, version) = split_doc_version(doc_name, version);
 
-    // 确保文档目录存在
-    let doc_dir = Path::new(&config.docs_path).join(doc_name);
+    // 确保文档目录存在，与抓取器的输出目录一致
+    let doc_dir = config.writable_root().join(DocSlug::new(doc_name).dir_name(version));
     fs::create_dir_all(&doc_dir)?;
 
     // 根据文档类型执行不同的下载操作
     match doc_name {
         "babel" => {
             // 使用Babel抓取器下载文档
-            let mut scraper = BabelScraper::new(&config.docs_path, version);
+            let mut scraper = BabelScraper::new(&config.docs_path(), version);
             scraper.run().await?;
         }
         // 添加其他文档类型的下载逻辑
@@ -106,24 +286,25 @@ pub async fn download_doc(doc_name: &str, version: &str) -> Result<(), Box<dyn E
     }
 
     // 确保下载后处理
-    println!("文档下载完成: {}", doc_name);
+    instrumentable::message(DOWNLOAD_DOCS_EVENT, format!("文档下载完成: {}", doc_name));
 
     Ok(())
 }
 
 /// 生成/抓取文档
 pub async fn generate_doc(doc_name: &str, version: &str) -> Result<(), Box<dyn Error>> {
-    println!("生成文档: {} (版本: {})", doc_name, version);
+    instrumentable::message(GENERATE_DOCS_EVENT, format!("生成文档: {} (版本: {})", doc_name, version));
 
     let config = Config::default();
+    let (doc_name, version) = split_doc_version(doc_name, version);
 
     match doc_name {
         "babel" => {
-            let mut scraper = BabelScraper::new(&config.docs_path, version);
+            let mut scraper = BabelScraper::new(&config.docs_path(), version);
             scraper.run().await?;
 
             // 生成索引
-            generate_doc_index(doc_name)?;
+            generate_doc_index(&DocSlug::new(doc_name).dir_name(version))?;
         }
         // 添加其他文档类型
         _ => {
@@ -134,12 +315,20 @@ pub async fn generate_doc(doc_name: &str, version: &str) -> Result<(), Box<dyn E
     Ok(())
 }
 
+/// 拆分 `<slug>~<version>` 形式的文档目录名，目录名中的版本优先于 `version`
+fn split_doc_version<'a>(doc_name: &'a str, version: &'a str) -> (&'a str, &'a str) {
+    match doc_name.split_once('~') {
+        Some((name, dir_version)) if !dir_version.is_empty() => (name, dir_version),
+        _ => (doc_name, version),
+    }
+}
+
 /// 生成文档索引
 fn generate_doc_index(doc_name: &str) -> Result<(), Box<dyn Error>> {
-    println!("生成文档索引: {}", doc_name);
+    instrumentable::message(GENERATE_DOCS_EVENT, format!("生成文档索引: {}", doc_name));
 
     let config = Config::default();
-    let doc_path = Path::new(&config.docs_path).join(doc_name);
+    let doc_path = config.writable_root().join(doc_name);
 
     if !doc_path.exists() {
         return Err(format!("文档路径不存在: {:?}", doc_path).into());
@@ -152,8 +341,7 @@ fn generate_doc_index(doc_name: &str) -> Result<(), Box<dyn Error>> {
     }
 
     // 读取条目数据
-    let entries_content = fs::read_to_string(&entries_file)?;
-    let entries: serde_json::Value = serde_json::from_str(&entries_content)?;
+    let entries: serde_json::Value = json::read_json(&entries_file)?;
 
     // 创建索引
     let mut index = serde_json::Map::new();
@@ -179,13 +367,13 @@ fn generate_doc_index(doc_name: &str) -> Result<(), Box<dyn Error>> {
     let index_content = serde_json::to_string_pretty(&index)?;
     fs::write(&index_file, index_content)?;
 
-    println!("索引生成完成: {:?}", index_file);
+    instrumentable::message(GENERATE_DOCS_EVENT, format!("索引生成完成: {:?}", index_file));
     Ok(())
 }
 
 /// 生成单页
 pub async fn generate_page(doc_name: &str, page_path: &str) -> Result<(), Box<dyn Error>> {
-    println!("生成页面: {}/{}", doc_name, page_path);
+    instrumentable::message(GENERATE_DOCS_EVENT, format!("生成页面: {}/{}", doc_name, page_path));
 
     let config = Config::default();
 
@@ -195,7 +383,7 @@ pub async fn generate_page(doc_name: &str, page_path: &str) -> Result<(), Box<dy
     }
 
     // 确定页面URL或路径
-    let page_url = match doc_name {
+    let url = match doc_name {
         "babel" => format!(
             "https://babeljs.io/docs/{}",
             page_path.trim_start_matches('/')
@@ -205,136 +393,423 @@ pub async fn generate_page(doc_name: &str, page_path: &str) -> Result<(), Box<dy
     };
 
     // 抓取单个页面
-    println!("抓取页面: {}", page_url);
+    instrumentable::message(FETCH_DOCS_EVENT, format!("抓取页面: {}", url));
 
     // 使用reqwest抓取页面内容
     let client = reqwest::Client::new();
     let response = client
-        .get(&page_url)
+        .get(&url)
         .header("User-Agent", "xwdoc/0.1.0")
         .send()
         .await?;
 
     if !response.status().is_success() {
-        return Err(format!("抓取页面失败: {} - {}", page_url, response.status()).into());
+        return Err(format!("抓取页面失败: {} - {}", url, response.status()).into());
     }
 
     let content = response.text().await?;
 
-    // 为文档创建输出目录
-    let doc_dir = Path::new(&config.docs_path).join(doc_name);
-    fs::create_dir_all(&doc_dir)?;
+    let output_file = store_page(config.writable_root(), doc_name, page_path, &content)?;
+
+    instrumentable::message(GENERATE_DOCS_EVENT, format!("页面抓取完成: {:?}", output_file));
+    let address = page_url(&config, doc_name, page_path);
+    instrumentable::message(GENERATE_DOCS_EVENT, format!("页面地址: {}", address));
+
+    Ok(())
+}
 
+/// 保存页面内容并发布页面更新事件
+///
+/// 页面保存到 `<docs_root>/<doc>/<page>/index.html`，与保留名称冲突的页面
+/// 保存在 `_p/` 下（见 [`page_paths`]）。
+pub fn store_page(
+    docs_root: &Path,
+    doc_name: &str,
+    page_path: &str,
+    content: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
     // 解析出页面的相对路径并创建目录
     let page_rel_path = page_path.trim_start_matches('/').trim_end_matches('/');
-    let page_dir = doc_dir.join(page_rel_path);
+    let page_dir = docs_root
+        .join(doc_name)
+        .join(page_paths::escape_page_path(page_rel_path));
     fs::create_dir_all(&page_dir)?;
 
     // 将页面内容写入文件
     let output_file = page_dir.join("index.html");
-    fs::write(&output_file, content)?;
+    let payload = HashMap::from([
+        ("doc".to_string(), doc_name.to_string()),
+        ("path".to_string(), page_rel_path.to_string()),
+    ]);
+    instrument(PAGE_UPDATED_EVENT, payload, || fs::write(&output_file, content))?;
 
-    println!("页面抓取完成: {:?}", output_file);
+    Ok(output_file)
+}
 
-    Ok(())
+/// 获取页面在 Web 服务器上的地址
+pub fn page_url(config: &Config, doc_name: &str, page_path: &str) -> String {
+    format!(
+        "http://{}:{}/docs/{}/{}",
+        config.host,
+        config.port,
+        doc_name,
+        page_path.trim_matches('/')
+    )
 }
 
-/// 打包文档
-pub fn package_doc(doc_name: &str) -> Result<(), Box<dyn Error>> {
-    println!("打包文档: {}", doc_name);
+/// 将单个文档打包为离线包，写入输出目录
+///
+/// 包中页面文件和 db.json 中的页面合并为 v1 格式的 db.json，
+/// 可以复制到其他机器上用 [`unpack_doc`] 安装。
+pub fn package_doc_archive(
+    doc_name: &str,
+    format: PackageFormat,
+    output: &Path,
+) -> Result<PathBuf, Box<dyn Error>> {
+    instrumentable::message(PACKAGE_DOCS_EVENT, format!("打包文档: {}", doc_name));
 
     let config = Config::default();
-    let doc_path = Path::new(&config.docs_path).join(doc_name);
-
+    let doc_path = config.writable_root().join(doc_name);
     if !doc_path.exists() {
         return Err(format!("文档路径不存在: {:?}", doc_path).into());
     }
 
-    // 确保必要的文件存在
-    let index_file = doc_path.join("index.json");
-    if !index_file.exists() {
-        return Err(format!("索引文件不存在: {:?}", index_file).into());
-    }
-
-    // 读取索引内容
-    let index_content = fs::read_to_string(&index_file)?;
-    let index: serde_json::Value = serde_json::from_str(&index_content)?;
-
-    // 读取页面数据
-    let mut pages_data = serde_json::Map::new();
-
-    // 处理entries数组，提取页面内容
-    if let Some(entries) = index.get("entries").and_then(|e| e.as_array()) {
-        for entry in entries {
-            if let Some(path) = entry.get("path").and_then(|p| p.as_str()) {
-                // 确定页面文件路径
-                let page_path = path.trim_start_matches('/');
-                let page_dir = doc_path.join(page_path);
-                let page_file = if page_dir.is_dir() {
-                    page_dir.join("index.html")
-                } else {
-                    doc_path.join(format!("{}.html", page_path))
-                };
-
-                // 如果页面文件存在，读取内容
-                if page_file.exists() {
-                    if let Ok(content) = fs::read_to_string(&page_file) {
-                        // 将页面内容添加到pages_data中
-                        pages_data.insert(path.to_string(), serde_json::Value::String(content));
-                    } else {
-                        println!("警告: 无法读取页面文件: {:?}", page_file);
-                    }
-                } else {
-                    println!("警告: 页面文件不存在: {:?}", page_file);
-                }
-            }
-        }
+    let file = package::write_package(&doc_path, output, format)?;
+    instrumentable::message(PACKAGE_DOCS_EVENT, format!("文档打包完成: {:?}", file));
+    Ok(file)
+}
+
+/// 安装离线包并更新文档清单
+///
+/// 已安装的同名文档会被替换，包格式根据文件扩展名判断。
+pub async fn unpack_doc(path: &Path) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(INSTALL_DOCS_EVENT, format!("安装文档包: {:?}", path));
+
+    let config = Config::default();
+    fs::create_dir_all(config.writable_root())?;
+    let doc_dir = package::unpack_package(path, config.writable_root())?;
+    refresh_manifest(&config).await?;
+
+    instrumentable::message(INSTALL_DOCS_EVENT, format!("已安装文档: {}", doc_dir));
+    Ok(())
+}
+
+/// 打包所有已安装的文档，生成可供他人下载的镜像
+///
+/// 输出目录中包含每个文档的 `.tar.gz` 归档、`SHA256SUMS` 和 `mirror.json`。
+/// 单个文档打包失败不会中断其他文档。
+pub fn package_all_docs(output: &Path) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(PACKAGE_DOCS_EVENT, format!("打包所有文档到: {:?}", output));
+
+    let config = Config::default();
+    let mut registry = DocRegistry::new();
+    registry.load_from_disk(&config.docs_paths)?;
+
+    let report = mirror::build_mirror(&registry, output)?;
+    for archive in &report.archives {
+        instrumentable::message(PACKAGE_DOCS_EVENT, format!("  {} ({} 字节)", archive.file, archive.size));
+    }
+    for failure in &report.failures {
+        instrumentable::message(ERROR_DOCS_EVENT, format!("打包 {} 失败: {}", failure.doc, failure.error));
     }
+    if !report.failures.is_empty() {
+        return Err(format!("{} 个文档打包失败", report.failures.len()).into());
+    }
+
+    instrumentable::message(PACKAGE_DOCS_EVENT, format!("镜像生成完成，共 {} 个文档", report.archives.len()));
+    Ok(())
+}
 
-    // 创建完整的包数据
-    let mut package_data = serde_json::Map::new();
+/// 将已安装文档的 db.json 原地升级为 v2 格式
+///
+/// 未指定文档时升级可写根目录中的所有文档，只读叠加目录中的文档不受影响。
+pub fn upgrade_format(doc_name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let config = Config::default();
+    let docs_path = config.writable_root();
+
+    let doc_dirs: Vec<PathBuf> = match doc_name {
+        Some(name) => vec![docs_path.join(name)],
+        None => fs::read_dir(docs_path)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect(),
+    };
 
-    // 添加文档元数据
-    let meta_file = doc_path.join("meta.json");
-    if meta_file.exists() {
-        if let Ok(meta_content) = fs::read_to_string(&meta_file) {
-            if let Ok(meta_json) = serde_json::from_str::<serde_json::Value>(&meta_content) {
-                package_data.insert("meta".to_string(), meta_json);
+    for doc_dir in doc_dirs {
+        let db_file = doc_dir.join(DB_FILENAME);
+        if !db_file.exists() {
+            if doc_name.is_some() {
+                return Err(format!("数据库文件不存在: {:?}", db_file).into());
             }
+            continue;
+        }
+
+        if PageDb::upgrade_file(&db_file)? {
+            instrumentable::message(UPGRADE_DOCS_EVENT, format!("已升级: {:?}", db_file));
+        } else {
+            instrumentable::message(UPGRADE_DOCS_EVENT, format!("已是 v2 格式: {:?}", db_file));
         }
     }
 
-    // 添加索引数据
-    package_data.insert("index".to_string(), index);
+    Ok(())
+}
 
-    // 添加页面数据
-    package_data.insert("pages".to_string(), serde_json::Value::Object(pages_data));
+/// 对已抓取的文档应用 redirections.json 中记录的重定向
+///
+/// 改写 db.json 中指向重定向前路径的内部链接，以及 entries.json 和 index.json 中的条目路径。
+pub fn fix_redirects(doc_name: &str) -> Result<(), Box<dyn Error>> {
+    let config = Config::default();
+    let doc_dir = config.writable_root().join(doc_name);
+    if !doc_dir.join(DB_FILENAME).exists() {
+        return Err(format!("文档未安装: {}", doc_name).into());
+    }
+    if !doc_dir.join(fix_redirections::REDIRECTIONS_FILENAME).exists() {
+        instrumentable::message(FIX_DOCS_EVENT, format!("{} 没有记录重定向，无需修复", doc_name));
+        return Ok(());
+    }
 
-    // 添加打包时间戳
-    package_data.insert(
-        "created_at".to_string(),
-        serde_json::Value::Number(serde_json::Number::from(chrono::Utc::now().timestamp())),
+    let summary = fix_redirections::fix_doc_dir(&doc_dir)?;
+    instrumentable::message(
+        FIX_DOCS_EVENT,
+        format!(
+            "已修复 {}：移动了 {} 个页面，改写了 {} 个页面中的链接和 {} 个条目",
+            doc_name, summary.moved_pages, summary.rewritten_pages, summary.rewritten_entries
+        ),
     );
+    Ok(())
+}
+
+/// 与保留名称冲突、未转义保存的页面
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PathCollision {
+    /// 文档目录名称
+    pub doc: String,
+    /// 相对于文档目录的页面文件
+    pub page: String,
+    /// 页面文件的完整路径
+    pub file: PathBuf,
+}
 
-    // 创建打包文件
-    let package_file = doc_path.join("package.json");
-    let package_content = serde_json::to_string_pretty(&serde_json::Value::Object(package_data))?;
-    fs::write(&package_file, package_content)?;
+/// 找出文档根目录中与保留名称冲突、未转义保存的页面
+///
+/// 旧版本把 `meta`、`icon` 等页面直接保存在文档目录中，可能覆盖文档自身的文件。
+/// 未指定文档时检查所有文档根目录，指定的文档未安装时返回错误。
+pub fn find_path_collisions(
+    docs_paths: &[PathBuf],
+    doc_name: Option<&str>,
+) -> Result<Vec<PathCollision>, Box<dyn Error>> {
+    let doc_dirs: Vec<PathBuf> = match doc_name {
+        Some(name) => docs_paths
+            .iter()
+            .map(|root| root.join(name))
+            .filter(|path| path.is_dir())
+            .collect(),
+        None => docs_paths
+            .iter()
+            .filter_map(|root| fs::read_dir(root).ok())
+            .flat_map(|entries| entries.filter_map(Result::ok).map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .collect(),
+    };
+    if let (Some(name), true) = (doc_name, doc_dirs.is_empty()) {
+        return Err(format!("文档未安装: {}", name).into());
+    }
 
-    println!("文档打包完成: {:?}", package_file);
+    let mut collisions = Vec::new();
+    for doc_dir in doc_dirs {
+        let doc = doc_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let pages = page_paths::find_collisions(&doc_dir)
+            .map_err(|e| format!("无法检查文档目录 {:?}: {}", doc_dir, e))?;
+        for page in pages {
+            collisions.push(PathCollision {
+                doc: doc.clone(),
+                file: doc_dir.join(&page),
+                page,
+            });
+        }
+    }
+    Ok(collisions)
+}
+
+/// 重新计算的文档质量分数
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DocQuality {
+    /// 文档目录名称
+    pub doc: String,
+    /// 质量分数
+    #[serde(flatten)]
+    pub score: QualityScore,
+}
+
+/// 由保存的抓取报告重新计算可写根目录中文档的质量分数，并更新各自的 meta.json
+///
+/// 只读的叠加目录不会修改；没有抓取报告的文档跳过。
+pub fn recompute_quality(root: &Path, doc_name: Option<&str>) -> Result<Vec<DocQuality>, Box<dyn Error>> {
+    let mut doc_dirs: Vec<PathBuf> = match doc_name {
+        Some(name) => vec![root.join(name)],
+        None => fs::read_dir(root)
+            .map(|entries| entries.filter_map(Result::ok).map(|entry| entry.path()).collect())
+            .unwrap_or_default(),
+    };
+    doc_dirs.retain(|path| path.is_dir());
+    doc_dirs.sort();
+
+    let mut scores = Vec::new();
+    for doc_dir in doc_dirs {
+        let score = quality::recompute(&doc_dir)
+            .map_err(|e| format!("无法计算文档 {:?} 的质量分数: {}", doc_dir, e))?;
+        if let Some(score) = score {
+            scores.push(DocQuality {
+                doc: doc_dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                score,
+            });
+        }
+    }
+    Ok(scores)
+}
+
+/// 检查已安装文档中与保留名称冲突、未转义保存的页面，发现冲突时返回错误
+pub fn validate_docs(doc_name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let config = Config::default();
+    let collisions = find_path_collisions(&config.docs_paths, doc_name)?;
+    for collision in &collisions {
+        instrumentable::message(
+            VALIDATE_DOCS_EVENT,
+            format!("未转义的页面: {:?} (文档 {}，页面 {})", collision.file, collision.doc, collision.page),
+        );
+    }
+    if collisions.is_empty() {
+        instrumentable::message(VALIDATE_DOCS_EVENT, "未发现页面路径冲突");
+    }
+    collisions_result(&collisions)
+}
+
+/// 有冲突的页面时返回错误，供命令行在任何输出格式下使用相同的退出码
+pub fn collisions_result(collisions: &[PathCollision]) -> Result<(), Box<dyn Error>> {
+    if !collisions.is_empty() {
+        return Err(format!(
+            "发现 {} 个与保留名称冲突的页面，请重新生成这些文档",
+            collisions.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 将已安装的文档导出为 EPUB
+///
+/// 文档可以用目录名（如 `react~18`）或别名指定，`types` 不为空时只导出这些类型。
+/// 未指定输出路径时写入当前目录下的 `<doc>.epub`。
+pub fn export_doc(doc_name: &str, types: &[String], output: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let config = Config::default();
+    let mut registry = DocRegistry::new();
+    registry.load_from_disk(&config.docs_paths)?;
+    let doc = installed_doc(&registry, doc_name)?;
+
+    let book = export::build_book(doc, types)?;
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.epub", doc.path())));
+    export::write_epub(&book, &output)?;
+
+    instrumentable::message(
+        EXPORT_DOCS_EVENT,
+        format!(
+            "已导出 {} 章、{} 个页面、{} 张图片: {:?}",
+            book.chapters.len(),
+            book.page_count(),
+            book.images.len(),
+            output
+        ),
+    );
+    Ok(())
+}
 
+/// 将已安装的文档导出为静态 HTML 站点，不需要服务器即可浏览
+///
+/// 文档的指定方式和 `types` 与 [`export_doc`] 相同。未指定输出目录时写入当前
+/// 目录下的 `<doc>/`。
+pub fn export_site(doc_name: &str, types: &[String], output: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let config = Config::default();
+    let mut registry = DocRegistry::new();
+    registry.load_from_disk(&config.docs_paths)?;
+    let doc = installed_doc(&registry, doc_name)?;
+
+    let site = export::build_site(doc, types)?;
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(doc.path()));
+    export::write_site(&site, &output)?;
+
+    instrumentable::message(
+        EXPORT_DOCS_EVENT,
+        format!(
+            "已导出 {} 个页面、{} 个条目、{} 个文件: {:?}",
+            site.pages.len(),
+            site.entries.len(),
+            site.assets.len(),
+            output
+        ),
+    );
     Ok(())
 }
 
+/// 按目录名（如 `react~18`）或别名查找已安装的文档
+pub fn installed_doc<'a>(registry: &'a DocRegistry, doc_name: &str) -> Result<&'a Documentation, Box<dyn Error>> {
+    let doc = registry
+        .all()
+        .iter()
+        .find(|doc| doc.path() == doc_name)
+        .or_else(|| registry.find(doc_name))
+        .ok_or_else(|| format!("文档未安装: {}", doc_name))?;
+    Ok(doc)
+}
+
+/// 读取已安装文档的页面，`markdown` 为 true 时转换为 Markdown
+///
+/// 页面文件优先，其次为 db.json。转换时链接按 db.json 中记录的页面源 URL
+/// 解析为绝对地址。
+pub fn read_page(config: &Config, doc_name: &str, page_path: &str, markdown: bool) -> Result<String, Box<dyn Error>> {
+    let mut registry = DocRegistry::new();
+    registry.load_from_disk(&config.docs_paths)?;
+
+    let doc = registry
+        .all()
+        .iter()
+        .find(|doc| doc.path() == doc_name)
+        .or_else(|| registry.find(doc_name))
+        .ok_or_else(|| format!("文档未安装: {}", doc_name))?;
+
+    let key = PageKey::new(page_path);
+    let stored = doc.stored_page(&PageCache::new(), &key);
+    let content = fs::read_to_string(doc.page_file(key.as_str()))
+        .ok()
+        .or_else(|| stored.as_ref().map(|page| page.content.clone()))
+        .ok_or_else(|| format!("页面不存在: {}/{}", doc.path(), key.as_str()))?;
+    if !markdown {
+        return Ok(content);
+    }
+
+    let source_url = stored.as_ref().and_then(|page| page.source_url.as_deref());
+    Ok(crate::core::markdown::html_to_markdown(&content, source_url))
+}
+
 /// 清理文档包
 pub fn clean_docs() -> Result<(), Box<dyn Error>> {
-    println!("清理文档包");
+    instrumentable::message(CLEAN_DOCS_EVENT, "清理文档包");
 
     let config = Config::default();
-    let docs_path = Path::new(&config.docs_path);
+    let docs_path = config.writable_root();
 
     if !docs_path.exists() {
-        println!("文档路径不存在，无需清理");
+        instrumentable::message(CLEAN_DOCS_EVENT, "文档路径不存在，无需清理");
         return Ok(());
     }
 
@@ -350,16 +825,16 @@ pub fn clean_docs() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    println!("所有文档包清理完成");
+    instrumentable::message(CLEAN_DOCS_EVENT, "所有文档包清理完成");
     Ok(())
 }
 
 /// 清理特定文档的文件
 fn clean_doc_files(doc_name: &str) -> Result<(), Box<dyn Error>> {
-    println!("清理文档: {}", doc_name);
+    instrumentable::message(CLEAN_DOCS_EVENT, format!("清理文档: {}", doc_name));
 
     let config = Config::default();
-    let doc_path = Path::new(&config.docs_path).join(doc_name);
+    let doc_path = config.writable_root().join(doc_name);
 
     if !doc_path.exists() {
         return Ok(());
@@ -370,15 +845,16 @@ fn clean_doc_files(doc_name: &str) -> Result<(), Box<dyn Error>> {
         "package.json",     // 打包文件
         "index.json.tmp",   // 临时索引文件
         "entries.json.tmp", // 临时条目文件
+        "db.json.tmp",      // 抓取中断时留下的临时页面数据
     ];
 
     for file in files_to_clean.iter() {
         let file_path = doc_path.join(file);
         if file_path.exists() {
             if let Err(e) = fs::remove_file(&file_path) {
-                println!("警告: 无法删除文件 {:?}: {}", file_path, e);
+                instrumentable::message(WARN_DOCS_EVENT, format!("无法删除文件 {:?}: {}", file_path, e));
             } else {
-                println!("已删除: {:?}", file_path);
+                instrumentable::message(CLEAN_DOCS_EVENT, format!("已删除: {:?}", file_path));
             }
         }
     }
@@ -389,26 +865,63 @@ fn clean_doc_files(doc_name: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-/// 生成文档清单
-pub fn generate_manifest() -> Result<(), Box<dyn Error>> {
-    println!("生成文档清单");
+/// 删除已安装的文档并更新文档清单
+///
+/// 只会删除 `config` 的可写根目录（命名空间中为命名空间的目录）中的文档，
+/// 只读叠加目录中的文档不受影响。
+pub async fn remove_doc(config: &Config, doc_name: &str, version: Option<&str>) -> Result<(), Box<dyn Error>> {
+    instrumentable::message(REMOVE_DOCS_EVENT, format!("删除文档: {}", doc_name));
 
-    let config = Config::default();
     let mut registry = DocRegistry::new();
+    registry.load_from_disk(&config.docs_paths)?;
 
-    // 加载已有文档
-    registry.load_from_disk(&config.docs_path)?;
-
-    // 生成清单
-    registry.generate_manifest(&config.docs_path)?;
+    let version = version.unwrap_or_default();
+    registry.remove(doc_name, version)?;
+    refresh_manifest(config).await?;
 
+    instrumentable::message(REMOVE_DOCS_EVENT, format!("文档删除完成: {}", doc_name));
     Ok(())
 }
 
+/// 重新生成文档清单，写入 `config` 的可写根目录（命名空间中为命名空间的目录）
+///
+/// `docs manifest` 命令以及安装、同步、删除文档后都通过这里更新清单，与其他同时
+/// 进行的清单生成合并，见 [`manifest::regenerate`]。返回当前的清单。
+pub async fn refresh_manifest(config: &Config) -> Result<manifest::Manifest, Box<dyn Error>> {
+    let refresh = manifest::regenerate(&config.docs_paths, config.writable_root(), manifest::system_clock).await?;
+
+    instrumentable::message(
+        MANIFEST_DOCS_EVENT,
+        format!(
+            "文档清单已更新: {} 个文档, {} 个条目",
+            refresh.manifest.docs.len(),
+            refresh.manifest.entry_count()
+        ),
+    );
+    Ok(refresh.manifest)
+}
+
+/// 已安装文档的目录名，跳过下载和解压用的隐藏目录
+pub fn installed_doc_dirs() -> Vec<String> {
+    let config = Config::default();
+    let mut dirs: Vec<String> = fs::read_dir(config.writable_root())
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+                .filter(|name| !name.starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
 /// 获取已安装的文档
 fn get_installed_docs() -> Vec<(String, String)> {
     let config = Config::default();
-    let docs_path = Path::new(&config.docs_path);
+    let docs_path = config.writable_root();
     let mut result = Vec::new();
 
     // 读取docs目录
@@ -435,7 +948,7 @@ fn get_installed_docs() -> Vec<(String, String)> {
 /// 获取文档版本
 fn get_doc_version(doc_name: &str) -> Option<String> {
     let config = Config::default();
-    let doc_path = Path::new(&config.docs_path).join(doc_name);
+    let doc_path = config.writable_root().join(doc_name);
 
     // 尝试读取版本文件
     let version_file = doc_path.join("version.txt");
@@ -456,3 +969,78 @@ fn get_doc_version(doc_name: &str) -> Option<String> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::scraper::filter::Filter;
+    use std::collections::BTreeSet;
+
+    /// 每个文档的抓取器和过滤器只有一个规范位置：`docs/<name>/{scraper,clean,entries}.rs`
+    #[test]
+    fn test_doc_modules_have_one_canonical_layout() {
+        // 引用每个规范类型，重命名或移动时编译失败
+        let filters: Vec<Box<dyn Filter>> = vec![
+            Box::new(babel::BabelCleanHtmlFilter::new()),
+            Box::new(babel::BabelEntriesFilter::new()),
+            Box::new(css::CssCleanHtmlFilter::new()),
+            Box::new(css::CssEntriesFilter::new()),
+            Box::new(html::CleanHtmlFilter::new()),
+            Box::new(html::HtmlEntriesFilter::new()),
+            Box::new(javascript::JavaScriptCleanHtmlFilter::new()),
+            Box::new(javascript::JavaScriptEntriesFilter::new()),
+            Box::new(typescript::TypeScriptEntriesFilter::new()),
+        ];
+        assert_eq!(filters.len(), 9);
+        let _ = (
+            babel::BabelScraper::new("", ""),
+            css::CssScraper::new("", ""),
+            html::HtmlScraper::new("", ""),
+            javascript::JavaScriptScraper::new("", ""),
+            rust::RustScraper::new("", ""),
+            typescript::TypeScriptScraper::new("", ""),
+        );
+
+        // 不允许再出现 `filters/` 之类的平行副本
+        let allowed: BTreeSet<&str> = ["mod.rs", "scraper.rs", "clean.rs", "entries.rs"].into();
+        let docs_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("docs");
+        for name in ["babel", "css", "html", "javascript", "rust", "typescript"] {
+            for entry in fs::read_dir(docs_dir.join(name)).unwrap() {
+                let entry = entry.unwrap();
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                assert!(
+                    entry.path().is_file() && allowed.contains(file_name.as_str()),
+                    "src/docs/{}/{} 不是规范的模块文件",
+                    name,
+                    file_name
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_each_continues_after_failure() {
+        let docs = ["css", "rust", "html"].map(|doc| (doc.to_string(), "latest".to_string())).to_vec();
+        let outcomes = download_each(docs, |doc, _| async move {
+            if doc == "rust" {
+                Err(format!("未支持的文档类型: {}", doc).into())
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+
+        let results: Vec<(&str, bool)> = outcomes.iter().map(|outcome| (outcome.doc.as_str(), outcome.ok)).collect();
+        assert_eq!(results, [("css", true), ("rust", false), ("html", true)]);
+        assert_eq!(outcomes[1].error.as_deref(), Some("未支持的文档类型: rust"));
+        assert!(downloads_result(&outcomes).is_err());
+        assert!(downloads_result(&outcomes[..1]).is_ok());
+    }
+
+    #[test]
+    fn test_split_doc_version() {
+        assert_eq!(split_doc_version("babel~6", "latest"), ("babel", "6"));
+        assert_eq!(split_doc_version("babel", "7"), ("babel", "7"));
+        assert_eq!(split_doc_version("babel~", "7"), ("babel~", "7"));
+    }
+}
@@ -0,0 +1,283 @@
+//! 单个文档的离线包
+//!
+//! 文档可以打包为 gzip 压缩的 tar 包（`<doc>.tar.gz`，包含 meta.json、
+//! index.json 和 db.json）或单个 gzip 压缩的 JSON 文件（`<doc>.json.gz`），
+//! 复制到其他机器后用 [`unpack_package`] 安装。单独保存的页面文件合并到
+//! db.json 中（v1 格式），包的内容直接写入压缩流，不需要先拼成一个字符串。
+
+use super::DocRegistry;
+use crate::core::doc::{DB_FILENAME, INDEX_FILENAME, META_FILENAME};
+use crate::core::error::{Error, Result};
+use crate::core::page_db::{DbFormat, PageDb};
+use crate::core::types::PageKey;
+use crate::storage::page_paths;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 离线包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PackageFormat {
+    /// gzip 压缩的 tar 包，文件与文档目录中的相同
+    #[default]
+    TarGz,
+    /// gzip 压缩的单个 JSON 对象：`{"doc", "meta", "index", "db"}`
+    JsonGz,
+}
+
+impl PackageFormat {
+    /// 包文件的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            PackageFormat::TarGz => "tar.gz",
+            PackageFormat::JsonGz => "json.gz",
+        }
+    }
+
+    /// 根据文件名判断包格式，返回格式和去掉扩展名后的文档目录名
+    pub fn from_file_name(name: &str) -> Option<(Self, &str)> {
+        [PackageFormat::TarGz, PackageFormat::JsonGz]
+            .into_iter()
+            .find_map(|format| {
+                name.strip_suffix(format.extension())
+                    .and_then(|stem| stem.strip_suffix('.'))
+                    .map(|stem| (format, stem))
+            })
+    }
+}
+
+impl FromStr for PackageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "tar.gz" => Ok(PackageFormat::TarGz),
+            "json.gz" => Ok(PackageFormat::JsonGz),
+            _ => Err(format!("未知的打包格式: {}（可选 tar.gz 或 json.gz）", s)),
+        }
+    }
+}
+
+/// 将文档目录打包到输出目录，返回包文件的路径
+///
+/// 包文件名为 `<doc_dir>.<扩展名>`，解包时据此确定文档目录名。
+pub fn write_package(doc_path: &Path, output: &Path, format: PackageFormat) -> Result<PathBuf> {
+    let doc_dir = doc_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::Doc(format!("无效的文档目录: {:?}", doc_path)))?;
+    let index_file = doc_path.join(INDEX_FILENAME);
+    if !index_file.exists() {
+        return Err(Error::Doc(format!("索引文件不存在: {:?}", index_file)));
+    }
+    let index = fs::read(&index_file)?;
+    let meta = fs::read(doc_path.join(META_FILENAME)).ok();
+    let db = collect_pages(doc_path, &serde_json::from_slice(&index)?)?;
+
+    fs::create_dir_all(output)?;
+    let file = output.join(format!("{}.{}", doc_dir, format.extension()));
+    let writer = GzEncoder::new(BufWriter::new(fs::File::create(&file)?), Compression::default());
+    match format {
+        PackageFormat::TarGz => {
+            let mut builder = tar::Builder::new(writer);
+            let db = db.to_json()?;
+            let mut files = vec![(INDEX_FILENAME, index.as_slice()), (DB_FILENAME, db.as_bytes())];
+            if let Some(meta) = &meta {
+                files.insert(0, (META_FILENAME, meta.as_slice()));
+            }
+            for (name, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(chrono::Utc::now().timestamp() as u64);
+                header.set_cksum();
+                builder.append_data(&mut header, name, content)?;
+            }
+            builder.into_inner()?.finish()?.flush()?;
+        }
+        PackageFormat::JsonGz => {
+            let meta: serde_json::Value = match &meta {
+                Some(meta) => serde_json::from_slice(meta)?,
+                None => serde_json::Value::Null,
+            };
+            let package = serde_json::json!({
+                "doc": doc_dir,
+                "meta": meta,
+                "index": serde_json::from_slice::<serde_json::Value>(&index)?,
+                "db": db.to_value(DbFormat::V1),
+            });
+            let mut writer = writer;
+            serde_json::to_writer(&mut writer, &package)?;
+            writer.finish()?.flush()?;
+        }
+    }
+    Ok(file)
+}
+
+/// 收集索引中所有条目的页面，页面文件优先，缺失时使用 db.json 中的内容
+fn collect_pages(doc_path: &Path, index: &serde_json::Value) -> Result<PageDb> {
+    let stored = match PageDb::load(&doc_path.join(DB_FILENAME)) {
+        Ok(db) => db,
+        Err(_) => PageDb::new(),
+    };
+    let mut db = PageDb::new().with_format(DbFormat::V1);
+    let paths = index
+        .get("entries")
+        .and_then(|entries| entries.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.get("path").and_then(|path| path.as_str()));
+    for path in paths {
+        let key = PageKey::new(path);
+        if db.has(&key) {
+            continue;
+        }
+        let page_file = page_paths::page_file(doc_path, key.as_str());
+        if page_file.is_file() {
+            db.add(key, fs::read_to_string(&page_file)?);
+        } else if let Some(page) = stored.page(&key) {
+            db.insert(key, page.clone());
+        } else {
+            log::warn!("页面不存在，未打包: {}", path);
+        }
+    }
+    Ok(db)
+}
+
+/// 将离线包安装到文档根目录，返回安装的文档目录名
+///
+/// 先解压到临时目录，成功后替换已安装的同名文档。
+pub fn unpack_package(file: &Path, docs_root: &Path) -> Result<String> {
+    let name = file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::Doc(format!("无效的包文件: {:?}", file)))?;
+    let (format, stem) = PackageFormat::from_file_name(name)
+        .ok_or_else(|| Error::Doc(format!("无法识别的包格式: {}（应为 .tar.gz 或 .json.gz）", name)))?;
+    let reader = GzDecoder::new(BufReader::new(fs::File::open(file)?));
+
+    let (doc_dir, staging) = match format {
+        PackageFormat::TarGz => {
+            let doc_dir = checked_dirname(stem)?;
+            let staging = docs_root.join(format!(".{}.partial", doc_dir));
+            reset_dir(&staging)?;
+            if let Err(e) = tar::Archive::new(reader).unpack(&staging) {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e.into());
+            }
+            (doc_dir, staging)
+        }
+        PackageFormat::JsonGz => {
+            let package: serde_json::Value = serde_json::from_reader(reader)?;
+            let doc_dir = checked_dirname(package.get("doc").and_then(|doc| doc.as_str()).unwrap_or(stem))?;
+            let staging = docs_root.join(format!(".{}.partial", doc_dir));
+            reset_dir(&staging)?;
+            let index = package
+                .get("index")
+                .ok_or_else(|| Error::Doc(format!("{} 中缺少 index", name)))?;
+            fs::write(staging.join(INDEX_FILENAME), serde_json::to_vec(index)?)?;
+            let db = package.get("db").cloned().unwrap_or_else(|| serde_json::json!({}));
+            fs::write(staging.join(DB_FILENAME), serde_json::to_vec(&db)?)?;
+            if let Some(meta) = package.get("meta").filter(|meta| !meta.is_null()) {
+                fs::write(staging.join(META_FILENAME), serde_json::to_vec_pretty(meta)?)?;
+            }
+            (doc_dir, staging)
+        }
+    };
+
+    if !staging.join(INDEX_FILENAME).exists() || !staging.join(DB_FILENAME).exists() {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(Error::Doc(format!("{} 中缺少 index.json 或 db.json", name)));
+    }
+    let target = docs_root.join(&doc_dir);
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::rename(&staging, &target)?;
+    Ok(doc_dir)
+}
+
+/// 检查包中的文档目录名，不允许路径分隔符和隐藏目录
+pub(super) fn checked_dirname(dirname: &str) -> Result<String> {
+    if dirname.is_empty()
+        || dirname.starts_with('.')
+        || dirname.contains(['/', '\\'])
+        || DocRegistry::canonical_dirname(dirname).is_empty()
+    {
+        return Err(Error::Doc(format!("无效的文档目录名: {:?}", dirname)));
+    }
+    Ok(dirname.to_string())
+}
+
+/// 清空并重新创建目录
+pub(super) fn reset_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 创建页面分别保存在页面文件和 db.json 中的文档
+    fn fixture_doc(root: &Path) -> PathBuf {
+        let dir = root.join("babel~7");
+        fs::create_dir_all(dir.join("guide")).unwrap();
+        fs::write(
+            dir.join(INDEX_FILENAME),
+            r#"{"entries":[{"name":"Guide","path":"guide","type":"Guides"},{"name":"Plugins","path":"plugins#list","type":"Guides"}],"types":[]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join(DB_FILENAME), r#"{"plugins": "<p>plugins</p>"}"#).unwrap();
+        fs::write(dir.join(META_FILENAME), r#"{"name":"Babel","release":"7.24"}"#).unwrap();
+        fs::write(dir.join("guide").join("index.html"), "<p>guide</p>").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_package_round_trip_in_both_formats() {
+        let docs = TempDir::new().unwrap();
+        let doc = fixture_doc(docs.path());
+        for format in [PackageFormat::TarGz, PackageFormat::JsonGz] {
+            let output = TempDir::new().unwrap();
+            let file = write_package(&doc, output.path(), format).unwrap();
+            assert_eq!(
+                file.file_name().unwrap().to_str().unwrap(),
+                format!("babel~7.{}", format.extension())
+            );
+
+            let target = TempDir::new().unwrap();
+            assert_eq!(unpack_package(&file, target.path()).unwrap(), "babel~7");
+            let mut registry = DocRegistry::new();
+            registry.load_from_disk(&[target.path()]).unwrap();
+            let installed = registry.find_with_version("babel", "7").unwrap();
+            assert_eq!(installed.name, "Babel");
+            assert_eq!(installed.entries.len(), 2);
+
+            let db = PageDb::load(&target.path().join("babel~7").join(DB_FILENAME)).unwrap();
+            assert_eq!(db.get(&PageKey::new("guide")).unwrap(), "<p>guide</p>");
+            assert_eq!(db.get(&PageKey::new("plugins")).unwrap(), "<p>plugins</p>");
+            assert!(!target.path().join(".babel~7.partial").exists());
+        }
+    }
+
+    #[test]
+    fn test_unpack_rejects_unknown_or_unsafe_packages() {
+        let target = TempDir::new().unwrap();
+        let file = target.path().join("babel.zip");
+        fs::write(&file, "").unwrap();
+        assert!(unpack_package(&file, target.path()).is_err());
+
+        assert!(checked_dirname("../babel").is_err());
+        assert!(checked_dirname(".releases").is_err());
+        assert_eq!(checked_dirname("babel~7@zh-CN").unwrap(), "babel~7@zh-CN");
+    }
+}
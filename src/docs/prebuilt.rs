@@ -0,0 +1,423 @@
+//! 预构建文档的增量下载
+//!
+//! devdocs 在 `docs.json` 中列出每个预构建文档的修改时间（`mtime`）和 db.json
+//! 的大小（`db_size`），归档位于 `<downloads>/<slug>.tar.gz`。下载前先读取
+//! `docs.json`，与已安装文档 meta.json 中记录的值相同时跳过下载。
+//!
+//! 归档先写入文档根目录下的 `.<slug>.tar.gz.partial`，传输中断时保留已收到的
+//! 部分，下次下载时通过 `Range` 请求续传。传输完成后校验大小（以及 `docs.json`
+//! 提供的摘要），再解压替换已安装的文档。
+
+use super::mirror::{sha256_hex, unpack_archive, MirrorFailure};
+use crate::core::doc::META_FILENAME;
+use crate::core::error::{Error, Result};
+use crate::core::http_fetch::TlsOptions;
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// devdocs 的文档列表地址
+pub const DEVDOCS_DOCS_URL: &str = "https://devdocs.io/docs.json";
+
+/// devdocs 的归档下载地址
+pub const DEVDOCS_DOWNLOADS_URL: &str = "https://downloads.devdocs.io";
+
+/// 预构建文档的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrebuiltSource {
+    /// 文档列表（`docs.json`）的地址
+    pub docs_url: String,
+    /// 归档所在的目录地址
+    pub downloads_url: String,
+    /// 下载使用的 TLS 设置
+    pub tls: TlsOptions,
+}
+
+impl Default for PrebuiltSource {
+    fn default() -> Self {
+        Self::new(DEVDOCS_DOCS_URL, DEVDOCS_DOWNLOADS_URL)
+    }
+}
+
+impl PrebuiltSource {
+    /// 使用指定的文档列表和归档地址
+    pub fn new(docs_url: &str, downloads_url: &str) -> Self {
+        Self {
+            docs_url: docs_url.to_string(),
+            downloads_url: downloads_url.trim_end_matches('/').to_string(),
+            tls: TlsOptions::default(),
+        }
+    }
+
+    /// 设置下载使用的 TLS 设置
+    pub fn with_tls(mut self, tls: TlsOptions) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// 文档归档的地址
+    pub fn archive_url(&self, slug: &str) -> String {
+        format!("{}/{}.tar.gz", self.downloads_url, slug)
+    }
+}
+
+/// `docs.json` 中的单个文档
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RemoteDoc {
+    /// 文档名称
+    pub name: String,
+    /// 文档目录名（`slug[~version]`）
+    pub slug: String,
+    /// 文档版本
+    #[serde(default)]
+    pub version: String,
+    /// 文档修改时间
+    pub mtime: u64,
+    /// db.json 的大小（字节）
+    #[serde(default)]
+    pub db_size: u64,
+    /// 归档的 SHA-256 摘要（十六进制），devdocs 不提供，自建的来源可以提供
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+/// 单个文档的下载结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrebuiltStatus {
+    /// 与已安装的文档相同，没有下载
+    Skipped,
+    /// 下载了完整的归档
+    Updated,
+    /// 从上次中断的位置续传
+    Resumed,
+}
+
+impl PrebuiltStatus {
+    /// 输出中的状态名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrebuiltStatus::Skipped => "skipped",
+            PrebuiltStatus::Updated => "updated",
+            PrebuiltStatus::Resumed => "resumed",
+        }
+    }
+}
+
+/// 成功处理的文档
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrebuiltOutcome {
+    /// 文档目录名
+    pub doc: String,
+    /// 处理结果
+    pub status: PrebuiltStatus,
+}
+
+/// 下载预构建文档的结果
+#[derive(Debug, Default)]
+pub struct PrebuiltReport {
+    /// 成功处理的文档
+    pub outcomes: Vec<PrebuiltOutcome>,
+    /// 处理失败的文档
+    pub failures: Vec<MirrorFailure>,
+}
+
+/// 读取远程文档列表
+pub async fn fetch_remote_docs(client: &reqwest::Client, source: &PrebuiltSource) -> Result<Vec<RemoteDoc>> {
+    let response = client.get(&source.docs_url).send().await?;
+    if !response.status().is_success() {
+        return Err(Error::HttpError(response.status().as_u16()));
+    }
+    Ok(serde_json::from_slice(&response.bytes().await?)?)
+}
+
+/// 已安装的文档是否与远程文档相同
+///
+/// 比较 meta.json 中记录的 `mtime` 和 `db_size`，没有记录时视为不同。
+pub fn is_up_to_date(remote: &RemoteDoc, docs_root: &Path) -> bool {
+    let meta_path = docs_root.join(&remote.slug).join(META_FILENAME);
+    let Some(meta) = fs::read_to_string(meta_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return false;
+    };
+    meta["mtime"].as_u64() == Some(remote.mtime) && meta["db_size"].as_u64() == Some(remote.db_size)
+}
+
+/// 下载预构建文档到文档根目录
+///
+/// `docs` 为文档目录名（`slug[~version]`）列表。单个文档失败不会中断其他文档，
+/// 中断的归档保留在 `.partial` 文件中，下次下载时续传。
+pub async fn download_prebuilt(
+    source: &PrebuiltSource,
+    docs_root: &Path,
+    docs: &[String],
+) -> Result<PrebuiltReport> {
+    let client = source.tls.client()?;
+    let remote_docs = fetch_remote_docs(&client, source).await?;
+    fs::create_dir_all(docs_root)?;
+
+    let mut report = PrebuiltReport::default();
+    for doc in docs {
+        let result = match remote_docs.iter().find(|remote| remote.slug == *doc) {
+            Some(remote) => install_prebuilt(&client, source, remote, docs_root).await,
+            None => Err(Error::Doc(format!("远程没有文档: {}", doc))),
+        };
+        match result {
+            Ok(status) => report.outcomes.push(PrebuiltOutcome {
+                doc: doc.clone(),
+                status,
+            }),
+            Err(e) => report.failures.push(MirrorFailure {
+                doc: doc.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// 下载并安装单个文档，已是最新时跳过
+async fn install_prebuilt(
+    client: &reqwest::Client,
+    source: &PrebuiltSource,
+    remote: &RemoteDoc,
+    docs_root: &Path,
+) -> Result<PrebuiltStatus> {
+    if is_up_to_date(remote, docs_root) {
+        return Ok(PrebuiltStatus::Skipped);
+    }
+
+    let partial = partial_path(docs_root, &remote.slug);
+    let resumed = download_archive(client, &source.archive_url(&remote.slug), &partial).await?;
+
+    let bytes = fs::read(&partial)?;
+    if let Some(expected) = &remote.digest {
+        let digest = sha256_hex(&bytes);
+        if digest != *expected {
+            // 摘要不符时续传没有意义，删除后下次重新下载
+            fs::remove_file(&partial)?;
+            return Err(Error::Doc(format!(
+                "{}.tar.gz 摘要不符: 应为 {}，实际为 {}",
+                remote.slug, expected, digest
+            )));
+        }
+    }
+    unpack_archive(&bytes, docs_root, &remote.slug)?;
+    write_remote_meta(remote, &docs_root.join(&remote.slug))?;
+    fs::remove_file(&partial)?;
+
+    Ok(if resumed {
+        PrebuiltStatus::Resumed
+    } else {
+        PrebuiltStatus::Updated
+    })
+}
+
+/// 归档下载过程中的临时文件
+pub fn partial_path(docs_root: &Path, slug: &str) -> PathBuf {
+    docs_root.join(format!(".{}.tar.gz.partial", slug))
+}
+
+/// 将归档下载到 `partial`，返回是否为续传
+///
+/// `partial` 已有内容时请求剩余部分，服务器不支持续传（返回 200）时从头下载，
+/// 续传位置无效（返回 416）时删除 `partial` 后从头下载。完成后校验文件大小
+/// 与 `Content-Range` 或 `Content-Length` 给出的大小一致。
+async fn download_archive(client: &reqwest::Client, url: &str, partial: &Path) -> Result<bool> {
+    let offset = fs::metadata(partial).map(|metadata| metadata.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await?;
+
+    let status = response.status().as_u16();
+    let (resumed, expected_size) = match status {
+        206 => {
+            let range = response
+                .headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_content_range);
+            match range {
+                Some((start, total)) if start == offset => (true, total),
+                _ => {
+                    fs::remove_file(partial)?;
+                    return Err(Error::Doc(format!("{} 的续传范围无效，已删除未完成的下载", url)));
+                }
+            }
+        }
+        416 if offset > 0 => {
+            fs::remove_file(partial)?;
+            return Box::pin(download_archive(client, url, partial)).await;
+        }
+        200..=299 => (false, response.content_length()),
+        _ => return Err(Error::HttpError(status)),
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(partial)?;
+    // 边接收边写入，中断时已收到的部分留在 partial 中
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+    }
+    file.flush()?;
+
+    let size = file.metadata()?.len();
+    if let Some(expected) = expected_size {
+        if size != expected {
+            return Err(Error::Doc(format!("{} 大小不符: 应为 {}，实际为 {}", url, expected, size)));
+        }
+    }
+    Ok(resumed)
+}
+
+/// 解析 `Content-Range: bytes <start>-<end>/<total>`，返回起始位置和总大小
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// 在 meta.json 中记录远程文档的修改时间和大小，供下次比较
+fn write_remote_meta(remote: &RemoteDoc, doc_dir: &Path) -> Result<()> {
+    let meta_path = doc_dir.join(META_FILENAME);
+    let mut meta = match fs::read_to_string(&meta_path).ok().map(|content| serde_json::from_str(&content)) {
+        Some(Ok(serde_json::Value::Object(map))) => map,
+        _ => serde_json::Map::new(),
+    };
+    meta.entry("name").or_insert_with(|| remote.name.clone().into());
+    meta.entry("version").or_insert_with(|| remote.version.clone().into());
+    meta.insert("mtime".to_string(), remote.mtime.into());
+    meta.insert("db_size".to_string(), remote.db_size.into());
+    fs::write(meta_path, serde_json::to_string_pretty(&meta)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tempfile::TempDir;
+
+    /// 生成包含 index.json 和 db.json 的归档，db.json 较大以便分段传输
+    fn fixture_archive() -> Vec<u8> {
+        let doc = TempDir::new().unwrap();
+        fs::write(doc.path().join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        let pages: serde_json::Map<String, serde_json::Value> = (0..200)
+            .map(|i| (format!("page{}", i), format!("<p>{}</p>", sha256_hex(&[i as u8])).into()))
+            .collect();
+        fs::write(doc.path().join("db.json"), serde_json::to_string(&pages).unwrap()).unwrap();
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        builder.append_dir_all(".", doc.path()).unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn docs_json(mtime: u64) -> String {
+        format!(r#"[{{"name":"CSS","slug":"css","type":"simple","version":"","mtime":{},"db_size":1234}}]"#, mtime)
+    }
+
+    async fn mock_docs_json(server: &mut mockito::Server, mtime: u64) -> mockito::Mock {
+        server
+            .mock("GET", "/docs.json")
+            .with_body(docs_json(mtime))
+            .create_async()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_remote_is_skipped() {
+        let mut server = mockito::Server::new_async().await;
+        mock_docs_json(&mut server, 1700000000).await;
+        let archive = server.mock("GET", "/css.tar.gz").expect(0).create_async().await;
+
+        let root = TempDir::new().unwrap();
+        fs::create_dir_all(root.path().join("css")).unwrap();
+        fs::write(
+            root.path().join("css").join(META_FILENAME),
+            r#"{"name":"CSS","mtime":1700000000,"db_size":1234}"#,
+        )
+        .unwrap();
+
+        let source = PrebuiltSource::new(&format!("{}/docs.json", server.url()), &server.url());
+        let report = download_prebuilt(&source, root.path(), &["css".to_string()]).await.unwrap();
+        assert_eq!(report.outcomes[0].status, PrebuiltStatus::Skipped);
+        archive.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_is_resumed() {
+        let bytes = fixture_archive();
+        let half = bytes.len() / 2;
+        let mut server = mockito::Server::new_async().await;
+        mock_docs_json(&mut server, 1700000100).await;
+        let source = PrebuiltSource::new(&format!("{}/docs.json", server.url()), &server.url());
+        let root = TempDir::new().unwrap();
+        let docs = ["css".to_string()];
+
+        // 第一次传输发送一半后连接中断
+        let first_half = bytes[..half].to_vec();
+        let interrupted = server
+            .mock("GET", "/css.tar.gz")
+            .with_chunked_body(move |writer| {
+                writer.write_all(&first_half)?;
+                writer.flush()?;
+                // 等已写入的部分发送出去再断开
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "interrupted"))
+            })
+            .create_async()
+            .await;
+        let report = download_prebuilt(&source, root.path(), &docs).await.unwrap();
+        assert_eq!(report.failures.len(), 1);
+        let partial = partial_path(root.path(), "css");
+        let received = fs::metadata(&partial).unwrap().len() as usize;
+        assert!(received > 0 && received <= half);
+        assert!(!root.path().join("css").exists());
+        interrupted.remove_async().await;
+
+        // 第二次只请求剩余部分
+        let resumed = server
+            .mock("GET", "/css.tar.gz")
+            .match_header("range", format!("bytes={}-", received).as_str())
+            .with_status(206)
+            .with_header(
+                "content-range",
+                &format!("bytes {}-{}/{}", received, bytes.len() - 1, bytes.len()),
+            )
+            .with_body(&bytes[received..])
+            .create_async()
+            .await;
+        let report = download_prebuilt(&source, root.path(), &docs).await.unwrap();
+        resumed.assert_async().await;
+        assert!(report.failures.is_empty());
+        assert_eq!(report.outcomes[0].status, PrebuiltStatus::Resumed);
+        assert!(!partial.exists());
+        assert!(root.path().join("css").join("db.json").exists());
+
+        let meta: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.path().join("css").join(META_FILENAME)).unwrap()).unwrap();
+        assert_eq!(meta["mtime"], 1700000100);
+        assert_eq!(meta["name"], "CSS");
+
+        // 远程没有变化，再次下载时跳过
+        let report = download_prebuilt(&source, root.path(), &docs).await.unwrap();
+        assert_eq!(report.outcomes[0].status, PrebuiltStatus::Skipped);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 100-199/200"), Some((100, Some(200))));
+        assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, None)));
+        assert_eq!(parse_content_range("items 1-2/3"), None);
+    }
+}
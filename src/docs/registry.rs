@@ -1,17 +1,101 @@
 //! 文档注册表管理
 
+use super::manifest::{self, Clock, Manifest, ManifestEntry, TypeCount};
 use super::Documentation;
-use crate::core::error::Result;
+use crate::core::config::Config;
+use crate::core::doc::INDEX_FILENAME;
+use crate::core::error::{Error, Result};
+use crate::core::index_entry::IndexEntry;
+use crate::core::scraper::quality;
+use crate::core::types::{DocSlug, PageKey};
+use crate::storage::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+
+/// 文档清单文件名
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// 条目名称与整个查询完全相同时的分数
+const SCORE_EXACT: u32 = 100;
+/// 查询词出现在名称开头时的分数
+const SCORE_PREFIX: u32 = 30;
+/// 查询词出现在名称中某个单词的开头（如 `Map.prototype.get` 中的 `get`）时的分数
+const SCORE_WORD_START: u32 = 20;
+/// 查询词出现在名称中间时的分数
+const SCORE_SUBSTRING: u32 = 10;
 
 /// 管理可用文档的注册表
+///
+/// 除全局命名空间的文档外，还可以加载各个命名空间（见 [`DocRegistry::load_namespace`]）。
+/// 不带命名空间的查找和搜索只涉及全局文档；带命名空间的版本（`*_in`）先在命名空间中
+/// 查找，找不到时回退到全局文档。
 pub struct DocRegistry {
     docs: Vec<Documentation>,
+    /// 命名空间名到该命名空间中文档的映射
+    namespaces: BTreeMap<String, Vec<Documentation>>,
 }
 
 impl DocRegistry {
     /// 创建新的空注册表
     pub fn new() -> Self {
-        Self { docs: Vec::new() }
+        Self {
+            docs: Vec::new(),
+            namespaces: BTreeMap::new(),
+        }
+    }
+
+    /// 按配置加载全局文档和所有命名空间
+    pub fn load_config(&mut self, config: &Config) -> Result<()> {
+        self.load_from_disk(&config.docs_paths)?;
+        for name in config.namespaces.keys() {
+            if let Some(root) = config.namespace_root(name) {
+                self.load_namespace(name, &root)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 从 `root` 加载一个命名空间的文档，替换该命名空间中已有的文档
+    ///
+    /// 目录不存在时命名空间为空（只有全局文档可用）。
+    pub fn load_namespace(&mut self, name: &str, root: &Path) -> Result<()> {
+        let mut loaded = DocRegistry::new();
+        if root.exists() {
+            loaded.load_root(root, false)?;
+        } else {
+            log::warn!("命名空间 {} 的文档目录不存在: {:?}", name, root);
+        }
+        let docs = loaded.docs.into_iter().map(|doc| doc.with_namespace(name)).collect();
+        self.namespaces.insert(name.to_string(), docs);
+        Ok(())
+    }
+
+    /// 是否加载了该命名空间
+    pub fn has_namespace(&self, name: &str) -> bool {
+        self.namespaces.contains_key(name)
+    }
+
+    /// 已加载的命名空间名
+    pub fn namespace_names(&self) -> impl Iterator<Item = &str> {
+        self.namespaces.keys().map(String::as_str)
+    }
+
+    /// 在命名空间中可见的文档：先是命名空间中的文档，再是没有被它们覆盖的全局文档
+    ///
+    /// 命名空间中的文档覆盖 slug、版本和语言都相同的全局文档。`namespace` 为 None
+    /// 或没有加载该命名空间时只返回全局文档。
+    pub fn visible(&self, namespace: Option<&str>) -> Vec<&Documentation> {
+        let scoped: &[Documentation] = namespace
+            .and_then(|name| self.namespaces.get(name))
+            .map_or(&[], Vec::as_slice);
+        let shadowed: Vec<String> = scoped.iter().map(Documentation::path).collect();
+        scoped
+            .iter()
+            .chain(self.docs.iter().filter(|doc| !shadowed.contains(&doc.path())))
+            .collect()
     }
 
     /// 添加文档到注册表
@@ -24,160 +108,475 @@ impl DocRegistry {
         &self.docs
     }
 
-    /// 通过别名查找文档
+    /// 通过别名查找文档，优先返回默认语言的版本
     pub fn find(&self, slug: &str) -> Option<&Documentation> {
-        self.docs.iter().find(|doc| doc.slug == slug)
+        find_among(&self.docs, slug)
     }
 
-    /// 通过别名和版本查找文档
-    pub fn find_with_version(&self, slug: &str, version: &str) -> Option<&Documentation> {
+    /// 按语言偏好查找文档
+    ///
+    /// 依次尝试每个语言：先完全匹配（不区分大小写），再匹配主语言标签
+    /// （如 `zh` 与 `zh-CN`）。都没有安装时回退到默认语言的版本。
+    /// `slug` 可以是 `<slug>~<version>`，此时只在该版本中查找。
+    pub fn find_localized<S: AsRef<str>>(&self, slug: &str, locales: &[S]) -> Option<&Documentation> {
+        find_localized_among(&self.docs, slug, locales)
+    }
+
+    /// 在命名空间中按语言偏好查找文档，命名空间中没有该文档时回退到全局文档
+    ///
+    /// 命名空间中有该文档的任何语言版本时都使用命名空间中的版本，不与全局文档
+    /// 混合。`namespace` 为 None 时与 [`DocRegistry::find_localized`] 相同。
+    pub fn find_localized_in<S: AsRef<str>>(
+        &self,
+        namespace: Option<&str>,
+        slug: &str,
+        locales: &[S],
+    ) -> Option<&Documentation> {
+        namespace
+            .and_then(|name| self.namespaces.get(name))
+            .and_then(|docs| find_localized_among(docs, slug, locales))
+            .or_else(|| self.find_localized(slug, locales))
+    }
+
+    /// 获取同一文档的所有语言版本
+    pub fn locale_variants(&self, slug: &str, version: &str) -> Vec<&Documentation> {
         self.docs
             .iter()
-            .find(|doc| doc.slug == slug && doc.version == version)
+            .filter(|doc| doc.slug == slug && doc.version == version)
+            .collect()
+    }
+
+    /// 通过别名和版本查找文档，优先返回默认语言的版本
+    pub fn find_with_version(&self, slug: &str, version: &str) -> Option<&Documentation> {
+        find_with_version_among(&self.docs, slug, version)
     }
 
-    /// 加载所有文档从磁盘
-    pub fn load_from_disk(&mut self, path: &str) -> Result<()> {
-        use crate::core::error::Error;
-        use std::fs;
-        use std::path::Path;
-        use std::time::UNIX_EPOCH;
+    /// 同一文档已安装的各版本（同一语言），按版本号从旧到新排序
+    pub fn versions(&self, slug: &str, locale: Option<&str>) -> Vec<&Documentation> {
+        versions_among(self.docs.iter(), slug, locale)
+    }
 
-        let base_path = Path::new(path);
-        if !base_path.exists() {
-            return Err(Error::Message(format!("文档路径不存在: {}", path)).into());
-        }
+    /// 文档的某个版本中是否有该页面，见 [`Documentation::has_page`]
+    pub fn has_page(&self, slug: &str, version: &str, path: &str) -> bool {
+        self.find_with_version(slug, version)
+            .is_some_and(|doc| doc.has_page(&PageKey::new(path)))
+    }
 
+    /// 从多个文档根目录加载所有文档
+    ///
+    /// 第一个根目录为可写目录，其余为只读叠加目录。
+    /// 当多个根目录中存在相同 slug 和版本的文档时，靠前的根目录优先。
+    pub fn load_from_disk<P: AsRef<Path>>(&mut self, roots: &[P]) -> Result<()> {
         // 清空当前文档列表
         self.docs.clear();
 
+        for (i, root) in roots.iter().enumerate() {
+            let root = root.as_ref();
+            if i > 0 && !root.exists() {
+                log::warn!("只读文档目录不存在，已跳过: {:?}", root);
+                continue;
+            }
+            self.load_root(root, i > 0)?;
+        }
+
+        Ok(())
+    }
+
+    /// 从单个根目录加载文档
+    fn load_root(&mut self, base_path: &Path, read_only: bool) -> Result<()> {
+        if !base_path.exists() {
+            return Err(Error::Message(format!("文档路径不存在: {:?}", base_path)));
+        }
+
         // 遍历文档目录
         let entries = match fs::read_dir(base_path) {
             Ok(entries) => entries,
-            Err(e) => return Err(Error::Message(format!("无法读取文档目录: {}", e)).into()),
+            Err(e) => return Err(Error::Message(format!("无法读取文档目录: {}", e))),
         };
 
-        for entry_result in entries {
-            let entry = match entry_result {
-                Ok(entry) => entry,
-                Err(_) => continue,
-            };
+        // 规范写法的目录名排在前面，只有大小写不同的目录冲突时使用规范的目录。
+        // 以 `.` 开头的目录是解压和同步时的暂存目录，不是文档
+        let mut dirnames: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|dirname| !dirname.starts_with('.'))
+            .collect();
+        dirnames.sort_by_key(|dirname| (Self::canonical_dirname(dirname) != *dirname, dirname.clone()));
 
-            let entry_path = entry.path();
-            if !entry_path.is_dir() {
+        let loaded_before = self.docs.len();
+        for dirname in dirnames {
+            let canonical = Self::canonical_dirname(&dirname);
+            // 靠前的根目录优先
+            if let Some(pos) = self.docs.iter().position(|doc| doc.path() == canonical) {
+                if pos >= loaded_before {
+                    log::warn!(
+                        "文档目录 {:?} 与 {:?} 对应同一个文档 {}，已忽略",
+                        base_path.join(&dirname),
+                        self.docs[pos].dir(),
+                        canonical
+                    );
+                }
                 continue;
             }
+            if canonical != dirname {
+                log::warn!("文档目录名 {:?} 不是规范写法，按 {} 加载", base_path.join(&dirname), canonical);
+            }
 
-            // 获取文档信息
-            if let Some(dirname) = entry_path.file_name().and_then(|n| n.to_str()) {
-                // 解析目录名
-                let (slug, version) = if dirname.contains('~') {
-                    let parts: Vec<&str> = dirname.split('~').collect();
-                    (parts[0].to_string(), parts[1].to_string())
-                } else {
-                    (dirname.to_string(), String::new())
-                };
+            // 添加到注册表
+            if let Some(doc) = Self::load_doc(base_path, &dirname, read_only) {
+                self.add(doc);
+            }
+        }
 
-                // 尝试读取index.json和meta.json
-                let index_path = entry_path.join("index.json");
-                let meta_path = entry_path.join("meta.json");
-                let db_path = entry_path.join("db.json");
+        Ok(())
+    }
 
-                if !index_path.exists() || !db_path.exists() {
-                    continue;
-                }
+    /// 目录名对应的规范写法，slug 按 [`DocSlug`] 规范化
+    pub(crate) fn canonical_dirname(dirname: &str) -> String {
+        let (slug, version, locale) = Self::parse_dirname(dirname);
+        Documentation::new(&slug, &slug, &version).with_locale(&locale).path()
+    }
+
+    /// 重新加载单个文档目录
+    ///
+    /// 按根目录顺序查找该目录，只重新读取这一个文档，其余文档保持不变。
+    /// 目录已不存在时从注册表中移除该文档。返回文档是否仍然存在。
+    /// 同一个根目录中规范写法的目录优先于只有大小写不同的目录。
+    pub fn reload_doc<P: AsRef<Path>>(&mut self, roots: &[P], dirname: &str) -> bool {
+        let canonical = Self::canonical_dirname(dirname);
+        let doc = roots.iter().enumerate().find_map(|(i, root)| {
+            Self::load_doc(root.as_ref(), &canonical, i > 0).or_else(|| Self::load_doc(root.as_ref(), dirname, i > 0))
+        });
+        let pos = self.docs.iter().position(|doc| doc.path() == canonical);
+
+        match (doc, pos) {
+            (Some(doc), Some(pos)) => {
+                self.docs[pos] = doc;
+                true
+            }
+            (Some(doc), None) => {
+                self.add(doc);
+                true
+            }
+            (None, Some(pos)) => {
+                self.docs.remove(pos);
+                false
+            }
+            (None, None) => false,
+        }
+    }
+
+    /// 重新加载命名空间中的单个文档目录，规则同 [`Self::reload_doc`]
+    ///
+    /// `root` 为命名空间的目录，返回文档是否仍然存在。
+    pub fn reload_namespace_doc(&mut self, name: &str, root: &Path, dirname: &str) -> bool {
+        let canonical = Self::canonical_dirname(dirname);
+        let doc = Self::load_doc(root, &canonical, false)
+            .or_else(|| Self::load_doc(root, dirname, false))
+            .map(|doc| doc.with_namespace(name));
+        let docs = self.namespaces.entry(name.to_string()).or_default();
+        let pos = docs.iter().position(|doc| doc.path() == canonical);
+
+        match (doc, pos) {
+            (Some(doc), Some(pos)) => {
+                docs[pos] = doc;
+                true
+            }
+            (Some(doc), None) => {
+                docs.push(doc);
+                true
+            }
+            (None, Some(pos)) => {
+                docs.remove(pos);
+                false
+            }
+            (None, None) => false,
+        }
+    }
 
-                // 提取基本信息
-                let index_size = fs::metadata(&index_path)
-                    .map(|m| m.len() as usize)
-                    .unwrap_or(0);
-                let db_size = fs::metadata(&db_path)
-                    .map(|m| m.len() as usize)
-                    .unwrap_or(0);
-
-                // 获取修改时间
-                let mtime = match fs::metadata(&entry_path) {
-                    Ok(metadata) => match metadata.modified() {
-                        Ok(modified_time) => match modified_time.duration_since(UNIX_EPOCH) {
-                            Ok(duration) => duration.as_secs(),
-                            Err(_) => 0,
-                        },
-                        Err(_) => 0,
-                    },
+    /// 解析文档目录名（`slug[~version][@locale]`），返回规范化的 slug、版本和语言
+    fn parse_dirname(dirname: &str) -> (String, String, String) {
+        let (base, locale) = match dirname.rsplit_once('@') {
+            Some((base, locale)) => (base, locale),
+            None => (dirname, ""),
+        };
+        if base.contains('~') {
+            let parts: Vec<&str> = base.split('~').collect();
+            (DocSlug::new(parts[0]).into_string(), parts[1].to_string(), locale.to_string())
+        } else {
+            (DocSlug::new(base).into_string(), String::new(), locale.to_string())
+        }
+    }
+
+    /// 从根目录中加载单个文档，缺少 index.json 或 db.json 时返回 None
+    fn load_doc(base_path: &Path, dirname: &str, read_only: bool) -> Option<Documentation> {
+        let entry_path = base_path.join(dirname);
+        if !entry_path.is_dir() {
+            return None;
+        }
+        let (slug, version, locale) = Self::parse_dirname(dirname);
+
+        // 尝试读取index.json和meta.json
+        let index_path = entry_path.join("index.json");
+        let meta_path = entry_path.join("meta.json");
+        let db_path = entry_path.join("db.json");
+
+        if !index_path.exists() || !db_path.exists() {
+            return None;
+        }
+
+        // 提取基本信息
+        let index_size = fs::metadata(&index_path)
+            .map(|m| m.len() as usize)
+            .unwrap_or(0);
+        let db_size = fs::metadata(&db_path)
+            .map(|m| m.len() as usize)
+            .unwrap_or(0);
+
+        // 获取修改时间
+        let mtime = match fs::metadata(&entry_path) {
+            Ok(metadata) => match metadata.modified() {
+                Ok(modified_time) => match modified_time.duration_since(UNIX_EPOCH) {
+                    Ok(duration) => duration.as_secs(),
                     Err(_) => 0,
-                };
+                },
+                Err(_) => 0,
+            },
+            Err(_) => 0,
+        };
+
+        // 读取元数据
+        let mut doc = Documentation::new(&slug, &slug, &version)
+            .with_mtime(mtime)
+            .with_db_size(db_size)
+            .with_index_size(index_size)
+            .with_root(base_path, read_only)
+            .with_locale(&locale)
+            .with_dir_name(dirname)
+            .with_icon(entry_path.join("icon.png").exists());
 
-                // 读取元数据
-                let mut doc = Documentation::new(&slug, &slug, &version)
-                    .with_mtime(mtime)
-                    .with_db_size(db_size)
-                    .with_index_size(index_size);
-
-                // 尝试读取元数据文件
-                if meta_path.exists() {
-                    if let Ok(meta_content) = fs::read_to_string(&meta_path) {
-                        if let Ok(meta_json) =
-                            serde_json::from_str::<serde_json::Value>(&meta_content)
-                        {
-                            if let Some(release) = meta_json.get("release").and_then(|v| v.as_str())
-                            {
-                                doc = doc.with_release(release);
-                            }
-                            if let Some(name) = meta_json.get("name").and_then(|v| v.as_str()) {
-                                doc.name = name.to_string();
-                            }
-                        }
+        // 索引或元数据无法读取时仍然加载文档（没有条目或元数据），错误记录在文档中，
+        // 在 `docs list` 中显示
+        match json::read_json::<serde_json::Value>(&index_path) {
+            Ok(index) => {
+                doc = doc
+                    .with_entries(Self::index_array(&index, "entries"))
+                    .with_types(Self::index_array(&index, "types"));
+            }
+            Err(e) => doc = doc.with_load_error(e),
+        }
+
+        // 尝试读取元数据文件
+        if meta_path.exists() {
+            match json::read_json::<serde_json::Value>(&meta_path) {
+                Ok(meta_json) => {
+                    if let Some(release) = meta_json.get("release").and_then(|v| v.as_str())
+                    {
+                        doc = doc.with_release(release);
+                    }
+                    if let Some(name) = meta_json.get("name").and_then(|v| v.as_str()) {
+                        doc.name = name.to_string();
+                    }
+                    if let Some(doc_type) = meta_json.get("type").and_then(|v| v.as_str()) {
+                        doc = doc.with_doc_type(doc_type);
+                    }
+                    if let Some(attribution) = meta_json.get("attribution").and_then(|v| v.as_str()) {
+                        doc = doc.with_attribution(attribution);
+                    }
+                    if let Some(license) = meta_json.get("license").and_then(|v| v.as_str()) {
+                        doc = doc.with_license(license);
+                    }
+                    if let Some(noindex) = meta_json.get("noindex").and_then(|v| v.as_bool()) {
+                        doc = doc.with_noindex(noindex);
+                    }
+                    if let Some(landing) =
+                        meta_json.get("landing_path").and_then(|v| v.as_str())
+                    {
+                        doc = doc.with_landing_path(landing);
+                    }
+                    if let Some(quality) = quality::read_from_meta(&meta_json) {
+                        doc = doc.with_quality(quality.score);
                     }
                 }
-
-                // 添加到注册表
-                self.add(doc);
+                Err(e) => doc = doc.with_load_error(e),
             }
         }
 
-        Ok(())
+        for error in &doc.load_errors {
+            log::warn!("文档 {} 加载时出错: {}", doc.path(), error);
+        }
+        Some(doc)
     }
 
-    /// 生成清单JSON
-    pub fn generate_manifest(&self, path: &str) -> Result<()> {
-        use crate::core::error::Error;
-        use serde_json::{json, to_string_pretty};
-        use std::fs;
-        use std::path::Path;
+    /// 读取 index.json 中的数组，无法解析的元素会被跳过
+    fn index_array<T: serde::de::DeserializeOwned>(index: &serde_json::Value, key: &str) -> Vec<T> {
+        index
+            .get(key)
+            .and_then(|e| e.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| serde_json::from_value::<T>(item.clone()).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        let manifest_path = Path::new(path).join("manifest.json");
+    /// 加载时出错的文档，每项为文档路径和错误说明
+    pub fn load_errors(&self) -> Vec<(String, &str)> {
+        self.docs
+            .iter()
+            .flat_map(|doc| doc.load_errors.iter().map(move |error| (doc.path(), error.as_str())))
+            .collect()
+    }
+
+    /// 在所有文档的条目中搜索
+    ///
+    /// 结果按匹配程度排序，见 [`DocRegistry::search_scored`]。
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(&Documentation, &IndexEntry)> {
+        self.search_scored(query, limit)
+            .into_iter()
+            .map(|(_, doc, entry)| (doc, entry))
+            .collect()
+    }
+
+    /// 在所有文档的条目中搜索，同时返回每个结果的分数
+    ///
+    /// 查询按空白拆分为多个词，条目名称（不区分大小写）需要包含所有的词。
+    /// 分数为各个词的分数之和：出现在名称开头最高，其次是单词开头，
+    /// 再次是名称中间；名称与整个查询完全相同时分数最高，并且不论其他条目的
+    /// 分数总是排在最前面。分数相同时名称越短越靠前。
+    pub fn search_scored(&self, query: &str, limit: usize) -> Vec<(u32, &Documentation, &IndexEntry)> {
+        self.search_scored_with(query, limit, |_, _| 0)
+    }
+
+    /// 搜索条目并给匹配的条目加上 `boost` 返回的分数，用于按搜索历史调整排名
+    pub fn search_scored_with<F>(&self, query: &str, limit: usize, boost: F) -> Vec<(u32, &Documentation, &IndexEntry)>
+    where
+        F: Fn(&Documentation, &IndexEntry) -> u32,
+    {
+        search_docs(&self.docs, query, limit, boost, |_, _| true)
+    }
+
+    /// 在命名空间中可见的文档（见 [`DocRegistry::visible`]）中搜索条目
+    pub fn search_scored_in<F>(
+        &self,
+        namespace: Option<&str>,
+        query: &str,
+        limit: usize,
+        boost: F,
+    ) -> Vec<(u32, &Documentation, &IndexEntry)>
+    where
+        F: Fn(&Documentation, &IndexEntry) -> u32,
+    {
+        self.search_scored_filtered(namespace, query, limit, boost, |_, _| true)
+    }
+
+    /// 在命名空间中可见的文档中搜索满足 `filter` 的条目，用于限定文档和条目类型
+    pub fn search_scored_filtered<F, P>(
+        &self,
+        namespace: Option<&str>,
+        query: &str,
+        limit: usize,
+        boost: F,
+        filter: P,
+    ) -> Vec<(u32, &Documentation, &IndexEntry)>
+    where
+        F: Fn(&Documentation, &IndexEntry) -> u32,
+        P: Fn(&Documentation, &IndexEntry) -> bool,
+    {
+        search_docs(self.visible(namespace), query, limit, boost, filter)
+    }
+
+    /// 删除文档
+    ///
+    /// 只允许删除可写根目录中的文档，来自只读叠加目录的文档会返回错误。
+    pub fn remove(&mut self, slug: &str, version: &str) -> Result<()> {
+        let pos = self
+            .docs
+            .iter()
+            .position(|doc| doc.slug == slug && doc.version == version)
+            .ok_or_else(|| Error::Message(format!("文档不存在: {}", slug)))?;
 
-        // 创建JSON数组
-        let docs_json: Vec<serde_json::Value> = self
+        if self.docs[pos].read_only {
+            return Err(Error::Message(format!(
+                "文档位于只读目录中，无法删除: {:?}",
+                self.docs[pos].dir()
+            )));
+        }
+
+        let doc = self.docs.remove(pos);
+        fs::remove_dir_all(doc.dir())?;
+
+        Ok(())
+    }
+
+    /// 生成文档清单并写入 `output` 下的 manifest.json，返回写入的清单
+    ///
+    /// `generated_at` 由 `clock` 提供（通常为 [`manifest::system_clock`]）。清单先写入
+    /// 同一目录下的临时文件再重命名，读取方不会看到写了一半的文件。
+    /// 多处调用时应通过 [`manifest::regenerate`] 合并写入。
+    pub fn refresh_manifest(&self, output: &Path, clock: Clock) -> Result<Manifest> {
+        let docs = self
             .docs
             .iter()
             .map(|doc| {
-                json!({
-                    "name": doc.name,
-                    "slug": doc.slug,
-                    "version": doc.version,
-                    "release": doc.release,
-                    "mtime": doc.mtime,
-                    "db_size": doc.db_size,
-                    "index_size": doc.index_size
-                })
+                let mut entry = ManifestEntry {
+                    name: doc.name.clone(),
+                    slug: doc.slug.clone(),
+                    version: doc.version.clone(),
+                    locale: doc.locale.clone(),
+                    release: doc.release.clone(),
+                    mtime: doc.mtime,
+                    db_size: doc.db_size,
+                    index_size: doc.index_size,
+                    entry_count: 0,
+                    types: Vec::new(),
+                    warning: None,
+                };
+                // 单个文档的索引有问题时仍然列出该文档，不中断整个清单的生成
+                match index_summary(&doc.dir().join(INDEX_FILENAME)) {
+                    Ok((entry_count, types)) => {
+                        entry.entry_count = entry_count;
+                        entry.types = types
+                            .into_iter()
+                            .map(|(name, count)| TypeCount { name, count })
+                            .collect();
+                    }
+                    Err(warning) => {
+                        log::warn!("文档 {} 的索引无法统计: {}", doc.path(), warning);
+                        entry.warning = Some(warning);
+                    }
+                }
+                entry
             })
             .collect();
-
-        // 创建整体JSON
-        let manifest = json!({
-            "docs": docs_json,
-            "generated_at": chrono::Utc::now().timestamp()
-        });
+        let manifest = Manifest {
+            docs,
+            generated_at: clock(),
+        };
 
         // 写入文件
-        let content = to_string_pretty(&manifest)
+        let content = serde_json::to_string_pretty(&manifest)
             .map_err(|e| Error::Message(format!("无法序列化清单JSON: {}", e)))?;
 
-        fs::write(&manifest_path, content)
-            .map_err(|e| Error::Message(format!("无法写入清单文件: {}", e)))?;
+        static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+        let manifest_path = output.join(MANIFEST_FILENAME);
+        let tmp_path = output.join(format!(
+            ".{}.{}-{}.tmp",
+            MANIFEST_FILENAME,
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp_path, content)
+            .and_then(|_| fs::rename(&tmp_path, &manifest_path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                Error::Message(format!("无法写入清单文件: {}", e))
+            })?;
 
-        Ok(())
+        Ok(manifest)
     }
 }
 
@@ -186,3 +585,639 @@ impl Default for DocRegistry {
         Self::new()
     }
 }
+
+/// 获取语言标签中的主语言部分，如 `zh-CN` 中的 `zh`
+fn primary_subtag(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// 在 `docs` 中通过别名查找文档，优先返回默认语言的版本
+fn find_among<'a>(docs: &'a [Documentation], slug: &str) -> Option<&'a Documentation> {
+    docs.iter()
+        .find(|doc| doc.slug == slug && doc.locale.is_none())
+        .or_else(|| docs.iter().find(|doc| doc.slug == slug))
+}
+
+/// 在 `docs` 中通过别名和版本查找文档，优先返回默认语言的版本
+fn find_with_version_among<'a>(docs: &'a [Documentation], slug: &str, version: &str) -> Option<&'a Documentation> {
+    docs.iter()
+        .find(|doc| doc.slug == slug && doc.version == version && doc.locale.is_none())
+        .or_else(|| docs.iter().find(|doc| doc.slug == slug && doc.version == version))
+}
+
+/// `docs` 中同一文档同一语言的各版本，见 [`DocRegistry::versions`]
+pub fn versions_among<'a>(
+    docs: impl IntoIterator<Item = &'a Documentation>,
+    slug: &str,
+    locale: Option<&str>,
+) -> Vec<&'a Documentation> {
+    let mut versions: Vec<&Documentation> = docs
+        .into_iter()
+        .filter(|doc| doc.slug == slug && doc.locale.as_deref() == locale)
+        .collect();
+    versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+    versions
+}
+
+/// 按版本号比较：逐段比较，两段都是数字时按数值比较，没有版本号的文档排在最后
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => {}
+    }
+    let mut left = a.split(['.', '-']);
+    let mut right = b.split(['.', '-']);
+    loop {
+        let ordering = match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                _ => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// 在 `docs` 中按语言偏好查找文档，见 [`DocRegistry::find_localized`]
+fn find_localized_among<'a, S: AsRef<str>>(
+    docs: &'a [Documentation],
+    slug: &str,
+    locales: &[S],
+) -> Option<&'a Documentation> {
+    let (slug, version) = match slug.split_once('~') {
+        Some((slug, version)) => (slug, Some(version)),
+        None => (slug, None),
+    };
+    let variants: Vec<&Documentation> = docs
+        .iter()
+        .filter(|doc| doc.slug == slug && version.is_none_or(|version| doc.version == version))
+        .collect();
+
+    for locale in locales {
+        let locale = locale.as_ref();
+        if let Some(doc) = variants
+            .iter()
+            .find(|doc| doc.locale_or_default().eq_ignore_ascii_case(locale))
+        {
+            return Some(doc);
+        }
+        let primary = primary_subtag(locale);
+        if let Some(doc) = variants
+            .iter()
+            .find(|doc| primary_subtag(doc.locale_or_default()).eq_ignore_ascii_case(primary))
+        {
+            return Some(doc);
+        }
+    }
+
+    match version {
+        Some(version) => find_with_version_among(docs, slug, version),
+        None => find_among(docs, slug),
+    }
+}
+
+/// 在 `docs` 中满足 `filter` 的条目中搜索，见 [`DocRegistry::search_scored`]
+fn search_docs<'a, I, F, P>(
+    docs: I,
+    query: &str,
+    limit: usize,
+    boost: F,
+    filter: P,
+) -> Vec<(u32, &'a Documentation, &'a IndexEntry)>
+where
+    I: IntoIterator<Item = &'a Documentation>,
+    F: Fn(&Documentation, &IndexEntry) -> u32,
+    P: Fn(&Documentation, &IndexEntry) -> bool,
+{
+    let query = query.to_lowercase();
+    let tokens: Vec<&str> = query.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+    let query = tokens.join(" ");
+
+    // 多个词的分数之和可能超过完全匹配的分数，完全匹配的条目单独排在最前面
+    let mut results: Vec<(bool, u32, &Documentation, &IndexEntry)> = Vec::new();
+    for doc in docs {
+        for entry in doc.entries.iter().filter(|entry| filter(doc, entry)) {
+            let name = entry.name.to_lowercase();
+            if let Some(score) = match_score(&name, &query, &tokens) {
+                results.push((name == query, score + boost(doc, entry), doc, entry));
+            }
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(b.1.cmp(&a.1))
+            .then(a.3.name.len().cmp(&b.3.name.len()))
+            .then(a.3.name.cmp(&b.3.name))
+    });
+    results
+        .into_iter()
+        .take(limit)
+        .map(|(_, score, doc, entry)| (score, doc, entry))
+        .collect()
+}
+
+/// 条目名称（已转为小写）与查询的匹配分数，有查询词不在名称中时返回 None
+fn match_score(name: &str, query: &str, tokens: &[&str]) -> Option<u32> {
+    if name == query {
+        return Some(SCORE_EXACT);
+    }
+    let mut score = 0;
+    for token in tokens {
+        let position = name.find(token)?;
+        score += if position == 0 {
+            SCORE_PREFIX
+        } else if name.match_indices(token).any(|(position, _)| is_word_start(name, position)) {
+            SCORE_WORD_START
+        } else {
+            SCORE_SUBSTRING
+        };
+    }
+    Some(score)
+}
+
+/// `position` 处是否为名称中一个单词的开头
+fn is_word_start(name: &str, position: usize) -> bool {
+    name[..position]
+        .chars()
+        .next_back()
+        .is_some_and(|c| !c.is_alphanumeric())
+}
+
+/// 统计 index.json 中的条目总数和每种类型的条目数（按类型名称排序）
+///
+/// 文件不存在、不是有效的 JSON 或缺少 `entries` 数组时返回错误说明。
+fn index_summary(index_path: &Path) -> std::result::Result<(usize, Vec<(String, usize)>), String> {
+    let index: serde_json::Value = json::read_json(index_path).map_err(|e| e.to_string())?;
+    let entries = index
+        .get("entries")
+        .and_then(|entries| entries.as_array())
+        .ok_or_else(|| format!("{} 中缺少 entries 数组", INDEX_FILENAME))?;
+
+    let mut types: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in entries {
+        if let Some(entry_type) = entry.get("type").and_then(|t| t.as_str()) {
+            *types.entry(entry_type.to_string()).or_default() += 1;
+        }
+    }
+    Ok((entries.len(), types.into_iter().collect()))
+}
+
+/// 在 `root` 下生成只有空索引和名称的文档目录，供各模块的测试使用
+#[cfg(test)]
+pub(crate) fn write_doc(root: &Path, dirname: &str, name: &str) {
+    let dir = root.join(dirname);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+    fs::write(dir.join("db.json"), "{}").unwrap();
+    fs::write(dir.join("meta.json"), format!(r#"{{"name":"{}"}}"#, name)).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_entry_counts_and_types() {
+        let root = TempDir::new().unwrap();
+        write_doc(root.path(), "css", "CSS");
+        fs::write(
+            root.path().join("css").join("index.json"),
+            r#"{"entries":[
+                {"name":"color","path":"color","type":"Properties"},
+                {"name":"calc()","path":"calc","type":"Functions"},
+                {"name":"margin","path":"margin","type":"Properties"}
+            ],"types":[]}"#,
+        )
+        .unwrap();
+        write_doc(root.path(), "broken", "Broken");
+        fs::write(root.path().join("broken").join("index.json"), "{not json").unwrap();
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[root.path()]).unwrap();
+        // 加载后被删除的索引
+        write_doc(root.path(), "gone", "Gone");
+        registry.reload_doc(&[root.path()], "gone");
+        fs::remove_file(root.path().join("gone").join("index.json")).unwrap();
+
+        let returned = registry.refresh_manifest(root.path(), manifest::system_clock).unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.path().join(MANIFEST_FILENAME)).unwrap()).unwrap();
+        let doc = |slug: &str| {
+            manifest["docs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|doc| doc["slug"] == slug)
+                .cloned()
+                .unwrap()
+        };
+
+        let css = doc("css");
+        assert_eq!(css["entry_count"], 3);
+        assert_eq!(
+            css["types"],
+            serde_json::json!([{"name": "Functions", "count": 1}, {"name": "Properties", "count": 2}])
+        );
+        assert!(css.get("warning").is_none());
+
+        for slug in ["broken", "gone"] {
+            let doc = doc(slug);
+            assert_eq!(doc["entry_count"], 0, "{}", slug);
+            assert_eq!(doc["types"], serde_json::json!([]));
+            assert!(doc["warning"].as_str().unwrap().contains("index.json"), "{}", slug);
+        }
+        assert_eq!(returned.docs.len(), 3);
+        assert_eq!(returned.entry_count(), 3);
+    }
+
+    #[test]
+    fn test_load_from_multiple_roots() {
+        let writable = TempDir::new().unwrap();
+        let overlay = TempDir::new().unwrap();
+
+        write_doc(writable.path(), "babel~7", "Local Babel");
+        write_doc(overlay.path(), "babel~7", "Shared Babel");
+        write_doc(overlay.path(), "css", "CSS");
+
+        let mut registry = DocRegistry::new();
+        registry
+            .load_from_disk(&[writable.path(), overlay.path()])
+            .unwrap();
+
+        assert_eq!(registry.all().len(), 2);
+
+        // 可写根目录优先
+        let babel = registry.find_with_version("babel", "7").unwrap();
+        assert_eq!(babel.name, "Local Babel");
+        assert!(!babel.read_only);
+        assert_eq!(babel.root, writable.path());
+
+        let css = registry.find("css").unwrap();
+        assert!(css.read_only);
+        assert_eq!(css.root, overlay.path());
+        assert!(css.read_file("db.json").is_ok());
+    }
+
+    #[test]
+    fn test_remove_respects_read_only_roots() {
+        let writable = TempDir::new().unwrap();
+        let overlay = TempDir::new().unwrap();
+
+        write_doc(writable.path(), "babel~7", "Babel");
+        write_doc(overlay.path(), "css", "CSS");
+
+        let mut registry = DocRegistry::new();
+        registry
+            .load_from_disk(&[writable.path(), overlay.path()])
+            .unwrap();
+
+        assert!(registry.remove("css", "").is_err());
+        assert!(overlay.path().join("css").exists());
+        assert!(registry.find("css").is_some());
+
+        registry.remove("babel", "7").unwrap();
+        assert!(!writable.path().join("babel~7").exists());
+        assert!(registry.find("babel").is_none());
+    }
+
+    #[test]
+    fn test_reload_doc_only_touches_one_doc() {
+        let writable = TempDir::new().unwrap();
+        let overlay = TempDir::new().unwrap();
+
+        write_doc(writable.path(), "babel~7", "Local Babel");
+        write_doc(overlay.path(), "babel~7", "Shared Babel");
+        write_doc(writable.path(), "css", "CSS");
+
+        let roots = [writable.path(), overlay.path()];
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&roots).unwrap();
+
+        write_doc(writable.path(), "css", "CSS 3");
+        write_doc(writable.path(), "babel~7", "Babel Renamed");
+        assert!(registry.reload_doc(&roots, "css"));
+        assert_eq!(registry.find("css").unwrap().name, "CSS 3");
+        assert_eq!(registry.find("babel").unwrap().name, "Local Babel");
+
+        // 可写目录中的文档被删除后回退到叠加目录
+        fs::remove_dir_all(writable.path().join("babel~7")).unwrap();
+        assert!(registry.reload_doc(&roots, "babel~7"));
+        let babel = registry.find("babel").unwrap();
+        assert_eq!(babel.name, "Shared Babel");
+        assert!(babel.read_only);
+
+        fs::remove_dir_all(writable.path().join("css")).unwrap();
+        assert!(!registry.reload_doc(&roots, "css"));
+        assert!(registry.find("css").is_none());
+        assert_eq!(registry.all().len(), 1);
+    }
+
+    #[test]
+    fn test_directories_differing_in_case() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "Babel~7", "Babel Upper");
+        write_doc(docs.path(), "babel~7", "Babel");
+        write_doc(docs.path(), "React Docs~18", "React");
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+        assert_eq!(registry.all().len(), 2);
+
+        // 只有大小写不同时使用规范写法的目录
+        let babel = registry.find_with_version("babel", "7").unwrap();
+        assert_eq!(babel.name, "Babel");
+        assert_eq!(babel.dir(), docs.path().join("babel~7"));
+        assert!(registry.reload_doc(&[docs.path()], "Babel~7"));
+        assert_eq!(registry.find("babel").unwrap().name, "Babel");
+
+        // 不规范的目录名按规范的 slug 注册，仍从原目录读取
+        let react = registry.find_localized("react_docs~18", &["en"]).unwrap();
+        assert_eq!(react.path(), "react_docs~18");
+        assert_eq!(react.dir(), docs.path().join("React Docs~18"));
+        assert!(react.read_file("db.json").is_ok());
+    }
+
+    #[test]
+    fn test_find_localized() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "javascript", "JavaScript");
+        write_doc(docs.path(), "javascript@zh-CN", "JavaScript 中文");
+        write_doc(docs.path(), "css@fr", "CSS français");
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+        assert_eq!(registry.all().len(), 3);
+
+        let zh = registry.find_localized("javascript", &["zh-cn"]).unwrap();
+        assert_eq!(zh.locale.as_deref(), Some("zh-CN"));
+        assert_eq!(zh.path(), "javascript@zh-CN");
+
+        // 主语言标签匹配
+        let zh = registry.find_localized("javascript", &["zh-TW", "en"]).unwrap();
+        assert_eq!(zh.locale.as_deref(), Some("zh-CN"));
+
+        // 未安装时回退到默认语言
+        let default = registry.find_localized("javascript", &["de"]).unwrap();
+        assert!(default.locale.is_none());
+        assert_eq!(registry.find("javascript").unwrap().name, "JavaScript");
+
+        // 只有本地化版本时仍可找到
+        assert_eq!(registry.find_localized("css", &["de"]).unwrap().name, "CSS français");
+    }
+
+    #[test]
+    fn test_find_localized_versioned() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "babel~6", "Babel 6");
+        write_doc(docs.path(), "babel~7", "Babel 7");
+        write_doc(docs.path(), "babel~7@zh-CN", "Babel 7 中文");
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+
+        let babel6 = registry.find_localized("babel~6", &["zh-CN"]).unwrap();
+        assert_eq!(babel6.name, "Babel 6");
+        assert_eq!(babel6.landing_url(), "/docs/babel~6");
+        assert_eq!(registry.find_localized("babel~7", &["zh"]).unwrap().name, "Babel 7 中文");
+        assert_eq!(registry.find_localized("babel~7", &["de"]).unwrap().name, "Babel 7");
+        assert!(registry.find_localized("babel~8", &["en"]).is_none());
+    }
+
+    #[test]
+    fn test_search_tokenized_and_scored() {
+        let entry = |name: &str| IndexEntry {
+            name: name.to_string(),
+            path: name.to_lowercase(),
+            entry_type: "Method".to_string(),
+            word_count: None,
+            inherited: None,
+        };
+        let mut registry = DocRegistry::new();
+        registry.add(Documentation::new("JavaScript", "javascript", "").with_entries(vec![
+            entry("Map.prototype.get()"),
+            entry("WeakMap.prototype.get()"),
+            entry("Map"),
+            entry("getter"),
+            entry("target"),
+        ]));
+
+        let names = |query: &str| -> Vec<(u32, String)> {
+            registry
+                .search_scored(query, 10)
+                .into_iter()
+                .map(|(score, _, entry)| (score, entry.name.clone()))
+                .collect()
+        };
+
+        assert_eq!(
+            names("GET"),
+            [
+                (SCORE_PREFIX, "getter".to_string()),
+                (SCORE_WORD_START, "Map.prototype.get()".to_string()),
+                (SCORE_WORD_START, "WeakMap.prototype.get()".to_string()),
+                (SCORE_SUBSTRING, "target".to_string()),
+            ]
+        );
+        // 所有的词都要出现在名称中
+        assert_eq!(
+            names("  map   get "),
+            [
+                (SCORE_PREFIX + SCORE_WORD_START, "Map.prototype.get()".to_string()),
+                (SCORE_SUBSTRING + SCORE_WORD_START, "WeakMap.prototype.get()".to_string()),
+            ]
+        );
+        assert_eq!(names("map")[0], (SCORE_EXACT, "Map".to_string()));
+        assert!(names("   ").is_empty());
+        assert_eq!(registry.search("get", 2).len(), 2);
+
+        // 搜索历史的加分只改变同一档内的顺序
+        let boosted: Vec<String> = registry
+            .search_scored_with("get", 10, |_, entry| {
+                if entry.name == "WeakMap.prototype.get()" || entry.name == "target" {
+                    crate::docs::search_history::MAX_BOOST
+                } else {
+                    0
+                }
+            })
+            .into_iter()
+            .map(|(_, _, entry)| entry.name.clone())
+            .collect();
+        assert_eq!(boosted, ["getter", "WeakMap.prototype.get()", "Map.prototype.get()", "target"]);
+    }
+
+    #[test]
+    fn test_exact_match_ranked_first_and_filtered() {
+        let entry = |name: &str, entry_type: &str| IndexEntry {
+            name: name.to_string(),
+            path: name.to_lowercase(),
+            entry_type: entry_type.to_string(),
+            word_count: None,
+            inherited: None,
+        };
+        let mut registry = DocRegistry::new();
+        registry.add(Documentation::new("Synthetic", "synthetic", "").with_entries(vec![
+            entry("a b c d e f", "Functions"),
+            entry("A B C D E", "Properties"),
+            entry("a-b-c-d-e", "Properties"),
+            entry("xa b c d e", "Functions"),
+        ]));
+
+        let names = |filter: &dyn Fn(&Documentation, &IndexEntry) -> bool| -> Vec<(u32, String)> {
+            registry
+                .search_scored_filtered(None, "a b c d e", 10, |_, _| 0, filter)
+                .into_iter()
+                .map(|(score, _, entry)| (score, entry.name.clone()))
+                .collect()
+        };
+
+        // 多个词的分数之和超过完全匹配的分数时，完全匹配的条目仍然排在最前面
+        let word_starts = SCORE_PREFIX + 4 * SCORE_WORD_START;
+        assert!(word_starts > SCORE_EXACT);
+        assert_eq!(
+            names(&|_, _| true),
+            [
+                (SCORE_EXACT, "A B C D E".to_string()),
+                (word_starts, "a-b-c-d-e".to_string()),
+                (word_starts, "a b c d e f".to_string()),
+                (SCORE_SUBSTRING + 4 * SCORE_WORD_START, "xa b c d e".to_string()),
+            ]
+        );
+        assert_eq!(
+            names(&|_, entry| entry.entry_type == "Functions"),
+            [(word_starts, "a b c d e f".to_string()), (SCORE_SUBSTRING + 4 * SCORE_WORD_START, "xa b c d e".to_string())]
+        );
+
+        // 搜索历史的加分也不会让其他条目排到完全匹配的条目前面
+        let boosted = registry.search_scored_with("a b c d e", 1, |_, entry| {
+            if entry.name == "A B C D E" {
+                0
+            } else {
+                crate::docs::search_history::MAX_BOOST
+            }
+        });
+        assert_eq!(boosted[0].2.name, "A B C D E");
+    }
+
+    #[test]
+    fn test_load_quality_from_meta() {
+        let root = TempDir::new().unwrap();
+        write_doc(root.path(), "css", "CSS");
+        write_doc(root.path(), "html", "HTML");
+        let score = quality::QualityScore { score: 62.5, pages: 4, pages_with_warnings: 3 };
+        quality::write_to_meta(&root.path().join("css"), &score).unwrap();
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[root.path()]).unwrap();
+
+        let css = registry.find("css").unwrap();
+        assert_eq!(css.name, "CSS");
+        assert_eq!(css.quality, Some(62.5));
+        assert_eq!(registry.find("html").unwrap().quality, None);
+    }
+
+    #[test]
+    fn test_load_bom_prefixed_and_broken_docs() {
+        let root = TempDir::new().unwrap();
+        write_doc(root.path(), "css", "CSS");
+        fs::write(
+            root.path().join("css").join("index.json"),
+            b"\xEF\xBB\xBF{\"entries\":[{\"name\":\"color\",\"path\":\"color\",\"type\":\"Properties\"}],\"types\":[]}",
+        )
+        .unwrap();
+        fs::write(root.path().join("css").join("meta.json"), b"\xEF\xBB\xBF{\"name\":\"CSS3\",\"type\":\"mdn\"}").unwrap();
+        write_doc(root.path(), "html", "HTML");
+        let utf16: Vec<u8> = "{\"name\":\"HTML\"}".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        fs::write(root.path().join("html").join("meta.json"), [&[0xFF, 0xFE][..], &utf16[..]].concat()).unwrap();
+        write_doc(root.path(), "dom", "DOM");
+        fs::write(root.path().join("dom").join("index.json"), r#"{"entries":[{"name":"#).unwrap();
+
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[root.path()]).unwrap();
+
+        // 带 BOM 的文件正常读取
+        let css = registry.find("css").unwrap();
+        assert_eq!(css.name, "CSS3");
+        assert_eq!(css.doc_type.as_deref(), Some("mdn"));
+        assert_eq!(css.entries.len(), 1);
+        assert!(css.load_errors.is_empty());
+
+        // 无法读取的文件不再被静默忽略，错误带有文件路径
+        let errors = registry.load_errors();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        let error = |doc: &str| errors.iter().find(|(path, _)| path == doc).unwrap().1;
+        assert!(error("html").contains("meta.json") && error("html").contains("UTF-16"), "{}", error("html"));
+        assert!(error("dom").contains("index.json") && error("dom").contains("字节偏移"), "{}", error("dom"));
+        assert!(registry.find("dom").unwrap().entries.is_empty());
+    }
+
+    #[test]
+    fn test_versions_and_has_page() {
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&["test_docs/web_versions"]).unwrap();
+        registry.add(Documentation::new("Lib", "lib", "10.0"));
+        registry.add(Documentation::new("Lib", "lib", ""));
+
+        let versions: Vec<&str> = registry.versions("lib", None).iter().map(|doc| doc.version.as_str()).collect();
+        assert_eq!(versions, ["1.0", "2.0", "10.0", ""]);
+
+        // 两个版本只差一个页面
+        assert!(registry.has_page("lib", "1.0", "legacy"));
+        assert!(registry.has_page("lib", "1.0", "/legacy.html"));
+        assert!(!registry.has_page("lib", "2.0", "legacy"));
+        assert!(registry.has_page("lib", "2.0", "intro"));
+        assert!(!registry.has_page("lib", "3.0", "intro"));
+
+        // 没有 pages.json 时查找页面文件
+        let root = TempDir::new().unwrap();
+        write_doc(root.path(), "old~1", "Old");
+        crate::docs::store_page(root.path(), "old~1", "guide/intro", "<p>intro</p>").unwrap();
+        registry.load_from_disk(&[root.path()]).unwrap();
+        assert!(registry.has_page("old", "1", "guide/intro"));
+        assert!(!registry.has_page("old", "1", "guide/missing"));
+    }
+
+    #[test]
+    fn test_namespace_precedence_over_global() {
+        let root = TempDir::new().unwrap();
+        write_doc(root.path(), "css", "Global CSS");
+        write_doc(root.path(), "html", "Global HTML");
+        let platform = root.path().join("teams").join("platform");
+        write_doc(&platform, "css", "Platform CSS");
+
+        let config = Config::default()
+            .with_docs_path(root.path().to_str().unwrap())
+            .with_namespace("platform", "teams/platform")
+            .with_namespace("empty", "teams/empty");
+        let mut registry = DocRegistry::new();
+        registry.load_config(&config).unwrap();
+
+        // 命名空间的子目录不是文档，不出现在全局命名空间中
+        assert_eq!(registry.all().len(), 2);
+        assert_eq!(registry.namespace_names().collect::<Vec<_>>(), ["empty", "platform"]);
+
+        let no_locales: &[&str] = &[];
+        let css = registry.find_localized_in(Some("platform"), "css", no_locales).unwrap();
+        assert_eq!(css.name, "Platform CSS");
+        assert_eq!(css.landing_url(), "/t/platform/docs/css");
+        assert_eq!(css.cache_key(), "t/platform/css");
+        assert_eq!(registry.find_localized_in(Some("platform"), "html", no_locales).unwrap().name, "Global HTML");
+        assert_eq!(registry.find_localized_in(None, "css", no_locales).unwrap().name, "Global CSS");
+        assert_eq!(registry.find_localized_in(Some("empty"), "css", no_locales).unwrap().name, "Global CSS");
+
+        let visible: Vec<&str> = registry.visible(Some("platform")).iter().map(|doc| doc.name.as_str()).collect();
+        assert_eq!(visible, ["Platform CSS", "Global HTML"]);
+    }
+}
@@ -1,3 +0,0 @@
-//! Rust过滤器模块
-
-// 在此处添加特定Rust过滤器模块导出
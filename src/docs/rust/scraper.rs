@@ -1,21 +1,41 @@
 //! Rust 文档抓取器
+//!
+//! 从 `https://doc.rust-lang.org/<版本>/` 抓取指定工具链版本的文档。版本为
+//! `latest`（或未指定）时，先从稳定版发布渠道的清单中查出当前的稳定版本号，
+//! 再抓取该版本的地址，保存的文档（目录 `rust~<版本>`、meta.json 中的 release）
+//! 都使用查出的版本号，可以重现。
 
 use crate::core::error::Result;
+use crate::core::instrumentable;
+use crate::core::scraper::filter::Filter;
+use crate::core::scraper::url_scraper::START_SCRAPER_EVENT;
 use crate::core::filters::{HtmlCleanerFilter, UrlNormalizerFilter};
+use crate::core::http_fetch::{HttpFetch, ReqwestFetch};
 use crate::core::scraper::{Scraper as CoreScraper, UrlScraper};
+use crate::core::version_probe::{self, RegexProbe, VersionProbe};
 use async_trait::async_trait;
+use regex::Regex;
+use std::sync::Arc;
+
+/// 稳定版发布渠道的清单
+const CHANNEL_MANIFEST_URL: &str = "https://static.rust-lang.org/dist/channel-rust-stable.toml";
+
+/// 文档站点，各版本的文档位于 `<站点>/<版本>/` 下
+const DOCS_URL: &str = "https://doc.rust-lang.org/";
 
 /// Rust文档抓取器
 pub struct RustScraper {
     /// 基础抓取器
     scraper: UrlScraper,
+    /// 查询最新版本使用的 HTTP 实现
+    fetch: Arc<dyn HttpFetch>,
 }
 
 impl RustScraper {
     /// 创建新的Rust文档抓取器
     pub fn new(version: &str, output_path: &str) -> Self {
-        let base_url = "https://doc.rust-lang.org/";
-        let mut scraper = UrlScraper::new("Rust", version, base_url, output_path);
+        let base_url = versioned_base_url(version);
+        let mut scraper = UrlScraper::new("Rust", version, &base_url, output_path);
 
         // 添加初始路径
         let initial_paths = vec![
@@ -26,25 +46,73 @@ impl RustScraper {
             "rustc/index.html".to_string(),
         ];
 
-        // 创建过滤器
-        let html_cleaner = Box::new(
-            HtmlCleanerFilter::new()
-                .with_remove_tag("footer")
-                .with_remove_tag("nav")
-                .with_remove_attr("data-*"),
-        );
-        let url_normalizer = Box::new(UrlNormalizerFilter::new(base_url, "/docs/rust/"));
+        // 添加初始路径和过滤器，过滤器随版本的地址变化，见 `set_version`
+        scraper = scraper.with_initial_paths(initial_paths);
+        scraper.filters = filters(&base_url);
 
-        // 添加过滤器和初始路径
-        scraper = scraper
-            .with_initial_paths(initial_paths)
-            .with_filter(html_cleaner)
-            .with_filter(url_normalizer);
+        Self {
+            scraper,
+            fetch: Arc::new(ReqwestFetch::new()),
+        }
+    }
+
+    /// 设置查询版本和抓取页面使用的 HTTP 实现
+    pub fn with_fetch(mut self, fetch: Arc<dyn HttpFetch>) -> Self {
+        self.scraper = self.scraper.with_fetch(fetch.clone());
+        self.fetch = fetch;
+        self
+    }
 
-        Self { scraper }
+    /// 从稳定版发布渠道的清单中获取当前的稳定版本号，如 `1.75.0`
+    pub async fn get_latest_version(&self) -> Result<String> {
+        version_probe::latest_version(&channel_probe(), self.fetch.as_ref()).await
+    }
+
+    /// 改为抓取指定版本，地址、发布版本和输出目录都随之改变
+    fn set_version(&mut self, version: &str) {
+        let base_url = versioned_base_url(version);
+        self.scraper.version = version.to_string();
+        self.scraper.release = version.to_string();
+        self.scraper.filters = filters(&base_url);
+        self.scraper.base_url = base_url;
     }
 }
 
+/// 版本对应的文档地址，`latest` 在查出版本号之前暂时使用 `stable`
+fn versioned_base_url(version: &str) -> String {
+    let version = version.trim();
+    if is_latest(version) {
+        format!("{}stable/", DOCS_URL)
+    } else {
+        format!("{}{}/", DOCS_URL, version)
+    }
+}
+
+/// 是否需要查询最新的稳定版本
+fn is_latest(version: &str) -> bool {
+    version.is_empty() || version == "latest"
+}
+
+/// 过滤器，链接按版本的地址规范化
+fn filters(base_url: &str) -> Vec<Box<dyn Filter>> {
+    let html_cleaner: Box<dyn Filter> = Box::new(
+        HtmlCleanerFilter::new()
+            .with_remove_tag("footer")
+            .with_remove_tag("nav")
+            .with_remove_attr("data-*"),
+    );
+    let url_normalizer: Box<dyn Filter> = Box::new(UrlNormalizerFilter::new(base_url, "/docs/rust/"));
+    vec![html_cleaner, url_normalizer]
+}
+
+/// 从发布渠道的清单（`channel-rust-*.toml`）中读取 `[pkg.rust]` 的版本号
+///
+/// 清单中的版本形如 `"1.75.0 (82e1608df 2023-12-21)"`，只取版本号部分。
+fn channel_probe() -> RegexProbe {
+    let pattern = Regex::new(r#"(?m)^\[pkg\.rust\][ \t]*$(?:\n[^\[\n].*)*?\nversion[ \t]*=[ \t]*"([^\s"]+)"#).unwrap();
+    RegexProbe::new(CHANNEL_MANIFEST_URL, pattern)
+}
+
 #[async_trait]
 impl CoreScraper for RustScraper {
     fn name(&self) -> &str {
@@ -56,7 +124,73 @@ impl CoreScraper for RustScraper {
     }
 
     async fn run(&mut self) -> Result<()> {
-        println!("开始抓取Rust文档...");
+        if is_latest(self.scraper.version()) {
+            let version = self.get_latest_version().await?;
+            instrumentable::message(START_SCRAPER_EVENT, format!("Rust 当前的稳定版本: {}", version));
+            self.set_version(&version);
+        }
+        instrumentable::message(START_SCRAPER_EVENT, "开始抓取Rust文档...");
         self.scraper.run().await
     }
+
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(&mut self.scraper)
+    }
+
+    fn version_probe(&self) -> Option<Box<dyn VersionProbe>> {
+        Some(Box::new(channel_probe()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_fetch::{FetchedResponse, MockFetch};
+
+    fn channel_manifest() -> String {
+        std::fs::read_to_string("test_docs/rust_test/channel-rust-stable.toml").unwrap()
+    }
+
+    #[test]
+    fn test_parse_channel_manifest_fixture() {
+        let parse = |manifest: String| channel_probe().parse(&FetchedResponse::new(200).with_body(manifest)).ok();
+
+        // 只读取 `[pkg.rust]` 的版本，不受 clippy 等组件的版本影响
+        assert_eq!(parse(channel_manifest()).as_deref(), Some("1.75.0"));
+        assert_eq!(parse("[pkg.cargo]\nversion = \"0.76.0\"\n".to_string()), None);
+        assert_eq!(parse("[pkg.rust]\ngit_commit_hash = \"82e1608\"\nversion = \"1.76.0\"\n".to_string()).as_deref(), Some("1.76.0"));
+    }
+
+    #[tokio::test]
+    async fn test_latest_version_from_channel_manifest() {
+        let fetch = Arc::new(MockFetch::new().with_response(
+            CHANNEL_MANIFEST_URL,
+            FetchedResponse::new(200).with_body(channel_manifest()),
+        ));
+        let scraper = RustScraper::new("latest", "").with_fetch(fetch.clone());
+        assert_eq!(scraper.get_latest_version().await.unwrap(), "1.75.0");
+        assert_eq!(fetch.requested_urls(), [CHANNEL_MANIFEST_URL]);
+
+        // 清单不可用时报错，不会退回到不可重现的 stable 地址
+        let scraper = RustScraper::new("latest", "").with_fetch(Arc::new(MockFetch::new()));
+        let error = scraper.get_latest_version().await.unwrap_err();
+        assert_eq!(error.url(), Some(CHANNEL_MANIFEST_URL));
+    }
+
+    #[test]
+    fn test_version_selects_base_url_and_dir() {
+        let mut scraper = RustScraper::new("1.75.0", "/tmp/docs");
+        let url_scraper = scraper.url_scraper_mut().unwrap();
+        assert_eq!(url_scraper.base_url, "https://doc.rust-lang.org/1.75.0/");
+        assert_eq!(url_scraper.doc_dir_name(), "rust~1.75.0");
+
+        // 查出最新版本后改为抓取该版本
+        let mut scraper = RustScraper::new("latest", "/tmp/docs");
+        assert_eq!(scraper.scraper.base_url, "https://doc.rust-lang.org/stable/");
+        scraper.set_version("1.75.0");
+        assert_eq!(scraper.scraper.base_url, "https://doc.rust-lang.org/1.75.0/");
+        assert_eq!(scraper.scraper.release, "1.75.0");
+        assert_eq!(scraper.scraper.doc_dir_name(), "rust~1.75.0");
+        assert_eq!(scraper.version(), "1.75.0");
+    }
 }
@@ -0,0 +1,591 @@
+//! 条目搜索结果
+//!
+//! `get`、`index` 这类常见名称在多个文档和类型中都会出现，搜索结果因此附带
+//! 条目类型以及所属文档的显示名称和版本。还可以按需附带目标页面的一小段文本
+//! 摘要：摘要取自页面第一个标题之后的内容，去掉 HTML 并限制长度。生成摘要需要
+//! 读取页面，只在请求时生成，并按文档和页面缓存。
+//!
+//! 搜索可以用 [`SearchFilter`] 限定到一个文档和（或）一种条目类型，结果中的
+//! `type_slug` 可供前端生成按类型筛选的选项。
+
+use crate::core::index_entry::IndexEntry;
+use crate::core::types::PageKey;
+use crate::docs::documentation::Documentation;
+use crate::docs::registry::DocRegistry;
+use crate::docs::search_history::SearchHistory;
+use crate::web::page_cache::PageCache;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// 摘要的最大字符数
+pub const SNIPPET_MAX_CHARS: usize = 160;
+
+/// 摘要缓存的最大条目数，超过后清空重新缓存
+const SNIPPET_CACHE_CAPACITY: usize = 10_000;
+
+/// 搜索结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    /// 条目名称
+    pub name: String,
+    /// 条目路径
+    pub path: String,
+    /// 条目类型
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    /// 条目类型的别名，可作为搜索的 `type` 参数
+    pub type_slug: String,
+    /// 文档路径（`slug~version`）
+    pub doc: String,
+    /// 文档显示名称（包含版本）
+    pub doc_name: String,
+    /// 文档版本
+    pub version: String,
+    /// 相关性分数，越高越相关
+    pub score: u32,
+    /// 文档内容的许可证（SPDX 标识），文档没有记录时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// 目标页面的文本摘要（仅在请求摘要时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl SearchResult {
+    /// 由文档和条目生成搜索结果，不含摘要
+    pub fn new(doc: &Documentation, entry: &IndexEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            path: entry.path.clone(),
+            entry_type: entry.entry_type.clone(),
+            type_slug: type_slug(doc, &entry.entry_type),
+            doc: doc.path(),
+            doc_name: doc.full_name(),
+            version: doc.version.clone(),
+            score: 0,
+            license: doc.license.clone(),
+            snippet: None,
+        }
+    }
+}
+
+/// 条目类型的别名：取文档类型列表中的别名，列表中没有该类型时为小写的类型名称
+pub fn type_slug(doc: &Documentation, entry_type: &str) -> String {
+    doc.types
+        .iter()
+        .find(|index_type| index_type.name == entry_type)
+        .map(|index_type| index_type.slug.clone())
+        .unwrap_or_else(|| entry_type.to_lowercase())
+}
+
+/// 搜索范围，默认不限定
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    /// 只搜索该文档，可以是 `<slug>~<version>`，不带版本时包含所有版本
+    pub doc: Option<String>,
+    /// 只搜索该类型的条目，与类型名称或别名比较，不区分大小写
+    pub entry_type: Option<String>,
+}
+
+impl SearchFilter {
+    /// 创建不限定范围的搜索过滤器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只搜索指定的文档
+    pub fn with_doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    /// 只搜索指定类型的条目
+    pub fn with_type(mut self, entry_type: &str) -> Self {
+        self.entry_type = Some(entry_type.to_string());
+        self
+    }
+
+    /// 文档是否在搜索范围内
+    pub fn matches_doc(&self, doc: &Documentation) -> bool {
+        let Some(scope) = &self.doc else {
+            return true;
+        };
+        match scope.split_once('~') {
+            Some((slug, version)) => doc.slug == slug && doc.version == version,
+            None => doc.slug == *scope,
+        }
+    }
+
+    /// 条目是否在搜索范围内
+    pub fn matches(&self, doc: &Documentation, entry: &IndexEntry) -> bool {
+        if !self.matches_doc(doc) {
+            return false;
+        }
+        match &self.entry_type {
+            Some(wanted) => {
+                entry.entry_type.eq_ignore_ascii_case(wanted)
+                    || type_slug(doc, &entry.entry_type).eq_ignore_ascii_case(wanted)
+            }
+            None => true,
+        }
+    }
+}
+
+/// 按文档和页面缓存的摘要
+#[derive(Debug, Default)]
+pub struct SnippetCache {
+    snippets: Mutex<HashMap<(String, u64, String), Option<String>>>,
+    /// 缓存的键和摘要占用的字节数
+    bytes: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// 超过容量清空缓存时丢弃的摘要数
+    evictions: AtomicU64,
+}
+
+impl SnippetCache {
+    /// 创建空的摘要缓存
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取页面摘要，没有缓存时用 `load_page` 读取页面并生成
+    ///
+    /// 文档修改时间是缓存键的一部分，重新抓取的文档会重新生成摘要。
+    pub fn snippet<F>(&self, doc: &Documentation, page_path: &str, load_page: F) -> Option<String>
+    where
+        F: FnOnce(&Documentation, &str) -> Option<String>,
+    {
+        let key = (doc.cache_key(), doc.mtime, page_path.to_string());
+        if let Some(snippet) = self.snippets.lock().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return snippet.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let snippet = load_page(doc, page_path).and_then(|html| extract_snippet(&html, SNIPPET_MAX_CHARS));
+        let size = key.0.len() + key.2.len() + snippet.as_ref().map_or(0, String::len);
+        let mut snippets = self.snippets.lock().unwrap();
+        if snippets.len() >= SNIPPET_CACHE_CAPACITY {
+            self.evictions.fetch_add(snippets.len() as u64, Ordering::Relaxed);
+            snippets.clear();
+            self.bytes.store(0, Ordering::Relaxed);
+        }
+        if snippets.insert(key, snippet.clone()).is_none() {
+            self.bytes.fetch_add(size, Ordering::Relaxed);
+        }
+        snippet
+    }
+
+    /// 缓存的页面数
+    pub fn len(&self) -> usize {
+        self.snippets.lock().unwrap().len()
+    }
+
+    /// 缓存是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 缓存的键和摘要占用的字节数（不加锁）
+    pub fn memory_usage(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// 命中、未命中和清空时丢弃的次数（不加锁）
+    pub fn counters(&self) -> (u64, u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+            self.evictions.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// 读取文档页面，页面文件优先，其次为 db.json
+///
+/// db.json 通过 `page_cache` 读取，同一文档的多个页面只解析一次。服务器传入共享的
+/// 页面缓存，命令行的每次搜索使用各自的缓存。
+pub fn load_page(page_cache: &PageCache, doc: &Documentation, page_path: &str) -> Option<String> {
+    std::fs::read_to_string(doc.page_file(page_path))
+        .ok()
+        .or_else(|| doc.stored_page(page_cache, &PageKey::new(page_path)).map(|page| page.content))
+}
+
+/// 在所有文档中搜索，`snippets` 为 true 时附带目标页面的摘要
+pub fn search(
+    registry: &DocRegistry,
+    query: &str,
+    limit: usize,
+    snippets: Option<&SnippetCache>,
+) -> Vec<SearchResult> {
+    search_filtered(registry, None, query, limit, snippets, None, &SearchFilter::new())
+}
+
+/// 在文档中搜索，按搜索历史提升常用条目的排名
+///
+/// `namespace` 不为 None 时在该命名空间和全局命名空间的文档中搜索，
+/// 见 [`DocRegistry::visible`]，否则只搜索全局文档。
+pub fn search_with_history(
+    registry: &DocRegistry,
+    namespace: Option<&str>,
+    query: &str,
+    limit: usize,
+    snippets: Option<&SnippetCache>,
+    history: Option<&SearchHistory>,
+) -> Vec<SearchResult> {
+    search_filtered(registry, namespace, query, limit, snippets, history, &SearchFilter::new())
+}
+
+/// 在 `filter` 限定的文档和条目类型中搜索，其余参数与 [`search_with_history`] 相同
+pub fn search_filtered(
+    registry: &DocRegistry,
+    namespace: Option<&str>,
+    query: &str,
+    limit: usize,
+    snippets: Option<&SnippetCache>,
+    history: Option<&SearchHistory>,
+    filter: &SearchFilter,
+) -> Vec<SearchResult> {
+    let page_cache = PageCache::new();
+    search_with(registry, namespace, query, limit, snippets, history, filter, |doc, page_path| {
+        load_page(&page_cache, doc, page_path)
+    })
+}
+
+/// 使用指定的页面读取函数搜索
+#[allow(clippy::too_many_arguments)]
+pub fn search_with<F>(
+    registry: &DocRegistry,
+    namespace: Option<&str>,
+    query: &str,
+    limit: usize,
+    snippets: Option<&SnippetCache>,
+    history: Option<&SearchHistory>,
+    filter: &SearchFilter,
+    load_page: F,
+) -> Vec<SearchResult>
+where
+    F: Fn(&Documentation, &str) -> Option<String>,
+{
+    let boosts = history.map(|history| history.boosts(chrono::Utc::now().timestamp())).unwrap_or_default();
+    let boost = |doc: &Documentation, entry: &IndexEntry| {
+        if boosts.is_empty() {
+            return 0;
+        }
+        boosts
+            .get(&doc.path())
+            .and_then(|paths| paths.get(&entry.path))
+            .copied()
+            .unwrap_or(0)
+    };
+    registry
+        .search_scored_filtered(namespace, query, limit, boost, |doc, entry| filter.matches(doc, entry))
+        .into_iter()
+        .map(|(score, doc, entry)| {
+            let mut result = SearchResult::new(doc, entry);
+            result.score = score;
+            if let Some(cache) = snippets {
+                let page_path = entry.path.split('#').next().unwrap_or(&entry.path);
+                result.snippet = cache.snippet(doc, page_path, &load_page);
+            }
+            result
+        })
+        .collect()
+}
+
+/// 从页面中提取摘要
+///
+/// 取第一个标题之后的段落文本（没有标题时取整个页面的文本），合并空白，
+/// 超过 `max_chars` 个字符时在单词边界处截断并加上省略号。
+pub fn extract_snippet(html: &str, max_chars: usize) -> Option<String> {
+    let document = Html::parse_fragment(html);
+    let heading = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+
+    let mut text = String::new();
+    match document.select(&heading).next() {
+        Some(heading) => {
+            for sibling in heading.next_siblings() {
+                if let Some(element) = ElementRef::wrap(sibling) {
+                    if is_heading(element.value().name()) {
+                        break;
+                    }
+                    push_text(&mut text, element.text());
+                } else if let Some(node_text) = sibling.value().as_text() {
+                    push_text(&mut text, std::iter::once(&*node_text.text));
+                }
+                if text.chars().count() > max_chars {
+                    break;
+                }
+            }
+        }
+        None => push_text(&mut text, document.root_element().text()),
+    }
+
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(truncate_chars(text, max_chars))
+}
+
+/// 是否为标题元素
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// 追加文本并合并空白
+fn push_text<'a>(text: &mut String, parts: impl Iterator<Item = &'a str>) {
+    for part in parts {
+        for word in part.split_whitespace() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(word);
+        }
+    }
+}
+
+/// 截断到最多 `max_chars` 个字符（含省略号），尽量在单词边界处截断
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    let cut = match cut.rfind(' ') {
+        Some(index) if index > cut.len() / 2 => &cut[..index],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::store_page;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extract_snippet_after_first_heading() {
+        let html = concat!(
+            "<nav>Skip me</nav><h1>Array.prototype.<code>map()</code></h1>",
+            "<p>The <strong>map()</strong> method &amp; friends\n   create a new array.</p>",
+            "<div class=\"_note\"><p>Since ES5.</p></div>",
+            "<h2>Syntax</h2><p>Not included.</p>",
+        );
+        assert_eq!(
+            extract_snippet(html, SNIPPET_MAX_CHARS).as_deref(),
+            Some("The map() method & friends create a new array. Since ES5.")
+        );
+
+        let long = format!("<h1>T</h1><p>{}</p>", "lorem ipsum ".repeat(50));
+        let snippet = extract_snippet(&long, 40).unwrap();
+        assert!(snippet.chars().count() <= 40);
+        assert!(snippet.ends_with("ipsum…"));
+
+        assert_eq!(extract_snippet("<p>No heading here</p>", 40).as_deref(), Some("No heading here"));
+        assert_eq!(extract_snippet("<h1>Only</h1>", 40), None);
+    }
+
+    #[test]
+    fn test_search_result_carries_doc_license() {
+        let entry = IndexEntry {
+            name: "map".to_string(),
+            path: "map".to_string(),
+            entry_type: "Array".to_string(),
+            word_count: None,
+            inherited: None,
+        };
+        let doc = Documentation::new("JavaScript", "javascript", "").with_license("CC-BY-SA-2.5");
+        let json = serde_json::to_value(SearchResult::new(&doc, &entry)).unwrap();
+        assert_eq!(json["license"], "CC-BY-SA-2.5");
+
+        // 没有记录许可证的文档省略该字段
+        let json = serde_json::to_value(SearchResult::new(&Documentation::new("CSS", "css", ""), &entry)).unwrap();
+        assert!(json.get("license").is_none());
+    }
+
+    #[test]
+    fn test_filter_scopes_doc_and_type() {
+        let entry = |name: &str, entry_type: &str| IndexEntry {
+            name: name.to_string(),
+            path: name.to_string(),
+            entry_type: entry_type.to_string(),
+            word_count: None,
+            inherited: None,
+        };
+        let index_type = |name: &str, slug: &str| crate::core::index_entry::IndexType {
+            name: name.to_string(),
+            count: 1,
+            slug: slug.to_string(),
+            weight: 0,
+            description: None,
+        };
+        let mut registry = DocRegistry::new();
+        registry.add(
+            Documentation::new("CSS", "css", "")
+                .with_types(vec![index_type("Media features", "media-features")])
+                .with_entries(vec![
+                    entry("border-top", "Properties"),
+                    entry("border", "Properties"),
+                    entry("border-radius()", "Media features"),
+                ]),
+        );
+        registry.add(Documentation::new("HTML", "html", "5").with_entries(vec![entry("border", "Attributes")]));
+
+        let search = |filter: SearchFilter| -> Vec<(String, String)> {
+            search_filtered(&registry, None, "border", 10, None, None, &filter)
+                .into_iter()
+                .map(|r| (r.doc, r.name))
+                .collect()
+        };
+
+        assert_eq!(search(SearchFilter::new()).len(), 4);
+        assert_eq!(
+            search(SearchFilter::new().with_doc("css").with_type("properties")),
+            [("css".to_string(), "border".to_string()), ("css".to_string(), "border-top".to_string())]
+        );
+        // 类型可以是名称或别名，结果中的别名取自文档的类型列表
+        let filter = SearchFilter::new().with_type("MEDIA-FEATURES");
+        let media = search_filtered(&registry, None, "border", 10, None, None, &filter);
+        assert_eq!(media.len(), 1);
+        assert_eq!(media[0].type_slug, "media-features");
+        assert_eq!(search(SearchFilter::new().with_type("media features")).len(), 1);
+        // 带版本的文档范围只匹配该版本
+        assert_eq!(search(SearchFilter::new().with_doc("html~5")), [("html~5".to_string(), "border".to_string())]);
+        assert!(search(SearchFilter::new().with_doc("html~4")).is_empty());
+    }
+
+    fn fixture_registry(root: &TempDir) -> DocRegistry {
+        store_page(
+            root.path(),
+            "javascript",
+            "global_objects/map/get",
+            "<h1>Map.prototype.get()</h1><p>Returns a specified element from a Map.</p>",
+        )
+        .unwrap();
+        let entry = |name: &str, path: &str, entry_type: &str| IndexEntry {
+            name: name.to_string(),
+            path: path.to_string(),
+            entry_type: entry_type.to_string(),
+            word_count: None,
+            inherited: None,
+        };
+
+        let mut registry = DocRegistry::new();
+        registry.add(
+            Documentation::new("JavaScript", "javascript", "")
+                .with_root(root.path(), false)
+                .with_entries(vec![entry("get", "global_objects/map/get#syntax", "Map")]),
+        );
+        registry.add(
+            Documentation::new("Python", "python", "3.12")
+                .with_root(root.path(), false)
+                .with_entries(vec![entry("get", "library/stdtypes", "dict")]),
+        );
+        registry
+    }
+
+    #[test]
+    fn test_results_disambiguate_doc_and_type() {
+        let root = TempDir::new().unwrap();
+        let registry = fixture_registry(&root);
+        let cache = SnippetCache::new();
+
+        let results = search(&registry, "get", 10, Some(&cache));
+        assert_eq!(results.len(), 2);
+        let javascript = results.iter().find(|r| r.doc == "javascript").unwrap();
+        assert_eq!(javascript.entry_type, "Map");
+        assert_eq!(javascript.doc_name, "JavaScript");
+        assert_eq!(javascript.snippet.as_deref(), Some("Returns a specified element from a Map."));
+        let python = results.iter().find(|r| r.doc == "python~3.12").unwrap();
+        assert_eq!(python.entry_type, "dict");
+        assert_eq!(python.version, "3.12");
+        assert_eq!(python.snippet, None);
+
+        let json = serde_json::to_value(javascript).unwrap();
+        assert_eq!(json["type"], "Map");
+    }
+
+    #[test]
+    fn test_snippets_are_lazy_and_cached() {
+        let root = TempDir::new().unwrap();
+        let registry = fixture_registry(&root);
+        let reads = Cell::new(0);
+        let page_cache = PageCache::new();
+        let counting_load = |doc: &Documentation, page: &str| {
+            reads.set(reads.get() + 1);
+            load_page(&page_cache, doc, page)
+        };
+
+        // 不请求摘要时完全不读取页面
+        let results = search_with(&registry, None, "get", 10, None, None, &SearchFilter::new(), counting_load);
+        assert!(results.iter().all(|r| r.snippet.is_none()));
+        assert_eq!(reads.get(), 0);
+
+        // 请求摘要时每个页面只读取一次
+        let cache = SnippetCache::new();
+        search_with(&registry, None, "get", 10, Some(&cache), None, &SearchFilter::new(), counting_load);
+        search_with(&registry, None, "get", 10, Some(&cache), None, &SearchFilter::new(), counting_load);
+        assert_eq!(reads.get(), 2);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.counters(), (2, 2, 0));
+        assert!(cache.memory_usage() > 0);
+    }
+
+    #[test]
+    fn test_load_page_uses_cached_db() {
+        let root = TempDir::new().unwrap();
+        let db_file = root.path().join("tmp").join(crate::core::doc::DB_FILENAME);
+        std::fs::create_dir(root.path().join("tmp")).unwrap();
+        std::fs::write(&db_file, r#"{"reference/array": "<h1>Array</h1><p>Arrays hold values.</p>"}"#).unwrap();
+        let doc = Documentation::new("Tmp", "tmp", "").with_root(root.path(), true);
+        let page_cache = PageCache::new();
+
+        let page = load_page(&page_cache, &doc, "reference/array").unwrap();
+        assert!(page.contains("Arrays hold values."));
+        assert_eq!(load_page(&page_cache, &doc, "reference/missing"), None);
+
+        // 修改时间不变时使用缓存的 db.json，不再读取文件
+        let modified = std::fs::metadata(&db_file).unwrap().modified().unwrap();
+        std::fs::write(&db_file, "{not json").unwrap();
+        std::fs::File::options().write(true).open(&db_file).unwrap().set_modified(modified).unwrap();
+        assert_eq!(load_page(&page_cache, &doc, "reference/array"), Some(page));
+    }
+
+    #[test]
+    fn test_history_boosts_selected_entry() {
+        let root = TempDir::new().unwrap();
+        let registry = fixture_registry(&root);
+        let history = SearchHistory::new(100, 30);
+        let order = |history: &SearchHistory| -> Vec<String> {
+            search_with_history(&registry, None, "get", 10, None, Some(history))
+                .into_iter()
+                .map(|r| r.doc)
+                .collect()
+        };
+
+        // 分数相同时按文档顺序排列
+        assert_eq!(order(&history), ["javascript", "python~3.12"]);
+
+        let now = chrono::Utc::now().timestamp();
+        history.record("python~3.12", "library/stdtypes", now).unwrap();
+        let results = search_with_history(&registry, None, "get", 10, None, Some(&history));
+        assert_eq!(results[0].doc, "python~3.12");
+        assert_eq!(results[0].score, results[1].score + 2);
+
+        // 加分有上限
+        for _ in 0..100 {
+            history.record("python~3.12", "library/stdtypes", now).unwrap();
+        }
+        let results = search_with_history(&registry, None, "get", 10, None, Some(&history));
+        assert_eq!(results[0].score, results[1].score + crate::docs::search_history::MAX_BOOST);
+
+        history.clear().unwrap();
+        assert_eq!(order(&history), ["javascript", "python~3.12"]);
+    }
+}
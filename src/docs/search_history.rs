@@ -0,0 +1,266 @@
+//! 搜索历史
+//!
+//! 开启 `search.history` 后，前端在用户选中搜索结果时调用
+//! `POST /api/search/select`，这里记录每个条目被选中的次数和最后一次选中的
+//! 时间。搜索时按次数和时间衰减计算出的频率（frecency）给条目加分，加分最多为
+//! [`MAX_BOOST`]，小于相邻两档匹配分数的差，只在匹配程度相同的条目之间起作用。
+//!
+//! 历史保存在一个 JSON 文件中，记录数和保留时间都有上限，超过的记录在写入时
+//! 丢弃。写入时先写临时文件再替换，不会留下写了一半的历史；写入文件时不持有
+//! 记录的锁，搜索不会等待磁盘。写入是阻塞的，服务器在阻塞线程池中调用
+//! [`SearchHistory::record`]。
+
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 选中次数的半衰期（天）：每过这么久，之前的选中只按一半计算
+pub const HALF_LIFE_DAYS: f64 = 14.0;
+
+/// 每次（未衰减的）选中增加的分数
+pub const BOOST_PER_SELECTION: f64 = 2.0;
+
+/// 加分的上限，小于匹配分数每档之间的差（10）
+pub const MAX_BOOST: u32 = 9;
+
+/// 一天的秒数
+const DAY_SECS: f64 = 86_400.0;
+
+/// 一个条目的选中记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Selection {
+    /// 文档路径（`slug~version`）
+    pub doc: String,
+    /// 条目路径
+    pub path: String,
+    /// 选中次数
+    pub count: u32,
+    /// 最后一次选中的时间（Unix 秒）
+    pub last_used: i64,
+}
+
+impl Selection {
+    /// 在 `now` 时的加分
+    pub fn boost(&self, now: i64) -> u32 {
+        let age_days = (now - self.last_used).max(0) as f64 / DAY_SECS;
+        let frecency = self.count as f64 * 0.5f64.powf(age_days / HALF_LIFE_DAYS);
+        ((frecency * BOOST_PER_SELECTION).round() as u32).min(MAX_BOOST)
+    }
+}
+
+/// 持久化的搜索历史
+#[derive(Debug)]
+pub struct SearchHistory {
+    /// 历史文件，None 时只保存在内存中
+    path: Option<PathBuf>,
+    /// 最多保留的记录数
+    max_records: usize,
+    /// 记录的最长保留时间（秒）
+    max_age_secs: i64,
+    records: Mutex<Vec<Selection>>,
+    /// 保证历史文件按顺序写入最新的记录
+    save_lock: Mutex<()>,
+}
+
+impl SearchHistory {
+    /// 创建只保存在内存中的搜索历史
+    pub fn new(max_records: usize, max_age_days: u64) -> Self {
+        Self {
+            path: None,
+            max_records,
+            max_age_secs: (max_age_days as i64).saturating_mul(DAY_SECS as i64),
+            records: Mutex::new(Vec::new()),
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    /// 打开历史文件，文件不存在或无法解析时从空的历史开始
+    pub fn open<P: Into<PathBuf>>(path: P, max_records: usize, max_age_days: u64) -> Self {
+        let path = path.into();
+        let records = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            records: Mutex::new(records),
+            ..Self::new(max_records, max_age_days)
+        }
+    }
+
+    /// 按配置打开搜索历史，没有开启时返回 None
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.search.history.then(|| {
+            Self::open(
+                config.search_history_path(),
+                config.search.history_max_records,
+                config.search.history_max_age_days,
+            )
+        })
+    }
+
+    /// 记录一次选中，并丢弃过期和超出数量的记录，之后写入历史文件
+    pub fn record(&self, doc: &str, path: &str, now: i64) -> Result<()> {
+        self.update(doc, path, now);
+        self.save()
+    }
+
+    /// 在内存中记录一次选中
+    fn update(&self, doc: &str, path: &str, now: i64) {
+        let mut records = self.records.lock().unwrap();
+        match records.iter_mut().find(|r| r.doc == doc && r.path == path) {
+            Some(selection) => {
+                selection.count = selection.count.saturating_add(1);
+                selection.last_used = now;
+            }
+            None => records.push(Selection {
+                doc: doc.to_string(),
+                path: path.to_string(),
+                count: 1,
+                last_used: now,
+            }),
+        }
+
+        records.retain(|r| now - r.last_used <= self.max_age_secs);
+        if records.len() > self.max_records {
+            records.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+            records.truncate(self.max_records);
+        }
+    }
+
+    /// 清空历史并删除历史文件
+    pub fn clear(&self) -> Result<()> {
+        let _guard = self.save_lock.lock().unwrap();
+        self.records.lock().unwrap().clear();
+        match &self.path {
+            Some(path) if path.exists() => Ok(fs::remove_file(path)?),
+            _ => Ok(()),
+        }
+    }
+
+    /// 各文档中条目在 `now` 时的加分，没有加分的条目不包含在内
+    pub fn boosts(&self, now: i64) -> HashMap<String, HashMap<String, u32>> {
+        let mut boosts: HashMap<String, HashMap<String, u32>> = HashMap::new();
+        for selection in self.records.lock().unwrap().iter() {
+            let boost = selection.boost(now);
+            if boost > 0 {
+                boosts
+                    .entry(selection.doc.clone())
+                    .or_default()
+                    .insert(selection.path.clone(), boost);
+            }
+        }
+        boosts
+    }
+
+    /// 记录数
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    /// 历史是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 将当前的记录写入历史文件，先写入临时文件再替换
+    ///
+    /// 在写入锁中读取记录，并发写入时最后写入的总是最新的记录。
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _guard = self.save_lock.lock().unwrap();
+        let json = serde_json::to_string(&*self.records.lock().unwrap())
+            .map_err(|e| Error::Message(format!("无法序列化搜索历史: {}", e)))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| Error::Message(format!("无法写入搜索历史 {:?}: {}", path, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const NOW: i64 = 1_700_000_000;
+    const DAY: i64 = 86_400;
+
+    #[test]
+    fn test_boost_decays_and_is_capped() {
+        let selection = |count, age_days: i64| Selection {
+            doc: "css".to_string(),
+            path: "color".to_string(),
+            count,
+            last_used: NOW - age_days * DAY,
+        };
+        assert_eq!(selection(1, 0).boost(NOW), 2);
+        assert_eq!(selection(2, 0).boost(NOW), 4);
+        assert_eq!(selection(2, 14).boost(NOW), 2);
+        assert_eq!(selection(1, 60).boost(NOW), 0);
+        assert_eq!(selection(100, 0).boost(NOW), MAX_BOOST);
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_persisted() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("history.json");
+        let history = SearchHistory::open(&file, 2, 30);
+
+        history.record("css", "old", NOW - 40 * DAY).unwrap();
+        history.record("css", "color", NOW - DAY).unwrap();
+        history.record("css", "color", NOW).unwrap();
+        // old 超过 30 天被丢弃
+        assert_eq!(history.len(), 1);
+        history.record("html", "div", NOW).unwrap();
+        history.record("html", "span", NOW + 1).unwrap();
+        // 超过 2 条时丢弃最久没有选中的，同时选中的按先后保留
+        assert_eq!(history.len(), 2);
+
+        let reopened = SearchHistory::open(&file, 2, 30);
+        let boosts = reopened.boosts(NOW + 1);
+        assert_eq!(boosts["html"]["span"], 2);
+        assert_eq!(boosts["css"]["color"], 4);
+        assert!(!boosts["html"].contains_key("div"));
+
+        reopened.clear().unwrap();
+        assert!(reopened.is_empty());
+        assert!(!file.exists());
+        assert!(SearchHistory::open(&file, 2, 30).is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_records_are_all_saved() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("history").join("history.json");
+        let history = std::sync::Arc::new(SearchHistory::open(&file, 100, 30));
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let history = history.clone();
+                std::thread::spawn(move || {
+                    for j in 0..5 {
+                        history.record("css", &format!("entry-{}-{}", i, j), NOW).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // 文件中是最新的完整记录，没有留下临时文件
+        assert_eq!(SearchHistory::open(&file, 100, 30).len(), 40);
+        assert!(!dir.path().join("history").join("history.json.tmp").exists());
+    }
+}
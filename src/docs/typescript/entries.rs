@@ -0,0 +1,223 @@
+//! TypeScript 条目过滤器
+//!
+//! 按 typescriptlang.org 的 URL 前缀对页面分类，TSConfig 参考页面中的每个编译选项
+//! 另外生成一个锚点条目。
+
+use crate::core::error::Result;
+use crate::core::filters::selector;
+use crate::core::scraper::filter::{Filter, FilterContext, FilterOutput};
+use scraper::Html;
+use std::any::Any;
+use std::collections::HashSet;
+
+/// 页面路径前缀对应的类型，按顺序匹配，第一个匹配的前缀生效
+const PAGE_TYPES: &[(&str, &str)] = &[
+    ("tsconfig", "Project Configuration"),
+    ("docs/handbook/declaration-files/", "Declaration Files"),
+    ("docs/handbook/tsconfig-json", "Project Configuration"),
+    ("docs/handbook/compiler-options", "Project Configuration"),
+    ("docs/handbook/project-references", "Project Configuration"),
+    ("docs/handbook/integrating-with-build-tools", "Project Configuration"),
+    ("docs/handbook/configuring-watch", "Project Configuration"),
+    ("docs/handbook/nightly-builds", "Project Configuration"),
+    ("docs/handbook/2/", "Handbook"),
+    ("docs/handbook/intro", "Handbook"),
+    ("docs/handbook/typescript-", "Handbook"),
+    ("docs/handbook/", "Reference"),
+];
+
+/// 不属于以上前缀的页面（如文档首页）的类型
+const DEFAULT_TYPE: &str = "Handbook";
+
+/// TSConfig 参考页面的路径
+const TSCONFIG_PAGE: &str = "tsconfig";
+
+/// TSConfig 参考页面中编译选项的类型
+const TSCONFIG_TYPE: &str = "Project Configuration";
+
+/// TypeScript 条目过滤器
+pub struct TypeScriptEntriesFilter {}
+
+impl TypeScriptEntriesFilter {
+    /// 创建新的 TypeScript 条目过滤器
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// 条目名称为页面的 `<h1>` 标题，没有标题时由路径的最后一段生成
+    fn get_name(&self, document: &Html, subpath: &str) -> String {
+        if let Some(heading) = document.select(&selector("h1")).next() {
+            let name = heading.text().collect::<String>().trim().to_string();
+            if !name.is_empty() {
+                return name;
+            }
+        }
+        let page = last_segment(subpath);
+        page.strip_suffix(".html").unwrap_or(page).replace('-', " ")
+    }
+
+    /// 由页面路径前缀推断类型
+    fn get_type(&self, subpath: &str) -> &'static str {
+        let path = subpath.trim_start_matches('/');
+        PAGE_TYPES
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix))
+            .map_or(DEFAULT_TYPE, |(_, entry_type)| entry_type)
+    }
+
+    /// 是否为 TSConfig 参考页面
+    fn is_tsconfig_page(&self, subpath: &str) -> bool {
+        let page = subpath.trim_matches('/');
+        page == TSCONFIG_PAGE || page.strip_suffix(".html") == Some(TSCONFIG_PAGE)
+    }
+
+    /// TSConfig 参考页面中每个编译选项的锚点条目
+    ///
+    /// 每个选项是一个带 id 的 `<h3>`，锚点就是选项名（标题中 `<code>` 的文本，没有时为
+    /// 标题的文本）。分类标题的锚点与文本不同（如 `Type_Checking_6248`），不生成条目，
+    /// 因此不依赖标题中的空白和链接符号。同一锚点只保留第一次出现的标题。
+    fn tsconfig_entries(&self, document: &Html, subpath: &str) -> Vec<(String, String, String)> {
+        let mut seen = HashSet::new();
+        document
+            .select(&selector("h3[id]"))
+            .filter_map(|heading| {
+                let id = heading.value().attr("id")?.trim();
+                let name = match heading.select(&selector("code")).next() {
+                    Some(code) => code.text().collect::<String>(),
+                    None => heading.text().collect::<String>(),
+                };
+                let name = name.trim().to_string();
+                if id.is_empty() || name != id {
+                    return None;
+                }
+                if !seen.insert(id.to_string()) {
+                    return None;
+                }
+                Some((name, format!("{}#{}", subpath, id), TSCONFIG_TYPE.to_string()))
+            })
+            .collect()
+    }
+}
+
+impl Filter for TypeScriptEntriesFilter {
+    fn apply_v2(&self, html: &str, context: &FilterContext) -> Result<FilterOutput> {
+        // 条目过滤器不修改HTML内容，只检查页面是否有标题
+        let mut output = FilterOutput::new(html);
+        if context.html_doc().select(&selector("h1")).next().is_none() {
+            output = output.with_warnings([self.warning("missing entry name").with_selector("h1")]);
+        }
+        Ok(output)
+    }
+
+    fn box_clone(&self) -> Box<dyn Filter> {
+        Box::new(Self::new())
+    }
+
+    fn get_entries(&self, context: &FilterContext) -> Vec<(String, String, String)> {
+        let document = context.html_doc();
+        let name = self.get_name(document, &context.current_path);
+        let entry_type = self.get_type(&context.current_path).to_string();
+
+        // 当前页面作为条目，TSConfig 参考页面再为每个编译选项添加锚点条目
+        let mut entries = vec![(name, context.current_path.clone(), entry_type)];
+        if self.is_tsconfig_page(&context.current_path) {
+            entries.extend(self.tsconfig_entries(document, &context.current_path));
+        }
+        entries
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// 路径的最后一段
+fn last_segment(path: &str) -> &str {
+    path.trim_matches('/').rsplit('/').next().unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries_for(path: &str, html: &str) -> Vec<(String, String, String)> {
+        let mut context = FilterContext::new().with_html(html);
+        context.current_path = path.to_string();
+        TypeScriptEntriesFilter::new().get_entries(&context)
+    }
+
+    /// 测试页面按线上 TSConfig 参考页面的结构手工整理，不是完整的页面快照（整理时
+    /// 无法访问网络）。更新时用 `curl -o test_docs/typescript_test/tsconfig.html
+    /// https://www.typescriptlang.org/tsconfig` 保存线上页面后重新运行这个测试。
+    #[test]
+    fn test_tsconfig_page_fixture() {
+        let html = std::fs::read_to_string("test_docs/typescript_test/tsconfig.html").unwrap();
+        let entries = entries_for("tsconfig", &html);
+
+        assert_eq!(
+            entries[0],
+            (
+                "Intro to the TSConfig Reference".to_string(),
+                "tsconfig".to_string(),
+                "Project Configuration".to_string()
+            )
+        );
+
+        let options = &entries[1..];
+        assert!(options.len() >= 100, "{}", options.len());
+        assert!(options.iter().all(|(_, path, entry_type)| path.starts_with("tsconfig#") && entry_type == TSCONFIG_TYPE));
+        let entry = |name: &str| (name.to_string(), format!("tsconfig#{}", name), TSCONFIG_TYPE.to_string());
+        assert!(options.contains(&entry("strictNullChecks")));
+        assert!(options.contains(&entry("moduleResolution")));
+        assert!(options.contains(&entry("files")));
+
+        // 分类标题不生成条目，每个锚点只有一个条目
+        assert!(!options.iter().any(|(name, _, _)| name == "Type Checking"));
+        let anchors: HashSet<&str> = options.iter().map(|(_, path, _)| path.as_str()).collect();
+        assert_eq!(anchors.len(), options.len());
+    }
+
+    #[test]
+    fn test_page_types_by_url_prefix() {
+        let html = "<h1>Title</h1>";
+        let entry_type = |path: &str| entries_for(path, html)[0].2.clone();
+
+        assert_eq!(entry_type("docs/handbook/2/basic-types.html"), "Handbook");
+        assert_eq!(entry_type("docs/handbook/typescript-in-5-minutes.html"), "Handbook");
+        assert_eq!(entry_type("docs/handbook/utility-types.html"), "Reference");
+        assert_eq!(entry_type("docs/handbook/declaration-files/introduction.html"), "Declaration Files");
+        assert_eq!(entry_type("docs/handbook/tsconfig-json.html"), "Project Configuration");
+        assert_eq!(entry_type("docs/handbook/project-references.html"), "Project Configuration");
+
+        // 其他页面只有页面条目
+        assert_eq!(entries_for("docs/handbook/tsconfig-json.html", "<h3 id=\"files\">files</h3>").len(), 1);
+    }
+
+    #[test]
+    fn test_tsconfig_headings_with_markup() {
+        let html = concat!(
+            "<h1>TSConfig Reference</h1>",
+            "<h3 id=\"Type_Checking_6248\">Type Checking</h3>",
+            "<h3 id=\"Emit\"><a href=\"#Emit\">#</a> Emit</h3>",
+            "<h3 id=\"strict\">\n  <a href=\"#strict\"><code>strict</code></a>\n  <span>#</span>\n</h3>",
+            "<h3 id=\"noEmit\">noEmit</h3>",
+        );
+        let names: Vec<String> = entries_for("tsconfig", html)[1..].iter().map(|(name, _, _)| name.clone()).collect();
+        assert_eq!(names, ["strict", "noEmit"]);
+    }
+
+    #[test]
+    fn test_name_falls_back_to_path() {
+        let entries = entries_for("docs/handbook/utility-types.html", "<p>No title</p>");
+        assert_eq!(entries[0].0, "utility types");
+
+        let mut context = FilterContext::new().with_html("<p>No title</p>");
+        context.current_path = "docs/handbook/utility-types.html".to_string();
+        let output = TypeScriptEntriesFilter::new().apply_v2("<p>No title</p>", &context).unwrap();
+        assert_eq!(output.warnings.len(), 1);
+    }
+}
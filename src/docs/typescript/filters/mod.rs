@@ -1,3 +0,0 @@
-//! TypeScript过滤器模块
-
-// 在此处添加特定TypeScript过滤器模块导出
@@ -2,6 +2,8 @@
 //! 
 //! 包含 TypeScript 文档的抓取器和过滤器实现
 
+mod entries;
 mod scraper;
 
+pub use entries::TypeScriptEntriesFilter;
 pub use scraper::TypeScriptScraper;
@@ -1,8 +1,12 @@
 //! TypeScript 文档爬虫
 
 use crate::core::error::Result;
+use crate::core::instrumentable;
+use crate::core::scraper::url_scraper::START_SCRAPER_EVENT;
 use crate::core::filters::{HtmlCleanerFilter, UrlNormalizerFilter};
 use crate::core::scraper::{Scraper as CoreScraper, UrlScraper};
+use crate::core::version_probe::{GitHubLatestRelease, VersionProbe};
+use crate::docs::typescript::TypeScriptEntriesFilter;
 use async_trait::async_trait;
 
 /// TypeScript 文档爬虫
@@ -14,19 +18,25 @@ pub struct TypeScriptScraper {
 impl TypeScriptScraper {
     /// 创建新的 TypeScript 文档爬虫
     pub fn new(version: &str, output_path: &str) -> Self {
-        let base_url = "https://www.typescriptlang.org/docs";
+        // 从站点根目录抓取，TSConfig 参考页面不在 `/docs` 下
+        let base_url = "https://www.typescriptlang.org/";
         let mut scraper = UrlScraper::new("TypeScript", version, base_url, output_path);
 
         // 添加初始路径
         let initial_paths = vec![
-            "/".to_string(),
-            "/handbook/intro.html".to_string(),
-            "/handbook/typescript-in-5-minutes.html".to_string(),
-            "/handbook/2/basic-types.html".to_string(),
-            "/handbook/2/functions.html".to_string(),
-            "/handbook/2/classes.html".to_string(),
+            "/docs/".to_string(),
+            "/docs/handbook/intro.html".to_string(),
+            "/docs/handbook/typescript-in-5-minutes.html".to_string(),
+            "/docs/handbook/2/basic-types.html".to_string(),
+            "/docs/handbook/2/functions.html".to_string(),
+            "/docs/handbook/2/classes.html".to_string(),
+            "/docs/handbook/declaration-files/introduction.html".to_string(),
+            "/tsconfig".to_string(),
         ];
 
+        // 只抓取文档和 TSConfig 参考，站点中其余的页面（演练场、社区、博客等）都跳过
+        let only_patterns = vec!["^docs/".to_string(), "^tsconfig".to_string()];
+
         // 创建过滤器
         let html_cleaner = Box::new(
             HtmlCleanerFilter::new()
@@ -35,12 +45,15 @@ impl TypeScriptScraper {
                 .with_remove_tag("aside"),
         );
         let url_normalizer = Box::new(UrlNormalizerFilter::new(base_url, "/docs/typescript/"));
+        let entries_filter = Box::new(TypeScriptEntriesFilter::new());
 
         // 添加过滤器和初始路径
         scraper = scraper
             .with_initial_paths(initial_paths)
+            .with_only_patterns(only_patterns)
             .with_filter(html_cleaner)
-            .with_filter(url_normalizer);
+            .with_filter(url_normalizer)
+            .with_filter(entries_filter);
 
         Self { scraper }
     }
@@ -57,7 +70,15 @@ impl CoreScraper for TypeScriptScraper {
     }
 
     async fn run(&mut self) -> Result<()> {
-        println!("开始抓取TypeScript文档...");
+        instrumentable::message(START_SCRAPER_EVENT, "开始抓取TypeScript文档...");
         self.scraper.run().await
     }
+
+    fn url_scraper_mut(&mut self) -> Option<&mut UrlScraper> {
+        Some(&mut self.scraper)
+    }
+
+    fn version_probe(&self) -> Option<Box<dyn VersionProbe>> {
+        Some(Box::new(GitHubLatestRelease::new("microsoft/TypeScript")))
+    }
 }
@@ -10,44 +10,157 @@ pub mod storage;
 pub mod web;
 
 use std::error::Error;
+use std::sync::Arc;
 
-use crate::core::config::Config;
+use crate::core::config::{Config, ScraperDefaults};
+use crate::core::format;
+use crate::core::http_fetch::TlsOptions;
+use crate::core::instrumentable;
+use crate::core::page_db::DbFormat;
+use crate::core::scraper::url_scraper::SAVE_SCRAPER_EVENT;
+use crate::core::scraper::{OversizePolicy, UrlScraper};
+use crate::core::subscriber::{FileSubscriber, Subscriber};
+use crate::core::types::DocSlug;
 use crate::docs::DocRegistry;
-use crate::storage::FileStore;
+use crate::storage::{FileStore, MemoryStore, Store};
 use crate::web::server::Server;
 
-/// 初始化 xwdoc 应用程序
-pub fn init(config: Config) -> Result<(), Box<dyn Error>> {
+/// 初始化 xwdoc 应用程序，返回已加载的文档注册表
+pub fn init(config: &Config) -> Result<DocRegistry, Box<dyn Error>> {
     // 创建文档注册表
     let mut registry = DocRegistry::new();
 
     // 初始化存储
-    let _store = FileStore::new(&config.docs_path);
+    let _store = FileStore::new(config.writable_root());
 
-    // 加载已有文档
-    registry.load_from_disk(&config.docs_path)?;
+    // 加载已有文档和各个命名空间
+    registry.load_config(config)?;
 
-    println!("xwdoc初始化完成，已加载{}个文档", registry.all().len());
-    Ok(())
+    // 配置了日志文件时将所有事件写入文件
+    if let Some(subscriber) = FileSubscriber::from_config(&config.logging) {
+        let subscriber = std::sync::Arc::new(subscriber);
+        core::instrumentable::subscribe("*", move |info| subscriber.handle_event(info));
+    }
+
+    log::info!("已加载{}个文档", registry.all().len());
+    Ok(registry)
 }
 
-/// 启动Web服务器
-pub async fn start(host: &str, port: u16) -> Result<(), Box<dyn Error>> {
-    let config = Config::default();
+/// 根据配置创建Web服务器，服务器与路由共用同一个文档注册表
+pub fn build_server(config: Config, host: &str, port: u16) -> Result<Server, Box<dyn Error>> {
+    let registry = init(&config)?;
+    let router = web::routes::create_routes_with_registry(&config, registry);
+    Ok(Server::new(config, host, port).with_router(router))
+}
 
-    // 初始化应用
-    init(config.clone())?;
+/// 使用默认配置启动Web服务器
+pub async fn start(host: &str, port: u16) -> Result<(), Box<dyn Error>> {
+    start_with_config(Config::default(), host, port).await
+}
 
-    // 创建路由
-    let router = web::routes::create_routes(&config);
+/// 使用指定配置启动Web服务器
+pub async fn start_with_config(config: Config, host: &str, port: u16) -> Result<(), Box<dyn Error>> {
+    build_server(config, host, port)?.run().await
+}
 
-    // 创建并启动服务器
-    let server = Server::new(config, host, port).with_router(router);
+/// 抓取选项
+#[derive(Debug, Clone, Default)]
+pub struct ScrapeOptions {
+    /// 过滤失败时是否保存未过滤的HTML
+    pub store_on_filter_error: bool,
+    /// 每分钟最大请求数（优先于配置和抓取器设置）
+    pub rate_limit: Option<u32>,
+    /// 最大并发请求数（优先于配置和抓取器设置）
+    pub concurrency: Option<usize>,
+    /// 是否输出页面字数统计
+    pub include_stats: bool,
+    /// 是否从上次中断的抓取日志中恢复
+    pub resume: bool,
+    /// db.json 格式（优先于配置和抓取器设置）
+    pub db_format: Option<DbFormat>,
+    /// 是否去掉近似重复的页面
+    pub drop_near_duplicates: bool,
+    /// 是否根据延迟和错误率自动调整并发数
+    pub adaptive_concurrency: bool,
+    /// 是否保存过滤前的页面，供 `docs refilter` 使用
+    pub keep_raw: bool,
+    /// 过滤后单个页面的大小限制（优先于配置和抓取器设置）
+    pub max_page_size: Option<usize>,
+    /// 超过大小限制的页面的处理策略（优先于配置和抓取器设置）
+    pub oversize_policy: Option<OversizePolicy>,
+    /// 将全部抓取事件（不采样）写入此文件
+    pub trace_urls: Option<std::path::PathBuf>,
+    /// 抓取事件发布到事件总线时的采样率
+    pub event_sample_rate: Option<u64>,
+    /// 是否从链接中去掉找不到的锚点
+    pub strip_broken_fragments: bool,
+    /// 是否不下载网站图标
+    pub no_favicon: bool,
+    /// 额外信任的 CA 证书（与配置中的证书合并）
+    pub ca_certs: Vec<std::path::PathBuf>,
+    /// 是否关闭 TLS 证书校验（`--insecure`）
+    pub insecure: bool,
+    /// 是否跳过开始抓取前的磁盘空间检查
+    pub ignore_disk_space: bool,
+    /// 严格模式：抓取质量检查没有通过时抓取失败（与配置中的 `strict.enabled` 任一开启即可）
+    pub strict: bool,
+    /// 试运行：输出文件写入内存，不修改磁盘上的文档，结束后输出将写入的文件和大小
+    pub dry_run: bool,
+    /// 抓取到该命名空间的目录中（见 [`Config::in_namespace`]）
+    pub namespace: Option<String>,
+    /// 取消抓取的令牌（命令行的 Ctrl-C、服务器的任务取消）
+    pub cancel: Option<tokio_util::sync::CancellationToken>,
+}
 
-    println!("服务器启动在 http://{}:{}", host, port);
-    println!("Web界面准备就绪，请在浏览器中访问");
+impl ScrapeOptions {
+    /// 命令行指定的 TLS 设置
+    pub fn tls(&self) -> TlsOptions {
+        TlsOptions {
+            ca_certs: self.ca_certs.clone(),
+            accept_invalid_certs: self.insecure,
+        }
+    }
 
-    server.run().await
+    /// 将选项应用到抓取器
+    ///
+    /// 优先级：命令行参数 > 抓取器显式设置 > 配置文件 > 内置默认值
+    pub fn apply_to(&self, scraper: &mut UrlScraper, defaults: &ScraperDefaults) {
+        scraper.apply_defaults(defaults);
+        scraper.store_on_filter_error = self.store_on_filter_error;
+        scraper.include_stats = self.include_stats;
+        scraper.resume = self.resume;
+        scraper.drop_near_duplicates |= self.drop_near_duplicates;
+        scraper.adaptive_concurrency |= self.adaptive_concurrency;
+        scraper.keep_raw |= self.keep_raw;
+        scraper.strip_broken_fragments |= self.strip_broken_fragments;
+        scraper.favicon &= !self.no_favicon;
+        scraper.ignore_disk_space |= self.ignore_disk_space;
+        scraper.tls.merge(&self.tls());
+        if let Some(rate_limit) = self.rate_limit {
+            scraper.rate_limit = Some(rate_limit);
+        }
+        if let Some(concurrency) = self.concurrency {
+            scraper.concurrency = Some(concurrency);
+        }
+        if let Some(db_format) = self.db_format {
+            scraper.db_format = Some(db_format);
+        }
+        if let Some(max_page_size) = self.max_page_size {
+            scraper.max_page_size = Some(max_page_size);
+        }
+        if let Some(policy) = self.oversize_policy {
+            scraper.oversize_policy = Some(policy);
+        }
+        if let Some(trace_urls) = &self.trace_urls {
+            scraper.trace_urls = Some(trace_urls.clone());
+        }
+        if let Some(sample_rate) = self.event_sample_rate {
+            scraper.event_sample_rate = sample_rate;
+        }
+        if let Some(cancel) = &self.cancel {
+            scraper.cancel = cancel.clone();
+        }
+    }
 }
 
 /// 异步抓取文档
@@ -56,54 +169,130 @@ pub async fn scrape_async(
     version: &str,
     output_or_url: &str,
 ) -> Result<(), Box<dyn Error>> {
-    use crate::core::scraper::Scraper as CoreScraper;
-    let config = Config::default();
-    let result = match name.to_lowercase().as_str() {
-        "html" => {
-            let mut scraper = docs::html::HtmlScraper::new(version, &config.docs_path);
-            scraper.run().await
-        }
-        "css" => {
-            let mut scraper = docs::css::CssScraper::new(version, &config.docs_path);
-            scraper.run().await
-        }
-        "javascript" => {
-            let mut scraper = docs::javascript::JavaScriptScraper::new(version, &config.docs_path);
-            scraper.run().await
-        }
-        "rust" => {
-            let mut scraper = docs::rust::RustScraper::new(version, &config.docs_path);
-            scraper.run().await
+    scrape_async_with_options(name, version, output_or_url, &ScrapeOptions::default()).await
+}
+
+/// 使用指定选项异步抓取文档
+pub async fn scrape_async_with_options(
+    name: &str,
+    version: &str,
+    output_or_url: &str,
+    options: &ScrapeOptions,
+) -> Result<(), Box<dyn Error>> {
+    let config = match &options.namespace {
+        Some(namespace) => Config::default()
+            .in_namespace(namespace)
+            .ok_or_else(|| format!("没有配置命名空间: {}", namespace))?,
+        None => Config::default(),
+    };
+    let mut scraper = build_scraper(name, version, output_or_url, &config.docs_path())?;
+
+    // 记录正在运行的抓取任务
+    let _job = crate::core::scraper::ScrapeJob::start();
+
+    // 应用抓取选项
+    let dry_run = options.dry_run.then(|| Arc::new(MemoryStore::new()));
+    if let Some(url_scraper) = scraper.url_scraper_mut() {
+        options.apply_to(url_scraper, &config.scraper);
+        url_scraper.exclude_entries.merge(&config.exclude_entries);
+        url_scraper.db_format.get_or_insert(config.db_format);
+        url_scraper.tls.merge(&config.tls);
+        url_scraper.disk_space = config.disk_space.clone();
+        if options.strict || config.strict.enabled {
+            url_scraper.strict.get_or_insert_with(|| config.strict.clone());
         }
-        "typescript" => {
-            let mut scraper = docs::typescript::TypeScriptScraper::new(version, &config.docs_path);
-            scraper.run().await
+        if let Some(store) = &dry_run {
+            url_scraper.store = Some(store.clone() as Arc<dyn Store + Send + Sync>);
         }
+    } else if dry_run.is_some() {
+        return Err(format!("{} 不支持试运行", name).into());
+    }
+
+    scraper
+        .run()
+        .await
+        .map_err(|e| Box::new(e) as Box<dyn Error>)?;
+
+    if let Some(store) = &dry_run {
+        instrumentable::message(SAVE_SCRAPER_EVENT, dry_run_summary(store));
+    }
+    Ok(())
+}
+
+/// 试运行的摘要：将写入的每个文件及其大小和总大小
+fn dry_run_summary(store: &MemoryStore) -> String {
+    let mut summary = format!(
+        "试运行：将写入 {} 个文件，共 {}",
+        store.len(),
+        format::format_bytes(store.total_size() as u64)
+    );
+    for (path, size) in store.files() {
+        summary.push_str(&format!("\n  {}  {}", path, format::format_bytes(size as u64)));
+    }
+    summary
+}
+
+/// 按文档类型创建抓取器，非内置类型使用通用的 URL 抓取器
+fn build_scraper(
+    name: &str,
+    version: &str,
+    output_or_url: &str,
+    docs_path: &str,
+) -> Result<Box<dyn crate::core::scraper::Scraper>, Box<dyn Error>> {
+    Ok(match DocSlug::new(name).as_str() {
+        "html" => Box::new(docs::html::HtmlScraper::new(version, docs_path)),
+        "css" => Box::new(docs::css::CssScraper::new(version, docs_path)),
+        "javascript" => Box::new(docs::javascript::JavaScriptScraper::new(version, docs_path)),
+        "rust" => Box::new(docs::rust::RustScraper::new(version, docs_path)),
+        "typescript" => Box::new(docs::typescript::TypeScriptScraper::new(version, docs_path)),
         "babel" => {
             // 使用输出路径或默认路径
             let output_path = if !output_or_url.is_empty() && !output_or_url.starts_with("http") {
                 output_or_url
             } else {
-                &config.docs_path
+                docs_path
             };
-            let mut scraper = docs::babel::BabelScraper::new(output_path, version);
-            scraper.run().await
+            Box::new(docs::babel::BabelScraper::new(output_path, version))
         }
         _ => {
             // 只有当不是内置类型时才需要 url
             if output_or_url.is_empty() || !output_or_url.starts_with("http") {
                 return Err("非内置文档类型必须指定 url".into());
             }
-            let mut scraper = crate::core::scraper::UrlScraper::new(
-                name,
-                version,
-                output_or_url,
-                &config.docs_path,
-            );
-            scraper.run().await
+            Box::new(UrlScraper::new(name, version, output_or_url, docs_path))
         }
-    };
-    result.map_err(|e| Box::new(e) as Box<dyn Error>)
+    })
+}
+
+/// 内置文档的上游最新版本查询方式，抓取器没有声明时返回 None
+pub fn version_probe(name: &str) -> Option<Box<dyn crate::core::version_probe::VersionProbe>> {
+    build_scraper(name, "", "", &Config::default().docs_path()).ok()?.version_probe()
+}
+
+/// 用当前的过滤器重新过滤文档保存的原始页面（`docs refilter`）
+///
+/// 文档需要用 `--keep-raw` 抓取过。非内置类型未指定 `url` 时使用
+/// scraper.json 中记录的基础网址。
+pub async fn refilter_async(name: &str, version: &str, url: &str) -> Result<(), Box<dyn Error>> {
+    let config = Config::default();
+    let docs_path = config.docs_path();
+    let mut url = url.to_string();
+    if url.is_empty() {
+        let dir_name = DocSlug::new(name).dir_name(version);
+        let provenance = std::path::Path::new(&docs_path).join(dir_name).join("scraper.json");
+        if let Ok(content) = std::fs::read_to_string(provenance) {
+            let provenance: serde_json::Value = serde_json::from_str(&content)?;
+            url = provenance["base_url"].as_str().unwrap_or_default().to_string();
+        }
+    }
+
+    let mut scraper = build_scraper(name, version, &url, &docs_path)?;
+    let url_scraper = scraper
+        .url_scraper_mut()
+        .ok_or_else(|| format!("{} 不支持重新过滤", name))?;
+    url_scraper.exclude_entries.merge(&config.exclude_entries);
+    url_scraper.db_format.get_or_insert(config.db_format);
+    url_scraper.refilter().await.map_err(|e| Box::new(e) as Box<dyn Error>)
 }
 
 /// 获取默认文档列表
@@ -118,16 +307,55 @@ pub fn scrape(name: &str, version: &str, url: &str) -> Result<(), Box<dyn Error>
     rt.block_on(scrape_async(name, version, url))
 }
 
-/// 生成文档清单
-pub fn generate_manifest() -> Result<(), Box<dyn Error>> {
-    let config = Config::default();
-    let mut registry = DocRegistry::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_defaults() -> ScraperDefaults {
+        ScraperDefaults {
+            rate_limit: 30,
+            concurrency: 4,
+            ..ScraperDefaults::default()
+        }
+    }
 
-    // 加载已有文档
-    registry.load_from_disk(&config.docs_path)?;
+    #[test]
+    fn test_scrape_options_without_overrides_use_config() {
+        let mut scraper = UrlScraper::new("Fixture", "1", "https://example.com", "docs");
+        ScrapeOptions::default().apply_to(&mut scraper, &config_defaults());
+        assert_eq!(scraper.effective_settings(), config_defaults());
+    }
 
-    // 生成清单
-    registry.generate_manifest(&config.docs_path)?;
+    #[test]
+    fn test_scrape_options_override_config_and_scraper() {
+        let options = ScrapeOptions {
+            rate_limit: Some(120),
+            concurrency: Some(8),
+            ..ScrapeOptions::default()
+        };
+        let mut scraper =
+            UrlScraper::new("Fixture", "1", "https://example.com", "docs").with_rate_limit(10);
+        options.apply_to(&mut scraper, &config_defaults());
 
-    Ok(())
+        let settings = scraper.effective_settings();
+        assert_eq!(settings.rate_limit, 120);
+        assert_eq!(settings.concurrency, 8);
+        assert_eq!(settings.max_retries, ScraperDefaults::default().max_retries);
+    }
+
+    #[test]
+    fn test_dry_run_summary_lists_files() {
+        let store = MemoryStore::new();
+        store.write("css~1/index.json", "{}").unwrap();
+        store.write("css~1/db.json", &"x".repeat(2048)).unwrap();
+        assert_eq!(
+            dry_run_summary(&store),
+            format!(
+                "试运行：将写入 2 个文件，共 {}\n  css~1/db.json  {}\n  css~1/index.json  {}",
+                format::format_bytes(2050),
+                format::format_bytes(2048),
+                format::format_bytes(2)
+            )
+        );
+    }
 }
@@ -1,16 +1,23 @@
 //! xwdoc - API 文档浏览器的主入口点
 
-use std::error::Error;
+use std::process::ExitCode;
 use xwdoc::cli::handle_cli;
+use xwdoc::core::error::error_chain;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> ExitCode {
     // 初始化日志
     env_logger::init();
     
-    // 显示版本信息
-    println!("xwdoc {}", env!("CARGO_PKG_VERSION"));
+    // 显示版本信息（输出到标准错误，不影响 `--output json` 的结果）
+    eprintln!("xwdoc {}", env!("CARGO_PKG_VERSION"));
     
-    // 处理命令行
-    handle_cli().await
+    // 处理命令行，失败时逐行输出错误链
+    match handle_cli().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("错误: {}", error_chain(error.as_ref()));
+            ExitCode::FAILURE
+        }
+    }
 }
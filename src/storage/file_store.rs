@@ -56,6 +56,20 @@ impl Store for FileStore {
         Ok(())
     }
 
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        fs::read(self.full_path(path)).map_err(Error::Io)
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<()> {
+        let full_path = self.full_path(path);
+
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).map_err(Error::Io)?;
+        }
+
+        fs::write(&full_path, content).map_err(Error::Io)
+    }
+
     fn exists(&self, path: &str) -> Result<bool> {
         let full_path = self.full_path(path);
         Ok(full_path.exists())
@@ -0,0 +1,129 @@
+//! 容错的 JSON 文件读取
+//!
+//! 由 Windows 工具生成或复制的文档文件可能带有 UTF-8 BOM，`serde_json` 会直接
+//! 拒绝这样的文件。这里去掉 UTF-8 BOM 后再解析；UTF-16 编码和无效的 UTF-8 给出
+//! 明确的说明，解析失败时报告文件路径和出错位置在文件中的字节偏移。
+
+use crate::core::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// UTF-8 的 BOM
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 读取并解析 JSON 文件
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let bytes = std::fs::read(path).map_err(|e| Error::Storage {
+        path: path.to_path_buf(),
+        source: Box::new(Error::Io(e)),
+    })?;
+    parse_json(&bytes, path)
+}
+
+/// 解析 JSON 文件的内容，`path` 只用于错误说明
+pub fn parse_json<T: DeserializeOwned>(bytes: &[u8], path: &Path) -> Result<T> {
+    let (text, bom_len) = json_text(bytes, path)?;
+    serde_json::from_str(text).map_err(|e| Error::InvalidJson {
+        path: path.to_path_buf(),
+        offset: Some(bom_len + error_offset(text, &e)),
+        detail: e.to_string(),
+    })
+}
+
+/// 去掉 UTF-8 BOM 后的文本和 BOM 的长度，UTF-16 和无效的 UTF-8 返回错误
+fn json_text<'a>(bytes: &'a [u8], path: &Path) -> Result<(&'a str, usize)> {
+    let invalid = |offset, detail: &str| Error::InvalidJson {
+        path: path.to_path_buf(),
+        offset,
+        detail: detail.to_string(),
+    };
+    if is_utf16(bytes) {
+        return Err(invalid(None, "文件是 UTF-16 编码，需要转换为 UTF-8"));
+    }
+
+    let (bytes, bom_len) = match bytes.strip_prefix(UTF8_BOM) {
+        Some(rest) => (rest, UTF8_BOM.len()),
+        None => (bytes, 0),
+    };
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok((text, bom_len)),
+        Err(e) => Err(invalid(Some(bom_len + e.valid_up_to()), "不是有效的 UTF-8")),
+    }
+}
+
+/// 是否为 UTF-16 编码：带有 UTF-16 的 BOM，或者开头的 ASCII 字符之间夹着 0 字节
+fn is_utf16(bytes: &[u8]) -> bool {
+    match bytes {
+        [0xFF, 0xFE, ..] | [0xFE, 0xFF, ..] => true,
+        [a, b, ..] => (*a == 0) != (*b == 0),
+        _ => false,
+    }
+}
+
+/// 解析错误所在字符在文本中的字节偏移
+///
+/// `serde_json` 报告的行号和列号都从 1 开始，列号按字节计算。
+fn error_offset(text: &str, error: &serde_json::Error) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(error.line().saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (line_start + error.column().saturating_sub(1)).min(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bom_prefixed_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("meta.json");
+        std::fs::write(&path, b"\xEF\xBB\xBF{\"name\": \"CSS\"}").unwrap();
+
+        let meta: serde_json::Value = read_json(&path).unwrap();
+        assert_eq!(meta["name"], "CSS");
+    }
+
+    #[test]
+    fn test_utf16_file() {
+        let json = "{\"name\": \"CSS\"}";
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(json.encode_utf16().flat_map(u16::to_le_bytes));
+
+        let error = parse_json::<serde_json::Value>(&bytes, Path::new("docs/css/index.json")).unwrap_err();
+        assert!(matches!(error, Error::InvalidJson { offset: None, .. }));
+        assert!(error.to_string().contains("docs/css/index.json"), "{}", error);
+        assert!(error.to_string().contains("UTF-16"), "{}", error);
+
+        // 没有 BOM 的 UTF-16 按 0 字节识别
+        let bytes: Vec<u8> = json.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let error = parse_json::<serde_json::Value>(&bytes, Path::new("index.json")).unwrap_err();
+        assert!(error.to_string().contains("UTF-16"), "{}", error);
+    }
+
+    #[test]
+    fn test_truncated_file_reports_offset() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("index.json");
+        let content = "{\"entries\": [\n  {\"name\": \"color\", \"path\": \"co";
+        std::fs::write(&path, content).unwrap();
+
+        let error = read_json::<serde_json::Value>(&path).unwrap_err();
+        let Error::InvalidJson { offset: Some(offset), .. } = &error else {
+            panic!("{:?}", error);
+        };
+        // 文件在字符串中间截断，指向最后一个字节
+        assert_eq!(*offset, content.len() - 1);
+        assert!(error.to_string().contains(&path.display().to_string()), "{}", error);
+
+        // 偏移按原文件计算，包含 BOM
+        let error = parse_json::<serde_json::Value>(b"\xEF\xBB\xBF{not json", &path).unwrap_err();
+        assert!(matches!(error, Error::InvalidJson { offset: Some(4), .. }), "{:?}", error);
+
+        let error = parse_json::<serde_json::Value>(b"{\"a\": \"\xFF\"}", &path).unwrap_err();
+        assert!(matches!(error, Error::InvalidJson { offset: Some(7), .. }), "{:?}", error);
+    }
+}
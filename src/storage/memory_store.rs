@@ -0,0 +1,178 @@
+//! 内存中的存储实现
+//!
+//! 所有文件保存在一个 `HashMap` 中，不访问文件系统，用于测试和抓取器的试运行
+//! （`scraper-run --dry-run`）。路径使用 `/` 分隔，与 [`FileStore`](super::FileStore)
+//! 一样相对于存储的根目录，目录只是路径的前缀，不单独保存。
+
+use super::store::Store;
+use crate::core::error::{Error, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::sync::RwLock;
+
+/// 内存中的存储
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    files: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    /// 创建空的内存存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 文件数
+    pub fn len(&self) -> usize {
+        self.files.read().unwrap().len()
+    }
+
+    /// 是否没有任何文件
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 所有文件的路径和大小，按路径排序
+    pub fn files(&self) -> Vec<(String, usize)> {
+        let mut files: Vec<(String, usize)> = self
+            .files
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(path, content)| (path.clone(), content.len()))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// 所有文件的总大小（字节）
+    pub fn total_size(&self) -> usize {
+        self.files.read().unwrap().values().map(Vec::len).sum()
+    }
+}
+
+/// 规范化路径：去掉开头的 `./` 和首尾的 `/`
+fn normalize(path: &str) -> String {
+    path.trim_start_matches("./").trim_matches('/').to_string()
+}
+
+/// `path` 是否就是 `dir` 或在 `dir` 之下
+fn is_under(path: &str, dir: &str) -> bool {
+    dir.is_empty() || path == dir || path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+impl Store for MemoryStore {
+    fn read(&self, path: &str) -> Result<String> {
+        String::from_utf8(self.read_bytes(path)?)
+            .map_err(|_| Error::Message(format!("{} 不是有效的 UTF-8 文本", path)))
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<()> {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.files
+            .read()
+            .unwrap()
+            .get(&normalize(path))
+            .cloned()
+            .ok_or_else(|| Error::Io(io::Error::new(io::ErrorKind::NotFound, format!("{} 不存在", path))))
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<()> {
+        self.files.write().unwrap().insert(normalize(path), content.to_vec());
+        Ok(())
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        let path = normalize(path);
+        Ok(self.files.read().unwrap().keys().any(|file| is_under(file, &path)))
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>> {
+        let dir = normalize(dir);
+        let files = self.files.read().unwrap();
+        if files.contains_key(&dir) {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", dir),
+            )));
+        }
+
+        // 与 FileStore 一样返回相对于根目录的路径，子目录只列出一次
+        let prefix = if dir.is_empty() { String::new() } else { format!("{}/", dir) };
+        let entries: BTreeSet<String> = files
+            .keys()
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .map(|rest| format!("{}{}", prefix, rest.split('/').next().unwrap_or(rest)))
+            .collect();
+        Ok(entries.into_iter().collect())
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        let path = normalize(path);
+        self.files.write().unwrap().retain(|file, _| !is_under(file, &path));
+        Ok(())
+    }
+
+    fn size(&self, path: &str) -> Result<usize> {
+        Ok(self.files.read().unwrap().get(&normalize(path)).map_or(0, Vec::len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_read_write_list_delete() {
+        let store = MemoryStore::new();
+        store.write("css~1/index.json", "{}").unwrap();
+        store.write("/css~1/db.json", "{\"a\":1}").unwrap();
+        store.write_bytes("css~1/icon.png", &[0x89, 0x50, 0x4e, 0x47]).unwrap();
+        store.write("html/pages/a/index.html", "<p>A</p>").unwrap();
+
+        assert_eq!(store.read("./css~1/db.json").unwrap(), "{\"a\":1}");
+        assert_eq!(store.read_bytes("css~1/icon.png").unwrap(), [0x89, 0x50, 0x4e, 0x47]);
+        assert!(store.read("css~1/icon.png").is_err());
+        assert!(matches!(store.read("missing"), Err(Error::Io(e)) if e.kind() == io::ErrorKind::NotFound));
+
+        assert!(store.exists("css~1").unwrap());
+        assert!(store.exists("css~1/index.json").unwrap());
+        assert!(!store.exists("css").unwrap());
+        assert_eq!(store.size("css~1/db.json").unwrap(), 7);
+        assert_eq!(store.size("missing").unwrap(), 0);
+        assert_eq!(store.total_size(), 2 + 7 + 4 + 8);
+
+        assert_eq!(store.list("").unwrap(), ["css~1", "html"]);
+        assert_eq!(store.list("css~1").unwrap(), ["css~1/db.json", "css~1/icon.png", "css~1/index.json"]);
+        assert_eq!(store.list("html").unwrap(), ["html/pages"]);
+        assert!(store.list("missing").unwrap().is_empty());
+        assert!(store.list("css~1/db.json").is_err());
+
+        store.delete("html").unwrap();
+        store.delete("css~1/index.json").unwrap();
+        assert_eq!(
+            store.files(),
+            [("css~1/db.json".to_string(), 7), ("css~1/icon.png".to_string(), 4)]
+        );
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let store = Arc::new(MemoryStore::new());
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let store = store.clone();
+                std::thread::spawn(move || store.write(&format!("doc/{}.html", i), "page").unwrap())
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.list("doc").unwrap().len(), 4);
+    }
+}
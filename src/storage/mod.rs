@@ -1,7 +1,11 @@
 //! u5b58u50a8u6a21u5757
 
 pub mod file_store;
+pub mod json;
+pub mod memory_store;
+pub mod page_paths;
 pub mod store;
 
 pub use store::Store;
-pub use file_store::FileStore;
\ No newline at end of file
+pub use file_store::FileStore;
+pub use memory_store::MemoryStore;
\ No newline at end of file
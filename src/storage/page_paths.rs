@@ -0,0 +1,153 @@
+//! 页面文件路径
+//!
+//! 页面以 `<doc>/<page>/index.html` 的形式保存在文档目录中，与 `index.json`、
+//! `meta.json`、`db.json`、`icon.png` 等文档自身的文件放在一起。第一段路径与
+//! 这些保留名称相同的页面（如 `meta` 或 `icon`）保存在 `_p/` 下，避免与文档
+//! 文件冲突。转换只发生在存储层，Web 路由使用的仍是原始页面路径。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 保留名称冲突的页面所在的目录
+pub const RESERVED_PAGE_DIR: &str = "_p";
+
+/// 文档目录中保留的名称（不含扩展名），页面路径的第一段不能使用
+pub const RESERVED_NAMES: &[&str] = &[
+    "index",
+    "meta",
+    "db",
+    "entries",
+    "icon",
+    "scraper",
+    "stats",
+    "report",
+    "pages",
+    "package",
+    "_assets",
+    "_images",
+    "_custom",
+    "_raw",
+    RESERVED_PAGE_DIR,
+];
+
+/// 页面路径的第一段是否与保留名称冲突（不区分大小写，忽略扩展名）
+pub fn is_reserved(segment: &str) -> bool {
+    let stem = segment.split('.').next().unwrap_or(segment);
+    RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem))
+}
+
+/// 获取页面在文档目录中保存的相对路径
+pub fn escape_page_path(page_path: &str) -> String {
+    let page_path = page_path.trim_matches('/');
+    let first = page_path.split('/').next().unwrap_or(page_path);
+    if is_reserved(first) {
+        format!("{}/{}", RESERVED_PAGE_DIR, page_path)
+    } else {
+        page_path.to_string()
+    }
+}
+
+/// 获取页面文件在文档目录中的位置
+///
+/// 页面目录中的 `index.html` 优先，其次为 `<page>.html`。旧版本按原始路径
+/// 保存的页面仍然可以读取，可以用 `docs-validate` 找出这些页面。
+pub fn page_file(doc_dir: &Path, page_path: &str) -> PathBuf {
+    let escaped = escape_page_path(page_path);
+    let legacy = page_path.trim_matches('/');
+
+    let candidates = [escaped.as_str(), legacy];
+    for (i, rel_path) in candidates.iter().enumerate() {
+        if i > 0 && *rel_path == escaped {
+            break;
+        }
+        let page_dir = doc_dir.join(rel_path);
+        if page_dir.join("index.html").is_file() {
+            return page_dir.join("index.html");
+        }
+        let page_file = doc_dir.join(format!("{}.html", rel_path));
+        if page_file.is_file() {
+            return page_file;
+        }
+    }
+
+    doc_dir.join(format!("{}.html", escaped))
+}
+
+/// 找出文档目录中按原始路径保存、与保留名称冲突的页面
+///
+/// 返回相对于文档目录的路径，按名称排序。
+pub fn find_collisions(doc_dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut collisions = Vec::new();
+    for entry in fs::read_dir(doc_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == RESERVED_PAGE_DIR || !is_reserved(&name) {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.is_dir() {
+            // `_assets`、`_images` 等目录本身是合法的，只有其中直接保存了页面时才算冲突
+            if path.join("index.html").is_file() {
+                collisions.push(format!("{}/index.html", name));
+            }
+        } else if name.ends_with(".html") {
+            collisions.push(name);
+        }
+    }
+    collisions.sort();
+    Ok(collisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_escape_page_path() {
+        assert_eq!(escape_page_path("meta"), "_p/meta");
+        assert_eq!(escape_page_path("/Index/"), "_p/Index");
+        assert_eq!(escape_page_path("db.json"), "_p/db.json");
+        assert_eq!(escape_page_path("icon/sizes"), "_p/icon/sizes");
+        assert_eq!(escape_page_path("_p/page"), "_p/_p/page");
+        assert_eq!(escape_page_path("guide/meta"), "guide/meta");
+        assert_eq!(escape_page_path("metadata"), "metadata");
+    }
+
+    #[test]
+    fn test_page_file_reads_escaped_then_legacy_location() {
+        let doc = TempDir::new().unwrap();
+
+        // 尚未保存的页面使用转义后的位置
+        assert_eq!(page_file(doc.path(), "meta"), doc.path().join("_p/meta.html"));
+
+        fs::create_dir_all(doc.path().join("meta")).unwrap();
+        fs::write(doc.path().join("meta/index.html"), "old").unwrap();
+        assert_eq!(page_file(doc.path(), "meta"), doc.path().join("meta/index.html"));
+
+        fs::create_dir_all(doc.path().join("_p/meta")).unwrap();
+        fs::write(doc.path().join("_p/meta/index.html"), "new").unwrap();
+        assert_eq!(page_file(doc.path(), "meta"), doc.path().join("_p/meta/index.html"));
+
+        assert_eq!(page_file(doc.path(), "guide"), doc.path().join("guide.html"));
+    }
+
+    #[test]
+    fn test_find_collisions() {
+        let doc = TempDir::new().unwrap();
+        for file in ["index.json", "meta.json", "db.json", "icon.png", "meta.html", "guide.html"] {
+            fs::write(doc.path().join(file), "").unwrap();
+        }
+        for dir in ["icon", "_assets", "_p/meta", "guide"] {
+            fs::create_dir_all(doc.path().join(dir)).unwrap();
+            fs::write(doc.path().join(dir).join("index.html"), "").unwrap();
+        }
+        fs::create_dir_all(doc.path().join("_images")).unwrap();
+
+        assert_eq!(
+            find_collisions(doc.path()).unwrap(),
+            vec!["_assets/index.html", "icon/index.html", "meta.html"]
+        );
+    }
+}
@@ -10,6 +10,12 @@ pub trait Store {
     /// 写入一个文件
     fn write(&self, path: &str, content: &str) -> Result<()>;
 
+    /// 以字节读取一个文件，用于图片等二进制资源
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>>;
+
+    /// 以字节写入一个文件，用于图片等二进制资源
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<()>;
+
     /// 检查文件是否存在
     fn exists(&self, path: &str) -> Result<bool>;
 
@@ -0,0 +1,293 @@
+//! 前端资源编译与服务
+//!
+//! 编译时将资源复制为带内容哈希的文件名，并生成 `manifest.json`
+//! （逻辑名称 → 哈希文件名）。服务时哈希文件名可长期缓存，
+//! 未带哈希的开发路径不缓存。
+
+use crate::core::error::{Error, Result};
+use crate::core::hash::fnv1a;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+/// 资源清单文件名
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// 哈希文件名的缓存策略
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, immutable, max-age=31536000";
+
+/// 开发路径的缓存策略
+pub const NO_CACHE_CONTROL: &str = "no-cache";
+
+/// 两次检查资源清单文件之间的最短间隔
+pub const MANIFEST_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 资源清单，逻辑名称 → 哈希文件名
+pub type AssetManifest = BTreeMap<String, String>;
+
+/// 生成带哈希的文件名，如 `app.css` → `app-<hash>.css`
+fn hashed_name(logical: &str, hash: &str) -> String {
+    match logical.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !stem.ends_with('/') => {
+            format!("{}-{}.{}", stem, hash, ext)
+        }
+        _ => format!("{}-{}", logical, hash),
+    }
+}
+
+/// 读取资源清单，清单不存在时返回空清单
+pub fn load_manifest(output_dir: &Path) -> Result<AssetManifest> {
+    let manifest_path = output_dir.join(MANIFEST_FILENAME);
+    if !manifest_path.exists() {
+        return Ok(AssetManifest::new());
+    }
+
+    parse_manifest(&std::fs::read(&manifest_path)?, &manifest_path)
+}
+
+/// 解析资源清单文件的内容
+fn parse_manifest(content: &[u8], manifest_path: &Path) -> Result<AssetManifest> {
+    serde_json::from_slice(content)
+        .map_err(|e| Error::Message(format!("无法解析资源清单 {:?}: {}", manifest_path, e)))
+}
+
+/// 编译资源
+///
+/// 将 `source_dir` 中的文件复制到 `output_dir` 并在文件名中加入内容哈希，
+/// 然后写入资源清单。清单先写入临时文件再替换，服务中的 [`AssetStore`] 不会读到写了一半的清单。
+pub fn compile(source_dir: &Path, output_dir: &Path) -> Result<AssetManifest> {
+    if !source_dir.exists() {
+        return Err(Error::Message(format!("资源目录不存在: {:?}", source_dir)));
+    }
+
+    let mut manifest = AssetManifest::new();
+    for entry in WalkDir::new(source_dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| Error::Message(format!("无法读取资源目录: {}", e)))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry
+            .path()
+            .strip_prefix(source_dir)
+            .map_err(|e| Error::Message(format!("无效的资源路径: {}", e)))?;
+        let logical = relative.to_string_lossy().replace('\\', "/");
+
+        let content = std::fs::read(entry.path())?;
+        let hashed = hashed_name(&logical, &format!("{:016x}", fnv1a(&content)));
+
+        // 内容未变时哈希文件已存在，不需要重复写入
+        let target = output_dir.join(&hashed);
+        if !target.exists() {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&target, &content)?;
+        }
+
+        manifest.insert(logical, hashed);
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| Error::Message(format!("无法序列化资源清单: {}", e)))?;
+    let manifest_path = output_dir.join(MANIFEST_FILENAME);
+    let tmp_path = output_dir.join(format!("{}.tmp", MANIFEST_FILENAME));
+    std::fs::write(&tmp_path, manifest_json)?;
+    std::fs::rename(&tmp_path, &manifest_path)?;
+
+    Ok(manifest)
+}
+
+/// 清理资源
+///
+/// 删除输出目录中未被当前资源清单引用的文件，返回被删除的文件。
+pub fn clean(output_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let manifest = load_manifest(output_dir)?;
+    let referenced: Vec<&String> = manifest.values().collect();
+
+    let mut removed = Vec::new();
+    for entry in WalkDir::new(output_dir) {
+        let entry = entry.map_err(|e| Error::Message(format!("无法读取资源目录: {}", e)))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = match entry.path().strip_prefix(output_dir) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => continue,
+        };
+        if relative == MANIFEST_FILENAME || referenced.contains(&&relative) {
+            continue;
+        }
+
+        std::fs::remove_file(entry.path())?;
+        removed.push(entry.path().to_path_buf());
+    }
+
+    Ok(removed)
+}
+
+/// 已编译资源的服务状态
+///
+/// 资源清单在启动时加载。请求时不读取清单文件，最多每隔 [`MANIFEST_CHECK_INTERVAL`]
+/// 检查一次，清单文件内容变化后（重新编译）自动重新加载。
+pub struct AssetStore {
+    output_dir: PathBuf,
+    /// 已加载的清单和清单文件内容的哈希（文件不存在时为 None）
+    manifest: RwLock<(AssetManifest, Option<u64>)>,
+    /// 检查清单文件的间隔
+    check_interval: Duration,
+    /// 上次检查清单文件的时间
+    last_check: Mutex<Instant>,
+}
+
+impl AssetStore {
+    /// 创建资源服务状态并加载资源清单
+    pub fn new<P: Into<PathBuf>>(output_dir: P) -> Self {
+        let store = Self {
+            output_dir: output_dir.into(),
+            manifest: RwLock::new((AssetManifest::new(), None)),
+            check_interval: MANIFEST_CHECK_INTERVAL,
+            last_check: Mutex::new(Instant::now()),
+        };
+        store.reload_if_changed();
+        store
+    }
+
+    /// 设置检查清单文件的间隔
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// 距上次检查是否已超过检查间隔，超过时记录本次检查的时间
+    fn check_due(&self) -> bool {
+        let mut last_check = self.last_check.lock().unwrap();
+        if last_check.elapsed() < self.check_interval {
+            return false;
+        }
+        *last_check = Instant::now();
+        true
+    }
+
+    /// 资源清单文件内容变化后重新加载
+    ///
+    /// 比较内容哈希而不是修改时间，时间精度较低的文件系统上快速连续的重新编译也能被发现。
+    fn reload_if_changed(&self) {
+        let manifest_path = self.output_dir.join(MANIFEST_FILENAME);
+        let content = std::fs::read(&manifest_path).ok();
+        let hash = content.as_deref().map(fnv1a);
+        if self.manifest.read().unwrap().1 == hash {
+            return;
+        }
+
+        let manifest = match &content {
+            Some(content) => parse_manifest(content, &manifest_path),
+            None => Ok(AssetManifest::new()),
+        };
+        match manifest {
+            Ok(manifest) => *self.manifest.write().unwrap() = (manifest, hash),
+            Err(e) => log::warn!("无法加载资源清单: {}", e),
+        }
+    }
+
+    /// 解析请求路径，返回文件位置和缓存策略
+    ///
+    /// 哈希文件名返回长期缓存策略；逻辑名称映射到当前的哈希文件，不缓存。
+    pub fn resolve(&self, path: &str) -> Option<(PathBuf, &'static str)> {
+        if self.check_due() {
+            self.reload_if_changed();
+        }
+
+        let path = path.trim_start_matches('/');
+        let manifest = self.manifest.read().unwrap();
+        if manifest.0.values().any(|hashed| hashed == path) {
+            return Some((self.output_dir.join(path), IMMUTABLE_CACHE_CONTROL));
+        }
+        manifest
+            .0
+            .get(path)
+            .map(|hashed| (self.output_dir.join(hashed), NO_CACHE_CONTROL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compile_is_deterministic_and_clean_removes_stale_files() {
+        let source = TempDir::new().unwrap();
+        let output = TempDir::new().unwrap();
+        std::fs::create_dir_all(source.path().join("css")).unwrap();
+        std::fs::write(source.path().join("css/app.css"), "body { color: red; }").unwrap();
+
+        let first = compile(source.path(), output.path()).unwrap();
+        let second = compile(source.path(), output.path()).unwrap();
+        assert_eq!(first, second);
+        let old_name = first["css/app.css"].clone();
+        assert!(old_name.starts_with("css/app-") && old_name.ends_with(".css"));
+
+        std::fs::write(source.path().join("css/app.css"), "body { color: blue; }").unwrap();
+        let third = compile(source.path(), output.path()).unwrap();
+        let new_name = third["css/app.css"].clone();
+        assert_ne!(old_name, new_name);
+
+        let removed = clean(output.path()).unwrap();
+        assert_eq!(removed, vec![output.path().join(&old_name)]);
+        assert!(!output.path().join(&old_name).exists());
+        assert!(output.path().join(&new_name).exists());
+    }
+
+    #[test]
+    fn test_resolve_cache_policy_and_reload() {
+        let source = TempDir::new().unwrap();
+        let output = TempDir::new().unwrap();
+        std::fs::write(source.path().join("app.js"), "console.log(1);").unwrap();
+        let manifest = compile(source.path(), output.path()).unwrap();
+
+        let store = AssetStore::new(output.path()).with_check_interval(Duration::ZERO);
+        let hashed = &manifest["app.js"];
+        assert_eq!(
+            store.resolve(hashed),
+            Some((output.path().join(hashed), IMMUTABLE_CACHE_CONTROL))
+        );
+        assert_eq!(
+            store.resolve("app.js"),
+            Some((output.path().join(hashed), NO_CACHE_CONTROL))
+        );
+        assert_eq!(store.resolve("missing.js"), None);
+
+        // 重新编译后无需重启即可解析到新文件
+        std::fs::write(source.path().join("app.js"), "console.log(2);").unwrap();
+        let manifest = compile(source.path(), output.path()).unwrap();
+        assert_eq!(
+            store.resolve("app.js").unwrap().0,
+            output.path().join(&manifest["app.js"])
+        );
+    }
+
+    #[test]
+    fn test_manifest_is_not_read_on_every_request() {
+        let source = TempDir::new().unwrap();
+        let output = TempDir::new().unwrap();
+        std::fs::write(source.path().join("app.js"), "console.log(1);").unwrap();
+        let manifest = compile(source.path(), output.path()).unwrap();
+        let store = AssetStore::new(output.path()).with_check_interval(Duration::from_secs(3600));
+
+        // 检查间隔内清单文件的变化不影响解析结果
+        std::fs::write(output.path().join(MANIFEST_FILENAME), r#"{"app.js": "app-new.js"}"#).unwrap();
+        assert_eq!(
+            store.resolve("app.js"),
+            Some((output.path().join(&manifest["app.js"]), NO_CACHE_CONTROL))
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! 响应压缩
+//!
+//! 客户端接受 gzip 时压缩文本类响应。页面缓存中已有压缩副本的页面由处理器
+//! 直接返回压缩后的内容，响应带有 [`PreEncoded`] 标记，这里不会再次压缩。
+//!
+//! 压缩后的响应与原始内容是不同的表示，ETag 带有 `-gz` 后缀（见 [`gzip_etag`]）。
+//! 条件请求交给处理器之前去掉该后缀，处理器只需比较原始内容的 ETag。
+
+use axum::body::{Bytes, Full, HttpBody};
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// 小于此字节数的响应不压缩
+pub const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// 响应已由处理器编码，压缩中间件原样返回
+#[derive(Debug, Clone, Copy)]
+pub struct PreEncoded;
+
+/// 客户端是否接受 gzip 编码（`Accept-Encoding` 中有 `gzip` 或 `*`，且 q 不为 0）
+pub fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let rejected = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !rejected
+        })
+}
+
+/// gzip 压缩后的响应使用的 ETag：在原始 ETag 的引号内加上 `-gz` 后缀
+pub fn gzip_etag(etag: &str) -> String {
+    format!("\"{}{}\"", etag.trim_matches('"'), GZIP_ETAG_SUFFIX)
+}
+
+/// 压缩后的响应的 ETag 后缀
+const GZIP_ETAG_SUFFIX: &str = "-gz";
+
+/// 去掉 `If-None-Match` 中 ETag 的 `-gz` 后缀，返回是否去掉了后缀
+fn strip_gzip_validators(headers: &mut HeaderMap) -> bool {
+    let Some(value) = headers.get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let suffixed = format!("{}\"", GZIP_ETAG_SUFFIX);
+    if !value.contains(&suffixed) {
+        return false;
+    }
+    match HeaderValue::from_str(&value.replace(&suffixed, "\"")) {
+        Ok(stripped) => {
+            headers.insert(header::IF_NONE_MATCH, stripped);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// 将响应的 ETag 换成压缩后的表示的 ETag
+fn use_gzip_etag(headers: &mut HeaderMap) {
+    let etag = headers.get(header::ETAG).and_then(|value| value.to_str().ok()).map(gzip_etag);
+    if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(&etag).ok()) {
+        headers.insert(header::ETAG, value);
+    }
+}
+
+/// gzip 压缩
+pub fn gzip(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// 直接返回 gzip 压缩后的 HTML，带有 [`PreEncoded`] 标记
+pub fn pre_encoded_html(gzip: Bytes) -> Response {
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("text/html; charset=utf-8")),
+            (header::CONTENT_ENCODING, HeaderValue::from_static("gzip")),
+            (header::VARY, HeaderValue::from_static("accept-encoding")),
+        ],
+        gzip,
+    )
+        .into_response();
+    response.extensions_mut().insert(PreEncoded);
+    response
+}
+
+/// 是否压缩该类型的内容
+fn is_compressible(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    // text/event-stream 需要逐条发送，不压缩
+    (mime.starts_with("text/") && mime != "text/event-stream")
+        || matches!(mime, "application/json" | "application/javascript" | "image/svg+xml")
+}
+
+/// 压缩响应的中间件
+///
+/// 跳过客户端不接受 gzip、已经编码（带有 `Content-Encoding` 或 [`PreEncoded`]
+/// 标记）、没有响应体（HEAD、204、304）、不是文本类或小于
+/// [`MIN_COMPRESS_BYTES`] 的响应。压缩在阻塞线程池中进行，不占用运行时的工作线程。
+pub async fn gzip_responses<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let accepts = accepts_gzip(request.headers()) && request.method() != Method::HEAD;
+    // 客户端缓存的是压缩后的表示时，304 响应同样使用压缩后的 ETag
+    let gzip_validator = accepts && strip_gzip_validators(request.headers_mut());
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if gzip_validator {
+            use_gzip_etag(response.headers_mut());
+        }
+        return response;
+    }
+    if !accepts
+        || response.extensions().get::<PreEncoded>().is_some()
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+        || response.status() == StatusCode::NO_CONTENT
+        || !is_compressible(response.headers())
+    {
+        return response;
+    }
+
+    let (mut parts, mut body) = response.into_parts();
+    let mut content = Vec::new();
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) => content.extend_from_slice(&chunk),
+            Err(e) => {
+                log::error!("读取响应内容失败: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+        }
+    }
+    if content.len() < MIN_COMPRESS_BYTES {
+        return Response::from_parts(parts, axum::body::boxed(Full::from(content)));
+    }
+
+    let content = Bytes::from(content);
+    let input = content.clone();
+    let compressed = match tokio::task::spawn_blocking(move || gzip(&input)).await {
+        Ok(Ok(compressed)) => compressed,
+        Ok(Err(e)) => {
+            log::warn!("压缩响应失败: {}", e);
+            return Response::from_parts(parts, axum::body::boxed(Full::from(content)));
+        }
+        Err(e) => {
+            log::warn!("压缩任务失败: {}", e);
+            return Response::from_parts(parts, axum::body::boxed(Full::from(content)));
+        }
+    };
+    use_gzip_etag(&mut parts.headers);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    parts.headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    Response::from_parts(parts, axum::body::boxed(Full::from(compressed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_gzip() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert!(accepts_gzip(&headers("gzip, deflate, br")));
+        assert!(accepts_gzip(&headers("br;q=1.0, GZIP;q=0.5")));
+        assert!(accepts_gzip(&headers("*")));
+        assert!(!accepts_gzip(&headers("gzip;q=0, br")));
+        assert!(!accepts_gzip(&headers("identity")));
+        assert!(!accepts_gzip(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_gzip_etag_round_trip() {
+        assert_eq!(gzip_etag("\"abc+1\""), "\"abc+1-gz\"");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"abc-gz\", \"def\""));
+        assert!(strip_gzip_validators(&mut headers));
+        assert_eq!(headers[header::IF_NONE_MATCH], "\"abc\", \"def\"");
+        assert!(!strip_gzip_validators(&mut headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_static("\"abc\""));
+        use_gzip_etag(&mut headers);
+        assert_eq!(headers[header::ETAG], "\"abc-gz\"");
+    }
+}
@@ -0,0 +1,236 @@
+//! 文档自定义样式和脚本
+//!
+//! 文档目录中可以放置 `custom.css` 和 `custom.js`（由抓取器写入或手动放置），
+//! 服务页面时只为该文档注入对应的 `<link>`/`<script>` 标签。文件通过
+//! `/docs/:slug/_custom/` 提供，链接带有由修改时间生成的版本参数，版本匹配时
+//! 长期缓存，否则按 ETag 重新验证。
+//!
+//! 注入可以通过 `ServeSettings::custom_assets` 整体关闭，脚本还需要单独开启
+//! `ServeSettings::custom_js`。
+
+use crate::core::config::ServeSettings;
+use crate::docs::Documentation;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 自定义文件的 URL 前缀（相对于 `/docs/:slug/`）
+pub const CUSTOM_PREFIX: &str = "_custom";
+
+/// 自定义样式文件名
+pub const CUSTOM_CSS: &str = "custom.css";
+
+/// 自定义脚本文件名
+pub const CUSTOM_JS: &str = "custom.js";
+
+/// 带版本参数的请求使用的缓存策略
+const IMMUTABLE_CACHE: &str = "public, max-age=31536000, immutable";
+
+/// 不带版本参数（或版本已过期）的请求使用的缓存策略
+const REVALIDATE_CACHE: &str = "no-cache";
+
+/// 文档目录中的一个自定义文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomAsset {
+    /// 文件名
+    pub name: &'static str,
+    /// 文件路径
+    pub file: PathBuf,
+    /// 由修改时间和大小生成的版本
+    pub version: String,
+    /// 修改时间
+    pub modified: SystemTime,
+}
+
+impl CustomAsset {
+    /// 读取文档目录中的自定义文件，文件不存在时返回 None
+    fn load(doc: &Documentation, name: &'static str) -> Option<Self> {
+        let file = doc.dir().join(name);
+        let metadata = std::fs::metadata(&file).ok().filter(|m| m.is_file())?;
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        let secs = modified.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Some(Self {
+            name,
+            file,
+            version: format!("{:x}-{:x}", secs, metadata.len()),
+            modified,
+        })
+    }
+
+    /// 文件的 ETag
+    fn etag(&self) -> String {
+        format!("\"{}\"", self.version)
+    }
+}
+
+/// 服务设置允许注入的自定义文件名
+fn allowed_names(settings: &ServeSettings) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if settings.custom_assets {
+        names.push(CUSTOM_CSS);
+        if settings.custom_js {
+            names.push(CUSTOM_JS);
+        }
+    }
+    names
+}
+
+/// 文档中需要注入页面的自定义文件
+pub fn enabled_assets(doc: &Documentation, settings: &ServeSettings) -> Vec<CustomAsset> {
+    allowed_names(settings)
+        .into_iter()
+        .filter_map(|name| CustomAsset::load(doc, name))
+        .collect()
+}
+
+/// 生成注入页面的标签
+pub fn tags(doc: &Documentation, assets: &[CustomAsset]) -> String {
+    let mut tags = String::new();
+    for asset in assets {
+        let href = asset_url(doc, asset);
+        if asset.name == CUSTOM_CSS {
+            tags.push_str(&format!(r#"<link rel="stylesheet" href="{}">"#, href));
+        } else {
+            tags.push_str(&format!(r#"<script src="{}" defer></script>"#, href));
+        }
+    }
+    tags
+}
+
+/// 自定义文件的 URL，带有版本参数，文档有语言时附带 `locale`
+fn asset_url(doc: &Documentation, asset: &CustomAsset) -> String {
+    let mut url = format!("{}/{}/{}/{}?v={}", doc.docs_prefix(), doc.route_slug(), CUSTOM_PREFIX, asset.name, asset.version);
+    if let Some(locale) = &doc.locale {
+        url.push_str("&locale=");
+        url.push_str(locale);
+    }
+    url
+}
+
+/// 将标签注入页面：有 `</head>` 时放在其前面，否则放在页面开头
+pub fn inject(html: &str, tags: &str) -> String {
+    if tags.is_empty() {
+        return html.to_string();
+    }
+    match find_ignore_case(html, "</head>") {
+        Some(index) => format!("{}{}{}", &html[..index], tags, &html[index..]),
+        None => format!("{}{}", tags, html),
+    }
+}
+
+/// 页面注入自定义文件后的 ETag，自定义文件变化时页面的 ETag 也随之变化
+pub fn page_etag(etag: &str, assets: &[CustomAsset]) -> String {
+    if assets.is_empty() {
+        return etag.to_string();
+    }
+    let versions: Vec<&str> = assets.iter().map(|asset| asset.version.as_str()).collect();
+    format!("\"{}+{}\"", etag.trim_matches('"'), versions.join("+"))
+}
+
+/// 提供自定义文件
+///
+/// 未开启注入、脚本未单独开启或文件不存在时返回 404。
+pub fn serve(
+    doc: &Documentation,
+    settings: &ServeSettings,
+    name: &str,
+    version: Option<&str>,
+    headers: &HeaderMap,
+) -> Response {
+    let asset = allowed_names(settings)
+        .into_iter()
+        .find(|allowed| *allowed == name)
+        .and_then(|name| CustomAsset::load(doc, name));
+    let asset = match asset {
+        Some(asset) => asset,
+        None => return (StatusCode::NOT_FOUND, "Custom asset not found").into_response(),
+    };
+
+    let etag = asset.etag();
+    let cache_control = if version == Some(asset.version.as_str()) {
+        IMMUTABLE_CACHE
+    } else {
+        REVALIDATE_CACHE
+    };
+    let last_modified = chrono::DateTime::<chrono::Utc>::from(asset.modified)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == etag);
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, cache_control.to_string())],
+        )
+            .into_response();
+    }
+
+    match std::fs::read(&asset.file) {
+        Ok(content) => {
+            let mut response = (
+                [
+                    (header::CONTENT_TYPE, content_type_for(asset.name)),
+                    (header::CACHE_CONTROL, cache_control),
+                ],
+                content,
+            )
+                .into_response();
+            let response_headers = response.headers_mut();
+            if let Ok(etag) = HeaderValue::from_str(&etag) {
+                response_headers.insert(header::ETAG, etag);
+            }
+            if let Ok(last_modified) = HeaderValue::from_str(&last_modified) {
+                response_headers.insert(header::LAST_MODIFIED, last_modified);
+            }
+            response
+        }
+        Err(_) => (StatusCode::NOT_FOUND, "Custom asset not found").into_response(),
+    }
+}
+
+/// 自定义文件的 Content-Type
+fn content_type_for(name: &str) -> &'static str {
+    if name == CUSTOM_CSS {
+        "text/css; charset=utf-8"
+    } else {
+        "application/javascript; charset=utf-8"
+    }
+}
+
+/// 不区分大小写地查找 ASCII 字符串
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.to_ascii_lowercase().find(needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_before_head_or_at_start() {
+        let tags = r#"<link rel="stylesheet" href="/x.css">"#;
+        assert_eq!(
+            inject("<html><HEAD><title>t</title></HEAD><body></body></html>", tags),
+            format!("<html><HEAD><title>t</title>{}</HEAD><body></body></html>", tags)
+        );
+        assert_eq!(inject("<p>fragment</p>", tags), format!("{}<p>fragment</p>", tags));
+        assert_eq!(inject("<p>fragment</p>", ""), "<p>fragment</p>");
+    }
+
+    #[test]
+    fn test_page_etag_includes_asset_versions() {
+        assert_eq!(page_etag("\"abc\"", &[]), "\"abc\"");
+
+        let asset = CustomAsset {
+            name: CUSTOM_CSS,
+            file: PathBuf::from("custom.css"),
+            version: "10-2".to_string(),
+            modified: UNIX_EPOCH,
+        };
+        assert_eq!(page_etag("\"abc\"", &[asset]), "\"abc+10-2\"");
+    }
+}
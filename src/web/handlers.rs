@@ -1,47 +1,163 @@
 //! u8bf7u6c42u5904u7406u7a0b
 
-use axum::response::{IntoResponse, Response, Html};
-use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response, Html, Redirect};
+use axum::extract::{OriginalUri, Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, Request, StatusCode, Uri};
+use axum::middleware::Next;
 use axum::Json;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
-use crate::docs::DocRegistry;
+use crate::core::doc::{EntryIndex, DB_FILENAME, INDEX_FILENAME};
+use crate::core::error::ResultExt;
+use crate::core::filters::markup::escape_html;
+use crate::core::format::{format_timestamp, TimeDisplay};
+use crate::core::hash::fnv1a;
+use crate::core::scraper::JobRegistry;
+use crate::core::index_entry::{FullIndex, TypeMeta};
+use crate::core::manifest::{DocSpec, Manifest};
+use crate::core::markdown;
+use crate::core::page_db::{PageDb, StoredPage};
+use crate::core::types::{DocSlug, PageKey};
+use crate::docs::search::{self as doc_search, SearchFilter, SnippetCache};
+use crate::docs::search_history::SearchHistory;
+use crate::docs::registry::{versions_among, MANIFEST_FILENAME};
+use crate::docs::{DocRegistry, Documentation};
 use crate::core::config::Config;
+use super::assets::AssetStore;
+use super::compression;
+use super::custom_assets;
+use super::page_cache::PageCache;
+use super::registry_reloader::RegistryReloader;
+
+/// 文档列表中过滤后、分页前的文档总数
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+/// 页面响应中列出同一文档已安装的各版本
+const AVAILABLE_VERSIONS_HEADER: &str = "x-available-versions";
+/// 搜索默认返回的结果数
+const SEARCH_DEFAULT_LIMIT: usize = 50;
+/// 搜索最多返回的结果数
+const SEARCH_MAX_LIMIT: usize = 500;
+/// 联想搜索默认返回的结果数
+const TYPEAHEAD_DEFAULT_LIMIT: usize = 20;
+/// 联想搜索最多返回的结果数
+const TYPEAHEAD_MAX_LIMIT: usize = 100;
+/// 联想搜索中每个文档优先占用的结果数
+const TYPEAHEAD_PER_DOC: usize = 5;
+/// 页面不存在时列出的相近页面数
+const SIMILAR_PAGES_LIMIT: usize = 5;
 
 /// u52a8u6001u72b6u6001
 pub struct AppState {
     pub config: Config,
-    pub doc_registry: Arc<DocRegistry>,
+    pub doc_registry: Arc<RwLock<DocRegistry>>,
+    /// 文档目录变化后的增量重新加载
+    pub reloader: Arc<RegistryReloader>,
+    /// 页面缓存
+    pub page_cache: Arc<PageCache>,
+    /// 搜索结果摘要缓存
+    pub snippet_cache: Arc<SnippetCache>,
+    /// 服务启动时间
+    pub started_at: Instant,
+    /// 编译后的前端资源
+    pub assets: Arc<AssetStore>,
+    /// 后台抓取任务
+    pub jobs: Arc<JobRegistry>,
+    /// 搜索历史，配置中没有开启时为 None
+    pub search_history: Option<Arc<SearchHistory>>,
 }
 
-/// u9996u9875
-pub async fn index() -> Html<&'static str> {
-    Html(r#"<!DOCTYPE html>
+/// 首页页面的公共部分
+const PAGE_HEAD: &str = r#"<!DOCTYPE html>
 <html>
 <head>
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>xwdoc</title>
+    <title>{title}</title>
     <style>
         body { font-family: system-ui, -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif; line-height: 1.6; margin: 0; padding: 20px; color: #333; }
         h1 { margin-top: 0; }
         a { color: #3490dc; text-decoration: none; }
         a:hover { text-decoration: underline; }
+        table { border-collapse: collapse; margin-bottom: 1.5em; }
+        th, td { text-align: left; padding: 2px 12px 2px 0; }
+        .muted { color: #888; }
     </style>
 </head>
 <body>
-    <h1>xwdoc</h1>
-    <p>Welcome to the Rust implementation of xwdoc API Documentation Browser.</p>
-    <p>This is a work in progress. Features:</p>
-    <ul>
-        <li><a href="/docs.json">Documentation list</a></li>
-        <li><a href="/search?q=html">Search</a></li>
-    </ul>
-</body>
-</html>
-"#)
+"#;
+
+/// 没有记录类型的文档在首页中的分组
+const UNTYPED_DOC_GROUP: &str = "other";
+
+/// u9996u9875
+///
+/// 按类型列出注册表中已安装的文档，每个文档链接到 `/docs/<slug>/`。
+pub async fn index(State(state): State<Arc<AppState>>) -> Html<String> {
+    let registry = state.doc_registry.read().unwrap();
+    Html(render_index(&registry.visible(None), state.config.time_display))
+}
+
+/// 首页的 HTML，文档按清单的类型分组，组内按名称排序
+fn render_index(docs: &[&Documentation], time_display: TimeDisplay) -> String {
+    // 同一文档的各语言版本只列出一次
+    let docs: Vec<&Documentation> = docs_list_items(docs).into_iter().map(|(doc, _)| doc).collect();
+    let mut manifest = Manifest::new();
+    for doc in &docs {
+        manifest.add(DocSpec {
+            name: doc.name.clone(),
+            slug: doc.route_slug(),
+            doc_type: doc.doc_type.clone().unwrap_or_else(|| UNTYPED_DOC_GROUP.to_string()),
+            version: Some(doc.version.clone()).filter(|v| !v.is_empty()),
+            release: Some(doc.release.clone()).filter(|r| !r.is_empty()),
+            links: None,
+            mtime: doc.mtime,
+            db_size: doc.db_size,
+        });
+    }
+
+    let mut html = PAGE_HEAD.replace("{title}", "xwdoc");
+    html.push_str("    <h1>xwdoc</h1>\n");
+    html.push_str("    <p><a href=\"/search?q=\">Search</a> · <a href=\"/docs.json\">docs.json</a></p>\n");
+    if docs.is_empty() {
+        html.push_str("    <p class=\"muted\">No documentation installed. Run <code>xwdoc docs-download &lt;doc&gt;</code> to add some.</p>\n");
+    }
+
+    let mut groups: Vec<(String, Vec<DocSpec>)> = manifest.docs_by_type().into_iter().collect();
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    for (doc_type, specs) in groups {
+        html.push_str(&format!("    <h2>{}</h2>\n", escape_html(&doc_type)));
+        html.push_str("    <table>\n        <tr><th>Name</th><th>Version</th><th>Release</th><th>Entries</th><th>Updated</th></tr>\n");
+        for spec in specs {
+            let Some(doc) = docs.iter().find(|doc| doc.route_slug() == spec.slug) else {
+                continue;
+            };
+            let updated = if spec.mtime == 0 {
+                "-".to_string()
+            } else {
+                format_timestamp(spec.mtime as i64, time_display)
+            };
+            html.push_str(&format!(
+                "        <tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&doc_home_url(doc)),
+                escape_html(&spec.name),
+                escape_html(spec.version.as_deref().unwrap_or("-")),
+                escape_html(spec.release.as_deref().unwrap_or("-")),
+                doc.entries.len(),
+                updated,
+            ));
+        }
+        html.push_str("    </table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// 文档首页（类型列表）的地址，如 `/docs/css/`
+fn doc_home_url(doc: &Documentation) -> String {
+    format!("{}/{}/", doc.docs_prefix(), doc.route_slug())
 }
 
 /// u5fc3u8df3u68c0u6d4b
@@ -49,59 +165,621 @@ pub async fn ping() -> &'static str {
     "pong"
 }
 
-/// u641cu7d22
+/// 搜索
+///
+/// 在所有已加载文档的条目中搜索，按相关性分数从高到低返回匹配的条目，
+/// 附带条目类型（及其别名 `type_slug`）和所属文档的名称、版本。`limit` 限制
+/// 结果数（默认 50），`snippets=1` 时同时返回目标页面的文本摘要。开启搜索历史时，
+/// 常选中的条目在匹配程度相同的结果中排在前面。
+///
+/// `doc` 将搜索限定到一个文档（`<slug>` 或 `<slug>~<version>`），文档不存在时
+/// 返回 404；`type` 只返回该类型的条目，与类型名称或别名比较，不区分大小写。
 pub async fn search(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Query(params): Query<HashMap<String, String>>
 ) -> Response {
-    // u6240u6709u6587u6863u641cu7d22
+    search_response(&state, None, &params)
+}
+
+/// 在命名空间和全局命名空间的文档中搜索（`/t/:namespace/search`）
+///
+/// 命名空间中的文档覆盖相同 slug 和版本的全局文档，参数与 [`search`] 相同。
+pub async fn namespaced_search(
+    State(state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if let Some(response) = unknown_namespace(&state, &namespace) {
+        return response;
+    }
+    search_response(&state, Some(&namespace), &params)
+}
+
+/// 搜索的响应，`namespace` 为 None 时只搜索全局文档
+fn search_response(state: &AppState, namespace: Option<&str>, params: &HashMap<String, String>) -> Response {
     let query = params.get("q").cloned().unwrap_or_default();
     if query.is_empty() {
         return (StatusCode::BAD_REQUEST, "Missing query parameter 'q'").into_response();
     }
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+        .min(SEARCH_MAX_LIMIT);
+
+    let mut filter = SearchFilter::new();
+    if let Some(doc) = params.get("doc").filter(|doc| !doc.is_empty()) {
+        filter = filter.with_doc(doc);
+    }
+    if let Some(entry_type) = params.get("type").filter(|entry_type| !entry_type.is_empty()) {
+        filter = filter.with_type(entry_type);
+    }
+
+    let registry = state.doc_registry.read().unwrap();
+    if let Some(doc) = &filter.doc {
+        if !registry.visible(namespace).into_iter().any(|d| filter.matches_doc(d)) {
+            return (StatusCode::NOT_FOUND, format!("Documentation '{}' not found", doc)).into_response();
+        }
+    }
 
-    // u5728u771fu6b63u5b9eu73b0u4e2d uff0cu8fd9u91ccu4f1au641cu7d22u6587u6863u7d22u5f15
-    let results: Vec<serde_json::Value> = vec![]; // u5360u4f4du7b26
+    let snippets = wants_snippets(&state.config, params).then_some(state.snippet_cache.as_ref());
+    let results = doc_search::search_with(
+        &registry,
+        namespace,
+        &query,
+        limit,
+        snippets,
+        state.search_history.as_deref(),
+        &filter,
+        |doc, page_path| doc_search::load_page(&state.page_cache, doc, page_path),
+    );
 
     Json(results).into_response()
 }
 
+/// 选中的搜索结果
+#[derive(Debug, serde::Deserialize)]
+pub struct SearchSelection {
+    /// 文档路径（搜索结果中的 `doc`）
+    pub doc: String,
+    /// 条目路径（搜索结果中的 `path`）
+    pub path: String,
+}
+
+/// 记录选中的搜索结果
+///
+/// 由前端在用户选中搜索结果时调用。没有开启搜索历史时返回 404，只读模式下
+/// 返回 403。
+pub async fn search_select(
+    State(state): State<Arc<AppState>>,
+    Json(selection): Json<SearchSelection>,
+) -> Response {
+    let history = match writable_search_history(&state) {
+        Ok(history) => history,
+        Err(response) => return response,
+    };
+    if selection.doc.is_empty() || selection.path.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing 'doc' or 'path'").into_response();
+    }
+    // 写入历史文件是阻塞的，不在运行时的工作线程中进行
+    let now = chrono::Utc::now().timestamp();
+    let recorded = tokio::task::spawn_blocking(move || history.record(&selection.doc, &selection.path, now)).await;
+    match recorded {
+        Ok(Ok(())) => StatusCode::NO_CONTENT.into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 清空搜索历史
+pub async fn clear_search_history(State(state): State<Arc<AppState>>) -> Response {
+    let history = match writable_search_history(&state) {
+        Ok(history) => history,
+        Err(response) => return response,
+    };
+    match history.clear() {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 可以修改的搜索历史，没有开启或只读模式下返回对应的错误响应
+fn writable_search_history(state: &AppState) -> Result<Arc<SearchHistory>, Response> {
+    if state.config.serve.read_only {
+        return Err((StatusCode::FORBIDDEN, "Server is read-only").into_response());
+    }
+    state
+        .search_history
+        .clone()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Search history is disabled").into_response())
+}
+
+/// 请求参数中是否要求返回摘要，配置中关闭摘要时总是 false
+fn wants_snippets(config: &Config, params: &HashMap<String, String>) -> bool {
+    config.serve.search_snippets
+        && params
+            .get("snippets")
+            .is_some_and(|value| matches!(value.as_str(), "1" | "true"))
+}
+
+/// 文档对搜索引擎索引的单独设置，由文档路由放入响应扩展中
+#[derive(Debug, Clone, Copy)]
+pub struct DocNoindex(pub bool);
+
+/// 为响应添加 `X-Robots-Tag` 头
+///
+/// 文档路由可通过 `DocNoindex` 覆盖全局的 `serve.noindex` 设置。
+pub async fn robots_tag<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let mut response = next.run(request).await;
+    let noindex = response
+        .extensions()
+        .get::<DocNoindex>()
+        .map_or(state.config.serve.noindex, |doc| doc.0);
+    if noindex {
+        response
+            .headers_mut()
+            .insert("x-robots-tag", HeaderValue::from_static("noindex"));
+    }
+    response
+}
+
+/// robots.txt
+pub async fn robots_txt(State(state): State<Arc<AppState>>) -> &'static str {
+    if state.config.serve.noindex {
+        "User-agent: *\nDisallow: /\n"
+    } else {
+        "User-agent: *\nDisallow:\n"
+    }
+}
+
+/// 健康检查
+///
+/// 只读取内存中的计数，不访问磁盘，适合监控系统频繁探测。
+/// 报告页面缓存、摘要缓存的用量、命中率和淘汰次数，以及常驻内存的文档索引大小。
+pub async fn healthz(State(state): State<Arc<AppState>>) -> Response {
+    let (docs, index_entries, index_bytes) = {
+        let registry = state.doc_registry.read().unwrap();
+        let docs = registry.all();
+        (
+            docs.len(),
+            docs.iter().map(|doc| doc.entries.len()).sum::<usize>(),
+            docs.iter().map(Documentation::index_memory).sum::<usize>(),
+        )
+    };
+    let (snippet_hits, snippet_misses, snippet_evictions) = state.snippet_cache.counters();
+    let body = serde_json::json!({
+        "status": "ok",
+        "read_only": state.config.serve.read_only,
+        "uptime_seconds": state.started_at.elapsed().as_secs(),
+        "docs": docs,
+        "page_cache": state.page_cache.stats(),
+        "snippet_cache": {
+            "entries": state.snippet_cache.len(),
+            "bytes": state.snippet_cache.memory_usage(),
+            "hits": snippet_hits,
+            "misses": snippet_misses,
+            "evictions": snippet_evictions,
+        },
+        "index": {
+            "entries": index_entries,
+            "bytes": index_bytes,
+        },
+        "scrape_jobs": crate::core::scraper::jobs::in_flight(),
+        "registry_reload": state.reloader.stats(),
+    });
+
+    ([(header::CACHE_CONTROL, "no-store")], Json(body)).into_response()
+}
+
+/// 后台抓取任务的状态
+pub async fn job_status(State(state): State<Arc<AppState>>, Path(id): Path<u64>) -> Response {
+    match state.jobs.status(id) {
+        Some(status) => Json(status).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("Job {} not found", id)).into_response(),
+    }
+}
+
+/// 取消后台抓取任务
+///
+/// 正在运行的任务转为 `cancelled` 状态，抓取器处理完当前页面后停止并保留
+/// 抓取日志；已经结束的任务返回 409 和当前状态。只读模式下返回 403。
+pub async fn cancel_job(State(state): State<Arc<AppState>>, Path(id): Path<u64>) -> Response {
+    if state.config.serve.read_only {
+        return (StatusCode::FORBIDDEN, "Server is read-only").into_response();
+    }
+    match state.jobs.cancel(id) {
+        Some(Ok(status)) => Json(status).into_response(),
+        Some(Err(status)) => (StatusCode::CONFLICT, Json(status)).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("Job {} not found", id)).into_response(),
+    }
+}
+
+/// 联想搜索
+///
+/// 按文档分组返回结果，每个文档先最多占用 `TYPEAHEAD_PER_DOC` 个位置，
+/// 剩余位置再按匹配程度补齐，前端无需额外请求即可渲染。
+pub async fn typeahead(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>
+) -> Response {
+    if !state.config.serve.typeahead {
+        return (StatusCode::NOT_FOUND, "Typeahead is disabled").into_response();
+    }
+    let query = params.get("q").cloned().unwrap_or_default();
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(TYPEAHEAD_DEFAULT_LIMIT)
+        .min(TYPEAHEAD_MAX_LIMIT);
+
+    let snippets = wants_snippets(&state.config, &params).then_some((state.snippet_cache.as_ref(), state.page_cache.as_ref()));
+    let groups = typeahead_groups(&state.doc_registry.read().unwrap(), &query, limit, snippets);
+    let body = serde_json::json!({
+        "query": query,
+        "results": groups,
+    });
+
+    // 结果随查询变化，不做缓存
+    ([(header::CACHE_CONTROL, "no-store")], Json(body)).into_response()
+}
+
+/// 前端键盘导航所需的元数据
+///
+/// 返回配置中的搜索别名、默认文档顺序、每个文档的条目数和语言版本，以及
+/// 服务器的功能开关（均由配置决定）。ETag 由内容和 manifest.json 的修改时间
+/// 生成，配置或文档变化后随之改变。
+pub async fn api_meta(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let body = meta_payload(&state.config, &state.doc_registry.read().unwrap());
+    let manifest_mtime = std::fs::metadata(state.config.writable_root().join(MANIFEST_FILENAME))
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs());
+    let etag = format!("\"{:x}-{:x}\"", fnv1a(body.to_string().as_bytes()), manifest_mtime);
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == etag);
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+    ([(header::ETAG, etag)], Json(body)).into_response()
+}
+
+/// 生成 `/api/meta` 的内容，默认文档按配置中的顺序排在前面，其余按别名排序
+fn meta_payload(config: &Config, registry: &DocRegistry) -> serde_json::Value {
+    let default_rank = |slug: &str| {
+        config
+            .default_docs
+            .iter()
+            .position(|doc| doc == slug)
+            .unwrap_or(usize::MAX)
+    };
+    let mut docs: Vec<&Documentation> = docs_list_items(&registry.visible(None)).into_iter().map(|(doc, _)| doc).collect();
+    docs.sort_by(|a, b| {
+        (default_rank(&a.slug), &a.slug, &a.version).cmp(&(default_rank(&b.slug), &b.slug, &b.version))
+    });
+    let docs: Vec<serde_json::Value> = docs
+        .into_iter()
+        .map(|doc| {
+            let mut locales: Vec<&str> = registry
+                .locale_variants(&doc.slug, &doc.version)
+                .iter()
+                .map(|variant| variant.locale_or_default())
+                .collect();
+            locales.sort();
+            serde_json::json!({
+                "slug": doc.slug,
+                "path": doc.path(),
+                "name": doc.name,
+                "version": doc.version,
+                "entries": doc.entries.len(),
+                "locales": locales,
+                "default": default_rank(&doc.slug) != usize::MAX,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "aliases": config.aliases,
+        "default_docs": config.default_docs,
+        "docs": docs,
+        "capabilities": {
+            "search_snippets": config.serve.search_snippets,
+            "typeahead": config.serve.typeahead,
+            "jobs_api": !config.serve.read_only,
+            "search_history": config.search.history && !config.serve.read_only,
+            "read_only": config.serve.read_only,
+        },
+    })
+}
+
+/// 将搜索结果按文档分组并应用每个文档的数量上限，`snippets` 不为空时附带摘要
+fn typeahead_groups(
+    registry: &DocRegistry,
+    query: &str,
+    limit: usize,
+    snippets: Option<(&SnippetCache, &PageCache)>,
+) -> Vec<serde_json::Value> {
+    let ranked = registry.search(query, usize::MAX);
+
+    // 第一轮：每个文档最多取 TYPEAHEAD_PER_DOC 个结果
+    let mut picked = vec![false; ranked.len()];
+    let mut per_doc: HashMap<String, usize> = HashMap::new();
+    let mut total = 0;
+    for (i, (doc, _)) in ranked.iter().enumerate() {
+        if total >= limit {
+            break;
+        }
+        let count = per_doc.entry(doc.path()).or_insert(0);
+        if *count < TYPEAHEAD_PER_DOC {
+            *count += 1;
+            picked[i] = true;
+            total += 1;
+        }
+    }
+
+    // 第二轮：用剩余结果补齐
+    for flag in picked.iter_mut() {
+        if total >= limit {
+            break;
+        }
+        if !*flag {
+            *flag = true;
+            total += 1;
+        }
+    }
+
+    // 按文档首次出现的顺序分组
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, (serde_json::Value, Vec<serde_json::Value>)> = HashMap::new();
+    for (i, (doc, entry)) in ranked.iter().enumerate() {
+        if !picked[i] {
+            continue;
+        }
+        let key = doc.path();
+        let group = groups.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            (typeahead_doc(doc), Vec::new())
+        });
+        let mut item = serde_json::json!({
+            "name": entry.name,
+            "type": entry.entry_type,
+            "path": entry.path,
+        });
+        if let Some((cache, page_cache)) = snippets {
+            let page_path = entry.path.split('#').next().unwrap_or(&entry.path);
+            item["snippet"] = cache
+                .snippet(doc, page_path, |doc, page_path| doc_search::load_page(page_cache, doc, page_path))
+                .into();
+        }
+        group.1.push(item);
+    }
+
+    // 查询与文档名称或别名完全相同时，该文档排在最前，链接到其落地页
+    let query = query.trim().to_lowercase();
+    let mut doc_matches: Vec<String> = Vec::new();
+    for doc in registry.all() {
+        if doc.name.to_lowercase() != query && doc.slug.to_lowercase() != query {
+            continue;
+        }
+        let key = doc.path();
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| (typeahead_doc(doc), Vec::new()));
+        order.retain(|k| k != &key);
+        doc_matches.push(key);
+    }
+
+    doc_matches
+        .into_iter()
+        .chain(order)
+        .filter_map(|key| groups.remove(&key))
+        .map(|(doc, entries)| serde_json::json!({ "doc": doc, "entries": entries }))
+        .collect()
+}
+
+/// 联想搜索结果中的文档信息
+fn typeahead_doc(doc: &Documentation) -> serde_json::Value {
+    serde_json::json!({
+        "name": doc.name,
+        "slug": doc.slug,
+        "version": doc.version,
+        "icon": doc.has_icon,
+        "url": doc.landing_url(),
+    })
+}
+
 /// u83b7u53d6u6240u6709u6587u6863u5217u8868
-pub async fn docs_list(State(_state): State<Arc<AppState>>) -> Response {
+///
+/// 可选的查询参数（都在内存中的注册表上处理，不读取磁盘）：
+/// - `q`：名称或标识包含该字符串（不区分大小写）
+/// - `type`：含有该类型条目的文档
+/// - `offset` / `limit`：分页
+/// - `fields`：逗号分隔的字段列表，只返回这些字段
+///
+/// 过滤后、分页前的文档总数在 `X-Total-Count` 响应头中。没有参数时返回完整列表。
+pub async fn docs_list(
+    State(_state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    docs_list_response(&_state, None, &params)
+}
+
+/// 获取命名空间中可见的文档列表（`/t/:namespace/docs.json`）
+///
+/// 包括命名空间中的文档和没有被它们覆盖的全局文档，参数与 [`docs_list`] 相同。
+pub async fn namespaced_docs_list(
+    State(state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if let Some(response) = unknown_namespace(&state, &namespace) {
+        return response;
+    }
+    docs_list_response(&state, Some(&namespace), &params)
+}
+
+/// 文档列表的响应，`namespace` 为 None 时只列出全局文档
+fn docs_list_response(state: &AppState, namespace: Option<&str>, params: &HashMap<String, String>) -> Response {
     // u8fd4u56deu6240u6709u53efu7528u6587u6863u7684u5217u8868
-    let docs = _state.doc_registry.all();
+    let registry = state.doc_registry.read().unwrap();
+    let query = params.get("q").map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+    let entry_type = params.get("type").map(|t| t.trim()).filter(|t| !t.is_empty());
 
-    // u5c06u6587u6863u8f6cu6362u4e3au53efu5e8fu5217u5316u7684u683cu5f0f
-    let result: Vec<serde_json::Value> = docs.iter().map(|doc| {
-        serde_json::json!({
+    let docs: Vec<serde_json::Value> = docs_list_items(&registry.visible(namespace))
+        .into_iter()
+        .filter(|(doc, _)| {
+            query.as_ref().is_none_or(|q| {
+                doc.name.to_lowercase().contains(q.as_str()) || doc.slug.to_lowercase().contains(q.as_str())
+            })
+        })
+        .filter(|(doc, _)| entry_type.is_none_or(|t| has_entry_type(doc, t)))
+        .map(|(_, item)| item)
+        .collect();
+    let total = docs.len();
+
+    let offset = params.get("offset").and_then(|o| o.parse::<usize>().ok()).unwrap_or(0);
+    let limit = params.get("limit").and_then(|l| l.parse::<usize>().ok()).unwrap_or(usize::MAX);
+    let fields: Option<Vec<&str>> = params
+        .get("fields")
+        .map(|fields| fields.split(',').map(str::trim).filter(|f| !f.is_empty()).collect());
+    let docs: Vec<serde_json::Value> = docs
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|mut item| {
+            if let (Some(fields), Some(object)) = (&fields, item.as_object_mut()) {
+                object.retain(|key, _| fields.contains(&key.as_str()));
+            }
+            item
+        })
+        .collect();
+
+    ([(TOTAL_COUNT_HEADER, total.to_string())], Json(docs)).into_response()
+}
+
+/// 没有加载该命名空间时的 404 响应
+fn unknown_namespace(state: &AppState, namespace: &str) -> Option<Response> {
+    if state.doc_registry.read().unwrap().has_namespace(namespace) {
+        return None;
+    }
+    Some((StatusCode::NOT_FOUND, format!("Namespace '{}' not found", namespace)).into_response())
+}
+
+/// 文档是否含有指定类型（不区分大小写）的条目
+fn has_entry_type(doc: &Documentation, entry_type: &str) -> bool {
+    doc.types.iter().any(|t| t.name.eq_ignore_ascii_case(entry_type))
+        || doc.entries.iter().any(|e| e.entry_type.eq_ignore_ascii_case(entry_type))
+}
+
+/// 文档列表的每一项及其代表文档
+fn docs_list_items<'a>(docs: &[&'a Documentation]) -> Vec<(&'a Documentation, serde_json::Value)> {
+    // 同一文档的各语言版本合并为一项，默认语言的版本优先作为代表
+    let mut seen: Vec<(&str, &str)> = Vec::new();
+    docs.iter().copied().filter_map(|doc| {
+        if seen.contains(&(doc.slug.as_str(), doc.version.as_str())) {
+            return None;
+        }
+        seen.push((doc.slug.as_str(), doc.version.as_str()));
+
+        let variants: Vec<&Documentation> = docs
+            .iter()
+            .copied()
+            .filter(|d| d.slug == doc.slug && d.version == doc.version)
+            .collect();
+        let doc = variants.iter().find(|d| d.locale.is_none()).copied().unwrap_or(doc);
+        let mut locales: Vec<&str> = variants.iter().map(|d| d.locale_or_default()).collect();
+        locales.sort();
+
+        Some((doc, serde_json::json!({
             "name": doc.name,
             "slug": doc.slug,
             "version": doc.version,
             "release": doc.release,
             "mtime": doc.mtime,
             "db_size": doc.db_size,
-            "index_size": doc.index_size
-        })
-    }).collect();
-
-    Json(result).into_response()
+            "index_size": doc.index_size,
+            "quality": doc.quality,
+            "attribution": doc.attribution,
+            "license": doc.license,
+            "locales": locales
+        })))
+    }).collect()
 }
 
 /// u83b7u53d6u7279u5b9au6587u6863u7684u7d22u5f15
+///
+/// 返回注册表中缓存的条目和类型列表，设置了落地页面的文档也一样；
+/// 落地页面的重定向由 HTML 首页（`/docs/:doc/`）负责。响应带有由文档修改时间生成的 ETag；文档已注册但 index.json 已被删除时
+/// 返回 404 和 `{"error": "index_missing"}`，与文档不存在的 404 区分开。
 pub async fn doc_index(
     State(state): State<Arc<AppState>>,
-    Path(doc_slug): Path<String>
+    Path(doc_slug): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    doc_index_response(&state, None, &doc_slug, &params, &uri, &headers)
+}
+
+/// 获取命名空间中文档的索引（`/t/:namespace/docs/:doc`）
+///
+/// 命名空间中没有该文档时使用全局命名空间中的文档。
+pub async fn namespaced_doc_index(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, doc_slug)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(response) = unknown_namespace(&state, &namespace) {
+        return response;
+    }
+    doc_index_response(&state, Some(&namespace), &doc_slug, &params, &uri, &headers)
+}
+
+/// 文档索引的响应，`namespace` 为 None 时只查找全局文档
+fn doc_index_response(
+    state: &AppState,
+    namespace: Option<&str>,
+    doc_slug: &str,
+    params: &HashMap<String, String>,
+    uri: &Uri,
+    headers: &HeaderMap,
 ) -> Response {
     // u68c0u67e5u6587u6863u662fu5426u5b58u5728
-    match state.doc_registry.find(&doc_slug) {
-        Some(_doc) => {
-            // u5728u771fu6b63u5b9eu73b0u4e2du FF0Cu4f1au8fd4u56deu6587u6863u7d22u5f15
-            let index = serde_json::json!({
-                "entries": [],
-                "types": []
-            });
+    let registry = state.doc_registry.read().unwrap();
+    match registry.find_localized_in(namespace, DocSlug::new(doc_slug).as_str(), &requested_locales(params, headers)) {
+        Some(doc) => {
+            if let Some(redirect) = canonical_doc_redirect(doc_slug, uri) {
+                return with_doc_headers(redirect, doc);
+            }
 
-            Json(index).into_response()
+            if !doc.dir().join(INDEX_FILENAME).exists() {
+                let body = serde_json::json!({
+                    "error": "index_missing",
+                    "doc": doc.path(),
+                    "message": format!("Index for documentation '{}' is missing", doc.path()),
+                });
+                return with_doc_headers((StatusCode::NOT_FOUND, Json(body)).into_response(), doc);
+            }
+
+            let etag = index_etag(doc);
+            let not_modified = headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value == etag);
+            if not_modified {
+                return with_doc_headers((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response(), doc);
+            }
+
+            with_doc_headers(([(header::ETAG, etag)], Json(full_index(doc))).into_response(), doc)
         },
         None => {
             (StatusCode::NOT_FOUND, format!("Documentation '{}' not found", doc_slug)).into_response()
@@ -109,21 +787,1262 @@ pub async fn doc_index(
     }
 }
 
+/// 文档的完整索引，类型按 index.json 中保存的权重排序，并带上说明
+fn full_index(doc: &Documentation) -> FullIndex {
+    let mut index = EntryIndex::new();
+    for index_type in &doc.types {
+        index.set_type_meta(
+            &index_type.name,
+            TypeMeta::new(index_type.weight, index_type.description.as_deref()),
+        );
+    }
+    index.add_multiple(doc.entries.clone());
+    index.to_full_index()
+}
+
+/// 文档首页（`/docs/:doc/`），以 HTML 列出文档的条目类型
+///
+/// 文档设置了落地页面时重定向到该页面。
+pub async fn doc_landing(
+    State(state): State<Arc<AppState>>,
+    Path(doc_slug): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    doc_landing_response(&state, None, &doc_slug, &params, &uri, &headers)
+}
+
+/// 命名空间中文档的首页（`/t/:namespace/docs/:doc/`）
+pub async fn namespaced_doc_landing(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, doc_slug)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(response) = unknown_namespace(&state, &namespace) {
+        return response;
+    }
+    doc_landing_response(&state, Some(&namespace), &doc_slug, &params, &uri, &headers)
+}
+
+/// 文档首页的响应，`namespace` 为 None 时只查找全局文档
+fn doc_landing_response(
+    state: &AppState,
+    namespace: Option<&str>,
+    doc_slug: &str,
+    params: &HashMap<String, String>,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    let registry = state.doc_registry.read().unwrap();
+    let Some(doc) = registry.find_localized_in(namespace, DocSlug::new(doc_slug).as_str(), &requested_locales(params, headers)) else {
+        return (StatusCode::NOT_FOUND, format!("Documentation '{}' not found", doc_slug)).into_response();
+    };
+    if let Some(redirect) = canonical_doc_redirect(doc_slug, uri) {
+        return with_doc_headers(redirect, doc);
+    }
+    if doc.landing_path.is_some() {
+        // 重定向后保留显式指定的语言。使用协商出的文档语言而不是原样回显参数，
+        // 参数中的任意字符不会进入 Location 头
+        let url = match params.get("locale") {
+            Some(_) => {
+                let locale: String = url::form_urlencoded::byte_serialize(doc.locale_or_default().as_bytes()).collect();
+                format!("{}?locale={}", doc.landing_url(), locale)
+            }
+            None => doc.landing_url(),
+        };
+        return with_doc_headers(Redirect::temporary(&url).into_response(), doc);
+    }
+    with_doc_headers(Html(render_doc_landing(doc)).into_response(), doc)
+}
+
+/// 文档首页的 HTML，每个类型显示条目数，展开后列出条目
+fn render_doc_landing(doc: &Documentation) -> String {
+    let title = if doc.version.is_empty() {
+        doc.name.clone()
+    } else {
+        format!("{} {}", doc.name, doc.version)
+    };
+    let mut html = PAGE_HEAD.replace("{title}", &escape_html(&title));
+    html.push_str(&format!("    <p><a href=\"/\">xwdoc</a></p>\n    <h1>{}</h1>\n", escape_html(&title)));
+    if !doc.release.is_empty() {
+        html.push_str(&format!("    <p class=\"muted\">Release {}</p>\n", escape_html(&doc.release)));
+    }
+
+    let index = full_index(doc);
+    if index.types.is_empty() {
+        html.push_str("    <p class=\"muted\">No entries.</p>\n");
+    }
+    let doc_url = doc_home_url(doc);
+    for index_type in &index.types {
+        html.push_str(&format!(
+            "    <details id=\"{}\">\n        <summary>{} <span class=\"muted\">({})</span></summary>\n",
+            escape_html(&index_type.slug),
+            escape_html(&index_type.name),
+            index_type.count,
+        ));
+        if let Some(description) = &index_type.description {
+            html.push_str(&format!("        <p class=\"muted\">{}</p>\n", escape_html(description)));
+        }
+        html.push_str("        <ul>\n");
+        for entry in index.entries.iter().filter(|entry| entry.entry_type == index_type.name) {
+            html.push_str(&format!(
+                "            <li><a href=\"{}{}\">{}</a></li>\n",
+                escape_html(&doc_url),
+                escape_html(&entry.path),
+                escape_html(&entry.name),
+            ));
+        }
+        html.push_str("        </ul>\n    </details>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
 /// u83b7u53d6u7279u5b9au6587u6863u7684u9875u9762
 pub async fn doc_page(
     State(state): State<Arc<AppState>>,
-    Path((doc_slug, page_path)): Path<(String, String)>
+    Path((doc_slug, page_key)): Path<(String, PageKey)>,
+    Query(params): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    doc_page_response(&state, None, &doc_slug, &page_key, &params, &uri, &headers)
+}
+
+/// 获取命名空间中文档的页面（`/t/:namespace/docs/:doc/*page`）
+///
+/// 命名空间中没有该文档时使用全局命名空间中的文档。
+pub async fn namespaced_doc_page(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, doc_slug, page_key)): Path<(String, String, PageKey)>,
+    Query(params): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(response) = unknown_namespace(&state, &namespace) {
+        return response;
+    }
+    doc_page_response(&state, Some(&namespace), &doc_slug, &page_key, &params, &uri, &headers)
+}
+
+/// 文档页面的响应，`namespace` 为 None 时只查找全局文档
+fn doc_page_response(
+    state: &AppState,
+    namespace: Option<&str>,
+    doc_slug: &str,
+    page_key: &PageKey,
+    params: &HashMap<String, String>,
+    uri: &Uri,
+    headers: &HeaderMap,
 ) -> Response {
     // u68c0u67e5u6587u6863u662fu5426u5b58u5728
-    match state.doc_registry.find(&doc_slug) {
+    let registry = state.doc_registry.read().unwrap();
+    match registry.find_localized_in(namespace, DocSlug::new(doc_slug).as_str(), &requested_locales(params, headers)) {
         Some(doc) => {
-            // u5728u771fu6b63u5b9eu73b0u4e2du FF0Cu4f1au8bbfu95eeu5e76u8fd4u56deu7279u5b9au9875u9762
-            let content = format!("<h1>Page: {}</h1><p>From documentation: {}</p>", page_path, doc.name);
+            if let Some(redirect) = canonical_doc_redirect(doc_slug, uri) {
+                return with_doc_headers(redirect, doc);
+            }
+
+            // 页面路径在提取时已规范化（去掉首尾斜杠），与 db.json 中的键一致
+            let page_path = page_key.as_str();
 
-            Html(content).into_response()
+            // 拒绝跳出文档目录的路径
+            if page_path.split('/').any(|segment| segment == "..") {
+                return (StatusCode::BAD_REQUEST, "Invalid page path").into_response();
+            }
+
+            // 文档的自定义样式和脚本
+            let custom_prefix = format!("{}/", custom_assets::CUSTOM_PREFIX);
+            if let Some(name) = page_path.strip_prefix(&custom_prefix) {
+                let version = params.get("v").map(String::as_str);
+                return with_doc_headers(
+                    custom_assets::serve(doc, &state.config.serve, name, version, headers),
+                    doc,
+                );
+            }
+
+            // `?format=md` 返回页面的 Markdown 版本，`?format=json` 返回带许可信息的 JSON
+            match params.get("format").map(String::as_str) {
+                Some("md" | "markdown") => return with_doc_headers(markdown_page(state, doc, page_key), doc),
+                Some("json") => return with_doc_headers(json_page(state, doc, page_key), doc),
+                _ => {}
+            }
+
+            let assets = custom_assets::enabled_assets(doc, &state.config.serve);
+            let tags = custom_assets::tags(doc, &assets);
+            // 同一文档安装了多个版本时在页面中加入版本切换控件
+            let versions = versions_among(registry.visible(namespace), &doc.slug, doc.locale.as_deref());
+            let switcher = version_switcher(doc, &versions, page_key);
+            let render = |content: &str| {
+                let content = inject_after_body(&version_relative_links(content, doc), &switcher);
+                custom_assets::inject(&content, &tags)
+            };
+
+            // 带 `.html` 后缀和不带后缀的路径指向同一页面，页面文件优先，其次为 db.json
+            let candidates = page_key_candidates(page_key);
+            let loaded = candidates.iter().find_map(|key| {
+                state
+                    .page_cache
+                    .get_or_load(&doc.cache_key(), key.as_str(), &doc.page_file(key.as_str()))
+                    .ok()
+            });
+            let page = match loaded {
+                Some(page) => page,
+                None => {
+                    let db = match state
+                        .page_cache
+                        .get_or_load_db(&doc.cache_key(), &doc.dir().join(DB_FILENAME))
+                        .with_context(|| format!("文档 {} 页面 {}", doc.path(), page_path))
+                    {
+                        Ok(db) => db,
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+                        }
+                    };
+                    let stored = db
+                        .as_deref()
+                        .and_then(|db| candidates.iter().find_map(|key| db.page(key)));
+                    return match stored {
+                        Some(stored) => with_doc_headers(
+                            with_versions_header(Html(render(&page_layout(doc, stored))).into_response(), &versions),
+                            doc,
+                        ),
+                        None => page_not_found(doc, page_key, db.as_deref()),
+                    };
+                }
+            };
+
+            // 安装或删除其他版本时版本切换控件变化，页面的 ETag 也随之变化
+            let mut etag = custom_assets::page_etag(&page.etag, &assets);
+            if !switcher.is_empty() {
+                etag = format!("\"{}+{:x}\"", etag.trim_matches('"'), fnv1a(switcher.as_bytes()));
+            }
+            let not_modified = headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok())
+                .map_or(false, |value| value == etag);
+            if not_modified {
+                return with_doc_headers(
+                    with_versions_header((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response(), &versions),
+                    doc,
+                );
+            }
+
+            // 页面不需要改写时，客户端接受 gzip 就直接返回缓存的压缩副本
+            let unchanged = doc.version.is_empty() && switcher.is_empty() && tags.is_empty();
+            if let Some(gzip) = page.gzip.clone().filter(|_| unchanged && compression::accepts_gzip(headers)) {
+                state.page_cache.record_gzip_served(&page);
+                let mut response = compression::pre_encoded_html(gzip);
+                if let Ok(value) = HeaderValue::from_str(&compression::gzip_etag(&etag)) {
+                    response.headers_mut().insert(header::ETAG, value);
+                }
+                return with_doc_headers(with_versions_header(response, &versions), doc);
+            }
+
+            with_doc_headers(
+                with_versions_header(([(header::ETAG, etag)], Html(render(&page.content))).into_response(), &versions),
+                doc,
+            )
         },
         None => {
             (StatusCode::NOT_FOUND, format!("Documentation '{}' not found", doc_slug)).into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+/// 版本在切换控件和响应头中的名称，没有版本号的文档为 `latest`
+fn version_label(doc: &Documentation) -> &str {
+    if doc.version.is_empty() {
+        "latest"
+    } else {
+        &doc.version
+    }
+}
+
+/// 添加列出各版本的响应头，如 `1.0, 2.0`
+fn with_versions_header(mut response: Response, versions: &[&Documentation]) -> Response {
+    let labels: Vec<&str> = versions.iter().map(|doc| version_label(doc)).collect();
+    if let Ok(value) = HeaderValue::from_str(&labels.join(", ")) {
+        response.headers_mut().insert(AVAILABLE_VERSIONS_HEADER, value);
+    }
+    response
+}
+
+/// 版本切换控件，只安装了一个版本时为空
+///
+/// 其他版本中有同一页面时链接到该页面，否则链接到该版本的文档首页。
+fn version_switcher(doc: &Documentation, versions: &[&Documentation], page_key: &PageKey) -> String {
+    if versions.len() < 2 {
+        return String::new();
+    }
+    let mut html = String::from("<nav class=\"_versions\">Version:");
+    for version in versions {
+        if std::ptr::eq(*version, doc) {
+            html.push_str(&format!(" <strong aria-current=\"page\">{}</strong>", escape_html(version_label(version))));
+            continue;
+        }
+        let href = if version.has_page(page_key) {
+            format!("{}/{}/{}", version.docs_prefix(), version.route_slug(), page_key.as_str())
+        } else {
+            doc_home_url(version)
+        };
+        html.push_str(&format!(" <a href=\"{}\">{}</a>", escape_html(&href), escape_html(version_label(version))));
+    }
+    html.push_str("</nav>\n");
+    html
+}
+
+/// 将片段放在 `<body>` 标签之后，没有 `<body>` 时放在页面开头
+fn inject_after_body(html: &str, fragment: &str) -> String {
+    if fragment.is_empty() {
+        return html.to_string();
+    }
+    let body_end = html
+        .to_ascii_lowercase()
+        .find("<body")
+        .and_then(|start| html[start..].find('>').map(|end| start + end + 1));
+    match body_end {
+        Some(index) => format!("{}{}{}", &html[..index], fragment, &html[index..]),
+        None => format!("{}{}", fragment, html),
+    }
+}
+
+/// 抓取时页面内的链接改写为不带版本的文档地址（如 `/docs/rust/std`），带有版本的
+/// 文档改为指向当前版本，跟随链接时停留在所选的版本中
+fn version_relative_links(html: &str, doc: &Documentation) -> String {
+    if doc.version.is_empty() {
+        return html.to_string();
+    }
+    let unversioned = format!("/docs/{}/", doc.slug);
+    let versioned = format!("{}/{}/", doc.docs_prefix(), doc.route_slug());
+    html.replace(&format!("=\"{}", unversioned), &format!("=\"{}", versioned))
+        .replace(&format!("='{}", unversioned), &format!("='{}", versioned))
+}
+
+/// 页面的 Markdown 版本，链接按 db.json 中记录的页面源 URL 解析为绝对地址
+fn markdown_page(state: &AppState, doc: &Documentation, page_key: &PageKey) -> Response {
+    match page_source(state, doc, page_key) {
+        Ok((content, stored)) => {
+            let source_url = stored.as_ref().and_then(|page| page.source_url.as_deref());
+            (
+                [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+                markdown::html_to_markdown(&content, source_url),
+            )
+                .into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+/// 页面的 JSON 表示，附带文档的版权说明和许可证，供下游展示内容时标注来源
+fn json_page(state: &AppState, doc: &Documentation, page_key: &PageKey) -> Response {
+    match page_source(state, doc, page_key) {
+        Ok((content, stored)) => {
+            let stored = stored.unwrap_or_default();
+            Json(serde_json::json!({
+                "doc": doc.path(),
+                "path": page_key.as_str(),
+                "title": stored.title,
+                "source_url": stored.source_url,
+                "content": content,
+                "attribution": doc.attribution,
+                "license": doc.license,
+            }))
+            .into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+/// 读取页面内容和 db.json 中保存的页面信息，找不到页面时返回 404 响应
+///
+/// 与 HTML 页面一样页面文件优先，其次为 db.json。
+fn page_source(
+    state: &AppState,
+    doc: &Documentation,
+    page_key: &PageKey,
+) -> std::result::Result<(String, Option<StoredPage>), Response> {
+    let candidates = page_key_candidates(page_key);
+    let db = match state
+        .page_cache
+        .get_or_load_db(&doc.cache_key(), &doc.dir().join(DB_FILENAME))
+        .with_context(|| format!("文档 {} 页面 {}", doc.path(), page_key.as_str()))
+    {
+        Ok(db) => db,
+        Err(e) => {
+            log::error!("{}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response());
+        }
+    };
+    let stored = db
+        .as_deref()
+        .and_then(|db| candidates.iter().find_map(|key| db.page(key)));
+
+    let content = candidates
+        .iter()
+        .find_map(|key| {
+            state
+                .page_cache
+                .get_or_load(&doc.cache_key(), key.as_str(), &doc.page_file(key.as_str()))
+                .ok()
+                .map(|page| page.content.to_string())
+        })
+        .or_else(|| stored.map(|page| page.content.clone()));
+    match content {
+        Some(content) => Ok((content, stored.cloned())),
+        None => Err(page_not_found(doc, page_key, db.as_deref())),
+    }
+}
+
+/// 请求路径对应的页面键：原路径，其次为去掉或加上 `.html` 后缀的路径
+///
+/// 抓取时页面键只去掉首尾斜杠（与 `UrlScraper::url_to_path` 一致），原网站的
+/// 页面可能带或不带 `.html` 后缀。
+fn page_key_candidates(key: &PageKey) -> Vec<PageKey> {
+    let path = key.as_str();
+    let alternative = match path.strip_suffix(".html") {
+        Some(stem) => PageKey::new(stem),
+        None => PageKey::new(&format!("{}.html", path)),
+    };
+    vec![key.clone(), alternative]
+}
+
+/// db.json 中的页面内容是过滤后的 HTML 片段，放入最简单的页面布局；已是完整页面时保持原样
+fn page_layout(doc: &Documentation, page: &StoredPage) -> String {
+    let head = page.content.get(..512).unwrap_or(&page.content).to_ascii_lowercase();
+    if head.contains("<html") || head.contains("<!doctype") {
+        return page.content.clone();
+    }
+    let title = match page.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => format!("{} - {}", title, doc.name),
+        _ => doc.name.clone(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n<title>{}</title>\n</head>\n<body>\n<main class=\"_page\">\n{}\n</main>\n</body>\n</html>\n",
+        escape_html(&title),
+        page.content
+    )
+}
+
+/// 页面不存在时的 404 响应，列出 db.json 中路径相近的页面
+fn page_not_found(doc: &Documentation, page_key: &PageKey, db: Option<&PageDb>) -> Response {
+    let similar = db.map(|db| similar_pages(db, page_key)).unwrap_or_default();
+    let mut body = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>Page not found</title>\n</head>\n<body>\n<h1>Page not found</h1>\n<p>Page '{}' not found in {}.</p>\n",
+        escape_html(page_key.as_str()),
+        escape_html(&doc.name)
+    );
+    if !similar.is_empty() {
+        body.push_str("<p>Similar pages:</p>\n<ul>\n");
+        for key in &similar {
+            body.push_str(&format!(
+                "<li><a href=\"{}/{}/{}\">{}</a></li>\n",
+                doc.docs_prefix(),
+                doc.route_slug(),
+                escape_html(key.as_str()),
+                escape_html(key.as_str())
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str(&format!("<p><a href=\"{}/{}\">Back to {}</a></p>\n</body>\n</html>\n", doc.docs_prefix(), doc.route_slug(), escape_html(&doc.name)));
+    with_doc_headers((StatusCode::NOT_FOUND, Html(body)).into_response(), doc)
+}
+
+/// db.json 中与请求路径最相近的页面，按编辑距离排序，距离相同时按路径排序
+///
+/// 只列出编辑距离不超过请求路径长度一半的页面，以及最后一段相同的页面。
+fn similar_pages(db: &PageDb, page_key: &PageKey) -> Vec<PageKey> {
+    let requested = page_key.as_str().to_lowercase();
+    let requested = requested.strip_suffix(".html").unwrap_or(&requested);
+    let last_segment = requested.rsplit('/').next().unwrap_or(requested);
+    let max_distance = (requested.chars().count() / 2).max(2);
+
+    let mut similar: Vec<(usize, &PageKey)> = db
+        .paths()
+        .into_iter()
+        .filter_map(|key| {
+            let path = key.as_str().to_lowercase();
+            let distance = edit_distance(requested, &path);
+            let same_segment = path.rsplit('/').next() == Some(last_segment);
+            (distance <= max_distance || same_segment).then_some((distance, key))
+        })
+        .collect();
+    similar.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)));
+    similar.into_iter().take(SIMILAR_PAGES_LIMIT).map(|(_, key)| key.clone()).collect()
+}
+
+/// 两个字符串之间的编辑距离（按字符计算）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 文档索引的 ETag，由文档目录（含语言）、修改时间和 index.json 的大小生成
+fn index_etag(doc: &Documentation) -> String {
+    format!("\"{:x}-{:x}-{:x}\"", fnv1a(doc.path().as_bytes()), doc.mtime, doc.index_size)
+}
+
+/// 为文档响应添加语言头，并记录文档对搜索引擎索引的单独设置
+fn with_doc_headers(mut response: Response, doc: &Documentation) -> Response {
+    if let Some(noindex) = doc.noindex {
+        response.extensions_mut().insert(DocNoindex(noindex));
+    }
+    let headers = response.headers_mut();
+    if let Ok(locale) = HeaderValue::from_str(doc.locale_or_default()) {
+        headers.insert(header::CONTENT_LANGUAGE, locale);
+    }
+    // 追加而不是覆盖，预压缩的页面已经带有 `Vary: accept-encoding`
+    headers.append(header::VARY, HeaderValue::from_static("accept-language"));
+    response
+}
+
+/// 请求中的文档标识不是规范写法（如 `/docs/Babel~7/intro`）时永久重定向到规范的地址
+///
+/// 页面路径和查询参数保持不变。
+fn canonical_doc_redirect(requested: &str, uri: &Uri) -> Option<Response> {
+    let canonical = DocSlug::new(requested);
+    if canonical.as_str() == requested {
+        return None;
+    }
+    // 命名空间中的文档保留 `/t/<namespace>` 前缀
+    let (namespace_prefix, after_prefix) = uri.path().split_once("/docs/")?;
+    let rest = after_prefix.find('/').map_or("", |i| &after_prefix[i..]);
+    let target = match uri.query() {
+        Some(query) => format!("{}/docs/{}{}?{}", namespace_prefix, canonical, rest, query),
+        None => format!("{}/docs/{}{}", namespace_prefix, canonical, rest),
+    };
+    // 规范的标识可能包含非 ASCII 字符，经过 URL 解析完成百分号编码
+    let url = url::Url::parse("http://localhost").ok()?.join(&target).ok()?;
+    Some(Redirect::permanent(&url[url::Position::BeforePath..]).into_response())
+}
+
+/// 获取请求的语言偏好
+///
+/// `?locale=` 参数优先，此时忽略 `Accept-Language`。
+fn requested_locales(params: &HashMap<String, String>, headers: &HeaderMap) -> Vec<String> {
+    match params.get("locale").filter(|locale| !locale.is_empty()) {
+        Some(locale) => vec![locale.clone()],
+        None => accepted_locales(headers),
+    }
+}
+
+/// 解析 `Accept-Language` 请求头，按权重从高到低返回语言
+fn accepted_locales(headers: &HeaderMap) -> Vec<String> {
+    let value = match headers.get(header::ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()) {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+
+    let mut locales: Vec<(f32, String)> = value
+        .split(',')
+        .filter_map(|part| {
+            let mut pieces = part.split(';');
+            let tag = pieces.next()?.trim();
+            let quality = pieces
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if tag.is_empty() || tag == "*" || quality <= 0.0 {
+                return None;
+            }
+            Some((quality, tag.to_string()))
+        })
+        .collect();
+
+    // 稳定排序，相同权重保持请求头中的顺序
+    locales.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    locales.into_iter().map(|(_, tag)| tag).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::index_entry::IndexEntry;
+    use axum::body::HttpBody;
+
+    fn entry(name: &str, entry_type: &str) -> IndexEntry {
+        IndexEntry {
+            name: name.to_string(),
+            path: name.to_lowercase().replace(' ', "-"),
+            entry_type: entry_type.to_string(),
+            word_count: None,
+            inherited: None,
+        }
+    }
+
+    /// 测试文档的 index.json 所在的根目录
+    const REGISTRY_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_docs/web_registry");
+
+    fn fixture_doc(name: &str, slug: &str) -> Documentation {
+        Documentation::new(name, slug, "").with_root(REGISTRY_FIXTURES, true)
+    }
+
+    fn test_state() -> Arc<AppState> {
+        test_state_with_config(Config::default())
+    }
+
+    fn test_state_with_config(config: Config) -> Arc<AppState> {
+        let mut registry = DocRegistry::new();
+        registry.add(
+            fixture_doc("CSS", "css").with_icon(true).with_landing_path("color").with_entries(vec![
+                entry("color", "Properties"),
+                entry("colors", "Properties"),
+                entry("color-mix()", "Functions"),
+                entry("color-gamut", "Media features"),
+                entry("color-scheme", "Properties"),
+                entry("color-adjust", "Properties"),
+                entry("color-rendering", "Properties"),
+                entry("color-interpolation", "Properties"),
+            ]),
+        );
+        registry.add(
+            fixture_doc("HTML", "html")
+                .with_entries(vec![entry("color attribute", "Attributes"), entry("div", "Elements")]),
+        );
+        registry.add(
+            fixture_doc("JavaScript", "javascript")
+                .with_entries(vec![entry("Color.from", "Methods")]),
+        );
+        registry.add(fixture_doc("JavaScript 中文", "javascript").with_locale("zh-CN"));
+
+        let doc_registry = Arc::new(RwLock::new(registry));
+        let search_history = SearchHistory::from_config(&config).map(Arc::new);
+        Arc::new(AppState {
+            config,
+            doc_registry: doc_registry.clone(),
+            reloader: Arc::new(RegistryReloader::new(doc_registry, Vec::new())),
+            page_cache: Arc::new(PageCache::new()),
+            snippet_cache: Arc::new(SnippetCache::new()),
+            started_at: Instant::now(),
+            assets: Arc::new(AssetStore::new("public/assets")),
+            jobs: Arc::new(JobRegistry::new()),
+            search_history,
+        })
+    }
+
+    fn group_sizes(groups: &[serde_json::Value]) -> Vec<(String, usize)> {
+        groups
+            .iter()
+            .map(|g| {
+                (
+                    g["doc"]["slug"].as_str().unwrap().to_string(),
+                    g["entries"].as_array().unwrap().len(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_typeahead_caps_results_per_doc() {
+        let state = test_state();
+        let groups = typeahead_groups(&state.doc_registry.read().unwrap(), "color", 7, None);
+
+        assert_eq!(
+            group_sizes(&groups),
+            vec![
+                ("css".to_string(), 5),
+                ("javascript".to_string(), 1),
+                ("html".to_string(), 1),
+            ]
+        );
+        assert_eq!(groups[0]["doc"]["icon"], true);
+        assert_eq!(groups[0]["entries"][0]["name"], "color");
+        assert_eq!(groups[0]["entries"][0]["type"], "Properties");
+    }
+
+    #[test]
+    fn test_typeahead_fills_remaining_slots() {
+        let state = test_state();
+        let groups = typeahead_groups(&state.doc_registry.read().unwrap(), "color", 20, None);
+
+        assert_eq!(
+            group_sizes(&groups),
+            vec![
+                ("css".to_string(), 8),
+                ("javascript".to_string(), 1),
+                ("html".to_string(), 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_typeahead_handler() {
+        let params = HashMap::from([
+            ("q".to_string(), "div".to_string()),
+            ("limit".to_string(), "5".to_string()),
+        ]);
+        let response = typeahead(State(test_state()), Query(params)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["query"], "div");
+        assert_eq!(group_sizes(json["results"].as_array().unwrap()), vec![("html".to_string(), 1)]);
+    }
+
+    async fn meta_response(config: Config, if_none_match: Option<&str>) -> (StatusCode, String, Option<serde_json::Value>) {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = if_none_match {
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(etag).unwrap());
+        }
+        let response = api_meta(State(test_state_with_config(config)), headers).await;
+        let status = response.status();
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+        let body = response
+            .into_body()
+            .data()
+            .await
+            .map(|body| serde_json::from_slice(&body.unwrap()).unwrap());
+        (status, etag, body)
+    }
+
+    #[tokio::test]
+    async fn test_api_meta() {
+        let config = Config::default().with_docs_path("/nonexistent");
+        let (status, etag, body) = meta_response(config.clone(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = body.unwrap();
+
+        // 默认文档按配置中的顺序排在前面
+        let slugs: Vec<&str> = body["docs"].as_array().unwrap().iter().map(|doc| doc["slug"].as_str().unwrap()).collect();
+        assert_eq!(slugs, vec!["html", "css", "javascript"]);
+        assert_eq!(body["docs"][1]["entries"], 8);
+        assert_eq!(body["docs"][2]["locales"], serde_json::json!(["en", "zh-CN"]));
+        assert_eq!(body["aliases"]["js"], "javascript");
+        assert_eq!(body["capabilities"]["typeahead"], true);
+        assert_eq!(body["capabilities"]["jobs_api"], true);
+        assert_eq!(body["capabilities"]["search_history"], false);
+
+        let (status, _, _) = meta_response(config.clone(), Some(&etag)).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+        // 添加别名或修改功能开关后内容和 ETag 都会变化
+        let (status, aliased_etag, aliased) = meta_response(config.clone().with_alias("mdn", "css"), Some(&etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(aliased_etag, etag);
+        assert_eq!(aliased.unwrap()["aliases"]["mdn"], "css");
+
+        let (_, read_only_etag, read_only) = meta_response(config.with_read_only(true), None).await;
+        assert_ne!(read_only_etag, etag);
+        assert_eq!(read_only.unwrap()["capabilities"]["jobs_api"], false);
+    }
+
+    #[tokio::test]
+    async fn test_search_handler_returns_doc_details() {
+        let params = HashMap::from([("q".to_string(), "div".to_string())]);
+        let response = search(State(test_state()), Query(params)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "name": "div",
+                "path": "div",
+                "type": "Elements",
+                "type_slug": "elements",
+                "doc": "html",
+                "doc_name": "HTML",
+                "version": "",
+                "score": 100,
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_scoped_to_doc_and_type() {
+        let query = |pairs: &[(&str, &str)]| {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>()
+        };
+
+        let params = query(&[("q", "color"), ("doc", "css"), ("type", "PROPERTIES")]);
+        let response = search(State(test_state()), Query(params)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let results = json.as_array().unwrap();
+        assert_eq!(results[0]["name"], "color");
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().all(|r| r["doc"] == "css" && r["type_slug"] == "properties"));
+
+        let response = search(State(test_state()), Query(query(&[("q", "color"), ("doc", "missing")]))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_search_history_boosts_selected_result() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = crate::core::config::SearchSettings {
+            history: true,
+            history_file: Some(dir.path().join("history.json")),
+            ..Default::default()
+        };
+        let state = test_state_with_config(Config::default().with_search(settings));
+        let first = |state: Arc<AppState>| async move {
+            let params = HashMap::from([("q".to_string(), "color-".to_string())]);
+            let body = search(State(state), Query(params)).await.into_body().data().await.unwrap().unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            json[0]["name"].as_str().unwrap().to_string()
+        };
+        assert_eq!(first(state.clone()).await, "color-gamut");
+
+        let selection = SearchSelection { doc: "css".to_string(), path: "color-mix()".to_string() };
+        let response = search_select(State(state.clone()), Json(selection)).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(first(state.clone()).await, "color-mix()");
+        assert!(dir.path().join("history.json").exists());
+
+        let response = clear_search_history(State(state.clone())).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(first(state).await, "color-gamut");
+    }
+
+    #[tokio::test]
+    async fn test_search_history_disabled_or_read_only() {
+        let selection = || Json(SearchSelection { doc: "css".to_string(), path: "color".to_string() });
+        let response = search_select(State(test_state()), selection()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = crate::core::config::SearchSettings {
+            history: true,
+            history_file: Some(dir.path().join("history.json")),
+            ..Default::default()
+        };
+        let state = test_state_with_config(Config::default().with_search(settings).with_read_only(true));
+        assert_eq!(search_select(State(state.clone()), selection()).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(clear_search_history(State(state)).await.status(), StatusCode::FORBIDDEN);
+        assert!(!dir.path().join("history.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_doc_index_with_landing_path_returns_json() {
+        let response = doc_index(State(test_state()), Path("css".to_string()), Query(HashMap::new()), OriginalUri(Uri::from_static("/docs/css")), HeaderMap::new()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert!(response.headers().contains_key(header::ETAG));
+        let json: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert!(json["types"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_doc_landing_redirects_to_landing_path() {
+        let landing = |params: HashMap<String, String>| {
+            doc_landing(State(test_state()), Path("css".to_string()), Query(params), OriginalUri(Uri::from_static("/docs/css/")), HeaderMap::new())
+        };
+
+        let response = landing(HashMap::new()).await;
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/docs/css/color");
+
+        // 语言参数不原样写入 Location 头
+        let params = HashMap::from([("locale".to_string(), "en\r\nSet-Cookie: a=b&x=1".to_string())]);
+        let response = landing(params).await;
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/docs/css/color?locale=en");
+    }
+
+    #[tokio::test]
+    async fn test_doc_index_without_landing_path_lists_types() {
+        let response = doc_index(State(test_state()), Path("html".to_string()), Query(HashMap::new()), OriginalUri(Uri::from_static("/docs/html")), HeaderMap::new()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["entries"].as_array().unwrap().len(), 2);
+        assert_eq!(json["types"][0]["name"], "Attributes");
+        assert_eq!(json["types"][1]["name"], "Elements");
+    }
+
+    #[tokio::test]
+    async fn test_doc_index_etag_and_missing_index() {
+        let uri = || OriginalUri(Uri::from_static("/docs/html"));
+        let response = doc_index(State(test_state()), Path("html".to_string()), Query(HashMap::new()), uri(), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let etag = response.headers()[header::ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.clone());
+        let response = doc_index(State(test_state()), Path("html".to_string()), Query(HashMap::new()), uri(), headers).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+
+        // 文档已注册但 index.json 不存在
+        let state = test_state();
+        state.doc_registry.write().unwrap().add(fixture_doc("Removed", "removed"));
+        let uri = OriginalUri(Uri::from_static("/docs/removed"));
+        let response = doc_index(State(state), Path("removed".to_string()), Query(HashMap::new()), uri, HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"], "index_missing");
+    }
+
+    async fn body_text(response: Response) -> String {
+        let body = response.into_body().data().await.unwrap().unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_index_lists_installed_docs_by_type() {
+        let state = test_state_with_config(Config::default().with_time_display(TimeDisplay::Utc));
+        state.doc_registry.write().unwrap().add(
+            Documentation::new("Rust", "rust", "1.75.0")
+                .with_doc_type("rustdoc")
+                .with_release("1.75.0")
+                .with_mtime(1_700_000_000)
+                .with_entries(vec![entry("Vec", "Structs")]),
+        );
+
+        let html = index(State(state)).await.0;
+        let rustdoc = html.find("<h2>rustdoc</h2>").unwrap();
+        let other = html.find("<h2>other</h2>").unwrap();
+        assert!(other < rustdoc);
+        assert!(html.contains(
+            "<tr><td><a href=\"/docs/rust~1.75.0/\">Rust</a></td><td>1.75.0</td><td>1.75.0</td><td>1</td><td>2023-11-14 22:13 UTC</td></tr>"
+        ), "{}", html);
+        assert!(html.contains("<a href=\"/docs/css/\">CSS</a></td><td>-</td><td>-</td><td>8</td><td>-</td>"), "{}", html);
+
+        // 各语言版本只列出一次
+        assert_eq!(html.matches("/docs/javascript/").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_doc_landing_lists_entry_types() {
+        let landing = |slug: &str, uri: &'static str| {
+            doc_landing(State(test_state()), Path(slug.to_string()), Query(HashMap::new()), OriginalUri(Uri::from_static(uri)), HeaderMap::new())
+        };
+
+        let response = landing("html", "/docs/html/").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = body_text(response).await;
+        assert!(html.contains("<summary>Attributes <span class=\"muted\">(1)</span></summary>"), "{}", html);
+        assert!(html.contains("<summary>Elements <span class=\"muted\">(1)</span></summary>"), "{}", html);
+        assert!(html.contains("<li><a href=\"/docs/html/div\">div</a></li>"), "{}", html);
+
+        assert_eq!(landing("missing", "/docs/missing/").await.status(), StatusCode::NOT_FOUND);
+        let response = landing("CSS", "/docs/CSS/").await;
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/docs/css/");
+    }
+
+    #[tokio::test]
+    async fn test_doc_page_from_db() {
+        let root = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(root.path().join("tmp")).unwrap();
+        std::fs::write(
+            root.path().join("tmp").join(DB_FILENAME),
+            r#"{"reference/array": "<h1>Array</h1>", "reference/map": "<h1>Map</h1>", "guide/intro": "<h1>Intro</h1>"}"#,
+        )
+        .unwrap();
+        let state = test_state();
+        state
+            .doc_registry
+            .write()
+            .unwrap()
+            .add(Documentation::new("Tmp", "tmp", "").with_root(root.path(), true));
+
+        let request = |path: &str| {
+            let uri = OriginalUri(format!("/docs/tmp/{}", path).parse::<Uri>().unwrap());
+            doc_page(
+                State(state.clone()),
+                Path(("tmp".to_string(), PageKey::new(path))),
+                Query(HashMap::new()),
+                uri,
+                HeaderMap::new(),
+            )
+        };
+        for path in ["reference/array", "reference/array/", "reference/array.html"] {
+            let response = request(path).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", path);
+            let body = response.into_body().data().await.unwrap().unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.starts_with("<!DOCTYPE html>"));
+            assert!(body.contains("<h1>Array</h1>"));
+        }
+
+        let response = request("reference/arary").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"<a href="/docs/tmp/reference/array">"#));
+        assert!(!body.contains("guide/intro"));
+
+        // 无法解析的 db.json 返回带有文档和页面的错误
+        std::fs::write(root.path().join("tmp").join(DB_FILENAME), "{not json").unwrap();
+        let response = request("reference/array").await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.starts_with("文档 tmp 页面 reference/array: 文档错误: 无法读取"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_doc_page_as_markdown() {
+        let root = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(root.path().join("tmp")).unwrap();
+        std::fs::write(
+            root.path().join("tmp").join(DB_FILENAME),
+            r#"{"__meta": {"format": 2}, "pages": {"reference/array": {"content": "<h1>Array</h1><p>See <a href=\"map\">Map</a>.</p>", "source_url": "https://example.com/reference/array"}}}"#,
+        )
+        .unwrap();
+        let state = test_state();
+        state
+            .doc_registry
+            .write()
+            .unwrap()
+            .add(Documentation::new("Tmp", "tmp", "").with_root(root.path(), true));
+
+        let params = HashMap::from([("format".to_string(), "md".to_string())]);
+        let response = doc_page(
+            State(state),
+            Path(("tmp".to_string(), PageKey::new("reference/array"))),
+            Query(params),
+            OriginalUri(Uri::from_static("/docs/tmp/reference/array?format=md")),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/markdown; charset=utf-8");
+        let body = response.into_body().data().await.unwrap().unwrap();
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            "# Array\n\nSee [Map](https://example.com/reference/map).\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_switcher_links_to_same_page() {
+        let state = test_state();
+        state
+            .doc_registry
+            .write()
+            .unwrap()
+            .load_from_disk(&[concat!(env!("CARGO_MANIFEST_DIR"), "/test_docs/web_versions")])
+            .unwrap();
+
+        let request = |slug: &str, path: &str| {
+            let uri = OriginalUri(format!("/docs/{}/{}", slug, path).parse::<Uri>().unwrap());
+            doc_page(State(state.clone()), Path((slug.to_string(), PageKey::new(path))), Query(HashMap::new()), uri, HeaderMap::new())
+        };
+
+        // 两个版本中都有的页面链接到另一个版本的同一页面
+        let response = request("lib~2.0", "intro").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[AVAILABLE_VERSIONS_HEADER], "1.0, 2.0");
+        let html = body_text(response).await;
+        assert!(html.contains(
+            "<body><nav class=\"_versions\">Version: <a href=\"/docs/lib~1.0/intro\">1.0</a> <strong aria-current=\"page\">2.0</strong></nav>"
+        ), "{}", html);
+
+        // 只有旧版本中有的页面链接到新版本的文档首页
+        let html = body_text(request("lib~1.0", "legacy").await).await;
+        assert!(html.contains("<a href=\"/docs/lib~2.0/\">2.0</a>"), "{}", html);
+
+        // 页面内的链接停留在当前版本
+        let html = body_text(request("lib~1.0", "intro").await).await;
+        assert!(html.contains("<a href=\"/docs/lib~1.0/legacy\">the legacy API</a>"), "{}", html);
+        assert!(html.contains("<a href=\"/docs/lib~2.0/intro\">2.0</a>"), "{}", html);
+    }
+
+    #[tokio::test]
+    async fn test_page_json_carries_scraper_license() {
+        use crate::core::filters::AttributionFilter;
+        use crate::core::http_fetch::{FetchedResponse, MockFetch};
+        use crate::core::scraper::UrlScraper;
+
+        let output = tempfile::TempDir::new().unwrap();
+        let fetch = MockFetch::new().with_response(
+            "https://example.com/",
+            FetchedResponse::html("<html><body><h1>Intro</h1><p>Hello</p></body></html>"),
+        );
+        let mut scraper = UrlScraper::new("Fixture", "", "https://example.com/", output.path().to_str().unwrap())
+            .with_attribution("© Example contributors")
+            .with_license("CC-BY-SA-2.5")
+            .with_filter(Box::new(AttributionFilter::new()))
+            .with_fetch(Arc::new(fetch))
+            .with_favicon(false)
+            .with_rate_limit(6000);
+        scraper.run().await.unwrap();
+
+        let state = test_state();
+        state.doc_registry.write().unwrap().load_from_disk(&[output.path()]).unwrap();
+        let params = HashMap::from([("format".to_string(), "json".to_string())]);
+        let uri = OriginalUri(Uri::from_static("/docs/fixture/index"));
+        let response = doc_page(
+            State(state.clone()),
+            Path(("fixture".to_string(), PageKey::new("index"))),
+            Query(params),
+            uri,
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let json: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(json["license"], "CC-BY-SA-2.5");
+        assert_eq!(json["attribution"], "© Example contributors");
+        assert!(json["content"].as_str().unwrap().contains(r#"data-license="CC-BY-SA-2.5""#), "{}", json);
+
+        // 文档列表中同样带有许可证
+        let response = docs_list(State(state), Query(HashMap::new())).await;
+        let docs: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(docs[0]["license"], "CC-BY-SA-2.5");
+    }
+
+    async fn negotiated_locale(params: &[(&str, &str)], accept_language: Option<&str>) -> String {
+        let params: HashMap<String, String> = params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let mut headers = HeaderMap::new();
+        if let Some(value) = accept_language {
+            headers.insert(header::ACCEPT_LANGUAGE, HeaderValue::from_str(value).unwrap());
+        }
+        let response = doc_index(State(test_state()), Path("javascript".to_string()), Query(params), OriginalUri(Uri::from_static("/docs/javascript")), headers).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::VARY], "accept-language");
+        response.headers()[header::CONTENT_LANGUAGE].to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_localized_doc_is_negotiated() {
+        // 已安装的语言
+        assert_eq!(negotiated_locale(&[], Some("zh-CN,zh;q=0.9,en;q=0.8")).await, "zh-CN");
+        assert_eq!(negotiated_locale(&[], Some("fr;q=0.5, zh;q=0.7")).await, "zh-CN");
+
+        // 未安装的语言和缺少请求头时使用默认语言
+        assert_eq!(negotiated_locale(&[], Some("de-DE")).await, "en");
+        assert_eq!(negotiated_locale(&[], None).await, "en");
+
+        // ?locale= 覆盖 Accept-Language
+        assert_eq!(negotiated_locale(&[("locale", "en")], Some("zh-CN")).await, "en");
+        assert_eq!(negotiated_locale(&[("locale", "zh-CN")], None).await, "zh-CN");
+    }
+
+    #[test]
+    fn test_accepted_locales_order_by_quality() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT_LANGUAGE,
+            HeaderValue::from_static("en;q=0.3, zh-CN, *;q=0.1, fr;q=0.8, de;q=0"),
+        );
+        assert_eq!(accepted_locales(&headers), vec!["zh-CN", "fr", "en"]);
+    }
+
+    async fn docs_list_json(params: &[(&str, &str)]) -> (String, serde_json::Value) {
+        let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let response = docs_list(State(test_state()), Query(params)).await;
+        let total = response.headers()[TOTAL_COUNT_HEADER].to_str().unwrap().to_string();
+        let body = response.into_body().data().await.unwrap().unwrap();
+        (total, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_docs_list_filters_and_fields() {
+        let (total, full) = docs_list_json(&[]).await;
+        assert_eq!(total, "3");
+        assert_eq!(full.as_array().unwrap()[0].as_object().unwrap().len(), 9);
+        // 测试文档没有 meta.json，没有质量分数
+        assert!(full[0]["quality"].is_null());
+
+        let (total, json) = docs_list_json(&[("q", "SCRIPT"), ("fields", "slug, locales,unknown")]).await;
+        assert_eq!(total, "1");
+        assert_eq!(json, serde_json::json!([{ "slug": "javascript", "locales": ["en", "zh-CN"] }]));
+
+        let (total, json) = docs_list_json(&[("type", "elements"), ("fields", "slug")]).await;
+        assert_eq!(total, "1");
+        assert_eq!(json, serde_json::json!([{ "slug": "html" }]));
+    }
+
+    #[tokio::test]
+    async fn test_docs_list_pagination_boundaries() {
+        let (_, full) = docs_list_json(&[]).await;
+        let slugs = |json: &serde_json::Value| -> Vec<String> {
+            json.as_array().unwrap().iter().map(|doc| doc["slug"].as_str().unwrap().to_string()).collect()
+        };
+        let all = slugs(&full);
+
+        let (total, page) = docs_list_json(&[("offset", "1"), ("limit", "1")]).await;
+        assert_eq!(total, "3");
+        assert_eq!(slugs(&page), all[1..2]);
+        let (_, page) = docs_list_json(&[("offset", "2"), ("limit", "5")]).await;
+        assert_eq!(slugs(&page), all[2..]);
+        let (total, page) = docs_list_json(&[("offset", "3")]).await;
+        assert_eq!(total, "3");
+        assert!(slugs(&page).is_empty());
+        let (_, page) = docs_list_json(&[("limit", "0")]).await;
+        assert!(slugs(&page).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_docs_list_groups_locale_variants() {
+        let response = docs_list(State(test_state()), Query(HashMap::new())).await;
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let docs = json.as_array().unwrap();
+
+        assert_eq!(docs.len(), 3);
+        let javascript = docs.iter().find(|doc| doc["slug"] == "javascript").unwrap();
+        assert_eq!(javascript["name"], "JavaScript");
+        assert_eq!(javascript["locales"], serde_json::json!(["en", "zh-CN"]));
+        let html = docs.iter().find(|doc| doc["slug"] == "html").unwrap();
+        assert_eq!(html["locales"], serde_json::json!(["en"]));
+    }
+
+    #[test]
+    fn test_typeahead_doc_name_links_to_landing() {
+        let state = test_state();
+
+        let groups = typeahead_groups(&state.doc_registry.read().unwrap(), "CSS", 20, None);
+        assert_eq!(groups[0]["doc"]["slug"], "css");
+        assert_eq!(groups[0]["doc"]["url"], "/docs/css/color");
+
+        let groups = typeahead_groups(&state.doc_registry.read().unwrap(), "html", 20, None);
+        assert_eq!(groups[0]["doc"]["slug"], "html");
+        assert_eq!(groups[0]["doc"]["url"], "/docs/html");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_job() {
+        let state = test_state();
+        let id = state.jobs.spawn("fixture", |cancel| async move {
+            cancel.cancelled().await;
+            Err(crate::core::error::Error::Cancelled)
+        });
+
+        let response = cancel_job(State(state.clone()), Path(id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["state"], "cancelled");
+
+        let response = job_status(State(state.clone()), Path(id)).await;
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["state"], "cancelled");
+
+        // 再次取消时任务已不在运行
+        assert_eq!(cancel_job(State(state.clone()), Path(id)).await.status(), StatusCode::CONFLICT);
+        assert_eq!(cancel_job(State(state), Path(id + 1)).await.status(), StatusCode::NOT_FOUND);
+    }
+}
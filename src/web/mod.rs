@@ -1,8 +1,13 @@
 //! Web u670du52a1u5668u6a21u5757
 
+pub mod assets;
+pub mod compression;
+pub mod custom_assets;
 pub mod routes;
 pub mod server;
 pub mod handlers;
+pub mod page_cache;
+pub mod registry_reloader;
 pub mod static_files;
 
 pub use server::Server;
\ No newline at end of file
@@ -0,0 +1,630 @@
+//! 文档页面缓存
+//!
+//! 缓存从磁盘读取的页面内容和 ETag。页面重新生成时通过 `page.updated`
+//! 事件或文件修改时间失效，只丢弃受影响的页面。
+//!
+//! 设置内存上限（`cache.max_memory_mb`）时，页面内容超过上限后按最近最少
+//! 使用的顺序淘汰，所有文档的页面共用同一个上限。淘汰一次降到上限的 90%，
+//! 避免每次加载新页面都要扫描缓存。只淘汰页面内容，文档索引不受影响。
+//!
+//! 没有单独页面文件的文档从 `db.json` 中读取页面，`db.json` 按文档整体缓存，
+//! 文件修改后重新读取，不计入内存上限。
+//!
+//! 文档整体被替换（`doc.reloaded` 事件）时丢弃该文档的所有页面和 `db.json`，
+//! 新版本的文件修改时间可能与旧版本相同。
+//!
+//! 开启预压缩（`cache.precompress`）时，缓存页面的同时保存 gzip 压缩的副本，
+//! 页面处理器可以直接返回压缩后的内容，不必每次请求都重新压缩。压缩副本同样
+//! 计入内存上限。在 tokio 运行时中压缩放到阻塞线程池里进行，完成后再补进缓存，
+//! 在此之前的请求返回原始内容。
+
+use crate::core::error::Result;
+use crate::core::instrumentable::{self, InstrumentInfo};
+use crate::core::page_db::PageDb;
+use crate::docs::documentation::cache_key;
+use crate::docs::PAGE_UPDATED_EVENT;
+use crate::web::compression;
+use crate::web::registry_reloader::DOC_RELOADED_EVENT;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use axum::body::Bytes;
+
+/// 缓存的页面
+#[derive(Debug, Clone)]
+pub struct CachedPage {
+    /// 页面内容
+    pub content: Arc<String>,
+    /// 页面 ETag
+    pub etag: String,
+    /// gzip 压缩后的页面内容，只在开启预压缩并且压缩后更小时保存
+    pub gzip: Option<Bytes>,
+    /// 读取时文件的修改时间
+    modified: Option<SystemTime>,
+}
+
+impl CachedPage {
+    /// 页面内容和压缩副本占用的字节数
+    pub fn size(&self) -> usize {
+        self.content.len() + self.gzip_size()
+    }
+
+    /// 压缩副本占用的字节数
+    fn gzip_size(&self) -> usize {
+        self.gzip.as_ref().map_or(0, Bytes::len)
+    }
+}
+
+/// 缓存中的页面及其最近一次使用的时间
+#[derive(Debug)]
+struct CacheEntry {
+    page: CachedPage,
+    last_used: AtomicU64,
+}
+
+/// 缓存的 db.json
+#[derive(Debug)]
+struct CachedDb {
+    db: Arc<PageDb>,
+    /// 读取时文件的修改时间
+    modified: Option<SystemTime>,
+}
+
+/// 页面缓存的统计信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageCacheStats {
+    /// 缓存的页面数量
+    pub entries: usize,
+    /// 缓存页面内容占用的字节数（包括压缩副本）
+    pub bytes: usize,
+    /// 其中压缩副本占用的字节数
+    pub gzip_bytes: usize,
+    /// 内存上限（字节），None 表示不限制
+    pub max_bytes: Option<usize>,
+    /// 命中次数
+    pub hits: u64,
+    /// 未命中次数（包括文件已修改的情况）
+    pub misses: u64,
+    /// 因超过内存上限淘汰的页面数
+    pub evictions: u64,
+    /// 命中率，没有请求时为 0
+    pub hit_rate: f64,
+    /// 直接返回压缩副本的响应数
+    pub gzip_served: u64,
+    /// 直接返回压缩副本比返回原始内容少传输的字节数
+    pub gzip_bytes_saved: u64,
+}
+
+/// 按文档和页面路径缓存的页面
+#[derive(Default)]
+pub struct PageCache {
+    pages: RwLock<HashMap<(String, String), CacheEntry>>,
+    /// 按文档缓存的 db.json
+    dbs: RwLock<HashMap<String, CachedDb>>,
+    /// 缓存的页面数量
+    entries: AtomicUsize,
+    /// 缓存页面内容占用的字节数
+    bytes: AtomicUsize,
+    /// 其中压缩副本占用的字节数
+    gzip_bytes: AtomicUsize,
+    /// 页面内容的内存上限（字节）
+    max_bytes: Option<usize>,
+    /// 是否同时缓存 gzip 压缩的页面内容
+    precompress: bool,
+    /// 使用顺序计数，用于找出最近最少使用的页面
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    gzip_served: AtomicU64,
+    gzip_bytes_saved: AtomicU64,
+}
+
+impl PageCache {
+    /// 创建不限制内存的页面缓存
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建页面内容最多占用 `max_bytes` 字节的缓存，None 表示不限制
+    pub fn with_budget(max_bytes: Option<usize>) -> Self {
+        Self {
+            max_bytes,
+            ..Self::default()
+        }
+    }
+
+    /// 设置是否在缓存页面时同时保存 gzip 压缩的副本
+    pub fn with_precompression(mut self, precompress: bool) -> Self {
+        self.precompress = precompress;
+        self
+    }
+
+    /// 获取页面，缓存缺失或文件已修改时从磁盘读取
+    ///
+    /// 开启预压缩时，在 tokio 运行时中读取的页面先不带压缩副本返回，副本在阻塞线程池中生成。
+    pub fn get_or_load(self: &Arc<Self>, doc: &str, path: &str, file: &Path) -> std::io::Result<CachedPage> {
+        let key = (doc.to_string(), normalize_path(path));
+        let modified = std::fs::metadata(file).and_then(|m| m.modified()).ok();
+
+        if let Some(entry) = self.pages.read().unwrap().get(&key) {
+            if entry.page.modified == modified {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(entry.page.clone());
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let content = std::fs::read_to_string(file)?;
+        // 没有运行时（如命令行中）时直接压缩
+        let runtime = tokio::runtime::Handle::try_current().ok().filter(|_| self.precompress);
+        let page = CachedPage {
+            etag: etag_for(&content),
+            gzip: if self.precompress && runtime.is_none() { gzip(&content) } else { None },
+            content: Arc::new(content),
+            modified,
+        };
+        let size = page.size();
+
+        let mut pages = self.pages.write().unwrap();
+        // 超过上限的页面不缓存，也不为它淘汰其他页面
+        if self.max_bytes.is_some_and(|max| size > max) {
+            if let Some(old) = pages.remove(&key) {
+                self.entries.fetch_sub(1, Ordering::Relaxed);
+                self.release(&old.page);
+            }
+            return Ok(page);
+        }
+        let entry = CacheEntry {
+            page: page.clone(),
+            last_used: AtomicU64::new(self.tick()),
+        };
+        if let Some(old) = pages.insert(key.clone(), entry) {
+            self.release(&old.page);
+        } else {
+            self.entries.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+        self.gzip_bytes.fetch_add(page.gzip_size(), Ordering::Relaxed);
+        self.enforce_budget(&mut pages, &key);
+        drop(pages);
+
+        if let Some(runtime) = runtime {
+            let cache = Arc::clone(self);
+            let content = page.content.clone();
+            runtime.spawn_blocking(move || {
+                if let Some(compressed) = gzip(&content) {
+                    cache.store_gzip(&key, &content, compressed);
+                }
+            });
+        }
+        Ok(page)
+    }
+
+    /// 保存在后台生成的压缩副本，页面在压缩期间已被替换或丢弃时不保存
+    fn store_gzip(&self, key: &(String, String), content: &Arc<String>, compressed: Bytes) {
+        let mut pages = self.pages.write().unwrap();
+        let Some(entry) = pages.get_mut(key) else {
+            return;
+        };
+        if !Arc::ptr_eq(&entry.page.content, content) || entry.page.gzip.is_some() {
+            return;
+        }
+        self.bytes.fetch_add(compressed.len(), Ordering::Relaxed);
+        self.gzip_bytes.fetch_add(compressed.len(), Ordering::Relaxed);
+        entry.page.gzip = Some(compressed);
+        self.enforce_budget(&mut pages, key);
+    }
+
+    /// 获取文档的 db.json，缓存缺失或文件已修改时从磁盘读取
+    ///
+    /// 文件不存在时返回 None，无法读取或解析时返回错误，两种情况都会丢弃之前缓存的内容。
+    pub fn get_or_load_db(&self, doc: &str, file: &Path) -> Result<Option<Arc<PageDb>>> {
+        let modified = std::fs::metadata(file).and_then(|m| m.modified()).ok();
+        if let Some(cached) = self.dbs.read().unwrap().get(doc) {
+            if modified.is_some() && cached.modified == modified {
+                return Ok(Some(cached.db.clone()));
+            }
+        }
+
+        let mut dbs = self.dbs.write().unwrap();
+        if !file.exists() {
+            dbs.remove(doc);
+            return Ok(None);
+        }
+        match PageDb::load(file) {
+            Ok(db) => {
+                let db = Arc::new(db);
+                dbs.insert(doc.to_string(), CachedDb { db: db.clone(), modified });
+                Ok(Some(db))
+            }
+            Err(e) => {
+                dbs.remove(doc);
+                Err(e)
+            }
+        }
+    }
+
+    /// 超过内存上限时淘汰最近最少使用的页面，直到降到上限的 90%
+    fn enforce_budget(&self, pages: &mut HashMap<(String, String), CacheEntry>, keep: &(String, String)) {
+        let Some(max_bytes) = self.max_bytes else {
+            return;
+        };
+        if self.bytes.load(Ordering::Relaxed) <= max_bytes {
+            return;
+        }
+
+        let target = max_bytes / 10 * 9;
+        let mut candidates: Vec<(u64, (String, String))> = pages
+            .iter()
+            .filter(|(key, _)| *key != keep)
+            .map(|(key, entry)| (entry.last_used.load(Ordering::Relaxed), key.clone()))
+            .collect();
+        candidates.sort_unstable_by_key(|(last_used, _)| *last_used);
+        for (_, key) in candidates {
+            if self.bytes.load(Ordering::Relaxed) <= target {
+                break;
+            }
+            if let Some(old) = pages.remove(&key) {
+                self.entries.fetch_sub(1, Ordering::Relaxed);
+                self.release(&old.page);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 从计数中减去丢弃的页面占用的字节数
+    fn release(&self, page: &CachedPage) {
+        self.bytes.fetch_sub(page.size(), Ordering::Relaxed);
+        self.gzip_bytes.fetch_sub(page.gzip_size(), Ordering::Relaxed);
+    }
+
+    /// 下一个使用顺序
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// 记录一次直接返回压缩副本的响应
+    pub fn record_gzip_served(&self, page: &CachedPage) {
+        self.gzip_served.fetch_add(1, Ordering::Relaxed);
+        let saved = page.content.len().saturating_sub(page.gzip_size());
+        self.gzip_bytes_saved.fetch_add(saved as u64, Ordering::Relaxed);
+    }
+
+    /// 统计信息（不加锁）
+    pub fn stats(&self) -> PageCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let requests = hits + misses;
+        PageCacheStats {
+            entries: self.entry_count(),
+            bytes: self.memory_usage(),
+            gzip_bytes: self.gzip_bytes.load(Ordering::Relaxed),
+            max_bytes: self.max_bytes,
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            hit_rate: if requests == 0 { 0.0 } else { hits as f64 / requests as f64 },
+            gzip_served: self.gzip_served.load(Ordering::Relaxed),
+            gzip_bytes_saved: self.gzip_bytes_saved.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 丢弃指定页面的缓存
+    pub fn invalidate(&self, doc: &str, path: &str) {
+        let removed = self
+            .pages
+            .write()
+            .unwrap()
+            .remove(&(doc.to_string(), normalize_path(path)));
+        if let Some(old) = removed {
+            self.entries.fetch_sub(1, Ordering::Relaxed);
+            self.release(&old.page);
+        }
+    }
+
+    /// 丢弃指定文档所有页面和 db.json 的缓存
+    ///
+    /// `doc` 为规范的文档目录名，命名空间中的文档同时给出 `namespace`，
+    /// 与 [`Documentation::cache_key`](crate::docs::Documentation::cache_key) 一致。
+    pub fn invalidate_doc(&self, namespace: Option<&str>, doc: &str) {
+        let doc = &cache_key(namespace, doc);
+        self.pages.write().unwrap().retain(|(page_doc, _), entry| {
+            if page_doc != doc {
+                return true;
+            }
+            self.entries.fetch_sub(1, Ordering::Relaxed);
+            self.release(&entry.page);
+            false
+        });
+        self.dbs.write().unwrap().remove(doc.as_str());
+    }
+
+    /// 缓存的页面数量（不加锁）
+    pub fn entry_count(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+
+    /// 缓存页面内容占用的字节数（不加锁）
+    pub fn memory_usage(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// 是否缓存了指定页面
+    pub fn contains(&self, doc: &str, path: &str) -> bool {
+        self.pages
+            .read()
+            .unwrap()
+            .contains_key(&(doc.to_string(), normalize_path(path)))
+    }
+
+    /// 订阅页面更新和文档重新加载事件，收到事件时丢弃对应页面或文档的缓存
+    pub fn subscribe_to_updates(self: &Arc<Self>) {
+        let weak = Arc::downgrade(self);
+        instrumentable::subscribe(DOC_RELOADED_EVENT, move |info: &InstrumentInfo| {
+            if let (Some(cache), Some(doc)) = (weak.upgrade(), info.payload.get("doc")) {
+                cache.invalidate_doc(info.payload.get("namespace").map(String::as_str), doc);
+            }
+        });
+        let cache = Arc::downgrade(self);
+        instrumentable::subscribe(PAGE_UPDATED_EVENT, move |info: &InstrumentInfo| {
+            if let (Some(cache), Some(doc), Some(path)) = (
+                cache.upgrade(),
+                info.payload.get("doc"),
+                info.payload.get("path"),
+            ) {
+                cache.invalidate(doc, path);
+            }
+        });
+    }
+}
+
+/// 统一页面路径格式
+fn normalize_path(path: &str) -> String {
+    path.trim_matches('/').to_string()
+}
+
+/// gzip 压缩页面内容，压缩后不比原始内容小时返回 None
+fn gzip(content: &str) -> Option<Bytes> {
+    let compressed = compression::gzip(content.as_bytes()).ok()?;
+    (compressed.len() < content.len()).then(|| Bytes::from(compressed))
+}
+
+/// 根据页面内容计算 ETag
+fn etag_for(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("\"{:x}\"", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_invalidate_only_affected_page() {
+        let dir = TempDir::new().unwrap();
+        let page_a = dir.path().join("a.html");
+        let page_b = dir.path().join("b.html");
+        std::fs::write(&page_a, "A").unwrap();
+        std::fs::write(&page_b, "B").unwrap();
+
+        let cache = Arc::new(PageCache::new());
+        let first = cache.get_or_load("doc", "a", &page_a).unwrap();
+        cache.get_or_load("doc", "b", &page_b).unwrap();
+
+        cache.invalidate("doc", "/a/");
+        assert!(!cache.contains("doc", "a"));
+        assert!(cache.contains("doc", "b"));
+
+        std::fs::write(&page_a, "A2").unwrap();
+        let second = cache.get_or_load("doc", "a", &page_a).unwrap();
+        assert_eq!(second.content.as_str(), "A2");
+        assert_ne!(first.etag, second.etag);
+    }
+
+    #[test]
+    fn test_invalidate_doc() {
+        let dir = TempDir::new().unwrap();
+        let page = dir.path().join("a.html");
+        let db = dir.path().join("db.json");
+        std::fs::write(&page, "A").unwrap();
+        std::fs::write(&db, r#"{"b": "B"}"#).unwrap();
+
+        let cache = Arc::new(PageCache::new());
+        cache.get_or_load("css", "a", &page).unwrap();
+        cache.get_or_load("html", "a", &page).unwrap();
+        let first = cache.get_or_load_db("css", &db).unwrap().unwrap();
+
+        cache.get_or_load("t/platform/css", "a", &page).unwrap();
+        cache.invalidate_doc(None, "css");
+        assert!(!cache.contains("css", "a"));
+        assert!(cache.contains("html", "a"));
+        assert!(cache.contains("t/platform/css", "a"));
+        cache.invalidate_doc(Some("platform"), "css");
+        assert!(!cache.contains("t/platform/css", "a"));
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(cache.stats().bytes, 1);
+        assert!(!Arc::ptr_eq(&first, &cache.get_or_load_db("css", &db).unwrap().unwrap()));
+    }
+
+    #[test]
+    fn test_db_is_cached_until_modified() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("db.json");
+        let cache = PageCache::new();
+        assert!(cache.get_or_load_db("doc", &file).unwrap().is_none());
+
+        std::fs::write(&file, r#"{"a": "A"}"#).unwrap();
+        let first = cache.get_or_load_db("doc", &file).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.get_or_load_db("doc", &file).unwrap().unwrap()));
+
+        // 修改时间变化后重新读取
+        std::fs::write(&file, r#"{"a": "A2", "b": "B"}"#).unwrap();
+        let modified = std::fs::metadata(&file).unwrap().modified().unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(modified + std::time::Duration::from_secs(1))
+            .unwrap();
+        let second = cache.get_or_load_db("doc", &file).unwrap().unwrap();
+        assert_eq!(second.len(), 2);
+    }
+
+    #[test]
+    fn test_size_accounting() {
+        let dir = TempDir::new().unwrap();
+        let page = dir.path().join("a.html");
+        std::fs::write(&page, "12345").unwrap();
+
+        let cache = Arc::new(PageCache::new());
+        cache.get_or_load("doc", "a", &page).unwrap();
+        assert_eq!(cache.entry_count(), 1);
+        assert_eq!(cache.memory_usage(), 5);
+
+        // 失效后重新加载，按新内容计算大小
+        std::fs::write(&page, "123").unwrap();
+        cache.invalidate("doc", "a");
+        cache.get_or_load("doc", "a", &page).unwrap();
+        assert_eq!(cache.entry_count(), 1);
+        assert_eq!(cache.memory_usage(), 3);
+
+        cache.invalidate("doc", "a");
+        assert_eq!(cache.entry_count(), 0);
+        assert_eq!(cache.memory_usage(), 0);
+    }
+
+    #[test]
+    fn test_precompressed_copy_counts_toward_budget() {
+        use std::io::Read;
+        let dir = TempDir::new().unwrap();
+        let page = dir.path().join("a.html");
+        let tiny = dir.path().join("b.html");
+        let content = "<p>compressible</p>".repeat(100);
+        std::fs::write(&page, &content).unwrap();
+        std::fs::write(&tiny, "B").unwrap();
+
+        let cache = Arc::new(PageCache::new().with_precompression(true));
+        let cached = cache.get_or_load("doc", "a", &page).unwrap();
+        let gzip = cached.gzip.clone().unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&gzip[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, content);
+        // 压缩后不会更小的页面不保存副本
+        assert!(cache.get_or_load("doc", "b", &tiny).unwrap().gzip.is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.gzip_bytes, gzip.len());
+        assert_eq!(stats.bytes, content.len() + 1 + gzip.len());
+
+        cache.record_gzip_served(&cached);
+        assert_eq!(cache.stats().gzip_served, 1);
+        assert_eq!(cache.stats().gzip_bytes_saved, (content.len() - gzip.len()) as u64);
+
+        cache.invalidate_doc(None, "doc");
+        assert_eq!(cache.stats().bytes, 0);
+        assert_eq!(cache.stats().gzip_bytes, 0);
+        assert!(Arc::new(PageCache::new()).get_or_load("doc", "a", &page).unwrap().gzip.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gzip_is_added_off_the_executor() {
+        let dir = TempDir::new().unwrap();
+        let page = dir.path().join("a.html");
+        let content = "<p>compressible</p>".repeat(100);
+        std::fs::write(&page, &content).unwrap();
+
+        let cache = Arc::new(PageCache::new().with_precompression(true));
+        assert!(cache.get_or_load("doc", "a", &page).unwrap().gzip.is_none());
+        let mut cached = cache.get_or_load("doc", "a", &page).unwrap();
+        for _ in 0..200 {
+            if cached.gzip.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            cached = cache.get_or_load("doc", "a", &page).unwrap();
+        }
+        let gzip = cached.gzip.unwrap();
+        assert_eq!(cache.stats().gzip_bytes, gzip.len());
+        assert_eq!(cache.stats().bytes, content.len() + gzip.len());
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    /// 按 Zipf 分布（s = 1）生成页面编号的确定性序列
+    fn zipf_sequence(pages: usize, count: usize) -> Vec<usize> {
+        let weights: Vec<f64> = (1..=pages).map(|rank| 1.0 / rank as f64).collect();
+        let total: f64 = weights.iter().sum();
+        let mut cumulative = Vec::with_capacity(pages);
+        let mut sum = 0.0;
+        for weight in weights {
+            sum += weight / total;
+            cumulative.push(sum);
+        }
+
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let sample = (state >> 11) as f64 / (1u64 << 53) as f64;
+                cumulative.partition_point(|&c| c < sample).min(pages - 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_budget_is_respected_under_zipfian_load() {
+        let dir = TempDir::new().unwrap();
+        let page_size = 1024;
+        let files: Vec<_> = (0..500)
+            .map(|i| {
+                let file = dir.path().join(format!("{}.html", i));
+                std::fs::write(&file, format!("{:0width$}", i, width = page_size)).unwrap();
+                file
+            })
+            .collect();
+
+        // 上限约为 100 个页面，页面分布在两个文档中
+        let budget = 100 * page_size;
+        let cache = Arc::new(PageCache::with_budget(Some(budget)));
+        let mut peak = 0;
+        for index in zipf_sequence(files.len(), 20_000) {
+            let doc = if index % 2 == 0 { "even" } else { "odd" };
+            let page = cache.get_or_load(doc, &index.to_string(), &files[index]).unwrap();
+            assert_eq!(page.content.len(), page_size);
+            peak = peak.max(cache.memory_usage());
+        }
+
+        let stats = cache.stats();
+        assert!(peak <= budget + page_size, "peak {} exceeds budget {}", peak, budget);
+        assert!(stats.bytes <= budget);
+        assert!(stats.evictions > 0);
+        assert_eq!(stats.entries * page_size, stats.bytes);
+        assert_eq!(stats.hits + stats.misses, 20_000);
+        // 前 100 个页面约占 76% 的请求，LRU 应接近这一命中率
+        assert!(stats.hit_rate > 0.6, "hit rate {}", stats.hit_rate);
+    }
+
+    #[test]
+    fn test_oversized_page_is_not_cached() {
+        let dir = TempDir::new().unwrap();
+        let small = dir.path().join("small.html");
+        let large = dir.path().join("large.html");
+        std::fs::write(&small, "small").unwrap();
+        std::fs::write(&large, "x".repeat(100)).unwrap();
+
+        let cache = Arc::new(PageCache::with_budget(Some(50)));
+        cache.get_or_load("doc", "small", &small).unwrap();
+        assert_eq!(cache.get_or_load("doc", "large", &large).unwrap().content.len(), 100);
+        assert!(cache.contains("doc", "small"));
+        assert!(!cache.contains("doc", "large"));
+        assert_eq!(cache.stats().evictions, 0);
+    }
+}
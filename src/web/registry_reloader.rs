@@ -0,0 +1,518 @@
+//! 文档注册表的增量重新加载
+//!
+//! 文档目录中的文件变化按目录合并：同一目录在窗口期内的多次变化只触发
+//! 一次重新加载，并且只重新读取该文档。目录中存在抓取日志时说明抓取
+//! 尚未结束，重新加载会推迟到日志删除之后。
+//!
+//! 其他进程（如 `docs sync`）对文档目录的修改不会产生事件，后台任务定期
+//! 检查各文档目录的链接目标和索引文件的修改时间（[`RegistryReloader::scan`]），
+//! 发现变化时同样按目录合并后重新加载。重新加载后发出 [`DOC_RELOADED_EVENT`]，
+//! 页面缓存据此丢弃该文档的所有页面。
+//!
+//! 命名空间的目录（见 [`RegistryReloader::with_namespaces`]）同样被检查，其中的
+//! 变化只重新加载该命名空间中的文档。
+
+use crate::core::doc::{DB_FILENAME, INDEX_FILENAME};
+use crate::core::instrumentable::{self, instrument, InstrumentInfo};
+use crate::core::scraper::page_log::WAL_FILENAME;
+use crate::docs::{DocRegistry, PAGE_UPDATED_EVENT};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 默认的合并窗口
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// 文档重新加载后发出的事件，payload 中的 `doc` 为规范的文档目录名，
+/// 命名空间中的文档还带有 `namespace`
+pub const DOC_RELOADED_EVENT: &str = "doc.reloaded";
+
+/// 待重新加载的文档：命名空间（None 表示全局文档）和文档目录名
+type DocKey = (Option<String>, String);
+
+/// 后台检查待处理变化的最短间隔
+const MIN_TICK: Duration = Duration::from_millis(100);
+
+/// 重新加载计数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReloadStats {
+    /// 收到的变化事件数
+    pub events: usize,
+    /// 实际重新加载的文档数
+    pub reloads: usize,
+    /// 因抓取未结束而推迟的次数
+    pub deferred: usize,
+    /// 等待重新加载的文档数
+    pub pending: usize,
+}
+
+/// 等待重新加载的文档目录
+struct PendingDoc {
+    /// 最近一次变化的时间
+    last_change: Instant,
+    /// 是否已因抓取未结束而推迟过
+    deferred: bool,
+}
+
+/// 文档目录在磁盘上的状态，任何一项变化都说明目录被修改或替换
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DirStamp {
+    /// 目录为符号链接时的链接目标
+    target: Option<PathBuf>,
+    /// 索引和页面数据库的修改时间及大小
+    files: Vec<Option<(SystemTime, u64)>>,
+}
+
+impl DirStamp {
+    fn read(dir: &Path) -> Self {
+        let files = [INDEX_FILENAME, DB_FILENAME, "meta.json"]
+            .iter()
+            .map(|name| {
+                std::fs::metadata(dir.join(name))
+                    .ok()
+                    .and_then(|m| m.modified().ok().map(|modified| (modified, m.len())))
+            })
+            .collect();
+        Self {
+            target: std::fs::read_link(dir).ok(),
+            files,
+        }
+    }
+}
+
+/// 按文档目录合并变化并重新加载注册表
+pub struct RegistryReloader {
+    registry: Arc<RwLock<DocRegistry>>,
+    /// 文档根目录，第一项可写，其余只读
+    roots: Vec<PathBuf>,
+    /// 命名空间名和命名空间的目录
+    namespaces: Vec<(String, PathBuf)>,
+    /// 合并窗口
+    window: Duration,
+    pending: Mutex<HashMap<DocKey, PendingDoc>>,
+    /// 上次检查时各文档目录（按命名空间、根目录和目录名）的状态
+    stamps: Mutex<HashMap<(Option<String>, usize, String), DirStamp>>,
+    events: AtomicUsize,
+    reloads: AtomicUsize,
+    deferred: AtomicUsize,
+}
+
+impl RegistryReloader {
+    /// 创建新的重新加载器
+    pub fn new(registry: Arc<RwLock<DocRegistry>>, roots: Vec<PathBuf>) -> Self {
+        let reloader = Self {
+            registry,
+            roots,
+            namespaces: Vec::new(),
+            window: DEFAULT_DEBOUNCE,
+            pending: Mutex::new(HashMap::new()),
+            stamps: Mutex::new(HashMap::new()),
+            events: AtomicUsize::new(0),
+            reloads: AtomicUsize::new(0),
+            deferred: AtomicUsize::new(0),
+        };
+        // 记录当前状态作为之后检查的基准
+        *reloader.stamps.lock().unwrap() = reloader.read_stamps();
+        reloader
+    }
+
+    /// 设置合并窗口
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// 同时检查命名空间的目录，`namespaces` 为命名空间名和目录
+    pub fn with_namespaces(mut self, namespaces: Vec<(String, PathBuf)>) -> Self {
+        self.namespaces = namespaces;
+        *self.stamps.get_mut().unwrap() = self.read_stamps();
+        self
+    }
+
+    /// 记录文档目录中的一次变化
+    pub fn record_change(&self, doc_dir: &str) {
+        self.record((None, doc_dir.to_string()));
+    }
+
+    /// 记录命名空间中文档目录的一次变化
+    pub fn record_namespace_change(&self, namespace: &str, doc_dir: &str) {
+        self.record((Some(namespace.to_string()), doc_dir.to_string()));
+    }
+
+    fn record(&self, (namespace, doc_dir): DocKey) {
+        let doc_dir = doc_dir.trim_matches('/');
+        if doc_dir.is_empty() {
+            return;
+        }
+        self.events.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().unwrap().insert(
+            (namespace, doc_dir.to_string()),
+            PendingDoc {
+                last_change: Instant::now(),
+                deferred: false,
+            },
+        );
+    }
+
+    /// 记录某个文件的变化，文件不在任何文档根目录中时忽略
+    ///
+    /// 命名空间的目录位于文档根目录之中，因此先按命名空间的目录查找。
+    pub fn record_path(&self, path: &Path) {
+        let first_dir = |root: &Path| {
+            path.strip_prefix(root)
+                .ok()
+                .and_then(|rel| rel.components().next())
+                .and_then(|c| c.as_os_str().to_str().map(str::to_string))
+        };
+        let key = self
+            .namespaces
+            .iter()
+            .find_map(|(name, root)| first_dir(root).map(|dir| (Some(name.clone()), dir)))
+            .or_else(|| self.roots.iter().find_map(|root| first_dir(root).map(|dir| (None, dir))));
+        if let Some(key) = key {
+            self.record(key);
+        }
+    }
+
+    /// 全局的根目录和命名空间的目录，附带所属的命名空间
+    fn all_roots(&self) -> impl Iterator<Item = (Option<&str>, usize, &Path)> {
+        let global = self.roots.iter().enumerate().map(|(i, root)| (None, i, root.as_path()));
+        let namespaced = self.namespaces.iter().map(|(name, root)| (Some(name.as_str()), 0, root.as_path()));
+        global.chain(namespaced)
+    }
+
+    /// 读取所有根目录中文档目录的状态，跳过以 `.` 开头的暂存目录
+    fn read_stamps(&self) -> HashMap<(Option<String>, usize, String), DirStamp> {
+        let mut stamps = HashMap::new();
+        for (namespace, i, root) in self.all_roots() {
+            let Ok(entries) = std::fs::read_dir(root) else {
+                continue;
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let Some(dirname) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if dirname.starts_with('.') || !entry.path().is_dir() {
+                    continue;
+                }
+                stamps.insert((namespace.map(str::to_string), i, dirname), DirStamp::read(&entry.path()));
+            }
+        }
+        stamps
+    }
+
+    /// 检查文档目录在磁盘上的变化，记录新增、删除、替换或索引被修改的目录
+    ///
+    /// 返回发现变化的目录数。变化同样按合并窗口处理，由 [`Self::flush`] 重新加载。
+    pub fn scan(&self) -> usize {
+        let current = self.read_stamps();
+        let changed: Vec<DocKey> = {
+            let mut stamps = self.stamps.lock().unwrap();
+            let mut changed: Vec<DocKey> = current
+                .iter()
+                .filter(|(key, stamp)| stamps.get(*key) != Some(*stamp))
+                .map(|((namespace, _, dirname), _)| (namespace.clone(), dirname.clone()))
+                .collect();
+            changed.extend(
+                stamps
+                    .keys()
+                    .filter(|key| !current.contains_key(*key))
+                    .map(|(namespace, _, dirname)| (namespace.clone(), dirname.clone())),
+            );
+            *stamps = current;
+            changed.sort();
+            changed.dedup();
+            changed
+        };
+        let count = changed.len();
+        for key in changed {
+            self.record(key);
+        }
+        count
+    }
+
+    /// 重新加载窗口期内没有新变化的文档，返回重新加载的文档数
+    pub fn flush(&self, now: Instant) -> usize {
+        let due: Vec<DocKey> = {
+            let mut pending = self.pending.lock().unwrap();
+            let mut due = Vec::new();
+            for (key, doc) in pending.iter_mut() {
+                if now.saturating_duration_since(doc.last_change) < self.window {
+                    continue;
+                }
+                if self.scrape_in_progress(key) {
+                    if !doc.deferred {
+                        doc.deferred = true;
+                        self.deferred.fetch_add(1, Ordering::Relaxed);
+                    }
+                    continue;
+                }
+                due.push(key.clone());
+            }
+            for key in &due {
+                pending.remove(key);
+            }
+            due
+        };
+
+        if due.is_empty() {
+            return 0;
+        }
+
+        {
+            let mut registry = self.registry.write().unwrap();
+            for (namespace, doc_dir) in &due {
+                match namespace.as_deref().and_then(|name| self.namespace_root(name).map(|root| (name, root))) {
+                    Some((name, root)) => {
+                        registry.reload_namespace_doc(name, root, doc_dir);
+                        log::info!("已重新加载文档: {} (命名空间 {})", doc_dir, name);
+                    }
+                    None => {
+                        registry.reload_doc(&self.roots, doc_dir);
+                        log::info!("已重新加载文档: {}", doc_dir);
+                    }
+                }
+            }
+        }
+        // 释放注册表的锁之后再通知，订阅者可以访问注册表
+        for (namespace, doc_dir) in &due {
+            let mut payload = HashMap::from([("doc".to_string(), DocRegistry::canonical_dirname(doc_dir))]);
+            if let Some(namespace) = namespace {
+                payload.insert("namespace".to_string(), namespace.clone());
+            }
+            instrument(DOC_RELOADED_EVENT, payload, || ());
+        }
+        self.reloads.fetch_add(due.len(), Ordering::Relaxed);
+        due.len()
+    }
+
+    /// 获取重新加载计数（不访问注册表）
+    pub fn stats(&self) -> ReloadStats {
+        ReloadStats {
+            events: self.events.load(Ordering::Relaxed),
+            reloads: self.reloads.load(Ordering::Relaxed),
+            deferred: self.deferred.load(Ordering::Relaxed),
+            pending: self.pending.lock().unwrap().len(),
+        }
+    }
+
+    /// 订阅页面更新事件，将其视为对应文档目录的变化
+    pub fn subscribe_to_updates(self: &Arc<Self>) {
+        let reloader = Arc::downgrade(self);
+        instrumentable::subscribe(PAGE_UPDATED_EVENT, move |info: &InstrumentInfo| {
+            if let (Some(reloader), Some(doc)) = (reloader.upgrade(), info.payload.get("doc")) {
+                match info.payload.get("namespace") {
+                    Some(namespace) => reloader.record_namespace_change(namespace, doc),
+                    None => reloader.record_change(doc),
+                }
+            }
+        });
+    }
+
+    /// 在后台定期处理待重新加载的文档，重新加载器被释放后停止
+    pub fn spawn(self: &Arc<Self>, handle: &tokio::runtime::Handle) {
+        let reloader = Arc::downgrade(self);
+        let tick = (self.window / 4).max(MIN_TICK);
+        handle.spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            loop {
+                interval.tick().await;
+                match reloader.upgrade() {
+                    Some(reloader) => {
+                        reloader.scan();
+                        reloader.flush(Instant::now());
+                    }
+                    None => break,
+                }
+            }
+        });
+    }
+
+    /// 命名空间的目录
+    fn namespace_root(&self, name: &str) -> Option<&Path> {
+        self.namespaces
+            .iter()
+            .find(|(namespace, _)| namespace == name)
+            .map(|(_, root)| root.as_path())
+    }
+
+    /// 文档目录中存在抓取日志时说明抓取尚未结束
+    fn scrape_in_progress(&self, (namespace, doc_dir): &DocKey) -> bool {
+        match namespace {
+            Some(name) => self
+                .namespace_root(name)
+                .is_some_and(|root| root.join(doc_dir).join(WAL_FILENAME).exists()),
+            None => self
+                .roots
+                .iter()
+                .any(|root| root.join(doc_dir).join(WAL_FILENAME).exists()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::registry::write_doc;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn reloader_for(root: &Path) -> RegistryReloader {
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[root]).unwrap();
+        RegistryReloader::new(Arc::new(RwLock::new(registry)), vec![root.to_path_buf()])
+    }
+
+    fn doc_name(reloader: &RegistryReloader, slug: &str) -> Option<String> {
+        let registry = reloader.registry.read().unwrap();
+        registry.find(slug).map(|doc| doc.name.clone())
+    }
+
+    #[test]
+    fn test_burst_is_coalesced_per_doc() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "css", "CSS");
+        write_doc(docs.path(), "html", "HTML");
+        let reloader = reloader_for(docs.path());
+
+        for i in 0..1000 {
+            reloader.record_path(&docs.path().join("css").join(format!("page{}.html", i)));
+            if i % 2 == 0 {
+                reloader.record_change("html");
+            }
+        }
+        reloader.record_path(Path::new("/elsewhere/css/page.html"));
+
+        // 窗口期内不重新加载
+        assert_eq!(reloader.flush(Instant::now()), 0);
+        assert_eq!(reloader.flush(Instant::now() + DEFAULT_DEBOUNCE), 2);
+        assert_eq!(reloader.flush(Instant::now() + DEFAULT_DEBOUNCE * 2), 0);
+
+        let stats = reloader.stats();
+        assert_eq!(stats.events, 1500);
+        assert_eq!(stats.reloads, 2);
+        assert_eq!(stats.pending, 0);
+    }
+
+    #[test]
+    fn test_only_changed_doc_is_reloaded() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "css", "CSS");
+        write_doc(docs.path(), "html", "HTML");
+        let reloader = reloader_for(docs.path());
+
+        write_doc(docs.path(), "css", "CSS 3");
+        write_doc(docs.path(), "html", "HTML 5");
+        write_doc(docs.path(), "javascript", "JavaScript");
+        reloader.record_change("css");
+        reloader.record_change("javascript");
+        reloader.flush(Instant::now() + DEFAULT_DEBOUNCE);
+
+        assert_eq!(doc_name(&reloader, "css").as_deref(), Some("CSS 3"));
+        assert_eq!(doc_name(&reloader, "html").as_deref(), Some("HTML"));
+        assert_eq!(doc_name(&reloader, "javascript").as_deref(), Some("JavaScript"));
+    }
+
+    #[test]
+    fn test_scan_detects_changes_from_other_processes() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "css", "CSS");
+        write_doc(docs.path(), "html", "HTML");
+        let reloader = reloader_for(docs.path());
+        assert_eq!(reloader.scan(), 0);
+
+        // 另一个进程替换了 css，新增了 javascript，暂存目录不算文档
+        std::thread::sleep(Duration::from_millis(20));
+        write_doc(docs.path(), "css", "CSS 3");
+        fs::write(docs.path().join("css").join("meta.json"), r#"{"name":"CSS 3","release":"3"}"#).unwrap();
+        write_doc(docs.path(), "javascript", "JavaScript");
+        write_doc(docs.path(), ".css.partial", "Partial");
+        assert_eq!(reloader.scan(), 2);
+        assert_eq!(reloader.scan(), 0);
+
+        let reloaded = Arc::new(Mutex::new(Vec::new()));
+        let events = reloaded.clone();
+        instrumentable::subscribe(DOC_RELOADED_EVENT, move |info: &InstrumentInfo| {
+            events.lock().unwrap().push(info.payload["doc"].clone());
+        });
+        assert_eq!(reloader.flush(Instant::now() + DEFAULT_DEBOUNCE), 2);
+        assert_eq!(doc_name(&reloader, "css").as_deref(), Some("CSS 3"));
+        assert_eq!(doc_name(&reloader, "javascript").as_deref(), Some("JavaScript"));
+        let reloaded = reloaded.lock().unwrap();
+        assert!(reloaded.contains(&"css".to_string()));
+        assert!(reloaded.contains(&"javascript".to_string()));
+    }
+
+    #[test]
+    fn test_namespaced_doc_is_reloaded() {
+        let docs = TempDir::new().unwrap();
+        let platform = docs.path().join("teams").join("platform");
+        write_doc(docs.path(), "css", "CSS");
+        write_doc(&platform, "css", "Platform CSS");
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+        registry.load_namespace("platform", &platform).unwrap();
+        let reloader = RegistryReloader::new(Arc::new(RwLock::new(registry)), vec![docs.path().to_path_buf()])
+            .with_namespaces(vec![("platform".to_string(), platform.clone())]);
+
+        let reloaded = Arc::new(Mutex::new(Vec::new()));
+        let events = reloaded.clone();
+        instrumentable::subscribe(DOC_RELOADED_EVENT, move |info: &InstrumentInfo| {
+            if let Some(namespace) = info.payload.get("namespace") {
+                events.lock().unwrap().push((namespace.clone(), info.payload["doc"].clone()));
+            }
+        });
+
+        // 文件变化记录为命名空间中的文档，而不是全局的 `teams`
+        std::thread::sleep(Duration::from_millis(20));
+        write_doc(&platform, "css", "Platform CSS 3");
+        reloader.record_path(&platform.join("css").join("db.json"));
+        // 新安装到命名空间中的文档由检查发现
+        write_doc(&platform, "fixture", "Fixture");
+        assert_eq!(reloader.scan(), 2);
+        assert_eq!(reloader.flush(Instant::now() + DEFAULT_DEBOUNCE), 2);
+
+        let registry = reloader.registry.read().unwrap();
+        let names: Vec<&str> = registry.visible(Some("platform")).iter().map(|doc| doc.name.as_str()).collect();
+        assert!(names.contains(&"Platform CSS 3"), "{:?}", names);
+        assert!(names.contains(&"Fixture"), "{:?}", names);
+        assert_eq!(registry.find("css").unwrap().name, "CSS");
+        assert!(registry.find("teams").is_none());
+        let mut reloaded = reloaded.lock().unwrap().clone();
+        reloaded.sort();
+        assert_eq!(
+            reloaded,
+            [
+                ("platform".to_string(), "css".to_string()),
+                ("platform".to_string(), "fixture".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reload_waits_for_running_scrape() {
+        let docs = TempDir::new().unwrap();
+        write_doc(docs.path(), "css", "CSS");
+        let reloader = reloader_for(docs.path());
+
+        let wal = docs.path().join("css").join(WAL_FILENAME);
+        fs::write(&wal, "").unwrap();
+        write_doc(docs.path(), "css", "CSS 3");
+        reloader.record_change("css");
+
+        let later = Instant::now() + DEFAULT_DEBOUNCE;
+        assert_eq!(reloader.flush(later), 0);
+        assert_eq!(reloader.flush(later), 0);
+        assert_eq!(reloader.stats().deferred, 1);
+        assert_eq!(reloader.stats().pending, 1);
+        assert_eq!(doc_name(&reloader, "css").as_deref(), Some("CSS"));
+
+        // 抓取结束后重新加载
+        fs::remove_file(&wal).unwrap();
+        assert_eq!(reloader.flush(later), 1);
+        assert_eq!(doc_name(&reloader, "css").as_deref(), Some("CSS 3"));
+    }
+}
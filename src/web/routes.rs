@@ -1,23 +1,732 @@
 //! u8defu7531u914du7f6e
 
 use axum::Router;
-use axum::routing::get;
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use crate::core::scraper::JobRegistry;
 use crate::core::config::Config;
 use super::handlers;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use super::handlers::AppState;
+use super::assets::AssetStore;
+use super::compression;
+use super::page_cache::PageCache;
+use crate::docs::search::SnippetCache;
+use crate::docs::search_history::SearchHistory;
+use super::registry_reloader::RegistryReloader;
+use super::static_files;
+use crate::docs::DocRegistry;
 
 /// u521bu5efau6240u6709u5e94u7528u7a0bu5e8fu8defu7531
 pub fn create_routes(_config: &Config) -> Router {
+    // 加载文档注册表和各个命名空间，文档目录不存在时以空注册表启动
+    let mut registry = DocRegistry::new();
+    if let Err(e) = registry.load_config(_config) {
+        log::warn!("无法加载文档: {}", e);
+    }
+
+    create_routes_with_registry(_config, registry)
+}
+
+/// 使用已加载的文档注册表创建路由
+pub fn create_routes_with_registry(_config: &Config, registry: DocRegistry) -> Router {
+    // 页面重新生成时丢弃对应的缓存
+    let page_cache = Arc::new(
+        PageCache::with_budget(_config.cache.max_memory_bytes()).with_precompression(_config.cache.precompress),
+    );
+    page_cache.subscribe_to_updates();
+
+    // 文档目录变化时只重新加载受影响的文档
+    let doc_registry = Arc::new(RwLock::new(registry));
+    let namespace_roots = _config
+        .namespaces
+        .keys()
+        .filter_map(|name| _config.namespace_root(name).map(|root| (name.clone(), root)))
+        .collect();
+    let reloader = Arc::new(
+        RegistryReloader::new(doc_registry.clone(), _config.docs_paths.clone()).with_namespaces(namespace_roots),
+    );
+    reloader.subscribe_to_updates();
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        reloader.spawn(&handle);
+    }
+
+    let state = Arc::new(AppState {
+        config: _config.clone(),
+        doc_registry,
+        reloader,
+        page_cache,
+        snippet_cache: Arc::new(SnippetCache::new()),
+        started_at: Instant::now(),
+        assets: Arc::new(AssetStore::new(&_config.assets_output_path)),
+        jobs: Arc::new(JobRegistry::new()),
+        search_history: SearchHistory::from_config(_config).map(Arc::new),
+    });
+
+    // GET 路由同时响应 HEAD 请求，返回相同的响应头但不包含响应体
     Router::new()
         .route("/", get(handlers::index))
         .route("/ping", get(handlers::ping))
+        .route("/robots.txt", get(handlers::robots_txt))
+        .route("/healthz", get(handlers::healthz))
         .route("/search", get(handlers::search))
+        .route("/api/typeahead", get(handlers::typeahead))
+        .route("/api/search/select", post(handlers::search_select))
+        .route("/api/search/history", delete(handlers::clear_search_history))
+        .route("/api/meta", get(handlers::api_meta))
+        .route("/api/jobs/:id", get(handlers::job_status).delete(handlers::cancel_job))
         .route("/docs.json", get(handlers::docs_list))
         .route("/docs/:doc", get(handlers::doc_index))
+        .route("/docs/:doc/", get(handlers::doc_landing))
         .route("/docs/:doc/*page", get(handlers::doc_page))
-        .with_state(Arc::new(AppState {
-            config: _config.clone(),
-            doc_registry: Arc::new(crate::docs::DocRegistry::new()),
-        }))
-}
\ No newline at end of file
+        .route("/t/:namespace/search", get(handlers::namespaced_search))
+        .route("/t/:namespace/docs.json", get(handlers::namespaced_docs_list))
+        .route("/t/:namespace/docs/:doc", get(handlers::namespaced_doc_index))
+        .route("/t/:namespace/docs/:doc/", get(handlers::namespaced_doc_landing))
+        .route("/t/:namespace/docs/:doc/*page", get(handlers::namespaced_doc_page))
+        .route("/assets/*path", get(static_files::serve_asset))
+        .layer(middleware::from_fn_with_state(state.clone(), handlers::robots_tag))
+        .layer(middleware::from_fn(compression::gzip_responses))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs::store_page;
+    use std::net::SocketAddr;
+    use tempfile::TempDir;
+
+    /// 在随机端口启动服务器，返回服务器地址
+    fn spawn_server(config: &Config) -> SocketAddr {
+        let router = create_routes(config);
+        let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
+            .serve(router.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_regenerated_page_is_served_without_restart() {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("fixture");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        store_page(docs.path(), "fixture", "guide/intro", "<p>old</p>").unwrap();
+
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+        let url = format!("http://{}/docs/fixture/guide/intro", addr);
+
+        let first = reqwest::get(&url).await.unwrap();
+        let first_etag = first.headers()["etag"].to_str().unwrap().to_string();
+        assert_eq!(first.text().await.unwrap(), "<p>old</p>");
+
+        // 在另一个任务中重新生成页面
+        let root = docs.path().to_path_buf();
+        tokio::spawn(async move {
+            store_page(&root, "fixture", "/guide/intro/", "<p>new</p>").unwrap();
+        })
+        .await
+        .unwrap();
+
+        let second = reqwest::get(&url).await.unwrap();
+        assert_ne!(second.headers()["etag"].to_str().unwrap(), first_etag);
+        assert_eq!(second.text().await.unwrap(), "<p>new</p>");
+    }
+
+    #[tokio::test]
+    async fn test_head_on_page_route() {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("fixture");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        store_page(docs.path(), "fixture", "intro", "<p>hello</p>").unwrap();
+
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+        let client = reqwest::Client::new();
+
+        let response = client
+            .head(format!("http://{}/docs/fixture/intro", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().contains_key("etag"));
+        assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+        assert_eq!(response.headers()["content-length"], "12");
+        assert!(response.bytes().await.unwrap().is_empty());
+
+        let response = client
+            .head(format!("http://{}/docs.json", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert!(response.bytes().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_page_served_from_db_json_in_either_format() {
+        let docs = TempDir::new().unwrap();
+        for (name, db) in [
+            ("v1", r#"{"intro": "<p>from v1</p>"}"#),
+            ("v2", r#"{"__meta": {"format": 2}, "pages": {"intro": {"content": "<p>from v2</p>", "title": "Intro"}}}"#),
+        ] {
+            let doc_dir = docs.path().join(name);
+            std::fs::create_dir_all(&doc_dir).unwrap();
+            std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+            std::fs::write(doc_dir.join("db.json"), db).unwrap();
+        }
+
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        for name in ["v1", "v2"] {
+            let response = reqwest::get(format!("http://{}/docs/{}/intro", addr, name)).await.unwrap();
+            assert_eq!(response.status(), 200);
+            // db.json 中的页面片段放在简单的页面布局中
+            let body = response.text().await.unwrap();
+            assert!(body.starts_with("<!DOCTYPE html>"));
+            assert!(body.contains(&format!("<p>from {}</p>", name)));
+        }
+
+        let response = reqwest::get(format!("http://{}/docs/v2/intro.html", addr)).await.unwrap();
+        assert!(response.text().await.unwrap().contains("<title>Intro - v2</title>"));
+
+        let response = reqwest::get(format!("http://{}/docs/v2/missing", addr)).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_page_named_like_docset_file() {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("fixture");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), r#"{"icon": "<p>Icons</p>"}"#).unwrap();
+        std::fs::write(doc_dir.join("meta.json"), r#"{"name":"Fixture"}"#).unwrap();
+
+        let page_file = store_page(docs.path(), "fixture", "meta", "<p>Meta tags</p>").unwrap();
+        assert_eq!(page_file, doc_dir.join("_p").join("meta").join("index.html"));
+        assert_eq!(std::fs::read_to_string(doc_dir.join("meta.json")).unwrap(), r#"{"name":"Fixture"}"#);
+
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        for (page, content) in [("meta", "<p>Meta tags</p>"), ("icon", "<p>Icons</p>")] {
+            let response = reqwest::get(format!("http://{}/docs/fixture/{}", addr, page)).await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert!(response.text().await.unwrap().contains(content));
+        }
+        let response = reqwest::get(format!("http://{}/docs.json", addr)).await.unwrap();
+        assert!(response.text().await.unwrap().contains("Fixture"));
+    }
+
+    #[tokio::test]
+    async fn test_healthz_schema() {
+        let docs = TempDir::new().unwrap();
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        let response = reqwest::get(format!("http://{}/healthz", addr)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+        assert!(body["uptime_seconds"].is_u64());
+        assert_eq!(body["docs"], 0);
+        assert_eq!(body["page_cache"]["entries"], 0);
+        assert_eq!(body["page_cache"]["bytes"], 0);
+        assert_eq!(body["page_cache"]["evictions"], 0);
+        assert!(body["page_cache"]["max_bytes"].is_null());
+        assert_eq!(body["page_cache"]["gzip_served"], 0);
+        assert_eq!(body["page_cache"]["gzip_bytes_saved"], 0);
+        assert_eq!(body["snippet_cache"]["bytes"], 0);
+        assert_eq!(body["index"]["entries"], 0);
+        assert!(body["scrape_jobs"].is_u64());
+        for counter in ["events", "reloads", "deferred", "pending"] {
+            assert!(body["registry_reload"][counter].is_u64());
+        }
+    }
+
+    /// 创建带有一个页面的文档目录，可选写入 meta.json
+    fn fixture_docs(meta: Option<&str>) -> TempDir {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("fixture");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        if let Some(meta) = meta {
+            std::fs::write(doc_dir.join("meta.json"), meta).unwrap();
+        }
+        store_page(docs.path(), "fixture", "intro", "<p>hello</p>").unwrap();
+        docs
+    }
+
+    #[tokio::test]
+    async fn test_noindex_enabled() {
+        let docs = fixture_docs(None);
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        for path in ["docs/fixture/intro", "docs/fixture", "docs.json"] {
+            let response = reqwest::get(format!("http://{}/{}", addr, path)).await.unwrap();
+            assert_eq!(response.headers()["x-robots-tag"], "noindex", "{}", path);
+        }
+
+        let robots = reqwest::get(format!("http://{}/robots.txt", addr)).await.unwrap();
+        assert_eq!(robots.text().await.unwrap(), "User-agent: *\nDisallow: /\n");
+    }
+
+    #[tokio::test]
+    async fn test_noindex_disabled() {
+        let docs = fixture_docs(None);
+        let config = Config::default()
+            .with_docs_path(docs.path().to_str().unwrap())
+            .with_noindex(false);
+        let addr = spawn_server(&config);
+
+        for path in ["docs/fixture/intro", "docs/fixture", "docs.json"] {
+            let response = reqwest::get(format!("http://{}/{}", addr, path)).await.unwrap();
+            assert!(!response.headers().contains_key("x-robots-tag"), "{}", path);
+        }
+
+        let robots = reqwest::get(format!("http://{}/robots.txt", addr)).await.unwrap();
+        assert_eq!(robots.text().await.unwrap(), "User-agent: *\nDisallow:\n");
+    }
+
+    #[tokio::test]
+    async fn test_noindex_per_doc_override() {
+        let docs = fixture_docs(Some(r#"{"name":"Fixture","noindex":false}"#));
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        let page = reqwest::get(format!("http://{}/docs/fixture/intro", addr)).await.unwrap();
+        assert!(!page.headers().contains_key("x-robots-tag"));
+
+        // 其他路由仍使用全局设置
+        let list = reqwest::get(format!("http://{}/docs.json", addr)).await.unwrap();
+        assert_eq!(list.headers()["x-robots-tag"], "noindex");
+    }
+
+    /// 两个文档，只有 `styled` 带有自定义样式和脚本
+    fn custom_asset_docs() -> TempDir {
+        let docs = TempDir::new().unwrap();
+        for name in ["styled", "plain"] {
+            let doc_dir = docs.path().join(name);
+            std::fs::create_dir_all(&doc_dir).unwrap();
+            std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+            std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+            store_page(docs.path(), name, "intro", "<html><head><title>Intro</title></head><body></body></html>").unwrap();
+        }
+        std::fs::write(docs.path().join("styled").join("custom.css"), "table { width: 100%; }").unwrap();
+        std::fs::write(docs.path().join("styled").join("custom.js"), "console.log(1)").unwrap();
+        docs
+    }
+
+    #[tokio::test]
+    async fn test_custom_assets_injected_per_doc() {
+        let docs = custom_asset_docs();
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        let page = reqwest::get(format!("http://{}/docs/styled/intro", addr)).await.unwrap();
+        let etag = page.headers()["etag"].to_str().unwrap().to_string();
+        let html = page.text().await.unwrap();
+        let href = html
+            .split(r#"<link rel="stylesheet" href=""#)
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("custom.css should be injected")
+            .to_string();
+        assert!(href.starts_with("/docs/styled/_custom/custom.css?v="));
+        assert!(html.find(&href).unwrap() < html.find("</head>").unwrap());
+        // 脚本默认不注入
+        assert!(!html.contains("custom.js"));
+
+        let plain = reqwest::get(format!("http://{}/docs/plain/intro", addr)).await.unwrap();
+        assert_eq!(
+            plain.text().await.unwrap(),
+            "<html><head><title>Intro</title></head><body></body></html>"
+        );
+
+        // 带版本参数时长期缓存，ETag 相同时返回 304
+        let css = reqwest::get(format!("http://{}{}", addr, href)).await.unwrap();
+        assert_eq!(css.headers()["content-type"], "text/css; charset=utf-8");
+        assert_eq!(css.headers()["cache-control"], "public, max-age=31536000, immutable");
+        assert!(css.headers().contains_key("last-modified"));
+        let css_etag = css.headers()["etag"].to_str().unwrap().to_string();
+        assert_eq!(css.text().await.unwrap(), "table { width: 100%; }");
+
+        let client = reqwest::Client::new();
+        let cached = client
+            .get(format!("http://{}/docs/styled/_custom/custom.css", addr))
+            .header("if-none-match", &css_etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(cached.status(), 304);
+        assert_eq!(cached.headers()["cache-control"], "no-cache");
+
+        // 自定义样式修改后页面的 ETag 随之变化
+        std::fs::write(docs.path().join("styled").join("custom.css"), "table { width: 50%; }").unwrap();
+        let page = reqwest::get(format!("http://{}/docs/styled/intro", addr)).await.unwrap();
+        assert_ne!(page.headers()["etag"].to_str().unwrap(), etag);
+
+        let missing = reqwest::get(format!("http://{}/docs/plain/_custom/custom.css", addr)).await.unwrap();
+        assert_eq!(missing.status(), 404);
+        let js = reqwest::get(format!("http://{}/docs/styled/_custom/custom.js", addr)).await.unwrap();
+        assert_eq!(js.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_custom_assets_config_gate() {
+        let docs = custom_asset_docs();
+
+        let config = Config::default()
+            .with_docs_path(docs.path().to_str().unwrap())
+            .with_custom_assets(true, true);
+        let addr = spawn_server(&config);
+        let html = reqwest::get(format!("http://{}/docs/styled/intro", addr)).await.unwrap().text().await.unwrap();
+        assert!(html.contains(r#"<script src="/docs/styled/_custom/custom.js?v="#));
+        assert!(html.contains("custom.css"));
+        let js = reqwest::get(format!("http://{}/docs/styled/_custom/custom.js", addr)).await.unwrap();
+        assert_eq!(js.headers()["content-type"], "application/javascript; charset=utf-8");
+
+        // 关闭注入时样式和脚本都不注入也不提供
+        let config = Config::default()
+            .with_docs_path(docs.path().to_str().unwrap())
+            .with_custom_assets(false, true);
+        let addr = spawn_server(&config);
+        let html = reqwest::get(format!("http://{}/docs/styled/intro", addr)).await.unwrap().text().await.unwrap();
+        assert_eq!(html, "<html><head><title>Intro</title></head><body></body></html>");
+        for name in ["custom.css", "custom.js"] {
+            let response = reqwest::get(format!("http://{}/docs/styled/_custom/{}", addr, name)).await.unwrap();
+            assert_eq!(response.status(), 404, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_doc_types_ordered_by_weight() {
+        let docs = fixture_docs(None);
+        std::fs::write(
+            docs.path().join("fixture").join("index.json"),
+            r#"{"entries":[
+                {"name":"Array","path":"array","type":"Reference"},
+                {"name":"Intro","path":"intro","type":"Guides"},
+                {"name":"Changelog","path":"changelog","type":"Misc"}
+            ],"types":[
+                {"name":"Guides","count":1,"slug":"guides","weight":-1,"description":"Start here"},
+                {"name":"Reference","count":1,"slug":"reference","weight":1}
+            ]}"#,
+        )
+        .unwrap();
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+
+        let body: serde_json::Value = reqwest::get(format!("http://{}/docs/fixture", addr))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let names: Vec<&str> = body["types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        // index.json 中没有的类型使用默认权重
+        assert_eq!(names, ["Guides", "Misc", "Reference"]);
+        assert_eq!(body["types"][0]["description"], "Start here");
+        assert!(body["types"][1].get("weight").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_doc_landing_redirect() {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        let docs = fixture_docs(Some(r#"{"name":"Fixture","landing_path":"intro"}"#));
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+        let response = client.get(format!("http://{}/docs/fixture/", addr)).send().await.unwrap();
+        assert_eq!(response.status(), 307);
+        assert_eq!(response.headers()["location"], "/docs/fixture/intro");
+
+        // 索引接口不重定向
+        let response = client.get(format!("http://{}/docs/fixture", addr)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["types"].is_array());
+
+        let docs = fixture_docs(Some(r#"{"name":"Fixture"}"#));
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+        let response = client.get(format!("http://{}/docs/fixture/", addr)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.text().await.unwrap().contains("<h1>Fixture</h1>"));
+    }
+
+    #[tokio::test]
+    async fn test_non_canonical_slug_redirects() {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("Babel~7");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        store_page(docs.path(), "Babel~7", "intro", "<p>babel</p>").unwrap();
+
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let addr = spawn_server(&config);
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        // 不区分大小写地找到文档，重定向到规范的地址，保留页面路径和查询参数
+        let response = client
+            .get(format!("http://{}/docs/Babel~7/intro?locale=en", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 308);
+        assert_eq!(response.headers()["location"], "/docs/babel~7/intro?locale=en");
+        let response = client.get(format!("http://{}/docs/BABEL~7", addr)).send().await.unwrap();
+        assert_eq!(response.headers()["location"], "/docs/babel~7");
+
+        // 规范的地址从原目录读取页面
+        let response = client.get(format!("http://{}/docs/babel~7/intro", addr)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "<p>babel</p>");
+    }
+
+    /// 生成只包含一个 css 文档的镜像，页面 `guide` 的内容为 `content`
+    fn css_mirror(content: &str) -> (TempDir, TempDir) {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("css");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        store_page(docs.path(), "css", "guide", content).unwrap();
+        let mut registry = DocRegistry::new();
+        registry.load_from_disk(&[docs.path()]).unwrap();
+        let mirror = TempDir::new().unwrap();
+        crate::docs::mirror::build_mirror(&registry, mirror.path()).unwrap();
+        (docs, mirror)
+    }
+
+    #[tokio::test]
+    async fn test_read_only_replica_serves_through_sync() {
+        let (_v1_docs, v1) = css_mirror("<p>v1</p>");
+        let (_v2_docs, v2) = css_mirror("<p>v2</p>");
+        let reader = TempDir::new().unwrap();
+        crate::docs::mirror::sync_from_mirror(v1.path().to_str().unwrap(), reader.path(), &[])
+            .await
+            .unwrap();
+
+        let config = Config::default()
+            .with_docs_path(reader.path().to_str().unwrap())
+            .with_read_only(true);
+        let addr = spawn_server(&config);
+        let client = reqwest::Client::new();
+
+        let health: serde_json::Value = client
+            .get(format!("http://{}/healthz", addr))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(health["read_only"], true);
+        let cancel = client.delete(format!("http://{}/api/jobs/1", addr)).send().await.unwrap();
+        assert_eq!(cancel.status(), 403);
+
+        // 同步期间持续请求页面，每个请求都必须成功
+        let url = format!("http://{}/docs/css/guide", addr);
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let hammer = {
+            let (client, url, stop) = (client.clone(), url.clone(), stop.clone());
+            tokio::spawn(async move {
+                let mut bodies = Vec::new();
+                while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                    let response = client.get(&url).send().await.unwrap();
+                    assert_eq!(response.status(), 200);
+                    bodies.push(response.text().await.unwrap());
+                }
+                bodies
+            })
+        };
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let report = crate::docs::mirror::sync_from_mirror(v2.path().to_str().unwrap(), reader.path(), &[])
+            .await
+            .unwrap();
+        assert_eq!(report.archives.len(), 1);
+
+        // 重新加载后切换到新版本
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let body = client.get(&url).send().await.unwrap().text().await.unwrap();
+            if body == "<p>v2</p>" {
+                break;
+            }
+            assert!(Instant::now() < deadline, "仍在返回旧版本: {}", body);
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        let bodies = hammer.await.unwrap();
+        assert!(bodies.iter().any(|body| body == "<p>v1</p>"));
+        assert!(bodies.iter().all(|body| body == "<p>v1</p>" || body == "<p>v2</p>"));
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_doc_shadows_global() {
+        let docs = TempDir::new().unwrap();
+        let write_doc = |root: &std::path::Path, slug: &str, entry: &str, body: &str| {
+            let doc_dir = root.join(slug);
+            std::fs::create_dir_all(&doc_dir).unwrap();
+            let index = serde_json::json!({"entries": [{"name": entry, "path": "intro", "type": "Classes"}], "types": []});
+            std::fs::write(doc_dir.join("index.json"), index.to_string()).unwrap();
+            std::fs::write(doc_dir.join("db.json"), serde_json::json!({ "intro": body }).to_string()).unwrap();
+        };
+        let platform = docs.path().join("teams").join("platform");
+        write_doc(docs.path(), "fixture", "GlobalWidget", "<p>global</p>");
+        write_doc(docs.path(), "shared", "SharedWidget", "<p>shared</p>");
+        write_doc(&platform, "fixture", "PlatformWidget", "<p>platform</p>");
+
+        let config = Config::default()
+            .with_docs_path(docs.path().to_str().unwrap())
+            .with_namespace("platform", "teams/platform");
+        let addr = spawn_server(&config);
+        let body = |path: &str| {
+            let url = format!("http://{}{}", addr, path);
+            async move { reqwest::get(url).await.unwrap().text().await.unwrap() }
+        };
+
+        // 命名空间中的文档优先，没有的文档回退到全局命名空间
+        assert!(body("/docs/fixture/intro").await.contains("<p>global</p>"));
+        assert!(body("/t/platform/docs/fixture/intro").await.contains("<p>platform</p>"));
+        assert!(body("/t/platform/docs/shared/intro").await.contains("<p>shared</p>"));
+        let response = reqwest::get(format!("http://{}/t/unknown/docs/fixture/intro", addr)).await.unwrap();
+        assert_eq!(response.status(), 404);
+
+        let names = |results: serde_json::Value| {
+            let mut names: Vec<String> =
+                results.as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap().to_string()).collect();
+            names.sort();
+            names
+        };
+        let results = serde_json::from_str(&body("/search?q=widget").await).unwrap();
+        assert_eq!(names(results), ["GlobalWidget", "SharedWidget"]);
+        let results = serde_json::from_str(&body("/t/platform/search?q=widget").await).unwrap();
+        assert_eq!(names(results), ["PlatformWidget", "SharedWidget"]);
+
+        let list: serde_json::Value = serde_json::from_str(&body("/t/platform/docs.json").await).unwrap();
+        assert_eq!(list.as_array().unwrap().len(), 2);
+    }
+
+    /// 解压 gzip 响应体
+    fn gunzip(bytes: &[u8]) -> String {
+        use std::io::Read;
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(bytes).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[tokio::test]
+    async fn test_precompressed_pages_are_served_once_encoded() {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("fixture");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        let page = format!("<h1>Guide</h1>{}", "<p>Repeated paragraph of text.</p>".repeat(200));
+        store_page(docs.path(), "fixture", "guide", &page).unwrap();
+
+        let get_if = |addr: SocketAddr, path: &'static str, gzip: bool, etag: Option<String>| async move {
+            let mut request = reqwest::Client::new().get(format!("http://{}{}", addr, path));
+            if gzip {
+                request = request.header("accept-encoding", "gzip");
+            }
+            if let Some(etag) = etag {
+                request = request.header("if-none-match", etag);
+            }
+            request.send().await.unwrap()
+        };
+        let get = |addr: SocketAddr, path: &'static str, gzip: bool| get_if(addr, path, gzip, None);
+        let etag = |response: &reqwest::Response| response.headers()["etag"].to_str().unwrap().to_string();
+
+        for precompress in [true, false] {
+            let config = Config::default()
+                .with_docs_path(docs.path().to_str().unwrap())
+                .with_precompression(precompress);
+            let addr = spawn_server(&config);
+
+            // 第一次请求时在后台生成压缩副本，此前由中间件压缩
+            let response = get(addr, "/docs/fixture/guide", true).await;
+            assert_eq!(response.headers()["content-encoding"], "gzip");
+            assert_eq!(gunzip(&response.bytes().await.unwrap()), page);
+            for _ in 0..200 {
+                let health: serde_json::Value = get(addr, "/healthz", false).await.json().await.unwrap();
+                if !precompress || health["page_cache"]["gzip_bytes"].as_u64().unwrap() > 0 {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+
+            // 并发请求同一页面，解压一次即为原始内容，不会被中间件再次压缩
+            let responses = futures::future::join_all((0..20).map(|_| get(addr, "/docs/fixture/guide", true))).await;
+            for response in responses {
+                assert_eq!(response.status(), 200);
+                assert_eq!(response.headers()["content-encoding"], "gzip");
+                assert_eq!(response.headers().get_all("content-encoding").iter().count(), 1);
+                assert!(response.headers().contains_key("etag"));
+                let vary: Vec<_> = response.headers().get_all("vary").iter().map(|v| v.to_str().unwrap()).collect();
+                assert!(vary.contains(&"accept-encoding"), "{:?}", vary);
+                assert!(vary.contains(&"accept-language"), "{:?}", vary);
+                assert_eq!(gunzip(&response.bytes().await.unwrap()), page);
+            }
+
+            // 不接受 gzip 的客户端收到原始内容
+            let response = get(addr, "/docs/fixture/guide", false).await;
+            assert!(response.headers().get("content-encoding").is_none());
+            let plain_etag = etag(&response);
+            assert_eq!(response.text().await.unwrap(), page);
+
+            let health: serde_json::Value = get(addr, "/healthz", false).await.json().await.unwrap();
+            let cache = &health["page_cache"];
+            if precompress {
+                assert_eq!(cache["gzip_served"], 20);
+                assert!(cache["gzip_bytes"].as_u64().unwrap() > 0);
+                let saved = cache["gzip_bytes_saved"].as_u64().unwrap();
+                let gzip_bytes = cache["gzip_bytes"].as_u64().unwrap();
+                assert_eq!(saved, 20 * (page.len() as u64 - gzip_bytes));
+                assert_eq!(cache["bytes"].as_u64().unwrap(), page.len() as u64 + gzip_bytes);
+            } else {
+                assert_eq!(cache["gzip_served"], 0);
+                assert_eq!(cache["gzip_bytes"], 0);
+            }
+
+            // 压缩后的表示有自己的 ETag，条件请求返回 304 时 ETag 不变
+            let gzip_etag = etag(&get(addr, "/docs/fixture/guide", true).await);
+            assert_eq!(gzip_etag, compression::gzip_etag(&plain_etag));
+            let response = get_if(addr, "/docs/fixture/guide", true, Some(gzip_etag.clone())).await;
+            assert_eq!(response.status(), 304);
+            assert_eq!(etag(&response), gzip_etag);
+            let response = get_if(addr, "/docs/fixture/guide", false, Some(plain_etag.clone())).await;
+            assert_eq!(response.status(), 304);
+            assert_eq!(etag(&response), plain_etag);
+        }
+    }
+}
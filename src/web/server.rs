@@ -3,7 +3,7 @@
 use crate::core::config::Config;
 use axum::Router;
 use std::error::Error;
-use std::net::SocketAddr;
+use std::net::{SocketAddr, TcpListener};
 
 /// Web u670du52a1u5668
 pub struct Server {
@@ -33,18 +33,62 @@ impl Server {
         self
     }
 
+    /// 获取服务器地址
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
     /// u8fd0u884cu670du52a1u5668
-    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
-        let router = self.router.clone().unwrap_or_else(|| {
+    pub async fn run(self) -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind(self.address)?;
+        self.run_on(listener).await
+    }
+
+    /// 在已绑定的监听器上运行服务器
+    pub async fn run_on(self, listener: TcpListener) -> Result<(), Box<dyn Error>> {
+        let router = self.router.unwrap_or_else(|| {
             // u521bu5efau9ed8u8ba4u8def u7531
             super::routes::create_routes(&self.config)
         });
 
-        println!("Server starting at http://{}", self.address);
+        log::info!("Server starting at http://{}", listener.local_addr()?);
 
-        axum::Server::bind(&self.address)
+        axum::Server::from_tcp(listener)?
             .serve(router.into_make_service())
             .await
             .map_err(|e| Box::new(e) as Box<dyn Error>)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_server_built_from_config() {
+        let docs = TempDir::new().unwrap();
+        let doc_dir = docs.path().join("fixture");
+        std::fs::create_dir_all(&doc_dir).unwrap();
+        std::fs::write(doc_dir.join("index.json"), r#"{"entries":[],"types":[]}"#).unwrap();
+        std::fs::write(doc_dir.join("db.json"), "{}").unwrap();
+        std::fs::write(doc_dir.join("meta.json"), r#"{"name":"Fixture"}"#).unwrap();
+
+        let config = Config::default().with_docs_path(docs.path().to_str().unwrap());
+        let server = crate::build_server(config, "127.0.0.1", 0).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = server.run_on(listener).await;
+        });
+
+        let docs: serde_json::Value = reqwest::get(format!("http://{}/docs.json", addr))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(docs.as_array().unwrap().len(), 1);
+        assert_eq!(docs[0]["name"], "Fixture");
+    }
+}
@@ -1,8 +1,11 @@
 //! u9759u6001u6587u4ef6u670du52a1
 
-use axum::extract::Path;
+use axum::extract::{Path, State};
 use axum::response::{IntoResponse, Response};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
+use std::sync::Arc;
+
+use super::handlers::AppState;
 
 /// u63d0u4f9bu9759u6001u6587u4ef6
 pub async fn serve_static_file(Path(_path): Path<String>) -> Response {
@@ -26,4 +29,44 @@ pub async fn serve_template(
     Ok(format!("<h1>Template: {}</h1><pre>{}</pre>", 
               name, 
               serde_json::to_string_pretty(&context)?))
-}
\ No newline at end of file
+}
+
+/// 提供编译后的前端资源
+///
+/// 哈希文件名长期缓存，未带哈希的开发路径每次重新验证。
+pub async fn serve_asset(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<String>,
+) -> Response {
+    let (file, cache_control) = match state.assets.resolve(&path) {
+        Some(resolved) => resolved,
+        None => return (StatusCode::NOT_FOUND, "Asset not found").into_response(),
+    };
+
+    match tokio::fs::read(&file).await {
+        Ok(content) => (
+            [
+                (header::CONTENT_TYPE, content_type_for(&path)),
+                (header::CACHE_CONTROL, cache_control),
+            ],
+            content,
+        )
+            .into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "Asset not found").into_response(),
+    }
+}
+
+/// 根据扩展名获取 Content-Type
+fn content_type_for(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or("") {
+        "css" => "text/css; charset=utf-8",
+        "js" => "application/javascript; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "ico" => "image/x-icon",
+        "woff2" => "font/woff2",
+        "html" => "text/html; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}